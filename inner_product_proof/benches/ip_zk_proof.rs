@@ -1,9 +1,7 @@
 #![allow(non_snake_case)]
 #[macro_use]
 extern crate criterion;
-use criterion::Criterion;
-
-use rand_core::OsRng;
+use criterion::{BenchmarkId, Criterion};
 
 use curve25519_dalek::scalar::Scalar;
 
@@ -14,13 +12,21 @@ use ip_zk_proof::{BulletproofGens, PedersenGens};
 
 static IP_SIZES: [usize; 6] = [4, 8, 16, 32, 64, 128];
 
-fn create_ip_zk_proof(c: &mut Criterion) {
-    let label = format!("Generation inner product proof");
+/// Curve backend the benchmarks were compiled with, so that runs with different
+/// `curve25519_dalek_backend` settings show up side by side in the criterion report. Without
+/// the override, `curve25519-dalek` picks the AVX2 backend at runtime when available.
+#[cfg(curve25519_dalek_backend = "serial")]
+static BACKEND: &str = "serial";
+#[cfg(curve25519_dalek_backend = "fiat")]
+static BACKEND: &str = "fiat";
+#[cfg(not(any(curve25519_dalek_backend = "serial", curve25519_dalek_backend = "fiat")))]
+static BACKEND: &str = "auto";
 
-    c.bench_function_over_inputs(
-        &label,
-        move |b, &&n| {
-            let pc_gens = PedersenGens::default();
+fn create_ip_zk_proof(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("Generation inner product proof ({})", BACKEND));
+    for n in IP_SIZES.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(n), n, |b, &n| {
+            let pc_gens: PedersenGens = PedersenGens::default();
             let bp_gens = BulletproofGens::new(n, 1);
             let mut rng = rand::thread_rng();
 
@@ -40,26 +46,24 @@ fn create_ip_zk_proof(c: &mut Criterion) {
                     &pc_gens,
                     &mut transcript,
                     value,
-                    lhs_ip.clone(),
-                    rhs_ip.clone(),
+                    &lhs_ip,
+                    &rhs_ip,
                     v_blinding,
                     a_blinding,
                     n,
                     &mut rng
                 )
             })
-        },
-        &IP_SIZES,
-    );
+        });
+    }
+    group.finish();
 }
 
 fn verify_ip_zk_proof(c: &mut Criterion) {
-    let label = format!("Verification inner product proof");
-
-    c.bench_function_over_inputs(
-        &label,
-        move |b, &&n| {
-            let pc_gens = PedersenGens::default();
+    let mut group = c.benchmark_group(format!("Verification inner product proof ({})", BACKEND));
+    for n in IP_SIZES.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(n), n, |b, &n| {
+            let pc_gens: PedersenGens = PedersenGens::default();
             let bp_gens = BulletproofGens::new(n, 1);
             let mut rng = rand::thread_rng();
 
@@ -76,8 +80,8 @@ fn verify_ip_zk_proof(c: &mut Criterion) {
                 &pc_gens,
                 &mut transcript,
                 value,
-                lhs_ip,
-                rhs_ip,
+                &lhs_ip,
+                &rhs_ip,
                 v_blinding,
                 a_blinding,
                 n,
@@ -91,9 +95,9 @@ fn verify_ip_zk_proof(c: &mut Criterion) {
 
                 proof.verify_single(&bp_gens, &pc_gens, &mut transcript, &value_commitments, n, &mut rng)
             });
-        },
-        &IP_SIZES,
-    );
+        });
+    }
+    group.finish();
 }
 
 criterion_group! {
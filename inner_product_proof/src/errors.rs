@@ -1,7 +1,11 @@
 //! Errors related to proving and verifying proofs.
 
 extern crate alloc;
+#[cfg(feature = "yoloproofs")]
+use alloc::string::String;
 use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use core::fmt;
 
 #[cfg(feature = "std")]
 use thiserror::Error;
@@ -37,6 +41,18 @@ pub enum ProofError {
         error("Invalid generators size, too few generators for proof")
     )]
     InvalidGeneratorsLength,
+    /// This error occurs when the generators contain the identity or a
+    /// repeated base, or do not match their capacities.
+    #[cfg_attr(feature = "std", error("Invalid generators, must be distinct non-identity bases."))]
+    InvalidGenerators,
+    /// This error occurs when the public weights of a weighted inner
+    /// product proof are not one non-zero scalar per element.
+    #[cfg_attr(feature = "std", error("Invalid weights, must have one non-zero weight per element."))]
+    InvalidWeights,
+    /// This error occurs when the public vector of an inner product
+    /// proof does not have one scalar per element.
+    #[cfg_attr(feature = "std", error("Invalid public vector, must have one scalar per element."))]
+    InvalidPublicVectorLength,
     /// This error results from an internal error during proving.
     ///
     /// The single-party prover is implemented by performing
@@ -47,6 +63,25 @@ pub enum ProofError {
     ProvingError(MPCError),
 }
 
+/// Same messages as the `thiserror` implementation, for `no_std` builds.
+#[cfg(not(feature = "std"))]
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofError::VerificationError => f.write_str("Proof verification failed."),
+            ProofError::FormatError => f.write_str("Proof data could not be parsed."),
+            ProofError::WrongNumBlindingFactors => f.write_str("Wrong number of blinding factors supplied."),
+            ProofError::InvalidBitsize => f.write_str("Invalid bitsize, must have n = 8,16,32,64."),
+            ProofError::InvalidAggregation => f.write_str("Invalid aggregation size, m must be a power of 2."),
+            ProofError::InvalidGeneratorsLength => f.write_str("Invalid generators size, too few generators for proof"),
+            ProofError::InvalidGenerators => f.write_str("Invalid generators, must be distinct non-identity bases."),
+            ProofError::InvalidWeights => f.write_str("Invalid weights, must have one non-zero weight per element."),
+            ProofError::InvalidPublicVectorLength => f.write_str("Invalid public vector, must have one scalar per element."),
+            ProofError::ProvingError(e) => write!(f, "Internal error during proof creation: {}", e),
+        }
+    }
+}
+
 impl From<MPCError> for ProofError {
     fn from(e: MPCError) -> ProofError {
         match e {
@@ -111,6 +146,95 @@ pub enum MPCError {
         /// A vector with the indexes of the parties whose shares were malformed.
         bad_shares: Vec<usize>,
     },
+    /// This error occurs when a session receives a message other than
+    /// the one expected at its step of the protocol.
+    #[cfg_attr(feature = "std", error("Unexpected message for the step of the protocol"))]
+    UnexpectedMessage,
 }
 
+/// Same messages as the `thiserror` implementation, for `no_std` builds.
+#[cfg(not(feature = "std"))]
+impl fmt::Display for MPCError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MPCError::MaliciousDealer => f.write_str("Dealer gave a malicious challenge value."),
+            MPCError::InvalidBitsize => f.write_str("Invalid bitsize, must have n = 8,16,32,64"),
+            MPCError::InvalidAggregation => f.write_str("Invalid aggregation size, m must be a power of 2"),
+            MPCError::InvalidGeneratorsLength => f.write_str("Invalid generators size, too few generators for proof"),
+            MPCError::WrongNumBitCommitments => f.write_str("Wrong number of value commitments"),
+            MPCError::WrongNumPolyCommitments => f.write_str("Wrong number of value commitments"),
+            MPCError::WrongNumProofShares => f.write_str("Wrong number of proof shares"),
+            MPCError::MalformedProofShares { bad_shares } => {
+                write!(f, "Malformed proof shares from parties {:?}", bad_shares)
+            }
+            MPCError::UnexpectedMessage => f.write_str("Unexpected message for the step of the protocol"),
+        }
+    }
+}
 
+/// Represents an error during the proving or verifying of a constraint system.
+///
+/// XXX: should this be separate from a `ProofError`?
+#[cfg(feature = "yoloproofs")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Error))]
+pub enum R1CSError {
+    /// Occurs when there are insufficient generators for the proof.
+    #[cfg_attr(feature = "std", error("Invalid generators size, too few generators for proof"))]
+    InvalidGeneratorsLength,
+    /// This error occurs when the proof encoding is malformed.
+    #[cfg_attr(feature = "std", error("Proof data could not be parsed."))]
+    FormatError,
+    /// Occurs when verification of an
+    /// [`R1CSProof`](::r1cs::R1CSProof) fails.
+    #[cfg_attr(feature = "std", error("R1CSProof did not verify correctly."))]
+    VerificationError,
+
+    /// Occurs when trying to use a missing variable assignment.
+    /// Used by gadgets that build the constraint system to signal that
+    /// a variable assignment is not provided when the prover needs it.
+    #[cfg_attr(feature = "std", error("Variable does not have a value assignment."))]
+    MissingAssignment,
+
+    /// Occurs when a gadget receives an inconsistent input.
+    #[cfg_attr(feature = "std", error("Gadget error: {description:?}"))]
+    GadgetError {
+        /// The description of the reasons for the error.
+        description: String,
+    },
+}
+
+/// Same messages as the `thiserror` implementation, for `no_std` builds.
+#[cfg(all(feature = "yoloproofs", not(feature = "std")))]
+impl fmt::Display for R1CSError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            R1CSError::InvalidGeneratorsLength => f.write_str("Invalid generators size, too few generators for proof"),
+            R1CSError::FormatError => f.write_str("Proof data could not be parsed."),
+            R1CSError::VerificationError => f.write_str("R1CSProof did not verify correctly."),
+            R1CSError::MissingAssignment => f.write_str("Variable does not have a value assignment."),
+            R1CSError::GadgetError { description } => write!(f, "Gadget error: {:?}", description),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_std_error<E: std::error::Error + Send + Sync + 'static>(_: &E) {}
+
+    #[test]
+    fn errors_are_std_errors() {
+        let error = ProofError::from(MPCError::MalformedProofShares { bad_shares: vec![1, 3] });
+        assert_std_error(&error);
+        assert_std_error(&MPCError::MaliciousDealer);
+        assert_eq!(
+            error.to_string(),
+            "Internal error during proof creation: Malformed proof shares from parties [1, 3]"
+        );
+
+        let boxed: Box<dyn std::error::Error> = Box::new(ProofError::FormatError);
+        assert_eq!(boxed.to_string(), "Proof data could not be parsed.");
+    }
+}
@@ -0,0 +1,313 @@
+//! Headers of the serialized proofs.
+//!
+//! Every `to_bytes` encoding starts with a two byte header: the version of
+//! the format, followed by the kind of proof. `from_bytes` reads the header
+//! and dispatches on the version, so that new encodings (e.g. of aggregated
+//! proofs) can be introduced while the proofs stored with the previous ones
+//! keep parsing, and a proof of one kind is never parsed as another.
+//!
+//! Proofs nested in other proofs, such as the inner product argument of a
+//! range proof, are encoded without a header.
+//!
+//! Encodings are parsed with a [`ProofReader`], which checks the length of
+//! every element before reading it, so that an attacker-supplied proof
+//! fails to parse instead of panicking, and rejects trailing bytes. The
+//! scalars must be canonically encoded, while the points are only checked by
+//! `from_bytes_strict`, see [`CanonicalPoints`].
+//!
+//! The proofs are ordered by their `to_bytes` encoding, and those that
+//! implement `Hash` hash it, so that they can be kept in ordered sets and
+//! maps, and duplicates are found by comparing bytes.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use crate::errors::ProofError;
+use crate::group::{CompressedPoint, PrimeScalar};
+
+/// Version of the format written by `to_bytes`, for the kinds of proofs
+/// whose encoding has not changed since. See [`ProofKind::version`].
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Size in bytes of the header.
+pub const HEADER_SIZE: usize = 2;
+
+/// Kind of proof encoded after the header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ProofKind {
+    InnerProduct = 1,
+    WeightedInnerProduct = 2,
+    InnerProductZK = 3,
+    InnerProductZKPlus = 4,
+    Range = 5,
+    RangePlus = 6,
+    R1CS = 7,
+}
+
+impl ProofKind {
+    fn from_u8(byte: u8) -> Option<ProofKind> {
+        match byte {
+            1 => Some(ProofKind::InnerProduct),
+            2 => Some(ProofKind::WeightedInnerProduct),
+            3 => Some(ProofKind::InnerProductZK),
+            4 => Some(ProofKind::InnerProductZKPlus),
+            5 => Some(ProofKind::Range),
+            6 => Some(ProofKind::RangePlus),
+            7 => Some(ProofKind::R1CS),
+            _ => None,
+        }
+    }
+
+    /// Reads the version and the kind of a serialized proof, without
+    /// parsing the proof itself. Returns `None` if the header is invalid.
+    pub fn of(bytes: &[u8]) -> Option<(u8, ProofKind)> {
+        if bytes.len() < HEADER_SIZE || bytes[0] == 0 {
+            return None;
+        }
+        ProofKind::from_u8(bytes[1]).map(|kind| (bytes[0], kind))
+    }
+
+    /// Latest version of the encoding of this kind, written by `to_bytes`.
+    /// Inner product proofs are in version 2, which prefixes the proof with
+    /// its number of rounds; all other kinds are in [`FORMAT_VERSION`].
+    pub fn version(self) -> u8 {
+        match self {
+            ProofKind::InnerProduct => 2,
+            _ => FORMAT_VERSION,
+        }
+    }
+
+    /// Header of the proofs of this kind in the current format.
+    pub(crate) fn header(self) -> [u8; HEADER_SIZE] {
+        [self.version(), self as u8]
+    }
+
+    /// Checks that `bytes` is a proof of this kind in a supported version, and
+    /// returns the version and the encoding after the header.
+    pub(crate) fn read_header(self, bytes: &[u8]) -> Result<(u8, &[u8]), ProofError> {
+        match ProofKind::of(bytes) {
+            Some((version, kind)) if kind == self && version <= self.version() => {
+                Ok((version, &bytes[HEADER_SIZE..]))
+            }
+            _ => Err(ProofError::FormatError),
+        }
+    }
+}
+
+/// Proofs that can report the size of their encoding before serializing
+/// it, so that transports can allocate buffers and enforce size limits.
+pub trait SerializedSize {
+    /// Size in bytes of the encoding of the proof.
+    fn serialized_size(&self) -> usize;
+}
+
+/// Proofs and parts of proofs whose points can be checked to be canonical
+/// encodings of group elements.
+///
+/// `from_bytes` reads any point encoding, and leaves the invalid ones to the
+/// verifier. `from_bytes_strict` also checks `points_are_canonical`, so that
+/// no other byte string parses into the same proof.
+pub trait CanonicalPoints {
+    /// Whether all the points are canonically encoded.
+    fn points_are_canonical(&self) -> bool;
+}
+
+impl<C: CompressedPoint> CanonicalPoints for C {
+    fn points_are_canonical(&self) -> bool {
+        self.is_canonical()
+    }
+}
+
+/// Decompressed points have a single encoding.
+impl CanonicalPoints for RistrettoPoint {
+    fn points_are_canonical(&self) -> bool {
+        true
+    }
+}
+
+impl<T: CanonicalPoints> CanonicalPoints for [T] {
+    fn points_are_canonical(&self) -> bool {
+        self.iter().all(|element| element.points_are_canonical())
+    }
+}
+
+impl<T: CanonicalPoints, const N: usize> CanonicalPoints for [T; N] {
+    fn points_are_canonical(&self) -> bool {
+        self[..].points_are_canonical()
+    }
+}
+
+impl<T: CanonicalPoints> CanonicalPoints for Vec<T> {
+    fn points_are_canonical(&self) -> bool {
+        self[..].points_are_canonical()
+    }
+}
+
+impl<T: CanonicalPoints> CanonicalPoints for Option<T> {
+    fn points_are_canonical(&self) -> bool {
+        self.as_ref().is_none_or(|element| element.points_are_canonical())
+    }
+}
+
+/// Checked reader over an encoded proof. Every method returns a
+/// `FormatError` instead of reading past the end of the encoding.
+#[derive(Clone, Debug)]
+pub struct ProofReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ProofReader<'a> {
+    /// Reader over `bytes`.
+    pub fn new(bytes: &'a [u8]) -> ProofReader<'a> {
+        ProofReader { bytes }
+    }
+
+    /// Number of bytes left to read.
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Whether all the bytes have been read.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Reads the next `len` bytes.
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], ProofError> {
+        if len > self.bytes.len() {
+            return Err(ProofError::FormatError);
+        }
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    /// Reads a byte.
+    pub fn read_u8(&mut self) -> Result<u8, ProofError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    /// Reads a little endian `u64`.
+    pub fn read_u64(&mut self) -> Result<u64, ProofError> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.read_bytes(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// Reads the number of elements of `element_size` bytes that follow, as a
+    /// little endian `u64`. Fails if the remaining bytes cannot hold them, so
+    /// that the count can be used to allocate.
+    pub fn read_count(&mut self, element_size: usize) -> Result<usize, ProofError> {
+        let count = usize::try_from(self.read_u64()?).map_err(|_| ProofError::FormatError)?;
+        match count.checked_mul(element_size) {
+            Some(len) if len <= self.remaining() => Ok(count),
+            _ => Err(ProofError::FormatError),
+        }
+    }
+
+    /// Reads a compressed point, without checking that it is a valid point,
+    /// see [`CanonicalPoints`].
+    pub fn read_point<C: CompressedPoint>(&mut self) -> Result<C, ProofError> {
+        Ok(C::read(self.read_bytes(C::SIZE)?))
+    }
+
+    /// Reads a scalar, which must be canonically encoded.
+    pub fn read_scalar<S: PrimeScalar>(&mut self) -> Result<S, ProofError> {
+        S::from_canonical_slice(self.read_bytes(S::SIZE)?).ok_or(ProofError::FormatError)
+    }
+
+    /// Reads a section prefixed by its length, as a little endian `u64`, and
+    /// returns a reader over it. See [`write_section`].
+    pub fn read_section(&mut self) -> Result<ProofReader<'a>, ProofError> {
+        let len = self.read_count(1)?;
+        Ok(ProofReader::new(self.read_bytes(len)?))
+    }
+
+    /// Checks that the whole encoding has been read.
+    pub fn finish(self) -> Result<(), ProofError> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(ProofError::FormatError)
+        }
+    }
+}
+
+/// Appends `section` to `buf` prefixed by its length, to be read with
+/// [`ProofReader::read_section`].
+pub fn write_section(buf: &mut Vec<u8>, section: &[u8]) {
+    buf.extend_from_slice(&(section.len() as u64).to_le_bytes());
+    buf.extend_from_slice(section);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::ristretto::CompressedRistretto;
+    use curve25519_dalek::scalar::Scalar;
+
+    #[test]
+    fn header_roundtrip() {
+        let header = ProofKind::Range.header();
+        assert_eq!(ProofKind::of(&header), Some((FORMAT_VERSION, ProofKind::Range)));
+        assert_eq!(ProofKind::Range.read_header(&[header[0], header[1], 42]), Ok((FORMAT_VERSION, &[42u8][..])));
+
+        // Other kinds, unknown versions and truncated headers are rejected
+        assert_eq!(ProofKind::RangePlus.read_header(&header), Err(ProofError::FormatError));
+        assert_eq!(ProofKind::Range.read_header(&[FORMAT_VERSION + 1, header[1]]), Err(ProofError::FormatError));
+        assert_eq!(ProofKind::Range.read_header(&[0, header[1]]), Err(ProofError::FormatError));
+        assert_eq!(ProofKind::Range.read_header(&header[..1]), Err(ProofError::FormatError));
+        assert_eq!(ProofKind::of(&[FORMAT_VERSION, 0]), None);
+
+        // Inner product proofs are read in both versions
+        let header = ProofKind::InnerProduct.header();
+        assert_eq!(header, [2, ProofKind::InnerProduct as u8]);
+        assert!(ProofKind::InnerProduct.read_header(&[1, header[1]]).is_ok());
+        assert_eq!(ProofKind::InnerProduct.read_header(&[3, header[1]]), Err(ProofError::FormatError));
+    }
+
+    #[test]
+    fn reader_checks_lengths() {
+        let mut buf = Vec::new();
+        buf.push(7u8);
+        buf.extend_from_slice(&2u64.to_le_bytes());
+        buf.extend_from_slice(Scalar::ONE.as_bytes());
+        buf.extend_from_slice(&[0xff; 32]);
+        write_section(&mut buf, b"section");
+
+        let mut reader = ProofReader::new(&buf);
+        assert_eq!(reader.read_u8(), Ok(7));
+        assert_eq!(reader.clone().read_count(64), Err(ProofError::FormatError));
+        assert_eq!(reader.read_count(32), Ok(2));
+        assert_eq!(reader.read_scalar::<Scalar>(), Ok(Scalar::ONE));
+        // Non canonical scalars are rejected, but any point encoding is read
+        assert_eq!(reader.clone().read_scalar::<Scalar>(), Err(ProofError::FormatError));
+        assert_eq!(reader.clone().read_point::<CompressedRistretto>(), Ok(CompressedRistretto([0xff; 32])));
+        assert!(!reader.read_point::<CompressedRistretto>().unwrap().points_are_canonical());
+
+        let mut section = reader.clone().read_section().unwrap();
+        assert_eq!(section.read_bytes(7), Ok(&b"section"[..]));
+        assert!(section.finish().is_ok());
+
+        // Truncated sections and trailing bytes are rejected
+        assert_eq!(ProofReader::new(&buf[..buf.len() - 1]).read_bytes(buf.len()), Err(ProofError::FormatError));
+        assert_eq!(reader.clone().finish(), Err(ProofError::FormatError));
+        let mut truncated = ProofReader::new(&buf[buf.len() - 15..buf.len() - 1]);
+        assert_eq!(truncated.read_section().unwrap_err(), ProofError::FormatError);
+    }
+
+    #[test]
+    fn canonical_points_of_nested_values() {
+        let valid = CompressedRistretto::default();
+        let invalid = CompressedRistretto([0xff; 32]);
+        assert!(vec![[valid; 2]; 3].points_are_canonical());
+        assert!(!vec![vec![valid], vec![valid, invalid]].points_are_canonical());
+        assert!(None::<CompressedRistretto>.points_are_canonical());
+        assert!(!Some(invalid).points_are_canonical());
+    }
+}
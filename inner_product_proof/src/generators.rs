@@ -7,13 +7,13 @@
 extern crate alloc;
 
 use alloc::vec::Vec;
-use curve25519_dalek::constants::RISTRETTO_BASEPOINT_COMPRESSED;
-use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use core::marker::PhantomData;
 use curve25519_dalek::ristretto::RistrettoPoint;
-use curve25519_dalek::scalar::Scalar;
-use curve25519_dalek::traits::MultiscalarMul;
-use digest::{ExtendableOutput, Input, XofReader};
-use sha3::{Sha3XofReader, Sha3_512, Shake256};
+use digest::{ExtendableOutput, Update, XofReader};
+use sha3::{Shake256, Shake256Reader};
+
+use crate::errors::ProofError;
+use crate::group::{CompressedPoint, PrimeGroup};
 
 /// Represents a pair of base points for Pedersen commitments.
 ///
@@ -23,31 +23,60 @@ use sha3::{Sha3XofReader, Sha3_512, Shake256};
 ///
 /// The default generators are:
 ///
-/// * `B`: the `ristretto255` basepoint;
-/// * `B_blinding`: the result of `ristretto255` SHA3-512
-/// hash-to-group on input `B_bytes`.
+/// * `B`: the generator of the group, which for `ristretto255` is
+///   its basepoint;
+/// * `B_blinding`: the result of SHA3-512 hash-to-group on the
+///   compressed encoding of `B`.
 #[derive(Copy, Clone)]
-pub struct PedersenGens {
+pub struct PedersenGens<G: PrimeGroup = RistrettoPoint> {
     /// Base for the committed value
-    pub B: RistrettoPoint,
+    pub B: G,
     /// Base for the blinding factor
-    pub B_blinding: RistrettoPoint,
+    pub B_blinding: G,
 }
 
-impl PedersenGens {
+impl<G: PrimeGroup> PedersenGens<G> {
     /// Creates a Pedersen commitment using the value scalar and a blinding factor.
-    pub fn commit(&self, value: Scalar, blinding: Scalar) -> RistrettoPoint {
-        RistrettoPoint::multiscalar_mul(&[value, blinding], &[self.B, self.B_blinding])
+    pub fn commit(&self, value: G::Scalar, blinding: G::Scalar) -> G {
+        G::multiscalar_mul(&[value, blinding], &[self.B, self.B_blinding])
+    }
+
+    /// Checks that neither base is the identity and that they are
+    /// different, as otherwise commitments are not binding. Returns an
+    /// `InvalidGenerators` error if not.
+    ///
+    /// The default generators are always valid, this is meant for
+    /// generators received from elsewhere, e.g. parsed parameters.
+    pub fn validate(&self) -> Result<(), ProofError> {
+        validate_bases([self.B, self.B_blinding].iter())
+    }
+}
+
+/// Checks that none of `bases` is the identity, and that they are
+/// pairwise distinct.
+pub(crate) fn validate_bases<'a, G: PrimeGroup + 'a>(
+    bases: impl Iterator<Item = &'a G>,
+) -> Result<(), ProofError> {
+    let mut encodings = Vec::new();
+    for base in bases {
+        if bool::from(base.is_identity()) {
+            return Err(ProofError::InvalidGenerators);
+        }
+        encodings.push(base.compress().as_bytes().to_vec());
     }
+    encodings.sort_unstable();
+    if encodings.windows(2).any(|w| w[0] == w[1]) {
+        return Err(ProofError::InvalidGenerators);
+    }
+    Ok(())
 }
 
-impl Default for PedersenGens {
+impl<G: PrimeGroup> Default for PedersenGens<G> {
     fn default() -> Self {
+        let B = G::generator();
         PedersenGens {
-            B: RISTRETTO_BASEPOINT_POINT,
-            B_blinding: RistrettoPoint::hash_from_bytes::<Sha3_512>(
-                RISTRETTO_BASEPOINT_COMPRESSED.as_bytes(),
-            ),
+            B,
+            B_blinding: G::hash_from_bytes(B.compress().as_bytes()),
         }
     }
 }
@@ -55,19 +84,21 @@ impl Default for PedersenGens {
 /// The `GeneratorsChain` creates an arbitrary-long sequence of
 /// orthogonal generators.  The sequence can be deterministically
 /// produced starting with an arbitrary point.
-struct GeneratorsChain {
-    reader: Sha3XofReader,
+struct GeneratorsChain<G> {
+    reader: Shake256Reader,
+    _group: PhantomData<G>,
 }
 
-impl GeneratorsChain {
+impl<G: PrimeGroup> GeneratorsChain<G> {
     /// Creates a chain of generators, determined by the hash of `label`.
     fn new(label: &[u8]) -> Self {
         let mut shake = Shake256::default();
-        shake.input(b"GeneratorsChain");
-        shake.input(label);
+        shake.update(b"GeneratorsChain");
+        shake.update(label);
 
         GeneratorsChain {
-            reader: shake.xof_result(),
+            reader: shake.finalize_xof(),
+            _group: PhantomData,
         }
     }
 
@@ -82,24 +113,24 @@ impl GeneratorsChain {
     }
 }
 
-impl Default for GeneratorsChain {
+impl<G: PrimeGroup> Default for GeneratorsChain<G> {
     fn default() -> Self {
         Self::new(&[])
     }
 }
 
-impl Iterator for GeneratorsChain {
-    type Item = RistrettoPoint;
+impl<G: PrimeGroup> Iterator for GeneratorsChain<G> {
+    type Item = G;
 
     fn next(&mut self) -> Option<Self::Item> {
         let mut uniform_bytes = [0u8; 64];
         self.reader.read(&mut uniform_bytes);
 
-        Some(RistrettoPoint::from_uniform_bytes(&uniform_bytes))
+        Some(G::from_uniform_bytes(&uniform_bytes))
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (usize::max_value(), None)
+        (usize::MAX, None)
     }
 }
 
@@ -114,7 +145,8 @@ impl Iterator for GeneratorsChain {
 ///
 /// To construct an arbitrary-length chain of generators, we apply
 /// SHAKE256 to a domain separator label, and feed each 64 bytes of
-/// XOF output into the `ristretto255` hash-to-group function.
+/// XOF output into the hash-to-group function of the group, which
+/// for `ristretto255` is its Elligator map.
 /// Each of the `m` parties' generators are constructed using a
 /// different domain separation label, and proving and verification
 /// uses the first `n` elements of the arbitrary-length chain.
@@ -130,30 +162,30 @@ impl Iterator for GeneratorsChain {
 /// constraint system proofs, since the generators are namespaced by
 /// their party index.
 #[derive(Clone)]
-pub struct BulletproofGens {
+pub struct BulletproofGens<G: PrimeGroup = RistrettoPoint> {
     /// The maximum number of usable generators for each party.
     pub gens_capacity: usize,
     /// Number of values or parties
     pub party_capacity: usize,
     /// Precomputed \\(\mathbf G\\) generators for each party.
-    pub G_vec: Vec<Vec<RistrettoPoint>>,
+    pub G_vec: Vec<Vec<G>>,
     /// Precomputed \\(\mathbf H\\) generators for each party.
-    pub H_vec: Vec<Vec<RistrettoPoint>>,
+    pub H_vec: Vec<Vec<G>>,
 }
 
-impl BulletproofGens {
+impl<G: PrimeGroup> BulletproofGens<G> {
     /// Create a new `BulletproofGens` object.
     ///
     /// # Inputs
     ///
     /// * `gens_capacity` is the number of generators to precompute
-    ///    for each party.  For rangeproofs, it is sufficient to pass
-    ///    `64`, the maximum bitsize of the rangeproofs.  For circuit
-    ///    proofs, the capacity must be greater than the number of
-    ///    multipliers, rounded up to the next power of two.
+    ///   for each party.  For rangeproofs, it is sufficient to pass
+    ///   `64`, the maximum bitsize of the rangeproofs.  For circuit
+    ///   proofs, the capacity must be greater than the number of
+    ///   multipliers, rounded up to the next power of two.
     ///
     /// * `party_capacity` is the maximum number of parties that can
-    ///    produce an aggregated proof.
+    ///   produce an aggregated proof.
     pub fn new(gens_capacity: usize, party_capacity: usize) -> Self {
         let mut gens = BulletproofGens {
             gens_capacity: 0,
@@ -167,9 +199,9 @@ impl BulletproofGens {
 
     /// Returns j-th share of generators, with an appropriate
     /// slice of vectors G and H for the j-th range proof.
-    pub fn share(&self, j: usize) -> BulletproofGensShare<'_> {
+    pub fn share(&self, j: usize) -> BulletproofGensShare<'_, G> {
         BulletproofGensShare {
-            gens: &self,
+            gens: self,
             share: j,
         }
     }
@@ -188,14 +220,14 @@ impl BulletproofGens {
             let mut label = [b'G', 0, 0, 0, 0];
             LittleEndian::write_u32(&mut label[1..5], party_index);
             self.G_vec[i].extend(
-                &mut GeneratorsChain::new(&label)
+                &mut GeneratorsChain::<G>::new(&label)
                     .fast_forward(self.gens_capacity)
                     .take(new_capacity - self.gens_capacity),
             );
 
             label[0] = b'H';
             self.H_vec[i].extend(
-                &mut GeneratorsChain::new(&label)
+                &mut GeneratorsChain::<G>::new(&label)
                     .fast_forward(self.gens_capacity)
                     .take(new_capacity - self.gens_capacity),
             );
@@ -203,8 +235,26 @@ impl BulletproofGens {
         self.gens_capacity = new_capacity;
     }
 
+    /// Checks that there are `gens_capacity` G and H generators for each
+    /// of the `party_capacity` parties, and that all of them are distinct
+    /// and not the identity. Returns an `InvalidGenerators` error if not.
+    ///
+    /// Generators built with `new` are always valid, but the verifier
+    /// should check generators it did not derive itself, as bases with a
+    /// known relation break the soundness of the proofs.
+    pub fn validate(&self) -> Result<(), ProofError> {
+        let vecs = self.G_vec.iter().chain(self.H_vec.iter());
+        if self.G_vec.len() != self.party_capacity
+            || self.H_vec.len() != self.party_capacity
+            || vecs.clone().any(|gens| gens.len() != self.gens_capacity)
+        {
+            return Err(ProofError::InvalidGenerators);
+        }
+        validate_bases(vecs.flatten())
+    }
+
     /// Return an iterator over the aggregation of the parties' G generators with given size `n`.
-    pub fn G(&self, n: usize, m: usize) -> impl Iterator<Item = &RistrettoPoint> {
+    pub fn G(&self, n: usize, m: usize) -> impl Iterator<Item = &G> {
         AggregatedGensIter {
             n,
             m,
@@ -215,7 +265,7 @@ impl BulletproofGens {
     }
 
     /// Return an iterator over the aggregation of the parties' H generators with given size `n`.
-    pub fn H(&self, n: usize, m: usize) -> impl Iterator<Item = &RistrettoPoint> {
+    pub fn H(&self, n: usize, m: usize) -> impl Iterator<Item = &G> {
         AggregatedGensIter {
             n,
             m,
@@ -226,16 +276,16 @@ impl BulletproofGens {
     }
 }
 
-struct AggregatedGensIter<'a> {
-    array: &'a Vec<Vec<RistrettoPoint>>,
+struct AggregatedGensIter<'a, G> {
+    array: &'a Vec<Vec<G>>,
     n: usize,
     m: usize,
     party_idx: usize,
     gen_idx: usize,
 }
 
-impl<'a> Iterator for AggregatedGensIter<'a> {
-    type Item = &'a RistrettoPoint;
+impl<'a, G> Iterator for AggregatedGensIter<'a, G> {
+    type Item = &'a G;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.gen_idx >= self.n {
@@ -267,21 +317,21 @@ impl<'a> Iterator for AggregatedGensIter<'a> {
 ///
 /// The `BulletproofGensShare` is produced by [`BulletproofGens::share()`].
 #[derive(Copy, Clone)]
-pub struct BulletproofGensShare<'a> {
+pub struct BulletproofGensShare<'a, G: PrimeGroup = RistrettoPoint> {
     /// The parent object that this is a view into
-    gens: &'a BulletproofGens,
+    gens: &'a BulletproofGens<G>,
     /// Which share we are
     share: usize,
 }
 
-impl<'a> BulletproofGensShare<'a> {
+impl<'a, G: PrimeGroup> BulletproofGensShare<'a, G> {
     /// Return an iterator over this party's G generators with given size `n`.
-    pub fn G(&self, n: usize) -> impl Iterator<Item = &'a RistrettoPoint> {
+    pub fn G(&self, n: usize) -> impl Iterator<Item = &'a G> {
         self.gens.G_vec[self.share].iter().take(n)
     }
 
     /// Return an iterator over this party's H generators with given size `n`.
-    pub fn H(&self, n: usize) -> impl Iterator<Item = &'a RistrettoPoint> {
+    pub fn H(&self, n: usize) -> impl Iterator<Item = &'a G> {
         self.gens.H_vec[self.share].iter().take(n)
     }
 }
@@ -289,10 +339,53 @@ impl<'a> BulletproofGensShare<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use curve25519_dalek::traits::Identity;
+
+    #[test]
+    fn default_pedersen_gens_are_unchanged() {
+        use curve25519_dalek::constants::{RISTRETTO_BASEPOINT_COMPRESSED, RISTRETTO_BASEPOINT_POINT};
+        use sha3::Sha3_512;
+
+        let pc_gens: PedersenGens = PedersenGens::default();
+        assert_eq!(pc_gens.B, RISTRETTO_BASEPOINT_POINT);
+        assert_eq!(
+            pc_gens.B_blinding,
+            RistrettoPoint::hash_from_bytes::<Sha3_512>(RISTRETTO_BASEPOINT_COMPRESSED.as_bytes())
+        );
+    }
+
+    #[test]
+    fn invalid_gens_are_rejected() {
+        let pc_gens: PedersenGens = PedersenGens::default();
+        assert!(pc_gens.validate().is_ok());
+        let repeated = PedersenGens { B: pc_gens.B, B_blinding: pc_gens.B };
+        assert_eq!(repeated.validate(), Err(ProofError::InvalidGenerators));
+        let identity = PedersenGens { B: pc_gens.B, B_blinding: RistrettoPoint::identity() };
+        assert_eq!(identity.validate(), Err(ProofError::InvalidGenerators));
+
+        let gens: BulletproofGens = BulletproofGens::new(16, 2);
+        assert!(gens.validate().is_ok());
+
+        let mut repeated = gens.clone();
+        repeated.H_vec[1][3] = repeated.G_vec[0][5];
+        assert_eq!(repeated.validate(), Err(ProofError::InvalidGenerators));
+
+        let mut identity = gens.clone();
+        identity.G_vec[1][0] = RistrettoPoint::identity();
+        assert_eq!(identity.validate(), Err(ProofError::InvalidGenerators));
+
+        let mut truncated = gens.clone();
+        truncated.H_vec[0].pop();
+        assert_eq!(truncated.validate(), Err(ProofError::InvalidGenerators));
+
+        let mut missing_party = gens;
+        missing_party.party_capacity = 3;
+        assert_eq!(missing_party.validate(), Err(ProofError::InvalidGenerators));
+    }
 
     #[test]
     fn aggregated_gens_iter_matches_flat_map() {
-        let gens = BulletproofGens::new(64, 8);
+        let gens: BulletproofGens = BulletproofGens::new(64, 8);
 
         let helper = |n: usize, m: usize| {
             let agg_G: Vec<RistrettoPoint> = gens.G(n, m).cloned().collect();
@@ -333,9 +426,9 @@ mod tests {
 
     #[test]
     fn resizing_small_gens_matches_creating_bigger_gens() {
-        let gens = BulletproofGens::new(64, 8);
+        let gens: BulletproofGens = BulletproofGens::new(64, 8);
 
-        let mut gen_resized = BulletproofGens::new(32, 8);
+        let mut gen_resized: BulletproofGens = BulletproofGens::new(32, 8);
         gen_resized.increase_capacity(64);
 
         let helper = |n: usize, m: usize| {
@@ -0,0 +1,106 @@
+//! Implementation of [`PrimeGroup`] for the G1 group of BLS12-381, so
+//! that the proofs can be verified alongside BLS signatures and other
+//! constructions standardized on this curve.
+//!
+//! Points are encoded in the 48-byte compressed form of the
+//! [ZCash serialization format](https://github.com/zkcrypto/pairing/tree/master/src/bls12_381#serialization),
+//! and mapped from uniform bytes with the `BLS12381G1_XMD:SHA-256_SSWU_RO_`
+//! suite of the hash-to-curve standard. `bls12_381` does not provide a
+//! multiscalar multiplication, so the one provided by [`PrimeGroup`] is
+//! used.
+
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+use bls12_381::{G1Affine, G1Projective, Scalar};
+
+use crate::group::{CompressedPoint, PrimeGroup, PrimeScalar};
+
+/// Domain separation tag of the hash-to-curve suite used to derive the
+/// generators.
+const HASH_TO_CURVE_DST: &[u8] = b"zkSVM-V01-CS01-with-BLS12381G1_XMD:SHA-256_SSWU_RO_";
+
+/// A compressed G1 point of BLS12-381.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct G1Compressed(pub [u8; 48]);
+
+impl PrimeScalar for Scalar {
+    const SIZE: usize = 32;
+
+    fn from_uniform_bytes(bytes: &[u8; 64]) -> Self {
+        Scalar::from_bytes_wide(bytes)
+    }
+}
+
+impl CompressedPoint for G1Compressed {
+    type Point = G1Projective;
+
+    const SIZE: usize = 48;
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn read(bytes: &[u8]) -> Self {
+        let mut buf = [0u8; 48];
+        buf.copy_from_slice(&bytes[..48]);
+        G1Compressed(buf)
+    }
+
+    fn decompress(&self) -> Option<G1Projective> {
+        Option::<G1Affine>::from(G1Affine::from_compressed(&self.0)).map(G1Projective::from)
+    }
+}
+
+impl PrimeGroup for G1Projective {
+    type ScalarField = Scalar;
+    type Compressed = G1Compressed;
+
+    fn compress(&self) -> G1Compressed {
+        G1Compressed(G1Affine::from(self).to_compressed())
+    }
+
+    fn from_uniform_bytes(bytes: &[u8; 64]) -> Self {
+        <G1Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(
+            &bytes[..],
+            HASH_TO_CURVE_DST,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ff::Field;
+    use group::Group;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn encoding_round_trips() {
+        let mut rng = ChaChaRng::from_seed([24u8; 32]);
+
+        let point = G1Projective::random(&mut rng);
+        let compressed = point.compress();
+        assert_eq!(compressed.decompress(), Some(point));
+        assert_eq!(G1Compressed::read(compressed.as_bytes()), compressed);
+        assert!(!compressed.is_identity());
+        assert!(G1Projective::identity().compress().is_identity());
+
+        // Not on the curve
+        assert_eq!(G1Compressed([0xffu8; 48]).decompress(), None);
+
+        let scalar = Scalar::random(&mut rng);
+        assert_eq!(Scalar::from_canonical_slice(&scalar.to_bytes()), Some(scalar));
+        assert_eq!(Scalar::from_canonical_slice(&[0xffu8; 32]), None);
+    }
+
+    #[test]
+    fn generators_are_distinct_and_in_the_group() {
+        let P = G1Projective::from_uniform_bytes(&[1u8; 64]);
+        let Q = G1Projective::from_uniform_bytes(&[2u8; 64]);
+        assert_ne!(P, Q);
+        assert!(!bool::from(P.is_identity()));
+        assert!(bool::from(G1Affine::from(P).is_torsion_free()));
+        assert_eq!(P, G1Projective::from_uniform_bytes(&[1u8; 64]));
+    }
+}
@@ -0,0 +1,239 @@
+//! The `group` module abstracts the prime-order group in which the
+//! commitments and the proofs are computed.
+//!
+//! The generators, the transcript helpers, the inner product proof and
+//! the [`InnerProductZKProof`](crate::InnerProductZKProof) are generic
+//! over a [`PrimeGroup`], with the `ristretto255` group of
+//! `curve25519-dalek` as the default. The range proof is only
+//! implemented over `ristretto255`.
+//!
+//! With the `bls12_381` feature, [`PrimeGroup`] is also implemented for
+//! the G1 group of BLS12-381, and with the `secp256k1` feature for the
+//! secp256k1 curve.
+
+#![allow(non_snake_case)]
+#![deny(missing_docs)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{MultiscalarMul, VartimeMultiscalarMul};
+use digest::Digest;
+use ff::PrimeField;
+use group::Group;
+use sha3::Sha3_512;
+use zeroize::Zeroize;
+
+#[cfg(feature = "bls12_381")]
+pub mod bls12_381_g1;
+#[cfg(feature = "secp256k1")]
+pub mod secp256k1;
+
+/// Scalar field of a [`PrimeGroup`].
+pub trait PrimeScalar: PrimeField + Zeroize {
+    /// Length in bytes of the canonical encoding of a scalar.
+    const SIZE: usize;
+
+    /// Reduces 64 uniformly random bytes into a scalar, so that the result
+    /// is statistically close to uniform. This is used to derive the
+    /// challenges from the transcript.
+    fn from_uniform_bytes(bytes: &[u8; 64]) -> Self;
+
+    /// Reads a scalar from the first `SIZE` bytes of `bytes`, returning
+    /// `None` if they are not its canonical encoding.
+    fn from_canonical_slice(bytes: &[u8]) -> Option<Self> {
+        let mut repr = Self::Repr::default();
+        if bytes.len() < Self::SIZE || repr.as_ref().len() != Self::SIZE {
+            return None;
+        }
+        repr.as_mut().copy_from_slice(&bytes[..Self::SIZE]);
+        Option::from(Self::from_repr(repr))
+    }
+}
+
+/// Compressed encoding of the points of a [`PrimeGroup`], which is what
+/// the proofs carry and what is appended to the transcript.
+pub trait CompressedPoint: Copy + Clone + Debug + PartialEq + Eq + Send + Sync + 'static {
+    /// The group this is an encoding of.
+    type Point: PrimeGroup<Compressed = Self>;
+
+    /// Length in bytes of the encoding.
+    const SIZE: usize;
+
+    /// View the encoding as bytes.
+    fn as_bytes(&self) -> &[u8];
+
+    /// Reads an encoding from the first `SIZE` bytes of `bytes`, without
+    /// checking that it is a valid point. Panics if `bytes` is shorter.
+    fn read(bytes: &[u8]) -> Self;
+
+    /// Returns the point, or `None` if the encoding is invalid.
+    fn decompress(&self) -> Option<Self::Point>;
+
+    /// Whether this is the encoding of the identity.
+    fn is_identity(&self) -> bool {
+        *self == PrimeGroup::compress(&Self::Point::identity())
+    }
+
+    /// Whether this is a valid encoding of a point, and the one `compress`
+    /// returns for it, so that no other encoding decodes to the same point.
+    fn is_canonical(&self) -> bool {
+        self.decompress()
+            .is_some_and(|point| PrimeGroup::compress(&point) == *self)
+    }
+}
+
+/// A group of prime order in which the discrete logarithm problem is hard.
+///
+/// On top of the group operations of [`group::Group`], the proofs need a
+/// canonical compressed encoding of the points, a way to map uniform bytes
+/// to points with unknown discrete logarithm relations (to derive the
+/// generators), and multiscalar multiplication.
+pub trait PrimeGroup: Group<Scalar = <Self as PrimeGroup>::ScalarField> {
+    /// The scalar field, which is the same as [`group::Group::Scalar`].
+    type ScalarField: PrimeScalar;
+
+    /// Compressed encoding of the points.
+    type Compressed: CompressedPoint<Point = Self>;
+
+    /// Compress the point into its canonical encoding.
+    fn compress(&self) -> Self::Compressed;
+
+    /// Maps 64 uniformly random bytes to a point. The discrete logarithm
+    /// relations between the resulting points must be unknown.
+    fn from_uniform_bytes(bytes: &[u8; 64]) -> Self;
+
+    /// Hashes `bytes` to a point with SHA3-512.
+    fn hash_from_bytes(bytes: &[u8]) -> Self {
+        let mut uniform_bytes = [0u8; 64];
+        uniform_bytes.copy_from_slice(&Sha3_512::digest(bytes));
+        Self::from_uniform_bytes(&uniform_bytes)
+    }
+
+    /// Computes \\(\sum_i s_i P_i\\) in constant time. The default
+    /// implementation multiplies each point separately.
+    fn multiscalar_mul(scalars: &[Self::Scalar], points: &[Self]) -> Self {
+        scalars.iter().zip(points.iter()).map(|(s, P)| *P * s).sum()
+    }
+
+    /// Computes \\(\sum_i s_i P_i\\) in variable time.
+    fn vartime_multiscalar_mul(scalars: &[Self::Scalar], points: &[Self]) -> Self {
+        Self::multiscalar_mul(scalars, points)
+    }
+
+    /// Computes \\(\sum_i s_i P_i\\) in variable time, returning `None` if
+    /// any of the points is `None`.
+    fn optional_vartime_multiscalar_mul(
+        scalars: &[Self::Scalar],
+        points: &[Option<Self>],
+    ) -> Option<Self> {
+        let points = points.iter().cloned().collect::<Option<Vec<Self>>>()?;
+        Some(Self::vartime_multiscalar_mul(scalars, &points))
+    }
+}
+
+impl PrimeScalar for Scalar {
+    const SIZE: usize = 32;
+
+    fn from_uniform_bytes(bytes: &[u8; 64]) -> Self {
+        Scalar::from_bytes_mod_order_wide(bytes)
+    }
+}
+
+impl CompressedPoint for CompressedRistretto {
+    type Point = RistrettoPoint;
+
+    const SIZE: usize = 32;
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn read(bytes: &[u8]) -> Self {
+        CompressedRistretto(crate::util::read32(bytes))
+    }
+
+    fn decompress(&self) -> Option<RistrettoPoint> {
+        CompressedRistretto::decompress(self)
+    }
+}
+
+impl PrimeGroup for RistrettoPoint {
+    type ScalarField = Scalar;
+    type Compressed = CompressedRistretto;
+
+    fn compress(&self) -> CompressedRistretto {
+        RistrettoPoint::compress(self)
+    }
+
+    fn from_uniform_bytes(bytes: &[u8; 64]) -> Self {
+        RistrettoPoint::from_uniform_bytes(bytes)
+    }
+
+    fn multiscalar_mul(scalars: &[Scalar], points: &[RistrettoPoint]) -> Self {
+        <RistrettoPoint as MultiscalarMul>::multiscalar_mul(scalars, points)
+    }
+
+    fn vartime_multiscalar_mul(scalars: &[Scalar], points: &[RistrettoPoint]) -> Self {
+        <RistrettoPoint as VartimeMultiscalarMul>::vartime_multiscalar_mul(scalars, points)
+    }
+
+    fn optional_vartime_multiscalar_mul(
+        scalars: &[Scalar],
+        points: &[Option<RistrettoPoint>],
+    ) -> Option<Self> {
+        <RistrettoPoint as VartimeMultiscalarMul>::optional_multiscalar_mul(
+            scalars,
+            points.iter().cloned(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use curve25519_dalek::constants::{RISTRETTO_BASEPOINT_COMPRESSED, RISTRETTO_BASEPOINT_POINT};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn ristretto_matches_dalek() {
+        let mut rng = ChaChaRng::from_seed([24u8; 32]);
+
+        assert_eq!(
+            <RistrettoPoint as PrimeGroup>::hash_from_bytes(b"test point"),
+            RistrettoPoint::hash_from_bytes::<Sha3_512>(b"test point")
+        );
+        assert_eq!(
+            PrimeGroup::compress(&RistrettoPoint::generator()),
+            RISTRETTO_BASEPOINT_COMPRESSED
+        );
+        assert!(CompressedPoint::is_identity(&PrimeGroup::compress(&RistrettoPoint::identity())));
+        assert!(!CompressedPoint::is_identity(&RISTRETTO_BASEPOINT_COMPRESSED));
+
+        let scalars: Vec<Scalar> = (0..4).map(|_| Scalar::random(&mut rng)).collect();
+        let points: Vec<RistrettoPoint> = (0..4).map(|_| RistrettoPoint::random(&mut rng)).collect();
+        let expected = <RistrettoPoint as MultiscalarMul>::multiscalar_mul(&scalars, &points);
+        assert_eq!(<RistrettoPoint as PrimeGroup>::vartime_multiscalar_mul(&scalars, &points), expected);
+
+        // The provided implementation, as other groups get it
+        let naive: RistrettoPoint = scalars.iter().zip(points.iter()).map(|(s, P)| P * s).sum();
+        assert_eq!(naive, expected);
+
+        let scalar = Scalar::random(&mut rng);
+        assert_eq!(Scalar::from_canonical_slice(scalar.as_bytes()), Some(scalar));
+        assert_eq!(Scalar::from_canonical_slice(&[0xff; 32]), None);
+        assert_eq!(Scalar::from_canonical_slice(&[0u8; 31]), None);
+        assert_eq!(
+            <CompressedRistretto as CompressedPoint>::read(RISTRETTO_BASEPOINT_COMPRESSED.as_bytes()).decompress(),
+            Some(RISTRETTO_BASEPOINT_POINT)
+        );
+        assert!(RISTRETTO_BASEPOINT_COMPRESSED.is_canonical());
+        assert!(!CompressedRistretto([0xff; 32]).is_canonical());
+    }
+}
@@ -0,0 +1,112 @@
+//! Implementation of [`PrimeGroup`] for secp256k1, on top of `k256`, so
+//! that the commitments and proofs can live next to existing secp256k1
+//! key infrastructure.
+//!
+//! Points are encoded in the 33-byte SEC1 compressed form, with the
+//! identity encoded as 33 zero bytes, and mapped from uniform bytes with
+//! the `secp256k1_XMD:SHA-256_SSWU_RO_` suite of the hash-to-curve
+//! standard. `k256` does not provide a multiscalar multiplication for
+//! arbitrary lengths, so the one provided by [`PrimeGroup`] is used.
+
+use group::GroupEncoding;
+use k256::elliptic_curve::bigint::U512;
+use k256::elliptic_curve::hash2curve::{ExpandMsgXmd, GroupDigest};
+use k256::elliptic_curve::ops::Reduce;
+use k256::{ProjectivePoint, Scalar, Secp256k1, WideBytes};
+
+use crate::group::{CompressedPoint, PrimeGroup, PrimeScalar};
+
+/// Domain separation tag of the hash-to-curve suite used to derive the
+/// generators.
+const HASH_TO_CURVE_DST: &[u8] = b"zkSVM-V01-CS01-with-secp256k1_XMD:SHA-256_SSWU_RO_";
+
+/// A compressed secp256k1 point.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Secp256k1Compressed(pub [u8; 33]);
+
+impl PrimeScalar for Scalar {
+    const SIZE: usize = 32;
+
+    fn from_uniform_bytes(bytes: &[u8; 64]) -> Self {
+        <Scalar as Reduce<U512>>::reduce_bytes(WideBytes::from_slice(bytes))
+    }
+}
+
+impl CompressedPoint for Secp256k1Compressed {
+    type Point = ProjectivePoint;
+
+    const SIZE: usize = 33;
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn read(bytes: &[u8]) -> Self {
+        let mut buf = [0u8; 33];
+        buf.copy_from_slice(&bytes[..33]);
+        Secp256k1Compressed(buf)
+    }
+
+    fn decompress(&self) -> Option<ProjectivePoint> {
+        let repr = <ProjectivePoint as GroupEncoding>::Repr::clone_from_slice(&self.0);
+        ProjectivePoint::from_bytes(&repr).into()
+    }
+}
+
+impl PrimeGroup for ProjectivePoint {
+    type ScalarField = Scalar;
+    type Compressed = Secp256k1Compressed;
+
+    fn compress(&self) -> Secp256k1Compressed {
+        let mut buf = [0u8; 33];
+        buf.copy_from_slice(&self.to_bytes());
+        Secp256k1Compressed(buf)
+    }
+
+    fn from_uniform_bytes(bytes: &[u8; 64]) -> Self {
+        Secp256k1::hash_from_bytes::<ExpandMsgXmd<k256::sha2::Sha256>>(&[&bytes[..]], &[HASH_TO_CURVE_DST])
+            .expect("the domain separation tag is valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ff::{Field, PrimeField};
+    use group::Group;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn encoding_round_trips() {
+        let mut rng = ChaChaRng::from_seed([24u8; 32]);
+
+        let point = ProjectivePoint::random(&mut rng);
+        let compressed = point.compress();
+        assert_eq!(compressed.decompress(), Some(point));
+        assert_eq!(Secp256k1Compressed::read(compressed.as_bytes()), compressed);
+        assert!(!compressed.is_identity());
+
+        let identity = ProjectivePoint::IDENTITY.compress();
+        assert!(identity.is_identity());
+        assert_eq!(identity, Secp256k1Compressed([0u8; 33]));
+        assert_eq!(identity.decompress(), Some(ProjectivePoint::IDENTITY));
+
+        // Not a valid SEC1 tag
+        assert_eq!(Secp256k1Compressed([0xffu8; 33]).decompress(), None);
+
+        let scalar = Scalar::random(&mut rng);
+        assert_eq!(Scalar::from_canonical_slice(&scalar.to_repr()), Some(scalar));
+        assert_eq!(Scalar::from_canonical_slice(&[0xffu8; 32]), None);
+    }
+
+    #[test]
+    fn generators_are_distinct() {
+        let P = ProjectivePoint::from_uniform_bytes(&[1u8; 64]);
+        let Q = ProjectivePoint::from_uniform_bytes(&[2u8; 64]);
+        assert_ne!(P, Q);
+        assert!(!bool::from(P.is_identity()));
+        assert_eq!(P, ProjectivePoint::from_uniform_bytes(&[1u8; 64]));
+    }
+}
@@ -3,26 +3,43 @@
 extern crate alloc;
 
 use alloc::borrow::Borrow;
+#[cfg(not(feature = "prover-only"))]
+use alloc::vec;
 use alloc::vec::Vec;
 
 use core::iter;
-use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
-use curve25519_dalek::scalar::Scalar;
-use curve25519_dalek::traits::VartimeMultiscalarMul;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use ff::{BatchInvert, Field, PrimeField};
 use merlin::Transcript;
 
 use crate::errors::ProofError;
+use crate::format::{CanonicalPoints, ProofKind, ProofReader, SerializedSize, HEADER_SIZE};
+use crate::group::{CompressedPoint, PrimeGroup, PrimeScalar};
+use crate::msm::{prover_multiscalar_mul, CpuMsmBackend, MsmBackend};
 use crate::transcript::TranscriptProtocol;
 
-#[derive(Clone, Debug)]
-pub struct InnerProductProof {
-    pub(crate) L_vec: Vec<CompressedRistretto>,
-    pub(crate) R_vec: Vec<CompressedRistretto>,
-    pub(crate) a: Scalar,
-    pub(crate) b: Scalar,
+#[cfg(not(feature = "prover-only"))]
+use rand_core::{CryptoRng, RngCore};
+
+/// Maximum number of rounds of a serialized inner product proof, i.e. the
+/// proof of vectors of length up to \\(2^{31}\\).
+pub const MAX_IPP_ROUNDS: usize = 31;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InnerProductProof<G: PrimeGroup = RistrettoPoint> {
+    pub(crate) L_vec: Vec<G::Compressed>,
+    pub(crate) R_vec: Vec<G::Compressed>,
+    pub(crate) a: G::Scalar,
+    pub(crate) b: G::Scalar,
 }
 
-impl InnerProductProof {
+/// The verification scalars \\([u\_{i}^{2}]\\), \\([u\_{i}^{-2}]\\) and \\([s\_{i}]\\) of
+/// `InnerProductProof::verification_scalars`.
+pub type VerificationScalars<S> = (Vec<S>, Vec<S>, Vec<S>);
+
+impl<G: PrimeGroup> InnerProductProof<G> {
     /// Create an inner-product proof.
     ///
     /// The proof is created with respect to the bases \\(G\\), \\(H'\\),
@@ -36,14 +53,40 @@ impl InnerProductProof {
     /// either 0 or a power of 2.
     pub fn create(
         transcript: &mut Transcript,
-        Q: &RistrettoPoint,
-        G_factors: &[Scalar],
-        H_factors: &[Scalar],
-        mut G_vec: Vec<RistrettoPoint>,
-        mut H_vec: Vec<RistrettoPoint>,
-        mut a_vec: Vec<Scalar>,
-        mut b_vec: Vec<Scalar>,
-    ) -> InnerProductProof {
+        Q: &G,
+        G_factors: &[G::Scalar],
+        H_factors: &[G::Scalar],
+        G_vec: Vec<G>,
+        H_vec: Vec<G>,
+        a_vec: Vec<G::Scalar>,
+        b_vec: Vec<G::Scalar>,
+    ) -> InnerProductProof<G> {
+        InnerProductProof::create_with_backend(
+            &CpuMsmBackend,
+            transcript,
+            Q,
+            G_factors,
+            H_factors,
+            G_vec,
+            H_vec,
+            a_vec,
+            b_vec,
+        )
+    }
+
+    /// Create an inner-product proof, computing the \\(L\\) and \\(R\\)
+    /// commitments of each round with the given `MsmBackend`.
+    pub fn create_with_backend<B: MsmBackend<G> + ?Sized>(
+        backend: &B,
+        transcript: &mut Transcript,
+        Q: &G,
+        G_factors: &[G::Scalar],
+        H_factors: &[G::Scalar],
+        mut G_vec: Vec<G>,
+        mut H_vec: Vec<G>,
+        mut a_vec: Vec<G::Scalar>,
+        mut b_vec: Vec<G::Scalar>,
+    ) -> InnerProductProof<G> {
         // Create slices G, H, a, b backed by their respective
         // vectors.  This lets us reslice as we compress the lengths
         // of the vectors in the main loop below.
@@ -74,40 +117,44 @@ impl InnerProductProof {
         // If it's the first iteration, unroll the Hprime = H*y_inv scalar mults
         // into multiscalar muls, for performance.
         if n != 1 {
-            n = n / 2;
+            n /= 2;
             let (a_L, a_R) = a.split_at_mut(n);
             let (b_L, b_R) = b.split_at_mut(n);
             let (G_L, G_R) = G.split_at_mut(n);
             let (H_L, H_R) = H.split_at_mut(n);
 
-            let c_L = inner_product(&a_L, &b_R);
-            let c_R = inner_product(&a_R, &b_L);
+            let c_L = inner_product(a_L, b_R);
+            let c_R = inner_product(a_R, b_L);
 
-            let L = RistrettoPoint::vartime_multiscalar_mul(
-                a_L.iter()
-                    .zip(G_factors[n..2 * n].into_iter())
-                    .map(|(a_L_i, g)| a_L_i * g)
+            let L = prover_multiscalar_mul(
+                backend,
+                &a_L.iter()
+                    .zip(G_factors[n..2 * n].iter())
+                    .map(|(a_L_i, g)| *a_L_i * g)
                     .chain(
                         b_R.iter()
-                            .zip(H_factors[0..n].into_iter())
-                            .map(|(b_R_i, h)| b_R_i * h),
+                            .zip(H_factors[0..n].iter())
+                            .map(|(b_R_i, h)| *b_R_i * h),
                     )
-                    .chain(iter::once(c_L)),
-                G_R.iter().chain(H_L.iter()).chain(iter::once(Q)),
+                    .chain(iter::once(c_L))
+                    .collect::<Vec<G::Scalar>>(),
+                &G_R.iter().chain(H_L.iter()).chain(iter::once(Q)).cloned().collect::<Vec<G>>(),
             )
             .compress();
 
-            let R = RistrettoPoint::vartime_multiscalar_mul(
-                a_R.iter()
-                    .zip(G_factors[0..n].into_iter())
-                    .map(|(a_R_i, g)| a_R_i * g)
+            let R = prover_multiscalar_mul(
+                backend,
+                &a_R.iter()
+                    .zip(G_factors[0..n].iter())
+                    .map(|(a_R_i, g)| *a_R_i * g)
                     .chain(
                         b_L.iter()
-                            .zip(H_factors[n..2 * n].into_iter())
-                            .map(|(b_L_i, h)| b_L_i * h),
+                            .zip(H_factors[n..2 * n].iter())
+                            .map(|(b_L_i, h)| *b_L_i * h),
                     )
-                    .chain(iter::once(c_R)),
-                G_L.iter().chain(H_R.iter()).chain(iter::once(Q)),
+                    .chain(iter::once(c_R))
+                    .collect::<Vec<G::Scalar>>(),
+                &G_L.iter().chain(H_R.iter()).chain(iter::once(Q)).cloned().collect::<Vec<G>>(),
             )
             .compress();
 
@@ -117,17 +164,17 @@ impl InnerProductProof {
             transcript.append_point(b"L", &L);
             transcript.append_point(b"R", &R);
 
-            let u = transcript.challenge_scalar(b"u");
-            let u_inv = u.invert();
+            let u: G::Scalar = transcript.challenge_scalar(b"u");
+            let u_inv = u.invert().unwrap();
 
             for i in 0..n {
                 a_L[i] = a_L[i] * u + u_inv * a_R[i];
                 b_L[i] = b_L[i] * u_inv + u * b_R[i];
-                G_L[i] = RistrettoPoint::vartime_multiscalar_mul(
+                G_L[i] = G::vartime_multiscalar_mul(
                     &[u_inv * G_factors[i], u * G_factors[n + i]],
                     &[G_L[i], G_R[i]],
                 );
-                H_L[i] = RistrettoPoint::vartime_multiscalar_mul(
+                H_L[i] = G::vartime_multiscalar_mul(
                     &[u * H_factors[i], u_inv * H_factors[n + i]],
                     &[H_L[i], H_R[i]],
                 )
@@ -140,24 +187,26 @@ impl InnerProductProof {
         }
 
         while n != 1 {
-            n = n / 2;
+            n /= 2;
             let (a_L, a_R) = a.split_at_mut(n);
             let (b_L, b_R) = b.split_at_mut(n);
             let (G_L, G_R) = G.split_at_mut(n);
             let (H_L, H_R) = H.split_at_mut(n);
 
-            let c_L = inner_product(&a_L, &b_R);
-            let c_R = inner_product(&a_R, &b_L);
+            let c_L = inner_product(a_L, b_R);
+            let c_R = inner_product(a_R, b_L);
 
-            let L = RistrettoPoint::vartime_multiscalar_mul(
-                a_L.iter().chain(b_R.iter()).chain(iter::once(&c_L)),
-                G_R.iter().chain(H_L.iter()).chain(iter::once(Q)),
+            let L = prover_multiscalar_mul(
+                backend,
+                &a_L.iter().chain(b_R.iter()).chain(iter::once(&c_L)).cloned().collect::<Vec<G::Scalar>>(),
+                &G_R.iter().chain(H_L.iter()).chain(iter::once(Q)).cloned().collect::<Vec<G>>(),
             )
             .compress();
 
-            let R = RistrettoPoint::vartime_multiscalar_mul(
-                a_R.iter().chain(b_L.iter()).chain(iter::once(&c_R)),
-                G_L.iter().chain(H_R.iter()).chain(iter::once(Q)),
+            let R = prover_multiscalar_mul(
+                backend,
+                &a_R.iter().chain(b_L.iter()).chain(iter::once(&c_R)).cloned().collect::<Vec<G::Scalar>>(),
+                &G_L.iter().chain(H_R.iter()).chain(iter::once(Q)).cloned().collect::<Vec<G>>(),
             )
             .compress();
 
@@ -167,14 +216,14 @@ impl InnerProductProof {
             transcript.append_point(b"L", &L);
             transcript.append_point(b"R", &R);
 
-            let u = transcript.challenge_scalar(b"u");
-            let u_inv = u.invert();
+            let u: G::Scalar = transcript.challenge_scalar(b"u");
+            let u_inv = u.invert().unwrap();
 
             for i in 0..n {
                 a_L[i] = a_L[i] * u + u_inv * a_R[i];
                 b_L[i] = b_L[i] * u_inv + u * b_R[i];
-                G_L[i] = RistrettoPoint::vartime_multiscalar_mul(&[u_inv, u], &[G_L[i], G_R[i]]);
-                H_L[i] = RistrettoPoint::vartime_multiscalar_mul(&[u, u_inv], &[H_L[i], H_R[i]]);
+                G_L[i] = G::vartime_multiscalar_mul(&[u_inv, u], &[G_L[i], G_R[i]]);
+                H_L[i] = G::vartime_multiscalar_mul(&[u, u_inv], &[H_L[i], H_R[i]]);
             }
 
             a = a_L;
@@ -184,21 +233,32 @@ impl InnerProductProof {
         }
 
         InnerProductProof {
-            L_vec: L_vec,
-            R_vec: R_vec,
+            L_vec,
+            R_vec,
             a: a[0],
             b: b[0],
         }
     }
 
     /// Computes three vectors of verification scalars \\([u\_{i}^{2}]\\), \\([u\_{i}^{-2}]\\) and \\([s\_{i}]\\) for combined multiscalar multiplication
-    /// in a parent protocol. See [inner product protocol notes](index.html#verification-equation) for details.
-    /// The verifier must provide the input length \\(n\\) explicitly to avoid unbounded allocation within the inner product proof.
-    pub(crate) fn verification_scalars(
+    /// in a parent protocol. The verifier must provide the input length \\(n\\) explicitly to avoid unbounded allocation within the inner product proof.
+    ///
+    /// The proof is valid for the bases \\(G'\\), \\(H'\\), \\(Q\\) and the commitment \\(P\\)
+    /// (see `verify`) if and only if
+    /// \\[
+    /// P = a b \cdot Q + \sum\_i a s\_i \cdot G'\_i + \sum\_i b s\_{n - 1 - i} \cdot H'\_i
+    ///     - \sum\_j (u\_j^2 \cdot L\_j + u\_j^{-2} \cdot R\_j),
+    /// \\]
+    /// where \\(a, b\\) are the `final_scalars` and \\(L\_j, R\_j\\) the `proof_points`. As
+    /// \\(s\_{n - 1 - i} = s\_i^{-1}\\), the last sum is over the inverses of the \\(s\_i\\).
+    ///
+    /// This appends the \\(L\_j, R\_j\\) points to the transcript and derives the challenges
+    /// from it, so it must be called at the same point of the protocol as `create`.
+    pub fn verification_scalars(
         &self,
         n: usize,
         transcript: &mut Transcript,
-    ) -> Result<(Vec<Scalar>, Vec<Scalar>, Vec<Scalar>), ProofError> {
+    ) -> Result<VerificationScalars<G::Scalar>, ProofError> {
         let lg_n = self.L_vec.len();
         if lg_n >= 32 {
             // 4 billion multiplications should be enough for anyone
@@ -213,7 +273,7 @@ impl InnerProductProof {
 
         // 1. Recompute x_k,...,x_1 based on the proof transcript
 
-        let mut challenges = Vec::with_capacity(lg_n);
+        let mut challenges: Vec<G::Scalar> = Vec::with_capacity(lg_n);
         for (L, R) in self.L_vec.iter().zip(self.R_vec.iter()) {
             transcript.validate_and_append_point(b"L", L)?;
             transcript.validate_and_append_point(b"R", R)?;
@@ -223,7 +283,7 @@ impl InnerProductProof {
         // 2. Compute 1/(u_k...u_1) and 1/u_k, ..., 1/u_1
 
         let mut challenges_inv = challenges.clone();
-        let allinv = Scalar::batch_invert(&mut challenges_inv);
+        let allinv = challenges_inv.iter_mut().batch_invert();
 
         // 3. Compute u_i^2 and (1/u_i)^2
 
@@ -237,41 +297,50 @@ impl InnerProductProof {
 
         // 4. Compute s values inductively.
 
-        let mut s = Vec::with_capacity(n);
-        s.push(allinv);
-        for i in 1..n {
-            let lg_i = (32 - 1 - (i as u32).leading_zeros()) as usize;
-            let k = 1 << lg_i;
-            // The challenges are stored in "creation order" as [u_k,...,u_1],
-            // so u_{lg(i)+1} = is indexed by (lg_n-1) - lg_i
-            let u_lg_i_sq = challenges_sq[(lg_n - 1) - lg_i];
-            s.push(s[i - k] * u_lg_i_sq);
-        }
+        let s = folding_scalars(&challenges_sq, allinv);
 
         Ok((challenges_sq, challenges_inv_sq, s))
     }
 
-    /// This method is for testing that proof generation work,
-    /// but for efficiency the actual protocols would use `verification_scalars`
-    /// method to combine inner product verification with other checks
-    /// in a single multiscalar multiplication.
-    #[allow(dead_code)]
+    /// The points \\(L\_0, \dots, L\_{k-1}, R\_0, \dots, R\_{k-1}\\) of the proof, in the
+    /// order of the challenges returned by `verification_scalars`. A point is `None` if it
+    /// does not decompress.
+    pub fn proof_points(&self) -> impl Iterator<Item = Option<G>> + '_ {
+        self.L_vec
+            .iter()
+            .chain(self.R_vec.iter())
+            .map(|p| p.decompress())
+    }
+
+    /// The scalars \\(a, b\\) the vectors are folded into.
+    pub fn final_scalars(&self) -> (G::Scalar, G::Scalar) {
+        (self.a, self.b)
+    }
+
+    /// Verifies the proof for vectors of length `n`, with respect to the bases \\(G'\\),
+    /// \\(H'\\), where \\(G'\_i = G\_i \cdot \texttt{G\_factors}\_i\\) and
+    /// \\(H'\_i = H\_i \cdot \texttt{H\_factors}\_i\\), and the commitment
+    /// \\(P = \langle a, G' \rangle + \langle b, H' \rangle + \langle a, b \rangle Q\\).
+    ///
+    /// Protocols embedding the inner product proof can instead use the
+    /// `verification_scalars` to combine this check with their own in a single
+    /// multiscalar multiplication, and `batch_verify` checks several proofs at once.
     pub fn verify<IG, IH>(
         &self,
         n: usize,
         transcript: &mut Transcript,
         G_factors: IG,
         H_factors: IH,
-        P: &RistrettoPoint,
-        Q: &RistrettoPoint,
-        G: &[RistrettoPoint],
-        H: &[RistrettoPoint],
+        P: &G,
+        Q: &G,
+        G: &[G],
+        H: &[G],
     ) -> Result<(), ProofError>
     where
         IG: IntoIterator,
-        IG::Item: Borrow<Scalar>,
+        IG::Item: Borrow<G::Scalar>,
         IH: IntoIterator,
-        IH::Item: Borrow<Scalar>,
+        IH::Item: Borrow<G::Scalar>,
     {
         let (u_sq, u_inv_sq, s) = self.verification_scalars(n, transcript)?;
 
@@ -289,8 +358,8 @@ impl InnerProductProof {
             .zip(inv_s)
             .map(|(h_i, s_i_inv)| (self.b * s_i_inv) * h_i.borrow());
 
-        let neg_u_sq = u_sq.iter().map(|ui| -ui);
-        let neg_u_inv_sq = u_inv_sq.iter().map(|ui| -ui);
+        let neg_u_sq = u_sq.iter().map(|ui| -*ui);
+        let neg_u_inv_sq = u_inv_sq.iter().map(|ui| -*ui);
 
         let Ls = self
             .L_vec
@@ -304,17 +373,20 @@ impl InnerProductProof {
             .map(|p| p.decompress().ok_or(ProofError::VerificationError))
             .collect::<Result<Vec<_>, _>>()?;
 
-        let expect_P = RistrettoPoint::vartime_multiscalar_mul(
-            iter::once(self.a * self.b)
+        let expect_P = G::vartime_multiscalar_mul(
+            &iter::once(self.a * self.b)
                 .chain(g_times_a_times_s)
                 .chain(h_times_b_div_s)
                 .chain(neg_u_sq)
-                .chain(neg_u_inv_sq),
-            iter::once(Q)
+                .chain(neg_u_inv_sq)
+                .collect::<Vec<G::Scalar>>(),
+            &iter::once(Q)
                 .chain(G.iter())
                 .chain(H.iter())
                 .chain(Ls.iter())
-                .chain(Rs.iter()),
+                .chain(Rs.iter())
+                .cloned()
+                .collect::<Vec<G>>(),
         );
 
         if expect_P == *P {
@@ -324,86 +396,280 @@ impl InnerProductProof {
         }
     }
 
+    /// Verifies several proofs over the same bases \\(Q\\), \\(G\\), \\(H\\) with a single
+    /// multiscalar multiplication, where proof `i` is checked as in `verify` over
+    /// `transcripts[i]` with the factors `G_factors[i]` and `H_factors[i]`, whose length
+    /// is the length of its vectors, and the commitment `P[i]`. The checks are combined
+    /// with random weights taken from `rng`. Not available with the `prover-only` feature.
+    #[cfg(not(feature = "prover-only"))]
+    pub fn batch_verify<T: RngCore + CryptoRng>(
+        proofs: &[&InnerProductProof<G>],
+        transcripts: &mut [&mut Transcript],
+        G_factors: &[&[G::Scalar]],
+        H_factors: &[&[G::Scalar]],
+        P: &[G],
+        Q: &G,
+        G: &[G],
+        H: &[G],
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let nr_proofs = proofs.len();
+        if transcripts.len() != nr_proofs
+            || G_factors.len() != nr_proofs
+            || H_factors.len() != nr_proofs
+            || P.len() != nr_proofs
+        {
+            return Err(ProofError::VerificationError);
+        }
+        let max_n = G_factors.iter().map(|factors| factors.len()).max().unwrap_or(0);
+        if max_n > G.len() || max_n > H.len() {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        let mut Q_scalar = G::Scalar::ZERO;
+        let mut G_scalars = vec![G::Scalar::ZERO; max_n];
+        let mut H_scalars = vec![G::Scalar::ZERO; max_n];
+        let mut dynamic_scalars: Vec<G::Scalar> = Vec::new();
+        let mut dynamic_points: Vec<G> = Vec::new();
+
+        for i in 0..nr_proofs {
+            let proof = proofs[i];
+            let n = G_factors[i].len();
+            if H_factors[i].len() != n {
+                return Err(ProofError::VerificationError);
+            }
+            let (u_sq, u_inv_sq, s) = proof.verification_scalars(n, transcripts[i])?;
+            let weight = G::Scalar::random(&mut *rng);
+
+            Q_scalar += weight * proof.a * proof.b;
+            let weighted_a = weight * proof.a;
+            let weighted_b = weight * proof.b;
+            // 1/s[j] is s[!j], and !j runs from n-1 to 0 as j runs from 0 to n-1
+            for (j, (s_j, s_j_inv)) in s.iter().zip(s.iter().rev()).enumerate() {
+                G_scalars[j] += weighted_a * s_j * G_factors[i][j];
+                H_scalars[j] += weighted_b * s_j_inv * H_factors[i][j];
+            }
+
+            for (L, u_sq_j) in proof.L_vec.iter().zip(u_sq.iter()) {
+                dynamic_scalars.push(-(weight * u_sq_j));
+                dynamic_points.push(L.decompress().ok_or(ProofError::VerificationError)?);
+            }
+            for (R, u_inv_sq_j) in proof.R_vec.iter().zip(u_inv_sq.iter()) {
+                dynamic_scalars.push(-(weight * u_inv_sq_j));
+                dynamic_points.push(R.decompress().ok_or(ProofError::VerificationError)?);
+            }
+            dynamic_scalars.push(-weight);
+            dynamic_points.push(P[i]);
+        }
+
+        let _span = msm_span!(
+            "inner_product",
+            size = G_scalars.len() + H_scalars.len() + dynamic_scalars.len() + 1
+        );
+        let mega_check = G::vartime_multiscalar_mul(
+            &iter::once(Q_scalar)
+                .chain(G_scalars)
+                .chain(H_scalars)
+                .chain(dynamic_scalars)
+                .collect::<Vec<G::Scalar>>(),
+            &iter::once(Q)
+                .chain(G[..max_n].iter())
+                .chain(H[..max_n].iter())
+                .cloned()
+                .chain(dynamic_points)
+                .collect::<Vec<G>>(),
+        );
+
+        if bool::from(mega_check.is_identity()) {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
     /// Returns the size in bytes required to serialize the inner
     /// product proof.
     ///
     /// For vectors of length `n` the proof size is
-    /// \\(32 \cdot (2\lg n+2)\\) bytes.
+    /// \\(32 \cdot (2\lg n+2)\\) bytes over Ristretto, and in general
+    /// \\(2\lg n\\) compressed points and two scalars, after the header and
+    /// the number of rounds.
     pub fn serialized_size(&self) -> usize {
-        (self.L_vec.len() * 2 + 2) * 32
+        HEADER_SIZE + 1 + self.body_size()
+    }
+
+    /// Size of the proof when nested in another proof, without the header.
+    pub(crate) fn body_size(&self) -> usize {
+        self.L_vec.len() * 2 * G::Compressed::SIZE + 2 * G::Scalar::SIZE
     }
 
-    /// Serializes the proof into a byte array of \\(2n+2\\) 32-byte elements.
+    /// Serializes the proof into a byte array of \\(2n+2\\) elements, which over
+    /// Ristretto are 32 bytes each, after the header (see [`ProofKind`]).
     /// The layout of the inner product proof is:
-    /// * \\(n\\) pairs of compressed Ristretto points \\(L_0, R_0 \dots, L_{n-1}, R_{n-1}\\),
+    /// * the number of rounds \\(n\\), as a byte,
+    /// * \\(n\\) pairs of compressed points \\(L_0, R_0 \dots, L_{n-1}, R_{n-1}\\),
     /// * two scalars \\(a, b\\).
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(self.serialized_size());
-        for (l, r) in self.L_vec.iter().zip(self.R_vec.iter()) {
-            buf.extend_from_slice(l.as_bytes());
-            buf.extend_from_slice(r.as_bytes());
-        }
-        buf.extend_from_slice(self.a.as_bytes());
-        buf.extend_from_slice(self.b.as_bytes());
+        buf.extend_from_slice(&ProofKind::InnerProduct.header());
+        buf.push(self.L_vec.len() as u8);
+        buf.extend(self.to_bytes_iter());
         buf
     }
 
-    /// Converts the proof into a byte iterator over serialized view of the proof.
+    /// Converts the proof into a byte iterator over serialized view of the proof,
+    /// as nested in other proofs, without the header and the number of rounds.
     /// The layout of the inner product proof is:
-    /// * \\(n\\) pairs of compressed Ristretto points \\(L_0, R_0 \dots, L_{n-1}, R_{n-1}\\),
+    /// * \\(n\\) pairs of compressed points \\(L_0, R_0 \dots, L_{n-1}, R_{n-1}\\),
     /// * two scalars \\(a, b\\).
     #[inline]
     pub(crate) fn to_bytes_iter(&self) -> impl Iterator<Item = u8> + '_ {
+        let a = self.a.to_repr();
+        let b = self.b.to_repr();
         self.L_vec
             .iter()
             .zip(self.R_vec.iter())
             .flat_map(|(l, r)| l.as_bytes().iter().chain(r.as_bytes()))
-            .chain(self.a.as_bytes())
-            .chain(self.b.as_bytes())
             .copied()
+            .chain(a.as_ref().to_vec())
+            .chain(b.as_ref().to_vec())
     }
 
     /// Deserializes the proof from a byte slice.
     /// Returns an error in the following cases:
-    /// * the slice does not have \\(2n+2\\) 32-byte elements,
-    /// * \\(n\\) is larger or equal to 32 (proof is too big),
-    /// * any of \\(2n\\) points are not valid compressed Ristretto points,
-    /// * any of 2 scalars are not canonical scalars modulo Ristretto group order.
-    pub fn from_bytes(slice: &[u8]) -> Result<InnerProductProof, ProofError> {
-        let b = slice.len();
-        if b % 32 != 0 {
+    /// * the header is not the one of an inner product proof in a supported version,
+    /// * the number of rounds \\(n\\) is larger than [`MAX_IPP_ROUNDS`],
+    /// * the slice does not have exactly \\(2n\\) points and 2 scalars,
+    /// * any of 2 scalars are not canonical scalars modulo the group order.
+    ///
+    /// The \\(2n\\) points are only decompressed when verifying.
+    pub fn from_bytes(slice: &[u8]) -> Result<InnerProductProof<G>, ProofError> {
+        InnerProductProof::from_bytes_with_max_rounds(slice, MAX_IPP_ROUNDS)
+    }
+
+    /// Deserializes the proof as `from_bytes`, rejecting the proofs of more than
+    /// `max_rounds` rounds before reading them. Verifiers that know the length
+    /// \\(2^n\\) of the vectors can use \\(n\\) to reject oversized proofs early.
+    pub fn from_bytes_with_max_rounds(slice: &[u8], max_rounds: usize) -> Result<InnerProductProof<G>, ProofError> {
+        let max_rounds = max_rounds.min(MAX_IPP_ROUNDS);
+        let (version, body) = ProofKind::InnerProduct.read_header(slice)?;
+        let mut reader = ProofReader::new(body);
+        let proof = match version {
+            1 => InnerProductProof::read_v1(&mut reader)?,
+            2 => InnerProductProof::read_v2(&mut reader)?,
+            _ => return Err(ProofError::FormatError),
+        };
+        if proof.L_vec.len() > max_rounds {
             return Err(ProofError::FormatError);
         }
-        let num_elements = b / 32;
-        if num_elements < 2 {
+        reader.finish()?;
+        Ok(proof)
+    }
+
+    /// Reads the proof in the first version of the format, without the header,
+    /// as nested in other proofs. The number of rounds is given by the length
+    /// of the encoding, so the proof takes all the remaining bytes.
+    pub(crate) fn read_v1(reader: &mut ProofReader) -> Result<InnerProductProof<G>, ProofError> {
+        let pair_size = 2 * G::Compressed::SIZE;
+        let points_size = reader
+            .remaining()
+            .checked_sub(2 * G::Scalar::SIZE)
+            .ok_or(ProofError::FormatError)?;
+        if points_size % pair_size != 0 {
             return Err(ProofError::FormatError);
         }
-        if (num_elements - 2) % 2 != 0 {
+        InnerProductProof::read_rounds(reader, points_size / pair_size)
+    }
+
+    /// Reads the proof in the second version of the format, without the
+    /// header, where the number of rounds is given by the first byte.
+    fn read_v2(reader: &mut ProofReader) -> Result<InnerProductProof<G>, ProofError> {
+        let lg_n = reader.read_u8()? as usize;
+        InnerProductProof::read_rounds(reader, lg_n)
+    }
+
+    /// Reads the points of `lg_n` rounds and the two scalars.
+    fn read_rounds(reader: &mut ProofReader, lg_n: usize) -> Result<InnerProductProof<G>, ProofError> {
+        if lg_n > MAX_IPP_ROUNDS {
             return Err(ProofError::FormatError);
         }
-        let lg_n = (num_elements - 2) / 2;
-        if lg_n >= 32 {
-            return Err(ProofError::FormatError);
+
+        let mut L_vec: Vec<G::Compressed> = Vec::with_capacity(lg_n);
+        let mut R_vec: Vec<G::Compressed> = Vec::with_capacity(lg_n);
+        for _ in 0..lg_n {
+            L_vec.push(reader.read_point()?);
+            R_vec.push(reader.read_point()?);
         }
+        let a = reader.read_scalar()?;
+        let b = reader.read_scalar()?;
 
-        use crate::util::read32;
+        Ok(InnerProductProof { L_vec, R_vec, a, b })
+    }
 
-        let mut L_vec: Vec<CompressedRistretto> = Vec::with_capacity(lg_n);
-        let mut R_vec: Vec<CompressedRistretto> = Vec::with_capacity(lg_n);
-        for i in 0..lg_n {
-            let pos = 2 * i * 32;
-            L_vec.push(CompressedRistretto(read32(&slice[pos..])));
-            R_vec.push(CompressedRistretto(read32(&slice[pos + 32..])));
+    /// Deserializes the proof as `from_bytes`, and also checks that all
+    /// the points are canonical encodings of group elements, so that no
+    /// other byte string parses into the same proof.
+    pub fn from_bytes_strict(slice: &[u8]) -> Result<InnerProductProof<G>, ProofError> {
+        let proof = InnerProductProof::<G>::from_bytes(slice)?;
+        if !proof.points_are_canonical() {
+            return Err(ProofError::FormatError);
         }
+        Ok(proof)
+    }
+}
 
-        let pos = 2 * lg_n * 32;
-        let a =
-            Scalar::from_canonical_bytes(read32(&slice[pos..])).ok_or(ProofError::FormatError)?;
-        let b = Scalar::from_canonical_bytes(read32(&slice[pos + 32..]))
-            .ok_or(ProofError::FormatError)?;
+impl<G: PrimeGroup> CanonicalPoints for InnerProductProof<G> {
+    fn points_are_canonical(&self) -> bool {
+        self.L_vec.points_are_canonical() && self.R_vec.points_are_canonical()
+    }
+}
 
-        Ok(InnerProductProof { L_vec, R_vec, a, b })
+impl<G: PrimeGroup> SerializedSize for InnerProductProof<G> {
+    fn serialized_size(&self) -> usize {
+        InnerProductProof::serialized_size(self)
+    }
+}
+
+impl<G: PrimeGroup> PartialOrd for InnerProductProof<G> {
+    fn partial_cmp(&self, other: &InnerProductProof<G>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<G: PrimeGroup> Ord for InnerProductProof<G> {
+    fn cmp(&self, other: &InnerProductProof<G>) -> Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
+impl<G: PrimeGroup> Hash for InnerProductProof<G> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+/// Computes the coefficients \\(s\_0, \dots, s\_{n-1}\\) of the generators folded by an inner
+/// product proof, where \\(n = 2^k\\), from the squares of the \\(k\\) challenges
+/// \\(u\_k^2, \dots, u\_1^2\\), in creation order, and the inverse of their product
+/// \\(\texttt{allinv} = (u\_k \cdots u\_1)^{-1}\\).
+///
+/// Writing \\(b(i, j)\\) for the \\(j\\)-th bit of \\(i\\), counting from the most significant,
+/// \\(s\_i = \prod\_j u\_j^{2 b(i, j) - 1}\\), which is computed inductively with one
+/// multiplication per coefficient.
+pub fn folding_scalars<S: Field>(challenges_sq: &[S], allinv: S) -> Vec<S> {
+    let lg_n = challenges_sq.len();
+    let n = 1 << lg_n;
+    let mut s = Vec::with_capacity(n);
+    s.push(allinv);
+    for i in 1..n {
+        let lg_i = (32 - 1 - (i as u32).leading_zeros()) as usize;
+        let k = 1 << lg_i;
+        // The challenges are stored in "creation order" as [u_k,...,u_1],
+        // so u_{lg(i)+1} = is indexed by (lg_n-1) - lg_i
+        let u_lg_i_sq = challenges_sq[(lg_n - 1) - lg_i];
+        s.push(s[i - k] * u_lg_i_sq);
     }
+    s
 }
 
 /// Computes an inner product of two vectors
@@ -411,8 +677,8 @@ impl InnerProductProof {
 ///    {\langle {\mathbf{a}}, {\mathbf{b}} \rangle} = \sum\_{i=0}^{n-1} a\_i \cdot b\_i.
 /// \\]
 /// Panics if the lengths of \\(\mathbf{a}\\) and \\(\mathbf{b}\\) are not equal.
-pub fn inner_product(a: &[Scalar], b: &[Scalar]) -> Scalar {
-    let mut out = Scalar::zero();
+pub fn inner_product<S: Field>(a: &[S], b: &[S]) -> S {
+    let mut out = S::ZERO;
     if a.len() != b.len() {
         panic!("inner_product(a,b): lengths of vectors do not match");
     }
@@ -427,47 +693,47 @@ mod tests {
     use super::*;
 
     use crate::util;
-    use sha3::Sha3_512;
+    use curve25519_dalek::scalar::Scalar;
     use rand_core::SeedableRng;
 
     use rand_chacha::ChaChaRng;
 
-    fn test_helper_create(n: usize) {
+    fn test_helper_create<G: PrimeGroup>(n: usize) {
         let mut test_rng = ChaChaRng::from_seed([24u8; 32]);
 
         use crate::generators::BulletproofGens;
-        let bp_gens = BulletproofGens::new(n, 1);
-        let G: Vec<RistrettoPoint> = bp_gens.share(0).G(n).cloned().collect();
-        let H: Vec<RistrettoPoint> = bp_gens.share(0).H(n).cloned().collect();
+        let bp_gens = BulletproofGens::<G>::new(n, 1);
+        let G: Vec<G> = bp_gens.share(0).G(n).cloned().collect();
+        let H: Vec<G> = bp_gens.share(0).H(n).cloned().collect();
 
         // Q would be determined upstream in the protocol, so we pick a random one.
-        let Q = RistrettoPoint::hash_from_bytes::<Sha3_512>(b"test point");
+        let Q = G::hash_from_bytes(b"test point");
 
         // a and b are the vectors for which we want to prove c = <a,b>
-        let a: Vec<_> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
-        let b: Vec<_> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let a: Vec<G::Scalar> = (0..n).map(|_| G::Scalar::random(&mut test_rng)).collect();
+        let b: Vec<G::Scalar> = (0..n).map(|_| G::Scalar::random(&mut test_rng)).collect();
         let c = inner_product(&a, &b);
 
-        let G_factors: Vec<Scalar> = iter::repeat(Scalar::one()).take(n).collect();
+        let G_factors: Vec<G::Scalar> = iter::repeat_n(G::Scalar::ONE, n).collect();
 
         // y_inv is (the inverse of) a random challenge
-        let y_inv = Scalar::random(&mut test_rng);
-        let H_factors: Vec<Scalar> = util::exp_iter(y_inv).take(n).collect();
-//        let H_factors: Vec<Scalar> = iter::repeat(Scalar::one()).take(n).collect();
+        let y_inv = G::Scalar::random(&mut test_rng);
+        let H_factors: Vec<G::Scalar> = util::exp_iter(y_inv).take(n).collect();
+//        let H_factors: Vec<Scalar> = iter::repeat(Scalar::ONE).take(n).collect();
         // P would be determined upstream, but we need a correct P to check the proof.
         //
         // To generate P = <a,G> + <b,H'> + <a,b> Q, compute
         //             P = <a,G> + <b',H> + <a,b> Q,
         // where b' = b \circ y^(-n)
-        let b_prime = b.iter().zip(util::exp_iter(y_inv)).map(|(bi, yi)| bi * yi);
+        let b_prime = b.iter().zip(util::exp_iter(y_inv)).map(|(bi, yi)| *bi * yi);
 //        let b_prime = b.iter().cloned();
 
         // a.iter() has Item=&Scalar, need Item=Scalar to chain with b_prime
         let a_prime = a.iter().cloned();
 
-        let P = RistrettoPoint::vartime_multiscalar_mul(
-            a_prime.chain(b_prime).chain(iter::once(c)),
-            G.iter().chain(H.iter()).chain(iter::once(&Q)),
+        let P = G::vartime_multiscalar_mul(
+            &a_prime.chain(b_prime).chain(iter::once(c)).collect::<Vec<G::Scalar>>(),
+            &G.iter().chain(H.iter()).chain(iter::once(&Q)).cloned().collect::<Vec<G>>(),
         );
 
         let mut verifier = Transcript::new(b"innerproducttest");
@@ -487,9 +753,9 @@ mod tests {
             .verify(
                 n,
                 &mut verifier,
-                iter::repeat(Scalar::one()).take(n),
+                iter::repeat_n(G::Scalar::ONE, n),
                 util::exp_iter(y_inv).take(n),
-//                iter::repeat(Scalar::one()).take(n),
+//                iter::repeat(Scalar::ONE).take(n),
                 &P,
                 &Q,
                 &G,
@@ -497,14 +763,14 @@ mod tests {
             )
             .is_ok());
 
-        let proof = InnerProductProof::from_bytes(proof.to_bytes().as_slice()).unwrap();
+        let proof = InnerProductProof::<G>::from_bytes(proof.to_bytes().as_slice()).unwrap();
         let mut verifier = Transcript::new(b"innerproducttest");
         assert!(proof
             .verify(
                 n,
                 &mut verifier,
-                iter::repeat(Scalar::one()).take(n),
-//                iter::repeat(Scalar::one()).take(n),
+                iter::repeat_n(G::Scalar::ONE, n),
+//                iter::repeat(Scalar::ONE).take(n),
                 util::exp_iter(y_inv).take(n),
                 &P,
                 &Q,
@@ -516,27 +782,175 @@ mod tests {
 
     #[test]
     fn make_ipp_1() {
-        test_helper_create(1);
+        test_helper_create::<RistrettoPoint>(1);
     }
 
     #[test]
     fn make_ipp_2() {
-        test_helper_create(2);
+        test_helper_create::<RistrettoPoint>(2);
     }
 
     #[test]
     fn make_ipp_4() {
-        test_helper_create(4);
+        test_helper_create::<RistrettoPoint>(4);
     }
 
     #[test]
     fn make_ipp_32() {
-        test_helper_create(32);
+        test_helper_create::<RistrettoPoint>(32);
     }
 
     #[test]
     fn make_ipp_64() {
-        test_helper_create(64);
+        test_helper_create::<RistrettoPoint>(64);
+    }
+
+    #[cfg(feature = "bls12_381")]
+    #[test]
+    fn make_ipp_bls12_381() {
+        test_helper_create::<bls12_381::G1Projective>(32);
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn make_ipp_secp256k1() {
+        test_helper_create::<k256::ProjectivePoint>(32);
+    }
+
+    #[cfg(not(feature = "prover-only"))]
+    #[test]
+    fn batch_verification() {
+        let mut test_rng = ChaChaRng::from_seed([24u8; 32]);
+        let bp_gens = crate::generators::BulletproofGens::<RistrettoPoint>::new(16, 1);
+        let G: Vec<RistrettoPoint> = bp_gens.share(0).G(16).cloned().collect();
+        let H: Vec<RistrettoPoint> = bp_gens.share(0).H(16).cloned().collect();
+        let Q = <RistrettoPoint as PrimeGroup>::hash_from_bytes(b"test point");
+
+        let mut proofs = Vec::new();
+        let mut Ps = Vec::new();
+        let mut H_factors = Vec::new();
+        for &n in [4usize, 8, 16].iter() {
+            let a: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+            let b: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+            let y_inv = Scalar::random(&mut test_rng);
+            let factors: Vec<Scalar> = util::exp_iter(y_inv).take(n).collect();
+            let b_prime = b.iter().zip(factors.iter()).map(|(bi, yi)| bi * yi);
+            Ps.push(RistrettoPoint::vartime_multiscalar_mul(
+                &a.iter().cloned().chain(b_prime).chain(iter::once(inner_product(&a, &b))).collect::<Vec<Scalar>>(),
+                &G[..n].iter().chain(H[..n].iter()).chain(iter::once(&Q)).cloned().collect::<Vec<RistrettoPoint>>(),
+            ));
+            proofs.push(InnerProductProof::create(
+                &mut Transcript::new(b"innerproducttest"),
+                &Q,
+                &vec![Scalar::ONE; n],
+                &factors,
+                G[..n].to_vec(),
+                H[..n].to_vec(),
+                a,
+                b,
+            ));
+            H_factors.push(factors);
+        }
+        let G_factors: Vec<Vec<Scalar>> = H_factors.iter().map(|f| vec![Scalar::ONE; f.len()]).collect();
+
+        let verify = |Ps: &[RistrettoPoint]| {
+            let mut transcripts: Vec<Transcript> = (0..3).map(|_| Transcript::new(b"innerproducttest")).collect();
+            InnerProductProof::batch_verify(
+                &proofs.iter().collect::<Vec<_>>(),
+                &mut transcripts.iter_mut().collect::<Vec<_>>(),
+                &G_factors.iter().map(|f| f.as_slice()).collect::<Vec<_>>(),
+                &H_factors.iter().map(|f| f.as_slice()).collect::<Vec<_>>(),
+                Ps,
+                &Q,
+                &G,
+                &H,
+                &mut test_rng.clone(),
+            )
+        };
+        assert!(verify(&Ps).is_ok());
+        assert_eq!(verify(&Ps[..2]), Err(ProofError::VerificationError));
+
+        Ps[1] += Q;
+        assert_eq!(verify(&Ps), Err(ProofError::VerificationError));
+    }
+
+    #[test]
+    fn folded_verification_equation() {
+        let mut test_rng = ChaChaRng::from_seed([24u8; 32]);
+        let n = 8;
+        let bp_gens = crate::generators::BulletproofGens::<RistrettoPoint>::new(n, 1);
+        let G: Vec<RistrettoPoint> = bp_gens.share(0).G(n).cloned().collect();
+        let H: Vec<RistrettoPoint> = bp_gens.share(0).H(n).cloned().collect();
+        let Q = <RistrettoPoint as PrimeGroup>::hash_from_bytes(b"test point");
+
+        let a: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let b: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let P = RistrettoPoint::vartime_multiscalar_mul(
+            &a.iter().chain(b.iter()).cloned().chain(iter::once(inner_product(&a, &b))).collect::<Vec<Scalar>>(),
+            &G.iter().chain(H.iter()).chain(iter::once(&Q)).cloned().collect::<Vec<RistrettoPoint>>(),
+        );
+        let ones = vec![Scalar::ONE; n];
+        let proof = InnerProductProof::create(
+            &mut Transcript::new(b"innerproducttest"), &Q, &ones, &ones, G.clone(), H.clone(), a, b,
+        );
+
+        let (u_sq, u_inv_sq, s) = proof
+            .verification_scalars(n, &mut Transcript::new(b"innerproducttest"))
+            .unwrap();
+        for i in 0..n {
+            assert_eq!(s[i] * s[n - 1 - i], Scalar::ONE);
+        }
+
+        let (a, b) = proof.final_scalars();
+        let expected_P = RistrettoPoint::optional_vartime_multiscalar_mul(
+            &iter::once(a * b)
+                .chain(s.iter().map(|s_i| a * s_i))
+                .chain(s.iter().rev().map(|s_i_inv| b * s_i_inv))
+                .chain(u_sq.iter().chain(u_inv_sq.iter()).map(|u| -u))
+                .collect::<Vec<Scalar>>(),
+            &iter::once(Some(Q))
+                .chain(G.iter().chain(H.iter()).map(|p| Some(*p)))
+                .chain(proof.proof_points())
+                .collect::<Vec<Option<RistrettoPoint>>>(),
+        );
+        assert_eq!(expected_P, Some(P));
+    }
+
+    #[test]
+    fn framing_rejects_malformed_encodings() {
+        let point = <RistrettoPoint as PrimeGroup>::hash_from_bytes(b"framing").compress();
+        let proof = InnerProductProof::<RistrettoPoint> {
+            L_vec: vec![point; 2],
+            R_vec: vec![point; 2],
+            a: Scalar::from(3u64),
+            b: Scalar::from(5u64),
+        };
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), proof.serialized_size());
+        assert_eq!(bytes[HEADER_SIZE], 2);
+        assert_eq!(InnerProductProof::from_bytes(&bytes), Ok(proof.clone()));
+
+        // Proofs in the first version, without the number of rounds, still parse
+        let mut v1 = vec![1, ProofKind::InnerProduct as u8];
+        v1.extend_from_slice(&bytes[HEADER_SIZE + 1..]);
+        assert_eq!(InnerProductProof::from_bytes(&v1), Ok(proof.clone()));
+
+        // Truncated or padded encodings are rejected, even by a whole round
+        let mut padded = bytes.clone();
+        padded.extend_from_slice(&[0u8; 64]);
+        assert_eq!(InnerProductProof::<RistrettoPoint>::from_bytes(&padded).unwrap_err(), ProofError::FormatError);
+        assert_eq!(InnerProductProof::<RistrettoPoint>::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(), ProofError::FormatError);
+        let mut miscounted = bytes.clone();
+        miscounted[HEADER_SIZE] = 1;
+        assert_eq!(InnerProductProof::<RistrettoPoint>::from_bytes(&miscounted).unwrap_err(), ProofError::FormatError);
+
+        // And so are the proofs with too many rounds
+        let mut oversized = bytes[..HEADER_SIZE].to_vec();
+        oversized.push(MAX_IPP_ROUNDS as u8 + 1);
+        oversized.extend_from_slice(&[0u8; 64 * (MAX_IPP_ROUNDS + 2)]);
+        assert_eq!(InnerProductProof::<RistrettoPoint>::from_bytes(&oversized).unwrap_err(), ProofError::FormatError);
+        assert_eq!(InnerProductProof::<RistrettoPoint>::from_bytes_with_max_rounds(&bytes, 1).unwrap_err(), ProofError::FormatError);
+        assert!(InnerProductProof::<RistrettoPoint>::from_bytes_with_max_rounds(&bytes, 2).is_ok());
     }
 
     #[test]
@@ -0,0 +1,429 @@
+//! Aggregation of several zero knowledge inner product proofs into one.
+//!
+//! Each of the \\(m\\) statements \\(v\_j = \langle \mathbf{a}\_j, \mathbf{b}\_j \rangle\\)
+//! is committed under its own share of the `BulletproofGens`, as the
+//! parties of an aggregated range proof, and the statements are combined
+//! with the powers of a challenge \\(z\\) into a single weighted inner
+//! product of length \\(n \cdot m\\).
+
+#![allow(non_snake_case)]
+
+use alloc::vec::Vec;
+
+use core::iter;
+
+use ff::{BatchInvert, Field};
+use merlin::Transcript;
+
+use crate::errors::ProofError;
+use crate::generators::{BulletproofGens, BulletproofGensShare, PedersenGens};
+use crate::group::PrimeGroup;
+use crate::inner_product_proof::InnerProductProof;
+use crate::msm::{CpuMsmBackend, MsmBackend};
+use crate::transcript::TranscriptProtocol;
+use crate::util;
+
+use rand_core::{CryptoRng, CryptoRngCore, RngCore};
+
+use super::{check_gens_capacity, InnerProductZKProof, MegaCheckScalars};
+
+impl<G: PrimeGroup> InnerProductZKProof<G> {
+    /// Commitment \\(A\_j\\) of the vectors of one statement of an aggregated proof, under
+    /// the generators `share` of its party. The \\(A\\) of a proof created with
+    /// `prove_multiple` is the sum of the commitments of all the statements, so each vector
+    /// can be committed separately and bound with `verify_multiple_with_expected_A`.
+    pub fn commit_share(
+        share: &BulletproofGensShare<'_, G>,
+        pc_gens: &PedersenGens<G>,
+        lhs_ip: &[G::Scalar],
+        rhs_ip: &[G::Scalar],
+        a_blinding: G::Scalar,
+        n: usize,
+    ) -> G {
+        G::multiscalar_mul(
+            &iter::once(&a_blinding).chain(lhs_ip[..n].iter()).chain(rhs_ip[..n].iter()).cloned().collect::<Vec<G::Scalar>>(),
+            &iter::once(&pc_gens.B_blinding).chain(share.G(n)).chain(share.H(n)).cloned().collect::<Vec<G>>(),
+        )
+    }
+
+    /// Create a proof that each of the `values` is the inner product of the corresponding
+    /// vectors of `lhs_ips` and `rhs_ips`, with the vectors of statement \\(j\\) over the
+    /// generators of party \\(j\\). Returns the proof and one commitment per value.
+    ///
+    /// The number of statements must be a power of two, and there must be one vector and
+    /// blinding of each kind per statement. The proof has the size of a single proof of
+    /// length \\(n \cdot m\\).
+    pub fn prove_multiple<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        values: &[G::Scalar],
+        lhs_ips: &[Vec<G::Scalar>],
+        rhs_ips: &[Vec<G::Scalar>],
+        v_blindings: &[G::Scalar],
+        a_blindings: &[G::Scalar],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(InnerProductZKProof<G>, Vec<G::Compressed>), ProofError> {
+        InnerProductZKProof::prove_multiple_with_backend(
+            bp_gens,
+            pc_gens,
+            &CpuMsmBackend,
+            transcript,
+            values,
+            lhs_ips,
+            rhs_ips,
+            v_blindings,
+            a_blindings,
+            n,
+            rng,
+        )
+    }
+
+    /// Create a proof as in `prove_multiple`, computing the multiscalar multiplications with
+    /// the given `MsmBackend`.
+    ///
+    /// The right hand side polynomial is weighted by \\(\mathbf{c} = (z^0 \cdot
+    /// \mathbf{1}^n, \dots, z^{m-1} \cdot \mathbf{1}^n)\\), as in `prove_single_weighted`,
+    /// so that \\(t\_0 = \sum\_j z^j v\_j\\). Since \\(z\\) is drawn once \\(A\\) is
+    /// committed, the statements cannot compensate for each other.
+    pub fn prove_multiple_with_backend<T: RngCore + CryptoRng, B: MsmBackend<G> + ?Sized>(
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        backend: &B,
+        transcript: &mut Transcript,
+        values: &[G::Scalar],
+        lhs_ips: &[Vec<G::Scalar>],
+        rhs_ips: &[Vec<G::Scalar>],
+        v_blindings: &[G::Scalar],
+        a_blindings: &[G::Scalar],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(InnerProductZKProof<G>, Vec<G::Compressed>), ProofError> {
+        let m = values.len();
+        if lhs_ips.len() != m || rhs_ips.len() != m || v_blindings.len() != m || a_blindings.len() != m {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+        if !m.is_power_of_two() {
+            return Err(ProofError::InvalidAggregation);
+        }
+        check_gens_capacity(bp_gens, n, m)?;
+
+        transcript.aggregated_ipzk_domain_sep(n as u64, m as u64);
+        let V: Vec<G::Compressed> = values.iter().zip(v_blindings.iter())
+            .map(|(v, v_blinding)| pc_gens.commit(*v, *v_blinding).compress())
+            .collect();
+        for V_j in V.iter() {
+            transcript.append_point(b"V", V_j);
+        }
+
+        let gens: Vec<G> = iter::once(&pc_gens.B_blinding)
+            .chain(bp_gens.G(n, m))
+            .chain(bp_gens.H(n, m))
+            .cloned()
+            .collect();
+
+        let lhs: Vec<G::Scalar> = lhs_ips.iter().flat_map(|a| a[..n].iter()).cloned().collect();
+        let rhs: Vec<G::Scalar> = rhs_ips.iter().flat_map(|b| b[..n].iter()).cloned().collect();
+        let a_blinding: G::Scalar = a_blindings.iter().sum();
+        let A: G = backend.multiscalar_mul(
+            &iter::once(&a_blinding).chain(lhs.iter()).chain(rhs.iter()).cloned().collect::<Vec<G::Scalar>>(),
+            &gens
+        );
+
+        let s_blinding = G::Scalar::random(&mut *rng);
+        let s_L: Vec<G::Scalar> = (0..n * m).map(|_| G::Scalar::random(&mut *rng)).collect();
+        let s_R: Vec<G::Scalar> = (0..n * m).map(|_| G::Scalar::random(&mut *rng)).collect();
+        let S = backend.multiscalar_mul(
+            &iter::once(&s_blinding).chain(s_L.iter()).chain(s_R.iter()).cloned().collect::<Vec<G::Scalar>>(),
+            &gens
+        );
+
+        transcript.append_point(b"A", &A.compress());
+        transcript.append_point(b"S", &S.compress());
+
+        let z: G::Scalar = transcript.challenge_scalar(b"z");
+        let weights = statement_weights(z, n, m);
+
+        let mut l_poly = util::VecPoly1::<G::Scalar>::zero(n * m);
+        let mut r_poly = util::VecPoly1::<G::Scalar>::zero(n * m);
+        for i in 0..n * m {
+            l_poly.0[i] = lhs[i];
+            l_poly.1[i] = s_L[i];
+            r_poly.0[i] = weights[i] * rhs[i];
+            r_poly.1[i] = weights[i] * s_R[i];
+        }
+
+        let t_poly = l_poly.inner_product(&r_poly);
+
+        let t_1_blinding = G::Scalar::random(&mut *rng);
+        let t_2_blinding = G::Scalar::random(&mut *rng);
+        let T_1 = pc_gens.commit(t_poly.1, t_1_blinding);
+        let T_2 = pc_gens.commit(t_poly.2, t_2_blinding);
+
+        transcript.append_point(b"T_1", &T_1.compress());
+        transcript.append_point(b"T_2", &T_2.compress());
+
+        let x: G::Scalar = transcript.challenge_scalar(b"x");
+
+        let v_blinding: G::Scalar = util::exp_iter(z).zip(v_blindings.iter())
+            .map(|(z_j, v_blinding)| z_j * v_blinding)
+            .sum();
+        let t_blinding_poly = util::Poly2(v_blinding, t_1_blinding, t_2_blinding);
+
+        let t_x = t_poly.eval(x);
+        let t_x_blinding = t_blinding_poly.eval(x);
+        let e_blinding = a_blinding + s_blinding * x;
+
+        transcript.append_scalar(b"t_x", &t_x);
+        transcript.append_scalar(b"t_x_blinding", &t_x_blinding);
+        transcript.append_scalar(b"e_blinding", &e_blinding);
+
+        let w: G::Scalar = transcript.challenge_scalar(b"w");
+        let Q = pc_gens.B * w;
+
+        let G_factors: Vec<G::Scalar> = iter::repeat_n(G::Scalar::ONE, n * m).collect();
+        let mut H_factors = weights;
+        H_factors.iter_mut().batch_invert();
+
+        let ipp_proof = InnerProductProof::create_with_backend(
+            backend,
+            transcript,
+            &Q,
+            &G_factors,
+            &H_factors,
+            bp_gens.G(n, m).cloned().collect(),
+            bp_gens.H(n, m).cloned().collect(),
+            l_poly.eval(x),
+            r_poly.eval(x),
+        );
+
+        let proof = InnerProductZKProof {
+            A: A.compress(),
+            S: S.compress(),
+            T_1: T_1.compress(),
+            T_2: T_2.compress(),
+            t_x, t_x_blinding, e_blinding, ipp_proof};
+
+        Ok((proof, V))
+    }
+
+    /// Verifies a proof created with `prove_multiple` for the commitments `V`, one per
+    /// statement.
+    pub fn verify_multiple<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        V: &[G::Compressed],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple_with_backend(bp_gens, pc_gens, &CpuMsmBackend, transcript, V, n, rng)
+    }
+
+    /// Verifies the proof as `verify_multiple`, for vectors committed in `expected_A`, e.g.
+    /// the sum of the `commit_share` of each statement. The expected commitment is bound as
+    /// in `verify_single_with_expected_A`, and the \\(A\\) of the proof is not used.
+    pub fn verify_multiple_with_expected_A<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        V: &[G::Compressed],
+        expected_A: &G::Compressed,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.with_A(expected_A)
+            .verify_multiple_with_backend(bp_gens, pc_gens, &CpuMsmBackend, transcript, V, n, rng)
+    }
+
+    /// Verifies an aggregated proof computing the mega-check with the given `MsmBackend`.
+    pub fn verify_multiple_with_backend<T: RngCore + CryptoRng, B: MsmBackend<G> + ?Sized>(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        backend: &B,
+        transcript: &mut Transcript,
+        V: &[G::Compressed],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let m = V.len();
+        if !m.is_power_of_two() {
+            return Err(ProofError::InvalidAggregation);
+        }
+        check_gens_capacity(bp_gens, n, m)?;
+
+        let scalars = self.aggregated_mega_check_scalars(transcript, V, n, Some(rng))?;
+        self.check_mega(bp_gens, pc_gens, backend, V, scalars, n, m)
+    }
+
+    /// Replays the transcript of an aggregated proof and computes the scalars of the
+    /// mega-check, in the same order as `mega_check_scalars`, where each \\(V\_j\\) is
+    /// weighted by \\(z^j\\).
+    fn aggregated_mega_check_scalars(
+        &self,
+        transcript: &mut Transcript,
+        V: &[G::Compressed],
+        n: usize,
+        rng: Option<&mut dyn CryptoRngCore>,
+    ) -> Result<MegaCheckScalars<G::Scalar>, ProofError> {
+        let m = V.len();
+        transcript.aggregated_ipzk_domain_sep(n as u64, m as u64);
+        for V_j in V.iter() {
+            transcript.append_point(b"V", V_j);
+        }
+        transcript.validate_and_append_point(b"A", &self.A)?;
+        transcript.validate_and_append_point(b"S", &self.S)?;
+
+        let z: G::Scalar = transcript.challenge_scalar(b"z");
+        let mut inv_weights = statement_weights(z, n, m);
+        inv_weights.iter_mut().batch_invert();
+
+        transcript.validate_and_append_point(b"T_1", &self.T_1)?;
+        transcript.validate_and_append_point(b"T_2", &self.T_2)?;
+
+        let x: G::Scalar = transcript.challenge_scalar(b"x");
+
+        transcript.append_scalar(b"t_x", &self.t_x);
+        transcript.append_scalar(b"t_x_blinding", &self.t_x_blinding);
+        transcript.append_scalar(b"e_blinding", &self.e_blinding);
+
+        let w: G::Scalar = transcript.challenge_scalar(b"w");
+
+        let (x_sq, x_inv_sq, s) = self.ipp_proof.verification_scalars(n * m, transcript)?;
+        // Challenge value for batching statements to be verified
+        let c = self.batching_challenge(transcript, rng);
+        let s_inv = s.iter().rev();
+
+        let a = self.ipp_proof.a;
+        let b = self.ipp_proof.b;
+
+        let g = s.iter().map(|s_i| - (a * s_i));
+        let h = s_inv.zip(inv_weights.iter()).map(|(s_i_inv, c_i_inv)| - (b * s_i_inv * c_i_inv));
+
+        let basepoint_scalar = w * (self.t_x - a * b) + c * ( - self.t_x);
+
+        let dynamic_scalars = iter::once(G::Scalar::ONE)
+            .chain(iter::once(x))
+            .chain(iter::once(c * x))
+            .chain(iter::once(c * x * x))
+            .chain(x_sq.iter().cloned())
+            .chain(x_inv_sq.iter().cloned())
+            .chain(util::exp_iter(z).take(m).map(|z_j| c * z_j))
+            .collect();
+
+        let static_scalars = iter::once(-self.e_blinding - c * self.t_x_blinding)
+            .chain(iter::once(basepoint_scalar))
+            .chain(g)
+            .chain(h)
+            .collect();
+
+        Ok((dynamic_scalars, static_scalars))
+    }
+}
+
+/// The weights \\(\mathbf{c}\\) of the right hand side of `m` aggregated statements of
+/// length `n`: \\(z^j\\) for each element of statement \\(j\\).
+fn statement_weights<S: Field>(z: S, n: usize, m: usize) -> Vec<S> {
+    util::exp_iter(z).take(m)
+        .flat_map(|z_j| iter::repeat_n(z_j, n))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use curve25519_dalek::ristretto::RistrettoPoint;
+    use curve25519_dalek::scalar::Scalar;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    use crate::inner_product_proof::inner_product;
+
+    #[test]
+    fn aggregated_proof() {
+        let (n, m) = (16, 4);
+        let pc_gens: PedersenGens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = ChaChaRng::from_seed([27u8; 32]);
+
+        let lhs_ips: Vec<Vec<Scalar>> = (0..m).map(|_| (0..n).map(|_| Scalar::random(&mut rng)).collect()).collect();
+        let rhs_ips: Vec<Vec<Scalar>> = (0..m).map(|_| (0..n).map(|_| Scalar::random(&mut rng)).collect()).collect();
+        let values: Vec<Scalar> = lhs_ips.iter().zip(rhs_ips.iter()).map(|(a, b)| inner_product(a, b)).collect();
+        let v_blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+        let a_blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"AggregatedTest");
+        let (proof, V) = InnerProductZKProof::prove_multiple(
+            &bp_gens, &pc_gens, &mut transcript, &values, &lhs_ips, &rhs_ips, &v_blindings, &a_blindings, n, &mut rng,
+        ).unwrap();
+        assert_eq!(proof.ipp_proof.L_vec.len(), 6);
+
+        let mut transcript = Transcript::new(b"AggregatedTest");
+        assert!(proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &V, n, &mut rng).is_ok());
+
+        // The commitments of each party add up to A
+        let A: RistrettoPoint = (0..m)
+            .map(|j| InnerProductZKProof::commit_share(&bp_gens.share(j), &pc_gens, &lhs_ips[j], &rhs_ips[j], a_blindings[j], n))
+            .sum();
+        assert!(proof.verify_expected_A(A.compress()));
+        let mut transcript = Transcript::new(b"AggregatedTest");
+        assert!(proof.verify_multiple_with_expected_A(&bp_gens, &pc_gens, &mut transcript, &V, &A.compress(), n, &mut rng).is_ok());
+        let A_0 = InnerProductZKProof::commit_share(&bp_gens.share(0), &pc_gens, &lhs_ips[0], &rhs_ips[0], a_blindings[0], n);
+        let mut transcript = Transcript::new(b"AggregatedTest");
+        assert!(proof.verify_multiple_with_expected_A(&bp_gens, &pc_gens, &mut transcript, &V, &A_0.compress(), n, &mut rng).is_err());
+
+        // Swapping two statements breaks the proof
+        let mut swapped = V.clone();
+        swapped.swap(0, 1);
+        let mut transcript = Transcript::new(b"AggregatedTest");
+        assert!(proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &swapped, n, &mut rng).is_err());
+
+        let mut transcript = Transcript::new(b"AggregatedTest");
+        assert_eq!(
+            proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &V[..3], n, &mut rng),
+            Err(ProofError::InvalidAggregation)
+        );
+        let mut transcript = Transcript::new(b"AggregatedTest");
+        assert_eq!(
+            proof.verify_multiple(&BulletproofGens::new(n, 2), &pc_gens, &mut transcript, &V, n, &mut rng),
+            Err(ProofError::InvalidGeneratorsLength)
+        );
+    }
+
+    #[test]
+    fn statements_do_not_compensate() {
+        let (n, m) = (8, 2);
+        let pc_gens: PedersenGens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = ChaChaRng::from_seed([28u8; 32]);
+
+        let lhs_ips: Vec<Vec<Scalar>> = (0..m).map(|_| (0..n).map(|_| Scalar::random(&mut rng)).collect()).collect();
+        let rhs_ips: Vec<Vec<Scalar>> = (0..m).map(|_| (0..n).map(|_| Scalar::random(&mut rng)).collect()).collect();
+        // The sum of the values is right, but not each of them
+        let mut values: Vec<Scalar> = lhs_ips.iter().zip(rhs_ips.iter()).map(|(a, b)| inner_product(a, b)).collect();
+        values[0] += Scalar::ONE;
+        values[1] -= Scalar::ONE;
+        let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"AggregatedTest");
+        let (proof, V) = InnerProductZKProof::prove_multiple(
+            &bp_gens, &pc_gens, &mut transcript, &values, &lhs_ips, &rhs_ips, &blindings, &blindings, n, &mut rng,
+        ).unwrap();
+
+        let mut transcript = Transcript::new(b"AggregatedTest");
+        assert!(proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &V, n, &mut rng).is_err());
+
+        let mut transcript = Transcript::new(b"AggregatedTest");
+        assert_eq!(
+            InnerProductZKProof::prove_multiple(
+                &bp_gens, &pc_gens, &mut transcript, &values, &lhs_ips, &rhs_ips, &blindings[..1], &blindings, n, &mut rng,
+            ).err(),
+            Some(ProofError::WrongNumBlindingFactors)
+        );
+    }
+}
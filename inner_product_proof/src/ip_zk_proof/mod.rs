@@ -7,22 +7,41 @@ extern crate rand;
 use alloc::vec::Vec;
 
 use core::iter;
+use core::slice;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
 
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
-use curve25519_dalek::traits::{IsIdentity, MultiscalarMul, VartimeMultiscalarMul};
+use ff::{BatchInvert, Field, PrimeField};
+#[cfg(not(feature = "prover-only"))]
+use group::Group;
 use merlin::Transcript;
 
 use crate::errors::ProofError;
+use crate::format::{CanonicalPoints, ProofKind, ProofReader, SerializedSize, HEADER_SIZE};
 use crate::generators::{BulletproofGens, PedersenGens};
+use crate::group::{CompressedPoint, PrimeGroup, PrimeScalar};
 use crate::inner_product_proof::InnerProductProof;
+use crate::rewind::{RewindKey, RewindNonces};
+use crate::msm::{CpuMsmBackend, MsmAlgorithm, MsmBackend};
+#[cfg(not(feature = "prover-only"))]
+use crate::msm::PrecomputedGens;
 use crate::transcript::TranscriptProtocol;
 use crate::util;
+#[cfg(feature = "std")]
+use crate::zk_proof::ZkProof;
 
-use rand_core::{CryptoRng, RngCore};
+use rand_core::{CryptoRng, CryptoRngCore, RngCore};
 use serde::de::Visitor;
 use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
 
+mod aggregated;
+mod plus;
+
+pub use self::plus::InnerProductZKProofPlus;
+
 
 /// The `RangeProof` struct represents a proof that one or more values
 /// are in a range.
@@ -47,92 +66,333 @@ use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
 /// protocol locally.  That API is exposed in the [`aggregation`](::range_proof_mpc)
 /// module and can be used to perform online aggregation between
 /// parties without revealing secret values to each other.
-#[derive(Clone, Debug)]
-pub struct InnerProductZKProof {
+///
+/// The proof is generic over the group, and computed over Ristretto by default.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InnerProductZKProof<G: PrimeGroup = RistrettoPoint> {
     /// Commitment to the bits of the value
-    A: CompressedRistretto,
+    A: G::Compressed,
     /// Commitment to the blinding factors
-    S: CompressedRistretto,
+    S: G::Compressed,
     /// Commitment to the \\(t_1\\) coefficient of \\( t(x) \\)
-    T_1: CompressedRistretto,
+    T_1: G::Compressed,
     /// Commitment to the \\(t_2\\) coefficient of \\( t(x) \\)
-    T_2: CompressedRistretto,
+    T_2: G::Compressed,
     /// Evaluation of the polynomial \\(t(x)\\) at the challenge point \\(x\\)
-    t_x: Scalar,
+    t_x: G::Scalar,
     /// Blinding factor for the synthetic commitment to \\(t(x)\\)
-    t_x_blinding: Scalar,
+    t_x_blinding: G::Scalar,
     /// Blinding factor for the synthetic commitment to the inner-product arguments
-    e_blinding: Scalar,
+    e_blinding: G::Scalar,
     /// Proof data for the inner-product argument.
-    ipp_proof: InnerProductProof,
+    ipp_proof: InnerProductProof<G>,
 }
 
-impl InnerProductZKProof {
+/// Scalars of the mega-check, for the points of the proof and for the static generators,
+/// see `InnerProductZKProof::mega_check_scalars`.
+type MegaCheckScalars<S> = (Vec<S>, Vec<S>);
+
+impl<G: PrimeGroup> InnerProductZKProof<G> {
     /// Create a rangeproof for a given pair of value `v` and
     /// blinding scalar `v_blinding`.
     /// This is a convenience wrapper around [`RangeProof::prove_multiple`].
-
     pub fn prove_single<T: RngCore + CryptoRng>(
-        bp_gens: &BulletproofGens,
-        pc_gens: &PedersenGens,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
         transcript: &mut Transcript,
-        v: Scalar,
-        lhs_ip: &Vec<Scalar>,
-        rhs_ip: &Vec<Scalar>,
-        v_blinding: Scalar,
-        a_blinding: Scalar,
+        v: G::Scalar,
+        lhs_ip: &Vec<G::Scalar>,
+        rhs_ip: &Vec<G::Scalar>,
+        v_blinding: G::Scalar,
+        a_blinding: G::Scalar,
         n: usize,
         rng: &mut T,
-    ) -> Result<(InnerProductZKProof, CompressedRistretto), ProofError> {
-        let V = pc_gens.commit(v.into(), v_blinding).compress();
-
-        let A: RistrettoPoint = RistrettoPoint::multiscalar_mul(
-            iter::once(&a_blinding).chain(lhs_ip.iter()).chain(rhs_ip.iter()),
-            iter::once(&pc_gens.B_blinding)
-                .chain(bp_gens.G(n, 1))
-                .chain(bp_gens.H(n, 1))
-        );
+    ) -> Result<(InnerProductZKProof<G>, G::Compressed), ProofError> {
+        InnerProductZKProof::prove_single_with_backend(
+            bp_gens,
+            pc_gens,
+            &CpuMsmBackend,
+            transcript,
+            v,
+            lhs_ip,
+            rhs_ip,
+            v_blinding,
+            a_blinding,
+            n,
+            rng,
+        )
+    }
+
+    /// Create a proof as in `prove_single`, computing the multiscalar multiplications with
+    /// the given `MsmBackend`.
+    pub fn prove_single_with_backend<T: RngCore + CryptoRng, B: MsmBackend<G> + ?Sized>(
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        backend: &B,
+        transcript: &mut Transcript,
+        v: G::Scalar,
+        lhs_ip: &Vec<G::Scalar>,
+        rhs_ip: &Vec<G::Scalar>,
+        v_blinding: G::Scalar,
+        a_blinding: G::Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(InnerProductZKProof<G>, G::Compressed), ProofError> {
+        InnerProductZKProof::prove_internal(
+            bp_gens, pc_gens, backend, transcript, v, lhs_ip, rhs_ip, None, v_blinding, a_blinding, None, None, n, rng,
+        )
+    }
+
+    /// Create a proof as in `prove_single`, for vectors committed in `A` with blinding
+    /// `a_blinding` by another party, e.g. a trusted module signing the commitment.
+    ///
+    /// The prover uses `A` as given instead of recomputing it, so that it cannot put a
+    /// commitment of its own in the proof. If `A` does not commit to `lhs_ip` and `rhs_ip`
+    /// with `a_blinding` over the generators of `bp_gens`, the proof does not verify. Returns
+    /// a `FormatError` if `A` is not a valid point.
+    pub fn prove_with_external_A<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        v: G::Scalar,
+        lhs_ip: &Vec<G::Scalar>,
+        rhs_ip: &Vec<G::Scalar>,
+        v_blinding: G::Scalar,
+        A: &G::Compressed,
+        a_blinding: G::Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(InnerProductZKProof<G>, G::Compressed), ProofError> {
+        if A.decompress().is_none() {
+            return Err(ProofError::FormatError);
+        }
+        InnerProductZKProof::prove_internal(
+            bp_gens, pc_gens, &CpuMsmBackend, transcript, v, lhs_ip, rhs_ip, None, v_blinding, a_blinding, Some(A), None, n, rng,
+        )
+    }
+
+    /// Create a proof as in `prove_single`, from which the holders of `rewind_key` can
+    /// recover `v` and `v_blinding` with `rewind`.
+    ///
+    /// The blinding of \\(A\\) is the value plus a nonce derived from the key and the
+    /// commitment, as are the blindings of \\(S, T_1, T_2\\), so each commitment must be
+    /// proven at most once with the same key, see [`RewindKey`].
+    pub fn prove_single_rewindable<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        v: G::Scalar,
+        lhs_ip: &Vec<G::Scalar>,
+        rhs_ip: &Vec<G::Scalar>,
+        v_blinding: G::Scalar,
+        n: usize,
+        rewind_key: &RewindKey,
+        rng: &mut T,
+    ) -> Result<(InnerProductZKProof<G>, G::Compressed), ProofError> {
+        let nonces = rewind_key.nonces(&pc_gens.commit(v, v_blinding).compress());
+        InnerProductZKProof::prove_internal(
+            bp_gens,
+            pc_gens,
+            &CpuMsmBackend,
+            transcript,
+            v,
+            lhs_ip,
+            rhs_ip,
+            None,
+            v_blinding,
+            nonces.alpha + v,
+            None,
+            Some(&nonces),
+            n,
+            rng,
+        )
+    }
+
+    /// Recovers the value and the blinding factor committed in \\(V\\) from a proof created
+    /// with `prove_single_rewindable` and the same key.
+    ///
+    /// The transcript must have the same initial state as the one of the prover. Returns an
+    /// error if the proof was not created with this key, as the recovered opening would not
+    /// match \\(V\\). The proof itself is not verified, which is done with `verify_single`.
+    pub fn rewind(
+        &self,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        V: &G::Compressed,
+        rewind_key: &RewindKey,
+    ) -> Result<(G::Scalar, G::Scalar), ProofError> {
+        // Replay the transcript until the challenge x, as the verifier
+        transcript.append_point(b"V", V);
+        transcript.validate_and_append_point(b"A", &self.A)?;
+        transcript.validate_and_append_point(b"S", &self.S)?;
+
+        transcript.validate_and_append_point(b"T_1", &self.T_1)?;
+        transcript.validate_and_append_point(b"T_2", &self.T_2)?;
+
+        let x: G::Scalar = transcript.challenge_scalar(b"x");
+
+        let nonces = rewind_key.nonces::<G::Scalar, _>(V);
+
+        // e_blinding = alpha + v + rho * x, and t_x_blinding = v_blinding + tau_1 * x + tau_2 * x^2
+        let v = self.e_blinding - nonces.rho * x - nonces.alpha;
+        let v_blinding = self.t_x_blinding - (nonces.tau_1 + nonces.tau_2 * x) * x;
+
+        if pc_gens.commit(v, v_blinding).compress() != *V {
+            return Err(ProofError::VerificationError);
+        }
+        Ok((v, v_blinding))
+    }
+
+    /// Create a proof that `v` is the weighted inner product
+    /// \\(\langle \mathbf{a}, \mathbf{c} \circ \mathbf{b} \rangle\\) of `lhs_ip` and `rhs_ip`,
+    /// for the public `weights` \\(\mathbf{c}\\).
+    ///
+    /// The vectors are committed in \\(A\\) as in `prove_single`, so weighted statements do
+    /// not need to pre-multiply the witness vectors by the weights. The weights must be
+    /// non-zero, and there must be one per element.
+    pub fn prove_single_weighted<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        v: G::Scalar,
+        lhs_ip: &Vec<G::Scalar>,
+        rhs_ip: &Vec<G::Scalar>,
+        weights: &[G::Scalar],
+        v_blinding: G::Scalar,
+        a_blinding: G::Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(InnerProductZKProof<G>, G::Compressed), ProofError> {
+        InnerProductZKProof::prove_single_weighted_with_backend(
+            bp_gens,
+            pc_gens,
+            &CpuMsmBackend,
+            transcript,
+            v,
+            lhs_ip,
+            rhs_ip,
+            weights,
+            v_blinding,
+            a_blinding,
+            n,
+            rng,
+        )
+    }
 
-        let s_blinding = Scalar::random(rng);
-        let s_L: Vec<Scalar> = (0..n).map(|_| Scalar::random(rng)).collect();
-        let s_R: Vec<Scalar> = (0..n).map(|_| Scalar::random(rng)).collect();
+    /// Create a proof as in `prove_single_weighted`, computing the multiscalar
+    /// multiplications with the given `MsmBackend`.
+    pub fn prove_single_weighted_with_backend<T: RngCore + CryptoRng, B: MsmBackend<G> + ?Sized>(
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        backend: &B,
+        transcript: &mut Transcript,
+        v: G::Scalar,
+        lhs_ip: &Vec<G::Scalar>,
+        rhs_ip: &Vec<G::Scalar>,
+        weights: &[G::Scalar],
+        v_blinding: G::Scalar,
+        a_blinding: G::Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(InnerProductZKProof<G>, G::Compressed), ProofError> {
+        InnerProductZKProof::prove_internal(
+            bp_gens, pc_gens, backend, transcript, v, lhs_ip, rhs_ip, Some(weights), v_blinding, a_blinding, None, None, n, rng,
+        )
+    }
+
+    /// Creates the proof for the inner product of `lhs_ip` and `rhs_ip`, weighted by
+    /// `weights` if given.
+    ///
+    /// With weights \\(\mathbf{c}\\), the right hand side polynomial is
+    /// \\(\mathbf{r}(x) = \mathbf{c} \circ (\mathbf{b} + \mathbf{s}\_R x)\\), and the inner
+    /// product argument runs over \\(\mathbf{H}' = \mathbf{c}^{-1} \circ \mathbf{H}\\), so
+    /// that \\(A\\) and \\(S\\) still commit to the unweighted vectors.
+    ///
+    /// The commitment \\(A\\) is taken from `external_A` if given, and computed from the
+    /// vectors and `a_blinding` otherwise. The blindings of \\(S, T_1, T_2\\) are taken from
+    /// `rewind` if given, and drawn from `rng` otherwise.
+    fn prove_internal<T: RngCore + CryptoRng, B: MsmBackend<G> + ?Sized>(
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        backend: &B,
+        transcript: &mut Transcript,
+        v: G::Scalar,
+        lhs_ip: &Vec<G::Scalar>,
+        rhs_ip: &Vec<G::Scalar>,
+        weights: Option<&[G::Scalar]>,
+        v_blinding: G::Scalar,
+        a_blinding: G::Scalar,
+        external_A: Option<&G::Compressed>,
+        rewind: Option<&RewindNonces<G::Scalar>>,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(InnerProductZKProof<G>, G::Compressed), ProofError> {
+        check_gens_capacity(bp_gens, n, 1)?;
+        let inv_weights = weights.map(|c| invert_weights(transcript, c, n)).transpose()?;
+
+        let V = pc_gens.commit(v, v_blinding).compress();
+
+        let gens: Vec<G> = iter::once(&pc_gens.B_blinding)
+            .chain(bp_gens.G(n, 1))
+            .chain(bp_gens.H(n, 1))
+            .cloned()
+            .collect();
+
+        let A = match external_A {
+            Some(A) => *A,
+            None => backend.multiscalar_mul(
+                &iter::once(&a_blinding).chain(lhs_ip.iter()).chain(rhs_ip.iter()).cloned().collect::<Vec<G::Scalar>>(),
+                &gens
+            ).compress(),
+        };
+
+        let s_blinding = match rewind {
+            Some(nonces) => nonces.rho,
+            None => G::Scalar::random(&mut *rng),
+        };
+        let s_L: Vec<G::Scalar> = (0..n).map(|_| G::Scalar::random(&mut *rng)).collect();
+        let s_R: Vec<G::Scalar> = (0..n).map(|_| G::Scalar::random(&mut *rng)).collect();
 
         // Compute S = <s_L, G> + <s_R, H> + s_blinding * B_blinding
-        let S = RistrettoPoint::multiscalar_mul(
-            iter::once(&s_blinding).chain(s_L.iter()).chain(s_R.iter()),
-            iter::once(&pc_gens.B_blinding)
-                .chain(bp_gens.G(n, 1))
-                .chain(bp_gens.H(n, 1))
+        let S = backend.multiscalar_mul(
+            &iter::once(&s_blinding).chain(s_L.iter()).chain(s_R.iter()).cloned().collect::<Vec<G::Scalar>>(),
+            &gens
         );
 
         // We already commit to the polynomials as well
         // Calculate t by calculating vectors l0, l1, r0, r1 and multiplying
-        let mut l_poly = util::VecPoly1::zero(n);
-        let mut r_poly = util::VecPoly1::zero(n);
-
-        for i in 0..n {
-            l_poly.0[i] = lhs_ip[i];
-            l_poly.1[i] = s_L[i];
-            r_poly.0[i] = rhs_ip[i];
-            r_poly.1[i] = s_R[i];
+        let mut l_poly = util::VecPoly1::<G::Scalar>::zero(n);
+        let mut r_poly = util::VecPoly1::<G::Scalar>::zero(n);
+
+        l_poly.0.copy_from_slice(&lhs_ip[..n]);
+        l_poly.1.copy_from_slice(&s_L[..n]);
+        r_poly.0.copy_from_slice(&rhs_ip[..n]);
+        r_poly.1.copy_from_slice(&s_R[..n]);
+        if let Some(c) = weights {
+            for (i, c_i) in c[..n].iter().enumerate() {
+                r_poly.0[i] *= c_i;
+                r_poly.1[i] *= c_i;
+            }
         }
 
         let t_poly = l_poly.inner_product(&r_poly);
 
         // Generate x by committing to T_1, T_2 (line 49-54)
-        let t_1_blinding = Scalar::random(rng);
-        let t_2_blinding = Scalar::random(rng);
+        let (t_1_blinding, t_2_blinding) = match rewind {
+            Some(nonces) => (nonces.tau_1, nonces.tau_2),
+            None => (G::Scalar::random(&mut *rng), G::Scalar::random(&mut *rng)),
+        };
         let T_1 = pc_gens.commit(t_poly.1, t_1_blinding);
         let T_2 = pc_gens.commit(t_poly.2, t_2_blinding);
 
         transcript.append_point(b"V", &V);
-        transcript.append_point(b"A", &A.compress());
+        transcript.append_point(b"A", &A);
         transcript.append_point(b"S", &S.compress());
 
         transcript.append_point(b"T_1", &T_1.compress());
         transcript.append_point(b"T_2", &T_2.compress());
 
-        let x = transcript.challenge_scalar(b"x");
+        let x: G::Scalar = transcript.challenge_scalar(b"x");
 
         let t_blinding_poly = util::Poly2(
             v_blinding,
@@ -152,16 +412,18 @@ impl InnerProductZKProof {
         transcript.append_scalar(b"e_blinding", &e_blinding);
 
         // Get a challenge value to combine statements for the IPP
-        let w = transcript.challenge_scalar(b"w");
-        let Q = w * pc_gens.B;
+        let w: G::Scalar = transcript.challenge_scalar(b"w");
+        let Q = pc_gens.B * w;
 
-        let G_factors: Vec<Scalar> = iter::repeat(Scalar::one()).take(n).collect();
-        let H_factors: Vec<Scalar> = iter::repeat(Scalar::one()).take(n).collect();
+        let G_factors: Vec<G::Scalar> = iter::repeat_n(G::Scalar::ONE, n).collect();
+        let H_factors: Vec<G::Scalar> =
+            inv_weights.unwrap_or_else(|| iter::repeat_n(G::Scalar::ONE, n).collect());
 
-        let G: Vec<RistrettoPoint> = bp_gens.G(n, 1).cloned().collect();
-        let H: Vec<RistrettoPoint> = bp_gens.H(n, 1).cloned().collect();
+        let G: Vec<G> = bp_gens.G(n, 1).cloned().collect();
+        let H: Vec<G> = bp_gens.H(n, 1).cloned().collect();
 
-        let ipp_proof = InnerProductProof::create(
+        let ipp_proof = InnerProductProof::create_with_backend(
+            backend,
             transcript,
             &Q,
             &G_factors,
@@ -172,6 +434,136 @@ impl InnerProductZKProof {
             r_vec.clone(),
         );
 
+        let proof = InnerProductZKProof{
+            A,
+            S: S.compress(),
+            T_1: T_1.compress(),
+            T_2: T_2.compress(),
+            t_x, t_x_blinding, e_blinding, ipp_proof};
+
+        Ok((proof, V))
+    }
+
+    /// Create a proof that `v` is the inner product of `lhs_ip` and the public vector
+    /// `rhs_ip`, e.g. the all-ones vector of a sum or the weights of a linear model.
+    ///
+    /// Since the verifier knows the right hand side, \\(A\\) and \\(S\\) only commit to
+    /// the left hand side, which halves the size of their multiscalar multiplications,
+    /// and \\(t(x)\\) is linear, so \\(T\_2\\) is left as the identity. The proof is
+    /// checked with `verify_with_public_rhs`, or with `verify_with_public_rhs_with_expected_A`
+    /// against \\(A = \tilde{a} \cdot \tilde{B} + \langle \mathbf{a}, \mathbf{G} \rangle\\).
+    pub fn prove_with_public_rhs<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        v: G::Scalar,
+        lhs_ip: &Vec<G::Scalar>,
+        rhs_ip: &[G::Scalar],
+        v_blinding: G::Scalar,
+        a_blinding: G::Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(InnerProductZKProof<G>, G::Compressed), ProofError> {
+        InnerProductZKProof::prove_with_public_rhs_with_backend(
+            bp_gens,
+            pc_gens,
+            &CpuMsmBackend,
+            transcript,
+            v,
+            lhs_ip,
+            rhs_ip,
+            v_blinding,
+            a_blinding,
+            n,
+            rng,
+        )
+    }
+
+    /// Create a proof as in `prove_with_public_rhs`, computing the multiscalar
+    /// multiplications with the given `MsmBackend`.
+    pub fn prove_with_public_rhs_with_backend<T: RngCore + CryptoRng, B: MsmBackend<G> + ?Sized>(
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        backend: &B,
+        transcript: &mut Transcript,
+        v: G::Scalar,
+        lhs_ip: &Vec<G::Scalar>,
+        rhs_ip: &[G::Scalar],
+        v_blinding: G::Scalar,
+        a_blinding: G::Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(InnerProductZKProof<G>, G::Compressed), ProofError> {
+        check_gens_capacity(bp_gens, n, 1)?;
+        append_public_rhs(transcript, rhs_ip, n)?;
+
+        let V = pc_gens.commit(v, v_blinding).compress();
+
+        let gens: Vec<G> = iter::once(&pc_gens.B_blinding)
+            .chain(bp_gens.G(n, 1))
+            .cloned()
+            .collect();
+
+        let A: G = backend.multiscalar_mul(
+            &iter::once(&a_blinding).chain(lhs_ip.iter()).cloned().collect::<Vec<G::Scalar>>(),
+            &gens
+        );
+
+        let s_blinding = G::Scalar::random(&mut *rng);
+        let s_L: Vec<G::Scalar> = (0..n).map(|_| G::Scalar::random(&mut *rng)).collect();
+
+        // Compute S = <s_L, G> + s_blinding * B_blinding
+        let S = backend.multiscalar_mul(
+            &iter::once(&s_blinding).chain(s_L.iter()).cloned().collect::<Vec<G::Scalar>>(),
+            &gens
+        );
+
+        // l(x) = lhs_ip + s_L * x and r(x) = rhs_ip, so t(x) = t_0 + t_1 * x
+        let t_1 = s_L.iter().zip(rhs_ip.iter()).fold(G::Scalar::ZERO, |acc, (s_i, b_i)| acc + *s_i * b_i);
+
+        let t_1_blinding = G::Scalar::random(&mut *rng);
+        let T_1 = pc_gens.commit(t_1, t_1_blinding);
+        let T_2 = G::identity();
+
+        transcript.append_point(b"V", &V);
+        transcript.append_point(b"A", &A.compress());
+        transcript.append_point(b"S", &S.compress());
+
+        transcript.append_point(b"T_1", &T_1.compress());
+
+        let x: G::Scalar = transcript.challenge_scalar(b"x");
+
+        let t_x = v + t_1 * x;
+        let t_x_blinding = v_blinding + t_1_blinding * x;
+        let e_blinding = a_blinding + s_blinding * x;
+
+        let l_vec: Vec<G::Scalar> = lhs_ip.iter().zip(s_L.iter()).map(|(a_i, s_i)| *a_i + *s_i * x).collect();
+
+        transcript.append_scalar(b"t_x", &t_x);
+        transcript.append_scalar(b"t_x_blinding", &t_x_blinding);
+        transcript.append_scalar(b"e_blinding", &e_blinding);
+
+        // Get a challenge value to combine statements for the IPP
+        let w: G::Scalar = transcript.challenge_scalar(b"w");
+        let Q = pc_gens.B * w;
+
+        let factors: Vec<G::Scalar> = iter::repeat_n(G::Scalar::ONE, n).collect();
+
+        let G: Vec<G> = bp_gens.G(n, 1).cloned().collect();
+        let H: Vec<G> = bp_gens.H(n, 1).cloned().collect();
+
+        let ipp_proof = InnerProductProof::create_with_backend(
+            backend,
+            transcript,
+            &Q,
+            &factors,
+            &factors,
+            G,
+            H,
+            l_vec,
+            rhs_ip.to_vec(),
+        );
+
         let proof = InnerProductZKProof{
             A: A.compress(),
             S: S.compress(),
@@ -182,19 +574,345 @@ impl InnerProductZKProof {
         Ok((proof, V))
     }
 
+    /// Create a proof that `v` is the inner product of the vectors committed in two
+    /// separate commitments, \\(A\_L = \tilde{a}\_L \cdot \tilde{B} + \langle \mathbf{a},
+    /// \mathbf{G} \rangle\\) to `lhs_ip` and \\(A\_R = \tilde{a}\_R \cdot \tilde{B} +
+    /// \langle \mathbf{b}, \mathbf{H} \rangle\\) to `rhs_ip`, e.g. commitments that were
+    /// signed beforehand and cannot be recomputed as a single \\(A\\).
+    ///
+    /// The proof runs over \\(A = A\_L + A\_R\\), so it only shows the relation for
+    /// commitments of that form: the verifier must know, e.g. from a signature, that
+    /// \\(A\_L\\) and \\(A\_R\\) only use the \\(\mathbf{G}\\) and \\(\mathbf{H}\\) bases
+    /// respectively.
+    pub fn prove_with_vector_commitments<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        v: G::Scalar,
+        lhs_ip: &Vec<G::Scalar>,
+        rhs_ip: &Vec<G::Scalar>,
+        v_blinding: G::Scalar,
+        lhs_blinding: G::Scalar,
+        rhs_blinding: G::Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(InnerProductZKProof<G>, G::Compressed), ProofError> {
+        InnerProductZKProof::prove_with_vector_commitments_with_backend(
+            bp_gens,
+            pc_gens,
+            &CpuMsmBackend,
+            transcript,
+            v,
+            lhs_ip,
+            rhs_ip,
+            v_blinding,
+            lhs_blinding,
+            rhs_blinding,
+            n,
+            rng,
+        )
+    }
+
+    /// Create a proof as in `prove_with_vector_commitments`, computing the multiscalar
+    /// multiplications with the given `MsmBackend`.
+    pub fn prove_with_vector_commitments_with_backend<T: RngCore + CryptoRng, B: MsmBackend<G> + ?Sized>(
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        backend: &B,
+        transcript: &mut Transcript,
+        v: G::Scalar,
+        lhs_ip: &Vec<G::Scalar>,
+        rhs_ip: &Vec<G::Scalar>,
+        v_blinding: G::Scalar,
+        lhs_blinding: G::Scalar,
+        rhs_blinding: G::Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(InnerProductZKProof<G>, G::Compressed), ProofError> {
+        check_gens_capacity(bp_gens, n, 1)?;
+        let A_L: G = backend.multiscalar_mul(
+            &iter::once(&lhs_blinding).chain(lhs_ip.iter()).cloned().collect::<Vec<G::Scalar>>(),
+            &iter::once(&pc_gens.B_blinding).chain(bp_gens.G(n, 1)).cloned().collect::<Vec<G>>(),
+        );
+        let A_R: G = backend.multiscalar_mul(
+            &iter::once(&rhs_blinding).chain(rhs_ip.iter()).cloned().collect::<Vec<G::Scalar>>(),
+            &iter::once(&pc_gens.B_blinding).chain(bp_gens.H(n, 1)).cloned().collect::<Vec<G>>(),
+        );
+
+        transcript.vector_commitments_ipzk_domain_sep(n as u64);
+        transcript.append_point(b"A_L", &A_L.compress());
+        transcript.append_point(b"A_R", &A_R.compress());
+
+        InnerProductZKProof::prove_internal(
+            bp_gens, pc_gens, backend, transcript, v, lhs_ip, rhs_ip, None, v_blinding, lhs_blinding + rhs_blinding, None, None, n, rng,
+        )
+    }
+
     /// Verifies a rangeproof for a given value commitment \\(V\\).
     ///
     /// This is a convenience wrapper around `verify_multiple` for the `m=1` case.
     pub fn verify_single<T: RngCore + CryptoRng>(
         &self,
-        bp_gens: &BulletproofGens,
-        pc_gens: &PedersenGens,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
         transcript: &mut Transcript,
-        V: &CompressedRistretto,
+        V: &G::Compressed,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.verify_single_with_backend(bp_gens, pc_gens, &CpuMsmBackend, transcript, V, n, rng)
+    }
+
+    /// Verifies the proof computing the mega-check with the given `MsmBackend`.
+    pub fn verify_single_with_backend<T: RngCore + CryptoRng, B: MsmBackend<G> + ?Sized>(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        backend: &B,
+        transcript: &mut Transcript,
+        V: &G::Compressed,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.verify_internal(bp_gens, pc_gens, backend, transcript, V, None, n, Some(rng))
+    }
+
+    /// Verifies the proof as `verify_single`, for vectors committed in `expected_A`, e.g.
+    /// computed by the verifier from signed vector commitments.
+    ///
+    /// The expected commitment takes the place of the \\(A\\) of the proof, both in the
+    /// transcript, so that the challenges are bound to it, and in the mega-check, so that
+    /// the check cannot be skipped or done after the challenges are drawn, as with a
+    /// separate call to `verify_expected_A`. The \\(A\\) of the proof is not used.
+    pub fn verify_single_with_expected_A<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        V: &G::Compressed,
+        expected_A: &G::Compressed,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.with_A(expected_A)
+            .verify_internal(bp_gens, pc_gens, &CpuMsmBackend, transcript, V, None, n, Some(rng))
+    }
+
+    /// The proof with its \\(A\\) replaced by `A`.
+    fn with_A(&self, A: &G::Compressed) -> InnerProductZKProof<G> {
+        InnerProductZKProof { A: *A, ..self.clone() }
+    }
+
+    /// Verifies the proof as `verify_single`, with the scalar batching the checks of the
+    /// mega-check derived from the transcript instead of drawn from an RNG. The verification
+    /// is then deterministic, which verifiers without a source of randomness, e.g. `no_std`
+    /// targets, rely on.
+    pub fn verify_single_deterministic(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        V: &G::Compressed,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_internal(bp_gens, pc_gens, &CpuMsmBackend, transcript, V, None, n, None)
+    }
+
+    /// Verifies a proof created with `prove_single_weighted` for the given `weights`.
+    pub fn verify_single_weighted<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        V: &G::Compressed,
+        weights: &[G::Scalar],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.verify_single_weighted_with_backend(bp_gens, pc_gens, &CpuMsmBackend, transcript, V, weights, n, rng)
+    }
+
+    /// Verifies a weighted proof computing the mega-check with the given `MsmBackend`.
+    pub fn verify_single_weighted_with_backend<T: RngCore + CryptoRng, B: MsmBackend<G> + ?Sized>(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        backend: &B,
+        transcript: &mut Transcript,
+        V: &G::Compressed,
+        weights: &[G::Scalar],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.verify_internal(bp_gens, pc_gens, backend, transcript, V, Some(weights), n, Some(rng))
+    }
+
+    /// Verifies a proof created with `prove_with_public_rhs` for the public vector `rhs_ip`.
+    pub fn verify_with_public_rhs<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        V: &G::Compressed,
+        rhs_ip: &[G::Scalar],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.verify_with_public_rhs_with_backend(bp_gens, pc_gens, &CpuMsmBackend, transcript, V, rhs_ip, n, rng)
+    }
+
+    /// Verifies the proof as `verify_with_public_rhs`, for a left hand side committed in
+    /// `expected_A`, bound as in `verify_single_with_expected_A`.
+    pub fn verify_with_public_rhs_with_expected_A<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        V: &G::Compressed,
+        expected_A: &G::Compressed,
+        rhs_ip: &[G::Scalar],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.with_A(expected_A)
+            .verify_with_public_rhs_with_backend(bp_gens, pc_gens, &CpuMsmBackend, transcript, V, rhs_ip, n, rng)
+    }
+
+    /// Verifies a proof with a public right hand side computing the mega-check with the
+    /// given `MsmBackend`.
+    pub fn verify_with_public_rhs_with_backend<T: RngCore + CryptoRng, B: MsmBackend<G> + ?Sized>(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        backend: &B,
+        transcript: &mut Transcript,
+        V: &G::Compressed,
+        rhs_ip: &[G::Scalar],
         n: usize,
         rng: &mut T,
     ) -> Result<(), ProofError> {
-//        self.verify_multiple_with_rng(bp_gens, pc_gens, transcript, &[*V], n, rng)
+        let scalars = self.public_rhs_mega_check_scalars(transcript, V, rhs_ip, n, Some(rng))?;
+        self.check_mega(bp_gens, pc_gens, backend, slice::from_ref(V), scalars, n, 1)
+    }
+
+    /// Verifies a proof created with `prove_with_vector_commitments` for the commitments
+    /// `lhs_commitment` and `rhs_commitment` to the two vectors.
+    pub fn verify_with_vector_commitments<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        V: &G::Compressed,
+        lhs_commitment: &G::Compressed,
+        rhs_commitment: &G::Compressed,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.verify_with_vector_commitments_with_backend(
+            bp_gens,
+            pc_gens,
+            &CpuMsmBackend,
+            transcript,
+            V,
+            lhs_commitment,
+            rhs_commitment,
+            n,
+            rng,
+        )
+    }
+
+    /// Verifies a proof over two vector commitments computing the mega-check with the
+    /// given `MsmBackend`.
+    pub fn verify_with_vector_commitments_with_backend<T: RngCore + CryptoRng, B: MsmBackend<G> + ?Sized>(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        backend: &B,
+        transcript: &mut Transcript,
+        V: &G::Compressed,
+        lhs_commitment: &G::Compressed,
+        rhs_commitment: &G::Compressed,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let A_L = lhs_commitment.decompress().ok_or(ProofError::VerificationError)?;
+        let A_R = rhs_commitment.decompress().ok_or(ProofError::VerificationError)?;
+        if (A_L + A_R).compress() != self.A {
+            return Err(ProofError::VerificationError);
+        }
+
+        transcript.vector_commitments_ipzk_domain_sep(n as u64);
+        transcript.append_point(b"A_L", lhs_commitment);
+        transcript.append_point(b"A_R", rhs_commitment);
+
+        self.verify_internal(bp_gens, pc_gens, backend, transcript, V, None, n, Some(rng))
+    }
+
+    fn verify_internal<B: MsmBackend<G> + ?Sized>(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        backend: &B,
+        transcript: &mut Transcript,
+        V: &G::Compressed,
+        weights: Option<&[G::Scalar]>,
+        n: usize,
+        rng: Option<&mut dyn CryptoRngCore>,
+    ) -> Result<(), ProofError> {
+        let scalars = self.mega_check_scalars(transcript, V, weights, n, rng)?;
+        self.check_mega(bp_gens, pc_gens, backend, slice::from_ref(V), scalars, n, 1)
+    }
+
+    /// Computes the mega-check for the scalars returned by `mega_check_scalars` or
+    /// `public_rhs_mega_check_scalars`.
+    fn check_mega<B: MsmBackend<G> + ?Sized>(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        backend: &B,
+        V: &[G::Compressed],
+        (dynamic_scalars, static_scalars): (Vec<G::Scalar>, Vec<G::Scalar>),
+        n: usize,
+        m: usize,
+    ) -> Result<(), ProofError> {
+        check_gens_capacity(bp_gens, n, m)?;
+        let scalars: Vec<G::Scalar> = dynamic_scalars.into_iter().chain(static_scalars).collect();
+        let points: Vec<Option<G>> = self.mega_check_points(V)
+            .chain(iter::once(Some(pc_gens.B_blinding)))
+            .chain(iter::once(Some(pc_gens.B)))
+            .chain(bp_gens.G(n, m).map(|&x| Some(x)))
+            .chain(bp_gens.H(n, m).map(|&x| Some(x)))
+            .collect();
+
+        let mega_check = backend.vartime_optional_multiscalar_mul(&scalars, &points)
+            .ok_or(ProofError::VerificationError)?;
+
+        if bool::from(mega_check.is_identity()) {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Number of points in the mega-check of a proof of size `n`.
+    pub fn verification_size(n: usize) -> usize {
+        // A, S, T_1, T_2, V, the L and R vectors, B, B_blinding and the G and H vectors
+        5 + 2 * (n.next_power_of_two().trailing_zeros() as usize) + 2 + 2 * n
+    }
+
+    /// Replays the transcript and computes the scalars of the mega-check. The first vector
+    /// goes with the points of `mega_check_points`, and the second one with the static
+    /// generators \\(\tilde{B}, B, \mathbf{G}, \mathbf{H}\\).
+    fn mega_check_scalars(
+        &self,
+        transcript: &mut Transcript,
+        V: &G::Compressed,
+        weights: Option<&[G::Scalar]>,
+        n: usize,
+        rng: Option<&mut dyn CryptoRngCore>,
+    ) -> Result<MegaCheckScalars<G::Scalar>, ProofError> {
+        let inv_weights = weights.map(|c| invert_weights(transcript, c, n)).transpose()?;
+
         transcript.append_point(b"V", V);
         transcript.validate_and_append_point(b"A", &self.A)?;
         transcript.validate_and_append_point(b"S", &self.S)?;
@@ -202,137 +920,311 @@ impl InnerProductZKProof {
         transcript.validate_and_append_point(b"T_1", &self.T_1)?;
         transcript.validate_and_append_point(b"T_2", &self.T_2)?;
 
-        let x = transcript.challenge_scalar(b"x");
+        let x: G::Scalar = transcript.challenge_scalar(b"x");
 
         transcript.append_scalar(b"t_x", &self.t_x);
         transcript.append_scalar(b"t_x_blinding", &self.t_x_blinding);
         transcript.append_scalar(b"e_blinding", &self.e_blinding);
 
-        let w = transcript.challenge_scalar(b"w");
-
+        let w: G::Scalar = transcript.challenge_scalar(b"w");
 
+        let (x_sq, x_inv_sq, s) = self.ipp_proof.verification_scalars(n, transcript)?;
         // Challenge value for batching statements to be verified
-        let c = Scalar::random(rng);
+        let c = self.batching_challenge(transcript, rng);
+        let s_inv = s.iter().rev();
+
+        let a = self.ipp_proof.a;
+        let b = self.ipp_proof.b;
+
+        let g = s.iter().map(|s_i| - (a * s_i));
+        let h: Vec<G::Scalar> = match inv_weights {
+            Some(c_inv) => s_inv.zip(c_inv.iter()).map(|(s_i_inv, c_i_inv)| - (b * s_i_inv * c_i_inv)).collect(),
+            None => s_inv.map(|s_i_inv| - (b * s_i_inv)).collect(),
+        };
+
+        let basepoint_scalar = w * (self.t_x - a * b) + c * ( - self.t_x);
+
+        let dynamic_scalars = iter::once(G::Scalar::ONE)
+            .chain(iter::once(x))
+            .chain(iter::once(c * x))
+            .chain(iter::once(c * x * x))
+            .chain(x_sq.iter().cloned())
+            .chain(x_inv_sq.iter().cloned())
+            .chain(iter::once(c))
+            .collect();
+
+        let static_scalars = iter::once(-self.e_blinding - c * self.t_x_blinding)
+            .chain(iter::once(basepoint_scalar))
+            .chain(g)
+            .chain(h)
+            .collect();
+
+        Ok((dynamic_scalars, static_scalars))
+    }
+
+    /// Replays the transcript of a proof with a public right hand side and computes the
+    /// scalars of the mega-check, in the same order as `mega_check_scalars`.
+    ///
+    /// The right hand side of the inner product argument is not committed in \\(A\\), so
+    /// the verifier adds \\(\langle \mathbf{b}, \mathbf{H} \rangle\\) itself, and
+    /// \\(T\_2\\) must be the identity.
+    fn public_rhs_mega_check_scalars(
+        &self,
+        transcript: &mut Transcript,
+        V: &G::Compressed,
+        rhs_ip: &[G::Scalar],
+        n: usize,
+        rng: Option<&mut dyn CryptoRngCore>,
+    ) -> Result<MegaCheckScalars<G::Scalar>, ProofError> {
+        append_public_rhs(transcript, rhs_ip, n)?;
+
+        if !self.T_2.is_identity() {
+            return Err(ProofError::VerificationError);
+        }
+
+        transcript.append_point(b"V", V);
+        transcript.validate_and_append_point(b"A", &self.A)?;
+        transcript.validate_and_append_point(b"S", &self.S)?;
+
+        transcript.validate_and_append_point(b"T_1", &self.T_1)?;
+
+        let x: G::Scalar = transcript.challenge_scalar(b"x");
+
+        transcript.append_scalar(b"t_x", &self.t_x);
+        transcript.append_scalar(b"t_x_blinding", &self.t_x_blinding);
+        transcript.append_scalar(b"e_blinding", &self.e_blinding);
+
+        let w: G::Scalar = transcript.challenge_scalar(b"w");
 
         let (x_sq, x_inv_sq, s) = self.ipp_proof.verification_scalars(n, transcript)?;
+        // Challenge value for batching statements to be verified
+        let c = self.batching_challenge(transcript, rng);
         let s_inv = s.iter().rev();
 
         let a = self.ipp_proof.a;
         let b = self.ipp_proof.b;
 
-        let g = s.iter().map(|s_i| - a * s_i);
-        let h = s_inv.map(|s_i_inv| - b * s_i_inv);
+        let g = s.iter().map(|s_i| - (a * s_i));
+        let h = s_inv.zip(rhs_ip.iter()).map(|(s_i_inv, b_i)| *b_i - b * s_i_inv);
 
         let basepoint_scalar = w * (self.t_x - a * b) + c * ( - self.t_x);
 
-        let mega_check = RistrettoPoint::optional_multiscalar_mul(
-            iter::once(Scalar::one())
-                .chain(iter::once(x))
-                .chain(iter::once(c * x))
-                .chain(iter::once(c * x * x))
-                .chain(x_sq.iter().cloned())
-                .chain(x_inv_sq.iter().cloned())
-                .chain(iter::once(-self.e_blinding - c * self.t_x_blinding))
-                .chain(iter::once(basepoint_scalar))
-                .chain(g)
-                .chain(h)
-                .chain(iter::once(c)),
-            iter::once(self.A.decompress())
-                .chain(iter::once(self.S.decompress()))
-                .chain(iter::once(self.T_1.decompress()))
-                .chain(iter::once(self.T_2.decompress()))
-                .chain(self.ipp_proof.L_vec.iter().map(|L| L.decompress()))
-                .chain(self.ipp_proof.R_vec.iter().map(|R| R.decompress()))
-                .chain(iter::once(Some(pc_gens.B_blinding)))
-                .chain(iter::once(Some(pc_gens.B)))
-                .chain(bp_gens.G(n, 1).map(|&x| Some(x)))
-                .chain(bp_gens.H(n, 1).map(|&x| Some(x)))
-                .chain(iter::once(V.decompress())),
-        )
-            .ok_or_else(|| ProofError::VerificationError)?;
+        let dynamic_scalars = iter::once(G::Scalar::ONE)
+            .chain(iter::once(x))
+            .chain(iter::once(c * x))
+            .chain(iter::once(G::Scalar::ZERO))
+            .chain(x_sq.iter().cloned())
+            .chain(x_inv_sq.iter().cloned())
+            .chain(iter::once(c))
+            .collect();
+
+        let static_scalars = iter::once(-self.e_blinding - c * self.t_x_blinding)
+            .chain(iter::once(basepoint_scalar))
+            .chain(g)
+            .chain(h)
+            .collect();
+
+        Ok((dynamic_scalars, static_scalars))
+    }
 
-        if mega_check.is_identity() {
-            Ok(())
-        } else {
-            Err(ProofError::VerificationError)
+    /// The scalar batching the two checks of the mega-check, drawn from `rng`. Without an RNG,
+    /// it is derived from the transcript once the whole proof is appended to it, so that the
+    /// prover cannot choose it.
+    fn batching_challenge(&self, transcript: &mut Transcript, rng: Option<&mut dyn CryptoRngCore>) -> G::Scalar {
+        match rng {
+            Some(rng) => G::Scalar::random(rng),
+            None => {
+                transcript.append_scalar(b"a", &self.ipp_proof.a);
+                transcript.append_scalar(b"b", &self.ipp_proof.b);
+                transcript.challenge_scalar(b"c")
+            }
         }
     }
 
+    /// Points of the mega-check that depend on the proof and on the commitments \\(V\\).
+    fn mega_check_points<'a>(
+        &'a self,
+        V: &'a [G::Compressed],
+    ) -> impl Iterator<Item = Option<G>> + 'a {
+        iter::once(self.A.decompress())
+            .chain(iter::once(self.S.decompress()))
+            .chain(iter::once(self.T_1.decompress()))
+            .chain(iter::once(self.T_2.decompress()))
+            .chain(self.ipp_proof.L_vec.iter().map(|L| L.decompress()))
+            .chain(self.ipp_proof.R_vec.iter().map(|R| R.decompress()))
+            .chain(V.iter().map(|V_j| V_j.decompress()))
+    }
+
     /// Verify that S corresponds to an expected value of S
-    pub fn verify_expected_A(&self, expected_A: CompressedRistretto) -> bool {
+    pub fn verify_expected_A(&self, expected_A: G::Compressed) -> bool {
         self.A == expected_A
     }
 
     /// Serializes the proof into a byte array of \\(2 \lg n + 9\\)
-    /// 32-byte elements, where \\(n\\) is the number of secret bits.
+    /// elements, where \\(n\\) is the number of secret bits. Over
+    /// Ristretto, each element takes 32 bytes.
     ///
     /// # Layout
     ///
     /// The layout of the range proof encoding is:
     ///
-    /// * four compressed Ristretto points \\(A,S,T_1,T_2\\),
+    /// * the header, see [`ProofKind`],
+    /// * four compressed points \\(A,S,T_1,T_2\\),
     /// * three scalars \\(t_x, \tilde{t}_x, \tilde{e}\\),
-    /// * \\(n\\) pairs of compressed Ristretto points \\(L_0,R_0\dots,L_{n-1},R_{n-1}\\),
+    /// * \\(n\\) pairs of compressed points \\(L_0,R_0\dots,L_{n-1},R_{n-1}\\),
     /// * two scalars \\(a, b\\).
     pub fn to_bytes(&self) -> Vec<u8> {
-        // 7 elements: points A, S, T1, T2, scalars tx, tx_bl, e_bl.
-        let mut buf = Vec::with_capacity(7 * 32 + self.ipp_proof.serialized_size());
+        let mut buf = Vec::with_capacity(self.serialized_size());
+        buf.extend_from_slice(&ProofKind::InnerProductZK.header());
         buf.extend_from_slice(self.A.as_bytes());
         buf.extend_from_slice(self.S.as_bytes());
         buf.extend_from_slice(self.T_1.as_bytes());
         buf.extend_from_slice(self.T_2.as_bytes());
-        buf.extend_from_slice(self.t_x.as_bytes());
-        buf.extend_from_slice(self.t_x_blinding.as_bytes());
-        buf.extend_from_slice(self.e_blinding.as_bytes());
+        buf.extend_from_slice(self.t_x.to_repr().as_ref());
+        buf.extend_from_slice(self.t_x_blinding.to_repr().as_ref());
+        buf.extend_from_slice(self.e_blinding.to_repr().as_ref());
         buf.extend(self.ipp_proof.to_bytes_iter());
         buf
     }
 
+    /// Returns the size in bytes required to serialize the proof, which for
+    /// vectors of length `n` over Ristretto is \\(32 \cdot (2\lg n + 9)\\) bytes
+    /// after the header.
+    pub fn serialized_size(&self) -> usize {
+        // 7 elements: points A, S, T1, T2, scalars tx, tx_bl, e_bl.
+        HEADER_SIZE + 4 * G::Compressed::SIZE + 3 * G::Scalar::SIZE + self.ipp_proof.body_size()
+    }
+
     /// Deserializes the proof from a byte slice.
     ///
-    /// Returns an error if the byte slice cannot be parsed into a `RangeProof`.
-    pub fn from_bytes(slice: &[u8]) -> Result<InnerProductZKProof, ProofError> {
-        if slice.len() % 32 != 0 {
-            return Err(ProofError::FormatError);
+    /// Returns an error if the byte slice cannot be parsed into an `InnerProductZKProof`.
+    pub fn from_bytes(slice: &[u8]) -> Result<InnerProductZKProof<G>, ProofError> {
+        match ProofKind::InnerProductZK.read_header(slice)? {
+            (1, body) => InnerProductZKProof::read_v1(&mut ProofReader::new(body)),
+            _ => Err(ProofError::FormatError),
         }
-        if slice.len() < 7 * 32 {
+    }
+
+    /// Reads the proof in the first version of the format, without the header.
+    fn read_v1(reader: &mut ProofReader) -> Result<InnerProductZKProof<G>, ProofError> {
+        Ok(InnerProductZKProof {
+            A: reader.read_point()?,
+            S: reader.read_point()?,
+            T_1: reader.read_point()?,
+            T_2: reader.read_point()?,
+            t_x: reader.read_scalar()?,
+            t_x_blinding: reader.read_scalar()?,
+            e_blinding: reader.read_scalar()?,
+            ipp_proof: InnerProductProof::read_v1(reader)?,
+        })
+    }
+
+    /// Deserializes the proof as `from_bytes`, and also checks that all
+    /// the points are canonical encodings of group elements.
+    ///
+    /// With `from_bytes`, invalid points are only rejected when verifying,
+    /// and groups with more than one encoding of a point would accept
+    /// several byte strings for the same proof. This rejects them at
+    /// parse time, so that each proof has a single encoding.
+    pub fn from_bytes_strict(slice: &[u8]) -> Result<InnerProductZKProof<G>, ProofError> {
+        let proof = InnerProductZKProof::<G>::from_bytes(slice)?;
+        if !proof.points_are_canonical() {
             return Err(ProofError::FormatError);
         }
+        Ok(proof)
+    }
+}
+
+impl<G: PrimeGroup> CanonicalPoints for InnerProductZKProof<G> {
+    fn points_are_canonical(&self) -> bool {
+        [self.A, self.S, self.T_1, self.T_2].points_are_canonical() && self.ipp_proof.points_are_canonical()
+    }
+}
+
+impl InnerProductZKProof {
+    /// Verifies the proof computing the mega-check with the given multiscalar multiplication
+    /// algorithm. With `MsmAlgorithm::Precomputed` the tables are built for this single
+    /// verification, so use `verify_single_precomputed` to share them across proofs. With
+    /// the `prover-only` feature it falls back to the algorithm of a single verification.
+    pub fn verify_single_with_msm<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+        n: usize,
+        algorithm: MsmAlgorithm,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        #[cfg(not(feature = "prover-only"))]
+        if algorithm == MsmAlgorithm::Precomputed {
+            let precomputed_gens = PrecomputedGens::new(bp_gens, pc_gens, n);
+            return self.verify_single_precomputed(&precomputed_gens, transcript, V, rng);
+        }
 
-        use util::read32;
+        self.verify_single_with_backend(bp_gens, pc_gens, &algorithm, transcript, V, n, rng)
+    }
 
-        let A = CompressedRistretto(read32(&slice[0 * 32..]));
-        let S = CompressedRistretto(read32(&slice[1 * 32..]));
-        let T_1 = CompressedRistretto(read32(&slice[2 * 32..]));
-        let T_2 = CompressedRistretto(read32(&slice[3 * 32..]));
+    /// Verifies the proof using tables precomputed for the generators, which is worth it
+    /// when verifying a large batch of proofs of the same size.
+    #[cfg(not(feature = "prover-only"))]
+    pub fn verify_single_precomputed<T: RngCore + CryptoRng>(
+        &self,
+        precomputed_gens: &PrecomputedGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.verify_precomputed_internal(precomputed_gens, transcript, V, rng)
+    }
 
-        let t_x = Scalar::from_canonical_bytes(read32(&slice[4 * 32..]))
-            .ok_or(ProofError::FormatError)?;
-        let t_x_blinding = Scalar::from_canonical_bytes(read32(&slice[5 * 32..]))
-            .ok_or(ProofError::FormatError)?;
-        let e_blinding = Scalar::from_canonical_bytes(read32(&slice[6 * 32..]))
-            .ok_or(ProofError::FormatError)?;
+    /// Verifies the proof as `verify_single_with_expected_A`, using tables precomputed for
+    /// the generators.
+    #[cfg(not(feature = "prover-only"))]
+    pub fn verify_single_precomputed_with_expected_A<T: RngCore + CryptoRng>(
+        &self,
+        precomputed_gens: &PrecomputedGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+        expected_A: &CompressedRistretto,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        self.with_A(expected_A).verify_precomputed_internal(precomputed_gens, transcript, V, rng)
+    }
 
-        let ipp_proof = InnerProductProof::from_bytes(&slice[7 * 32..])?;
+    #[cfg(not(feature = "prover-only"))]
+    fn verify_precomputed_internal<T: RngCore + CryptoRng>(
+        &self,
+        precomputed_gens: &PrecomputedGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let (dynamic_scalars, static_scalars) =
+            self.mega_check_scalars(transcript, V, None, precomputed_gens.size(), Some(rng))?;
+
+        let _span = msm_span!("inner_product_zk_precomputed", size = precomputed_gens.size());
+        let mega_check = precomputed_gens.optional_mixed_multiscalar_mul(
+            static_scalars,
+            dynamic_scalars,
+            self.mega_check_points(slice::from_ref(V)),
+        )
+            .ok_or(ProofError::VerificationError)?;
 
-        Ok(InnerProductZKProof {
-            A,
-            S,
-            T_1,
-            T_2,
-            t_x,
-            t_x_blinding,
-            e_blinding,
-            ipp_proof,
-        })
+        if bool::from(mega_check.is_identity()) {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
     }
+
     /// Computes an inner product of two vectors
     /// \\[
     ///    {\langle {\mathbf{a}}, {\mathbf{b}} \rangle} = \sum\_{i=0}^{n-1} a\_i \cdot b\_i.
     /// \\]
     /// Panics if the lengths of \\(\mathbf{a}\\) and \\(\mathbf{b}\\) are not equal.
     pub fn inner_product(a: &[Scalar], b: &[Scalar]) -> Scalar {
-        let mut out = Scalar::zero();
+        let mut out = Scalar::ZERO;
         if a.len() != b.len() {
             panic!("inner_product(a,b): lengths of vectors do not match");
         }
@@ -343,7 +1235,119 @@ impl InnerProductZKProof {
     }
 }
 
-impl Serialize for InnerProductZKProof {
+/// Checks that there is one non-zero weight per element, appends the weights to the
+/// transcript, and returns their inverses.
+fn invert_weights<S: PrimeField>(
+    transcript: &mut Transcript,
+    weights: &[S],
+    n: usize,
+) -> Result<Vec<S>, ProofError> {
+    if weights.len() != n {
+        return Err(ProofError::InvalidWeights);
+    }
+    if weights.iter().any(|c_i| bool::from(c_i.is_zero())) {
+        return Err(ProofError::InvalidWeights);
+    }
+
+    transcript.weighted_ipzk_domain_sep(n as u64);
+    for c_i in weights {
+        transcript.append_scalar(b"c", c_i);
+    }
+
+    let mut inv_weights = weights.to_vec();
+    inv_weights.iter_mut().batch_invert();
+    Ok(inv_weights)
+}
+
+/// Checks that there are generators for `m` parties with `n` elements each.
+fn check_gens_capacity<G: PrimeGroup>(bp_gens: &BulletproofGens<G>, n: usize, m: usize) -> Result<(), ProofError> {
+    if bp_gens.gens_capacity < n || bp_gens.party_capacity < m {
+        return Err(ProofError::InvalidGeneratorsLength);
+    }
+    Ok(())
+}
+
+/// Checks that the public right hand side has one scalar per element, and appends it to
+/// the transcript.
+fn append_public_rhs<S: PrimeField>(
+    transcript: &mut Transcript,
+    rhs_ip: &[S],
+    n: usize,
+) -> Result<(), ProofError> {
+    if rhs_ip.len() != n {
+        return Err(ProofError::InvalidPublicVectorLength);
+    }
+
+    transcript.public_rhs_ipzk_domain_sep(n as u64);
+    for b_i in rhs_ip {
+        transcript.append_scalar(b"b", b_i);
+    }
+    Ok(())
+}
+
+impl<G: PrimeGroup> SerializedSize for InnerProductZKProof<G> {
+    fn serialized_size(&self) -> usize {
+        InnerProductZKProof::serialized_size(self)
+    }
+}
+
+impl<G: PrimeGroup> PartialOrd for InnerProductZKProof<G> {
+    fn partial_cmp(&self, other: &InnerProductZKProof<G>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<G: PrimeGroup> Ord for InnerProductZKProof<G> {
+    fn cmp(&self, other: &InnerProductZKProof<G>) -> Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
+impl<G: PrimeGroup> Hash for InnerProductZKProof<G> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+/// The parameters are the generators and the size \\(n\\) of the vectors,
+/// the witness the inner product \\(v\\), the vectors and the blinding
+/// factors of \\(V\\) and \\(A\\), as in `prove_single`, and the statement
+/// the commitment \\(V\\). The proof is verified with a `thread_rng`.
+#[cfg(feature = "std")]
+impl<G: PrimeGroup> ZkProof for InnerProductZKProof<G> {
+    type Params<'a> = (&'a BulletproofGens<G>, &'a PedersenGens<G>, usize);
+    type Witness<'a> = (G::Scalar, &'a Vec<G::Scalar>, &'a Vec<G::Scalar>, G::Scalar, G::Scalar);
+    type Statement = G::Compressed;
+    type Error = ProofError;
+
+    fn prove<R: RngCore + CryptoRng>(
+        (bp_gens, pc_gens, n): Self::Params<'_>,
+        (v, lhs_ip, rhs_ip, v_blinding, a_blinding): Self::Witness<'_>,
+        transcript: &mut Transcript,
+        rng: &mut R,
+    ) -> Result<(InnerProductZKProof<G>, G::Compressed), ProofError> {
+        InnerProductZKProof::prove_single(bp_gens, pc_gens, transcript, v, lhs_ip, rhs_ip, v_blinding, a_blinding, n, rng)
+    }
+
+    fn verify(
+        &self,
+        (bp_gens, pc_gens, n): Self::Params<'_>,
+        V: &G::Compressed,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_single(bp_gens, pc_gens, transcript, V, n, &mut rand::thread_rng())
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        InnerProductZKProof::to_bytes(self)
+    }
+
+    fn from_bytes(slice: &[u8]) -> Result<InnerProductZKProof<G>, ProofError> {
+        InnerProductZKProof::from_bytes(slice)
+    }
+}
+
+impl<G: PrimeGroup> Serialize for InnerProductZKProof<G> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where
             S: Serializer,
@@ -352,21 +1356,21 @@ impl Serialize for InnerProductZKProof {
     }
 }
 
-impl<'de> Deserialize<'de> for InnerProductZKProof {
+impl<'de, G: PrimeGroup> Deserialize<'de> for InnerProductZKProof<G> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where
             D: Deserializer<'de>,
     {
-        struct RangeProofVisitor;
+        struct RangeProofVisitor<G>(PhantomData<G>);
 
-        impl<'de> Visitor<'de> for RangeProofVisitor {
-            type Value = InnerProductZKProof;
+        impl<'de, G: PrimeGroup> Visitor<'de> for RangeProofVisitor<G> {
+            type Value = InnerProductZKProof<G>;
 
             fn expecting(&self, formatter: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
                 formatter.write_str("a valid RangeProof")
             }
 
-            fn visit_bytes<E>(self, v: &[u8]) -> Result<InnerProductZKProof, E>
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<InnerProductZKProof<G>, E>
                 where
                     E: serde::de::Error,
             {
@@ -381,7 +1385,7 @@ impl<'de> Deserialize<'de> for InnerProductZKProof {
             }
         }
 
-        deserializer.deserialize_bytes(RangeProofVisitor)
+        deserializer.deserialize_bytes(RangeProofVisitor(PhantomData))
     }
 }
 
@@ -395,20 +1399,20 @@ mod tests {
 
     use rand_chacha::ChaChaRng;
 
-    fn single_ip_zk_proof_helper(n: usize) {
+    fn single_ip_zk_proof_helper<G: PrimeGroup>(n: usize) {
         let max_bitsize = 128;
-        let pc_gens = PedersenGens::default();
-        let bp_gens = BulletproofGens::new(max_bitsize, 1);
+        let pc_gens = PedersenGens::<G>::default();
+        let bp_gens = BulletproofGens::<G>::new(max_bitsize, 1);
         let mut test_rng = ChaChaRng::from_seed([24u8; 32]);
 
         let (proof_bytes, value_commitments) = {
 
-            let lhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
-            let rhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
-            let values: Scalar = InnerProductZKProof::inner_product(lhs_ip.as_slice(), rhs_ip.as_slice());
+            let lhs_ip: Vec<G::Scalar> = (0..n).map(|_| G::Scalar::random(&mut test_rng)).collect();
+            let rhs_ip: Vec<G::Scalar> = (0..n).map(|_| G::Scalar::random(&mut test_rng)).collect();
+            let values = crate::inner_product_proof::inner_product(&lhs_ip, &rhs_ip);
 
-            let v_blinding: Scalar = Scalar::random(&mut test_rng);
-            let a_blinding: Scalar = Scalar::random(&mut test_rng);
+            let v_blinding = G::Scalar::random(&mut test_rng);
+            let a_blinding = G::Scalar::random(&mut test_rng);
 
             // 1. Create the proof
             let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
@@ -433,7 +1437,7 @@ mod tests {
         // Verifier's scope
         {
             // 3. Deserialize
-            let proof: InnerProductZKProof = bincode::deserialize(&proof_bytes).unwrap();
+            let proof: InnerProductZKProof<G> = bincode::deserialize(&proof_bytes).unwrap();
 
             // 4. Verify with the same customization label as above
             let mut transcript = Transcript::new(b"AggregatedRangeProofTest");
@@ -445,17 +1449,582 @@ mod tests {
     }
 
     #[test]
-    fn create_and_verify_ip_proof_8() {single_ip_zk_proof_helper(8);}
+    fn create_and_verify_ip_proof_8() {single_ip_zk_proof_helper::<RistrettoPoint>(8);}
+
+    #[test]
+    fn create_and_verify_ip_proof_16() {single_ip_zk_proof_helper::<RistrettoPoint>(16);}
+
+    #[test]
+    fn create_and_verify_ip_proof_32() {single_ip_zk_proof_helper::<RistrettoPoint>(32);}
+
+    #[test]
+    fn create_and_verify_ip_proof_64() {single_ip_zk_proof_helper::<RistrettoPoint>(64);}
+
+    #[test]
+    fn create_and_verify_ip_proof_128() {single_ip_zk_proof_helper::<RistrettoPoint>(128);}
+
+    #[cfg(feature = "bls12_381")]
+    #[test]
+    fn create_and_verify_ip_proof_bls12_381() {single_ip_zk_proof_helper::<bls12_381::G1Projective>(16);}
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn create_and_verify_ip_proof_secp256k1() {single_ip_zk_proof_helper::<k256::ProjectivePoint>(16);}
+
+    #[test]
+    fn verify_with_every_msm_algorithm() {
+        let n = 16;
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, 1);
+        let mut test_rng = ChaChaRng::from_seed([24u8; 32]);
+
+        let lhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let rhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let value = InnerProductZKProof::inner_product(lhs_ip.as_slice(), rhs_ip.as_slice());
+
+        let mut transcript = Transcript::new(b"MsmAlgorithmTest");
+        let (proof, value_commitment) = InnerProductZKProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            value,
+            &lhs_ip,
+            &rhs_ip,
+            Scalar::random(&mut test_rng),
+            Scalar::random(&mut test_rng),
+            n,
+            &mut test_rng
+        )
+            .unwrap();
+
+        for &algorithm in &[MsmAlgorithm::Straus, MsmAlgorithm::Pippenger, MsmAlgorithm::Precomputed] {
+            let mut transcript = Transcript::new(b"MsmAlgorithmTest");
+            assert!(proof
+                .verify_single_with_msm(&bp_gens, &pc_gens, &mut transcript, &value_commitment, n, algorithm, &mut test_rng)
+                .is_ok());
+        }
+
+        #[cfg(not(feature = "prover-only"))]
+        {
+            let precomputed_gens = PrecomputedGens::new(&bp_gens, &pc_gens, n);
+            let mut transcript = Transcript::new(b"MsmAlgorithmTest");
+            assert!(proof
+                .verify_single_precomputed(&precomputed_gens, &mut transcript, &value_commitment, &mut test_rng)
+                .is_ok());
+
+            let wrong_commitment = pc_gens.commit(value + Scalar::ONE, Scalar::random(&mut test_rng)).compress();
+            let mut transcript = Transcript::new(b"MsmAlgorithmTest");
+            assert!(proof
+                .verify_single_precomputed(&precomputed_gens, &mut transcript, &wrong_commitment, &mut test_rng)
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn verify_deterministic() {
+        let n = 16;
+        let pc_gens: PedersenGens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, 1);
+        let mut test_rng = ChaChaRng::from_seed([25u8; 32]);
+
+        let lhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let rhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let value = InnerProductZKProof::inner_product(lhs_ip.as_slice(), rhs_ip.as_slice());
+
+        let mut transcript = Transcript::new(b"DeterministicTest");
+        let (proof, value_commitment) = InnerProductZKProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            value,
+            &lhs_ip,
+            &rhs_ip,
+            Scalar::random(&mut test_rng),
+            Scalar::random(&mut test_rng),
+            n,
+            &mut test_rng
+        )
+            .unwrap();
+
+        // Both verifiers leave the transcript in the same state
+        let mut first = Transcript::new(b"DeterministicTest");
+        let mut second = Transcript::new(b"DeterministicTest");
+        assert!(proof.verify_single_deterministic(&bp_gens, &pc_gens, &mut first, &value_commitment, n).is_ok());
+        assert!(proof.verify_single_deterministic(&bp_gens, &pc_gens, &mut second, &value_commitment, n).is_ok());
+        let (mut first_challenge, mut second_challenge) = ([0u8; 32], [0u8; 32]);
+        first.challenge_bytes(b"next", &mut first_challenge);
+        second.challenge_bytes(b"next", &mut second_challenge);
+        assert_eq!(first_challenge, second_challenge);
+
+        let wrong_commitment = pc_gens.commit(value + Scalar::ONE, Scalar::random(&mut test_rng)).compress();
+        let mut transcript = Transcript::new(b"DeterministicTest");
+        assert!(proof.verify_single_deterministic(&bp_gens, &pc_gens, &mut transcript, &wrong_commitment, n).is_err());
+    }
+
+    #[test]
+    fn prove_with_external_A() {
+        let n = 16;
+        let pc_gens: PedersenGens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, 1);
+        let mut test_rng = ChaChaRng::from_seed([29u8; 32]);
+
+        let lhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let rhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let value = InnerProductZKProof::inner_product(lhs_ip.as_slice(), rhs_ip.as_slice());
+        let a_blinding = Scalar::random(&mut test_rng);
+        let commit_A = |blinding: Scalar| (pc_gens.B_blinding * blinding
+            + RistrettoPoint::vartime_multiscalar_mul(
+                &lhs_ip.iter().chain(rhs_ip.iter()).cloned().collect::<Vec<Scalar>>(),
+                &bp_gens.G(n, 1).chain(bp_gens.H(n, 1)).cloned().collect::<Vec<RistrettoPoint>>(),
+            )).compress();
+        let A = commit_A(a_blinding);
+
+        // The proof is the same as if A was computed by the prover
+        let mut transcript = Transcript::new(b"ExternalATest");
+        let (proof, value_commitment) = InnerProductZKProof::prove_with_external_A(
+            &bp_gens, &pc_gens, &mut transcript, value, &lhs_ip, &rhs_ip, Scalar::ONE, &A, a_blinding, n, &mut ChaChaRng::from_seed([30u8; 32]),
+        ).unwrap();
+        let mut transcript = Transcript::new(b"ExternalATest");
+        let (computed, _) = InnerProductZKProof::prove_single(
+            &bp_gens, &pc_gens, &mut transcript, value, &lhs_ip, &rhs_ip, Scalar::ONE, a_blinding, n, &mut ChaChaRng::from_seed([30u8; 32]),
+        ).unwrap();
+        assert_eq!(proof, computed);
+
+        let mut transcript = Transcript::new(b"ExternalATest");
+        assert!(proof.verify_single_with_expected_A(&bp_gens, &pc_gens, &mut transcript, &value_commitment, &A, n, &mut test_rng).is_ok());
+
+        // A commitment with another blinding does not verify
+        let other_A = commit_A(a_blinding + Scalar::ONE);
+        let mut transcript = Transcript::new(b"ExternalATest");
+        let (proof, value_commitment) = InnerProductZKProof::prove_with_external_A(
+            &bp_gens, &pc_gens, &mut transcript, value, &lhs_ip, &rhs_ip, Scalar::ONE, &other_A, a_blinding, n, &mut test_rng,
+        ).unwrap();
+        let mut transcript = Transcript::new(b"ExternalATest");
+        assert!(proof.verify_single(&bp_gens, &pc_gens, &mut transcript, &value_commitment, n, &mut test_rng).is_err());
+
+        let mut transcript = Transcript::new(b"ExternalATest");
+        assert_eq!(
+            InnerProductZKProof::prove_with_external_A(
+                &bp_gens, &pc_gens, &mut transcript, value, &lhs_ip, &rhs_ip, Scalar::ONE, &CompressedRistretto([0xff; 32]), a_blinding, n, &mut test_rng,
+            ).err(),
+            Some(ProofError::FormatError)
+        );
+    }
+
+    #[test]
+    fn insufficient_generators() {
+        let n = 16;
+        let pc_gens: PedersenGens = PedersenGens::default();
+        let mut test_rng = ChaChaRng::from_seed([26u8; 32]);
+
+        let lhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let rhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let value = InnerProductZKProof::inner_product(lhs_ip.as_slice(), rhs_ip.as_slice());
+
+        for bp_gens in [BulletproofGens::new(n / 2, 1), BulletproofGens::new(n, 0)].iter() {
+            let mut transcript = Transcript::new(b"CapacityTest");
+            let result = InnerProductZKProof::prove_single(
+                bp_gens,
+                &pc_gens,
+                &mut transcript,
+                value,
+                &lhs_ip,
+                &rhs_ip,
+                Scalar::random(&mut test_rng),
+                Scalar::random(&mut test_rng),
+                n,
+                &mut test_rng
+            );
+            assert_eq!(result.err(), Some(ProofError::InvalidGeneratorsLength));
+        }
+
+        let bp_gens = BulletproofGens::new(n, 1);
+        let mut transcript = Transcript::new(b"CapacityTest");
+        let (proof, value_commitment) = InnerProductZKProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            value,
+            &lhs_ip,
+            &rhs_ip,
+            Scalar::random(&mut test_rng),
+            Scalar::random(&mut test_rng),
+            n,
+            &mut test_rng
+        )
+            .unwrap();
+        let mut transcript = Transcript::new(b"CapacityTest");
+        assert_eq!(
+            proof.verify_single(&BulletproofGens::new(n / 2, 1), &pc_gens, &mut transcript, &value_commitment, n, &mut test_rng),
+            Err(ProofError::InvalidGeneratorsLength)
+        );
+    }
+
+    #[test]
+    fn create_and_verify_weighted_ip_proof() {
+        let n = 16;
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, 1);
+        let mut test_rng = ChaChaRng::from_seed([24u8; 32]);
+
+        let lhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let rhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let weights: Vec<Scalar> = (1..=n as u64).map(Scalar::from).collect();
+        let weighted_rhs: Vec<Scalar> = rhs_ip.iter().zip(weights.iter()).map(|(b, c)| b * c).collect();
+        let value = InnerProductZKProof::inner_product(&lhs_ip, &weighted_rhs);
+        let a_blinding = Scalar::random(&mut test_rng);
+
+        let mut transcript = Transcript::new(b"WeightedTest");
+        let (proof, value_commitment) = InnerProductZKProof::prove_single_weighted(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            value,
+            &lhs_ip,
+            &rhs_ip,
+            &weights,
+            Scalar::random(&mut test_rng),
+            a_blinding,
+            n,
+            &mut test_rng
+        )
+            .unwrap();
+
+        // A commits to the unweighted vectors
+        let expected_A = pc_gens.B_blinding * a_blinding
+            + RistrettoPoint::vartime_multiscalar_mul(
+                &lhs_ip.iter().chain(rhs_ip.iter()).cloned().collect::<Vec<Scalar>>(),
+                &bp_gens.G(n, 1).chain(bp_gens.H(n, 1)).cloned().collect::<Vec<RistrettoPoint>>(),
+            );
+        assert!(proof.verify_expected_A(expected_A.compress()));
+
+        // The same expected A is bound to the verification of the unweighted proof
+        let mut transcript = Transcript::new(b"WeightedTest");
+        let (unweighted, unweighted_commitment) = InnerProductZKProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            InnerProductZKProof::inner_product(&lhs_ip, &rhs_ip),
+            &lhs_ip,
+            &rhs_ip,
+            Scalar::random(&mut test_rng),
+            a_blinding,
+            n,
+            &mut test_rng
+        )
+            .unwrap();
+        for expected in [expected_A, expected_A + pc_gens.B].iter() {
+            let mut transcript = Transcript::new(b"WeightedTest");
+            let result = unweighted.verify_single_with_expected_A(
+                &bp_gens, &pc_gens, &mut transcript, &unweighted_commitment, &expected.compress(), n, &mut test_rng,
+            );
+            assert_eq!(result.is_ok(), *expected == expected_A);
+
+            #[cfg(not(feature = "prover-only"))]
+            {
+                let precomputed_gens = PrecomputedGens::new(&bp_gens, &pc_gens, n);
+                let mut transcript = Transcript::new(b"WeightedTest");
+                let precomputed_result = unweighted.verify_single_precomputed_with_expected_A(
+                    &precomputed_gens, &mut transcript, &unweighted_commitment, &expected.compress(), &mut test_rng,
+                );
+                assert_eq!(precomputed_result, result);
+            }
+        }
+
+        let mut transcript = Transcript::new(b"WeightedTest");
+        assert!(proof
+            .verify_single_weighted(&bp_gens, &pc_gens, &mut transcript, &value_commitment, &weights, n, &mut test_rng)
+            .is_ok());
+
+        // Neither the unweighted statement nor other weights verify
+        let mut transcript = Transcript::new(b"WeightedTest");
+        assert!(proof
+            .verify_single(&bp_gens, &pc_gens, &mut transcript, &value_commitment, n, &mut test_rng)
+            .is_err());
+
+        let mut other_weights = weights.clone();
+        other_weights[3] += Scalar::ONE;
+        let mut transcript = Transcript::new(b"WeightedTest");
+        assert!(proof
+            .verify_single_weighted(&bp_gens, &pc_gens, &mut transcript, &value_commitment, &other_weights, n, &mut test_rng)
+            .is_err());
+
+        other_weights[3] = Scalar::ZERO;
+        let mut transcript = Transcript::new(b"WeightedTest");
+        assert_eq!(
+            proof.verify_single_weighted(&bp_gens, &pc_gens, &mut transcript, &value_commitment, &other_weights, n, &mut test_rng),
+            Err(ProofError::InvalidWeights)
+        );
+    }
 
     #[test]
-    fn create_and_verify_ip_proof_16() {single_ip_zk_proof_helper(16);}
+    fn create_and_verify_ip_proof_with_public_rhs() {
+        let n = 16;
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, 1);
+        let mut test_rng = ChaChaRng::from_seed([24u8; 32]);
+
+        let lhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let rhs_ip: Vec<Scalar> = iter::repeat_n(Scalar::ONE, n).collect();
+        let value = InnerProductZKProof::inner_product(&lhs_ip, &rhs_ip);
+        let a_blinding = Scalar::random(&mut test_rng);
+
+        let mut transcript = Transcript::new(b"PublicRhsTest");
+        let (proof, value_commitment) = InnerProductZKProof::prove_with_public_rhs(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            value,
+            &lhs_ip,
+            &rhs_ip,
+            Scalar::random(&mut test_rng),
+            a_blinding,
+            n,
+            &mut test_rng
+        )
+            .unwrap();
+
+        // A only commits to the left hand side
+        let expected_A = pc_gens.B_blinding * a_blinding
+            + RistrettoPoint::vartime_multiscalar_mul(&lhs_ip, &bp_gens.G(n, 1).cloned().collect::<Vec<RistrettoPoint>>());
+        assert!(proof.verify_expected_A(expected_A.compress()));
+
+        let mut transcript = Transcript::new(b"PublicRhsTest");
+        assert!(proof
+            .verify_with_public_rhs(&bp_gens, &pc_gens, &mut transcript, &value_commitment, &rhs_ip, n, &mut test_rng)
+            .is_ok());
+
+        for expected in [expected_A, expected_A + pc_gens.B].iter() {
+            let mut transcript = Transcript::new(b"PublicRhsTest");
+            let result = proof.verify_with_public_rhs_with_expected_A(
+                &bp_gens, &pc_gens, &mut transcript, &value_commitment, &expected.compress(), &rhs_ip, n, &mut test_rng,
+            );
+            assert_eq!(result.is_ok(), *expected == expected_A);
+        }
+
+        // The proof is not valid for another right hand side, nor as a regular proof
+        let mut other_rhs = rhs_ip.clone();
+        other_rhs[5] = Scalar::from(2u64);
+        let mut transcript = Transcript::new(b"PublicRhsTest");
+        assert!(proof
+            .verify_with_public_rhs(&bp_gens, &pc_gens, &mut transcript, &value_commitment, &other_rhs, n, &mut test_rng)
+            .is_err());
+
+        let mut transcript = Transcript::new(b"PublicRhsTest");
+        assert!(proof
+            .verify_single(&bp_gens, &pc_gens, &mut transcript, &value_commitment, n, &mut test_rng)
+            .is_err());
+
+        let mut transcript = Transcript::new(b"PublicRhsTest");
+        assert_eq!(
+            proof.verify_with_public_rhs(&bp_gens, &pc_gens, &mut transcript, &value_commitment, &rhs_ip[1..], n, &mut test_rng),
+            Err(ProofError::InvalidPublicVectorLength)
+        );
+
+        // The proof survives serialization
+        let proof = InnerProductZKProof::<RistrettoPoint>::from_bytes(&proof.to_bytes()).unwrap();
+        let mut transcript = Transcript::new(b"PublicRhsTest");
+        assert!(proof
+            .verify_with_public_rhs(&bp_gens, &pc_gens, &mut transcript, &value_commitment, &rhs_ip, n, &mut test_rng)
+            .is_ok());
+    }
 
     #[test]
-    fn create_and_verify_ip_proof_32() {single_ip_zk_proof_helper(32);}
+    fn create_and_verify_ip_proof_with_vector_commitments() {
+        let n = 16;
+        let pc_gens = PedersenGens::<RistrettoPoint>::default();
+        let bp_gens = BulletproofGens::<RistrettoPoint>::new(n, 1);
+        let mut test_rng = ChaChaRng::from_seed([24u8; 32]);
+
+        let lhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let rhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let value = InnerProductZKProof::inner_product(&lhs_ip, &rhs_ip);
+        let lhs_blinding = Scalar::random(&mut test_rng);
+        let rhs_blinding = Scalar::random(&mut test_rng);
+
+        // Commitments computed independently of the proof
+        let G: Vec<RistrettoPoint> = bp_gens.G(n, 1).cloned().collect();
+        let H: Vec<RistrettoPoint> = bp_gens.H(n, 1).cloned().collect();
+        let lhs_commitment = (pc_gens.B_blinding * lhs_blinding + RistrettoPoint::vartime_multiscalar_mul(&lhs_ip, &G)).compress();
+        let rhs_commitment = (pc_gens.B_blinding * rhs_blinding + RistrettoPoint::vartime_multiscalar_mul(&rhs_ip, &H)).compress();
+
+        let mut transcript = Transcript::new(b"VectorCommitmentsTest");
+        let (proof, value_commitment) = InnerProductZKProof::prove_with_vector_commitments(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            value,
+            &lhs_ip,
+            &rhs_ip,
+            Scalar::random(&mut test_rng),
+            lhs_blinding,
+            rhs_blinding,
+            n,
+            &mut test_rng
+        )
+            .unwrap();
+
+        let mut transcript = Transcript::new(b"VectorCommitmentsTest");
+        assert!(proof
+            .verify_with_vector_commitments(&bp_gens, &pc_gens, &mut transcript, &value_commitment, &lhs_commitment, &rhs_commitment, n, &mut test_rng)
+            .is_ok());
+
+        // Commitments to other vectors, or the same ones swapped, are rejected
+        let other_commitment = (pc_gens.B_blinding * rhs_blinding + RistrettoPoint::vartime_multiscalar_mul(&lhs_ip, &H)).compress();
+        let mut transcript = Transcript::new(b"VectorCommitmentsTest");
+        assert!(proof
+            .verify_with_vector_commitments(&bp_gens, &pc_gens, &mut transcript, &value_commitment, &lhs_commitment, &other_commitment, n, &mut test_rng)
+            .is_err());
+
+        let mut transcript = Transcript::new(b"VectorCommitmentsTest");
+        assert!(proof
+            .verify_with_vector_commitments(&bp_gens, &pc_gens, &mut transcript, &value_commitment, &rhs_commitment, &lhs_commitment, n, &mut test_rng)
+            .is_err());
+    }
+
+    #[test]
+    fn strict_deserialization_rejects_invalid_points() {
+        let n = 8;
+        let pc_gens: PedersenGens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, 1);
+        let mut test_rng = ChaChaRng::from_seed([24u8; 32]);
+
+        let lhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let rhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let mut transcript = Transcript::new(b"StrictDeserializationTest");
+        let (proof, _) = InnerProductZKProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            InnerProductZKProof::inner_product(&lhs_ip, &rhs_ip),
+            &lhs_ip,
+            &rhs_ip,
+            Scalar::random(&mut test_rng),
+            Scalar::random(&mut test_rng),
+            n,
+            &mut test_rng
+        )
+            .unwrap();
+
+        let bytes = proof.to_bytes();
+        let parsed: InnerProductZKProof = InnerProductZKProof::from_bytes_strict(&bytes).unwrap();
+        assert_eq!(parsed.to_bytes(), bytes);
+        assert_eq!(bytes.len(), proof.serialized_size());
+
+        // An invalid S, or an invalid L of the inner product argument, is only caught by
+        // the strict parser
+        for position in [HEADER_SIZE + 32, HEADER_SIZE + 7 * 32] {
+            let mut invalid = bytes.clone();
+            invalid[position..position + 32].copy_from_slice(&[0xff; 32]);
+            assert!(InnerProductZKProof::<RistrettoPoint>::from_bytes(&invalid).is_ok());
+            assert_eq!(
+                InnerProductZKProof::<RistrettoPoint>::from_bytes_strict(&invalid).unwrap_err(),
+                ProofError::FormatError
+            );
+        }
+    }
 
     #[test]
-    fn create_and_verify_ip_proof_64() {single_ip_zk_proof_helper(64);}
+    fn rewind_recovers_the_opening() {
+        let n = 8;
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, 1);
+        let mut rng = ChaChaRng::from_seed([24u8; 32]);
+        let rewind_key = RewindKey::from_bytes([7u8; 32]);
+
+        let lhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let rhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let v = crate::inner_product_proof::inner_product(&lhs_ip, &rhs_ip);
+        let v_blinding = Scalar::random(&mut rng);
+
+        let mut transcript = Transcript::new(b"RewindTest");
+        let (proof, V) = InnerProductZKProof::<RistrettoPoint>::prove_single_rewindable(
+            &bp_gens, &pc_gens, &mut transcript, v, &lhs_ip, &rhs_ip, v_blinding, n, &rewind_key, &mut rng,
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"RewindTest");
+        assert!(proof.verify_single(&bp_gens, &pc_gens, &mut transcript, &V, n, &mut rng).is_ok());
+
+        let mut transcript = Transcript::new(b"RewindTest");
+        assert_eq!(proof.rewind(&pc_gens, &mut transcript, &V, &rewind_key).unwrap(), (v, v_blinding));
+
+        let mut transcript = Transcript::new(b"RewindTest");
+        let other_key = RewindKey::from_bytes([8u8; 32]);
+        assert_eq!(
+            proof.rewind(&pc_gens, &mut transcript, &V, &other_key).unwrap_err(),
+            ProofError::VerificationError
+        );
+    }
 
     #[test]
-    fn create_and_verify_ip_proof_128() {single_ip_zk_proof_helper(128);}
-}
\ No newline at end of file
+    fn verification_size_matches_mega_check() {
+        assert_eq!(InnerProductZKProof::<RistrettoPoint>::verification_size(8), 29);
+        assert_eq!(InnerProductZKProof::<RistrettoPoint>::verification_size(128), 277);
+    }
+
+    /// Backend counting the multiplications it is handed, to check that both the prover
+    /// and the verifier go through it.
+    struct CountingBackend {
+        calls: core::cell::Cell<usize>,
+    }
+
+    impl MsmBackend for CountingBackend {
+        fn multiscalar_mul(&self, scalars: &[Scalar], points: &[RistrettoPoint]) -> RistrettoPoint {
+            self.calls.set(self.calls.get() + 1);
+            CpuMsmBackend.multiscalar_mul(scalars, points)
+        }
+
+        fn vartime_optional_multiscalar_mul(
+            &self,
+            scalars: &[Scalar],
+            points: &[Option<RistrettoPoint>],
+        ) -> Option<RistrettoPoint> {
+            self.calls.set(self.calls.get() + 1);
+            CpuMsmBackend.vartime_optional_multiscalar_mul(scalars, points)
+        }
+    }
+
+    #[test]
+    fn prove_and_verify_with_custom_backend() {
+        let n = 8;
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, 1);
+        let mut test_rng = ChaChaRng::from_seed([24u8; 32]);
+        let backend = CountingBackend { calls: core::cell::Cell::new(0) };
+
+        let lhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let rhs_ip: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut test_rng)).collect();
+        let value = InnerProductZKProof::inner_product(lhs_ip.as_slice(), rhs_ip.as_slice());
+
+        let mut transcript = Transcript::new(b"MsmBackendTest");
+        let (proof, value_commitment) = InnerProductZKProof::prove_single_with_backend(
+            &bp_gens,
+            &pc_gens,
+            &backend,
+            &mut transcript,
+            value,
+            &lhs_ip,
+            &rhs_ip,
+            Scalar::random(&mut test_rng),
+            Scalar::random(&mut test_rng),
+            n,
+            &mut test_rng
+        )
+            .unwrap();
+        // A, S and the L and R of the three rounds of the inner product proof
+        assert_eq!(backend.calls.get(), 8);
+
+        let mut transcript = Transcript::new(b"MsmBackendTest");
+        assert!(proof
+            .verify_single_with_backend(&bp_gens, &pc_gens, &backend, &mut transcript, &value_commitment, n, &mut test_rng)
+            .is_ok());
+        assert_eq!(backend.calls.get(), 9);
+
+        // Proofs created through a custom backend verify with the default one
+        let mut transcript = Transcript::new(b"MsmBackendTest");
+        assert!(proof
+            .verify_single(&bp_gens, &pc_gens, &mut transcript, &value_commitment, n, &mut test_rng)
+            .is_ok());
+    }
+}
@@ -0,0 +1,447 @@
+//! The [Bulletproofs+](https://eprint.iacr.org/2020/735) variant of the
+//! zero knowledge inner product proof.
+
+#![allow(non_snake_case)]
+
+use alloc::vec::Vec;
+
+use core::iter;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use ff::Field;
+use merlin::Transcript;
+
+use crate::errors::ProofError;
+use crate::format::{CanonicalPoints, ProofKind, ProofReader, SerializedSize, HEADER_SIZE};
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::group::{CompressedPoint, PrimeGroup};
+use crate::msm::{CpuMsmBackend, MsmBackend};
+use crate::transcript::TranscriptProtocol;
+use crate::weighted_inner_product_proof::{WeightedInnerProductProof, WipGens, WipWitness};
+use crate::zk_proof::ZkProof;
+
+use rand_core::{CryptoRng, RngCore};
+use serde::de::Visitor;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Proof that the vectors committed in \\(A\\) have the inner product
+/// committed in \\(V\\), as `InnerProductZKProof`, but built on the
+/// weighted inner product argument of Bulletproofs+.
+///
+/// Since the weighted inner product argument is zero knowledge on its
+/// own, the proof does not need the \\(S, T_1, T_2\\) commitments, and
+/// is 3 elements shorter than an `InnerProductZKProof` of the same size.
+/// Both proofs take the same inputs, so a prover can switch between
+/// them, and proofs in the `InnerProductZKProof` format remain
+/// verifiable as before.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InnerProductZKProofPlus<G: PrimeGroup = RistrettoPoint> {
+    /// Commitment to the vectors
+    A: G::Compressed,
+    /// Proof data for the weighted inner-product argument.
+    wip_proof: WeightedInnerProductProof<G>,
+}
+
+impl<G: PrimeGroup> InnerProductZKProofPlus<G> {
+    /// Create a proof that `v` is the inner product of `lhs_ip` and `rhs_ip`,
+    /// with the same arguments as `InnerProductZKProof::prove_single`.
+    pub fn prove_single<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        v: G::Scalar,
+        lhs_ip: &Vec<G::Scalar>,
+        rhs_ip: &Vec<G::Scalar>,
+        v_blinding: G::Scalar,
+        a_blinding: G::Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(InnerProductZKProofPlus<G>, G::Compressed), ProofError> {
+        InnerProductZKProofPlus::prove_single_with_backend(
+            bp_gens,
+            pc_gens,
+            &CpuMsmBackend,
+            transcript,
+            v,
+            lhs_ip,
+            rhs_ip,
+            v_blinding,
+            a_blinding,
+            n,
+            rng,
+        )
+    }
+
+    /// Create a proof as in `prove_single`, computing the multiscalar multiplications with
+    /// the given `MsmBackend`.
+    pub fn prove_single_with_backend<T: RngCore + CryptoRng, B: MsmBackend<G> + ?Sized>(
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        backend: &B,
+        transcript: &mut Transcript,
+        v: G::Scalar,
+        lhs_ip: &Vec<G::Scalar>,
+        rhs_ip: &Vec<G::Scalar>,
+        v_blinding: G::Scalar,
+        a_blinding: G::Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(InnerProductZKProofPlus<G>, G::Compressed), ProofError> {
+        let V = pc_gens.commit(v, v_blinding).compress();
+
+        let G: Vec<G> = bp_gens.G(n, 1).cloned().collect();
+        let H: Vec<G> = bp_gens.H(n, 1).cloned().collect();
+
+        let A: G = backend.multiscalar_mul(
+            &iter::once(&a_blinding).chain(lhs_ip.iter()).chain(rhs_ip.iter()).cloned().collect::<Vec<G::Scalar>>(),
+            &iter::once(&pc_gens.B_blinding).chain(G.iter()).chain(H.iter()).cloned().collect::<Vec<G>>(),
+        );
+
+        transcript.append_point(b"V", &V);
+        transcript.append_point(b"A", &A.compress());
+
+        // The value base is scaled by a challenge, so that A cannot hide a
+        // component along B that cancels out with V.
+        let w: G::Scalar = transcript.challenge_scalar(b"w");
+
+        // A + w * V commits to lhs_ip, rhs_ip and their inner product on w * B,
+        // which is the statement of the weighted inner product argument for y = 1.
+        let wip_proof = WeightedInnerProductProof::create_with_backend(
+            backend,
+            transcript,
+            WipGens { g: pc_gens.B * w, h: pc_gens.B_blinding, G_vec: G, H_vec: H },
+            G::Scalar::ONE,
+            WipWitness { a_vec: lhs_ip.clone(), b_vec: rhs_ip.clone(), alpha: a_blinding + w * v_blinding },
+            rng,
+        );
+
+        Ok((InnerProductZKProofPlus { A: A.compress(), wip_proof }, V))
+    }
+
+    /// Verifies the proof for a given value commitment \\(V\\).
+    pub fn verify_single(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        transcript: &mut Transcript,
+        V: &G::Compressed,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_single_with_backend(bp_gens, pc_gens, &CpuMsmBackend, transcript, V, n)
+    }
+
+    /// Verifies the proof computing the verification equation with the given `MsmBackend`.
+    pub fn verify_single_with_backend<B: MsmBackend<G> + ?Sized>(
+        &self,
+        bp_gens: &BulletproofGens<G>,
+        pc_gens: &PedersenGens<G>,
+        backend: &B,
+        transcript: &mut Transcript,
+        V: &G::Compressed,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        transcript.append_point(b"V", V);
+        transcript.validate_and_append_point(b"A", &self.A)?;
+
+        let w: G::Scalar = transcript.challenge_scalar(b"w");
+
+        let scalars = self.wip_proof.verification_scalars(n, G::Scalar::ONE, transcript)?;
+
+        let mega_check = backend.vartime_optional_multiscalar_mul(
+            &iter::once(scalars.P)
+                .chain(iter::once(scalars.P * w))
+                .chain(scalars.proof_scalars())
+                .chain(iter::once(scalars.g * w))
+                .chain(iter::once(scalars.h))
+                .chain(scalars.G.iter().cloned())
+                .chain(scalars.H.iter().cloned())
+                .collect::<Vec<G::Scalar>>(),
+            &iter::once(self.A.decompress())
+                .chain(iter::once(V.decompress()))
+                .chain(self.wip_proof.proof_points())
+                .chain(iter::once(Some(pc_gens.B)))
+                .chain(iter::once(Some(pc_gens.B_blinding)))
+                .chain(bp_gens.G(n, 1).map(|&x| Some(x)))
+                .chain(bp_gens.H(n, 1).map(|&x| Some(x)))
+                .collect::<Vec<Option<G>>>(),
+        )
+            .ok_or(ProofError::VerificationError)?;
+
+        if bool::from(mega_check.is_identity()) {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Number of points in the verification equation of a proof of size `n`.
+    pub fn verification_size(n: usize) -> usize {
+        // A, V, the L and R vectors, A and B of the argument, B, B_blinding and the G and H vectors
+        2 + 2 * (n.next_power_of_two().trailing_zeros() as usize) + 2 + 2 + 2 * n
+    }
+
+    /// Verify that A corresponds to an expected value of A
+    pub fn verify_expected_A(&self, expected_A: G::Compressed) -> bool {
+        self.A == expected_A
+    }
+
+    /// Serializes the proof into a byte array of \\(2 \lg n + 6\\)
+    /// elements, where \\(n\\) is the size of the vectors. Over
+    /// Ristretto, each element takes 32 bytes.
+    ///
+    /// # Layout
+    ///
+    /// The layout of the proof encoding is:
+    ///
+    /// * the header, see [`ProofKind`],
+    /// * the compressed point \\(A\\),
+    /// * \\(n\\) pairs of compressed points \\(L_0,R_0\dots,L_{n-1},R_{n-1}\\),
+    /// * two compressed points \\(A', B'\\),
+    /// * three scalars \\(r', s', \delta'\\).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.serialized_size());
+        buf.extend_from_slice(&ProofKind::InnerProductZKPlus.header());
+        buf.extend_from_slice(self.A.as_bytes());
+        buf.extend(self.wip_proof.to_bytes_iter());
+        buf
+    }
+
+    /// Returns the size in bytes required to serialize the proof.
+    pub fn serialized_size(&self) -> usize {
+        HEADER_SIZE + G::Compressed::SIZE + self.wip_proof.body_size()
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into an `InnerProductZKProofPlus`.
+    pub fn from_bytes(slice: &[u8]) -> Result<InnerProductZKProofPlus<G>, ProofError> {
+        match ProofKind::InnerProductZKPlus.read_header(slice)? {
+            (1, body) => InnerProductZKProofPlus::read_v1(&mut ProofReader::new(body)),
+            _ => Err(ProofError::FormatError),
+        }
+    }
+
+    /// Reads the proof in the first version of the format, without the header.
+    fn read_v1(reader: &mut ProofReader) -> Result<InnerProductZKProofPlus<G>, ProofError> {
+        Ok(InnerProductZKProofPlus {
+            A: reader.read_point()?,
+            wip_proof: WeightedInnerProductProof::read_v1(reader)?,
+        })
+    }
+
+    /// Deserializes the proof as `from_bytes`, and also checks that all
+    /// the points are canonical encodings of group elements, as
+    /// `InnerProductZKProof::from_bytes_strict`.
+    pub fn from_bytes_strict(slice: &[u8]) -> Result<InnerProductZKProofPlus<G>, ProofError> {
+        let proof = InnerProductZKProofPlus::<G>::from_bytes(slice)?;
+        if !proof.points_are_canonical() {
+            return Err(ProofError::FormatError);
+        }
+        Ok(proof)
+    }
+}
+
+impl<G: PrimeGroup> CanonicalPoints for InnerProductZKProofPlus<G> {
+    fn points_are_canonical(&self) -> bool {
+        self.A.points_are_canonical() && self.wip_proof.points_are_canonical()
+    }
+}
+
+impl<G: PrimeGroup> SerializedSize for InnerProductZKProofPlus<G> {
+    fn serialized_size(&self) -> usize {
+        InnerProductZKProofPlus::serialized_size(self)
+    }
+}
+
+impl<G: PrimeGroup> PartialOrd for InnerProductZKProofPlus<G> {
+    fn partial_cmp(&self, other: &InnerProductZKProofPlus<G>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<G: PrimeGroup> Ord for InnerProductZKProofPlus<G> {
+    fn cmp(&self, other: &InnerProductZKProofPlus<G>) -> Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
+impl<G: PrimeGroup> Hash for InnerProductZKProofPlus<G> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+/// Takes the same parameters, witness and statement as `InnerProductZKProof`.
+impl<G: PrimeGroup> ZkProof for InnerProductZKProofPlus<G> {
+    type Params<'a> = (&'a BulletproofGens<G>, &'a PedersenGens<G>, usize);
+    type Witness<'a> = (G::Scalar, &'a Vec<G::Scalar>, &'a Vec<G::Scalar>, G::Scalar, G::Scalar);
+    type Statement = G::Compressed;
+    type Error = ProofError;
+
+    fn prove<R: RngCore + CryptoRng>(
+        (bp_gens, pc_gens, n): Self::Params<'_>,
+        (v, lhs_ip, rhs_ip, v_blinding, a_blinding): Self::Witness<'_>,
+        transcript: &mut Transcript,
+        rng: &mut R,
+    ) -> Result<(InnerProductZKProofPlus<G>, G::Compressed), ProofError> {
+        InnerProductZKProofPlus::prove_single(bp_gens, pc_gens, transcript, v, lhs_ip, rhs_ip, v_blinding, a_blinding, n, rng)
+    }
+
+    fn verify(
+        &self,
+        (bp_gens, pc_gens, n): Self::Params<'_>,
+        V: &G::Compressed,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_single(bp_gens, pc_gens, transcript, V, n)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        InnerProductZKProofPlus::to_bytes(self)
+    }
+
+    fn from_bytes(slice: &[u8]) -> Result<InnerProductZKProofPlus<G>, ProofError> {
+        InnerProductZKProofPlus::from_bytes(slice)
+    }
+}
+
+impl<G: PrimeGroup> Serialize for InnerProductZKProofPlus<G> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de, G: PrimeGroup> Deserialize<'de> for InnerProductZKProofPlus<G> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+    {
+        struct InnerProductZKProofPlusVisitor<G>(PhantomData<G>);
+
+        impl<'de, G: PrimeGroup> Visitor<'de> for InnerProductZKProofPlusVisitor<G> {
+            type Value = InnerProductZKProofPlus<G>;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                formatter.write_str("a valid InnerProductZKProofPlus")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<InnerProductZKProofPlus<G>, E>
+                where
+                    E: serde::de::Error,
+            {
+                // Using Error::custom requires T: Display, which our error
+                // type only implements when it implements std::error::Error.
+                #[cfg(feature = "std")]
+                    return InnerProductZKProofPlus::from_bytes(v).map_err(serde::de::Error::custom);
+                // In no-std contexts, drop the error message.
+                #[cfg(not(feature = "std"))]
+                    return InnerProductZKProofPlus::from_bytes(v)
+                    .map_err(|_| serde::de::Error::custom("deserialization error"));
+            }
+        }
+
+        deserializer.deserialize_bytes(InnerProductZKProofPlusVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::group::PrimeScalar;
+    use crate::inner_product_proof::inner_product;
+    use crate::ip_zk_proof::InnerProductZKProof;
+    use rand_core::SeedableRng;
+
+    use rand_chacha::ChaChaRng;
+
+    fn single_ip_zk_proof_plus_helper<G: PrimeGroup>(n: usize) {
+        let pc_gens = PedersenGens::<G>::default();
+        let bp_gens = BulletproofGens::<G>::new(128, 1);
+        let mut test_rng = ChaChaRng::from_seed([24u8; 32]);
+
+        let lhs_ip: Vec<G::Scalar> = (0..n).map(|_| G::Scalar::random(&mut test_rng)).collect();
+        let rhs_ip: Vec<G::Scalar> = (0..n).map(|_| G::Scalar::random(&mut test_rng)).collect();
+        let value = inner_product(&lhs_ip, &rhs_ip);
+        let v_blinding = G::Scalar::random(&mut test_rng);
+        let a_blinding = G::Scalar::random(&mut test_rng);
+
+        let mut transcript = Transcript::new(b"InnerProductZKProofPlusTest");
+        let (proof, value_commitment) = InnerProductZKProofPlus::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            value,
+            &lhs_ip,
+            &rhs_ip,
+            v_blinding,
+            a_blinding,
+            n,
+            &mut test_rng,
+        )
+            .unwrap();
+
+        let proof_bytes = bincode::serialize(&proof).unwrap();
+        let proof: InnerProductZKProofPlus<G> = bincode::deserialize(&proof_bytes).unwrap();
+
+        let mut transcript = Transcript::new(b"InnerProductZKProofPlusTest");
+        assert!(proof
+            .verify_single(&bp_gens, &pc_gens, &mut transcript, &value_commitment, n)
+            .is_ok());
+
+        // The same inputs produce an `InnerProductZKProof` with the same A,
+        // which is 3 elements longer.
+        let mut transcript = Transcript::new(b"InnerProductZKProofPlusTest");
+        let (legacy_proof, _) = InnerProductZKProof::prove_single(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            value,
+            &lhs_ip,
+            &rhs_ip,
+            v_blinding,
+            a_blinding,
+            n,
+            &mut test_rng,
+        )
+            .unwrap();
+        assert!(legacy_proof.verify_expected_A(proof.A));
+        assert_eq!(proof.to_bytes().len(), proof.serialized_size());
+        assert_eq!(
+            legacy_proof.to_bytes().len(),
+            proof.to_bytes().len() + G::Compressed::SIZE + 2 * G::Scalar::SIZE,
+        );
+
+        let wrong_commitment = pc_gens.commit(value + G::Scalar::ONE, v_blinding).compress();
+        let mut transcript = Transcript::new(b"InnerProductZKProofPlusTest");
+        assert!(proof
+            .verify_single(&bp_gens, &pc_gens, &mut transcript, &wrong_commitment, n)
+            .is_err());
+    }
+
+    #[test]
+    fn create_and_verify_ip_proof_plus_8() {single_ip_zk_proof_plus_helper::<RistrettoPoint>(8);}
+
+    #[test]
+    fn create_and_verify_ip_proof_plus_64() {single_ip_zk_proof_plus_helper::<RistrettoPoint>(64);}
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn create_and_verify_ip_proof_plus_secp256k1() {single_ip_zk_proof_plus_helper::<k256::ProjectivePoint>(16);}
+
+    #[test]
+    fn verification_size_is_one_point_smaller() {
+        for &n in &[8, 128] {
+            assert_eq!(
+                InnerProductZKProofPlus::<RistrettoPoint>::verification_size(n) + 1,
+                InnerProductZKProof::<RistrettoPoint>::verification_size(n),
+            );
+        }
+    }
+}
@@ -1,27 +1,72 @@
 #![cfg_attr(not(feature = "std"), no_std)]
-#![feature(nll)]
-#![feature(external_doc)]
-#![feature(try_trait)]
+// The proofs take their generators, transcript and randomness as separate arguments, as in
+// the Bulletproofs API they extend, and the protocol sessions move their large states
+// between rounds instead of boxing them.
+#![allow(clippy::too_many_arguments, clippy::large_enum_variant)]
 
 extern crate alloc;
 extern crate serde_derive;
 
+/// Enters a `trace` span named `msm`, closed at the end of the enclosing scope, around the
+/// multiscalar multiplication labelled `check`. Does nothing without the `tracing` feature.
+#[cfg(feature = "tracing")]
+macro_rules! msm_span {
+    ($check:literal $(, $($field:tt)+)?) => {
+        tracing::trace_span!("msm", check = $check $(, $($field)+)?).entered()
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! msm_span {
+    ($check:literal $(, $($field:tt)+)?) => {
+        ()
+    };
+}
+
 mod util;
 
 mod errors;
+mod format;
 mod generators;
+mod group;
 mod inner_product_proof;
 mod ip_zk_proof;
+mod msm;
 mod range_proof;
+mod rewind;
+#[cfg(feature = "yoloproofs")]
+pub mod r1cs;
 mod transcript;
+#[cfg(feature = "transcript-debug")]
+mod transcript_debug;
+mod weighted_inner_product_proof;
+mod zk_proof;
 
 pub use crate::range_proof::dealer;
 pub use crate::range_proof::messages;
 pub use crate::range_proof::party;
+pub use crate::range_proof::session;
 
-pub use crate::errors::ProofError;
+pub use crate::errors::{MPCError, ProofError};
+pub use crate::format::{write_section, CanonicalPoints, ProofKind, ProofReader, SerializedSize, FORMAT_VERSION};
 pub use crate::generators::{BulletproofGens, BulletproofGensShare, PedersenGens};
-pub use crate::ip_zk_proof::InnerProductZKProof;
-pub use crate::inner_product_proof::{InnerProductProof, inner_product, };
-pub use crate::util::exp_iter;
-pub use crate::range_proof::RangeProof;
+pub use crate::group::{CompressedPoint, PrimeGroup, PrimeScalar};
+#[cfg(feature = "bls12_381")]
+pub use crate::group::bls12_381_g1::G1Compressed;
+#[cfg(feature = "secp256k1")]
+pub use crate::group::secp256k1::Secp256k1Compressed;
+pub use crate::ip_zk_proof::{InnerProductZKProof, InnerProductZKProofPlus};
+pub use crate::msm::{
+    CpuMsmBackend, MsmAlgorithm, MsmBackend, PIPPENGER_THRESHOLD, PRECOMPUTATION_THRESHOLD,
+};
+#[cfg(not(feature = "prover-only"))]
+pub use crate::msm::PrecomputedGens;
+pub use crate::inner_product_proof::{folding_scalars, inner_product, InnerProductProof, MAX_IPP_ROUNDS};
+pub use crate::util::{exp_iter, read32, try_read32, Poly2, ScalarExp, VecPoly1};
+pub use crate::range_proof::{RangeProof, RangeProofPlus};
+pub use crate::rewind::RewindKey;
+pub use crate::weighted_inner_product_proof::{WeightedInnerProductProof, WipGens, WipWitness};
+pub use crate::transcript::TranscriptProtocol;
+#[cfg(feature = "transcript-debug")]
+pub use crate::transcript_debug::{record_transcripts, TranscriptEvent, TranscriptLog, TranscriptOp};
+pub use crate::zk_proof::ZkProof;
@@ -0,0 +1,282 @@
+#![allow(non_snake_case)]
+//! Selection of the multiscalar multiplication algorithm used in the verification
+//! equations.
+//!
+//! The mega-check of the inner product proof goes from ~30 points for \\(n = 8\\) to
+//! ~280 points for \\(n = 128\\). Straus' method is the fastest for the small end of that
+//! range and Pippenger's for the large one, while a precomputed table for the static
+//! generators only pays off when it is reused over a large batch of verifications.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::iter;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+#[cfg(not(feature = "prover-only"))]
+use curve25519_dalek::ristretto::VartimeRistrettoPrecomputation;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{Identity, MultiscalarMul, VartimeMultiscalarMul};
+#[cfg(not(feature = "prover-only"))]
+use curve25519_dalek::traits::VartimePrecomputedMultiscalarMul;
+
+#[cfg(not(feature = "prover-only"))]
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::group::PrimeGroup;
+#[cfg(not(feature = "prover-only"))]
+use crate::ip_zk_proof::InnerProductZKProof;
+
+/// Number of points from which Pippenger's method outperforms Straus' method. This is the
+/// same threshold `curve25519-dalek` uses internally.
+pub const PIPPENGER_THRESHOLD: usize = 190;
+
+/// Number of verifications sharing the same generators from which building a
+/// [`PrecomputedGens`] table pays off. Measured with the serial backend, where the table
+/// costs roughly as much as a hundred Straus multiscalar multiplications of the same size.
+pub const PRECOMPUTATION_THRESHOLD: usize = 128;
+
+/// Algorithm used to compute a variable-time multiscalar multiplication.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MsmAlgorithm {
+    /// Straus' interleaved window method.
+    Straus,
+    /// Pippenger's bucket method.
+    Pippenger,
+    /// Straus' method with precomputed tables for the static generators.
+    Precomputed,
+}
+
+impl MsmAlgorithm {
+    /// Selects the algorithm for a multiscalar multiplication over `size` points, which is
+    /// computed `batch_size` times over the same static generators.
+    pub fn select(size: usize, batch_size: usize) -> MsmAlgorithm {
+        if size >= PIPPENGER_THRESHOLD {
+            MsmAlgorithm::Pippenger
+        } else if batch_size >= PRECOMPUTATION_THRESHOLD {
+            MsmAlgorithm::Precomputed
+        } else {
+            MsmAlgorithm::Straus
+        }
+    }
+}
+
+/// Multiscalar multiplication engine used by the proving and verification equations over
+/// the group `G`.
+///
+/// The default [`CpuMsmBackend`] runs on the implementation of the group, which for Ristretto
+/// is `curve25519-dalek`. Integrators with a GPU or a hardware accelerator can implement this
+/// trait and pass it to the `_with_backend` variants of the prover and verifier, e.g. for bulk
+/// verification workloads. Points and scalars are handed over as contiguous slices of equal
+/// length, so that they can be copied to the device in one go.
+pub trait MsmBackend<G: PrimeGroup = RistrettoPoint> {
+    /// Computes \\(\sum_i s_i P_i\\) in constant time. This is used by the prover over
+    /// secret scalars.
+    fn multiscalar_mul(&self, scalars: &[G::Scalar], points: &[G]) -> G;
+
+    /// Computes \\(\sum_i s_i P_i\\) in variable time, returning `None` if any of the
+    /// points is `None`. This is used by the verifier, where points come from decompressing
+    /// the proof.
+    fn vartime_optional_multiscalar_mul(
+        &self,
+        scalars: &[G::Scalar],
+        points: &[Option<G>],
+    ) -> Option<G>;
+
+    /// Computes \\(\sum_i s_i P_i\\) in variable time.
+    fn vartime_multiscalar_mul(&self, scalars: &[G::Scalar], points: &[G]) -> G {
+        let points: Vec<Option<G>> = points.iter().map(|&P| Some(P)).collect();
+        self.vartime_optional_multiscalar_mul(scalars, &points)
+            .expect("all points are valid")
+    }
+}
+
+/// Computes \\(\sum_i s_i P_i\\) over secret scalars of the prover, e.g. the folded vectors of
+/// an inner product argument. With the `constant-time` feature this is the constant time
+/// multiplication of `backend`, and otherwise the variable time one, which is faster but
+/// leaks the scalars through timing.
+pub(crate) fn prover_multiscalar_mul<G: PrimeGroup, B: MsmBackend<G> + ?Sized>(
+    backend: &B,
+    scalars: &[G::Scalar],
+    points: &[G],
+) -> G {
+    if cfg!(feature = "constant-time") {
+        backend.multiscalar_mul(scalars, points)
+    } else {
+        backend.vartime_multiscalar_mul(scalars, points)
+    }
+}
+
+/// Default backend, computing on the CPU with the multiscalar multiplication of the group.
+/// For Ristretto this is the algorithm picked by [`MsmAlgorithm::select`] for a single
+/// multiplication.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuMsmBackend;
+
+impl<G: PrimeGroup> MsmBackend<G> for CpuMsmBackend {
+    fn multiscalar_mul(&self, scalars: &[G::Scalar], points: &[G]) -> G {
+        let _span = msm_span!("cpu", size = scalars.len());
+        G::multiscalar_mul(scalars, points)
+    }
+
+    fn vartime_optional_multiscalar_mul(
+        &self,
+        scalars: &[G::Scalar],
+        points: &[Option<G>],
+    ) -> Option<G> {
+        let _span = msm_span!("cpu", size = scalars.len());
+        G::optional_vartime_multiscalar_mul(scalars, points)
+    }
+}
+
+/// Forces the given algorithm on the CPU. `curve25519-dalek` only exposes its own size-based
+/// choice, so Straus is forced by splitting the input in chunks below
+/// [`PIPPENGER_THRESHOLD`], and Pippenger by padding it with the identity up to it.
+/// `Precomputed` has no static generators to work with here, and falls back to
+/// [`MsmAlgorithm::select`] for a single multiplication.
+impl MsmBackend for MsmAlgorithm {
+    fn multiscalar_mul(&self, scalars: &[Scalar], points: &[RistrettoPoint]) -> RistrettoPoint {
+        <RistrettoPoint as MultiscalarMul>::multiscalar_mul(scalars, points)
+    }
+
+    fn vartime_optional_multiscalar_mul(
+        &self,
+        scalars: &[Scalar],
+        points: &[Option<RistrettoPoint>],
+    ) -> Option<RistrettoPoint> {
+        match self {
+            MsmAlgorithm::Straus => scalars
+                .chunks(PIPPENGER_THRESHOLD - 1)
+                .zip(points.chunks(PIPPENGER_THRESHOLD - 1))
+                .map(|(s, p)| RistrettoPoint::optional_multiscalar_mul(s, p.iter().cloned()))
+                .try_fold(RistrettoPoint::identity(), |acc, chunk| Some(acc + chunk?)),
+            MsmAlgorithm::Pippenger => {
+                let padding = PIPPENGER_THRESHOLD.saturating_sub(scalars.len());
+                RistrettoPoint::optional_multiscalar_mul(
+                    scalars.iter().cloned().chain(iter::repeat_n(Scalar::ZERO, padding)),
+                    points
+                        .iter()
+                        .cloned()
+                        .chain(iter::repeat_n(Some(RistrettoPoint::identity()), padding)),
+                )
+            }
+            MsmAlgorithm::Precomputed => MsmAlgorithm::select(scalars.len(), 1)
+                .vartime_optional_multiscalar_mul(scalars, points),
+        }
+    }
+}
+
+/// Precomputed tables for the static generators of the inner product proof of size `n`,
+/// this is \\(\tilde{B}, B, \mathbf{G}, \mathbf{H}\\), in this order.
+///
+/// Building the table is expensive, so it should only be used to verify a large batch of
+/// proofs over the same generators (see [`MsmAlgorithm::select`]). Not available with the
+/// `prover-only` feature.
+#[cfg(not(feature = "prover-only"))]
+pub struct PrecomputedGens {
+    n: usize,
+    table: VartimeRistrettoPrecomputation,
+}
+
+#[cfg(not(feature = "prover-only"))]
+impl PrecomputedGens {
+    /// Precomputes the tables for proofs of size `n`.
+    pub fn new(bp_gens: &BulletproofGens, pc_gens: &PedersenGens, n: usize) -> Self {
+        let table = VartimeRistrettoPrecomputation::new(
+            iter::once(&pc_gens.B_blinding)
+                .chain(iter::once(&pc_gens.B))
+                .chain(bp_gens.G(n, 1))
+                .chain(bp_gens.H(n, 1)),
+        );
+        PrecomputedGens { n, table }
+    }
+
+    /// Precomputes the tables only if [`MsmAlgorithm::select`] considers it worth it for
+    /// verifying `batch_size` proofs of size `n`.
+    pub fn for_batch(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        n: usize,
+        batch_size: usize,
+    ) -> Option<Self> {
+        let size = InnerProductZKProof::<RistrettoPoint>::verification_size(n);
+        match MsmAlgorithm::select(size, batch_size) {
+            MsmAlgorithm::Precomputed => Some(PrecomputedGens::new(bp_gens, pc_gens, n)),
+            _ => None,
+        }
+    }
+
+    /// Size of the proofs these tables were computed for.
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    pub(crate) fn optional_mixed_multiscalar_mul<I, J, K>(
+        &self,
+        static_scalars: I,
+        dynamic_scalars: J,
+        dynamic_points: K,
+    ) -> Option<RistrettoPoint>
+    where
+        I: IntoIterator<Item = Scalar>,
+        J: IntoIterator<Item = Scalar>,
+        K: IntoIterator<Item = Option<RistrettoPoint>>,
+    {
+        self.table
+            .optional_mixed_multiscalar_mul(static_scalars, dynamic_scalars, dynamic_points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn selection_follows_size_and_batch() {
+        assert_eq!(MsmAlgorithm::select(30, 1), MsmAlgorithm::Straus);
+        assert_eq!(MsmAlgorithm::select(280, 1), MsmAlgorithm::Pippenger);
+        assert_eq!(MsmAlgorithm::select(30, PRECOMPUTATION_THRESHOLD), MsmAlgorithm::Precomputed);
+        assert_eq!(MsmAlgorithm::select(280, PRECOMPUTATION_THRESHOLD), MsmAlgorithm::Pippenger);
+    }
+
+    #[cfg(not(feature = "prover-only"))]
+    #[test]
+    fn precomputation_follows_batch() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        assert!(PrecomputedGens::for_batch(&bp_gens, &pc_gens, 8, 12).is_none());
+        assert!(PrecomputedGens::for_batch(&bp_gens, &pc_gens, 8, PRECOMPUTATION_THRESHOLD).is_some());
+    }
+
+    #[test]
+    fn all_algorithms_agree() {
+        let mut rng = ChaChaRng::from_seed([24u8; 32]);
+
+        for &size in &[3, 189, 190, 400] {
+            let scalars: Vec<Scalar> = (0..size).map(|_| Scalar::random(&mut rng)).collect();
+            let points: Vec<RistrettoPoint> =
+                (0..size).map(|_| RistrettoPoint::random(&mut rng)).collect();
+            let expected = <RistrettoPoint as VartimeMultiscalarMul>::vartime_multiscalar_mul(&scalars, &points);
+
+            for &algorithm in &[MsmAlgorithm::Straus, MsmAlgorithm::Pippenger, MsmAlgorithm::Precomputed] {
+                assert_eq!(algorithm.vartime_multiscalar_mul(&scalars, &points), expected);
+            }
+            assert_eq!(CpuMsmBackend.vartime_multiscalar_mul(&scalars, &points), expected);
+            assert_eq!(CpuMsmBackend.multiscalar_mul(&scalars, &points), expected);
+        }
+    }
+
+    #[test]
+    fn invalid_points_propagate() {
+        let scalars: Vec<Scalar> = iter::repeat_n(Scalar::ONE, 300).collect();
+        let points: Vec<Option<RistrettoPoint>> = iter::repeat_n(Some(RistrettoPoint::identity()), 299)
+            .chain(iter::once(None))
+            .collect();
+        for &algorithm in &[MsmAlgorithm::Straus, MsmAlgorithm::Pippenger] {
+            assert!(algorithm.vartime_optional_multiscalar_mul(&scalars, &points).is_none());
+        }
+        assert!(CpuMsmBackend.vartime_optional_multiscalar_mul(&scalars, &points).is_none());
+    }
+}
@@ -0,0 +1,372 @@
+//! Reusable gadgets for the constraint systems.
+//!
+//! Each gadget adds its constraints to any [`ConstraintSystem`], so the
+//! same code builds the statement for the [`Prover`](::r1cs::Prover) and
+//! the [`Verifier`](::r1cs::Verifier). The assignments are only needed by
+//! the prover, and the verifier passes `None` for them.
+//!
+//! Values are integers embedded in the scalar field, with negative values
+//! represented by their additive inverse. Gadgets that range check a value
+//! take its bitsize `n`, which must be at most \\(64\\).
+
+use alloc::string::ToString;
+
+use curve25519_dalek::scalar::Scalar;
+use subtle::{Choice, ConditionallyNegatable};
+
+use super::{ConstraintSystem, LinearCombination, R1CSError, Variable};
+
+/// Returns the scalar representing the signed integer `value`.
+pub fn signed_scalar(value: i64) -> Scalar {
+    // The sign is applied without branching on it, as the value may be secret
+    let mut scalar = Scalar::from(value.unsigned_abs());
+    scalar.conditional_negate(Choice::from((value as u64 >> 63) as u8));
+    scalar
+}
+
+fn check_bitsize(n: usize) -> Result<(), R1CSError> {
+    if n > 64 {
+        return Err(R1CSError::GadgetError {
+            description: "Invalid bitsize, must have n <= 64.".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Allocates a variable constrained to be a bit, \\(b \cdot (1 - b) = 0\\).
+///
+/// Returns the variable holding the bit.
+pub fn boolean<CS: ConstraintSystem>(
+    cs: &mut CS,
+    assignment: Option<bool>,
+) -> Result<Variable, R1CSError> {
+    let (a, b, o) = cs.allocate_multiplier(assignment.map(|bit| {
+        let bit = Scalar::from(bit as u64);
+        (Scalar::ONE - bit, bit)
+    }))?;
+    // Enforce a * b = 0, so one of (a,b) is zero
+    cs.constrain(o.into());
+    // Enforce that a = 1 - b, so they both are 1 or 0.
+    cs.constrain(a + (b - Scalar::ONE));
+    Ok(b)
+}
+
+/// Constrains `v` to be in \\([0, 2^n)\\), by decomposing
+/// `v_assignment` in \\(n\\) bits.
+pub fn range<CS: ConstraintSystem>(
+    cs: &mut CS,
+    mut v: LinearCombination,
+    v_assignment: Option<u64>,
+    n: usize,
+) -> Result<(), R1CSError> {
+    check_bitsize(n)?;
+
+    let mut exp_2 = Scalar::ONE;
+    for i in 0..n {
+        let b = boolean(cs, v_assignment.map(|q| (q >> i) & 1 == 1))?;
+        // Add `-b_i*2^i` to the linear combination
+        // in order to form the following constraint by the end of the loop:
+        // v = Sum(b_i * 2^i, i = 0..n-1)
+        v = v - b * exp_2;
+        exp_2 = exp_2 + exp_2;
+    }
+
+    // Enforce that v = Sum(b_i * 2^i, i = 0..n-1)
+    cs.constrain(v);
+    Ok(())
+}
+
+/// Constrains `a` \\(\leq\\) `b`, where `difference_assignment` is
+/// \\(b - a\\), and the difference fits in \\(n\\) bits.
+pub fn less_or_equal<CS: ConstraintSystem>(
+    cs: &mut CS,
+    a: LinearCombination,
+    b: LinearCombination,
+    difference_assignment: Option<u64>,
+    n: usize,
+) -> Result<(), R1CSError> {
+    range(cs, b - a, difference_assignment, n)
+}
+
+/// Constrains `a` \\(<\\) `b`, where `difference_assignment` is
+/// \\(b - a - 1\\), and the difference fits in \\(n\\) bits.
+pub fn less_than<CS: ConstraintSystem>(
+    cs: &mut CS,
+    a: LinearCombination,
+    b: LinearCombination,
+    difference_assignment: Option<u64>,
+    n: usize,
+) -> Result<(), R1CSError> {
+    range(cs, b - a - Scalar::ONE, difference_assignment, n)
+}
+
+/// Computes the absolute value of `v`, whose assignment is the signed
+/// `v_assignment`, and constrains it to \\(n\\) bits.
+///
+/// Returns the variable holding \\(|v|\\).
+pub fn absolute_value<CS: ConstraintSystem>(
+    cs: &mut CS,
+    v: LinearCombination,
+    v_assignment: Option<i64>,
+    n: usize,
+) -> Result<Variable, R1CSError> {
+    // The sign is 1 for negative values, so that |v| = v * (1 - 2 * sign)
+    let sign = boolean(cs, v_assignment.map(|v| v < 0))?;
+    let (_, _, abs) = cs.multiply(
+        v,
+        LinearCombination::from(Scalar::ONE) - sign * Scalar::from(2u64),
+    );
+
+    // With the wrong sign, abs would be the additive inverse of a small value
+    range(cs, abs.into(), v_assignment.map(|v| v.unsigned_abs()), n)?;
+    Ok(abs)
+}
+
+/// Multiplies two fixed-point values with `precision` fractional bits,
+/// and truncates the product back to `precision` fractional bits.
+///
+/// The product must fit in \\(n\\) bits after the truncation, which
+/// requires the assignments to be non-negative.
+///
+/// Returns the variable holding the truncated product.
+pub fn fixed_point_multiply<CS: ConstraintSystem>(
+    cs: &mut CS,
+    a: LinearCombination,
+    b: LinearCombination,
+    a_assignment: Option<u64>,
+    b_assignment: Option<u64>,
+    precision: usize,
+    n: usize,
+) -> Result<Variable, R1CSError> {
+    check_bitsize(precision)?;
+
+    let (_, _, product) = cs.multiply(a, b);
+
+    // product = result * 2^precision + remainder, with remainder < 2^precision
+    let assignments = a_assignment.zip(b_assignment).map(|(a, b)| {
+        let product = a as u128 * b as u128;
+        (
+            (product >> precision) as u64,
+            (product & ((1u128 << precision) - 1)) as u64,
+        )
+    });
+    let result = cs.allocate(assignments.map(|(result, _)| Scalar::from(result)))?;
+    let remainder = cs.allocate(assignments.map(|(_, remainder)| Scalar::from(remainder)))?;
+
+    let scale = Scalar::from(1u128 << precision);
+    cs.constrain(product - result * scale - remainder);
+    range(cs, remainder.into(), assignments.map(|(_, remainder)| remainder), precision)?;
+    range(cs, result.into(), assignments.map(|(result, _)| result), n)?;
+    Ok(result)
+}
+
+/// Constrains the sum of `values` to equal `total`.
+pub fn sum<CS: ConstraintSystem>(
+    cs: &mut CS,
+    values: &[LinearCombination],
+    total: LinearCombination,
+) {
+    let sum = values
+        .iter()
+        .fold(LinearCombination::default(), |acc, value| acc + value.clone());
+    cs.constrain(sum - total);
+}
+
+/// Computes the inner product of `a` and `b`, with one multiplier per element.
+///
+/// Returns the linear combination of the products, or an error if the
+/// lengths of the vectors differ.
+pub fn inner_product<CS: ConstraintSystem>(
+    cs: &mut CS,
+    a: &[LinearCombination],
+    b: &[LinearCombination],
+) -> Result<LinearCombination, R1CSError> {
+    if a.len() != b.len() {
+        return Err(R1CSError::GadgetError {
+            description: "Inner product of vectors of different length.".to_string(),
+        });
+    }
+
+    Ok(a
+        .iter()
+        .zip(b.iter())
+        .map(|(a_i, b_i)| cs.multiply(a_i.clone(), b_i.clone()).2)
+        .fold(LinearCombination::default(), |acc, o| acc + o))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec::Vec;
+
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    use crate::generators::{BulletproofGens, PedersenGens};
+    use crate::r1cs::{Prover, Verifier};
+
+    /// Commits to `values`, and proves and verifies the statement built by
+    /// `prover_gadget` and `verifier_gadget` over the committed variables.
+    fn prove_and_verify<P, V>(
+        values: &[Scalar],
+        prover_gadget: P,
+        verifier_gadget: V,
+    ) -> Result<(), R1CSError>
+    where
+        P: FnOnce(&mut Prover<'_, Transcript>, &[Variable]) -> Result<(), R1CSError>,
+        V: FnOnce(&mut Verifier<Transcript>, &[Variable]) -> Result<(), R1CSError>,
+    {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(256, 1);
+        let mut rng = ChaChaRng::from_seed([24u8; 32]);
+
+        let mut prover = Prover::new(&pc_gens, Transcript::new(b"R1CSGadgetsTest"));
+        let (commitments, vars): (Vec<_>, Vec<_>) = values
+            .iter()
+            .map(|v| prover.commit(*v, Scalar::random(&mut rng)))
+            .unzip();
+        prover_gadget(&mut prover, &vars)?;
+        let proof = prover.prove_with_rng(&bp_gens, &mut rng)?;
+
+        let mut verifier = Verifier::new(Transcript::new(b"R1CSGadgetsTest"));
+        let vars: Vec<_> = commitments.iter().map(|c| verifier.commit(*c)).collect();
+        verifier_gadget(&mut verifier, &vars)?;
+        verifier.verify_with_rng(&proof, &pc_gens, &bp_gens, &mut rng)
+    }
+
+    fn range_helper(v: u64, n: usize) -> Result<(), R1CSError> {
+        prove_and_verify(
+            &[Scalar::from(v)],
+            |cs, vars| range(cs, vars[0].into(), Some(v), n),
+            |cs, vars| range(cs, vars[0].into(), None, n),
+        )
+    }
+
+    #[test]
+    fn range_gadget() {
+        assert!(range_helper(0, 8).is_ok());
+        assert!(range_helper(255, 8).is_ok());
+        assert!(range_helper(256, 8).is_err());
+        assert!(range_helper(u64::MAX, 64).is_ok());
+        assert!(range_helper(1, 65).is_err());
+    }
+
+    #[test]
+    fn comparison_gadgets() {
+        let values = [Scalar::from(3u64), Scalar::from(7u64)];
+        assert!(prove_and_verify(
+            &values,
+            |cs, vars| less_or_equal(cs, vars[0].into(), vars[1].into(), Some(4), 8),
+            |cs, vars| less_or_equal(cs, vars[0].into(), vars[1].into(), None, 8),
+        )
+        .is_ok());
+        assert!(prove_and_verify(
+            &values,
+            |cs, vars| less_than(cs, vars[0].into(), vars[1].into(), Some(3), 8),
+            |cs, vars| less_than(cs, vars[0].into(), vars[1].into(), None, 8),
+        )
+        .is_ok());
+
+        // 7 <= 3 does not hold, whatever the claimed difference
+        assert!(prove_and_verify(
+            &values,
+            |cs, vars| less_or_equal(cs, vars[1].into(), vars[0].into(), Some(4), 8),
+            |cs, vars| less_or_equal(cs, vars[1].into(), vars[0].into(), None, 8),
+        )
+        .is_err());
+        // 7 < 7 does not hold either
+        assert!(prove_and_verify(
+            &values,
+            |cs, vars| less_than(cs, vars[1].into(), vars[1].into(), Some(0), 8),
+            |cs, vars| less_than(cs, vars[1].into(), vars[1].into(), None, 8),
+        )
+        .is_err());
+    }
+
+    fn absolute_value_helper(v: i64, expected: u64) -> Result<(), R1CSError> {
+        prove_and_verify(
+            &[signed_scalar(v), Scalar::from(expected)],
+            |cs, vars| {
+                let abs = absolute_value(cs, vars[0].into(), Some(v), 16)?;
+                cs.constrain(abs - vars[1]);
+                Ok(())
+            },
+            |cs, vars| {
+                let abs = absolute_value(cs, vars[0].into(), None, 16)?;
+                cs.constrain(abs - vars[1]);
+                Ok(())
+            },
+        )
+    }
+
+    #[test]
+    fn absolute_value_gadget() {
+        assert!(absolute_value_helper(-5, 5).is_ok());
+        assert!(absolute_value_helper(5, 5).is_ok());
+        assert!(absolute_value_helper(0, 0).is_ok());
+        assert!(absolute_value_helper(-5, 6).is_err());
+    }
+
+    fn fixed_point_helper(a: u64, b: u64, expected: u64) -> Result<(), R1CSError> {
+        // 8 fractional bits
+        prove_and_verify(
+            &[Scalar::from(a), Scalar::from(b), Scalar::from(expected)],
+            |cs, vars| {
+                let result =
+                    fixed_point_multiply(cs, vars[0].into(), vars[1].into(), Some(a), Some(b), 8, 16)?;
+                cs.constrain(result - vars[2]);
+                Ok(())
+            },
+            |cs, vars| {
+                let result =
+                    fixed_point_multiply(cs, vars[0].into(), vars[1].into(), None, None, 8, 16)?;
+                cs.constrain(result - vars[2]);
+                Ok(())
+            },
+        )
+    }
+
+    #[test]
+    fn fixed_point_multiply_gadget() {
+        // 1.5 * 2.25 = 3.375
+        assert!(fixed_point_helper(384, 576, 864).is_ok());
+        // 1.5 * 1.00390625 = 1.505859375, truncated to 1.5
+        assert!(fixed_point_helper(384, 257, 385).is_ok());
+        assert!(fixed_point_helper(384, 576, 865).is_err());
+    }
+
+    /// Constrains the first three values to add up to 6, and their inner
+    /// product with the next three values to be the last value.
+    fn sum_and_inner_product_statement<CS: ConstraintSystem>(
+        cs: &mut CS,
+        vars: &[Variable],
+    ) -> Result<(), R1CSError> {
+        let lcs: Vec<LinearCombination> = vars.iter().map(|v| (*v).into()).collect();
+        sum(cs, &lcs[0..3], Scalar::from(6u64).into());
+        let ip = inner_product(cs, &lcs[0..3], &lcs[3..6])?;
+        cs.constrain(ip - vars[6]);
+        Ok(())
+    }
+
+    #[test]
+    fn sum_and_inner_product_gadgets() {
+        let statement = |values: [u64; 7]| {
+            let values: Vec<Scalar> = values.iter().map(|v| Scalar::from(*v)).collect();
+            prove_and_verify(
+                &values,
+                |cs, vars| sum_and_inner_product_statement(cs, vars),
+                sum_and_inner_product_statement,
+            )
+        };
+        assert!(statement([1, 2, 3, 4, 5, 6, 32]).is_ok());
+        assert!(statement([1, 2, 3, 4, 5, 6, 33]).is_err());
+        assert!(statement([1, 2, 4, 4, 5, 6, 38]).is_err());
+
+        let mut transcript = Transcript::new(b"R1CSGadgetsTest");
+        let mut verifier = Verifier::new(&mut transcript);
+        let a = [verifier.commit(Default::default()).into()];
+        assert!(inner_product(&mut verifier, &a, &[]).is_err());
+    }
+}
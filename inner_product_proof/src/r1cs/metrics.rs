@@ -0,0 +1,14 @@
+/// A struct that contains metrics about a constraint system.
+///
+/// See [`ConstraintSystem::metrics`](::r1cs::ConstraintSystem::metrics).
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    /// Number of multiplications
+    pub multipliers: usize,
+    /// Number of constraints
+    pub constraints: usize,
+    /// Number of linear constraints
+    pub phase_one_constraints: usize,
+    /// Number of linear constraints
+    pub phase_two_constraints: usize,
+}
@@ -0,0 +1,212 @@
+//! Proofs of statements expressed as rank-1 constraint systems (R1CS),
+//! following the constraint system proofs of the Bulletproofs paper.
+//!
+//! A statement is written once as a gadget over the [`ConstraintSystem`]
+//! trait, and is then run by a [`Prover`] to produce an [`R1CSProof`],
+//! and by a [`Verifier`] to check it. High-level variables are given as
+//! Pedersen commitments, so that the same commitments can be shared with
+//! the other proofs of this crate.
+//!
+//! As the range proof, the constraint system proofs are only implemented
+//! over ristretto255.
+
+mod constraint_system;
+pub mod gadgets;
+mod linear_combination;
+mod metrics;
+mod proof;
+mod prover;
+mod verifier;
+
+pub use self::constraint_system::{
+    ConstraintSystem, RandomizableConstraintSystem, RandomizedConstraintSystem,
+};
+pub use self::linear_combination::{LinearCombination, Variable};
+pub use self::metrics::Metrics;
+pub use self::proof::R1CSProof;
+pub use self::prover::{Prover, RandomizingProver};
+pub use self::verifier::{RandomizingVerifier, Verifier};
+
+pub use crate::errors::R1CSError;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec::Vec;
+
+    use curve25519_dalek::ristretto::CompressedRistretto;
+    use curve25519_dalek::scalar::Scalar;
+    use merlin::Transcript;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    use crate::format::HEADER_SIZE;
+    use crate::generators::{BulletproofGens, PedersenGens};
+
+    /// Constrains `x * y = product`, for a public `product`.
+    fn product_gadget<CS: ConstraintSystem>(
+        cs: &mut CS,
+        x: Variable,
+        y: Variable,
+        product: Scalar,
+    ) {
+        let (_, _, o) = cs.multiply(x.into(), y.into());
+        cs.constrain(o - product);
+    }
+
+    /// Constrains `{a, b}` to be a permutation of `{x, y}`, by checking that
+    /// `(a - z)(b - z) = (x - z)(y - z)` for a challenge `z`.
+    fn shuffle_gadget<CS: RandomizableConstraintSystem>(
+        cs: &mut CS,
+        x: Variable,
+        y: Variable,
+        a: Variable,
+        b: Variable,
+    ) -> Result<(), R1CSError> {
+        cs.specify_randomized_constraints(move |cs| {
+            let z = cs.challenge_scalar(b"shuffle challenge");
+            let (_, _, input) = cs.multiply(x - z, y - z);
+            let (_, _, output) = cs.multiply(a - z, b - z);
+            cs.constrain(input - output);
+            Ok(())
+        })
+    }
+
+    fn product_proof(
+        x: u64,
+        y: u64,
+        product: u64,
+    ) -> Result<(R1CSProof, Vec<CompressedRistretto>), R1CSError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let mut rng = ChaChaRng::from_seed([24u8; 32]);
+
+        let mut transcript = Transcript::new(b"R1CSProductTest");
+        let mut prover = Prover::new(&pc_gens, &mut transcript);
+
+        let (x_commitment, x_var) = prover.commit(Scalar::from(x), Scalar::random(&mut rng));
+        let (y_commitment, y_var) = prover.commit(Scalar::from(y), Scalar::random(&mut rng));
+        product_gadget(&mut prover, x_var, y_var, Scalar::from(product));
+
+        let proof = prover.prove_with_rng(&bp_gens, &mut rng)?;
+        Ok((proof, vec![x_commitment, y_commitment]))
+    }
+
+    fn verify_product_proof(
+        proof: &R1CSProof,
+        commitments: &[CompressedRistretto],
+        product: u64,
+    ) -> Result<(), R1CSError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let mut rng = ChaChaRng::from_seed([42u8; 32]);
+
+        let mut transcript = Transcript::new(b"R1CSProductTest");
+        let mut verifier = Verifier::new(&mut transcript);
+
+        let x_var = verifier.commit(commitments[0]);
+        let y_var = verifier.commit(commitments[1]);
+        product_gadget(&mut verifier, x_var, y_var, Scalar::from(product));
+
+        verifier.verify_with_rng(proof, &pc_gens, &bp_gens, &mut rng)
+    }
+
+    #[test]
+    fn product_gadget_works() {
+        let (proof, commitments) = product_proof(3, 7, 21).unwrap();
+        assert!(verify_product_proof(&proof, &commitments, 21).is_ok());
+        assert!(verify_product_proof(&proof, &commitments, 22).is_err());
+
+        // A proof of a false statement does not verify either
+        let (proof, commitments) = product_proof(3, 7, 22).unwrap();
+        assert!(verify_product_proof(&proof, &commitments, 22).is_err());
+    }
+
+    #[test]
+    fn proof_serialization_roundtrip() {
+        let (proof, commitments) = product_proof(3, 7, 21).unwrap();
+
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), proof.serialized_size());
+        let parsed = R1CSProof::from_bytes(&bytes).unwrap();
+        assert!(verify_product_proof(&parsed, &commitments, 21).is_ok());
+
+        let parsed: R1CSProof = bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+        assert_eq!(parsed.to_bytes(), bytes);
+
+        assert_eq!(R1CSProof::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(), R1CSError::FormatError);
+        assert_eq!(R1CSProof::from_bytes(&[]).unwrap_err(), R1CSError::FormatError);
+
+        // T_1 is not a valid point, which only the strict parser checks
+        assert_eq!(R1CSProof::from_bytes_strict(&bytes).unwrap().to_bytes(), bytes);
+        let mut invalid = bytes.clone();
+        invalid[HEADER_SIZE + 1 + 3 * 32..HEADER_SIZE + 1 + 4 * 32].copy_from_slice(&[0xff; 32]);
+        assert!(R1CSProof::from_bytes(&invalid).is_ok());
+        assert_eq!(R1CSProof::from_bytes_strict(&invalid).unwrap_err(), R1CSError::FormatError);
+    }
+
+    fn shuffle_helper(input: (u64, u64), output: (u64, u64)) -> Result<(), R1CSError> {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let mut rng = ChaChaRng::from_seed([24u8; 32]);
+
+        let (proof, commitments) = {
+            let mut transcript = Transcript::new(b"R1CSShuffleTest");
+            let mut prover = Prover::new(&pc_gens, &mut transcript);
+
+            let (commitments, vars): (Vec<_>, Vec<_>) = [input.0, input.1, output.0, output.1]
+                .iter()
+                .map(|v| prover.commit(Scalar::from(*v), Scalar::random(&mut rng)))
+                .unzip();
+            shuffle_gadget(&mut prover, vars[0], vars[1], vars[2], vars[3])?;
+            assert_eq!(prover.metrics().phase_two_constraints, 1);
+
+            (prover.prove_with_rng(&bp_gens, &mut rng)?, commitments)
+        };
+
+        // The randomized constraints need the second phase commitments
+        assert_eq!(proof.to_bytes()[HEADER_SIZE], 1);
+
+        let mut transcript = Transcript::new(b"R1CSShuffleTest");
+        let mut verifier = Verifier::new(&mut transcript);
+        let vars: Vec<_> = commitments.iter().map(|c| verifier.commit(*c)).collect();
+        shuffle_gadget(&mut verifier, vars[0], vars[1], vars[2], vars[3])?;
+
+        verifier.verify_with_rng(&proof, &pc_gens, &bp_gens, &mut rng)
+    }
+
+    #[test]
+    fn shuffle_gadget_works() {
+        assert!(shuffle_helper((3, 7), (7, 3)).is_ok());
+        assert!(shuffle_helper((3, 7), (3, 7)).is_ok());
+        assert!(shuffle_helper((3, 7), (3, 8)).is_err());
+    }
+
+    #[test]
+    fn allocate_requires_assignment_for_prover() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let mut rng = ChaChaRng::from_seed([24u8; 32]);
+
+        let mut transcript = Transcript::new(b"R1CSAllocateTest");
+        let mut prover = Prover::new(&pc_gens, &mut transcript);
+        assert_eq!(prover.allocate(None).unwrap_err(), R1CSError::MissingAssignment);
+
+        // An odd number of allocations leaves a half-assigned multiplier
+        let a = prover.allocate(Some(Scalar::from(5u64))).unwrap();
+        let b = prover.allocate(Some(Scalar::from(6u64))).unwrap();
+        let c = prover.allocate(Some(Scalar::from(11u64))).unwrap();
+        prover.constrain(a + b - c);
+        assert_eq!(prover.metrics().multipliers, 2);
+        let proof = prover.prove_with_rng(&bp_gens, &mut rng).unwrap();
+
+        let mut transcript = Transcript::new(b"R1CSAllocateTest");
+        let mut verifier = Verifier::new(&mut transcript);
+        let a = verifier.allocate(None).unwrap();
+        let b = verifier.allocate(None).unwrap();
+        let c = verifier.allocate(None).unwrap();
+        verifier.constrain(a + b - c);
+        assert!(verifier.verify_with_rng(&proof, &pc_gens, &bp_gens, &mut rng).is_ok());
+    }
+}
@@ -0,0 +1,269 @@
+#![allow(non_snake_case)]
+//! Definition of the proof struct.
+
+use alloc::vec::Vec;
+
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{Identity, IsIdentity};
+
+use crate::errors::{ProofError, R1CSError};
+use crate::format::{CanonicalPoints, ProofKind, ProofReader, SerializedSize, HEADER_SIZE};
+use crate::inner_product_proof::InnerProductProof;
+
+use serde::de::Visitor;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+const ONE_PHASE_COMMITMENTS: u8 = 0;
+const TWO_PHASE_COMMITMENTS: u8 = 1;
+
+/// A proof of some statement specified by a
+/// [`ConstraintSystem`](::r1cs::ConstraintSystem).
+///
+/// Statements are specified by writing gadget functions which add
+/// constraints to a [`ConstraintSystem`](::r1cs::ConstraintSystem)
+/// implementation.  To construct an [`R1CSProof`], a prover constructs
+/// a [`Prover`](::r1cs::Prover), then passes it to gadget
+/// functions to build the constraint system, then consumes the
+/// constraint system using
+/// [`Prover::prove`](::r1cs::Prover::prove) to produce an
+/// [`R1CSProof`].  To verify an [`R1CSProof`], a verifier constructs a
+/// [`Verifier`](::r1cs::Verifier), then passes it to the same
+/// gadget functions to (re)build the constraint system, then consumes
+/// the constraint system using
+/// [`Verifier::verify`](::r1cs::Verifier::verify) to verify the
+/// proof.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct R1CSProof {
+    /// Commitment to the values of input wires in the first phase.
+    pub(super) A_I1: CompressedRistretto,
+    /// Commitment to the values of output wires in the first phase.
+    pub(super) A_O1: CompressedRistretto,
+    /// Commitment to the blinding factors in the first phase.
+    pub(super) S1: CompressedRistretto,
+    /// Commitment to the values of input wires in the second phase.
+    pub(super) A_I2: CompressedRistretto,
+    /// Commitment to the values of output wires in the second phase.
+    pub(super) A_O2: CompressedRistretto,
+    /// Commitment to the blinding factors in the second phase.
+    pub(super) S2: CompressedRistretto,
+    /// Commitment to the \\(t_1\\) coefficient of \\( t(x) \\)
+    pub(super) T_1: CompressedRistretto,
+    /// Commitment to the \\(t_3\\) coefficient of \\( t(x) \\)
+    pub(super) T_3: CompressedRistretto,
+    /// Commitment to the \\(t_4\\) coefficient of \\( t(x) \\)
+    pub(super) T_4: CompressedRistretto,
+    /// Commitment to the \\(t_5\\) coefficient of \\( t(x) \\)
+    pub(super) T_5: CompressedRistretto,
+    /// Commitment to the \\(t_6\\) coefficient of \\( t(x) \\)
+    pub(super) T_6: CompressedRistretto,
+    /// Evaluation of the polynomial \\(t(x)\\) at the challenge point \\(x\\)
+    pub(super) t_x: Scalar,
+    /// Blinding factor for the synthetic commitment to \\( t(x) \\)
+    pub(super) t_x_blinding: Scalar,
+    /// Blinding factor for the synthetic commitment to the
+    /// inner-product arguments
+    pub(super) e_blinding: Scalar,
+    /// Proof data for the inner-product argument.
+    pub(super) ipp_proof: InnerProductProof,
+}
+
+impl R1CSProof {
+    /// Serializes the proof into a byte array of the header, 1 phase byte and \\((13 or 16) + 2k\\)
+    /// 32-byte elements, where \\(k=\lceil \log_2(n) \rceil\\) and \\(n\\) is the number of
+    /// multiplication gates.
+    ///
+    /// # Layout
+    ///
+    /// The layout of the r1cs proof encoding is:
+    /// * the header, see [`ProofKind`],
+    /// * 1 phase byte indicating whether the proof contains second-phase commitments or not,
+    /// * 8 or 11 compressed Ristretto points \\(A_{I1},A_{O1},S_1,(A_{I2},A_{O2},S_2),T_1,...,T_6\\)
+    ///   (\\(T_2\\) is skipped),
+    /// * three scalars \\(t_x, \tilde{t}_x, \tilde{e}\\),
+    /// * \\(k\\) pairs of compressed Ristretto points \\(L_0,R_0\dots,L_{k-1},R_{k-1}\\),
+    /// * two scalars \\(a, b\\).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.serialized_size());
+        buf.extend_from_slice(&ProofKind::R1CS.header());
+        if self.missing_phase2_commitments() {
+            buf.push(ONE_PHASE_COMMITMENTS);
+            buf.extend_from_slice(self.A_I1.as_bytes());
+            buf.extend_from_slice(self.A_O1.as_bytes());
+            buf.extend_from_slice(self.S1.as_bytes());
+        } else {
+            buf.push(TWO_PHASE_COMMITMENTS);
+            buf.extend_from_slice(self.A_I1.as_bytes());
+            buf.extend_from_slice(self.A_O1.as_bytes());
+            buf.extend_from_slice(self.S1.as_bytes());
+            buf.extend_from_slice(self.A_I2.as_bytes());
+            buf.extend_from_slice(self.A_O2.as_bytes());
+            buf.extend_from_slice(self.S2.as_bytes());
+        }
+        buf.extend_from_slice(self.T_1.as_bytes());
+        buf.extend_from_slice(self.T_3.as_bytes());
+        buf.extend_from_slice(self.T_4.as_bytes());
+        buf.extend_from_slice(self.T_5.as_bytes());
+        buf.extend_from_slice(self.T_6.as_bytes());
+        buf.extend_from_slice(self.t_x.as_bytes());
+        buf.extend_from_slice(self.t_x_blinding.as_bytes());
+        buf.extend_from_slice(self.e_blinding.as_bytes());
+        buf.extend(self.ipp_proof.to_bytes_iter());
+        buf
+    }
+
+    /// Returns the size in bytes required to serialize the `R1CSProof`.
+    pub fn serialized_size(&self) -> usize {
+        // header + phase tag + (11 or 14) elements + the ipp
+        let elements = if self.missing_phase2_commitments() {
+            11
+        } else {
+            14
+        };
+        HEADER_SIZE + 1 + elements * 32 + self.ipp_proof.body_size()
+    }
+
+    fn missing_phase2_commitments(&self) -> bool {
+        self.A_I2.is_identity() && self.A_O2.is_identity() && self.S2.is_identity()
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a `R1CSProof`.
+    pub fn from_bytes(slice: &[u8]) -> Result<R1CSProof, R1CSError> {
+        match ProofKind::R1CS.read_header(slice).map_err(|_| R1CSError::FormatError)? {
+            (1, body) => R1CSProof::read_v1(&mut ProofReader::new(body)).map_err(|_| R1CSError::FormatError),
+            _ => Err(R1CSError::FormatError),
+        }
+    }
+
+    /// Reads the proof in the first version of the format, without the header.
+    fn read_v1(reader: &mut ProofReader) -> Result<R1CSProof, ProofError> {
+        let phase = reader.read_u8()?;
+        if phase != ONE_PHASE_COMMITMENTS && phase != TWO_PHASE_COMMITMENTS {
+            return Err(ProofError::FormatError);
+        }
+
+        let A_I1 = reader.read_point()?;
+        let A_O1 = reader.read_point()?;
+        let S1 = reader.read_point()?;
+        let (A_I2, A_O2, S2) = if phase == ONE_PHASE_COMMITMENTS {
+            (
+                CompressedRistretto::identity(),
+                CompressedRistretto::identity(),
+                CompressedRistretto::identity(),
+            )
+        } else {
+            (reader.read_point()?, reader.read_point()?, reader.read_point()?)
+        };
+
+        Ok(R1CSProof {
+            A_I1,
+            A_O1,
+            S1,
+            A_I2,
+            A_O2,
+            S2,
+            T_1: reader.read_point()?,
+            T_3: reader.read_point()?,
+            T_4: reader.read_point()?,
+            T_5: reader.read_point()?,
+            T_6: reader.read_point()?,
+            t_x: reader.read_scalar()?,
+            t_x_blinding: reader.read_scalar()?,
+            e_blinding: reader.read_scalar()?,
+            ipp_proof: InnerProductProof::read_v1(reader)?,
+        })
+    }
+
+    /// Deserializes the proof as `from_bytes`, and also checks that all
+    /// the points are canonical encodings of Ristretto points, instead of
+    /// leaving it to the verifier.
+    pub fn from_bytes_strict(slice: &[u8]) -> Result<R1CSProof, R1CSError> {
+        let proof = R1CSProof::from_bytes(slice)?;
+        if !proof.points_are_canonical() {
+            return Err(R1CSError::FormatError);
+        }
+        Ok(proof)
+    }
+}
+
+impl CanonicalPoints for R1CSProof {
+    fn points_are_canonical(&self) -> bool {
+        [
+            self.A_I1, self.A_O1, self.S1, self.A_I2, self.A_O2, self.S2,
+            self.T_1, self.T_3, self.T_4, self.T_5, self.T_6,
+        ]
+        .points_are_canonical()
+            && self.ipp_proof.points_are_canonical()
+    }
+}
+
+impl SerializedSize for R1CSProof {
+    fn serialized_size(&self) -> usize {
+        R1CSProof::serialized_size(self)
+    }
+}
+
+impl PartialOrd for R1CSProof {
+    fn partial_cmp(&self, other: &R1CSProof) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for R1CSProof {
+    fn cmp(&self, other: &R1CSProof) -> Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
+impl Hash for R1CSProof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+impl Serialize for R1CSProof {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for R1CSProof {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct R1CSProofVisitor;
+
+        impl<'de> Visitor<'de> for R1CSProofVisitor {
+            type Value = R1CSProof;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                formatter.write_str("a valid R1CSProof")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<R1CSProof, E>
+            where
+                E: serde::de::Error,
+            {
+                // Using Error::custom requires T: Display, which our error
+                // type only implements when it implements std::error::Error.
+                #[cfg(feature = "std")]
+                return R1CSProof::from_bytes(v).map_err(serde::de::Error::custom);
+                // In no-std contexts, drop the error message.
+                #[cfg(not(feature = "std"))]
+                return R1CSProof::from_bytes(v)
+                    .map_err(|_| serde::de::Error::custom("deserialization error"));
+            }
+        }
+
+        deserializer.deserialize_bytes(R1CSProofVisitor)
+    }
+}
@@ -4,7 +4,6 @@
 //! For more explanation of how the `dealer`, `party`, and `messages` modules orchestrate the protocol execution, see
 //! [the API for the aggregated multiparty computation protocol](../aggregation/index.html#api-for-the-aggregated-multiparty-computation-protocol).
 
-use core::iter;
 
 extern crate alloc;
 
@@ -34,6 +33,7 @@ pub struct Dealer {}
 
 impl Dealer {
     /// Creates a new dealer coordinating `m` parties proving `n`-bit ranges.
+    #[allow(clippy::new_ret_no_self)]
     pub fn new<'a, 'b>(
         bp_gens: &'b BulletproofGens,
         pc_gens: &'b PedersenGens,
@@ -115,8 +115,8 @@ impl<'a, 'b> DealerAwaitingBitCommitments<'a, 'b> {
         let S: RistrettoPoint = bit_commitments.iter().map(|vc| vc.S_j).sum();
         self.transcript.append_point(b"S", &S.compress());
 
-        let y = self.transcript.challenge_scalar(b"y");
-        let z = self.transcript.challenge_scalar(b"z");
+        let y: Scalar = self.transcript.challenge_scalar(b"y");
+        let z: Scalar = self.transcript.challenge_scalar(b"z");
         let bit_challenge = BitChallenge { y, z };
 
         Ok((
@@ -172,7 +172,7 @@ impl<'a, 'b> DealerAwaitingPolyCommitments<'a, 'b> {
         self.transcript.append_point(b"T_1", &T_1.compress());
         self.transcript.append_point(b"T_2", &T_2.compress());
 
-        let x = self.transcript.challenge_scalar(b"x");
+        let x: Scalar = self.transcript.challenge_scalar(b"x");
         let poly_challenge = PolyChallenge { x };
 
         Ok((
@@ -232,13 +232,13 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
         let mut bad_shares = Vec::<usize>::new(); // no allocations until we append
         for (j, share) in proof_shares.iter().enumerate() {
             share
-                .check_size(self.n, &self.bp_gens, j)
+                .check_size(self.n, self.bp_gens, j)
                 .unwrap_or_else(|_| {
                     bad_shares.push(j);
                 });
         }
 
-        if bad_shares.len() > 0 {
+        if !bad_shares.is_empty() {
             return Err(MPCError::MalformedProofShares { bad_shares });
         }
 
@@ -252,10 +252,10 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
         self.transcript.append_scalar(b"e_blinding", &e_blinding);
 
         // Get a challenge value to combine statements for the IPP
-        let w = self.transcript.challenge_scalar(b"w");
+        let w: Scalar = self.transcript.challenge_scalar(b"w");
         let Q = w * self.pc_gens.B;
 
-        let G_factors: Vec<Scalar> = iter::repeat(Scalar::one()).take(self.n * self.m).collect();
+        let G_factors: Vec<Scalar> = core::iter::repeat_n(Scalar::ONE, self.n * self.m).collect();
         let H_factors: Vec<Scalar> = util::exp_iter(self.bit_challenge.y.invert())
             .take(self.n * self.m)
             .collect();
@@ -335,10 +335,10 @@ impl<'a, 'b> DealerAwaitingProofShares<'a, 'b> {
         } else {
             // Proof verification failed. Now audit the parties:
             let mut bad_shares = Vec::new();
-            for j in 0..self.m {
-                match proof_shares[j].audit_share(
-                    &self.bp_gens,
-                    &self.pc_gens,
+            for (j, share) in proof_shares.iter().enumerate().take(self.m) {
+                match share.audit_share(
+                    self.bp_gens,
+                    self.pc_gens,
                     j,
                     &self.bit_commitments[j],
                     &self.bit_challenge,
@@ -6,9 +6,12 @@ extern crate rand;
 
 #[cfg(feature = "std")]
 use self::rand::thread_rng;
+use alloc::vec;
 use alloc::vec::Vec;
 
 use core::iter;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
 
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
@@ -16,10 +19,14 @@ use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
 use merlin::Transcript;
 
 use crate::errors::ProofError;
+use crate::format::{CanonicalPoints, ProofKind, ProofReader, SerializedSize, HEADER_SIZE};
 use crate::generators::{BulletproofGens, PedersenGens};
 use crate::inner_product_proof::InnerProductProof;
+use crate::rewind::RewindKey;
 use crate::transcript::TranscriptProtocol;
 use crate::util;
+#[cfg(feature = "std")]
+use crate::zk_proof::ZkProof;
 
 use rand_core::{CryptoRng, RngCore};
 use serde::de::Visitor;
@@ -30,6 +37,11 @@ use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
 pub mod dealer;
 pub mod messages;
 pub mod party;
+pub mod session;
+
+mod plus;
+
+pub use self::plus::RangeProofPlus;
 
 /// The `RangeProof` struct represents a proof that one or more values
 /// are in a range.
@@ -54,7 +66,18 @@ pub mod party;
 /// protocol locally.  That API is exposed in the [`aggregation`](::range_proof_mpc)
 /// module and can be used to perform online aggregation between
 /// parties without revealing secret values to each other.
-#[derive(Clone, Debug)]
+///
+/// # Compatibility
+///
+/// The generators, the transcript and the verification equation are the
+/// ones of [dalek-cryptography/bulletproofs](https://github.com/dalek-cryptography/bulletproofs),
+/// so a range proof created by either crate verifies with the other, for
+/// the same `BulletproofGens`, `PedersenGens` and transcript. No flag is
+/// needed to select the upstream transcript: the labels and domain
+/// separators of `RangeProof` must not diverge from it. The encodings
+/// only differ by the header of `to_bytes`, see `to_upstream_bytes` and
+/// `from_upstream_bytes`.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct RangeProof {
     /// Commitment to the bits of the value
     A: CompressedRistretto,
@@ -309,6 +332,127 @@ impl RangeProof {
         )
     }
 
+    /// Create a rangeproof of `v` as `prove_single_with_rng`, from which the
+    /// holders of `rewind_key` can recover `v` and `v_blinding` with `rewind`.
+    ///
+    /// The blindings of \\(A, S, T_1, T_2\\) are derived from the key and
+    /// the commitment, so each commitment must be proven at most once with
+    /// the same key, see [`RewindKey`].
+    pub fn prove_single_rewindable_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        n: usize,
+        rewind_key: &RewindKey,
+        rng: &mut T,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        use self::dealer::*;
+        use self::party::*;
+
+        let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, 1)?;
+        let party = Party::new(bp_gens, pc_gens, v, *v_blinding, n)?;
+        let nonces = rewind_key.nonces(&pc_gens.commit(Scalar::from(v), *v_blinding).compress());
+
+        let (party, bit_commitment) = party.assign_position_with_blindings(
+            0,
+            nonces.alpha + Scalar::from(v),
+            nonces.rho,
+            rng,
+        )?;
+        let value_commitment = bit_commitment.V_j;
+
+        let (dealer, bit_challenge) = dealer.receive_bit_commitments(vec![bit_commitment])?;
+        let (party, poly_commitment) =
+            party.apply_challenge_with_blindings(&bit_challenge, nonces.tau_1, nonces.tau_2);
+        let (dealer, poly_challenge) = dealer.receive_poly_commitments(vec![poly_commitment])?;
+        let proof_share = party.apply_challenge(&poly_challenge)?;
+        let proof = dealer.receive_trusted_shares(&[proof_share])?;
+
+        Ok((proof, value_commitment))
+    }
+
+    /// Create a rewindable rangeproof.
+    /// This is a convenience wrapper around [`RangeProof::prove_single_rewindable_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_single_rewindable(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        n: usize,
+        rewind_key: &RewindKey,
+    ) -> Result<(RangeProof, CompressedRistretto), ProofError> {
+        RangeProof::prove_single_rewindable_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            v,
+            v_blinding,
+            n,
+            rewind_key,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Recovers the value and the blinding factor committed in \\(V\\) from a
+    /// proof created with `prove_single_rewindable_with_rng` and the same key.
+    ///
+    /// The transcript must have the same initial state as the one of the
+    /// prover. Returns an error if the proof was not created with this key,
+    /// as the recovered opening would not match \\(V\\). The proof itself is
+    /// not verified, which is done with `verify_single`.
+    pub fn rewind(
+        &self,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+        n: usize,
+        rewind_key: &RewindKey,
+    ) -> Result<(u64, Scalar), ProofError> {
+        if !(n == 8 || n == 16 || n == 32 || n == 64) {
+            return Err(ProofError::InvalidBitsize);
+        }
+
+        // Replay the transcript until the challenge x, as the verifier
+        transcript.rangeproof_domain_sep(n as u64, 1);
+        transcript.append_point(b"V", V);
+        transcript.validate_and_append_point(b"A", &self.A)?;
+        transcript.validate_and_append_point(b"S", &self.S)?;
+
+        let _y: Scalar = transcript.challenge_scalar(b"y");
+        let z: Scalar = transcript.challenge_scalar(b"z");
+
+        transcript.validate_and_append_point(b"T_1", &self.T_1)?;
+        transcript.validate_and_append_point(b"T_2", &self.T_2)?;
+
+        let x: Scalar = transcript.challenge_scalar(b"x");
+
+        let nonces = rewind_key.nonces::<Scalar, _>(V);
+
+        // e_blinding = alpha + rho * x, where the value was added to alpha
+        let value = self.e_blinding - nonces.rho * x - nonces.alpha;
+        // t_x_blinding = z^2 * v_blinding + tau_1 * x + tau_2 * x^2
+        let v_blinding = (self.t_x_blinding - nonces.tau_1 * x - nonces.tau_2 * x * x)
+            * (z * z).invert();
+
+        let bytes = value.as_bytes();
+        if bytes[8..].iter().any(|&b| b != 0) {
+            return Err(ProofError::VerificationError);
+        }
+        let mut v_bytes = [0u8; 8];
+        v_bytes.copy_from_slice(&bytes[..8]);
+        let v = u64::from_le_bytes(v_bytes);
+
+        if (n < 64 && v >> n != 0) || pc_gens.commit(value, v_blinding).compress() != *V {
+            return Err(ProofError::VerificationError);
+        }
+        Ok((v, v_blinding))
+    }
+
     /// Verifies a rangeproof for a given value commitment \\(V\\).
     ///
     /// This is a convenience wrapper around `verify_multiple` for the `m=1` case.
@@ -375,21 +519,21 @@ impl RangeProof {
         transcript.validate_and_append_point(b"A", &self.A)?;
         transcript.validate_and_append_point(b"S", &self.S)?;
 
-        let y = transcript.challenge_scalar(b"y");
-        let z = transcript.challenge_scalar(b"z");
+        let y: Scalar = transcript.challenge_scalar(b"y");
+        let z: Scalar = transcript.challenge_scalar(b"z");
         let zz = z * z;
         let minus_z = -z;
 
         transcript.validate_and_append_point(b"T_1", &self.T_1)?;
         transcript.validate_and_append_point(b"T_2", &self.T_2)?;
 
-        let x = transcript.challenge_scalar(b"x");
+        let x: Scalar = transcript.challenge_scalar(b"x");
 
         transcript.append_scalar(b"t_x", &self.t_x);
         transcript.append_scalar(b"t_x_blinding", &self.t_x_blinding);
         transcript.append_scalar(b"e_blinding", &self.e_blinding);
 
-        let w = transcript.challenge_scalar(b"w");
+        let w: Scalar = transcript.challenge_scalar(b"w");
 
         // Challenge value for batching statements to be verified
         let c = Scalar::random(rng);
@@ -417,8 +561,9 @@ impl RangeProof {
         let value_commitment_scalars = util::exp_iter(z).take(m).map(|z_exp| c * zz * z_exp);
         let basepoint_scalar = w * (self.t_x - a * b) + c * (delta(n, m, &y, &z) - self.t_x);
 
+        let _span = msm_span!("range_proof", n, m);
         let mega_check = RistrettoPoint::optional_multiscalar_mul(
-            iter::once(Scalar::one())
+            iter::once(Scalar::ONE)
                 .chain(iter::once(x))
                 .chain(iter::once(c * x))
                 .chain(iter::once(c * x * x))
@@ -441,7 +586,7 @@ impl RangeProof {
                 .chain(bp_gens.H(n, m).map(|&x| Some(x)))
                 .chain(value_commitments.iter().map(|V| V.decompress())),
         )
-        .ok_or_else(|| ProofError::VerificationError)?;
+        .ok_or(ProofError::VerificationError)?;
 
         if mega_check.is_identity() {
             Ok(())
@@ -479,13 +624,31 @@ impl RangeProof {
     ///
     /// The layout of the range proof encoding is:
     ///
+    /// * the header, see [`ProofKind`],
     /// * four compressed Ristretto points \\(A,S,T_1,T_2\\),
     /// * three scalars \\(t_x, \tilde{t}_x, \tilde{e}\\),
     /// * \\(n\\) pairs of compressed Ristretto points \\(L_0,R_0\dots,L_{n-1},R_{n-1}\\),
     /// * two scalars \\(a, b\\).
     pub fn to_bytes(&self) -> Vec<u8> {
-        // 7 elements: points A, S, T1, T2, scalars tx, tx_bl, e_bl.
-        let mut buf = Vec::with_capacity(7 * 32 + self.ipp_proof.serialized_size());
+        let mut buf = Vec::with_capacity(self.serialized_size());
+        buf.extend_from_slice(&ProofKind::Range.header());
+        self.write_body(&mut buf);
+        buf
+    }
+
+    /// Serializes the proof in the encoding of the upstream
+    /// [bulletproofs](https://github.com/dalek-cryptography/bulletproofs)
+    /// crate, i.e. the layout of `to_bytes` without the header. The proof
+    /// verifies with the upstream crate, see the compatibility notes of
+    /// [`RangeProof`].
+    pub fn to_upstream_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.serialized_size() - HEADER_SIZE);
+        self.write_body(&mut buf);
+        buf
+    }
+
+    /// Appends the encoding of the proof without the header to `buf`.
+    fn write_body(&self, buf: &mut Vec<u8>) {
         buf.extend_from_slice(self.A.as_bytes());
         buf.extend_from_slice(self.S.as_bytes());
         buf.extend_from_slice(self.T_1.as_bytes());
@@ -494,46 +657,127 @@ impl RangeProof {
         buf.extend_from_slice(self.t_x_blinding.as_bytes());
         buf.extend_from_slice(self.e_blinding.as_bytes());
         buf.extend(self.ipp_proof.to_bytes_iter());
-        buf
+    }
+
+    /// Returns the size in bytes required to serialize the proof, see `to_bytes`.
+    pub fn serialized_size(&self) -> usize {
+        // 7 elements: points A, S, T1, T2, scalars tx, tx_bl, e_bl.
+        HEADER_SIZE + 7 * 32 + self.ipp_proof.body_size()
     }
 
     /// Deserializes the proof from a byte slice.
     ///
     /// Returns an error if the byte slice cannot be parsed into a `RangeProof`.
     pub fn from_bytes(slice: &[u8]) -> Result<RangeProof, ProofError> {
-        if slice.len() % 32 != 0 {
-            return Err(ProofError::FormatError);
+        match ProofKind::Range.read_header(slice)? {
+            (1, body) => RangeProof::read_v1(&mut ProofReader::new(body)),
+            _ => Err(ProofError::FormatError),
         }
-        if slice.len() < 7 * 32 {
+    }
+
+    /// Deserializes a proof from the encoding of the upstream
+    /// [bulletproofs](https://github.com/dalek-cryptography/bulletproofs)
+    /// crate, e.g. as created by systems that already hold standard
+    /// bulletproof range proofs, which can then be verified as any other
+    /// `RangeProof` without proving again.
+    ///
+    /// The upstream encoding has no header, so this returns a
+    /// `FormatError` for the encodings of `to_bytes`, as for any slice
+    /// that is not an upstream encoding.
+    pub fn from_upstream_bytes(slice: &[u8]) -> Result<RangeProof, ProofError> {
+        RangeProof::read_v1(&mut ProofReader::new(slice))
+    }
+
+    /// Reads the proof in the first version of the format, without the header.
+    fn read_v1(reader: &mut ProofReader) -> Result<RangeProof, ProofError> {
+        Ok(RangeProof {
+            A: reader.read_point()?,
+            S: reader.read_point()?,
+            T_1: reader.read_point()?,
+            T_2: reader.read_point()?,
+            t_x: reader.read_scalar()?,
+            t_x_blinding: reader.read_scalar()?,
+            e_blinding: reader.read_scalar()?,
+            ipp_proof: InnerProductProof::read_v1(reader)?,
+        })
+    }
+
+    /// Deserializes the proof as `from_bytes`, and also checks that all
+    /// the points are canonical encodings of Ristretto points, instead of
+    /// leaving it to the verifier.
+    pub fn from_bytes_strict(slice: &[u8]) -> Result<RangeProof, ProofError> {
+        let proof = RangeProof::from_bytes(slice)?;
+        if !proof.points_are_canonical() {
             return Err(ProofError::FormatError);
         }
+        Ok(proof)
+    }
+}
 
-        use crate::util::read32;
+impl CanonicalPoints for RangeProof {
+    fn points_are_canonical(&self) -> bool {
+        [self.A, self.S, self.T_1, self.T_2].points_are_canonical() && self.ipp_proof.points_are_canonical()
+    }
+}
 
-        let A = CompressedRistretto(read32(&slice[0 * 32..]));
-        let S = CompressedRistretto(read32(&slice[1 * 32..]));
-        let T_1 = CompressedRistretto(read32(&slice[2 * 32..]));
-        let T_2 = CompressedRistretto(read32(&slice[3 * 32..]));
+impl SerializedSize for RangeProof {
+    fn serialized_size(&self) -> usize {
+        RangeProof::serialized_size(self)
+    }
+}
 
-        let t_x = Scalar::from_canonical_bytes(read32(&slice[4 * 32..]))
-            .ok_or(ProofError::FormatError)?;
-        let t_x_blinding = Scalar::from_canonical_bytes(read32(&slice[5 * 32..]))
-            .ok_or(ProofError::FormatError)?;
-        let e_blinding = Scalar::from_canonical_bytes(read32(&slice[6 * 32..]))
-            .ok_or(ProofError::FormatError)?;
+impl PartialOrd for RangeProof {
+    fn partial_cmp(&self, other: &RangeProof) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-        let ipp_proof = InnerProductProof::from_bytes(&slice[7 * 32..])?;
+impl Ord for RangeProof {
+    fn cmp(&self, other: &RangeProof) -> Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
 
-        Ok(RangeProof {
-            A,
-            S,
-            T_1,
-            T_2,
-            t_x,
-            t_x_blinding,
-            e_blinding,
-            ipp_proof,
-        })
+impl Hash for RangeProof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+/// The parameters are the generators and the bitsize \\(n\\), the witness
+/// the values and their blinding factors, and the statement the
+/// commitments to the values. The proof is verified with a `thread_rng`.
+#[cfg(feature = "std")]
+impl ZkProof for RangeProof {
+    type Params<'a> = (&'a BulletproofGens, &'a PedersenGens, usize);
+    type Witness<'a> = (&'a [u64], &'a [Scalar]);
+    type Statement = Vec<CompressedRistretto>;
+    type Error = ProofError;
+
+    fn prove<R: RngCore + CryptoRng>(
+        (bp_gens, pc_gens, n): Self::Params<'_>,
+        (values, blindings): Self::Witness<'_>,
+        transcript: &mut Transcript,
+        rng: &mut R,
+    ) -> Result<(RangeProof, Vec<CompressedRistretto>), ProofError> {
+        RangeProof::prove_multiple_with_rng(bp_gens, pc_gens, transcript, values, blindings, n, rng)
+    }
+
+    fn verify(
+        &self,
+        (bp_gens, pc_gens, n): Self::Params<'_>,
+        value_commitments: &Vec<CompressedRistretto>,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple(bp_gens, pc_gens, transcript, value_commitments, n)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        RangeProof::to_bytes(self)
+    }
+
+    fn from_bytes(slice: &[u8]) -> Result<RangeProof, ProofError> {
+        RangeProof::from_bytes(slice)
     }
 }
 
@@ -610,13 +854,13 @@ mod tests {
         // code copied from previous implementation
         let z2 = z * z;
         let z3 = z2 * z;
-        let mut power_g = Scalar::zero();
-        let mut exp_y = Scalar::one(); // start at y^0 = 1
-        let mut exp_2 = Scalar::one(); // start at 2^0 = 1
+        let mut power_g = Scalar::ZERO;
+        let mut exp_y = Scalar::ONE; // start at y^0 = 1
+        let mut exp_2 = Scalar::ONE; // start at 2^0 = 1
         for _ in 0..n {
             power_g += (z - z2) * exp_y - z3 * exp_2;
 
-            exp_y = exp_y * y; // y^i -> y^(i+1)
+            exp_y *= y; // y^i -> y^(i+1)
             exp_2 = exp_2 + exp_2; // 2^i -> 2^(i+1)
         }
 
@@ -649,7 +893,7 @@ mod tests {
 
             // 0. Create witness data
             let (min, max) = (0u64, ((1u128 << n) - 1) as u64);
-            let values: Vec<u64> = (0..m).map(|_| rng.gen_range(min, max)).collect();
+            let values: Vec<u64> = (0..m).map(|_| rng.gen_range(min..max)).collect();
             let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
 
             // 1. Create the proof
@@ -796,6 +1040,195 @@ mod tests {
         }
     }
 
+    #[test]
+    fn strict_deserialization_rejects_invalid_points() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut rng = rand::thread_rng();
+
+        let mut transcript = Transcript::new(b"StrictDeserializationTest");
+        let (proof, _) = RangeProof::prove_single_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            1037578891,
+            &Scalar::random(&mut rng),
+            32,
+            &mut rng,
+        )
+        .unwrap();
+
+        let bytes = proof.to_bytes();
+        assert_eq!(RangeProof::from_bytes_strict(&bytes).unwrap().to_bytes(), bytes);
+
+        let mut invalid = bytes.clone();
+        invalid[HEADER_SIZE + 3 * 32..HEADER_SIZE + 4 * 32].copy_from_slice(&[0xff; 32]);
+        assert!(RangeProof::from_bytes(&invalid).is_ok());
+        assert_eq!(RangeProof::from_bytes_strict(&invalid).unwrap_err(), ProofError::FormatError);
+    }
+
+    #[test]
+    fn serialization_header() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut rng = rand::thread_rng();
+
+        let mut transcript = Transcript::new(b"SerializationHeaderTest");
+        let (proof, _) = RangeProof::prove_single_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            1037578891,
+            &Scalar::random(&mut rng),
+            32,
+            &mut rng,
+        )
+        .unwrap();
+
+        let bytes = proof.to_bytes();
+        assert_eq!(ProofKind::of(&bytes), Some((crate::FORMAT_VERSION, ProofKind::Range)));
+
+        // The encoding of a range proof is not parsed as another kind of proof
+        assert_eq!(RangeProofPlus::from_bytes(&bytes).unwrap_err(), ProofError::FormatError);
+        assert_eq!(InnerProductProof::<RistrettoPoint>::from_bytes(&bytes).unwrap_err(), ProofError::FormatError);
+
+        // Nor are the encodings of future versions
+        let mut future = bytes.clone();
+        future[0] += 1;
+        assert_eq!(RangeProof::from_bytes(&future).unwrap_err(), ProofError::FormatError);
+        assert_eq!(RangeProof::from_bytes(&bytes[HEADER_SIZE..]).unwrap_err(), ProofError::FormatError);
+    }
+
+    #[test]
+    fn upstream_bulletproofs_compatibility() {
+        let bp_gens = BulletproofGens::new(64, 2);
+        let pc_gens = PedersenGens::default();
+        let upstream_bp_gens = bulletproofs::BulletproofGens::new(64, 2);
+        let upstream_pc_gens = bulletproofs::PedersenGens::default();
+        let mut rng = rand::thread_rng();
+        let values = [1037578891u64, 7];
+        let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+
+        // Our proofs verify with the upstream crate
+        let (proof, commitments) = RangeProof::prove_multiple(
+            &bp_gens, &pc_gens, &mut Transcript::new(b"UpstreamCompatTest"), &values, &blindings, 64,
+        ).unwrap();
+        let upstream_proof = bulletproofs::RangeProof::from_bytes(&proof.to_upstream_bytes()).unwrap();
+        assert!(upstream_proof.verify_multiple(
+            &upstream_bp_gens, &upstream_pc_gens, &mut Transcript::new(b"UpstreamCompatTest"), &commitments, 64,
+        ).is_ok());
+
+        // And the upstream proofs with ours
+        let (upstream_proof, commitments) = bulletproofs::RangeProof::prove_multiple(
+            &upstream_bp_gens, &upstream_pc_gens, &mut Transcript::new(b"UpstreamCompatTest"), &values, &blindings, 64,
+        ).unwrap();
+        let proof = RangeProof::from_upstream_bytes(&upstream_proof.to_bytes()).unwrap();
+        assert_eq!(proof.to_upstream_bytes(), upstream_proof.to_bytes());
+        assert_eq!(proof.to_bytes()[HEADER_SIZE..], proof.to_upstream_bytes()[..]);
+        // The encodings of either format are not parsed as the other
+        assert_eq!(RangeProof::from_upstream_bytes(&proof.to_bytes()), Err(ProofError::FormatError));
+        assert_eq!(RangeProof::from_bytes(&proof.to_upstream_bytes()), Err(ProofError::FormatError));
+        assert!(proof.verify_multiple(
+            &bp_gens, &pc_gens, &mut Transcript::new(b"UpstreamCompatTest"), &commitments, 64,
+        ).is_ok());
+        assert!(proof.verify_multiple(
+            &bp_gens, &pc_gens, &mut Transcript::new(b"OtherTest"), &commitments, 64,
+        ).is_err());
+    }
+
+    #[test]
+    fn equality_and_ordering() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(8, 1);
+        let mut rng = rand::thread_rng();
+
+        let proofs: Vec<RangeProof> = (0..3)
+            .map(|v| {
+                RangeProof::prove_single_with_rng(
+                    &bp_gens,
+                    &pc_gens,
+                    &mut Transcript::new(b"EqualityTest"),
+                    v,
+                    &Scalar::random(&mut rng),
+                    8,
+                    &mut rng,
+                )
+                .unwrap()
+                .0
+            })
+            .collect();
+
+        let parsed = RangeProof::from_bytes(&proofs[0].to_bytes()).unwrap();
+        assert_eq!(parsed, proofs[0]);
+        assert_ne!(proofs[0], proofs[1]);
+
+        let mut sorted: Vec<RangeProof> = proofs.iter().rev().cloned().chain(Some(parsed)).collect();
+        sorted.sort();
+        assert!(sorted.windows(2).all(|w| w[0].to_bytes() <= w[1].to_bytes()));
+
+        assert_eq!(sorted.iter().collect::<BTreeSet<_>>().len(), 3);
+        assert_eq!(sorted.iter().collect::<HashSet<_>>().len(), 3);
+    }
+
+    #[test]
+    fn party_debug_hides_the_witness() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut rng = rand::thread_rng();
+        let v_blinding = Scalar::random(&mut rng);
+
+        let party = party::Party::new(&bp_gens, &pc_gens, 1037578891, v_blinding, 32).unwrap();
+        let debug = format!("{:?}", party);
+        assert!(debug.starts_with("PartyAwaitingPosition { n: 32, V: "));
+        assert!(!debug.contains("1037578891"));
+        assert!(!debug.contains(&format!("{:?}", v_blinding)));
+
+        let (party, _) = party.assign_position_with_rng(0, &mut rng).unwrap();
+        assert_eq!(format!("{:?}", party), "PartyAwaitingBitChallenge { n: 32, j: 0, .. }");
+        assert_eq!(format!("{:?}", RewindKey::from_bytes([7u8; 32])), "RewindKey(..)");
+    }
+
+    #[test]
+    fn rewind_recovers_the_opening() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(32, 1);
+        let mut rng = rand::thread_rng();
+        let rewind_key = RewindKey::from_bytes([7u8; 32]);
+        let v_blinding = Scalar::random(&mut rng);
+
+        let mut transcript = Transcript::new(b"RewindTest");
+        let (proof, V) = RangeProof::prove_single_rewindable_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            1037578891,
+            &v_blinding,
+            32,
+            &rewind_key,
+            &mut rng,
+        )
+        .unwrap();
+
+        // The proof is still a regular rangeproof
+        let mut transcript = Transcript::new(b"RewindTest");
+        assert!(proof.verify_single(&bp_gens, &pc_gens, &mut transcript, &V, 32).is_ok());
+
+        let mut transcript = Transcript::new(b"RewindTest");
+        assert_eq!(
+            proof.rewind(&pc_gens, &mut transcript, &V, 32, &rewind_key).unwrap(),
+            (1037578891, v_blinding)
+        );
+
+        let mut transcript = Transcript::new(b"RewindTest");
+        let other_key = RewindKey::from_bytes([8u8; 32]);
+        assert_eq!(
+            proof.rewind(&pc_gens, &mut transcript, &V, 32, &other_key).unwrap_err(),
+            ProofError::VerificationError
+        );
+    }
+
     #[test]
     fn detect_dishonest_dealer_during_aggregation() {
         use self::dealer::*;
@@ -831,7 +1264,7 @@ mod tests {
             dealer.receive_poly_commitments(vec![poly_com0]).unwrap();
 
         // But now simulate a malicious dealer choosing x = 0
-        poly_challenge.x = Scalar::zero();
+        poly_challenge.x = Scalar::ZERO;
 
         let maybe_share0 = party0.apply_challenge(&poly_challenge);
 
@@ -0,0 +1,611 @@
+//! The [Bulletproofs+](https://eprint.iacr.org/2020/735) variant of the
+//! aggregated range proof.
+
+#![allow(non_snake_case)]
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use alloc::vec::Vec;
+
+use core::iter;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+use merlin::Transcript;
+
+use crate::errors::ProofError;
+use crate::format::{CanonicalPoints, ProofKind, ProofReader, SerializedSize, HEADER_SIZE};
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::msm::{prover_multiscalar_mul, CpuMsmBackend};
+use crate::transcript::TranscriptProtocol;
+use crate::util;
+use crate::weighted_inner_product_proof::{WeightedInnerProductProof, WipGens, WipWitness};
+use crate::zk_proof::ZkProof;
+
+use rand_core::{CryptoRng, RngCore};
+use serde::de::Visitor;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+
+/// An aggregated range proof, as `RangeProof`, built on the weighted
+/// inner product argument of Bulletproofs+.
+///
+/// The proof only has the commitment \\(A\\) to the bits next to the
+/// weighted inner product argument, so it is 3 elements shorter than a
+/// `RangeProof` for the same values. It takes the same parameters as
+/// `RangeProof`, with the same restrictions on the bitsize `n` and the
+/// aggregation size `m`, so a prover can switch between both, and
+/// proofs in the `RangeProof` format remain verifiable as before.
+///
+/// Unlike `RangeProof`, the values are aggregated by a single prover,
+/// as there is no multiparty protocol for this variant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RangeProofPlus {
+    /// Commitment to the bits of the values
+    A: CompressedRistretto,
+    /// Proof data for the weighted inner-product argument.
+    wip_proof: WeightedInnerProductProof,
+}
+
+impl RangeProofPlus {
+    /// Create a rangeproof for a given pair of value `v` and
+    /// blinding scalar `v_blinding`.
+    /// This is a convenience wrapper around [`RangeProofPlus::prove_multiple_with_rng`].
+    pub fn prove_single_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(RangeProofPlus, CompressedRistretto), ProofError> {
+        let (p, Vs) = RangeProofPlus::prove_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            &[v],
+            &[*v_blinding],
+            n,
+            rng,
+        )?;
+        Ok((p, Vs[0]))
+    }
+
+    /// Create a rangeproof for a given pair of value `v` and
+    /// blinding scalar `v_blinding`.
+    /// This is a convenience wrapper around [`RangeProofPlus::prove_single_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_single(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        v: u64,
+        v_blinding: &Scalar,
+        n: usize,
+    ) -> Result<(RangeProofPlus, CompressedRistretto), ProofError> {
+        RangeProofPlus::prove_single_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            v,
+            v_blinding,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Create a rangeproof for a set of values.
+    pub fn prove_multiple_with_rng<T: RngCore + CryptoRng>(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+        rng: &mut T,
+    ) -> Result<(RangeProofPlus, Vec<CompressedRistretto>), ProofError> {
+        if values.len() != blindings.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+        let m = values.len();
+        check_parameters(bp_gens, n, m)?;
+
+        transcript.rangeproof_plus_domain_sep(n as u64, m as u64);
+
+        let value_commitments: Vec<CompressedRistretto> = values
+            .iter()
+            .zip(blindings.iter())
+            .map(|(&v, &v_blinding)| pc_gens.commit(Scalar::from(v), v_blinding).compress())
+            .collect();
+        for V in value_commitments.iter() {
+            transcript.append_point(b"V", V);
+        }
+
+        // Commit to the bits a_L of the values, and to a_R = a_L - 1
+        let a_L: Vec<Scalar> = values
+            .iter()
+            .flat_map(|&v| (0..n).map(move |i| Scalar::from((v >> i) & 1)))
+            .collect();
+        let a_R: Vec<Scalar> = a_L.iter().map(|bit| bit - Scalar::ONE).collect();
+
+        let a_blinding = Scalar::random(&mut *rng);
+        let _span = msm_span!("range_proof_plus_bit_commitment", n, m);
+        let A = prover_multiscalar_mul(
+            &CpuMsmBackend,
+            &iter::once(a_blinding).chain(a_L.iter().cloned()).chain(a_R.iter().cloned()).collect::<Vec<Scalar>>(),
+            &iter::once(pc_gens.B_blinding)
+                .chain(bp_gens.G(n, m).cloned())
+                .chain(bp_gens.H(n, m).cloned())
+                .collect::<Vec<RistrettoPoint>>(),
+        )
+        .compress();
+
+        transcript.validate_and_append_point(b"A", &A)?;
+
+        let y: Scalar = transcript.challenge_scalar(b"y");
+        let z: Scalar = transcript.challenge_scalar(b"z");
+
+        let nm = n * m;
+        let d = powers_of_z_and_2(n, m, &z);
+        let exp_y: Vec<Scalar> = util::exp_iter(y).take(nm + 2).collect();
+
+        // a_L - z and a_R + d * y^(nm - i) + z are the vectors of the weighted
+        // inner product argument for A - z <1, G> + <d * y^(nm - i) + z, H> +
+        // y^(nm + 1) sum_j z^(2j + 2) V_j + zeta(y, z) B.
+        let a_L_hat: Vec<Scalar> = a_L.iter().map(|a_L_i| a_L_i - z).collect();
+        let a_R_hat: Vec<Scalar> = a_R
+            .iter()
+            .zip(d.iter())
+            .enumerate()
+            .map(|(i, (a_R_i, d_i))| a_R_i + d_i * exp_y[nm - i] + z)
+            .collect();
+        let alpha_hat = a_blinding
+            + exp_y[nm + 1]
+                * d.iter()
+                    .step_by(n)
+                    .zip(blindings.iter())
+                    .map(|(z_exp, v_blinding)| z_exp * v_blinding)
+                    .sum::<Scalar>();
+
+        let wip_proof = WeightedInnerProductProof::create(
+            transcript,
+            WipGens {
+                g: pc_gens.B,
+                h: pc_gens.B_blinding,
+                G_vec: bp_gens.G(n, m).cloned().collect(),
+                H_vec: bp_gens.H(n, m).cloned().collect(),
+            },
+            y,
+            WipWitness { a_vec: a_L_hat, b_vec: a_R_hat, alpha: alpha_hat },
+            rng,
+        );
+
+        Ok((RangeProofPlus { A, wip_proof }, value_commitments))
+    }
+
+    /// Create a rangeproof for a set of values.
+    /// This is a convenience wrapper around [`RangeProofPlus::prove_multiple_with_rng`],
+    /// passing in a threadsafe RNG.
+    #[cfg(feature = "std")]
+    pub fn prove_multiple(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        values: &[u64],
+        blindings: &[Scalar],
+        n: usize,
+    ) -> Result<(RangeProofPlus, Vec<CompressedRistretto>), ProofError> {
+        RangeProofPlus::prove_multiple_with_rng(
+            bp_gens,
+            pc_gens,
+            transcript,
+            values,
+            blindings,
+            n,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Verifies a rangeproof for a given value commitment \\(V\\).
+    ///
+    /// This is a convenience wrapper around `verify_multiple` for the `m=1` case.
+    pub fn verify_single(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        V: &CompressedRistretto,
+        n: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple(bp_gens, pc_gens, transcript, &[*V], n)
+    }
+
+    /// Verifies an aggregated rangeproof for the given value commitments.
+    pub fn verify_multiple(
+        &self,
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        transcript: &mut Transcript,
+        value_commitments: &[CompressedRistretto],
+        n: usize,
+    ) -> Result<(), ProofError> {
+        let m = value_commitments.len();
+        check_parameters(bp_gens, n, m)?;
+
+        transcript.rangeproof_plus_domain_sep(n as u64, m as u64);
+
+        for V in value_commitments.iter() {
+            // Allow the commitments to be zero (0 value, 0 blinding)
+            transcript.append_point(b"V", V);
+        }
+
+        transcript.validate_and_append_point(b"A", &self.A)?;
+
+        let y: Scalar = transcript.challenge_scalar(b"y");
+        let z: Scalar = transcript.challenge_scalar(b"z");
+
+        let nm = n * m;
+        let scalars = self.wip_proof.verification_scalars(nm, y, transcript)?;
+        let e_sq = scalars.P;
+
+        let d = powers_of_z_and_2(n, m, &z);
+        let exp_y: Vec<Scalar> = util::exp_iter(y).take(nm + 2).collect();
+
+        let g = scalars.G.iter().map(|g_i| g_i - e_sq * z);
+        let h = scalars
+            .H
+            .iter()
+            .zip(d.iter())
+            .enumerate()
+            .map(|(i, (h_i, d_i))| h_i + e_sq * (d_i * exp_y[nm - i] + z));
+        let value_commitment_scalars = d.iter().step_by(n).map(|z_exp| e_sq * exp_y[nm + 1] * z_exp);
+        let basepoint_scalar = e_sq * zeta(n, m, &y, &z) + scalars.g;
+
+        let _span = msm_span!("range_proof_plus", n, m);
+        let mega_check = RistrettoPoint::optional_multiscalar_mul(
+            iter::once(e_sq)
+                .chain(scalars.proof_scalars())
+                .chain(iter::once(scalars.h))
+                .chain(iter::once(basepoint_scalar))
+                .chain(g)
+                .chain(h)
+                .chain(value_commitment_scalars),
+            iter::once(self.A.decompress())
+                .chain(self.wip_proof.proof_points())
+                .chain(iter::once(Some(pc_gens.B_blinding)))
+                .chain(iter::once(Some(pc_gens.B)))
+                .chain(bp_gens.G(n, m).map(|&x| Some(x)))
+                .chain(bp_gens.H(n, m).map(|&x| Some(x)))
+                .chain(value_commitments.iter().map(|V| V.decompress())),
+        )
+        .ok_or(ProofError::VerificationError)?;
+
+        if mega_check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Serializes the proof into a byte array of \\(2 \lg n + 6\\)
+    /// 32-byte elements, where \\(n\\) is the number of secret bits.
+    ///
+    /// # Layout
+    ///
+    /// The layout of the range proof encoding is:
+    ///
+    /// * the header, see [`ProofKind`],
+    /// * the compressed Ristretto point \\(A\\),
+    /// * \\(n\\) pairs of compressed Ristretto points \\(L_0,R_0\dots,L_{n-1},R_{n-1}\\),
+    /// * two compressed Ristretto points \\(A', B'\\),
+    /// * three scalars \\(r', s', \delta'\\).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.serialized_size());
+        buf.extend_from_slice(&ProofKind::RangePlus.header());
+        buf.extend_from_slice(self.A.as_bytes());
+        buf.extend(self.wip_proof.to_bytes_iter());
+        buf
+    }
+
+    /// Returns the size in bytes required to serialize the proof, see `to_bytes`.
+    pub fn serialized_size(&self) -> usize {
+        HEADER_SIZE + 32 + self.wip_proof.body_size()
+    }
+
+    /// Deserializes the proof from a byte slice.
+    ///
+    /// Returns an error if the byte slice cannot be parsed into a `RangeProofPlus`.
+    pub fn from_bytes(slice: &[u8]) -> Result<RangeProofPlus, ProofError> {
+        match ProofKind::RangePlus.read_header(slice)? {
+            (1, body) => RangeProofPlus::read_v1(&mut ProofReader::new(body)),
+            _ => Err(ProofError::FormatError),
+        }
+    }
+
+    /// Reads the proof in the first version of the format, without the header.
+    fn read_v1(reader: &mut ProofReader) -> Result<RangeProofPlus, ProofError> {
+        Ok(RangeProofPlus {
+            A: reader.read_point()?,
+            wip_proof: WeightedInnerProductProof::read_v1(reader)?,
+        })
+    }
+
+    /// Deserializes the proof as `from_bytes`, and also checks that all
+    /// the points are canonical encodings of Ristretto points.
+    pub fn from_bytes_strict(slice: &[u8]) -> Result<RangeProofPlus, ProofError> {
+        let proof = RangeProofPlus::from_bytes(slice)?;
+        if !proof.points_are_canonical() {
+            return Err(ProofError::FormatError);
+        }
+        Ok(proof)
+    }
+}
+
+impl CanonicalPoints for RangeProofPlus {
+    fn points_are_canonical(&self) -> bool {
+        self.A.points_are_canonical() && self.wip_proof.points_are_canonical()
+    }
+}
+
+impl SerializedSize for RangeProofPlus {
+    fn serialized_size(&self) -> usize {
+        RangeProofPlus::serialized_size(self)
+    }
+}
+
+impl PartialOrd for RangeProofPlus {
+    fn partial_cmp(&self, other: &RangeProofPlus) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RangeProofPlus {
+    fn cmp(&self, other: &RangeProofPlus) -> Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
+impl Hash for RangeProofPlus {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+/// Takes the same parameters, witness and statement as `RangeProof`.
+impl ZkProof for RangeProofPlus {
+    type Params<'a> = (&'a BulletproofGens, &'a PedersenGens, usize);
+    type Witness<'a> = (&'a [u64], &'a [Scalar]);
+    type Statement = Vec<CompressedRistretto>;
+    type Error = ProofError;
+
+    fn prove<R: RngCore + CryptoRng>(
+        (bp_gens, pc_gens, n): Self::Params<'_>,
+        (values, blindings): Self::Witness<'_>,
+        transcript: &mut Transcript,
+        rng: &mut R,
+    ) -> Result<(RangeProofPlus, Vec<CompressedRistretto>), ProofError> {
+        RangeProofPlus::prove_multiple_with_rng(bp_gens, pc_gens, transcript, values, blindings, n, rng)
+    }
+
+    fn verify(
+        &self,
+        (bp_gens, pc_gens, n): Self::Params<'_>,
+        value_commitments: &Vec<CompressedRistretto>,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_multiple(bp_gens, pc_gens, transcript, value_commitments, n)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        RangeProofPlus::to_bytes(self)
+    }
+
+    fn from_bytes(slice: &[u8]) -> Result<RangeProofPlus, ProofError> {
+        RangeProofPlus::from_bytes(slice)
+    }
+}
+
+impl Serialize for RangeProofPlus {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for RangeProofPlus {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct RangeProofPlusVisitor;
+
+        impl<'de> Visitor<'de> for RangeProofPlusVisitor {
+            type Value = RangeProofPlus;
+
+            fn expecting(&self, formatter: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                formatter.write_str("a valid RangeProofPlus")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<RangeProofPlus, E>
+            where
+                E: serde::de::Error,
+            {
+                // Using Error::custom requires T: Display, which our error
+                // type only implements when it implements std::error::Error.
+                #[cfg(feature = "std")]
+                return RangeProofPlus::from_bytes(v).map_err(serde::de::Error::custom);
+                // In no-std contexts, drop the error message.
+                #[cfg(not(feature = "std"))]
+                return RangeProofPlus::from_bytes(v)
+                    .map_err(|_| serde::de::Error::custom("deserialization error"));
+            }
+        }
+
+        deserializer.deserialize_bytes(RangeProofPlusVisitor)
+    }
+}
+
+/// Checks the bitsize `n` and the aggregation size `m` against the generators.
+fn check_parameters(bp_gens: &BulletproofGens, n: usize, m: usize) -> Result<(), ProofError> {
+    if !(n == 8 || n == 16 || n == 32 || n == 64) {
+        return Err(ProofError::InvalidBitsize);
+    }
+    if !m.is_power_of_two() {
+        return Err(ProofError::InvalidAggregation);
+    }
+    if bp_gens.gens_capacity < n {
+        return Err(ProofError::InvalidGeneratorsLength);
+    }
+    if bp_gens.party_capacity < m {
+        return Err(ProofError::InvalidGeneratorsLength);
+    }
+    Ok(())
+}
+
+/// Computes the vector
+/// \\(z^{2} \cdot \mathbf{2}^n \\| z^{4} \cdot \mathbf{2}^n \\| \dots \\| z^{2m} \cdot \mathbf{2}^n\\).
+fn powers_of_z_and_2(n: usize, m: usize, z: &Scalar) -> Vec<Scalar> {
+    let powers_of_2: Vec<Scalar> = util::exp_iter(Scalar::from(2u64)).take(n).collect();
+    let zz = z * z;
+    util::exp_iter(zz)
+        .skip(1)
+        .take(m)
+        .flat_map(|exp_z| powers_of_2.iter().map(move |exp_2| exp_2 * exp_z))
+        .collect()
+}
+
+/// Compute
+/// \\[
+/// \zeta(y,z) = (z - z^{2}) \sum\_{i=1}^{n \cdot m} y^i - z \cdot y^{n \cdot m + 1} \cdot \langle \mathbf{1}, {\mathbf{2}}^{n} \rangle \sum\_{j=1}^{m} z^{2j}
+/// \\]
+fn zeta(n: usize, m: usize, y: &Scalar, z: &Scalar) -> Scalar {
+    let zz = z * z;
+    let sum_y = y * util::sum_of_powers(y, n * m);
+    let sum_2 = util::sum_of_powers(&Scalar::from(2u64), n);
+    let sum_z = zz * util::sum_of_powers(&zz, m);
+    let y_nm_1 = util::scalar_exp_vartime(y, (n * m + 1) as u64);
+
+    (z - zz) * sum_y - z * y_nm_1 * sum_2 * sum_z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::range_proof::RangeProof;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    fn create_and_verify_helper(n: usize, m: usize) {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 8);
+        let mut rng = ChaChaRng::from_seed([24u8; 32]);
+
+        let values: Vec<u64> = (0..m).map(|_| rng.next_u64() >> (64 - n)).collect();
+        let blindings: Vec<Scalar> = (0..m).map(|_| Scalar::random(&mut rng)).collect();
+
+        let mut transcript = Transcript::new(b"RangeProofPlusTest");
+        let (proof, value_commitments) = RangeProofPlus::prove_multiple_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            n,
+            &mut rng,
+        )
+        .unwrap();
+
+        let proof_bytes = bincode::serialize(&proof).unwrap();
+        let proof: RangeProofPlus = bincode::deserialize(&proof_bytes).unwrap();
+
+        let mut transcript = Transcript::new(b"RangeProofPlusTest");
+        assert!(proof
+            .verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, n)
+            .is_ok());
+
+        // The proof is 3 elements shorter than a `RangeProof` for the same values
+        let mut transcript = Transcript::new(b"RangeProofPlusTest");
+        let (legacy_proof, _) = RangeProof::prove_multiple_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            &values,
+            &blindings,
+            n,
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(legacy_proof.to_bytes().len(), proof.to_bytes().len() + 3 * 32);
+        assert_eq!(legacy_proof.to_bytes().len(), legacy_proof.serialized_size());
+        assert_eq!(proof.to_bytes().len(), proof.serialized_size());
+    }
+
+    #[test]
+    fn create_and_verify_n_8_m_1() {
+        create_and_verify_helper(8, 1);
+    }
+
+    #[test]
+    fn create_and_verify_n_32_m_4() {
+        create_and_verify_helper(32, 4);
+    }
+
+    #[test]
+    fn create_and_verify_n_64_m_8() {
+        create_and_verify_helper(64, 8);
+    }
+
+    #[test]
+    fn out_of_range_values_do_not_verify() {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(64, 1);
+        let mut rng = ChaChaRng::from_seed([24u8; 32]);
+
+        let blinding = Scalar::random(&mut rng);
+        let mut transcript = Transcript::new(b"RangeProofPlusTest");
+        let (proof, _) = RangeProofPlus::prove_single_with_rng(
+            &bp_gens,
+            &pc_gens,
+            &mut transcript,
+            1 << 20,
+            &blinding,
+            16,
+            &mut rng,
+        )
+        .unwrap();
+
+        // The proof commits to the lower 16 bits, which are not the value
+        let value_commitment = pc_gens.commit(Scalar::from(1u64 << 20), blinding).compress();
+        let mut transcript = Transcript::new(b"RangeProofPlusTest");
+        assert!(proof
+            .verify_single(&bp_gens, &pc_gens, &mut transcript, &value_commitment, 16)
+            .is_err());
+    }
+
+    #[test]
+    fn test_zeta() {
+        let mut rng = ChaChaRng::from_seed([24u8; 32]);
+        let y = Scalar::random(&mut rng);
+        let z = Scalar::random(&mut rng);
+
+        let (n, m) = (8, 4);
+        let d = powers_of_z_and_2(n, m, &z);
+        let exp_y: Vec<Scalar> = util::exp_iter(y).take(n * m + 2).collect();
+
+        // zeta(y, z) is the weighted inner product of -z and d * y^(nm - i) + z,
+        // minus the terms with d * y^(nm + 1)
+        let mut expected = Scalar::ZERO;
+        for i in 0..n * m {
+            expected += (z - z * z) * exp_y[i + 1] - z * d[i] * exp_y[n * m + 1];
+        }
+
+        assert_eq!(expected, zeta(n, m, &y, &z));
+    }
+}
@@ -0,0 +1,430 @@
+//! The `session` module drives the `dealer` and `party` state machines
+//! of the aggregated multiparty computation protocol from messages, so
+//! that applications only need to carry the messages between devices.
+//!
+//! The dealer and each party exchange [`DealerMessage`]s and
+//! [`PartyMessage`]s, which can be encoded with `to_bytes` or with
+//! `serde`. The sessions do not perform any I/O: a [`DealerSession`]
+//! returns the messages to send to every party, and is fed their replies
+//! once all of them have arrived, whichever way they are transported. For
+//! transports that reply synchronously, [`aggregate_range_proofs`] runs
+//! the whole protocol over a set of [`Participant`]s.
+
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::mem;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{MPCError, ProofError};
+use crate::format::ProofReader;
+use crate::generators::{BulletproofGens, PedersenGens};
+use crate::range_proof::RangeProof;
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+
+use super::dealer::*;
+use super::messages::*;
+use super::party::*;
+
+/// A message from the dealer to a party.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum DealerMessage {
+    /// The position of the party in the aggregated proof.
+    Position(usize),
+    /// The challenges derived from the bit commitments of all the parties.
+    BitChallenge(BitChallenge),
+    /// The challenge derived from the polynomial commitments of all the parties.
+    PolyChallenge(PolyChallenge),
+}
+
+/// A message from a party to the dealer, replying to a [`DealerMessage`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum PartyMessage {
+    /// The reply to a `Position`.
+    BitCommitment(BitCommitment),
+    /// The reply to a `BitChallenge`.
+    PolyCommitment(PolyCommitment),
+    /// The reply to a `PolyChallenge`.
+    ProofShare(ProofShare),
+}
+
+impl DealerMessage {
+    /// Serializes the message as a tag byte followed by the position (8 bytes, little
+    /// endian) or the challenges (32 bytes each).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            DealerMessage::Position(j) => {
+                buf.push(0);
+                buf.extend_from_slice(&(*j as u64).to_le_bytes());
+            }
+            DealerMessage::BitChallenge(challenge) => {
+                buf.push(1);
+                buf.extend_from_slice(challenge.y.as_bytes());
+                buf.extend_from_slice(challenge.z.as_bytes());
+            }
+            DealerMessage::PolyChallenge(challenge) => {
+                buf.push(2);
+                buf.extend_from_slice(challenge.x.as_bytes());
+            }
+        }
+        buf
+    }
+
+    /// Parses a message serialized with `to_bytes`.
+    pub fn from_bytes(slice: &[u8]) -> Result<DealerMessage, ProofError> {
+        let mut reader = ProofReader::new(slice);
+        let message = match reader.read_u8()? {
+            0 => DealerMessage::Position(
+                usize::try_from(reader.read_u64()?).map_err(|_| ProofError::FormatError)?,
+            ),
+            1 => DealerMessage::BitChallenge(BitChallenge {
+                y: reader.read_scalar()?,
+                z: reader.read_scalar()?,
+            }),
+            2 => DealerMessage::PolyChallenge(PolyChallenge { x: reader.read_scalar()? }),
+            _ => return Err(ProofError::FormatError),
+        };
+        reader.finish()?;
+        Ok(message)
+    }
+}
+
+impl PartyMessage {
+    /// Serializes the message as a tag byte followed by its points and scalars (32 bytes
+    /// each). The vectors of a proof share are prefixed by their length (8 bytes, little
+    /// endian).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            PartyMessage::BitCommitment(commitment) => {
+                buf.push(0);
+                buf.extend_from_slice(commitment.V_j.as_bytes());
+                buf.extend_from_slice(commitment.A_j.compress().as_bytes());
+                buf.extend_from_slice(commitment.S_j.compress().as_bytes());
+            }
+            PartyMessage::PolyCommitment(commitment) => {
+                buf.push(1);
+                buf.extend_from_slice(commitment.T_1_j.compress().as_bytes());
+                buf.extend_from_slice(commitment.T_2_j.compress().as_bytes());
+            }
+            PartyMessage::ProofShare(share) => {
+                buf.push(2);
+                buf.extend_from_slice(share.t_x.as_bytes());
+                buf.extend_from_slice(share.t_x_blinding.as_bytes());
+                buf.extend_from_slice(share.e_blinding.as_bytes());
+                buf.extend_from_slice(&(share.l_vec.len() as u64).to_le_bytes());
+                for scalar in share.l_vec.iter().chain(share.r_vec.iter()) {
+                    buf.extend_from_slice(scalar.as_bytes());
+                }
+            }
+        }
+        buf
+    }
+
+    /// Parses a message serialized with `to_bytes`. The points must be valid encodings, and
+    /// both vectors of a proof share must have the same length.
+    pub fn from_bytes(slice: &[u8]) -> Result<PartyMessage, ProofError> {
+        let mut reader = ProofReader::new(slice);
+        let message = match reader.read_u8()? {
+            0 => PartyMessage::BitCommitment(BitCommitment {
+                V_j: reader.read_point()?,
+                A_j: read_point(&mut reader)?,
+                S_j: read_point(&mut reader)?,
+            }),
+            1 => PartyMessage::PolyCommitment(PolyCommitment {
+                T_1_j: read_point(&mut reader)?,
+                T_2_j: read_point(&mut reader)?,
+            }),
+            2 => {
+                let t_x = reader.read_scalar()?;
+                let t_x_blinding = reader.read_scalar()?;
+                let e_blinding = reader.read_scalar()?;
+                let n = reader.read_count(64)?;
+                let l_vec = (0..n).map(|_| reader.read_scalar()).collect::<Result<Vec<Scalar>, ProofError>>()?;
+                let r_vec = (0..n).map(|_| reader.read_scalar()).collect::<Result<Vec<Scalar>, ProofError>>()?;
+                PartyMessage::ProofShare(ProofShare { t_x, t_x_blinding, e_blinding, l_vec, r_vec })
+            }
+            _ => return Err(ProofError::FormatError),
+        };
+        reader.finish()?;
+        Ok(message)
+    }
+}
+
+/// Reads a point, which must be a valid encoding.
+fn read_point(reader: &mut ProofReader) -> Result<RistrettoPoint, ProofError> {
+    reader.read_point::<CompressedRistretto>()?
+        .decompress()
+        .ok_or(ProofError::FormatError)
+}
+
+/// The state of a party of the protocol, which replies to each [`DealerMessage`] in turn.
+pub struct PartySession<'a> {
+    state: PartyState<'a>,
+}
+
+enum PartyState<'a> {
+    AwaitingPosition(PartyAwaitingPosition<'a>),
+    AwaitingBitChallenge(PartyAwaitingBitChallenge<'a>),
+    AwaitingPolyChallenge(PartyAwaitingPolyChallenge),
+    Finished,
+}
+
+impl<'a> PartySession<'a> {
+    /// Starts a session to prove that `v` is an `n`-bit value, see `Party::new`.
+    pub fn new(
+        bp_gens: &'a BulletproofGens,
+        pc_gens: &'a PedersenGens,
+        v: u64,
+        v_blinding: Scalar,
+        n: usize,
+    ) -> Result<PartySession<'a>, MPCError> {
+        Ok(PartySession {
+            state: PartyState::AwaitingPosition(Party::new(bp_gens, pc_gens, v, v_blinding, n)?),
+        })
+    }
+
+    /// Replies to the next message of the dealer.
+    #[cfg(feature = "std")]
+    pub fn handle(&mut self, message: &DealerMessage) -> Result<PartyMessage, MPCError> {
+        self.handle_with_rng(message, &mut thread_rng())
+    }
+
+    /// Replies to the next message of the dealer. Returns an `UnexpectedMessage` error if
+    /// the message is not the one the party is waiting for, after which the session is
+    /// finished.
+    pub fn handle_with_rng<T: RngCore + CryptoRng>(
+        &mut self,
+        message: &DealerMessage,
+        rng: &mut T,
+    ) -> Result<PartyMessage, MPCError> {
+        match (mem::replace(&mut self.state, PartyState::Finished), message) {
+            (PartyState::AwaitingPosition(party), DealerMessage::Position(j)) => {
+                let (party, commitment) = party.assign_position_with_rng(*j, rng)?;
+                self.state = PartyState::AwaitingBitChallenge(party);
+                Ok(PartyMessage::BitCommitment(commitment))
+            }
+            (PartyState::AwaitingBitChallenge(party), DealerMessage::BitChallenge(challenge)) => {
+                let (party, commitment) = party.apply_challenge_with_rng(challenge, rng);
+                self.state = PartyState::AwaitingPolyChallenge(party);
+                Ok(PartyMessage::PolyCommitment(commitment))
+            }
+            (PartyState::AwaitingPolyChallenge(party), DealerMessage::PolyChallenge(challenge)) => {
+                Ok(PartyMessage::ProofShare(party.apply_challenge(challenge)?))
+            }
+            _ => Err(MPCError::UnexpectedMessage),
+        }
+    }
+}
+
+/// The next step of a [`DealerSession`].
+pub enum DealerStep {
+    /// Messages to send to the parties, one per party in order of position, whose replies
+    /// are expected next.
+    Send(Vec<DealerMessage>),
+    /// The aggregated proof, and the commitments to the values of the parties.
+    Done(RangeProof, Vec<CompressedRistretto>),
+}
+
+/// The state of the dealer of the protocol, which collects the replies of all the parties
+/// at each round.
+pub struct DealerSession<'a, 'b> {
+    state: DealerState<'a, 'b>,
+}
+
+enum DealerState<'a, 'b> {
+    AwaitingBitCommitments(DealerAwaitingBitCommitments<'a, 'b>),
+    AwaitingPolyCommitments(DealerAwaitingPolyCommitments<'a, 'b>, Vec<CompressedRistretto>),
+    AwaitingProofShares(DealerAwaitingProofShares<'a, 'b>, Vec<CompressedRistretto>),
+    Finished,
+}
+
+impl<'a, 'b> DealerSession<'a, 'b> {
+    /// Starts a session aggregating the proofs of `m` parties proving `n`-bit ranges, see
+    /// `Dealer::new`. Returns the session and the `Position` message of each party.
+    pub fn new(
+        bp_gens: &'b BulletproofGens,
+        pc_gens: &'b PedersenGens,
+        transcript: &'a mut Transcript,
+        n: usize,
+        m: usize,
+    ) -> Result<(DealerSession<'a, 'b>, Vec<DealerMessage>), MPCError> {
+        let dealer = Dealer::new(bp_gens, pc_gens, transcript, n, m)?;
+        Ok((
+            DealerSession { state: DealerState::AwaitingBitCommitments(dealer) },
+            (0..m).map(DealerMessage::Position).collect(),
+        ))
+    }
+
+    /// Receives the replies of all the parties to the last messages, in order of position.
+    #[cfg(feature = "std")]
+    pub fn receive(&mut self, replies: Vec<PartyMessage>) -> Result<DealerStep, MPCError> {
+        self.receive_with_rng(replies, &mut thread_rng())
+    }
+
+    /// Receives the replies of all the parties to the last messages, in order of position,
+    /// using `rng` to verify the aggregated proof at the end. Returns an `UnexpectedMessage`
+    /// error if a reply does not answer the last message, after which the session is
+    /// finished.
+    pub fn receive_with_rng<T: RngCore + CryptoRng>(
+        &mut self,
+        replies: Vec<PartyMessage>,
+        rng: &mut T,
+    ) -> Result<DealerStep, MPCError> {
+        match mem::replace(&mut self.state, DealerState::Finished) {
+            DealerState::AwaitingBitCommitments(dealer) => {
+                let commitments = expect_replies(replies, |reply| match reply {
+                    PartyMessage::BitCommitment(commitment) => Some(commitment),
+                    _ => None,
+                })?;
+                let value_commitments: Vec<CompressedRistretto> = commitments.iter().map(|commitment| commitment.V_j).collect();
+                let (dealer, challenge) = dealer.receive_bit_commitments(commitments)?;
+                let messages = vec![DealerMessage::BitChallenge(challenge); value_commitments.len()];
+                self.state = DealerState::AwaitingPolyCommitments(dealer, value_commitments);
+                Ok(DealerStep::Send(messages))
+            }
+            DealerState::AwaitingPolyCommitments(dealer, value_commitments) => {
+                let commitments = expect_replies(replies, |reply| match reply {
+                    PartyMessage::PolyCommitment(commitment) => Some(commitment),
+                    _ => None,
+                })?;
+                let (dealer, challenge) = dealer.receive_poly_commitments(commitments)?;
+                let messages = vec![DealerMessage::PolyChallenge(challenge); value_commitments.len()];
+                self.state = DealerState::AwaitingProofShares(dealer, value_commitments);
+                Ok(DealerStep::Send(messages))
+            }
+            DealerState::AwaitingProofShares(dealer, value_commitments) => {
+                let shares = expect_replies(replies, |reply| match reply {
+                    PartyMessage::ProofShare(share) => Some(share),
+                    _ => None,
+                })?;
+                let proof = dealer.receive_shares_with_rng(&shares, rng)?;
+                Ok(DealerStep::Done(proof, value_commitments))
+            }
+            DealerState::Finished => Err(MPCError::UnexpectedMessage),
+        }
+    }
+}
+
+/// Extracts the expected message from each reply.
+fn expect_replies<M>(
+    replies: Vec<PartyMessage>,
+    extract: impl Fn(PartyMessage) -> Option<M>,
+) -> Result<Vec<M>, MPCError> {
+    replies.into_iter()
+        .map(|reply| extract(reply).ok_or(MPCError::UnexpectedMessage))
+        .collect()
+}
+
+/// A party as seen by the dealer, which replies to each message it is sent, e.g. over a
+/// connection to a device, or a local `PartySession`.
+pub trait Participant {
+    /// Sends `message` to the party and returns its reply.
+    fn exchange(&mut self, message: &DealerMessage) -> Result<PartyMessage, MPCError>;
+}
+
+#[cfg(feature = "std")]
+impl<'a> Participant for PartySession<'a> {
+    fn exchange(&mut self, message: &DealerMessage) -> Result<PartyMessage, MPCError> {
+        self.handle(message)
+    }
+}
+
+/// Runs the aggregated protocol as the dealer with the given `participants`, in order of
+/// position, which prove `n`-bit ranges. Returns the aggregated proof and the commitments to
+/// the values of the participants.
+#[cfg(feature = "std")]
+pub fn aggregate_range_proofs<P: Participant>(
+    bp_gens: &BulletproofGens,
+    pc_gens: &PedersenGens,
+    transcript: &mut Transcript,
+    n: usize,
+    participants: &mut [P],
+) -> Result<(RangeProof, Vec<CompressedRistretto>), MPCError> {
+    let (mut dealer, mut messages) = DealerSession::new(bp_gens, pc_gens, transcript, n, participants.len())?;
+    loop {
+        let replies = participants.iter_mut().zip(messages.iter())
+            .map(|(participant, message)| participant.exchange(message))
+            .collect::<Result<Vec<PartyMessage>, MPCError>>()?;
+        match dealer.receive(replies)? {
+            DealerStep::Send(next) => messages = next,
+            DealerStep::Done(proof, value_commitments) => return Ok((proof, value_commitments)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A participant which serializes the messages both ways, as over a connection.
+    struct Remote<'a>(PartySession<'a>);
+
+    impl<'a> Participant for Remote<'a> {
+        fn exchange(&mut self, message: &DealerMessage) -> Result<PartyMessage, MPCError> {
+            let received = DealerMessage::from_bytes(&message.to_bytes()).unwrap();
+            assert_eq!(received, *message);
+            let reply = self.0.handle(&received)?;
+            let sent = PartyMessage::from_bytes(&reply.to_bytes()).unwrap();
+            assert_eq!(sent, reply);
+            Ok(sent)
+        }
+    }
+
+    #[test]
+    fn aggregated_range_proof() {
+        let (n, m) = (32, 4);
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, m);
+        let mut rng = thread_rng();
+
+        let values = [7u64, 1 << 20, 0, u32::MAX as u64];
+        let mut participants: Vec<Remote> = values.iter()
+            .map(|&v| Remote(PartySession::new(&bp_gens, &pc_gens, v, Scalar::random(&mut rng), n).unwrap()))
+            .collect();
+
+        let mut transcript = Transcript::new(b"SessionTest");
+        let (proof, value_commitments) =
+            aggregate_range_proofs(&bp_gens, &pc_gens, &mut transcript, n, &mut participants).unwrap();
+
+        let mut transcript = Transcript::new(b"SessionTest");
+        assert!(proof.verify_multiple(&bp_gens, &pc_gens, &mut transcript, &value_commitments, n).is_ok());
+    }
+
+    #[test]
+    fn unexpected_messages() {
+        let n = 8;
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(n, 2);
+
+        let mut party = PartySession::new(&bp_gens, &pc_gens, 3, Scalar::ONE, n).unwrap();
+        let challenge = DealerMessage::PolyChallenge(PolyChallenge { x: Scalar::ONE });
+        assert_eq!(party.handle(&challenge).err(), Some(MPCError::UnexpectedMessage));
+
+        let mut transcript = Transcript::new(b"SessionTest");
+        let (mut dealer, messages) = DealerSession::new(&bp_gens, &pc_gens, &mut transcript, n, 2).unwrap();
+        let mut parties: Vec<PartySession> = (0..2)
+            .map(|_| PartySession::new(&bp_gens, &pc_gens, 3, Scalar::ONE, n).unwrap())
+            .collect();
+        let mut replies: Vec<PartyMessage> = parties.iter_mut().zip(messages.iter())
+            .map(|(party, message)| party.handle(message).unwrap())
+            .collect();
+        replies[1] = PartyMessage::PolyCommitment(PolyCommitment {
+            T_1_j: RistrettoPoint::default(),
+            T_2_j: RistrettoPoint::default(),
+        });
+        assert_eq!(dealer.receive(replies).err(), Some(MPCError::UnexpectedMessage));
+
+        assert_eq!(DealerMessage::from_bytes(&[3]).err(), Some(ProofError::FormatError));
+        assert_eq!(PartyMessage::from_bytes(&challenge.to_bytes()[..1]).err(), Some(ProofError::FormatError));
+    }
+}
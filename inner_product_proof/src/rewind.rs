@@ -0,0 +1,88 @@
+//! Keys to rewind proofs, i.e. to recover the opening of the committed
+//! value from the proof itself.
+//!
+//! A rewindable proof takes the blindings \\(\alpha, \rho, \tau_1, \tau_2\\)
+//! of the prover from a key shared with a designated party, and the value
+//! is added to \\(\alpha\\). From the revealed
+//! \\(\tilde{e} = \alpha + \rho x\\) and
+//! \\(\tilde{t}_x = \tau_1 x + \tau_2 x^2 + \dots\\), the holder of the
+//! key recovers the value and its blinding factor, while for everyone else
+//! the blindings are as random as the ones of any other proof. This is the
+//! rewinding of the Mimblewimble range proofs.
+//!
+//! The blindings only depend on the key and on the value commitment, so a
+//! commitment must be proven at most once with a given key: a few proofs of
+//! the same commitment would reveal its blinding factor.
+
+#![allow(non_snake_case)]
+
+use core::fmt;
+
+use merlin::Transcript;
+use zeroize::Zeroize;
+
+use crate::group::{CompressedPoint, PrimeScalar};
+use crate::transcript::{unrecorded, TranscriptProtocol};
+
+/// Key shared between a prover and a designated party, which can then
+/// recover the values committed in the rewindable proofs of the prover.
+#[derive(Clone)]
+pub struct RewindKey([u8; 32]);
+
+impl RewindKey {
+    /// Creates a key from 32 secret bytes, e.g. the output of a key
+    /// exchange between the prover and the designated party.
+    pub fn from_bytes(bytes: [u8; 32]) -> RewindKey {
+        RewindKey(bytes)
+    }
+
+    /// Derives the blindings of the proof of the value committed in `V`.
+    pub(crate) fn nonces<S: PrimeScalar, C: CompressedPoint>(&self, V: &C) -> RewindNonces<S> {
+        // The nonces are secret, so they are kept out of the transcript logs
+        unrecorded(|| {
+            let mut transcript = Transcript::new(b"ip_zk_proof rewind");
+            transcript.append_message(b"key", &self.0);
+            transcript.append_point(b"V", V);
+
+            RewindNonces {
+                alpha: transcript.challenge_scalar(b"alpha"),
+                rho: transcript.challenge_scalar(b"rho"),
+                tau_1: transcript.challenge_scalar(b"tau_1"),
+                tau_2: transcript.challenge_scalar(b"tau_2"),
+            }
+        })
+    }
+}
+
+/// Does not show the key.
+impl fmt::Debug for RewindKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RewindKey(..)")
+    }
+}
+
+/// Overwrite the key with null bytes when it goes out of scope.
+impl Drop for RewindKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Blindings of a rewindable proof. The value is added to `alpha` to
+/// obtain the blinding of \\(A\\).
+pub(crate) struct RewindNonces<S: PrimeScalar> {
+    pub(crate) alpha: S,
+    pub(crate) rho: S,
+    pub(crate) tau_1: S,
+    pub(crate) tau_2: S,
+}
+
+/// Overwrite secrets with null bytes when they go out of scope.
+impl<S: PrimeScalar> Drop for RewindNonces<S> {
+    fn drop(&mut self) {
+        self.alpha.zeroize();
+        self.rho.zeroize();
+        self.tau_1.zeroize();
+        self.tau_2.zeroize();
+    }
+}
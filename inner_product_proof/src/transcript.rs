@@ -1,95 +1,311 @@
 //! Defines a `TranscriptProtocol` trait for using a Merlin transcript.
+//!
+//! The proofs of this crate follow the same conventions on the transcript:
+//! each protocol starts with a domain separator, labelled `dom-sep`, that
+//! names the protocol and binds its public sizes; points are appended in
+//! their compressed encoding, and those sent by the prover are checked not
+//! to be the identity; scalars are appended in their canonical encoding;
+//! and challenges are reduced from 64 bytes of transcript output, so that
+//! they are uniform. Protocols composing these proofs can use the same
+//! trait, so that they bind their own messages in the same way.
+//!
+//! The blinding factors of a prover can also be derived from the
+//! transcript, with `witness_rng`, so that they stay secret on devices
+//! with a weak system RNG.
+//!
+//! With the `transcript-debug` feature, the operations of this trait are
+//! recorded, see `transcript_debug`.
 
-use curve25519_dalek::ristretto::CompressedRistretto;
-use curve25519_dalek::scalar::Scalar;
-use merlin::Transcript;
+use ff::PrimeField;
+use merlin::{Transcript, TranscriptRng};
+use rand_core::{CryptoRng, RngCore};
 
 use crate::errors::ProofError;
+use crate::group::{CompressedPoint, PrimeScalar};
+#[cfg(feature = "transcript-debug")]
+use crate::transcript_debug::TranscriptOp;
 
+/// Extension trait to `merlin::Transcript`, with the messages of the
+/// proofs of this crate.
 pub trait TranscriptProtocol {
+    /// Append a domain separator for the protocol named `label`.
+    ///
+    /// Protocols built on top of these proofs should start with their own
+    /// domain separator, so that their transcripts cannot be confused
+    /// with the ones of other protocols.
+    fn domain_sep(&mut self, label: &'static [u8]);
+
     /// Append a domain separator for an `n`-bit, `m`-party range proof.
     fn rangeproof_domain_sep(&mut self, n: u64, m: u64);
 
+    /// Append a domain separator for an `n`-bit, `m`-party Bulletproofs+ range proof.
+    fn rangeproof_plus_domain_sep(&mut self, n: u64, m: u64);
+
     /// Append a domain separator for a length-`n` inner product proof.
     fn innerproduct_domain_sep(&mut self, n: u64);
 
+    /// Append a domain separator for a length-`n` weighted inner product proof.
+    fn weighted_innerproduct_domain_sep(&mut self, n: u64);
+
+    /// Append a domain separator for the public weights of a length-`n`
+    /// weighted zero knowledge inner product proof.
+    fn weighted_ipzk_domain_sep(&mut self, n: u64);
+
+    /// Append a domain separator for the public right hand side vector of
+    /// a length-`n` zero knowledge inner product proof.
+    fn public_rhs_ipzk_domain_sep(&mut self, n: u64);
+
+    /// Append a domain separator for the two vector commitments of a
+    /// length-`n` zero knowledge inner product proof.
+    fn vector_commitments_ipzk_domain_sep(&mut self, n: u64);
+
+    /// Append a domain separator for `m` aggregated length-`n` zero
+    /// knowledge inner product proofs.
+    fn aggregated_ipzk_domain_sep(&mut self, n: u64, m: u64);
+
     /// Append a domain separator for a constraint system.
+    #[cfg(feature = "yoloproofs")]
     fn r1cs_domain_sep(&mut self);
 
     /// Commit a domain separator for a CS without randomized constraints.
+    #[cfg(feature = "yoloproofs")]
     fn r1cs_1phase_domain_sep(&mut self);
 
     /// Commit a domain separator for a CS with randomized constraints.
+    #[cfg(feature = "yoloproofs")]
     fn r1cs_2phase_domain_sep(&mut self);
 
+    /// Append a raw `message` with the given `label`, as
+    /// `Transcript::append_message` does.
+    fn append_bytes(&mut self, label: &'static [u8], message: &[u8]);
+
+    /// Append an integer `n` with the given `label`, as
+    /// `Transcript::append_u64` does.
+    fn append_integer(&mut self, label: &'static [u8], n: u64);
+
+    /// Fill `dest` with `label`ed challenge bytes, as
+    /// `Transcript::challenge_bytes` does.
+    fn challenge_into(&mut self, label: &'static [u8], dest: &mut [u8]);
+
     /// Append a `scalar` with the given `label`.
-    fn append_scalar(&mut self, label: &'static [u8], scalar: &Scalar);
+    fn append_scalar<S: PrimeField>(&mut self, label: &'static [u8], scalar: &S);
 
     /// Append a `point` with the given `label`.
-    fn append_point(&mut self, label: &'static [u8], point: &CompressedRistretto);
+    fn append_point<C: CompressedPoint>(&mut self, label: &'static [u8], point: &C);
 
     /// Check that a point is not the identity, then append it to the
     /// transcript.  Otherwise, return an error.
-    fn validate_and_append_point(
+    fn validate_and_append_point<C: CompressedPoint>(
         &mut self,
         label: &'static [u8],
-        point: &CompressedRistretto,
+        point: &C,
     ) -> Result<(), ProofError>;
 
     /// Compute a `label`ed challenge variable.
-    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar;
+    fn challenge_scalar<S: PrimeScalar>(&mut self, label: &'static [u8]) -> S;
+
+    /// Build an RNG for the blinding factors of a prover, keyed by the
+    /// transcript so far, by the `witness` scalars and by `rng`. The
+    /// transcript itself is left unchanged.
+    ///
+    /// The output is unpredictable as long as either the witness or `rng`
+    /// are, so a weak or broken system RNG does not reveal the witness
+    /// through the blindings of the proof. It can be passed as the `rng`
+    /// of any of the provers of this crate.
+    fn witness_rng<S: PrimeField, R: RngCore + CryptoRng>(
+        &self,
+        label: &'static [u8],
+        witness: &[S],
+        rng: &mut R,
+    ) -> TranscriptRng;
 }
 
 impl TranscriptProtocol for Transcript {
+    fn domain_sep(&mut self, label: &'static [u8]) {
+        self.append_bytes(b"dom-sep", label);
+    }
+
     fn rangeproof_domain_sep(&mut self, n: u64, m: u64) {
-        self.append_message(b"dom-sep", b"rangeproof v1");
-        self.append_u64(b"n", n);
-        self.append_u64(b"m", m);
+        self.domain_sep(b"rangeproof v1");
+        self.append_integer(b"n", n);
+        self.append_integer(b"m", m);
+    }
+
+    fn rangeproof_plus_domain_sep(&mut self, n: u64, m: u64) {
+        self.domain_sep(b"rangeproof+ v1");
+        self.append_integer(b"n", n);
+        self.append_integer(b"m", m);
     }
 
     fn innerproduct_domain_sep(&mut self, n: u64) {
-        self.append_message(b"dom-sep", b"ipp v1");
-        self.append_u64(b"n", n);
+        self.domain_sep(b"ipp v1");
+        self.append_integer(b"n", n);
+    }
+
+    fn weighted_innerproduct_domain_sep(&mut self, n: u64) {
+        self.domain_sep(b"wip v1");
+        self.append_integer(b"n", n);
+    }
+
+    fn weighted_ipzk_domain_sep(&mut self, n: u64) {
+        self.domain_sep(b"weighted ipzk v1");
+        self.append_integer(b"n", n);
+    }
+
+    fn public_rhs_ipzk_domain_sep(&mut self, n: u64) {
+        self.domain_sep(b"public rhs ipzk v1");
+        self.append_integer(b"n", n);
+    }
+
+    fn vector_commitments_ipzk_domain_sep(&mut self, n: u64) {
+        self.domain_sep(b"vector commitments ipzk v1");
+        self.append_integer(b"n", n);
+    }
+
+    fn aggregated_ipzk_domain_sep(&mut self, n: u64, m: u64) {
+        self.domain_sep(b"aggregated ipzk v1");
+        self.append_integer(b"n", n);
+        self.append_integer(b"m", m);
     }
 
+    #[cfg(feature = "yoloproofs")]
     fn r1cs_domain_sep(&mut self) {
-        self.append_message(b"dom-sep", b"r1cs v1");
+        self.domain_sep(b"r1cs v1");
     }
 
+    #[cfg(feature = "yoloproofs")]
     fn r1cs_1phase_domain_sep(&mut self) {
-        self.append_message(b"dom-sep", b"r1cs-1phase");
+        self.domain_sep(b"r1cs-1phase");
     }
 
+    #[cfg(feature = "yoloproofs")]
     fn r1cs_2phase_domain_sep(&mut self) {
-        self.append_message(b"dom-sep", b"r1cs-2phase");
+        self.domain_sep(b"r1cs-2phase");
+    }
+
+    fn append_bytes(&mut self, label: &'static [u8], message: &[u8]) {
+        #[cfg(feature = "transcript-debug")]
+        crate::transcript_debug::record(TranscriptOp::Append, label, message);
+        self.append_message(label, message);
+    }
+
+    fn append_integer(&mut self, label: &'static [u8], n: u64) {
+        #[cfg(feature = "transcript-debug")]
+        crate::transcript_debug::record(TranscriptOp::Append, label, &n.to_le_bytes());
+        self.append_u64(label, n);
+    }
+
+    fn challenge_into(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        self.challenge_bytes(label, dest);
+        #[cfg(feature = "transcript-debug")]
+        crate::transcript_debug::record(TranscriptOp::Challenge, label, dest);
     }
 
-    fn append_scalar(&mut self, label: &'static [u8], scalar: &Scalar) {
-        self.append_message(label, scalar.as_bytes());
+    fn append_scalar<S: PrimeField>(&mut self, label: &'static [u8], scalar: &S) {
+        self.append_bytes(label, scalar.to_repr().as_ref());
     }
 
-    fn append_point(&mut self, label: &'static [u8], point: &CompressedRistretto) {
-        self.append_message(label, point.as_bytes());
+    fn append_point<C: CompressedPoint>(&mut self, label: &'static [u8], point: &C) {
+        self.append_bytes(label, point.as_bytes());
     }
 
-    fn validate_and_append_point(
+    fn validate_and_append_point<C: CompressedPoint>(
         &mut self,
         label: &'static [u8],
-        point: &CompressedRistretto,
+        point: &C,
     ) -> Result<(), ProofError> {
-        use curve25519_dalek::traits::IsIdentity;
-
         if point.is_identity() {
             Err(ProofError::VerificationError)
         } else {
-            Ok(self.append_message(label, point.as_bytes()))
+            self.append_bytes(label, point.as_bytes());
+            Ok(())
         }
     }
 
-    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar {
+    fn challenge_scalar<S: PrimeScalar>(&mut self, label: &'static [u8]) -> S {
         let mut buf = [0u8; 64];
-        self.challenge_bytes(label, &mut buf);
+        self.challenge_into(label, &mut buf);
+
+        S::from_uniform_bytes(&buf)
+    }
+
+    fn witness_rng<S: PrimeField, R: RngCore + CryptoRng>(
+        &self,
+        label: &'static [u8],
+        witness: &[S],
+        rng: &mut R,
+    ) -> TranscriptRng {
+        witness
+            .iter()
+            .fold(self.build_rng(), |builder, w| {
+                builder.rekey_with_witness_bytes(label, w.to_repr().as_ref())
+            })
+            .finalize(rng)
+    }
+}
+
+/// Runs `f` without recording its operations, e.g. for the transcripts that
+/// only the prover uses to derive its secrets. Just runs `f` without the
+/// `transcript-debug` feature.
+pub(crate) fn unrecorded<T, F: FnOnce() -> T>(f: F) -> T {
+    #[cfg(feature = "transcript-debug")]
+    return crate::transcript_debug::unrecorded(f);
+    #[cfg(not(feature = "transcript-debug"))]
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use curve25519_dalek::ristretto::CompressedRistretto;
+    use curve25519_dalek::scalar::Scalar;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn identity_points_are_rejected() {
+        let mut transcript = Transcript::new(b"TranscriptProtocolTest");
+        transcript.domain_sep(b"test v1");
+        assert_eq!(
+            transcript.validate_and_append_point(b"P", &CompressedRistretto::default()),
+            Err(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn challenges_depend_on_the_domain_separator() {
+        let challenge = |label: &'static [u8]| {
+            let mut transcript = Transcript::new(b"TranscriptProtocolTest");
+            transcript.domain_sep(label);
+            transcript.append_scalar(b"s", &Scalar::ONE);
+            transcript.challenge_scalar::<Scalar>(b"c")
+        };
+        assert_eq!(challenge(b"test v1"), challenge(b"test v1"));
+        assert_ne!(challenge(b"test v1"), challenge(b"test v2"));
+    }
+
+    #[test]
+    fn witness_rng_depends_on_the_witness() {
+        let mut transcript = Transcript::new(b"TranscriptProtocolTest");
+        transcript.domain_sep(b"test v1");
+        let random_scalar = |witness: &[Scalar], seed: u8| {
+            let mut rng = ChaChaRng::from_seed([seed; 32]);
+            Scalar::random(&mut transcript.witness_rng(b"witness", witness, &mut rng))
+        };
+        let witness = [Scalar::from(3u64), Scalar::from(7u64)];
+
+        // Even with a fixed RNG, the blindings change with the witness
+        assert_eq!(random_scalar(&witness, 24), random_scalar(&witness, 24));
+        assert_ne!(random_scalar(&witness, 24), random_scalar(&witness[..1], 24));
+        assert_ne!(random_scalar(&witness, 24), random_scalar(&witness, 42));
 
-        Scalar::from_bytes_mod_order_wide(&buf)
+        // The transcript is not modified
+        let mut fresh = Transcript::new(b"TranscriptProtocolTest");
+        fresh.domain_sep(b"test v1");
+        assert_eq!(
+            transcript.challenge_scalar::<Scalar>(b"c"),
+            fresh.challenge_scalar::<Scalar>(b"c")
+        );
     }
 }
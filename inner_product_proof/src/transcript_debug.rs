@@ -0,0 +1,165 @@
+//! A log of the operations on the transcripts, to find where the transcript
+//! of a verifier departs from the one of the prover. Enabled with the
+//! `transcript-debug` feature.
+//!
+//! The operations are recorded through the methods of `TranscriptProtocol`,
+//! within a call to `record_transcripts`, on the current thread:
+//!
+//! ```ignore
+//! let (proof, prover_log) = record_transcripts(|| prove(&mut Transcript::new(b"app")));
+//! let (result, verifier_log) = record_transcripts(|| proof.verify(&mut Transcript::new(b"app")));
+//! if let Some(i) = prover_log.first_mismatch(&verifier_log) {
+//!     println!("{}", prover_log.dump());
+//!     println!("{}", verifier_log.dump());
+//! }
+//! ```
+//!
+//! The calls to the inherent methods of `merlin::Transcript`, e.g. the label
+//! of `Transcript::new`, are not recorded.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::string::String;
+use std::vec::Vec;
+
+/// The kind of an operation on a transcript.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TranscriptOp {
+    /// A message appended to the transcript
+    Append,
+    /// A challenge read from the transcript, with its bytes as data
+    Challenge,
+}
+
+/// An operation on a transcript, with its label and data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptEvent {
+    pub op: TranscriptOp,
+    pub label: Vec<u8>,
+    pub data: Vec<u8>,
+}
+
+/// The operations recorded by `record_transcripts`, in order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TranscriptLog {
+    events: Vec<TranscriptEvent>,
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<Vec<TranscriptEvent>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, recording the operations on the transcripts of the current
+/// thread. Recordings can be nested, the operations are only recorded in
+/// the innermost one.
+pub fn record_transcripts<T, F: FnOnce() -> T>(f: F) -> (T, TranscriptLog) {
+    let outer = RECORDER.with(|r| r.replace(Some(Vec::new())));
+    let result = f();
+    let events = RECORDER.with(|r| r.replace(outer)).unwrap_or_default();
+    (result, TranscriptLog { events })
+}
+
+/// See `transcript::unrecorded`.
+pub(crate) fn unrecorded<T, F: FnOnce() -> T>(f: F) -> T {
+    let outer = RECORDER.with(|r| r.replace(None));
+    let result = f();
+    RECORDER.with(|r| r.replace(outer));
+    result
+}
+
+pub(crate) fn record(op: TranscriptOp, label: &[u8], data: &[u8]) {
+    RECORDER.with(|r| {
+        if let Some(events) = r.borrow_mut().as_mut() {
+            events.push(TranscriptEvent { op, label: label.to_vec(), data: data.to_vec() });
+        }
+    });
+}
+
+impl TranscriptLog {
+    pub fn events(&self) -> &[TranscriptEvent] {
+        &self.events
+    }
+
+    /// Index of the first operation that differs from `other`, if any. The
+    /// challenges after it differ as well, so it is the one to look at.
+    pub fn first_mismatch(&self, other: &TranscriptLog) -> Option<usize> {
+        self.events
+            .iter()
+            .zip(other.events.iter())
+            .position(|(a, b)| a != b)
+            .or_else(|| {
+                if self.events.len() != other.events.len() {
+                    Some(self.events.len().min(other.events.len()))
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// One line per operation, with its index, kind, label and data in hex.
+    pub fn dump(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for TranscriptEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self.op {
+            TranscriptOp::Append => "append",
+            TranscriptOp::Challenge => "challenge",
+        };
+        write!(f, "{:<9} {} ", op, String::from_utf8_lossy(&self.label))?;
+        for byte in &self.data {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TranscriptLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, event) in self.events.iter().enumerate() {
+            writeln!(f, "{:>5} {}", i, event)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::transcript::TranscriptProtocol;
+    use curve25519_dalek::scalar::Scalar;
+    use merlin::Transcript;
+
+    fn protocol(n: u64) -> Scalar {
+        let mut transcript = Transcript::new(b"TranscriptDebugTest");
+        transcript.innerproduct_domain_sep(n);
+        transcript.append_scalar(b"s", &Scalar::ONE);
+        transcript.challenge_scalar(b"c")
+    }
+
+    #[test]
+    fn logs_locate_the_mismatch() {
+        let (prover_challenge, prover_log) = record_transcripts(|| protocol(4));
+        let (_, verifier_log) = record_transcripts(|| protocol(4));
+        assert_eq!(prover_log.events().len(), 4);
+        assert_eq!(prover_log.first_mismatch(&verifier_log), None);
+
+        let challenge = &prover_log.events()[3];
+        assert_eq!(challenge.op, TranscriptOp::Challenge);
+        assert_eq!(challenge.label, b"c".to_vec());
+        let mut bytes = [0u8; 64];
+        bytes.copy_from_slice(&challenge.data);
+        assert_eq!(Scalar::from_bytes_mod_order_wide(&bytes), prover_challenge);
+
+        let (_, wrong_log) = record_transcripts(|| protocol(8));
+        assert_eq!(prover_log.first_mismatch(&wrong_log), Some(1));
+        assert!(wrong_log.dump().lines().nth(1).unwrap().ends_with("append    n 0800000000000000"));
+
+        // Nor within `unrecorded`
+        let (_, log) = record_transcripts(|| unrecorded(|| protocol(4)));
+        assert!(log.events().is_empty());
+    }
+}
@@ -5,13 +5,16 @@ extern crate alloc;
 
 use alloc::vec;
 use alloc::vec::Vec;
-use clear_on_drop::clear::Clear;
+use ff::Field;
+#[cfg(feature = "yoloproofs")]
+use zeroize::Zeroize;
 use curve25519_dalek::scalar::Scalar;
 
+use crate::group::PrimeScalar;
 use crate::inner_product_proof::inner_product;
 
 /// Represents a degree-1 vector polynomial \\(\mathbf{a} + \mathbf{b} \cdot x\\).
-pub struct VecPoly1(pub Vec<Scalar>, pub Vec<Scalar>);
+pub struct VecPoly1<S: PrimeScalar = Scalar>(pub Vec<S>, pub Vec<S>);
 
 /// Represents a degree-3 vector polynomial
 /// \\(\mathbf{a} + \mathbf{b} \cdot x + \mathbf{c} \cdot x^2 + \mathbf{d} \cdot x^3 \\).
@@ -24,7 +27,7 @@ pub struct VecPoly3(
 );
 
 /// Represents a degree-2 scalar polynomial \\(a + b \cdot x + c \cdot x^2\\)
-pub struct Poly2(pub Scalar, pub Scalar, pub Scalar);
+pub struct Poly2<S: PrimeScalar = Scalar>(pub S, pub S, pub S);
 
 /// Represents a degree-6 scalar polynomial, without the zeroth degree
 /// \\(a \cdot x + b \cdot x^2 + c \cdot x^3 + d \cdot x^4 + e \cdot x^5 + f \cdot x^6\\)
@@ -41,49 +44,52 @@ pub struct Poly6 {
 /// Provides an iterator over the powers of a `Scalar`.
 ///
 /// This struct is created by the `exp_iter` function.
-pub struct ScalarExp {
-    x: Scalar,
-    next_exp_x: Scalar,
+pub struct ScalarExp<S: Field = Scalar> {
+    x: S,
+    next_exp_x: S,
 }
 
-impl Iterator for ScalarExp {
-    type Item = Scalar;
+impl<S: Field> Iterator for ScalarExp<S> {
+    type Item = S;
 
-    fn next(&mut self) -> Option<Scalar> {
+    fn next(&mut self) -> Option<S> {
         let exp_x = self.next_exp_x;
         self.next_exp_x *= self.x;
         Some(exp_x)
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (usize::max_value(), None)
+        (usize::MAX, None)
     }
 }
 
 /// Return an iterator of the powers of `x`.
-pub fn exp_iter(x: Scalar) -> ScalarExp {
-    let next_exp_x = Scalar::one();
+pub fn exp_iter<S: Field>(x: S) -> ScalarExp<S> {
+    let next_exp_x = S::ONE;
     ScalarExp { x, next_exp_x }
 }
 
-pub fn add_vec(a: &[Scalar], b: &[Scalar]) -> Vec<Scalar> {
+pub fn add_vec<S: Field>(a: &[S], b: &[S]) -> Vec<S> {
     if a.len() != b.len() {
         // throw some error
         //println!("lengths of vectors don't match for vector addition");
     }
-    let mut out = vec![Scalar::zero(); b.len()];
+    let mut out = vec![S::ZERO; b.len()];
     for i in 0..a.len() {
         out[i] = a[i] + b[i];
     }
     out
 }
 
-impl VecPoly1 {
+impl<S: PrimeScalar> VecPoly1<S> {
+    /// The zero polynomial over vectors of length `n`.
     pub fn zero(n: usize) -> Self {
-        VecPoly1(vec![Scalar::zero(); n], vec![Scalar::zero(); n])
+        VecPoly1(vec![S::ZERO; n], vec![S::ZERO; n])
     }
 
-    pub fn inner_product(&self, rhs: &VecPoly1) -> Poly2 {
+    /// The inner product of two vector polynomials of the same length, which is a
+    /// degree-2 scalar polynomial.
+    pub fn inner_product(&self, rhs: &VecPoly1<S>) -> Poly2<S> {
         // Uses Karatsuba's method
         let l = self;
         let r = rhs;
@@ -99,13 +105,10 @@ impl VecPoly1 {
         Poly2(t0, t1, t2)
     }
 
-    pub fn eval(&self, x: Scalar) -> Vec<Scalar> {
+    /// Evaluates the polynomial at `x`.
+    pub fn eval(&self, x: S) -> Vec<S> {
         let n = self.0.len();
-        let mut out = vec![Scalar::zero(); n];
-        for i in 0..n {
-            out[i] = self.0[i] + self.1[i] * x;
-        }
-        out
+        (0..n).map(|i| self.0[i] + self.1[i] * x).collect()
     }
 }
 
@@ -113,16 +116,17 @@ impl VecPoly1 {
 impl VecPoly3 {
     pub fn zero(n: usize) -> Self {
         VecPoly3(
-            vec![Scalar::zero(); n],
-            vec![Scalar::zero(); n],
-            vec![Scalar::zero(); n],
-            vec![Scalar::zero(); n],
+            vec![Scalar::ZERO; n],
+            vec![Scalar::ZERO; n],
+            vec![Scalar::ZERO; n],
+            vec![Scalar::ZERO; n],
         )
     }
 
     /// Compute an inner product of `lhs`, `rhs` which have the property that:
     /// - `lhs.0` is zero;
-    /// - `rhs.2` is zero;
+    /// - `rhs.2` is zero.
+    ///
     /// This is the case in the constraint system proof.
     pub fn special_inner_product(lhs: &Self, rhs: &Self) -> Poly6 {
 
@@ -145,16 +149,13 @@ impl VecPoly3 {
 
     pub fn eval(&self, x: Scalar) -> Vec<Scalar> {
         let n = self.0.len();
-        let mut out = vec![Scalar::zero(); n];
-        for i in 0..n {
-            out[i] = self.0[i] + x * (self.1[i] + x * (self.2[i] + x * self.3[i]));
-        }
-        out
+        (0..n).map(|i| self.0[i] + x * (self.1[i] + x * (self.2[i] + x * self.3[i]))).collect()
     }
 }
 
-impl Poly2 {
-    pub fn eval(&self, x: Scalar) -> Scalar {
+impl<S: PrimeScalar> Poly2<S> {
+    /// Evaluates the polynomial at `x`.
+    pub fn eval(&self, x: S) -> S {
         self.0 + x * (self.1 + x * self.2)
     }
 }
@@ -166,22 +167,22 @@ impl Poly6 {
     }
 }
 
-impl Drop for VecPoly1 {
+impl<S: PrimeScalar> Drop for VecPoly1<S> {
     fn drop(&mut self) {
         for e in self.0.iter_mut() {
-            e.clear();
+            e.zeroize();
         }
         for e in self.1.iter_mut() {
-            e.clear();
+            e.zeroize();
         }
     }
 }
 
-impl Drop for Poly2 {
+impl<S: PrimeScalar> Drop for Poly2<S> {
     fn drop(&mut self) {
-        self.0.clear();
-        self.1.clear();
-        self.2.clear();
+        self.0.zeroize();
+        self.1.zeroize();
+        self.2.zeroize();
     }
 }
 
@@ -189,16 +190,16 @@ impl Drop for Poly2 {
 impl Drop for VecPoly3 {
     fn drop(&mut self) {
         for e in self.0.iter_mut() {
-            e.clear();
+            e.zeroize();
         }
         for e in self.1.iter_mut() {
-            e.clear();
+            e.zeroize();
         }
         for e in self.2.iter_mut() {
-            e.clear();
+            e.zeroize();
         }
         for e in self.3.iter_mut() {
-            e.clear();
+            e.zeroize();
         }
     }
 }
@@ -206,26 +207,26 @@ impl Drop for VecPoly3 {
 #[cfg(feature = "yoloproofs")]
 impl Drop for Poly6 {
     fn drop(&mut self) {
-        self.t1.clear();
-        self.t2.clear();
-        self.t3.clear();
-        self.t4.clear();
-        self.t5.clear();
-        self.t6.clear();
+        self.t1.zeroize();
+        self.t2.zeroize();
+        self.t3.zeroize();
+        self.t4.zeroize();
+        self.t5.zeroize();
+        self.t6.zeroize();
     }
 }
 
 /// Raises `x` to the power `n` using binary exponentiation,
 /// with (1 to 2)*lg(n) scalar multiplications.
 pub fn scalar_exp_vartime(x: &Scalar, mut n: u64) -> Scalar {
-    let mut result = Scalar::one();
+    let mut result = Scalar::ONE;
     let mut aux = *x; // x, x^2, x^4, x^8, ...
     while n > 0 {
         let bit = n & 1;
         if bit == 1 {
-            result = result * aux;
+            result *= aux;
         }
-        n = n >> 1;
+        n >>= 1;
         aux = aux * aux; //
     }
     result
@@ -243,12 +244,12 @@ pub fn sum_of_powers(x: &Scalar, n: usize) -> Scalar {
         return Scalar::from(n as u64);
     }
     let mut m = n;
-    let mut result = Scalar::one() + x;
+    let mut result = Scalar::ONE + x;
     let mut factor = *x;
     while m > 2 {
         factor = factor * factor;
         result = result + factor * result;
-        m = m / 2;
+        m /= 2;
     }
     result
 }
@@ -259,15 +260,34 @@ fn sum_of_powers_slow(x: &Scalar, n: usize) -> Scalar {
 }
 
 /// Given `data` with `len >= 32`, return the first 32 bytes.
+///
+/// Panics if `data` is shorter, see `try_read32` for untrusted inputs.
 pub fn read32(data: &[u8]) -> [u8; 32] {
     let mut buf32 = [0u8; 32];
     buf32[..].copy_from_slice(&data[..32]);
     buf32
 }
 
+/// Returns the first 32 bytes of `data`, or `None` if it is shorter.
+pub fn try_read32(data: &[u8]) -> Option<[u8; 32]> {
+    if data.len() < 32 {
+        return None;
+    }
+    Some(read32(data))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use zeroize::Zeroize;
+
+    #[test]
+    fn read_32_bytes() {
+        let data: Vec<u8> = (0..40).collect();
+        assert_eq!(try_read32(&data), Some(read32(&data)));
+        assert_eq!(read32(&data)[31], 31);
+        assert_eq!(try_read32(&data[..31]), None);
+    }
 
     #[test]
     fn exp_2_is_powers_of_2() {
@@ -298,19 +318,19 @@ mod tests {
 
     /// Raises `x` to the power `n`.
     fn scalar_exp_vartime_slow(x: &Scalar, n: u64) -> Scalar {
-        let mut result = Scalar::one();
+        let mut result = Scalar::ONE;
         for _ in 0..n {
-            result = result * x;
+            result *= x;
         }
         result
     }
 
     #[test]
     fn test_scalar_exp() {
-        let x = Scalar::from_bits(
+        let x = Scalar::from_bytes_mod_order(
             *b"\x84\xfc\xbcOx\x12\xa0\x06\xd7\x91\xd9z:'\xdd\x1e!CE\xf7\xb1\xb9Vz\x810sD\x96\x85\xb5\x07",
         );
-        assert_eq!(scalar_exp_vartime(&x, 0), Scalar::one());
+        assert_eq!(scalar_exp_vartime(&x, 0), Scalar::ONE);
         assert_eq!(scalar_exp_vartime(&x, 1), x);
         assert_eq!(scalar_exp_vartime(&x, 2), x * x);
         assert_eq!(scalar_exp_vartime(&x, 3), x * x * x);
@@ -339,8 +359,8 @@ mod tests {
     #[test]
     fn test_sum_of_powers_slow() {
         let x = Scalar::from(10u64);
-        assert_eq!(sum_of_powers_slow(&x, 0), Scalar::zero());
-        assert_eq!(sum_of_powers_slow(&x, 1), Scalar::one());
+        assert_eq!(sum_of_powers_slow(&x, 0), Scalar::ZERO);
+        assert_eq!(sum_of_powers_slow(&x, 1), Scalar::ONE);
         assert_eq!(sum_of_powers_slow(&x, 2), Scalar::from(11u64));
         assert_eq!(sum_of_powers_slow(&x, 3), Scalar::from(111u64));
         assert_eq!(sum_of_powers_slow(&x, 4), Scalar::from(1111u64));
@@ -349,11 +369,11 @@ mod tests {
     }
 
     #[test]
-    fn vec_of_scalars_clear_on_drop() {
+    fn vec_of_scalars_zeroize_on_drop() {
         let mut v = vec![Scalar::from(24u64), Scalar::from(42u64)];
 
         for e in v.iter_mut() {
-            e.clear();
+            e.zeroize();
         }
 
         fn flat_slice<T>(x: &[T]) -> &[u8] {
@@ -363,22 +383,22 @@ mod tests {
             unsafe { slice::from_raw_parts(x.as_ptr() as *const u8, mem::size_of_val(x)) }
         }
 
-        assert_eq!(flat_slice(&v.as_slice()), &[0u8; 64][..]);
-        assert_eq!(v[0], Scalar::zero());
-        assert_eq!(v[1], Scalar::zero());
+        assert_eq!(flat_slice(v.as_slice()), &[0u8; 64][..]);
+        assert_eq!(v[0], Scalar::ZERO);
+        assert_eq!(v[1], Scalar::ZERO);
     }
 
     #[test]
-    fn tuple_of_scalars_clear_on_drop() {
+    fn tuple_of_scalars_zeroize_on_drop() {
         let mut v = Poly2(
             Scalar::from(24u64),
             Scalar::from(42u64),
             Scalar::from(255u64),
         );
 
-        v.0.clear();
-        v.1.clear();
-        v.2.clear();
+        v.0.zeroize();
+        v.1.zeroize();
+        v.2.zeroize();
 
         fn as_bytes<T>(x: &T) -> &[u8] {
             use core::mem;
@@ -388,8 +408,8 @@ mod tests {
         }
 
         assert_eq!(as_bytes(&v), &[0u8; 96][..]);
-        assert_eq!(v.0, Scalar::zero());
-        assert_eq!(v.1, Scalar::zero());
-        assert_eq!(v.2, Scalar::zero());
+        assert_eq!(v.0, Scalar::ZERO);
+        assert_eq!(v.1, Scalar::ZERO);
+        assert_eq!(v.2, Scalar::ZERO);
     }
 }
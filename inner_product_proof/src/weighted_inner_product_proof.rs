@@ -0,0 +1,624 @@
+#![allow(non_snake_case)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use core::iter;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use ff::{BatchInvert, Field, PrimeField};
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::errors::ProofError;
+use crate::format::{CanonicalPoints, ProofKind, ProofReader, SerializedSize, HEADER_SIZE};
+use crate::group::{CompressedPoint, PrimeGroup, PrimeScalar};
+use crate::inner_product_proof::folding_scalars;
+use crate::msm::{prover_multiscalar_mul, CpuMsmBackend, MsmBackend};
+use crate::transcript::TranscriptProtocol;
+use crate::util;
+
+/// The zero-knowledge weighted inner product argument of
+/// [Bulletproofs+](https://eprint.iacr.org/2020/735).
+///
+/// For a weight \\(y\\), the weighted inner product of two vectors is
+/// \\(\mathbf{a} \odot\_y \mathbf{b} = \sum\_{i=0}^{n-1} a\_i b\_i y^{i+1}\\).
+/// The proof shows knowledge of \\(\mathbf{a}, \mathbf{b}, \alpha\\) such that
+/// \\[
+/// P = \langle \mathbf{a}, \mathbf{G} \rangle + \langle \mathbf{b}, \mathbf{H} \rangle
+///     + (\mathbf{a} \odot\_y \mathbf{b}) \cdot g + \alpha \cdot h,
+/// \\]
+/// without revealing them. Unlike `InnerProductProof`, the argument is
+/// zero knowledge on its own, so the parent protocol does not need to
+/// blind and reveal the inner product separately.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WeightedInnerProductProof<G: PrimeGroup = RistrettoPoint> {
+    pub(crate) L_vec: Vec<G::Compressed>,
+    pub(crate) R_vec: Vec<G::Compressed>,
+    pub(crate) A: G::Compressed,
+    pub(crate) B: G::Compressed,
+    pub(crate) r: G::Scalar,
+    pub(crate) s: G::Scalar,
+    pub(crate) delta: G::Scalar,
+}
+
+/// Bases of the statement of a `WeightedInnerProductProof`: the vector bases
+/// \\(\mathbf{G}, \mathbf{H}\\), the base \\(g\\) of the weighted inner product and
+/// the base \\(h\\) of the blinding factor.
+#[derive(Clone, Debug)]
+pub struct WipGens<G: PrimeGroup = RistrettoPoint> {
+    /// Base for the weighted inner product
+    pub g: G,
+    /// Base for the blinding factor
+    pub h: G,
+    /// Bases for the left hand side vector
+    pub G_vec: Vec<G>,
+    /// Bases for the right hand side vector
+    pub H_vec: Vec<G>,
+}
+
+/// Opening \\(\mathbf{a}, \mathbf{b}, \alpha\\) of the statement of a
+/// `WeightedInnerProductProof`.
+#[derive(Clone, Debug)]
+pub struct WipWitness<S> {
+    /// Left hand side vector
+    pub a_vec: Vec<S>,
+    /// Right hand side vector
+    pub b_vec: Vec<S>,
+    /// Blinding factor
+    pub alpha: S,
+}
+
+/// Scalars of the verification equation of a `WeightedInnerProductProof`,
+/// to be combined with the checks of a parent protocol in a single
+/// multiscalar multiplication.
+///
+/// The equation is
+/// \\[
+/// P \cdot \texttt{P} + \sum\_j (L\_j \cdot \texttt{L}\_j + R\_j \cdot \texttt{R}\_j)
+///     + A \cdot \texttt{A} + B + \langle \texttt{G}, \mathbf{G} \rangle
+///     + \langle \texttt{H}, \mathbf{H} \rangle + g \cdot \texttt{g} + h \cdot \texttt{h} = 0.
+/// \\]
+pub(crate) struct WipVerificationScalars<S> {
+    pub(crate) P: S,
+    pub(crate) L: Vec<S>,
+    pub(crate) R: Vec<S>,
+    pub(crate) A: S,
+    pub(crate) G: Vec<S>,
+    pub(crate) H: Vec<S>,
+    pub(crate) g: S,
+    pub(crate) h: S,
+}
+
+impl<S: Field> WipVerificationScalars<S> {
+    /// Scalars for the points of the proof, in the order of
+    /// `WeightedInnerProductProof::proof_points`.
+    pub(crate) fn proof_scalars(&self) -> impl Iterator<Item = S> + '_ {
+        self.L
+            .iter()
+            .chain(self.R.iter())
+            .cloned()
+            .chain(iter::once(self.A))
+            .chain(iter::once(S::ONE))
+    }
+}
+
+impl<G: PrimeGroup> WeightedInnerProductProof<G> {
+    /// Create a weighted inner-product proof of the `witness` for the weight `y`,
+    /// with respect to the bases `gens`.
+    ///
+    /// The `transcript` is passed in as a parameter so that the
+    /// challenges depend on the *entire* transcript (including parent
+    /// protocols).
+    ///
+    /// The lengths of the vectors must all be the same, and must be a
+    /// power of 2.
+    pub fn create<T: RngCore + CryptoRng>(
+        transcript: &mut Transcript,
+        gens: WipGens<G>,
+        y: G::Scalar,
+        witness: WipWitness<G::Scalar>,
+        rng: &mut T,
+    ) -> WeightedInnerProductProof<G> {
+        WeightedInnerProductProof::create_with_backend(&CpuMsmBackend, transcript, gens, y, witness, rng)
+    }
+
+    /// Create a weighted inner-product proof, computing the \\(L\\) and
+    /// \\(R\\) commitments of each round with the given `MsmBackend`.
+    pub fn create_with_backend<T: RngCore + CryptoRng, B: MsmBackend<G> + ?Sized>(
+        backend: &B,
+        transcript: &mut Transcript,
+        gens: WipGens<G>,
+        y: G::Scalar,
+        witness: WipWitness<G::Scalar>,
+        rng: &mut T,
+    ) -> WeightedInnerProductProof<G> {
+        let WipGens { g, h, mut G_vec, mut H_vec } = gens;
+        let WipWitness { mut a_vec, mut b_vec, mut alpha } = witness;
+        let (g, h) = (&g, &h);
+        let mut G = &mut G_vec[..];
+        let mut H = &mut H_vec[..];
+        let mut a = &mut a_vec[..];
+        let mut b = &mut b_vec[..];
+
+        let mut n = G.len();
+
+        // All of the input vectors must have the same length.
+        assert_eq!(H.len(), n);
+        assert_eq!(a.len(), n);
+        assert_eq!(b.len(), n);
+
+        // All of the input vectors must have a length that is a power of two.
+        assert!(n.is_power_of_two());
+
+        transcript.weighted_innerproduct_domain_sep(n as u64);
+
+        let lg_n = n.trailing_zeros() as usize;
+        let mut L_vec = Vec::with_capacity(lg_n);
+        let mut R_vec = Vec::with_capacity(lg_n);
+
+        // y^1, ..., y^n, the weights of the first half of the vectors of every round,
+        // and their inverses.
+        let exp_y: Vec<G::Scalar> = util::exp_iter(y).skip(1).take(n).collect();
+        let exp_y_inv: Vec<G::Scalar> = util::exp_iter(y.invert().unwrap()).skip(1).take(n).collect();
+
+        while n != 1 {
+            n /= 2;
+            let (a_L, a_R) = a.split_at_mut(n);
+            let (b_L, b_R) = b.split_at_mut(n);
+            let (G_L, G_R) = G.split_at_mut(n);
+            let (H_L, H_R) = H.split_at_mut(n);
+
+            let y_n = exp_y[n - 1];
+            let y_n_inv = exp_y_inv[n - 1];
+
+            let c_L = weighted_inner_product(a_L, b_R, &exp_y);
+            let c_R = y_n * weighted_inner_product(a_R, b_L, &exp_y);
+
+            let d_L = G::Scalar::random(&mut *rng);
+            let d_R = G::Scalar::random(&mut *rng);
+
+            let L = prover_multiscalar_mul(
+                backend,
+                &a_L.iter()
+                    .map(|a_L_i| *a_L_i * y_n_inv)
+                    .chain(b_R.iter().cloned())
+                    .chain(iter::once(c_L))
+                    .chain(iter::once(d_L))
+                    .collect::<Vec<G::Scalar>>(),
+                &G_R.iter()
+                    .chain(H_L.iter())
+                    .chain(iter::once(g))
+                    .chain(iter::once(h))
+                    .cloned()
+                    .collect::<Vec<G>>(),
+            )
+            .compress();
+
+            let R = prover_multiscalar_mul(
+                backend,
+                &a_R.iter()
+                    .map(|a_R_i| *a_R_i * y_n)
+                    .chain(b_L.iter().cloned())
+                    .chain(iter::once(c_R))
+                    .chain(iter::once(d_R))
+                    .collect::<Vec<G::Scalar>>(),
+                &G_L.iter()
+                    .chain(H_R.iter())
+                    .chain(iter::once(g))
+                    .chain(iter::once(h))
+                    .cloned()
+                    .collect::<Vec<G>>(),
+            )
+            .compress();
+
+            L_vec.push(L);
+            R_vec.push(R);
+
+            transcript.append_point(b"L", &L);
+            transcript.append_point(b"R", &R);
+
+            let e: G::Scalar = transcript.challenge_scalar(b"e");
+            let e_inv = e.invert().unwrap();
+
+            for i in 0..n {
+                a_L[i] = a_L[i] * e + e_inv * y_n * a_R[i];
+                b_L[i] = b_L[i] * e_inv + e * b_R[i];
+                G_L[i] = G::vartime_multiscalar_mul(&[e_inv, e * y_n_inv], &[G_L[i], G_R[i]]);
+                H_L[i] = G::vartime_multiscalar_mul(&[e, e_inv], &[H_L[i], H_R[i]]);
+            }
+            alpha += e * e * d_L + e_inv * e_inv * d_R;
+
+            a = a_L;
+            b = b_L;
+            G = G_L;
+            H = H_L;
+        }
+
+        let r = G::Scalar::random(&mut *rng);
+        let s = G::Scalar::random(&mut *rng);
+        let delta = G::Scalar::random(&mut *rng);
+        let eta = G::Scalar::random(&mut *rng);
+
+        let A = prover_multiscalar_mul(
+            backend,
+            &[r, s, y * (r * b[0] + s * a[0]), delta],
+            &[G[0], H[0], *g, *h],
+        )
+        .compress();
+        let B = prover_multiscalar_mul(backend, &[y * r * s, eta], &[*g, *h]).compress();
+
+        transcript.append_point(b"A", &A);
+        transcript.append_point(b"B", &B);
+
+        let e: G::Scalar = transcript.challenge_scalar(b"e");
+
+        WeightedInnerProductProof {
+            L_vec,
+            R_vec,
+            A,
+            B,
+            r: r + a[0] * e,
+            s: s + b[0] * e,
+            delta: eta + delta * e + alpha * e * e,
+        }
+    }
+
+    /// Replays the transcript and computes the scalars of the
+    /// verification equation for vectors of length `n` and the weight `y`.
+    /// The verifier must provide the input length \\(n\\) explicitly to
+    /// avoid unbounded allocation within the proof.
+    pub(crate) fn verification_scalars(
+        &self,
+        n: usize,
+        y: G::Scalar,
+        transcript: &mut Transcript,
+    ) -> Result<WipVerificationScalars<G::Scalar>, ProofError> {
+        let lg_n = self.L_vec.len();
+        if lg_n >= 32 {
+            // 4 billion multiplications should be enough for anyone
+            // and this check prevents overflow in 1<<lg_n below.
+            return Err(ProofError::VerificationError);
+        }
+        if n != (1 << lg_n) {
+            return Err(ProofError::VerificationError);
+        }
+
+        transcript.weighted_innerproduct_domain_sep(n as u64);
+
+        // 1. Recompute the challenges based on the proof transcript
+
+        let mut challenges: Vec<G::Scalar> = Vec::with_capacity(lg_n);
+        for (L, R) in self.L_vec.iter().zip(self.R_vec.iter()) {
+            transcript.validate_and_append_point(b"L", L)?;
+            transcript.validate_and_append_point(b"R", R)?;
+            challenges.push(transcript.challenge_scalar(b"e"));
+        }
+
+        transcript.validate_and_append_point(b"A", &self.A)?;
+        transcript.validate_and_append_point(b"B", &self.B)?;
+
+        let e: G::Scalar = transcript.challenge_scalar(b"e");
+        let e_sq = e * e;
+
+        // 2. Compute the squares of the challenges and of their inverses
+
+        let mut challenges_inv = challenges.clone();
+        let allinv = challenges_inv.iter_mut().batch_invert();
+
+        for i in 0..lg_n {
+            challenges[i] = challenges[i] * challenges[i];
+            challenges_inv[i] = challenges_inv[i] * challenges_inv[i];
+        }
+
+        // 3. Compute the coefficients of the folded generators
+
+        let s = folding_scalars(&challenges, allinv);
+
+        let y_inv = Option::<G::Scalar>::from(y.invert()).ok_or(ProofError::VerificationError)?;
+        let r_e = self.r * e;
+        let s_e = self.s * e;
+
+        Ok(WipVerificationScalars {
+            P: e_sq,
+            L: challenges.iter().map(|e_j_sq| e_sq * e_j_sq).collect(),
+            R: challenges_inv.iter().map(|e_j_inv_sq| e_sq * e_j_inv_sq).collect(),
+            A: e,
+            // 1/s[i] is s[!i], and !i runs from n-1 to 0 as i runs from 0 to n-1
+            G: s.iter().zip(util::exp_iter(y_inv)).map(|(s_i, y_inv_i)| -(r_e * y_inv_i * s_i)).collect(),
+            H: s.iter().rev().map(|s_i_inv| -(s_e * s_i_inv)).collect(),
+            g: -(self.r * self.s * y),
+            h: -self.delta,
+        })
+    }
+
+    /// Points of the proof, in the order of
+    /// `WipVerificationScalars::proof_scalars`.
+    pub(crate) fn proof_points(&self) -> impl Iterator<Item = Option<G>> + '_ {
+        self.L_vec
+            .iter()
+            .chain(self.R_vec.iter())
+            .chain(iter::once(&self.A))
+            .chain(iter::once(&self.B))
+            .map(|p| p.decompress())
+    }
+
+    /// This method is for testing that proof generation work,
+    /// but for efficiency the actual protocols would use `verification_scalars`
+    /// method to combine the verification with other checks
+    /// in a single multiscalar multiplication.
+    #[allow(dead_code)]
+    pub fn verify(
+        &self,
+        n: usize,
+        transcript: &mut Transcript,
+        P: &G,
+        gens: &WipGens<G>,
+        y: G::Scalar,
+    ) -> Result<(), ProofError> {
+        let scalars = self.verification_scalars(n, y, transcript)?;
+
+        let points = self.proof_points().collect::<Option<Vec<G>>>()
+            .ok_or(ProofError::VerificationError)?;
+
+        let _span = msm_span!("weighted_inner_product", size = points.len() + 1);
+        let check = G::vartime_multiscalar_mul(
+            &iter::once(scalars.P)
+                .chain(scalars.proof_scalars())
+                .chain(scalars.G.iter().cloned())
+                .chain(scalars.H.iter().cloned())
+                .chain(iter::once(scalars.g))
+                .chain(iter::once(scalars.h))
+                .collect::<Vec<G::Scalar>>(),
+            &iter::once(P)
+                .chain(points.iter())
+                .chain(gens.G_vec.iter())
+                .chain(gens.H_vec.iter())
+                .chain(iter::once(&gens.g))
+                .chain(iter::once(&gens.h))
+                .cloned()
+                .collect::<Vec<G>>(),
+        );
+
+        if bool::from(check.is_identity()) {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Returns the size in bytes required to serialize the proof.
+    ///
+    /// For vectors of length `n` the proof has \\(2\lg n + 2\\) compressed
+    /// points and three scalars, which over Ristretto is
+    /// \\(32 \cdot (2\lg n+5)\\) bytes, after the header.
+    pub fn serialized_size(&self) -> usize {
+        HEADER_SIZE + self.body_size()
+    }
+
+    /// Size of the proof when nested in another proof, without the header.
+    pub(crate) fn body_size(&self) -> usize {
+        (self.L_vec.len() * 2 + 2) * G::Compressed::SIZE + 3 * G::Scalar::SIZE
+    }
+
+    /// Serializes the proof into a byte array, after the header (see [`ProofKind`]).
+    /// The layout of the weighted inner product proof is:
+    /// * \\(n\\) pairs of compressed points \\(L_0, R_0 \dots, L_{n-1}, R_{n-1}\\),
+    /// * two compressed points \\(A, B\\),
+    /// * three scalars \\(r', s', \delta'\\).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        ProofKind::WeightedInnerProduct.header().iter().copied().chain(self.to_bytes_iter()).collect()
+    }
+
+    /// Converts the proof into a byte iterator over serialized view of the proof,
+    /// with the layout of `to_bytes` without the header.
+    #[inline]
+    pub(crate) fn to_bytes_iter(&self) -> impl Iterator<Item = u8> + '_ {
+        let scalars: Vec<u8> = [self.r, self.s, self.delta]
+            .iter()
+            .flat_map(|x| x.to_repr().as_ref().to_vec())
+            .collect();
+        self.L_vec
+            .iter()
+            .zip(self.R_vec.iter())
+            .flat_map(|(l, r)| l.as_bytes().iter().chain(r.as_bytes()))
+            .chain(self.A.as_bytes())
+            .chain(self.B.as_bytes())
+            .copied()
+            .chain(scalars)
+    }
+
+    /// Deserializes the proof from a byte slice.
+    /// Returns an error in the following cases:
+    /// * the header is not the one of a weighted inner product proof in a supported version,
+    /// * the slice does not have \\(2n+2\\) points and 3 scalars,
+    /// * \\(n\\) is larger or equal to 32 (proof is too big),
+    /// * any of 3 scalars are not canonical scalars modulo the group order.
+    ///
+    /// The points are only decompressed when verifying.
+    pub fn from_bytes(slice: &[u8]) -> Result<WeightedInnerProductProof<G>, ProofError> {
+        match ProofKind::WeightedInnerProduct.read_header(slice)? {
+            (1, body) => WeightedInnerProductProof::read_v1(&mut ProofReader::new(body)),
+            _ => Err(ProofError::FormatError),
+        }
+    }
+
+    /// Reads the proof in the first version of the format, without the header,
+    /// as nested in other proofs. The number of rounds is given by the length
+    /// of the encoding, so the proof takes all the remaining bytes.
+    pub(crate) fn read_v1(reader: &mut ProofReader) -> Result<WeightedInnerProductProof<G>, ProofError> {
+        let point_size = G::Compressed::SIZE;
+        let points_size = reader
+            .remaining()
+            .checked_sub(2 * point_size + 3 * G::Scalar::SIZE)
+            .ok_or(ProofError::FormatError)?;
+        if points_size % (2 * point_size) != 0 {
+            return Err(ProofError::FormatError);
+        }
+        let lg_n = points_size / (2 * point_size);
+        if lg_n >= 32 {
+            return Err(ProofError::FormatError);
+        }
+
+        let mut L_vec: Vec<G::Compressed> = Vec::with_capacity(lg_n);
+        let mut R_vec: Vec<G::Compressed> = Vec::with_capacity(lg_n);
+        for _ in 0..lg_n {
+            L_vec.push(reader.read_point()?);
+            R_vec.push(reader.read_point()?);
+        }
+        let A = reader.read_point()?;
+        let B = reader.read_point()?;
+        let r = reader.read_scalar()?;
+        let s = reader.read_scalar()?;
+        let delta = reader.read_scalar()?;
+
+        Ok(WeightedInnerProductProof { L_vec, R_vec, A, B, r, s, delta })
+    }
+
+    /// Deserializes the proof as `from_bytes`, and also checks that all
+    /// the points are canonical encodings of group elements.
+    pub fn from_bytes_strict(slice: &[u8]) -> Result<WeightedInnerProductProof<G>, ProofError> {
+        let proof = WeightedInnerProductProof::<G>::from_bytes(slice)?;
+        if !proof.points_are_canonical() {
+            return Err(ProofError::FormatError);
+        }
+        Ok(proof)
+    }
+}
+
+impl<G: PrimeGroup> CanonicalPoints for WeightedInnerProductProof<G> {
+    fn points_are_canonical(&self) -> bool {
+        self.L_vec.points_are_canonical()
+            && self.R_vec.points_are_canonical()
+            && [self.A, self.B].points_are_canonical()
+    }
+}
+
+impl<G: PrimeGroup> SerializedSize for WeightedInnerProductProof<G> {
+    fn serialized_size(&self) -> usize {
+        WeightedInnerProductProof::serialized_size(self)
+    }
+}
+
+impl<G: PrimeGroup> PartialOrd for WeightedInnerProductProof<G> {
+    fn partial_cmp(&self, other: &WeightedInnerProductProof<G>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<G: PrimeGroup> Ord for WeightedInnerProductProof<G> {
+    fn cmp(&self, other: &WeightedInnerProductProof<G>) -> Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
+impl<G: PrimeGroup> Hash for WeightedInnerProductProof<G> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+/// Computes the weighted inner product
+/// \\(\sum\_{i=0}^{n-1} a\_i b\_i y^{i+1}\\), given the weights
+/// \\(y^1, \dots, y^n\\) in `exp_y`.
+/// Panics if the lengths of \\(\mathbf{a}\\) and \\(\mathbf{b}\\) are not equal.
+fn weighted_inner_product<S: Field>(a: &[S], b: &[S], exp_y: &[S]) -> S {
+    if a.len() != b.len() {
+        panic!("weighted_inner_product(a,b): lengths of vectors do not match");
+    }
+    a.iter()
+        .zip(b.iter())
+        .zip(exp_y.iter())
+        .fold(S::ZERO, |acc, ((a_i, b_i), y_i)| acc + *a_i * b_i * y_i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::generators::{BulletproofGens, PedersenGens};
+    use curve25519_dalek::scalar::Scalar;
+    use rand_core::SeedableRng;
+
+    use rand_chacha::ChaChaRng;
+
+    fn test_helper_create<G: PrimeGroup>(n: usize) {
+        let mut test_rng = ChaChaRng::from_seed([24u8; 32]);
+
+        let bp_gens = BulletproofGens::<G>::new(n, 1);
+        let pc_gens = PedersenGens::<G>::default();
+        let G: Vec<G> = bp_gens.share(0).G(n).cloned().collect();
+        let H: Vec<G> = bp_gens.share(0).H(n).cloned().collect();
+
+        let a: Vec<G::Scalar> = (0..n).map(|_| G::Scalar::random(&mut test_rng)).collect();
+        let b: Vec<G::Scalar> = (0..n).map(|_| G::Scalar::random(&mut test_rng)).collect();
+        let alpha = G::Scalar::random(&mut test_rng);
+
+        // y would be a challenge of the parent protocol, so we pick a random one.
+        let y = G::Scalar::random(&mut test_rng);
+        let exp_y: Vec<G::Scalar> = util::exp_iter(y).skip(1).take(n).collect();
+        let c = weighted_inner_product(&a, &b, &exp_y);
+
+        let P = G::vartime_multiscalar_mul(
+            &a.iter().chain(b.iter()).chain(iter::once(&c)).chain(iter::once(&alpha)).cloned()
+                .collect::<Vec<G::Scalar>>(),
+            &G.iter().chain(H.iter()).chain(iter::once(&pc_gens.B)).chain(iter::once(&pc_gens.B_blinding))
+                .cloned().collect::<Vec<G>>(),
+        );
+
+        let gens = WipGens { g: pc_gens.B, h: pc_gens.B_blinding, G_vec: G, H_vec: H };
+        let mut transcript = Transcript::new(b"weightedinnerproducttest");
+        let proof = WeightedInnerProductProof::create(
+            &mut transcript,
+            gens.clone(),
+            y,
+            WipWitness { a_vec: a, b_vec: b, alpha },
+            &mut test_rng,
+        );
+        assert_eq!(proof.to_bytes().len(), proof.serialized_size());
+
+        let mut transcript = Transcript::new(b"weightedinnerproducttest");
+        assert!(proof
+            .verify(n, &mut transcript, &P, &gens, y)
+            .is_ok());
+
+        let proof = WeightedInnerProductProof::<G>::from_bytes(proof.to_bytes().as_slice()).unwrap();
+        let mut transcript = Transcript::new(b"weightedinnerproducttest");
+        assert!(proof
+            .verify(n, &mut transcript, &P, &gens, y)
+            .is_ok());
+
+        // A statement with a different weighted inner product does not verify
+        let wrong_P = P + pc_gens.B;
+        let mut transcript = Transcript::new(b"weightedinnerproducttest");
+        assert!(proof
+            .verify(n, &mut transcript, &wrong_P, &gens, y)
+            .is_err());
+    }
+
+    #[test]
+    fn make_wip_1() {
+        test_helper_create::<RistrettoPoint>(1);
+    }
+
+    #[test]
+    fn make_wip_2() {
+        test_helper_create::<RistrettoPoint>(2);
+    }
+
+    #[test]
+    fn make_wip_32() {
+        test_helper_create::<RistrettoPoint>(32);
+    }
+
+    #[test]
+    fn test_weighted_inner_product() {
+        let a = vec![Scalar::from(1u64), Scalar::from(2u64), Scalar::from(3u64)];
+        let b = vec![Scalar::from(2u64), Scalar::from(3u64), Scalar::from(4u64)];
+        let exp_y: Vec<Scalar> = util::exp_iter(Scalar::from(2u64)).skip(1).take(3).collect();
+        // 2 * 2 + 6 * 4 + 12 * 8
+        assert_eq!(Scalar::from(124u64), weighted_inner_product(&a, &b, &exp_y));
+    }
+}
@@ -0,0 +1,123 @@
+//! A common interface to the proofs of the crate and of the crates built on it.
+//!
+//! Every proof type takes its own arguments to prove and verify, e.g. the
+//! bitsize of a range proof or the pair of generators of an equality proof.
+//! [`ZkProof`] groups them into public parameters, known to both parties,
+//! a witness, only known to the prover, and a statement, which the prover
+//! computes from the witness and hands to the verifier next to the proof.
+//! Code that handles proofs generically (benchmarks, test vectors, network
+//! services) can then prove, serialize, parse and verify any of them.
+//!
+//! The trait covers the proofs that stand on their own: the range and inner
+//! product proofs of this crate, and the dlog, opening, equality, removal,
+//! sparse, padding, count and hidden count proofs of
+//! `pedersen_commitments_proofs`. It is not implemented for
+//! * the proofs of the features, e.g. the average, variance, std, diff and
+//!   square proofs, which are steps of `zkSVMProver`: they share its
+//!   transcript and commitments, and are not verified on their own;
+//! * `zkSVMProver` and the proofs built on it, e.g. `ClassificationProof`,
+//!   which are parsed against the `PublicParams` whose fingerprint they carry,
+//!   so `from_bytes` does not apply to them.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+
+/// A zero-knowledge proof with its public parameters, witness and statement.
+pub trait ZkProof: Sized {
+    /// Public parameters of the proof, such as the generators, borrowed for
+    /// the duration of a call.
+    type Params<'a>: Copy
+    where
+        Self: 'a;
+    /// Secret values known to the prover.
+    type Witness<'a>
+    where
+        Self: 'a;
+    /// Public values the proof is about, e.g. the commitments to the witness.
+    type Statement;
+    /// Error returned when proving, verifying or parsing fails.
+    type Error;
+
+    /// Creates a proof for `witness`, and returns it with its statement.
+    fn prove<R: RngCore + CryptoRng>(
+        params: Self::Params<'_>,
+        witness: Self::Witness<'_>,
+        transcript: &mut Transcript,
+        rng: &mut R,
+    ) -> Result<(Self, Self::Statement), Self::Error>;
+
+    /// Verifies the proof for `statement`, over a transcript with the same
+    /// initial state as the one of the prover.
+    fn verify(
+        &self,
+        params: Self::Params<'_>,
+        statement: &Self::Statement,
+        transcript: &mut Transcript,
+    ) -> Result<(), Self::Error>;
+
+    /// Serializes the proof.
+    fn to_bytes(&self) -> Vec<u8>;
+
+    /// Parses a proof serialized with `to_bytes`.
+    fn from_bytes(slice: &[u8]) -> Result<Self, Self::Error>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+    use rand::thread_rng;
+
+    use crate::errors::ProofError;
+    use crate::generators::{BulletproofGens, PedersenGens};
+    use crate::ip_zk_proof::{InnerProductZKProof, InnerProductZKProofPlus};
+    use crate::range_proof::{RangeProof, RangeProofPlus};
+
+    fn round_trip<P>(params: P::Params<'_>, witness: P::Witness<'_>) -> Result<(), P::Error>
+    where
+        P: ZkProof<Error = ProofError>,
+    {
+        let mut rng = thread_rng();
+        let (proof, statement) = P::prove(params, witness, &mut Transcript::new(b"ZkProofTest"), &mut rng)?;
+        let proof = P::from_bytes(&proof.to_bytes())?;
+        proof.verify(params, &statement, &mut Transcript::new(b"ZkProofTest"))?;
+        assert!(proof.verify(params, &statement, &mut Transcript::new(b"OtherTest")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn range_proofs() {
+        let bp_gens = BulletproofGens::new(32, 2);
+        let pc_gens = PedersenGens::default();
+        let mut rng = thread_rng();
+        let values = [7u64, 1 << 20];
+        let blindings = [Scalar::random(&mut rng), Scalar::random(&mut rng)];
+
+        assert!(round_trip::<RangeProof>((&bp_gens, &pc_gens, 32), (&values, &blindings)).is_ok());
+        assert!(round_trip::<RangeProofPlus>((&bp_gens, &pc_gens, 32), (&values, &blindings)).is_ok());
+        assert_eq!(
+            round_trip::<RangeProof>((&bp_gens, &pc_gens, 32), (&values, &blindings[..1])),
+            Err(ProofError::WrongNumBlindingFactors)
+        );
+    }
+
+    #[test]
+    fn inner_product_proofs() {
+        let n = 16;
+        let bp_gens = BulletproofGens::new(n, 1);
+        let pc_gens = PedersenGens::default();
+        let mut rng = thread_rng();
+        let lhs: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let rhs: Vec<Scalar> = (0..n).map(|_| Scalar::random(&mut rng)).collect();
+        let v = InnerProductZKProof::inner_product(&lhs, &rhs);
+        let witness = || (v, &lhs, &rhs, Scalar::random(&mut thread_rng()), Scalar::random(&mut thread_rng()));
+
+        assert!(round_trip::<InnerProductZKProof>((&bp_gens, &pc_gens, n), witness()).is_ok());
+        assert!(round_trip::<InnerProductZKProofPlus>((&bp_gens, &pc_gens, n), witness()).is_ok());
+    }
+}
@@ -0,0 +1,302 @@
+#![allow(non_snake_case)]
+//! Benchmarks of the composite proofs of `zkSVMProver`, created and verified as the prover and
+//! the verifier do. `DiffProofs`, `AvgProof` and `VarianceProof` prove the four windows of a
+//! zkSVM proof, and are parameterized by the window size and the number of readings per
+//! window. `StdProof` does not depend on the window size, and is parameterized by the number of
+//! sensor vectors it covers.
+#[macro_use]
+extern crate criterion;
+
+use criterion::{BenchmarkId, Criterion};
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use pedersen_commitments_proofs::algebraic_proofs::average_proof::AvgProof;
+use pedersen_commitments_proofs::algebraic_proofs::diff_vector_gen_proof::DiffProofs;
+use pedersen_commitments_proofs::algebraic_proofs::std_proof::StdProof;
+use pedersen_commitments_proofs::algebraic_proofs::variance_proof::VarianceProof;
+use pedersen_commitments_proofs::utils::commitment_fns::multiple_commit;
+use pedersen_commitments_proofs::utils::misc::{all_sensors_diff_points, decompress_all};
+use pedersen_commitments_proofs::utils::randomness::random_scalar_matrix;
+use pedersen_commitments_proofs::{FeatureWitness, PedersenConfig, PublicParams, SensorFeatures};
+
+/// Window sizes, i.e. the size of the vectors and of the generators.
+const WINDOW_SIZES: [usize; 3] = [64, 128, 256];
+
+/// Fractions of the window filled with readings, in percent.
+const FILL_RATES: [usize; 2] = [50, 100];
+
+/// Numbers of sensor vectors of the standard deviation proofs.
+const SENSOR_COUNTS: [usize; 4] = [1, 2, 4, 8];
+
+/// The witness of four windows of `size` elements with `readings` readings each, which are
+/// within 10^8 +- 5 * 10^5 as in a window of the dataset, and their signed commitments.
+struct Windows {
+    size: usize,
+    config: PedersenConfig,
+    witness: FeatureWitness,
+    signed_commitments: Vec<Vec<RistrettoPoint>>,
+    signed_blindings: Vec<Vec<Scalar>>,
+}
+
+impl Windows {
+    fn new(size: usize, readings: usize) -> Windows {
+        let mut rng = StdRng::seed_from_u64(size as u64 * 1000 + readings as u64);
+        let mut axis = || -> Vec<i128> {
+            (0..size)
+                .map(|i| if i < readings { rng.gen_range(99_500_000i128..100_500_000) } else { 0 })
+                .collect()
+        };
+        let windows: Vec<[Vec<i128>; 3]> = (0..4).map(|_| [axis(), axis(), axis()]).collect();
+        let witness = SensorFeatures::extract(&windows, &[readings; 4]).witness().unwrap();
+
+        let config = PublicParams::new(size).config();
+        let (signed_commitments, signed_blindings) =
+            multiple_commit(config.G_vec(), &witness.input_vector[..4]);
+        Windows {
+            size,
+            config,
+            witness,
+            signed_commitments: decompress_all(&signed_commitments).unwrap(),
+            signed_blindings,
+        }
+    }
+
+    /// Creates the diff proofs, and returns them with the blinding factors of the diff
+    /// commitments.
+    fn create_diff(&self) -> (DiffProofs, Vec<Vec<Scalar>>) {
+        DiffProofs::create(
+            &self.witness.input_vector[..4],
+            &self.witness.diff_vectors,
+            &self.signed_blindings,
+            &self.config,
+            &self.witness.non_zero_elements,
+        )
+    }
+
+    /// The iterated and the diff commitments, as decompressed by the verifier.
+    fn diff_commitments(&self, proof: &DiffProofs) -> (Vec<Vec<RistrettoPoint>>, Vec<Vec<RistrettoPoint>>) {
+        let iter_commitments = decompress_all(&proof.iter_commitments).unwrap();
+        let diff_commitments = all_sensors_diff_points(&self.signed_commitments, &iter_commitments);
+        (iter_commitments, diff_commitments)
+    }
+
+    fn verify_diff(&self, proof: &DiffProofs) {
+        let (iter_commitments, diff_commitments) = self.diff_commitments(proof);
+        proof.clone().verify(
+            &self.signed_commitments,
+            &iter_commitments,
+            &diff_commitments,
+            &self.config,
+            &self.witness.non_zero_elements,
+        ).unwrap();
+    }
+
+    fn create_avg(&self, diff_blindings: &[Vec<Scalar>]) -> AvgProof {
+        let mut blindings = self.signed_blindings.clone();
+        blindings.extend(diff_blindings.iter().cloned());
+        AvgProof::create(
+            &self.config.get_bp_gens(),
+            self.config.pedersen_gens(),
+            &self.config.acc_bases_G(&self.witness.non_zero_elements),
+            &self.config.acc_bases_H(&self.witness.non_zero_elements),
+            &self.witness.input_vector,
+            &random_scalar_matrix(&mut rand::thread_rng(), &[3; 8]),
+            &blindings,
+            self.config.labels(),
+        ).unwrap()
+    }
+
+    fn verify_avg(&self, proof: &AvgProof) {
+        proof.verify(
+            &self.config.get_bp_gens(),
+            self.config.pedersen_gens(),
+            &self.config.acc_bases_G(&self.witness.non_zero_elements),
+            &self.config.acc_bases_H(&self.witness.non_zero_elements),
+            self.size,
+            self.config.labels(),
+        ).unwrap();
+    }
+
+    fn create_variance(&self, diff_blindings: &[Vec<Scalar>]) -> VarianceProof {
+        VarianceProof::create(
+            &self.witness.input_vector,
+            &self.witness.stds,
+            &self.witness.additions,
+            &self.witness.variances,
+            &self.config.get_bp_gens(),
+            self.config.pedersen_gens(),
+            self.config.G_vec(),
+            self.config.H_vec(),
+            &self.signed_blindings,
+            diff_blindings,
+            &self.witness.non_zero_elements,
+            self.size,
+            self.config.labels(),
+        ).unwrap().0
+    }
+
+    fn verify_variance(&self, proof: &VarianceProof, proof_diff: &DiffProofs, proof_avg: &AvgProof) {
+        let (_, diff_commitments) = self.diff_commitments(proof_diff);
+        proof.clone().verify(
+            &self.signed_commitments,
+            &diff_commitments,
+            &proof_diff.last_exp(),
+            &proof_avg.average_commitment_base_G,
+            &proof_avg.average_commitment_base_H,
+            &self.config.get_bp_gens(),
+            self.config.pedersen_gens(),
+            self.config.G_vec(),
+            self.config.H_vec(),
+            &self.witness.non_zero_elements,
+            self.size,
+            proof_avg.average_commitment.len(),
+            self.config.labels(),
+        ).unwrap();
+    }
+}
+
+/// Runs `bench` over the windows of every window size and number of readings.
+fn over_windows<F: FnMut(&str, &Windows)>(mut bench: F) {
+    for &size in WINDOW_SIZES.iter() {
+        for &fill in FILL_RATES.iter() {
+            let readings = size * fill / 100;
+            bench(&format!("{}/{}", size, readings), &Windows::new(size, readings));
+        }
+    }
+}
+
+fn diff_proofs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("DiffProofs");
+    over_windows(|id, windows| {
+        group.bench_with_input(BenchmarkId::new("create", id), windows, |b, windows| {
+            b.iter(|| windows.create_diff())
+        });
+        let (proof, _) = windows.create_diff();
+        group.bench_with_input(BenchmarkId::new("verify", id), windows, |b, windows| {
+            b.iter(|| windows.verify_diff(&proof))
+        });
+    });
+    group.finish();
+}
+
+fn avg_proof(c: &mut Criterion) {
+    let mut group = c.benchmark_group("AvgProof");
+    over_windows(|id, windows| {
+        let (_, diff_blindings) = windows.create_diff();
+        group.bench_with_input(BenchmarkId::new("create", id), windows, |b, windows| {
+            b.iter(|| windows.create_avg(&diff_blindings))
+        });
+        let proof = windows.create_avg(&diff_blindings);
+        group.bench_with_input(BenchmarkId::new("verify", id), windows, |b, windows| {
+            b.iter(|| windows.verify_avg(&proof))
+        });
+    });
+    group.finish();
+}
+
+fn variance_proof(c: &mut Criterion) {
+    let mut group = c.benchmark_group("VarianceProof");
+    over_windows(|id, windows| {
+        let (proof_diff, diff_blindings) = windows.create_diff();
+        let proof_avg = windows.create_avg(&diff_blindings);
+        group.bench_with_input(BenchmarkId::new("create", id), windows, |b, windows| {
+            b.iter(|| windows.create_variance(&diff_blindings))
+        });
+        let proof = windows.create_variance(&diff_blindings);
+        group.bench_with_input(BenchmarkId::new("verify", id), windows, |b, windows| {
+            b.iter(|| windows.verify_variance(&proof, &proof_diff, &proof_avg))
+        });
+    });
+    group.finish();
+}
+
+/// Standard deviations of `nr_sensors` sensor vectors, three axes each, with the variances
+/// they are the integer square roots of, and the commitments to both.
+struct Stds {
+    stds: Vec<Vec<Scalar>>,
+    variances: Vec<Vec<Scalar>>,
+    std_commitments: Vec<Vec<CompressedRistretto>>,
+    variance_commitments: Vec<Vec<CompressedRistretto>>,
+    std_blindings: Vec<Vec<Scalar>>,
+    variance_blindings: Vec<Vec<Scalar>>,
+}
+
+impl Stds {
+    fn new(config: &PedersenConfig, nr_sensors: usize) -> Stds {
+        let mut rng = StdRng::seed_from_u64(nr_sensors as u64);
+        let (stds, variances): (Vec<Vec<Scalar>>, Vec<Vec<Scalar>>) = (0..nr_sensors)
+            .map(|_| (0..3).map(|_| {
+                let std: u64 = rng.gen_range(1..1 << 31);
+                (Scalar::from(std), Scalar::from(std * std + rng.gen_range(0..2 * std + 1)))
+            }).unzip())
+            .unzip();
+        let shape = vec![3; nr_sensors];
+        let std_blindings = random_scalar_matrix(&mut rng, &shape);
+        let variance_blindings = random_scalar_matrix(&mut rng, &shape);
+        let commit = |values: &Vec<Vec<Scalar>>, blindings: &Vec<Vec<Scalar>>| -> Vec<Vec<CompressedRistretto>> {
+            values.iter().zip(blindings.iter()).map(
+                |(v, b)| v.iter().zip(b.iter()).map(|(v, b)| config.pedersen_gens().commit(*v, *b).compress()).collect()
+            ).collect()
+        };
+        Stds {
+            std_commitments: commit(&stds, &std_blindings),
+            variance_commitments: commit(&variances, &variance_blindings),
+            stds,
+            variances,
+            std_blindings,
+            variance_blindings,
+        }
+    }
+
+    fn create(&self, config: &PedersenConfig) -> Vec<Vec<StdProof>> {
+        StdProof::create_all(
+            &config.get_bp_gens(),
+            config.pedersen_gens(),
+            &self.stds,
+            &self.variances,
+            &self.std_commitments,
+            &self.std_blindings,
+            &self.variance_blindings,
+            config.labels(),
+        ).unwrap()
+    }
+}
+
+fn std_proof(c: &mut Criterion) {
+    let mut group = c.benchmark_group("StdProof");
+    // The range proofs of the standard deviations only need the smallest generators
+    let config = PublicParams::new(WINDOW_SIZES[0]).config();
+    for &nr_sensors in SENSOR_COUNTS.iter() {
+        let stds = Stds::new(&config, nr_sensors);
+        group.bench_with_input(BenchmarkId::new("create", nr_sensors), &stds, |b, stds| {
+            b.iter(|| stds.create(&config))
+        });
+        let proofs = stds.create(&config);
+        group.bench_with_input(BenchmarkId::new("verify", nr_sensors), &stds, |b, stds| {
+            b.iter(|| StdProof::verify_all(
+                &config.get_bp_gens(),
+                config.pedersen_gens(),
+                &stds.std_commitments,
+                &stds.variance_commitments,
+                &proofs,
+                config.labels(),
+            ).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets =
+    diff_proofs,
+    avg_proof,
+    variance_proof,
+    std_proof
+);
+
+criterion_main!(benches);
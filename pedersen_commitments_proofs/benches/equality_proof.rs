@@ -3,17 +3,17 @@
 extern crate criterion;
 
 use criterion::Criterion;
-use pedersen_commitments_proofs::equality_proof::EqualityZKProof;
+use pedersen_commitments_proofs::boolean_proofs::equality_proof::EqualityZKProof;
 use pedersen_commitments_proofs::PedersenVecGens;
 
 use curve25519_dalek::scalar::Scalar;
 
 use merlin::Transcript;
 
-use rand_core::OsRng;
+use rand::rngs::OsRng;
 
 fn prove_equality(c: &mut Criterion) {
-    let label = format!("Proving equality of openings");
+    let label = "Proving equality of openings".to_string();
     c.bench_function(&label, move |b| {
         let size = 128;
         let ped_gens_1 = PedersenVecGens::new(size);
@@ -39,7 +39,7 @@ fn prove_equality(c: &mut Criterion) {
 }
 
 fn verify_equality_proof(c: &mut Criterion) {
-    let label = format!("Verifying equality proof of openings");
+    let label = "Verifying equality proof of openings".to_string();
     c.bench_function(&label, move |b| {
         let size = 128;
         let ped_gens_1 = PedersenVecGens::new(size);
@@ -3,17 +3,17 @@
 extern crate criterion;
 
 use criterion::Criterion;
-use pedersen_commitments_proofs::opening_proof::OpeningZKProof;
+use pedersen_commitments_proofs::boolean_proofs::opening_proof::OpeningZKProof;
 use pedersen_commitments_proofs::PedersenVecGens;
 
 use curve25519_dalek::scalar::Scalar;
 
 use merlin::Transcript;
 
-use rand_core::OsRng;
+use rand::rngs::OsRng;
 
 fn prove_equality(c: &mut Criterion) {
-    let label = format!("Proving opening knowledge of openings");
+    let label = "Proving opening knowledge of openings".to_string();
     c.bench_function(&label, move |b| {
         let size = 128;
         let ped_gens = PedersenVecGens::new(size);
@@ -30,7 +30,7 @@ fn prove_equality(c: &mut Criterion) {
 }
 
 fn verify_equality_proof(c: &mut Criterion) {
-    let label = format!("Verifying opening knowledge proof of openings");
+    let label = "Verifying opening knowledge proof of openings".to_string();
     c.bench_function(&label, move |b| {
         let size = 128;
         let ped_gens = PedersenVecGens::new(size);
@@ -8,13 +8,13 @@ use curve25519_dalek::scalar::Scalar;
 
 use merlin::Transcript;
 
-use pedersen_commitments_proofs::square_proof::FloatingSquareZKProof;
+use pedersen_commitments_proofs::boolean_proofs::square_proof::FloatingSquareZKProof;
 use rand::thread_rng;
 
 use ip_zk_proof::{PedersenGens, BulletproofGens};
 
 fn prove_rounded_sqr(c: &mut Criterion) {
-    let label = format!("Proving rounded square root relation of commitments");
+    let label = "Proving rounded square root relation of commitments".to_string();
     c.bench_function(&label, move |b| {
         let bulletproof_generators = BulletproofGens::new(32, 1);
         let pedersen_generators = PedersenGens::default();
@@ -48,7 +48,7 @@ fn prove_rounded_sqr(c: &mut Criterion) {
 }
 
 fn verify_rounded_sqr_proof(c: &mut Criterion) {
-    let label = format!("Verifying rounded square root proof");
+    let label = "Verifying rounded square root proof".to_string();
     c.bench_function(&label, move |b| {
         let bulletproof_generators = BulletproofGens::new(32, 1);
         let pedersen_generators = PedersenGens::default();
@@ -1,33 +1,45 @@
-use ip_zk_proof::{InnerProductZKProof, BulletproofGens, PedersenGens, inner_product, ProofError};
+use alloc::vec::Vec;
+use alloc::vec;
+use ip_zk_proof::{CanonicalPoints, InnerProductZKProof, BulletproofGens, PedersenGens, inner_product, ProofError, SerializedSize};
+#[cfg(not(feature = "prover-only"))]
+use ip_zk_proof::PrecomputedGens;
 
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::ristretto::{RistrettoPoint, CompressedRistretto};
 
 use core::iter;
-use merlin::Transcript;
-use zkp::CompactProof;
-
-// ZKPs macros
-define_proof! {
-          avg_comm_proof,   // Name of the module for generated implementation
-          "AvgComm",       // Label for the proof statement
-          (x, r),         // Secret variables
-          (A, G, B, H),   // Public variables unique to each proof
-          (C) :        // Public variables common between proofs
-          A = (x * G + r * B), // Statements to prove
-          C = (x * H)
+use crate::sigma::{PointVar, Prover, ScalarVar, SchnorrCS, SigmaProof, Verifier};
+use crate::metrics::{count, PhaseMetrics, ProofPhase, ProverMetrics, Stopwatch};
+#[cfg(feature = "std")]
+use crate::metrics::NoMetrics;
+use crate::TranscriptLabels;
+
+use crate::utils::misc::map_sensor_coords;
+use crate::utils::misc::bincode_size;
+use crate::utils::randomness::random_seed_matrix;
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand_chacha::ChaChaRng;
+use rand_core::{CryptoRng, CryptoRngCore, RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Proves knowledge of the opening `(x, r)` of the commitment `A = x * G + r * B`, and that
+/// `C` commits to the same value under the base `H`, i.e. `C = x * H`.
+fn avg_comm_statement<CS: SchnorrCS>(
+    cs: &mut CS,
+    x: ScalarVar,
+    r: ScalarVar,
+    A: PointVar,
+    G: PointVar,
+    B: PointVar,
+    C: PointVar,
+    H: PointVar,
+) {
+    cs.constrain(A, vec![(x, G), (r, B)]);
+    cs.constrain(C, vec![(x, H)]);
 }
 
-define_proof! {
-    dlog,
-    "DLog",
-    (x),
-    (A),
-    (G) :
-    A = (x * G)
-}
-
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
 /// We describe the AvgProof structure, which encapsulates all the proves necessary around the
 /// average. In our paper we calculate the Sum and not the Average. Here we do the same, but we
 /// refer to it as Avg proof, as we compute a factor of the average, and it makes readability easier
@@ -40,63 +52,77 @@ pub struct AvgProof {
     pub average_commitment_base_G: Vec<Vec<RistrettoPoint>>,
     pub average_commitment_base_H: Vec<Vec<RistrettoPoint>>,
     // Proofs of correctness
-    proofs_avg_comm_base_G: Vec<Vec<CompactProof>>,
-    proofs_avg_comm_base_H: Vec<Vec<CompactProof>>,
+    proofs_avg_comm_base_G: Vec<Vec<SigmaProof>>,
+    proofs_avg_comm_base_H: Vec<Vec<SigmaProof>>,
 }
 
 impl AvgProof{
+    #[cfg(feature = "std")]
     pub fn create(
-        size_sensors: &Vec<usize>,
         bp_generators: &BulletproofGens,
         ped_generators: &PedersenGens,
-        input_vectors: &Vec<[Vec<Scalar>; 3]>,
-        v_blindings: &Vec<Vec<Scalar>>,
-        a_blindings: &Vec<Vec<Scalar>>,
-    ) -> AvgProof {
+        // B_blinding plus the sum of the first size_sensors[i] bases of G and H, see
+        // `PedersenConfig::acc_bases_G`
+        multiply_ped_sign_acc_bases_G: &[RistrettoPoint],
+        multiply_ped_acc_bases_H: &[RistrettoPoint],
+        input_vectors: &[[Vec<Scalar>; 3]],
+        v_blindings: &[Vec<Scalar>],
+        a_blindings: &[Vec<Scalar>],
+        labels: &TranscriptLabels,
+    ) -> Result<AvgProof, ProofError> {
+        AvgProof::create_with_metrics(
+            bp_generators,
+            ped_generators,
+            multiply_ped_sign_acc_bases_G,
+            multiply_ped_acc_bases_H,
+            input_vectors,
+            v_blindings,
+            a_blindings,
+            labels,
+            &mut thread_rng(),
+            &mut NoMetrics,
+        )
+    }
+
+    /// As `create`, with the external randomness of the proofs drawn from `rng`, and reporting
+    /// the `AvgProof` phase to `metrics`.
+    pub fn create_with_metrics(
+        bp_generators: &BulletproofGens,
+        ped_generators: &PedersenGens,
+        multiply_ped_sign_acc_bases_G: &[RistrettoPoint],
+        multiply_ped_acc_bases_H: &[RistrettoPoint],
+        input_vectors: &[[Vec<Scalar>; 3]],
+        v_blindings: &[Vec<Scalar>],
+        a_blindings: &[Vec<Scalar>],
+        labels: &TranscriptLabels,
+        mut rng: &mut dyn CryptoRngCore,
+        metrics: &mut dyn ProverMetrics,
+    ) -> Result<AvgProof, ProofError> {
+        let _span = phase_span!("avg_proof");
+        let stopwatch = Stopwatch::start(metrics);
         let sensor_additions = AvgProof::compute_sensors_addition(
-            &input_vectors
+            input_vectors
         );
 
-        let mut multiply_ped_sign_acc_bases_G: Vec<RistrettoPoint> = Vec::new();
-        for &size in size_sensors {
-            let mut value = ped_generators.B_blinding;
-            for base in bp_generators.G_vec[0].clone()[0..size].to_vec() {
-                value += &base;
-            }
-            multiply_ped_sign_acc_bases_G.push(value);
-        }
-
-        let mut multiply_ped_acc_bases_H: Vec<RistrettoPoint> = Vec::new();
-        for &size in size_sensors {
-            let mut value = ped_generators.B_blinding;
-            for base in bp_generators.H_vec[0].clone()[0..size].to_vec() {
-                value += &base;
-            }
-            multiply_ped_acc_bases_H.push(value);
-        }
-
-        let length_vectors = input_vectors.len();
-        let mut compressed_points: Vec<Vec<CompressedRistretto>> =
-            (0..length_vectors).map(
-                |_| Vec::new()
-            ).collect();
-        let mut ip_proofs: Vec<Vec<InnerProductZKProof>> =
-            (0..length_vectors).map(
-                |_| Vec::new()
-            ).collect();
-        for (i, a) in input_vectors.iter().enumerate() {
-            for (j, b) in a.iter().enumerate() {
-                let proof = AvgProof::single_proof_average(
-                    &bp_generators,
-                    &ped_generators,
-                    b,
-                    v_blindings[i][j],
-                    a_blindings[i][j],
-                );
-                compressed_points[i].push(proof.0);
-                ip_proofs[i].push(proof.1)
-            }
-        }
+        let shape: Vec<usize> = input_vectors.iter().map(|a| a.len()).collect();
+        let seeds = random_seed_matrix(&mut rng, &shape);
+        let proofs: Vec<Vec<Result<(CompressedRistretto, InnerProductZKProof), ProofError>>> = map_sensor_coords(
+            &shape,
+            |i, j| AvgProof::single_proof_average(
+                bp_generators,
+                ped_generators,
+                &input_vectors[i][j],
+                v_blindings[i][j],
+                a_blindings[i][j],
+                labels,
+                &mut ChaChaRng::from_seed(seeds[i][j]),
+            )
+        );
+        let proofs: Vec<Vec<(CompressedRistretto, InnerProductZKProof)>> = proofs.into_iter()
+            .map(|a| a.into_iter().collect::<Result<_, _>>())
+            .collect::<Result<_, _>>()?;
+        let (compressed_points, ip_proofs): (Vec<Vec<CompressedRistretto>>, Vec<Vec<InnerProductZKProof>>) =
+            proofs.into_iter().map(|a| a.into_iter().unzip()).unzip();
         // Generate the average commitment with the two bases. Here we use the multiplied bases
         // of each vector commitment given that the value to commit is one repeated number (the sum)
         let average_commitment_base_G: Vec<Vec<RistrettoPoint>> = sensor_additions
@@ -120,30 +146,44 @@ impl AvgProof{
         ).collect();
 
         let proofs_avg_comm_base_G = AvgProof::all_proof_avg_comm(
-            &ped_generators,
+            ped_generators,
             &sensor_additions,
-            &v_blindings,
+            v_blindings,
             &compressed_points,
             &average_commitment_base_G,
-            &multiply_ped_sign_acc_bases_G
+            multiply_ped_sign_acc_bases_G,
+            labels,
+            &mut rng,
         );
 
         let proofs_avg_comm_base_H = AvgProof::all_proof_avg_comm(
-            &ped_generators,
+            ped_generators,
             &sensor_additions,
-            &v_blindings,
+            v_blindings,
             &compressed_points,
             &average_commitment_base_H,
-            &multiply_ped_acc_bases_H
+            multiply_ped_acc_bases_H,
+            labels,
+            &mut rng,
         );
-        AvgProof{
+        let proof = AvgProof{
             average_commitment: compressed_points,
             proof_average: ip_proofs,
             average_commitment_base_G,
             average_commitment_base_H,
             proofs_avg_comm_base_G,
             proofs_avg_comm_base_H,
-        }
+        };
+        metrics.phase(ProofPhase::AvgProof, PhaseMetrics {
+            duration: stopwatch.elapsed(metrics),
+            commitments: 3 * count(&proof.average_commitment),
+            proofs: count(&proof.proof_average)
+                + count(&proof.proofs_avg_comm_base_G)
+                + count(&proof.proofs_avg_comm_base_H),
+            proof_size: proof.serialized_size(),
+        });
+
+        Ok(proof)
     }
 
     fn single_proof_average(
@@ -152,15 +192,16 @@ impl AvgProof{
         input_vector: &Vec<Scalar>,
         v_blinding: Scalar,
         a_blinding: Scalar,
-    ) -> (CompressedRistretto, InnerProductZKProof)
+        labels: &TranscriptLabels,
+        rng: &mut ChaChaRng,
+    ) -> Result<(CompressedRistretto, InnerProductZKProof), ProofError>
     {
-        let mut rng = rand::thread_rng();
         let size = input_vector.len();
-        let one_vector: Vec<Scalar> = iter::repeat(Scalar::one()).take(size).collect();
+        let one_vector: Vec<Scalar> = iter::repeat_n(Scalar::ONE, size).collect();
 
-        let sum = inner_product(&input_vector, &one_vector);
+        let sum = inner_product(input_vector, &one_vector);
 
-        let mut transcript = Transcript::new(b"InnerProductAverage");
+        let mut transcript = labels.transcript(labels.average);
         let (proof, commitment_sum) = InnerProductZKProof::prove_single(
             bp_gens,
             pc_gens,
@@ -171,43 +212,45 @@ impl AvgProof{
             v_blinding,
             a_blinding,
             size,
-            &mut rng,
-        ).unwrap();
+            rng,
+        )?;
 
-        (commitment_sum, proof)
+        Ok((commitment_sum, proof))
     }
     /// Generate a proof that the committed value is indeed the average
-    fn all_proof_avg_comm (
+    fn all_proof_avg_comm<T: RngCore + CryptoRng>(
         pd_generators: &PedersenGens,
         sensor_additions: &[Vec<Scalar>],
-        add_comm_blindings: &Vec<Vec<Scalar>>,
-        avg_comm: &Vec<Vec<CompressedRistretto>>,
-        avg_comm_base: &Vec<Vec<RistrettoPoint>>,
-        multiplied_ped_sign_bases: &Vec<RistrettoPoint>
-    ) -> Vec<Vec<CompactProof>>{
+        add_comm_blindings: &[Vec<Scalar>],
+        avg_comm: &[Vec<CompressedRistretto>],
+        avg_comm_base: &[Vec<RistrettoPoint>],
+        multiplied_ped_sign_bases: &[RistrettoPoint],
+        labels: &TranscriptLabels,
+        rng: &mut T,
+    ) -> Vec<Vec<SigmaProof>>{
         // Now we prove correcness, both for base G and base H
 
-        let mut transcript = Transcript::new(b"ProofAverageCommitmentG");
+        let mut transcript = labels.transcript(labels.average_commitment);
         (0..4).map(
             |i| (0..3).map(
-                |j| avg_comm_proof::prove_compact(
-                    &mut transcript,
-                    avg_comm_proof::ProveAssignments {
-                        x: &sensor_additions[i][j],
-                        r: &add_comm_blindings[i][j],
-                        A: &avg_comm[i][j].decompress().unwrap(),
-                        G: &pd_generators.B,
-                        B: &pd_generators.B_blinding,
-                        C: &avg_comm_base[i][j],
-                        H: &multiplied_ped_sign_bases[i],
-                    },
-                ).0
+                |j| {
+                    let mut prover = Prover::new(b"AvgComm", &mut transcript);
+                    let x = prover.allocate_scalar(b"x", sensor_additions[i][j]);
+                    let r = prover.allocate_scalar(b"r", add_comm_blindings[i][j]);
+                    let (A, _) = prover.allocate_point(b"A", avg_comm[i][j].decompress().unwrap());
+                    let (G, _) = prover.allocate_point(b"G", pd_generators.B);
+                    let (B, _) = prover.allocate_point(b"B", pd_generators.B_blinding);
+                    let (C, _) = prover.allocate_point(b"C", avg_comm_base[i][j]);
+                    let (H, _) = prover.allocate_point(b"H", multiplied_ped_sign_bases[i]);
+                    avg_comm_statement(&mut prover, x, r, A, G, B, C, H);
+                    prover.prove_with_rng(rng)
+                }
             ).collect()
         ).collect()
     }
 
     pub fn compute_sensors_addition(
-        sensors_vectors: &Vec<[Vec<Scalar>; 3]>
+        sensors_vectors: &[[Vec<Scalar>; 3]]
     ) -> Vec<Vec<Scalar>> {
         let mut additions: Vec<Vec<Scalar>> = (0..sensors_vectors.len()).map(
             |_| Vec::new()
@@ -224,37 +267,46 @@ impl AvgProof{
 
     /// Verify all proofs contained in AvgProof. This is, the proof of correctness of
     /// the average, and the proofs of commitment under other bases.
+    #[cfg(feature = "std")]
     pub fn verify(
         &self,
         bp_generators: &BulletproofGens,
         ped_generators: &PedersenGens,
+        multiply_ped_sign_acc_bases_G: &[RistrettoPoint],
+        multiply_ped_acc_bases_H: &[RistrettoPoint],
         size_vector: usize,
-        size_sensors: &Vec<usize>
+        labels: &TranscriptLabels,
     ) -> Result<(), ProofError> {
-        let mut multiply_ped_sign_acc_bases_G: Vec<RistrettoPoint> = Vec::new();
-        for &size in size_sensors {
-            let mut value = ped_generators.B_blinding;
-            for base in bp_generators.G_vec[0].clone()[0..size].to_vec() {
-                value += &base;
-            }
-            multiply_ped_sign_acc_bases_G.push(value);
-        }
-
-        let mut multiply_ped_acc_bases_H: Vec<RistrettoPoint> = Vec::new();
-        for &size in size_sensors {
-            let mut value = ped_generators.B_blinding;
-            for base in bp_generators.H_vec[0].clone()[0..size].to_vec() {
-                value += &base;
-            }
-            multiply_ped_acc_bases_H.push(value);
-        }
+        self.verify_with_rng(
+            bp_generators,
+            ped_generators,
+            multiply_ped_sign_acc_bases_G,
+            multiply_ped_acc_bases_H,
+            size_vector,
+            labels,
+            &mut thread_rng(),
+        )
+    }
 
+    /// As `verify`, with the randomness of the verification drawn from `rng`.
+    pub fn verify_with_rng(
+        &self,
+        bp_generators: &BulletproofGens,
+        ped_generators: &PedersenGens,
+        multiply_ped_sign_acc_bases_G: &[RistrettoPoint],
+        multiply_ped_acc_bases_H: &[RistrettoPoint],
+        size_vector: usize,
+        labels: &TranscriptLabels,
+        mut rng: &mut dyn CryptoRngCore,
+    ) -> Result<(), ProofError> {
+        let _span = phase_span!("avg_proof_verify");
         AvgProof::verify_avg_comm_different_base(
             &self.proofs_avg_comm_base_G,
             ped_generators,
             &self.average_commitment,
             &self.average_commitment_base_G,
-            &multiply_ped_sign_acc_bases_G
+            multiply_ped_sign_acc_bases_G,
+            labels
         )?;
 
         AvgProof::verify_avg_comm_different_base(
@@ -262,7 +314,8 @@ impl AvgProof{
             ped_generators,
             &self.average_commitment,
             &self.average_commitment_base_H,
-            &multiply_ped_acc_bases_H
+            multiply_ped_acc_bases_H,
+            labels
         )?;
 
         AvgProof::verify_avg(
@@ -270,34 +323,36 @@ impl AvgProof{
             ped_generators,
             &self.proof_average,
             &self.average_commitment,
-            size_vector
+            size_vector,
+            labels,
+            &mut rng,
         )?;
 
         Ok(())
     }
 
     fn verify_avg_comm_different_base(
-        proofs: &Vec<Vec<CompactProof>>,
+        proofs: &[Vec<SigmaProof>],
         pd_generators: &PedersenGens,
-        avg_comm: &Vec<Vec<CompressedRistretto>>,
-        avg_comm_base: &Vec<Vec<RistrettoPoint>>,
-        multiplied_ped_sign_bases: &Vec<RistrettoPoint>
+        avg_comm: &[Vec<CompressedRistretto>],
+        avg_comm_base: &[Vec<RistrettoPoint>],
+        multiplied_ped_sign_bases: &[RistrettoPoint],
+        labels: &TranscriptLabels,
     ) -> Result<(), ProofError> {
-        let mut transcript = Transcript::new(b"ProofAverageCommitmentG");
+        let mut transcript = labels.transcript(labels.average_commitment);
         let mut checks = true;
         for (i, a) in proofs.iter().enumerate() {
             for (j, proof) in a.iter().enumerate() {
-                checks &= avg_comm_proof::verify_compact(
-                    &proof,
-                    &mut transcript,
-                    avg_comm_proof::VerifyAssignments {
-                        A: &avg_comm[i][j],
-                        G: &pd_generators.B.compress(),
-                        B: &pd_generators.B_blinding.compress(),
-                        C: &avg_comm_base[i][j].compress(),
-                        H: &multiplied_ped_sign_bases[i].compress(),
-                    },
-                ).is_ok();
+                let mut verifier = Verifier::new(b"AvgComm", &mut transcript);
+                let x = verifier.allocate_scalar(b"x");
+                let r = verifier.allocate_scalar(b"r");
+                let A = verifier.allocate_point(b"A", avg_comm[i][j])?;
+                let G = verifier.allocate_point(b"G", pd_generators.B.compress())?;
+                let B = verifier.allocate_point(b"B", pd_generators.B_blinding.compress())?;
+                let C = verifier.allocate_point(b"C", avg_comm_base[i][j].compress())?;
+                let H = verifier.allocate_point(b"H", multiplied_ped_sign_bases[i].compress())?;
+                avg_comm_statement(&mut verifier, x, r, A, G, B, C, H);
+                checks &= verifier.verify(proof).is_ok();
             }
         }
         if checks {
@@ -308,45 +363,57 @@ impl AvgProof{
         }
     }
 
-    fn verify_avg(
+    fn verify_avg<T: RngCore + CryptoRng>(
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
-        proof_average: &Vec<Vec<InnerProductZKProof>>,
-        average_commitment: &Vec<Vec<CompressedRistretto>>,
-        size_vector: usize
+        proof_average: &[Vec<InnerProductZKProof>],
+        average_commitment: &[Vec<CompressedRistretto>],
+        size_vector: usize,
+        labels: &TranscriptLabels,
+        rng: &mut T,
     ) -> Result<(), ProofError> {
+        // Without the tables of the `prover-only` builds, every proof is verified on its own
+        #[cfg(not(feature = "prover-only"))]
+        let precomputed_gens = {
+            let nr_proofs = proof_average.iter().map(|a| a.len()).sum();
+            PrecomputedGens::for_batch(bp_gens, pc_gens, size_vector, nr_proofs)
+        };
 
         for (i, a) in proof_average.iter().enumerate() {
-            for (j, b) in a.iter().enumerate() {
-                AvgProof::verify_single(
-                    &bp_gens,
+            for (j, ip_proof) in a.iter().enumerate() {
+                let mut transcript = labels.transcript(labels.average);
+                #[cfg(not(feature = "prover-only"))]
+                if let Some(gens) = &precomputed_gens {
+                    ip_proof.verify_single_precomputed(gens, &mut transcript, &average_commitment[i][j], rng)?;
+                    continue;
+                }
+                ip_proof.verify_single(
+                    bp_gens,
                     pc_gens,
-                    average_commitment[i][j],
-                    b,
-                    size_vector)?
+                    &mut transcript,
+                    &average_commitment[i][j],
+                    size_vector,
+                    rng
+                )?;
             }
         }
 
         Ok(())
     }
+}
 
-    fn verify_single(
-        bp_gens: &BulletproofGens,
-        pc_gens: &PedersenGens,
-        commitment_sum: CompressedRistretto,
-        ip_proof: &InnerProductZKProof,
-        size_vector: usize
-    ) -> Result<(), ProofError> {
-        let mut rng = rand::thread_rng();
-        let mut transcript = Transcript::new(b"InnerProductAverage");
-        ip_proof.verify_single(
-            &bp_gens,
-            &pc_gens,
-            &mut transcript,
-            &commitment_sum,
-            size_vector,
-            &mut rng
-        )
+/// The commitments under the bases of the bulletproof generators are decompressed when
+/// parsing, and the sigma proofs only hold scalars.
+impl CanonicalPoints for AvgProof {
+    fn points_are_canonical(&self) -> bool {
+        self.average_commitment.points_are_canonical() && self.proof_average.points_are_canonical()
+    }
+}
+
+impl SerializedSize for AvgProof {
+    /// Size of the bincode encoding of the proof.
+    fn serialized_size(&self) -> usize {
+        bincode_size(self)
     }
 }
 
@@ -372,4 +439,21 @@ mod tests {
 
         assert_eq!(expected_addition, computed_addition)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn too_few_generators_is_an_error() {
+        let input_vectors = vec![[vec![Scalar::ONE; 4], vec![Scalar::ONE; 4], vec![Scalar::ONE; 4]]];
+        let blindings = vec![vec![Scalar::ONE; 3]];
+        let result = AvgProof::create(
+            &BulletproofGens::new(2, 1),
+            &PedersenGens::default(),
+            &[RistrettoPoint::default()],
+            &[RistrettoPoint::default()],
+            &input_vectors,
+            &blindings,
+            &blindings,
+            &TranscriptLabels::default(),
+        );
+        assert_eq!(result.err(), Some(ProofError::InvalidGeneratorsLength));
+    }
+}
@@ -0,0 +1,288 @@
+#![allow(non_snake_case)]
+use alloc::vec::Vec;
+use alloc::vec;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::generators::PedersenVecGens;
+use crate::sigma::{PointVar, Prover, ScalarVar, SchnorrCS, SigmaProof, Verifier};
+use crate::TranscriptLabels;
+use ip_zk_proof::{PedersenGens, ProofError, TranscriptProtocol};
+use serde::{Deserialize, Serialize};
+
+/// Proof that the windows of several sensors belong together: they have the same number of
+/// readings, and the timestamps of the readings of each sensor are those of the first sensor,
+/// the reference, shifted by a committed offset.
+///
+/// The timestamps \\(t^k\\) of each sensor are committed as a vector, e.g. by the trusted module
+/// next to the signed commitments, as \\(T_k = \sum_i t^k_i G_i + r_k B\\), zero-padded after the
+/// \\(n\\) readings. With \\(S_n = \sum_{i < n} G_i\\), the prover shows with a sigma proof that
+/// * it knows an opening of \\(T_0\\) over the first \\(n\\) bases only, so that the reference
+///   has at most \\(n\\) readings,
+/// * for every other sensor, \\(T_k - T_0 = \delta_k S_n + (r_k - r_0) B\\), and
+///   \\(O_k = \delta_k G + \rho_k H\\) commits to the offset \\(\delta_k\\),
+///
+/// which binds the timestamps of each sensor to \\(t^0_i + \delta_k\\) for the first \\(n\\)
+/// readings, and to zero after them. The offsets are not revealed, and the verifier may bound
+/// them with a range proof over the offset commitments.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct ConsistencyProof {
+    /// Commitments to the offset of each sensor but the reference
+    offset_commitments: Vec<CompressedRistretto>,
+    proof: SigmaProof,
+}
+
+/// Secret scalars of the statement, in the order in which they are allocated.
+struct ConsistencyScalars {
+    reference: Vec<ScalarVar>,
+    reference_blinding: ScalarVar,
+    offsets: Vec<ScalarVar>,
+    // Blinding of the timestamps of each sensor minus the one of the reference
+    difference_blindings: Vec<ScalarVar>,
+    offset_blindings: Vec<ScalarVar>,
+}
+
+/// Public points of the statement, in the order in which they are allocated.
+struct ConsistencyPoints {
+    bases: Vec<PointVar>,
+    vector_blinding_base: PointVar,
+    sum_bases: PointVar,
+    G: PointVar,
+    H: PointVar,
+    reference: PointVar,
+    differences: Vec<PointVar>,
+    offsets: Vec<PointVar>,
+}
+
+fn consistency_statement<CS: SchnorrCS>(cs: &mut CS, scalars: &ConsistencyScalars, points: &ConsistencyPoints) {
+    cs.constrain(
+        points.reference,
+        scalars.reference.iter().cloned().zip(points.bases.iter().cloned())
+            .chain(Some((scalars.reference_blinding, points.vector_blinding_base)))
+            .collect(),
+    );
+    for k in 0..points.differences.len() {
+        cs.constrain(
+            points.differences[k],
+            vec![(scalars.offsets[k], points.sum_bases), (scalars.difference_blindings[k], points.vector_blinding_base)],
+        );
+        cs.constrain(points.offsets[k], vec![(scalars.offsets[k], points.G), (scalars.offset_blindings[k], points.H)]);
+    }
+}
+
+/// The offset of the timestamps of each sensor but the first with respect to the first one,
+/// taken at the first reading, which `ConsistencyProof::create` commits to.
+pub fn timestamp_offsets(timestamps: &[Vec<Scalar>]) -> Vec<Scalar> {
+    timestamps.iter().skip(1).map(|t| t[0] - timestamps[0][0]).collect()
+}
+
+impl ConsistencyProof {
+    /// Proves that the `timestamps` of each sensor, committed under `vec_gens` with
+    /// `timestamp_blindings`, have `nr_readings` readings, aligned with those of the first
+    /// sensor. Returns the proof, with the blinding factors of the commitments to the offsets
+    /// of `timestamp_offsets`. If the timestamps are not aligned, the proof does not verify.
+    ///
+    /// Returns an `InvalidGeneratorsLength` if there are less than two sensors, if the
+    /// timestamps do not have the size of the generators, or if there are more readings, or
+    /// none, and a `WrongNumBlindingFactors` if there is not one blinding per sensor.
+    #[cfg(feature = "std")]
+    pub fn create(
+        vec_gens: &PedersenVecGens,
+        ped_gens: &PedersenGens,
+        labels: &TranscriptLabels,
+        timestamps: &[Vec<Scalar>],
+        timestamp_blindings: &[Scalar],
+        nr_readings: usize,
+    ) -> Result<(ConsistencyProof, Vec<Scalar>), ProofError> {
+        ConsistencyProof::create_with_rng(
+            vec_gens,
+            ped_gens,
+            labels,
+            timestamps,
+            timestamp_blindings,
+            nr_readings,
+            &mut thread_rng(),
+        )
+    }
+
+    /// As `create`, with the blindings of the offsets and the nonces drawn from `rng`.
+    pub fn create_with_rng<T: RngCore + CryptoRng>(
+        vec_gens: &PedersenVecGens,
+        ped_gens: &PedersenGens,
+        labels: &TranscriptLabels,
+        timestamps: &[Vec<Scalar>],
+        timestamp_blindings: &[Scalar],
+        nr_readings: usize,
+        rng: &mut T,
+    ) -> Result<(ConsistencyProof, Vec<Scalar>), ProofError> {
+        if timestamps.len() < 2
+            || timestamps.iter().any(|t| t.len() != vec_gens.size)
+            || nr_readings == 0
+            || nr_readings > vec_gens.size
+        {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        if timestamp_blindings.len() != timestamps.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+
+        let offsets = timestamp_offsets(timestamps);
+        let offset_blindings: Vec<Scalar> = offsets.iter().map(|_| Scalar::random(rng)).collect();
+        let offset_commitments: Vec<RistrettoPoint> = offsets.iter().zip(offset_blindings.iter())
+            .map(|(delta, rho)| ped_gens.commit(*delta, *rho))
+            .collect();
+        let differences: Vec<RistrettoPoint> = timestamps.iter().zip(timestamp_blindings.iter()).skip(1)
+            .map(|(t, r)| vec_gens.commit(t, *r) - vec_gens.commit(&timestamps[0], timestamp_blindings[0]))
+            .collect();
+
+        let mut transcript = labels.transcript(labels.consistency);
+        transcript.append_integer(b"readings", nr_readings as u64);
+        let mut prover = Prover::new(b"Consistency", &mut transcript);
+
+        let scalars = ConsistencyScalars {
+            reference: timestamps[0][..nr_readings].iter().map(|t| prover.allocate_scalar(b"t", *t)).collect(),
+            reference_blinding: prover.allocate_scalar(b"reference blinding", timestamp_blindings[0]),
+            offsets: offsets.iter().map(|delta| prover.allocate_scalar(b"offset", *delta)).collect(),
+            difference_blindings: timestamp_blindings[1..].iter()
+                .map(|r| prover.allocate_scalar(b"difference blinding", r - timestamp_blindings[0]))
+                .collect(),
+            offset_blindings: offset_blindings.iter().map(|rho| prover.allocate_scalar(b"offset blinding", *rho)).collect(),
+        };
+
+        let bases = &vec_gens.B[..nr_readings];
+        let mut offset_points = Vec::with_capacity(offsets.len());
+        let points = ConsistencyPoints {
+            bases: bases.iter().map(|B| prover.allocate_point(b"base", *B).0).collect(),
+            vector_blinding_base: prover.allocate_point(b"blinding base", vec_gens.B_blinding).0,
+            sum_bases: prover.allocate_point(b"sum bases", bases.iter().sum()).0,
+            G: prover.allocate_point(b"G", ped_gens.B).0,
+            H: prover.allocate_point(b"H", ped_gens.B_blinding).0,
+            reference: prover.allocate_point(b"reference", vec_gens.commit(&timestamps[0], timestamp_blindings[0])).0,
+            differences: differences.iter().map(|D| prover.allocate_point(b"difference", *D).0).collect(),
+            offsets: offset_commitments.iter()
+                .map(|O| {
+                    let (var, compressed) = prover.allocate_point(b"offset", *O);
+                    offset_points.push(compressed);
+                    var
+                })
+                .collect(),
+        };
+
+        consistency_statement(&mut prover, &scalars, &points);
+        let proof = prover.prove_with_rng(rng);
+
+        Ok((ConsistencyProof { offset_commitments: offset_points, proof }, offset_blindings))
+    }
+
+    /// The commitments to the offset of the timestamps of each sensor but the first, under
+    /// the `PedersenGens` of the proof.
+    pub fn offset_commitments(&self) -> &Vec<CompressedRistretto> {
+        &self.offset_commitments
+    }
+
+    /// Verifies that the timestamps of `timestamp_commitments`, committed under `vec_gens`,
+    /// have `nr_readings` readings, aligned up to the offsets of `offset_commitments`.
+    pub fn verify(
+        &self,
+        vec_gens: &PedersenVecGens,
+        ped_gens: &PedersenGens,
+        labels: &TranscriptLabels,
+        timestamp_commitments: &[CompressedRistretto],
+        nr_readings: usize,
+    ) -> Result<(), ProofError> {
+        if nr_readings == 0 || nr_readings > vec_gens.size {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        if timestamp_commitments.len() < 2 || self.offset_commitments.len() != timestamp_commitments.len() - 1 {
+            return Err(ProofError::VerificationError);
+        }
+        let timestamps = timestamp_commitments.iter()
+            .map(|T| T.decompress().ok_or(ProofError::FormatError))
+            .collect::<Result<Vec<RistrettoPoint>, ProofError>>()?;
+
+        let mut transcript = labels.transcript(labels.consistency);
+        transcript.append_integer(b"readings", nr_readings as u64);
+        let mut verifier = Verifier::new(b"Consistency", &mut transcript);
+
+        let nr_offsets = self.offset_commitments.len();
+        let scalars = ConsistencyScalars {
+            reference: (0..nr_readings).map(|_| verifier.allocate_scalar(b"t")).collect(),
+            reference_blinding: verifier.allocate_scalar(b"reference blinding"),
+            offsets: (0..nr_offsets).map(|_| verifier.allocate_scalar(b"offset")).collect(),
+            difference_blindings: (0..nr_offsets).map(|_| verifier.allocate_scalar(b"difference blinding")).collect(),
+            offset_blindings: (0..nr_offsets).map(|_| verifier.allocate_scalar(b"offset blinding")).collect(),
+        };
+
+        let bases = &vec_gens.B[..nr_readings];
+        let points = ConsistencyPoints {
+            bases: bases.iter()
+                .map(|B| verifier.allocate_point(b"base", B.compress()))
+                .collect::<Result<Vec<PointVar>, ProofError>>()?,
+            vector_blinding_base: verifier.allocate_point(b"blinding base", vec_gens.B_blinding.compress())?,
+            sum_bases: verifier.allocate_point(b"sum bases", bases.iter().sum::<RistrettoPoint>().compress())?,
+            G: verifier.allocate_point(b"G", ped_gens.B.compress())?,
+            H: verifier.allocate_point(b"H", ped_gens.B_blinding.compress())?,
+            reference: verifier.allocate_point(b"reference", timestamp_commitments[0])?,
+            differences: timestamps[1..].iter()
+                .map(|T| verifier.allocate_point(b"difference", (T - timestamps[0]).compress()))
+                .collect::<Result<Vec<PointVar>, ProofError>>()?,
+            offsets: self.offset_commitments.iter()
+                .map(|O| verifier.allocate_point(b"offset", *O))
+                .collect::<Result<Vec<PointVar>, ProofError>>()?,
+        };
+
+        consistency_statement(&mut verifier, &scalars, &points);
+        verifier.verify(&self.proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timestamps(start: u64, period: u64, nr_readings: usize, size: usize) -> Vec<Scalar> {
+        (0..size)
+            .map(|i| if i < nr_readings { Scalar::from(start + period * i as u64) } else { Scalar::ZERO })
+            .collect()
+    }
+
+    fn consistency_helper(timestamps: &[Vec<Scalar>], nr_readings: usize) -> Result<(), ProofError> {
+        let vec_gens = PedersenVecGens::new(8);
+        let ped_gens = PedersenGens::default();
+        let labels = TranscriptLabels::default();
+        let blindings: Vec<Scalar> = timestamps.iter().map(|_| Scalar::random(&mut thread_rng())).collect();
+        let commitments: Vec<CompressedRistretto> = timestamps.iter().zip(blindings.iter())
+            .map(|(t, r)| vec_gens.commit(t, *r).compress())
+            .collect();
+
+        let (proof, offset_blindings) =
+            ConsistencyProof::create(&vec_gens, &ped_gens, &labels, timestamps, &blindings, nr_readings)?;
+        for ((commitment, delta), rho) in proof.offset_commitments().iter().zip(timestamp_offsets(timestamps)).zip(offset_blindings) {
+            assert_eq!(*commitment, ped_gens.commit(delta, rho).compress());
+        }
+        proof.verify(&vec_gens, &ped_gens, &labels, &commitments, nr_readings)
+    }
+
+    #[test]
+    fn aligned_windows() {
+        let aligned = [timestamps(1000, 20, 5, 8), timestamps(1007, 20, 5, 8), timestamps(993, 20, 5, 8)];
+        assert!(consistency_helper(&aligned, 5).is_ok());
+    }
+
+    #[test]
+    fn misaligned_windows() {
+        // Different sampling periods
+        assert!(consistency_helper(&[timestamps(1000, 20, 5, 8), timestamps(1007, 21, 5, 8)], 5).is_err());
+        // Different window lengths
+        assert!(consistency_helper(&[timestamps(1000, 20, 5, 8), timestamps(1007, 20, 6, 8)], 5).is_err());
+        assert!(consistency_helper(&[timestamps(1000, 20, 6, 8), timestamps(1007, 20, 6, 8)], 5).is_err());
+        assert_eq!(
+            consistency_helper(&[timestamps(1000, 20, 5, 8)], 5),
+            Err(ProofError::InvalidGeneratorsLength)
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use alloc::vec::Vec;
+use ip_zk_proof::{InnerProductZKProof, BulletproofGens, PedersenGens, ProofError};
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::ristretto::CompressedRistretto;
+
+use merlin::Transcript;
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::PedersenVecGens;
+use crate::boolean_proofs::equality_proof::EqualityZKProof;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// Proof that two inner product statements, possibly over different generators, have the
+/// same inner product. Each statement is proven with an `InnerProductZKProof`, whose value
+/// commitments are then linked with an `EqualityZKProof`, so that the verifier learns that
+/// both commitments hide the same value without learning it.
+///
+/// The proofs share a single transcript, so that none of them can be replaced on its own.
+///
+/// `VarianceProof` does not use it: its `StdProof`s are verified against the very variance
+/// commitments of its inner product proofs, under the same `PedersenGens`, so there is no
+/// second commitment to link. The proof is meant for statements committed under different
+/// generators, e.g. by different parties.
+pub struct EqualInnerProductsProof {
+    // Commitments to the inner product, with the Pedersen generators of each statement
+    pub value_commitments: [CompressedRistretto; 2],
+    // Proofs of the inner product of each statement
+    proofs_inner_product: [InnerProductZKProof; 2],
+    // Proof that both value commitments hide the same value
+    proof_equality: EqualityZKProof,
+}
+
+impl EqualInnerProductsProof {
+    /// Creates the proof that `value` is the inner product of `lhs_1` and `rhs_1` under the
+    /// first set of generators, and of `lhs_2` and `rhs_2` under the second one. The
+    /// vectors of each statement are committed in an \\(A\\) with blinding `a_blinding_1`
+    /// and `a_blinding_2` respectively, see `InnerProductZKProof::prove_single`.
+    #[cfg(feature = "std")]
+    pub fn create(
+        bp_gens_1: &BulletproofGens,
+        pc_gens_1: &PedersenGens,
+        bp_gens_2: &BulletproofGens,
+        pc_gens_2: &PedersenGens,
+        value: Scalar,
+        lhs_1: &Vec<Scalar>,
+        rhs_1: &Vec<Scalar>,
+        lhs_2: &Vec<Scalar>,
+        rhs_2: &Vec<Scalar>,
+        a_blinding_1: Scalar,
+        a_blinding_2: Scalar,
+    ) -> Result<EqualInnerProductsProof, ProofError> {
+        EqualInnerProductsProof::create_with_rng(
+            bp_gens_1,
+            pc_gens_1,
+            bp_gens_2,
+            pc_gens_2,
+            value,
+            lhs_1,
+            rhs_1,
+            lhs_2,
+            rhs_2,
+            a_blinding_1,
+            a_blinding_2,
+            &mut thread_rng(),
+        )
+    }
+
+    /// As `create`, with the blinding factors and the randomness of the proofs drawn from `rng`.
+    pub fn create_with_rng<T: RngCore + CryptoRng>(
+        bp_gens_1: &BulletproofGens,
+        pc_gens_1: &PedersenGens,
+        bp_gens_2: &BulletproofGens,
+        pc_gens_2: &PedersenGens,
+        value: Scalar,
+        lhs_1: &Vec<Scalar>,
+        rhs_1: &Vec<Scalar>,
+        lhs_2: &Vec<Scalar>,
+        rhs_2: &Vec<Scalar>,
+        a_blinding_1: Scalar,
+        a_blinding_2: Scalar,
+        rng: &mut T,
+    ) -> Result<EqualInnerProductsProof, ProofError> {
+        let v_blinding_1 = Scalar::random(rng);
+        let v_blinding_2 = Scalar::random(rng);
+
+        let mut transcript = Transcript::new(b"EqualInnerProducts");
+        let (proof_1, value_commitment_1) = InnerProductZKProof::prove_single(
+            bp_gens_1,
+            pc_gens_1,
+            &mut transcript,
+            value,
+            lhs_1,
+            rhs_1,
+            v_blinding_1,
+            a_blinding_1,
+            lhs_1.len(),
+            rng,
+        )?;
+
+        let (proof_2, value_commitment_2) = InnerProductZKProof::prove_single(
+            bp_gens_2,
+            pc_gens_2,
+            &mut transcript,
+            value,
+            lhs_2,
+            rhs_2,
+            v_blinding_2,
+            a_blinding_2,
+            lhs_2.len(),
+            rng,
+        )?;
+
+        let proof_equality = EqualityZKProof::prove_equality_with_rng(
+            &PedersenVecGens::from(*pc_gens_1),
+            &PedersenVecGens::from(*pc_gens_2),
+            &[value],
+            v_blinding_1,
+            v_blinding_2,
+            &mut transcript,
+            rng,
+        )?;
+
+        Ok(EqualInnerProductsProof {
+            value_commitments: [value_commitment_1, value_commitment_2],
+            proofs_inner_product: [proof_1, proof_2],
+            proof_equality,
+        })
+    }
+
+    /// Verifies both inner product proofs, for vectors of size `size_1` and `size_2`
+    /// committed in `expected_A_1` and `expected_A_2`, and that their value commitments hide
+    /// the same value. The expected commitments are bound as in
+    /// `InnerProductZKProof::verify_single_with_expected_A`.
+    #[cfg(feature = "std")]
+    pub fn verify(
+        &self,
+        bp_gens_1: &BulletproofGens,
+        pc_gens_1: &PedersenGens,
+        bp_gens_2: &BulletproofGens,
+        pc_gens_2: &PedersenGens,
+        expected_A_1: &CompressedRistretto,
+        expected_A_2: &CompressedRistretto,
+        size_1: usize,
+        size_2: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_with_rng(
+            bp_gens_1,
+            pc_gens_1,
+            bp_gens_2,
+            pc_gens_2,
+            expected_A_1,
+            expected_A_2,
+            size_1,
+            size_2,
+            &mut thread_rng(),
+        )
+    }
+
+    /// As `verify`, with the randomness of the verification drawn from `rng`.
+    pub fn verify_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bp_gens_1: &BulletproofGens,
+        pc_gens_1: &PedersenGens,
+        bp_gens_2: &BulletproofGens,
+        pc_gens_2: &PedersenGens,
+        expected_A_1: &CompressedRistretto,
+        expected_A_2: &CompressedRistretto,
+        size_1: usize,
+        size_2: usize,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let mut transcript = Transcript::new(b"EqualInnerProducts");
+        self.proofs_inner_product[0].verify_single_with_expected_A(
+            bp_gens_1,
+            pc_gens_1,
+            &mut transcript,
+            &self.value_commitments[0],
+            expected_A_1,
+            size_1,
+            rng,
+        )?;
+
+        self.proofs_inner_product[1].verify_single_with_expected_A(
+            bp_gens_2,
+            pc_gens_2,
+            &mut transcript,
+            &self.value_commitments[1],
+            expected_A_2,
+            size_2,
+            rng,
+        )?;
+
+        self.proof_equality.verify_equality(
+            &PedersenVecGens::from(*pc_gens_1),
+            &PedersenVecGens::from(*pc_gens_2),
+            self.value_commitments[0],
+            self.value_commitments[1],
+            &mut transcript,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ip_zk_proof::inner_product;
+    use curve25519_dalek::ristretto::RistrettoPoint;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use sha3::Sha3_512;
+
+    fn statements(size_1: usize, size_2: usize, rng: &mut ChaChaRng) -> (Vec<Scalar>, Vec<Scalar>, Vec<Scalar>, Vec<Scalar>) {
+        let lhs_1: Vec<Scalar> = (0..size_1).map(|_| Scalar::random(rng)).collect();
+        let rhs_1: Vec<Scalar> = (0..size_1).map(|_| Scalar::random(rng)).collect();
+
+        // The second statement is <lhs_2, 1> with the same value
+        let mut lhs_2: Vec<Scalar> = (0..size_2).map(|_| Scalar::random(rng)).collect();
+        let difference = inner_product(&lhs_1, &rhs_1) - lhs_2.iter().sum::<Scalar>();
+        lhs_2[0] += difference;
+        let rhs_2 = vec![Scalar::ONE; size_2];
+        (lhs_1, rhs_1, lhs_2, rhs_2)
+    }
+
+    /// The \\(A\\) committing to the vectors of a statement.
+    fn expected_A(
+        bp_gens: &BulletproofGens,
+        pc_gens: &PedersenGens,
+        lhs: &[Scalar],
+        rhs: &[Scalar],
+        a_blinding: Scalar,
+    ) -> CompressedRistretto {
+        InnerProductZKProof::commit_share(&bp_gens.share(0), pc_gens, lhs, rhs, a_blinding, lhs.len()).compress()
+    }
+
+    #[test]
+    fn proof_works() {
+        let mut rng = ChaChaRng::from_seed([37u8; 32]);
+        let (size_1, size_2) = (8, 16);
+        let bp_gens_1 = BulletproofGens::new(size_1, 1);
+        let pc_gens_1 = PedersenGens::default();
+        let bp_gens_2 = BulletproofGens::new(size_2, 1);
+        let pc_gens_2 = PedersenGens {
+            B: RistrettoPoint::hash_from_bytes::<Sha3_512>(b"second value base"),
+            B_blinding: RistrettoPoint::hash_from_bytes::<Sha3_512>(b"second blinding base"),
+        };
+
+        let (lhs_1, rhs_1, lhs_2, rhs_2) = statements(size_1, size_2, &mut rng);
+        let (a_blinding_1, a_blinding_2) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+        let proof = EqualInnerProductsProof::create_with_rng(
+            &bp_gens_1,
+            &pc_gens_1,
+            &bp_gens_2,
+            &pc_gens_2,
+            inner_product(&lhs_1, &rhs_1),
+            &lhs_1,
+            &rhs_1,
+            &lhs_2,
+            &rhs_2,
+            a_blinding_1,
+            a_blinding_2,
+            &mut rng,
+        ).unwrap();
+        let A_1 = expected_A(&bp_gens_1, &pc_gens_1, &lhs_1, &rhs_1, a_blinding_1);
+        let A_2 = expected_A(&bp_gens_2, &pc_gens_2, &lhs_2, &rhs_2, a_blinding_2);
+
+        assert!(proof.verify_with_rng(&bp_gens_1, &pc_gens_1, &bp_gens_2, &pc_gens_2, &A_1, &A_2, size_1, size_2, &mut rng).is_ok());
+
+        // The statements are bound to the expected As
+        assert!(proof.verify_with_rng(&bp_gens_1, &pc_gens_1, &bp_gens_2, &pc_gens_2, &A_2, &A_1, size_1, size_2, &mut rng).is_err());
+        let other_A = expected_A(&bp_gens_1, &pc_gens_1, &rhs_1, &lhs_1, a_blinding_1);
+        assert!(proof.verify_with_rng(&bp_gens_1, &pc_gens_1, &bp_gens_2, &pc_gens_2, &other_A, &A_2, size_1, size_2, &mut rng).is_err());
+
+        // Swapping the commitments breaks the link
+        let mut swapped = proof.clone();
+        swapped.value_commitments.swap(0, 1);
+        assert!(swapped.verify_with_rng(&bp_gens_1, &pc_gens_1, &bp_gens_2, &pc_gens_2, &A_1, &A_2, size_1, size_2, &mut rng).is_err());
+    }
+
+    #[test]
+    fn proof_fails_for_different_values() {
+        let mut rng = ChaChaRng::from_seed([38u8; 32]);
+        let size = 8;
+        let bp_gens = BulletproofGens::new(size, 1);
+        let pc_gens = PedersenGens::default();
+
+        let (lhs_1, rhs_1, mut lhs_2, rhs_2) = statements(size, size, &mut rng);
+        lhs_2[0] += Scalar::ONE;
+        let (a_blinding_1, a_blinding_2) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+        let proof = EqualInnerProductsProof::create(
+            &bp_gens,
+            &pc_gens,
+            &bp_gens,
+            &pc_gens,
+            inner_product(&lhs_1, &rhs_1),
+            &lhs_1,
+            &rhs_1,
+            &lhs_2,
+            &rhs_2,
+            a_blinding_1,
+            a_blinding_2,
+        ).unwrap();
+        let A_1 = expected_A(&bp_gens, &pc_gens, &lhs_1, &rhs_1, a_blinding_1);
+        let A_2 = expected_A(&bp_gens, &pc_gens, &lhs_2, &rhs_2, a_blinding_2);
+
+        assert!(proof.verify(&bp_gens, &pc_gens, &bp_gens, &pc_gens, &A_1, &A_2, size, size).is_err());
+    }
+}
@@ -0,0 +1,491 @@
+//! Proof of the features of the sensor vectors with their numbers of readings committed.
+//!
+//! `zkSVMProver` takes the number of readings of each sensor vector in the clear, as it sizes
+//! the statements of its sub-proofs, which tells the verifier how active each sensor was. The
+//! `HiddenCountProof` proves the same features over vectors padded to the size of the
+//! parameters, with each count committed and only bounded by a public `max_count`.
+
+use alloc::vec::Vec;
+use alloc::vec;
+use ip_zk_proof::{BulletproofGens, CanonicalPoints, ProofError, TranscriptProtocol};
+#[cfg(feature = "std")]
+use ip_zk_proof::ZkProof;
+use ip_zk_proof::r1cs::{ConstraintSystem, LinearCombination, Prover, R1CSError, R1CSProof, Variable, Verifier};
+use ip_zk_proof::r1cs::gadgets::{boolean, less_or_equal, sum};
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::ristretto::CompressedRistretto;
+
+use merlin::Transcript;
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::algebraic_proofs::std_r1cs_proof::{scalar_to_u64, std_gadget};
+use crate::boolean_proofs::plausibility_proof::{prove_readings, verify_readings};
+use crate::sigma::SigmaProof;
+use crate::svm_proof::decision_proof::from_r1cs_error;
+#[cfg(feature = "std")]
+use crate::utils::commitment_fns::multiple_commit_with_blindings;
+use crate::utils::misc::{bincode_deserialize, bincode_serialize, check_canonical};
+use crate::utils::randomness::random_scalars;
+use crate::{FeatureWitness, PublicParams};
+
+/// Bitsize of the range proof of `max_count` minus the count.
+pub const COUNT_BITSIZE: usize = 32;
+
+/// Number of generators needed by the constraint system of one sensor vector of `size`
+/// elements, i.e. its multipliers rounded up to the next power of two. Each axis takes five
+/// multipliers per element and those of `std_gadget`, and the count two per element and its
+/// range proof.
+pub fn hidden_count_gens_capacity(size: usize) -> usize {
+    (17 * size + 326).next_power_of_two()
+}
+
+/// Proof of the additions and standard deviations of the sensor vectors, as the features of
+/// `zkSVMProver`, where the number of readings \\(n\\) of each vector is committed.
+///
+/// The prover commits to each reading under the `PedersenGens`, and proves with a sigma proof
+/// that these are the elements of the signed commitments, as the `PlausibilityProof`. For each
+/// sensor vector, a constraint system then takes the readings and the committed count, and
+/// shows that:
+/// - the count is the number of leading ones of a bit vector, between 2 and `max_count`,
+/// - the readings after the count are zero,
+/// - the additions are the sums of the readings, and the additions of the diff vectors are
+///   \\(x_0 - x_{n-1}\\), the sum of their adjacent differences,
+/// - the standard deviations are the integer square roots of
+///   \\(\sum_{i < n} (n x_i - s)^2 = n^3 \sigma^2\\), with \\(s\\) the addition.
+///
+/// The verifier only learns that each vector has at most `max_count` readings. The proof is
+/// as large as for vectors of `max_count` readings, whatever their counts.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct HiddenCountProof {
+    /// Commitments to each reading, per sensor and axis
+    readings: Vec<Vec<Vec<CompressedRistretto>>>,
+    link: SigmaProof,
+    count_commitments: Vec<CompressedRistretto>,
+    addition_commitments: Vec<Vec<CompressedRistretto>>,
+    std_commitments: Vec<Vec<CompressedRistretto>>,
+    proofs: Vec<R1CSProof>,
+}
+
+/// Committed variables of the constraint system of one sensor vector.
+struct SensorVariables {
+    /// Per axis
+    readings: Vec<Vec<Variable>>,
+    count: Variable,
+    additions: Vec<Variable>,
+    diff_additions: Vec<Variable>,
+    stds: Vec<Variable>,
+}
+
+/// Assignments of the constraint system of one sensor vector, only known by the prover.
+struct SensorAssignment {
+    count: usize,
+    variances: Vec<u64>,
+    stds: Vec<u64>,
+}
+
+fn hidden_count_gadget<CS: ConstraintSystem>(
+    cs: &mut CS,
+    vars: &SensorVariables,
+    max_count: u64,
+    assignment: Option<&SensorAssignment>,
+) -> Result<(), R1CSError> {
+    let size = vars.readings[0].len();
+    let count = assignment.map(|a| a.count);
+
+    let bits = (0..size)
+        .map(|i| boolean(cs, count.map(|n| i < n)))
+        .collect::<Result<Vec<Variable>, R1CSError>>()?;
+    // At least two readings, as the diff vectors drop their last one
+    cs.constrain(bits[0] - Scalar::ONE);
+    cs.constrain(bits[1] - Scalar::ONE);
+    // Once a bit is zero, so are the next ones
+    for i in 1..size {
+        let (_, _, zero) = cs.multiply(bits[i].into(), LinearCombination::from(Scalar::ONE) - bits[i - 1]);
+        cs.constrain(zero.into());
+    }
+    let bit_lcs: Vec<LinearCombination> = bits.iter().map(|b| (*b).into()).collect();
+    sum(cs, &bit_lcs, vars.count.into());
+    less_or_equal(
+        cs,
+        vars.count.into(),
+        Scalar::from(max_count).into(),
+        count.map(|n| max_count.wrapping_sub(n as u64)),
+        COUNT_BITSIZE,
+    )?;
+
+    for k in 0..3 {
+        let readings = &vars.readings[k];
+        for (x, b) in readings.iter().zip(bits.iter()) {
+            let (_, _, zero) = cs.multiply((*x).into(), LinearCombination::from(Scalar::ONE) - *b);
+            cs.constrain(zero.into());
+        }
+        let reading_lcs: Vec<LinearCombination> = readings.iter().map(|x| (*x).into()).collect();
+        sum(cs, &reading_lcs, vars.additions[k].into());
+
+        let mut variance = LinearCombination::default();
+        for (x, b) in readings.iter().zip(bits.iter()) {
+            let (_, _, scaled) = cs.multiply(vars.count.into(), (*x).into());
+            let (_, _, centered) = cs.multiply((*b).into(), scaled - vars.additions[k]);
+            let (_, _, square) = cs.multiply(centered.into(), centered.into());
+            variance = variance + square;
+        }
+        // The standard deviation gadget takes the variance as a variable
+        let (_, _, variance) = cs.multiply(variance, Scalar::ONE.into());
+        std_gadget(
+            cs,
+            vars.stds[k],
+            variance,
+            assignment.map(|a| a.stds[k]),
+            assignment.map(|a| a.variances[k]),
+        )?;
+
+        // The differences of adjacent bits select the last reading
+        let mut last = LinearCombination::default();
+        for i in 0..size {
+            let next = bits.get(i + 1).map_or(LinearCombination::default(), |b| (*b).into());
+            let (_, _, selected) = cs.multiply(LinearCombination::from(bits[i]) - next, readings[i].into());
+            last = last + selected;
+        }
+        cs.constrain(vars.diff_additions[k] - readings[0] + last);
+    }
+    Ok(())
+}
+
+impl HiddenCountProof {
+    /// Proves the features of `witness`, whose sensor vectors are padded to the size of the
+    /// parameters, e.g. with `SensorFeatures::extract_padded`, and committed in the
+    /// `signed_commitments` under `G_vec` with `signed_blindings` (one per axis). Returns an
+    /// `InvalidGeneratorsLength` if the shapes do not match. Counts above `max_count`, or
+    /// features that are not the ones of the vectors, give a proof that does not verify.
+    #[cfg(feature = "std")]
+    pub fn create(
+        params: &PublicParams,
+        witness: &FeatureWitness,
+        signed_blindings: &[Vec<Scalar>],
+        max_count: u64,
+        transcript: &mut Transcript,
+    ) -> Result<HiddenCountProof, ProofError> {
+        HiddenCountProof::create_with_rng(params, witness, signed_blindings, max_count, transcript, &mut thread_rng())
+    }
+
+    /// As `create`, with the blindings and the nonces of the proofs drawn from `rng`.
+    pub fn create_with_rng<T: RngCore + CryptoRng>(
+        params: &PublicParams,
+        witness: &FeatureWitness,
+        signed_blindings: &[Vec<Scalar>],
+        max_count: u64,
+        transcript: &mut Transcript,
+        rng: &mut T,
+    ) -> Result<HiddenCountProof, ProofError> {
+        let nr_sensors = witness.stds.len();
+        let size = params.size();
+        if size < 2
+            || witness.input_vector.len() != 2 * nr_sensors
+            || witness.additions.len() != 2 * nr_sensors
+            || witness.variances.len() != nr_sensors
+            || witness.non_zero_elements.len() != 2 * nr_sensors
+            || witness.input_vector[..nr_sensors].iter().flatten().any(|axis| axis.len() != size)
+            || witness.additions.iter().chain(witness.variances.iter()).chain(witness.stds.iter()).any(|f| f.len() != 3)
+        {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        if signed_blindings.len() != nr_sensors || signed_blindings.iter().any(|b| b.len() != 3) {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+        let sensor_vectors = witness.input_vector[..nr_sensors].to_vec();
+        let ped_gens = params.pedersen_gens();
+        let bp_gens = BulletproofGens::new(hidden_count_gens_capacity(size), 1);
+
+        append_statement(nr_sensors, max_count, transcript);
+        let reading_blindings = random_scalars(rng, 3 * size * nr_sensors);
+        let (readings, link) = prove_readings(
+            b"HiddenCountReadings",
+            params.G_vec(),
+            ped_gens,
+            &sensor_vectors,
+            signed_blindings,
+            &reading_blindings,
+            transcript,
+            rng,
+        );
+
+        let mut count_commitments = Vec::with_capacity(nr_sensors);
+        let mut addition_commitments = vec![Vec::with_capacity(3); 2 * nr_sensors];
+        let mut std_commitments = Vec::with_capacity(nr_sensors);
+        let mut proofs = Vec::with_capacity(nr_sensors);
+        for (j, sensor) in sensor_vectors.iter().enumerate() {
+            let mut prover = Prover::new(ped_gens, &mut *transcript);
+            let mut commit = |value: Scalar, commitments: &mut Vec<CompressedRistretto>| {
+                let (commitment, var) = prover.commit(value, Scalar::random(rng));
+                commitments.push(commitment);
+                var
+            };
+
+            let mut sensor_commitments = Vec::with_capacity(1);
+            let count = commit(Scalar::from(witness.non_zero_elements[j] as u64), &mut sensor_commitments);
+            count_commitments.append(&mut sensor_commitments);
+            let additions = (0..3).map(|k| commit(witness.additions[j][k], &mut addition_commitments[j])).collect();
+            let diff_additions = (0..3)
+                .map(|k| commit(witness.additions[nr_sensors + j][k], &mut addition_commitments[nr_sensors + j]))
+                .collect();
+            let mut sensor_stds = Vec::with_capacity(3);
+            let stds = (0..3).map(|k| commit(witness.stds[j][k], &mut sensor_stds)).collect();
+            std_commitments.push(sensor_stds);
+
+            let readings = sensor.iter().enumerate()
+                .map(|(k, axis)| axis.iter().enumerate()
+                    .map(|(i, x)| prover.commit(*x, reading_blindings[(3 * j + k) * size + i]).1)
+                    .collect())
+                .collect();
+            let vars = SensorVariables { readings, count, additions, diff_additions, stds };
+            let assignment = SensorAssignment {
+                count: witness.non_zero_elements[j],
+                variances: witness.variances[j].iter().map(scalar_to_u64).collect(),
+                stds: witness.stds[j].iter().map(scalar_to_u64).collect(),
+            };
+            hidden_count_gadget(&mut prover, &vars, max_count, Some(&assignment)).map_err(from_r1cs_error)?;
+            proofs.push(prover.prove_with_rng(&bp_gens, rng).map_err(from_r1cs_error)?);
+        }
+
+        Ok(HiddenCountProof {
+            readings: readings.iter()
+                .map(|sensor| sensor.iter().map(|axis| axis.iter().map(|Y| Y.compress()).collect()).collect())
+                .collect(),
+            link,
+            count_commitments,
+            addition_commitments,
+            std_commitments,
+            proofs,
+        })
+    }
+
+    /// Verifies the features of the vectors of the `signed_commitments`, each with at most
+    /// `max_count` readings.
+    #[cfg(feature = "std")]
+    pub fn verify(
+        &self,
+        params: &PublicParams,
+        signed_commitments: &[Vec<CompressedRistretto>],
+        max_count: u64,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_with_rng(params, signed_commitments, max_count, transcript, &mut thread_rng())
+    }
+
+    /// As `verify`, with the randomness of the verification drawn from `rng`.
+    pub fn verify_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        params: &PublicParams,
+        signed_commitments: &[Vec<CompressedRistretto>],
+        max_count: u64,
+        transcript: &mut Transcript,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let nr_sensors = signed_commitments.len();
+        let size = params.size();
+        if size < 2
+            || signed_commitments.iter().any(|c| c.len() != 3)
+            || self.readings.len() != nr_sensors
+            || self.readings.iter().any(|sensor| sensor.len() != 3)
+            || self.readings.iter().flatten().any(|axis| axis.len() != size)
+            || self.count_commitments.len() != nr_sensors
+            || self.addition_commitments.len() != 2 * nr_sensors
+            || self.std_commitments.len() != nr_sensors
+            || self.addition_commitments.iter().chain(self.std_commitments.iter()).any(|c| c.len() != 3)
+            || self.proofs.len() != nr_sensors
+        {
+            return Err(ProofError::VerificationError);
+        }
+        let ped_gens = params.pedersen_gens();
+        let bp_gens = BulletproofGens::new(hidden_count_gens_capacity(size), 1);
+
+        append_statement(nr_sensors, max_count, transcript);
+        verify_readings(
+            b"HiddenCountReadings",
+            params.G_vec(),
+            ped_gens,
+            signed_commitments,
+            &self.readings,
+            &self.link,
+            transcript,
+        )?;
+
+        for j in 0..nr_sensors {
+            let mut verifier = Verifier::new(&mut *transcript);
+            let count = verifier.commit(self.count_commitments[j]);
+            let additions = self.addition_commitments[j].iter().map(|c| verifier.commit(*c)).collect();
+            let diff_additions = self.addition_commitments[nr_sensors + j].iter().map(|c| verifier.commit(*c)).collect();
+            let stds = self.std_commitments[j].iter().map(|c| verifier.commit(*c)).collect();
+            let readings = self.readings[j].iter()
+                .map(|axis| axis.iter().map(|Y| verifier.commit(*Y)).collect())
+                .collect();
+            let vars = SensorVariables { readings, count, additions, diff_additions, stds };
+
+            hidden_count_gadget(&mut verifier, &vars, max_count, None).map_err(from_r1cs_error)?;
+            verifier.verify_with_rng(&self.proofs[j], ped_gens, &bp_gens, rng).map_err(from_r1cs_error)?;
+        }
+        Ok(())
+    }
+
+    /// The commitments to the number of readings of each sensor vector, under the
+    /// `PedersenGens` of the parameters.
+    pub fn count_commitments(&self) -> &Vec<CompressedRistretto> {
+        &self.count_commitments
+    }
+
+    /// The commitments to the features, with the shape of `zkSVMProver::feature_commitments`,
+    /// so that an `SvmModel` is evaluated over them.
+    pub fn feature_commitments(&self) -> (&Vec<Vec<CompressedRistretto>>, &Vec<Vec<CompressedRistretto>>) {
+        (&self.addition_commitments, &self.std_commitments)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode_serialize(self).expect("Should never happen, as all the fields can be serialized.")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<HiddenCountProof, ProofError> {
+        bincode_deserialize(bytes).map_err(|_| ProofError::FormatError)
+    }
+
+    /// As `from_bytes`, rejecting proofs with non-canonical points, see `CanonicalPoints`.
+    pub fn from_bytes_strict(bytes: &[u8]) -> Result<HiddenCountProof, ProofError> {
+        check_canonical(HiddenCountProof::from_bytes(bytes)?)
+    }
+}
+
+impl CanonicalPoints for HiddenCountProof {
+    fn points_are_canonical(&self) -> bool {
+        self.readings.points_are_canonical()
+            && self.count_commitments.points_are_canonical()
+            && self.addition_commitments.points_are_canonical()
+            && self.std_commitments.points_are_canonical()
+            && self.proofs.points_are_canonical()
+    }
+}
+
+/// The parameters are the public parameters and `max_count`, the witness the features and the
+/// blindings of the commitments to the sensor vectors, and the statement those commitments.
+/// The proof is verified with a `thread_rng`.
+#[cfg(feature = "std")]
+impl ZkProof for HiddenCountProof {
+    type Params<'a> = (&'a PublicParams, u64);
+    type Witness<'a> = (&'a FeatureWitness, &'a Vec<Vec<Scalar>>);
+    type Statement = Vec<Vec<CompressedRistretto>>;
+    type Error = ProofError;
+
+    fn prove<R: RngCore + CryptoRng>(
+        (params, max_count): Self::Params<'_>,
+        (witness, signed_blindings): Self::Witness<'_>,
+        transcript: &mut Transcript,
+        rng: &mut R,
+    ) -> Result<(HiddenCountProof, Vec<Vec<CompressedRistretto>>), ProofError> {
+        let proof = HiddenCountProof::create_with_rng(params, witness, signed_blindings, max_count, transcript, rng)?;
+        let sensor_vectors = witness.input_vector[..witness.stds.len()].to_vec();
+        let signed_commitments = multiple_commit_with_blindings(params.G_vec(), &sensor_vectors, signed_blindings)
+            .ok_or(ProofError::WrongNumBlindingFactors)?;
+        Ok((proof, signed_commitments))
+    }
+
+    fn verify(
+        &self,
+        (params, max_count): Self::Params<'_>,
+        signed_commitments: &Vec<Vec<CompressedRistretto>>,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        HiddenCountProof::verify(self, params, signed_commitments, max_count, transcript)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        HiddenCountProof::to_bytes(self)
+    }
+
+    fn from_bytes(slice: &[u8]) -> Result<HiddenCountProof, ProofError> {
+        HiddenCountProof::from_bytes(slice)
+    }
+}
+
+fn append_statement(nr_sensors: usize, max_count: u64, transcript: &mut Transcript) {
+    transcript.domain_sep(b"HiddenCountProof");
+    transcript.append_integer(b"hidden count sensors", nr_sensors as u64);
+    transcript.append_integer(b"max count", max_count);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::commitment_fns::multiple_commit;
+    use crate::SensorFeatures;
+
+    /// Proves the counts of sensor vectors of fixed readings, padded to 8. Returns the
+    /// parameters and the commitments to the signed vectors, together with the proof.
+    fn hidden_count_proof(
+        counts: &[usize],
+        max_count: u64,
+    ) -> Result<(PublicParams, Vec<Vec<CompressedRistretto>>, HiddenCountProof), ProofError> {
+        let size = 8;
+        let params = PublicParams::new(size);
+        let input_vector: Vec<[Vec<i128>; 3]> = counts.iter().enumerate()
+            .map(|(j, &n)| {
+                let axis = |k: usize| (0..n).map(|i| ((i * 7 + j * 3 + k * 5) % 11) as i128 - 5).collect::<Vec<i128>>();
+                [axis(0), axis(1), axis(2)]
+            })
+            .collect();
+        let witness = SensorFeatures::extract_padded(&input_vector, counts, size)?.witness()?;
+        let (signed_commitments, signed_blindings) = multiple_commit(params.G_vec(), &witness.input_vector[..counts.len()]);
+
+        let proof = HiddenCountProof::create(&params, &witness, &signed_blindings, max_count, &mut Transcript::new(b"test"))?;
+        Ok((params, signed_commitments, proof))
+    }
+
+    fn hidden_count_helper(counts: &[usize], max_count: u64) -> Result<(), ProofError> {
+        let (params, signed_commitments, proof) = hidden_count_proof(counts, max_count)?;
+        let proof = HiddenCountProof::from_bytes(&proof.to_bytes())?;
+        proof.verify(&params, &signed_commitments, max_count, &mut Transcript::new(b"test"))
+    }
+
+    #[test]
+    fn hidden_counts() {
+        assert!(hidden_count_helper(&[3, 8], 8).is_ok());
+        assert!(hidden_count_helper(&[2, 5], 5).is_ok());
+
+        assert_eq!(hidden_count_helper(&[3, 6], 5).err(), Some(ProofError::VerificationError));
+        assert_eq!(hidden_count_helper(&[1, 4], 8).err(), Some(ProofError::VerificationError));
+    }
+
+    #[test]
+    fn zk_proof_round_trip() {
+        let params = PublicParams::new(8);
+        let input_vector: Vec<[Vec<i128>; 3]> = vec![[vec![1, -2, 3], vec![0, 4, 4], vec![5, 5, -1]]];
+        let witness = SensorFeatures::extract_padded(&input_vector, &[3], 8).unwrap().witness().unwrap();
+        let signed_blindings = vec![(0..3).map(|_| Scalar::random(&mut thread_rng())).collect()];
+
+        let (proof, signed_commitments) = <HiddenCountProof as ZkProof>::prove(
+            (&params, 4),
+            (&witness, &signed_blindings),
+            &mut Transcript::new(b"test"),
+            &mut thread_rng(),
+        ).unwrap();
+        let proof = <HiddenCountProof as ZkProof>::from_bytes(&ZkProof::to_bytes(&proof)).unwrap();
+        assert!(ZkProof::verify(&proof, (&params, 4), &signed_commitments, &mut Transcript::new(b"test")).is_ok());
+        assert_eq!(
+            ZkProof::verify(&proof, (&params, 2), &signed_commitments, &mut Transcript::new(b"test")).err(),
+            Some(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn strict_parsing_rejects_non_canonical_points() {
+        let (_, _, proof) = hidden_count_proof(&[3, 8], 8).unwrap();
+        assert!(HiddenCountProof::from_bytes_strict(&proof.to_bytes()) == Ok(proof.clone()));
+
+        let mut tampered = proof;
+        tampered.readings[0][0][0] = CompressedRistretto([0xff; 32]);
+        let bytes = tampered.to_bytes();
+        assert!(HiddenCountProof::from_bytes(&bytes).is_ok());
+        assert_eq!(HiddenCountProof::from_bytes_strict(&bytes).err(), Some(ProofError::FormatError));
+    }
+}
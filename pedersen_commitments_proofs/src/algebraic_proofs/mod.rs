@@ -1,4 +1,12 @@
 pub mod average_proof;
+pub mod consistency_proof;
 pub mod std_proof;
+pub mod std_r1cs_proof;
 pub mod variance_proof;
-pub mod diff_vector_gen_proof;
\ No newline at end of file
+pub mod diff_vector_gen_proof;
+pub mod equal_inner_products_proof;
+// The samplers of the noise take the floating point functions of `std`
+#[cfg(feature = "std")]
+pub mod noise_proof;
+pub mod normalization_proof;
+pub mod hidden_count_proof;
@@ -0,0 +1,297 @@
+//! Release of differentially private statistics.
+//!
+//! A statistic committed by the prover, e.g. an addition of a sensor vector, is released with
+//! noise added to it. The prover commits to the noise \\(\eta\\), sampled from a discrete
+//! Laplace or Gaussian distribution, so that the commitment to the noised statistic is the sum
+//! of both commitments, and proves with an aggregated range proof that
+//! \\(B + \eta\\) and \\(B - \eta\\) are in \\([0, 2^{32})\\), i.e. that
+//! \\(|\eta| \leq B\\) for the public bound \\(B\\) of the mechanism. The noised statistic can
+//! then be opened, giving a release that is both differentially private and bound to the
+//! committed data.
+//!
+//! The samplers use floating point arithmetic, so the privacy guarantee of the released values
+//! is the one of the (truncated) distributions up to the precision of `f64`.
+
+use ip_zk_proof::{BulletproofGens, PedersenGens, ProofError, RangeProof, TranscriptProtocol};
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+use rand::{thread_rng, CryptoRng, Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use ip_zk_proof::r1cs::gadgets::signed_scalar;
+
+/// Bitsize of the range proofs of the noise. The bound of a mechanism must be smaller than
+/// \\(2^{31}\\).
+pub const NOISE_RANGE_BITSIZE: usize = 32;
+
+/// Distribution of the noise added to a statistic, over the integers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoiseDistribution {
+    /// Discrete Laplace distribution with the given scale, i.e. \\(P(\eta) \propto
+    /// e^{-|\eta| / scale}\\). A scale of \\(\Delta / \epsilon\\) gives
+    /// \\(\epsilon\\)-differential privacy for a statistic of sensitivity \\(\Delta\\).
+    Laplace { scale: f64 },
+    /// Discrete Gaussian distribution with the given standard deviation, i.e.
+    /// \\(P(\eta) \propto e^{-\eta^2 / 2\sigma^2}\\).
+    Gaussian { sigma: f64 },
+}
+
+impl NoiseDistribution {
+    /// Bound on the absolute value of the noise exceeded with a probability of about
+    /// `tail_probability`, from the tails of the continuous distributions.
+    pub fn calibrated_bound(&self, tail_probability: f64) -> u64 {
+        let log_tail = (2.0 / tail_probability).ln();
+        match *self {
+            NoiseDistribution::Laplace { scale } => (scale * log_tail).ceil() as u64,
+            NoiseDistribution::Gaussian { sigma } => (sigma * (2.0 * log_tail).sqrt()).ceil() as u64,
+        }
+    }
+
+    /// Samples the noise, following Canonne, Kamath and Steinke for the discrete Gaussian,
+    /// which is sampled by rejection from a discrete Laplace.
+    pub fn sample<R: RngCore + CryptoRng>(&self, rng: &mut R) -> i64 {
+        match *self {
+            NoiseDistribution::Laplace { scale } => sample_laplace(rng, scale),
+            NoiseDistribution::Gaussian { sigma } => {
+                let scale = sigma.floor() + 1.0;
+                loop {
+                    let candidate = sample_laplace(rng, scale);
+                    let distance = candidate.abs() as f64 - sigma * sigma / scale;
+                    if rng.gen::<f64>() < (-distance * distance / (2.0 * sigma * sigma)).exp() {
+                        return candidate;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Difference of two geometric samples with parameter \\(1 - e^{-1/scale}\\), which follows
+/// the discrete Laplace distribution.
+fn sample_laplace<R: RngCore + CryptoRng>(rng: &mut R, scale: f64) -> i64 {
+    let mut geometric = || {
+        // 1 - U is in (0, 1], so its logarithm is finite
+        (-scale * (1.0 - rng.gen::<f64>()).ln()).floor() as i64
+    };
+    geometric() - geometric()
+}
+
+/// A noise distribution truncated to a public bound, see `NoiseDistribution::calibrated_bound`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NoiseMechanism {
+    pub distribution: NoiseDistribution,
+    pub bound: u64,
+}
+
+impl NoiseMechanism {
+    /// Mechanism with the bound of the distribution for the given tail probability.
+    pub fn calibrated(distribution: NoiseDistribution, tail_probability: f64) -> NoiseMechanism {
+        NoiseMechanism {
+            distribution,
+            bound: distribution.calibrated_bound(tail_probability),
+        }
+    }
+
+    /// Samples the noise until its absolute value is at most the bound.
+    pub fn sample<R: RngCore + CryptoRng>(&self, rng: &mut R) -> i64 {
+        loop {
+            let noise = self.distribution.sample(rng);
+            if noise.unsigned_abs() <= self.bound {
+                return noise;
+            }
+        }
+    }
+}
+
+/// Proof that a commitment to the noise, added to the commitment to a statistic, has an
+/// absolute value of at most the bound of a `NoiseMechanism`.
+///
+/// The `BulletproofGens` must have a capacity of at least `NOISE_RANGE_BITSIZE` for two parties.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct NoiseProof {
+    noise_commitment: CompressedRistretto,
+    range_proof: RangeProof,
+}
+
+impl NoiseProof {
+    /// Samples the noise of `mechanism` and proves its bound. Returns the proof, the noised
+    /// statistic and the blinding factor of its commitment, which is the commitment to the
+    /// statistic plus the commitment to the noise.
+    pub fn create(
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        mechanism: &NoiseMechanism,
+        statistic: Scalar,
+        blinding: Scalar,
+        transcript: &mut Transcript,
+    ) -> Result<(NoiseProof, Scalar, Scalar), ProofError> {
+        let noise = mechanism.sample(&mut thread_rng());
+        NoiseProof::create_with_noise(bulletproof_generators, pedersen_generators, mechanism.bound, statistic, blinding, noise, transcript)
+    }
+
+    /// As `create`, with the given noise. Returns `InvalidBitsize` if the bound does not fit
+    /// in the range proofs, and a proof that does not verify if the noise exceeds the bound.
+    pub fn create_with_noise(
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        bound: u64,
+        statistic: Scalar,
+        blinding: Scalar,
+        noise: i64,
+        transcript: &mut Transcript,
+    ) -> Result<(NoiseProof, Scalar, Scalar), ProofError> {
+        if bound >= 1 << (NOISE_RANGE_BITSIZE - 1) {
+            return Err(ProofError::InvalidBitsize);
+        }
+        let mut csprng = thread_rng();
+        let noise_blinding = Scalar::random(&mut csprng);
+        let noise_commitment = pedersen_generators.commit(signed_scalar(noise), noise_blinding).compress();
+
+        transcript.append_integer(b"noise bound", bound);
+        transcript.append_bytes(b"noise commitment", noise_commitment.as_bytes());
+        // Out of bound noises wrap around, and the range proofs do not verify
+        let (range_proof, _) = RangeProof::prove_multiple_with_rng(
+            bulletproof_generators,
+            pedersen_generators,
+            transcript,
+            &[(bound as i64).wrapping_add(noise) as u64, (bound as i64).wrapping_sub(noise) as u64],
+            &[noise_blinding, -noise_blinding],
+            NOISE_RANGE_BITSIZE,
+            &mut csprng,
+        )?;
+
+        Ok((
+            NoiseProof { noise_commitment, range_proof },
+            statistic + signed_scalar(noise),
+            blinding + noise_blinding,
+        ))
+    }
+
+    /// Verifies the bound of the noise, and returns the commitment to the noised statistic
+    /// of `commitment`.
+    pub fn verify(
+        &self,
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        bound: u64,
+        commitment: CompressedRistretto,
+        transcript: &mut Transcript,
+    ) -> Result<CompressedRistretto, ProofError> {
+        if bound >= 1 << (NOISE_RANGE_BITSIZE - 1) {
+            return Err(ProofError::InvalidBitsize);
+        }
+        let noise = self.noise_commitment.decompress().ok_or(ProofError::FormatError)?;
+        let statistic = commitment.decompress().ok_or(ProofError::FormatError)?;
+        let bound_commitment: RistrettoPoint = Scalar::from(bound) * pedersen_generators.B;
+
+        transcript.append_integer(b"noise bound", bound);
+        transcript.append_bytes(b"noise commitment", self.noise_commitment.as_bytes());
+        self.range_proof.verify_multiple(
+            bulletproof_generators,
+            pedersen_generators,
+            transcript,
+            &[(bound_commitment + noise).compress(), (bound_commitment - noise).compress()],
+            NOISE_RANGE_BITSIZE,
+        )?;
+
+        Ok((statistic + noise).compress())
+    }
+
+    /// Verifies the bound of the noise as `verify`, and that the noised statistic of
+    /// `commitment` is the released one.
+    pub fn verify_release(
+        &self,
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        bound: u64,
+        commitment: CompressedRistretto,
+        noised_statistic: Scalar,
+        noised_blinding: Scalar,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        let noised_commitment = self.verify(bulletproof_generators, pedersen_generators, bound, commitment, transcript)?;
+        if noised_commitment != pedersen_generators.commit(noised_statistic, noised_blinding).compress() {
+            return Err(ProofError::VerificationError);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noise_proof_helper(bound: u64, noise: i64) -> Result<(), ProofError> {
+        let bp_gens = BulletproofGens::new(NOISE_RANGE_BITSIZE, 2);
+        let ped_gens: PedersenGens = PedersenGens::default();
+        let statistic = Scalar::from(1_000_000u64);
+        let blinding = Scalar::random(&mut thread_rng());
+        let commitment = ped_gens.commit(statistic, blinding).compress();
+
+        let mut transcript = Transcript::new(b"test");
+        let (proof, noised, noised_blinding) =
+            NoiseProof::create_with_noise(&bp_gens, &ped_gens, bound, statistic, blinding, noise, &mut transcript)?;
+        assert_eq!(noised, statistic + signed_scalar(noise));
+
+        transcript = Transcript::new(b"test");
+        proof.verify_release(&bp_gens, &ped_gens, bound, commitment, noised, noised_blinding, &mut transcript)
+    }
+
+    #[test]
+    fn proof_works() {
+        assert!(noise_proof_helper(100, 0).is_ok());
+        assert!(noise_proof_helper(100, 100).is_ok());
+        assert!(noise_proof_helper(100, -100).is_ok());
+        assert!(noise_proof_helper(100, -37).is_ok());
+    }
+
+    #[test]
+    fn proof_fails() {
+        assert_eq!(noise_proof_helper(100, 101).err(), Some(ProofError::VerificationError));
+        assert_eq!(noise_proof_helper(100, -101).err(), Some(ProofError::VerificationError));
+        assert_eq!(noise_proof_helper(1 << 31, 0).err(), Some(ProofError::InvalidBitsize));
+    }
+
+    #[cfg(feature = "transcript-debug")]
+    #[test]
+    fn transcripts_of_prover_and_verifier() {
+        use ip_zk_proof::record_transcripts;
+
+        let bp_gens = BulletproofGens::new(NOISE_RANGE_BITSIZE, 2);
+        let ped_gens: PedersenGens = PedersenGens::default();
+        let commitment = ped_gens.commit(Scalar::from(7u64), Scalar::ONE).compress();
+        let (created, prover_log) = record_transcripts(|| {
+            NoiseProof::create_with_noise(&bp_gens, &ped_gens, 100, Scalar::from(7u64), Scalar::ONE, 3, &mut Transcript::new(b"test"))
+        });
+        let (proof, _, _) = created.unwrap();
+        let verify = |bound| record_transcripts(|| proof.verify(&bp_gens, &ped_gens, bound, commitment, &mut Transcript::new(b"test")));
+
+        let (result, verifier_log) = verify(100);
+        assert!(result.is_ok());
+        assert_eq!(prover_log, verifier_log);
+
+        // The logs depart at the bound
+        let (result, verifier_log) = verify(101);
+        assert!(result.is_err());
+        let mismatch = prover_log.first_mismatch(&verifier_log).unwrap();
+        assert_eq!(verifier_log.events()[mismatch].label, b"noise bound".to_vec());
+    }
+
+    #[test]
+    fn sampled_noise_is_bounded() {
+        let mut rng = thread_rng();
+        for distribution in [NoiseDistribution::Laplace { scale: 10.0 }, NoiseDistribution::Gaussian { sigma: 10.0 }].iter() {
+            let mechanism = NoiseMechanism::calibrated(*distribution, 1e-6);
+            assert!(mechanism.bound > 10 && mechanism.bound < 200);
+            let samples: Vec<i64> = (0..1000).map(|_| mechanism.sample(&mut rng)).collect();
+            assert!(samples.iter().all(|n| n.unsigned_abs() <= mechanism.bound));
+            assert!(samples.iter().any(|&n| n != 0));
+
+            let mean = samples.iter().sum::<i64>() as f64 / samples.len() as f64;
+            assert!(mean.abs() < 3.0);
+        }
+    }
+}
@@ -0,0 +1,386 @@
+#![allow(non_snake_case)]
+use alloc::vec::Vec;
+use alloc::vec;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::sigma::{PointVar, Prover, ScalarVar, SchnorrCS, SigmaProof, Verifier};
+use crate::utils::randomness::random_scalar_matrix;
+use crate::TranscriptLabels;
+use ip_zk_proof::{PedersenGens, ProofError};
+use serde::{Deserialize, Serialize};
+
+/// Proof that committed features are standardized, i.e. that each committed normalized
+/// feature is \\(z = (x - \mu) \cdot s\\) for the committed feature \\(x\\), e.g. an addition or
+/// a standard deviation of `zkSVMProver::feature_commitments`, and the committed mean
+/// \\(\mu\\) and scale \\(s\\) of that feature.
+///
+/// Standardizing divides by the standard deviation \\(\sigma\\) of the training set, so the
+/// scale is its inverse in fixed point, e.g. \\(s = \lfloor 2^{16} / \sigma \rceil\\), and the
+/// normalized features carry the same fixed point, which the weights of the model account for.
+///
+/// With \\(D = X - M\\), the difference of the commitments to the feature and to the mean, the
+/// prover shows with a sigma proof that it knows the opening \\((s, r_s)\\) of the commitment to
+/// the scale, and a \\(t\\) such that \\(Z = s \cdot D + t \cdot H\\), which holds with
+/// \\(t = r_z - s (r_x - r_\mu)\\) and binds \\(z\\) to \\((x - \mu) \cdot s\\).
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct NormalizationProof {
+    /// Commitments to each normalized feature
+    normalized_commitments: Vec<Vec<CompressedRistretto>>,
+    proof: SigmaProof,
+}
+
+/// Secret scalars of the statement of one feature.
+struct FeatureScalars {
+    scale: ScalarVar,
+    scale_blinding: ScalarVar,
+    // Blinding of the normalized feature, minus the scale times the blinding of the difference
+    product_blinding: ScalarVar,
+}
+
+/// Public points of the statement of one feature.
+struct FeaturePoints {
+    scale: PointVar,
+    difference: PointVar,
+    normalized: PointVar,
+}
+
+fn normalization_statement<CS: SchnorrCS>(
+    cs: &mut CS,
+    scalars: &[FeatureScalars],
+    points: &[FeaturePoints],
+    G: PointVar,
+    H: PointVar,
+) {
+    for (scalars, points) in scalars.iter().zip(points.iter()) {
+        cs.constrain(points.scale, vec![(scalars.scale, G), (scalars.scale_blinding, H)]);
+        cs.constrain(points.normalized, vec![(scalars.scale, points.difference), (scalars.product_blinding, H)]);
+    }
+}
+
+/// The features normalized with the given means and scales, \\((x - \mu) \cdot s\\), which
+/// `NormalizationProof::create` commits to.
+pub fn normalized_features(
+    features: &[Vec<Scalar>],
+    means: &[Vec<Scalar>],
+    scales: &[Vec<Scalar>],
+) -> Vec<Vec<Scalar>> {
+    features.iter().zip(means.iter()).zip(scales.iter())
+        .map(|((x, mu), s)| x.iter().zip(mu.iter()).zip(s.iter()).map(|((x, mu), s)| (x - mu) * s).collect())
+        .collect()
+}
+
+impl NormalizationProof {
+    /// Proves the normalization of the `features`, committed under `ped_gens` with
+    /// `feature_blindings`, with the `means` and the `scales` committed with their blindings,
+    /// all of them with the shape of the features. Returns the proof, with the blinding factors
+    /// of the commitments to the normalized features.
+    ///
+    /// Returns an `InvalidGeneratorsLength` if the means or the scales do not have the shape
+    /// of the features, and a `WrongNumBlindingFactors` if a blinding is missing.
+    #[cfg(feature = "std")]
+    pub fn create(
+        ped_gens: &PedersenGens,
+        labels: &TranscriptLabels,
+        features: &[Vec<Scalar>],
+        feature_blindings: &[Vec<Scalar>],
+        means: &[Vec<Scalar>],
+        mean_blindings: &[Vec<Scalar>],
+        scales: &[Vec<Scalar>],
+        scale_blindings: &[Vec<Scalar>],
+    ) -> Result<(NormalizationProof, Vec<Vec<Scalar>>), ProofError> {
+        NormalizationProof::create_with_rng(
+            ped_gens,
+            labels,
+            features,
+            feature_blindings,
+            means,
+            mean_blindings,
+            scales,
+            scale_blindings,
+            &mut thread_rng(),
+        )
+    }
+
+    /// As `create`, with the blindings of the normalized features drawn from `rng`.
+    pub fn create_with_rng<T: RngCore + CryptoRng>(
+        ped_gens: &PedersenGens,
+        labels: &TranscriptLabels,
+        features: &[Vec<Scalar>],
+        feature_blindings: &[Vec<Scalar>],
+        means: &[Vec<Scalar>],
+        mean_blindings: &[Vec<Scalar>],
+        scales: &[Vec<Scalar>],
+        scale_blindings: &[Vec<Scalar>],
+        rng: &mut T,
+    ) -> Result<(NormalizationProof, Vec<Vec<Scalar>>), ProofError> {
+        let shape: Vec<usize> = features.iter().map(Vec::len).collect();
+        if !same_shape(&shape, means) || !same_shape(&shape, scales) {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        if !same_shape(&shape, feature_blindings) || !same_shape(&shape, mean_blindings) || !same_shape(&shape, scale_blindings) {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+
+        let normalized = normalized_features(features, means, scales);
+        let normalized_blindings = random_scalar_matrix(rng, &shape);
+
+        let mut transcript = labels.transcript(labels.normalization);
+        let mut prover = Prover::new(b"Normalization", &mut transcript);
+        let G = prover.allocate_point(b"G", ped_gens.B).0;
+        let H = prover.allocate_point(b"H", ped_gens.B_blinding).0;
+
+        let mut scalars = Vec::with_capacity(shape.iter().sum());
+        let mut points = Vec::with_capacity(shape.iter().sum());
+        let mut normalized_commitments = Vec::with_capacity(shape.len());
+        for (i, &len) in shape.iter().enumerate() {
+            let mut commitments = Vec::with_capacity(len);
+            for k in 0..len {
+                let (x, mu, s, z) = (features[i][k], means[i][k], scales[i][k], normalized[i][k]);
+                let difference_blinding = feature_blindings[i][k] - mean_blindings[i][k];
+                scalars.push(FeatureScalars {
+                    scale: prover.allocate_scalar(b"scale", s),
+                    scale_blinding: prover.allocate_scalar(b"scale blinding", scale_blindings[i][k]),
+                    product_blinding: prover.allocate_scalar(
+                        b"product blinding",
+                        normalized_blindings[i][k] - s * difference_blinding,
+                    ),
+                });
+
+                let (normalized_var, normalized_commitment) =
+                    prover.allocate_point(b"normalized", ped_gens.commit(z, normalized_blindings[i][k]));
+                points.push(FeaturePoints {
+                    scale: prover.allocate_point(b"scale", ped_gens.commit(s, scale_blindings[i][k])).0,
+                    difference: prover.allocate_point(b"difference", ped_gens.commit(x - mu, difference_blinding)).0,
+                    normalized: normalized_var,
+                });
+                commitments.push(normalized_commitment);
+            }
+            normalized_commitments.push(commitments);
+        }
+
+        normalization_statement(&mut prover, &scalars, &points, G, H);
+        let proof = prover.prove_with_rng(rng);
+
+        Ok((NormalizationProof { normalized_commitments, proof }, normalized_blindings))
+    }
+
+    /// The commitments to the normalized features, with the shape of the features. These are
+    /// the features a model trained over standardized features is evaluated over.
+    pub fn normalized_commitments(&self) -> &Vec<Vec<CompressedRistretto>> {
+        &self.normalized_commitments
+    }
+
+    /// Verifies that the normalized commitments commit to the features of
+    /// `feature_commitments`, under `ped_gens`, normalized with the means and the scales of
+    /// `mean_commitments` and `scale_commitments`.
+    pub fn verify(
+        &self,
+        ped_gens: &PedersenGens,
+        labels: &TranscriptLabels,
+        feature_commitments: &[Vec<CompressedRistretto>],
+        mean_commitments: &[Vec<CompressedRistretto>],
+        scale_commitments: &[Vec<CompressedRistretto>],
+    ) -> Result<(), ProofError> {
+        let shape: Vec<usize> = feature_commitments.iter().map(Vec::len).collect();
+        if !same_shape(&shape, mean_commitments)
+            || !same_shape(&shape, scale_commitments)
+            || !same_shape(&shape, &self.normalized_commitments)
+        {
+            return Err(ProofError::VerificationError);
+        }
+
+        let mut transcript = labels.transcript(labels.normalization);
+        let mut verifier = Verifier::new(b"Normalization", &mut transcript);
+        let G = verifier.allocate_point(b"G", ped_gens.B.compress())?;
+        let H = verifier.allocate_point(b"H", ped_gens.B_blinding.compress())?;
+
+        let mut scalars = Vec::with_capacity(shape.iter().sum());
+        let mut points = Vec::with_capacity(shape.iter().sum());
+        for (i, &len) in shape.iter().enumerate() {
+            for k in 0..len {
+                let decompress = |c: &CompressedRistretto| -> Result<RistrettoPoint, ProofError> {
+                    c.decompress().ok_or(ProofError::FormatError)
+                };
+                let difference = decompress(&feature_commitments[i][k])? - decompress(&mean_commitments[i][k])?;
+
+                scalars.push(FeatureScalars {
+                    scale: verifier.allocate_scalar(b"scale"),
+                    scale_blinding: verifier.allocate_scalar(b"scale blinding"),
+                    product_blinding: verifier.allocate_scalar(b"product blinding"),
+                });
+                let normalized = verifier.allocate_point(b"normalized", self.normalized_commitments[i][k])?;
+                points.push(FeaturePoints {
+                    scale: verifier.allocate_point(b"scale", scale_commitments[i][k])?,
+                    difference: verifier.allocate_point(b"difference", difference.compress())?,
+                    normalized,
+                });
+            }
+        }
+
+        normalization_statement(&mut verifier, &scalars, &points, G, H);
+        verifier.verify(&self.proof)
+    }
+}
+
+/// The scaled features and their blinding factors, see `min_max_scaled_features`.
+pub type ScaledFeatures = (Vec<Vec<Scalar>>, Vec<Vec<Scalar>>);
+
+/// The features scaled to the range of the training set, \\((x - min) \cdot k\\), with the
+/// committed minimums and the public scales \\(k\\), e.g. \\(\lfloor 2^{16} / (max - min) \rceil\\),
+/// together with their blinding factors. These are the openings of the commitments of
+/// `min_max_scaled_commitments`.
+///
+/// As the scales are public, the commitments to the scaled features are computed from the
+/// commitments to the features and to the minimums, with no proof. That the minimums are
+/// those of the training set is up to the party that commits to them.
+pub fn min_max_scaled_features(
+    features: &[Vec<Scalar>],
+    feature_blindings: &[Vec<Scalar>],
+    minimums: &[Vec<Scalar>],
+    minimum_blindings: &[Vec<Scalar>],
+    scales: &[Vec<Scalar>],
+) -> Result<ScaledFeatures, ProofError> {
+    let shape: Vec<usize> = features.iter().map(Vec::len).collect();
+    if !same_shape(&shape, minimums) || !same_shape(&shape, scales) {
+        return Err(ProofError::InvalidGeneratorsLength);
+    }
+    if !same_shape(&shape, feature_blindings) || !same_shape(&shape, minimum_blindings) {
+        return Err(ProofError::WrongNumBlindingFactors);
+    }
+
+    Ok((
+        normalized_features(features, minimums, scales),
+        normalized_features(feature_blindings, minimum_blindings, scales),
+    ))
+}
+
+/// The commitments to the features of `feature_commitments` scaled with the minimums of
+/// `minimum_commitments` and the public `scales`, see `min_max_scaled_features`. Returns a
+/// `VerificationError` if the shapes differ, and a `FormatError` if a commitment is not a
+/// valid point.
+pub fn min_max_scaled_commitments(
+    feature_commitments: &[Vec<CompressedRistretto>],
+    minimum_commitments: &[Vec<CompressedRistretto>],
+    scales: &[Vec<Scalar>],
+) -> Result<Vec<Vec<CompressedRistretto>>, ProofError> {
+    let shape: Vec<usize> = feature_commitments.iter().map(Vec::len).collect();
+    if !same_shape(&shape, minimum_commitments) || !same_shape(&shape, scales) {
+        return Err(ProofError::VerificationError);
+    }
+
+    feature_commitments.iter().zip(minimum_commitments.iter()).zip(scales.iter())
+        .map(|((X, M), k)| X.iter().zip(M.iter()).zip(k.iter())
+            .map(|((X, M), k)| {
+                let X = X.decompress().ok_or(ProofError::FormatError)?;
+                let M = M.decompress().ok_or(ProofError::FormatError)?;
+                Ok((k * (X - M)).compress())
+            })
+            .collect())
+        .collect()
+}
+
+fn same_shape<T>(shape: &[usize], matrix: &[Vec<T>]) -> bool {
+    matrix.len() == shape.len() && matrix.iter().zip(shape.iter()).all(|(row, &len)| row.len() == len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ip_zk_proof::r1cs::gadgets::signed_scalar;
+
+    fn scalars(values: &[i64]) -> Vec<Vec<Scalar>> {
+        values.chunks(3).map(|c| c.iter().map(|&x| signed_scalar(x)).collect()).collect()
+    }
+
+    fn commit(ped_gens: &PedersenGens, values: &[Vec<Scalar>], blindings: &[Vec<Scalar>]) -> Vec<Vec<CompressedRistretto>> {
+        values.iter().zip(blindings.iter())
+            .map(|(v, b)| v.iter().zip(b.iter()).map(|(v, b)| ped_gens.commit(*v, *b).compress()).collect())
+            .collect()
+    }
+
+    /// Proves the normalization of fixed features, with the given scales for the prover, and
+    /// verifies it against commitments to `verifier_scales`.
+    fn normalization_helper(scales: &[i64], verifier_scales: &[i64]) -> Result<(), ProofError> {
+        let ped_gens = PedersenGens::default();
+        let labels = TranscriptLabels::default();
+        let mut rng = thread_rng();
+        let features = scalars(&[120, -40, 7, 900, 3, 0]);
+        let means = scalars(&[100, -50, 0, 1000, 3, 2]);
+        let blindings = || random_scalar_matrix(&mut thread_rng(), &[3, 3]);
+        let (feature_blindings, mean_blindings, scale_blindings) = (blindings(), blindings(), blindings());
+
+        let (proof, normalized_blindings) = NormalizationProof::create_with_rng(
+            &ped_gens,
+            &labels,
+            &features,
+            &feature_blindings,
+            &means,
+            &mean_blindings,
+            &scalars(scales),
+            &scale_blindings,
+            &mut rng,
+        )?;
+
+        // The normalized features are (20, 10, 7, -100, 0, -2) times the scales
+        let expected = normalized_features(&features, &means, &scalars(scales));
+        assert_eq!(expected[1][0], signed_scalar(-100 * scales[3]));
+        assert_eq!(proof.normalized_commitments(), &commit(&ped_gens, &expected, &normalized_blindings));
+
+        proof.verify(
+            &ped_gens,
+            &labels,
+            &commit(&ped_gens, &features, &feature_blindings),
+            &commit(&ped_gens, &means, &mean_blindings),
+            &commit(&ped_gens, &scalars(verifier_scales), &scale_blindings),
+        )
+    }
+
+    #[test]
+    fn normalization_proof_works() {
+        let scales = [3, 5, 1, 2, 7, 65536];
+        assert!(normalization_helper(&scales, &scales).is_ok());
+    }
+
+    #[test]
+    fn min_max_scaling() {
+        let ped_gens = PedersenGens::default();
+        let features = scalars(&[120, -40, 7]);
+        let minimums = scalars(&[100, -50, 0]);
+        let scales = scalars(&[3, 5, 65536]);
+        let feature_blindings = random_scalar_matrix(&mut thread_rng(), &[3]);
+        let minimum_blindings = random_scalar_matrix(&mut thread_rng(), &[3]);
+
+        let (scaled, scaled_blindings) =
+            min_max_scaled_features(&features, &feature_blindings, &minimums, &minimum_blindings, &scales).unwrap();
+        assert_eq!(scaled, scalars(&[60, 50, 7 * 65536]));
+        assert_eq!(
+            min_max_scaled_commitments(
+                &commit(&ped_gens, &features, &feature_blindings),
+                &commit(&ped_gens, &minimums, &minimum_blindings),
+                &scales,
+            ),
+            Ok(commit(&ped_gens, &scaled, &scaled_blindings))
+        );
+        assert_eq!(
+            min_max_scaled_features(&features, &feature_blindings, &minimums, &minimum_blindings, &scalars(&[1])).err(),
+            Some(ProofError::InvalidGeneratorsLength)
+        );
+    }
+
+    #[test]
+    fn normalization_proof_fails() {
+        assert_eq!(
+            normalization_helper(&[3, 5, 1, 2, 7, 9], &[3, 5, 1, 2, 7, 8]).err(),
+            Some(ProofError::VerificationError)
+        );
+        assert_eq!(
+            normalization_helper(&[3, 5, 1], &[3, 5, 1]).err(),
+            Some(ProofError::InvalidGeneratorsLength)
+        );
+    }
+}
@@ -1,11 +1,24 @@
+use alloc::vec::Vec;
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::ristretto::CompressedRistretto;
 use crate::boolean_proofs::square_proof::FloatingSquareZKProof;
-use ip_zk_proof::{PedersenGens, BulletproofGens, ProofError};
+#[cfg(feature = "std")]
+use crate::boolean_proofs::square_proof::SQUARE_RANGE_BITSIZE;
+use ip_zk_proof::{CanonicalPoints, PedersenGens, BulletproofGens, ProofError, SerializedSize};
+#[cfg(feature = "std")]
 use rand::thread_rng;
-use merlin::Transcript;
+use rand_chacha::ChaChaRng;
+use rand_core::{CryptoRng, CryptoRngCore, RngCore, SeedableRng};
+use crate::utils::misc::map_sensor_coords;
+use crate::utils::randomness::{random_scalar_matrix, random_seed_matrix};
+use crate::metrics::{count, PhaseMetrics, ProofPhase, ProverMetrics, Stopwatch};
+#[cfg(feature = "std")]
+use crate::metrics::NoMetrics;
+use crate::TranscriptLabels;
+use crate::utils::misc::bincode_size;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
 /// This structure will prove the correct generation of the standard
 /// deviation. The tools we may use here are a commitment of the Variance and the Variance.
 /// The proof then consists in proving that the square of the committed value we claim to be
@@ -18,31 +31,85 @@ pub struct StdProof {
 }
 
 impl StdProof {
+    #[cfg(feature = "std")]
     pub fn create_all(
         bulletproof_generators: &BulletproofGens,
         pedersen_generators: &PedersenGens,
-        stds: &Vec<Vec<Scalar>>,
-        variances: &Vec<Vec<Scalar>>,
+        stds: &[Vec<Scalar>],
+        variances: &[Vec<Scalar>],
         commitment_std: &Vec<Vec<CompressedRistretto>>,
-        blinding_commitment_std: &Vec<Vec<Scalar>>,
-        blinding_commitment_variance: &Vec<Vec<Scalar>>
+        blinding_commitment_std: &[Vec<Scalar>],
+        blinding_commitment_variance: &[Vec<Scalar>],
+        labels: &TranscriptLabels,
     ) -> Result<Vec<Vec<StdProof>>, ProofError> {
-        let mut proofs: Vec<Vec<StdProof>> = stds.iter().map(|_| Vec::new()).collect();
-        for (index, a) in stds.into_iter().enumerate() {
-            for (jindex, &std) in a.into_iter().enumerate() {
-                proofs[index].push(StdProof::create(
-                    &bulletproof_generators,
-                    pedersen_generators,
-                    std,
-                    variances[index][jindex],
-                    commitment_std[index][jindex],
-                    blinding_commitment_std[index][jindex],
-                    blinding_commitment_variance[index][jindex]
-                )?)
-            }
-        }
+        StdProof::create_all_with_metrics(
+            bulletproof_generators,
+            pedersen_generators,
+            stds,
+            variances,
+            commitment_std,
+            blinding_commitment_std,
+            blinding_commitment_variance,
+            labels,
+            SQUARE_RANGE_BITSIZE,
+            &mut thread_rng(),
+            &mut NoMetrics,
+        )
+    }
+
+    /// As `create_all`, with the blinding factors and the nonces drawn from `rng`, with range
+    /// proofs of `range_bitsize` bits, see `FloatingSquareZKProof::create_with_rng`, and
+    /// reporting the `StdProofs` phase to `metrics`. The commitments to the standard deviations
+    /// are counted in the phase.
+    pub fn create_all_with_metrics(
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        stds: &[Vec<Scalar>],
+        variances: &[Vec<Scalar>],
+        commitment_std: &Vec<Vec<CompressedRistretto>>,
+        blinding_commitment_std: &[Vec<Scalar>],
+        blinding_commitment_variance: &[Vec<Scalar>],
+        labels: &TranscriptLabels,
+        range_bitsize: usize,
+        mut rng: &mut dyn CryptoRngCore,
+        metrics: &mut dyn ProverMetrics,
+    ) -> Result<Vec<Vec<StdProof>>, ProofError> {
+        let _span = phase_span!("std_proofs");
+        let stopwatch = Stopwatch::start(metrics);
+        let shape: Vec<usize> = stds.iter().map(|a| a.len()).collect();
+        let blindings_round_square = random_scalar_matrix(&mut rng, &shape);
+        let seeds = random_seed_matrix(&mut rng, &shape);
+        let proofs: Vec<Vec<Result<StdProof, ProofError>>> = map_sensor_coords(
+            &shape,
+            |index, jindex| StdProof::create_with_blinding(
+                bulletproof_generators,
+                pedersen_generators,
+                stds[index][jindex],
+                variances[index][jindex],
+                commitment_std[index][jindex],
+                blinding_commitment_std[index][jindex],
+                blinding_commitment_variance[index][jindex],
+                blindings_round_square[index][jindex],
+                labels,
+                range_bitsize,
+                &mut ChaChaRng::from_seed(seeds[index][jindex]),
+            )
+        );
+        let proofs: Vec<Vec<StdProof>> = proofs.into_iter()
+            .map(|a| a.into_iter().collect())
+            .collect::<Result<_, ProofError>>()?;
+        metrics.phase(ProofPhase::StdProofs, PhaseMetrics {
+            duration: stopwatch.elapsed(metrics),
+            // Each proof carries the commitment to the square of its standard deviation
+            commitments: count(commitment_std) + count(&proofs),
+            proofs: count(&proofs),
+            proof_size: bincode_size(commitment_std) + bincode_size(&proofs),
+        });
+
         Ok(proofs)
     }
+
+    #[cfg(feature = "std")]
     pub fn create(
         bulletproof_generators: &BulletproofGens,
         pedersen_generators: &PedersenGens,
@@ -51,16 +118,44 @@ impl StdProof {
         commitment_std: CompressedRistretto,
         blinding_commitment_std: Scalar,
         blinding_commitment_variance: Scalar,
+        labels: &TranscriptLabels,
+    ) -> Result<StdProof, ProofError> {
+        StdProof::create_with_blinding(
+            bulletproof_generators,
+            pedersen_generators,
+            std,
+            variance,
+            commitment_std,
+            blinding_commitment_std,
+            blinding_commitment_variance,
+            Scalar::random(&mut thread_rng()),
+            labels,
+            SQUARE_RANGE_BITSIZE,
+            &mut thread_rng(),
+        )
+    }
+
+    fn create_with_blinding<T: RngCore + CryptoRng>(
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        std: Scalar,
+        variance: Scalar,
+        commitment_std: CompressedRistretto,
+        blinding_commitment_std: Scalar,
+        blinding_commitment_variance: Scalar,
+        blinding_factor_round_square: Scalar,
+        labels: &TranscriptLabels,
+        range_bitsize: usize,
+        rng: &mut T,
     ) -> Result<StdProof, ProofError> {
         // This most likely won't exactly equal the variance, as we are working with integer
         // values.
-        let squared_std = &std * &std;
-        let blinding_factor_round_square = Scalar::random(&mut thread_rng());
+        let squared_std = std * std;
         let commitment_sq_std = pedersen_generators.commit(squared_std, blinding_factor_round_square);
 
-        let mut transcript = Transcript::new(b"StandardDeviationProof");
+        let mut transcript = labels.transcript(labels.standard_deviation);
 
-        let square_root_proof = FloatingSquareZKProof::create(
+        let square_root_proof = FloatingSquareZKProof::create_with_rng(
             bulletproof_generators,
             *pedersen_generators,
             variance,
@@ -70,7 +165,9 @@ impl StdProof {
             blinding_commitment_std,
             blinding_factor_round_square,
             commitment_std,
-            &mut transcript
+            &mut transcript,
+            range_bitsize,
+            rng,
         )?;
 
         Ok(StdProof{
@@ -79,46 +176,116 @@ impl StdProof {
         })
     }
 
+    #[cfg(feature = "std")]
     pub fn verify_all(
         bulletproof_generators: &BulletproofGens,
         pedersen_generators: &PedersenGens,
-        commitment_std: &Vec<Vec<CompressedRistretto>>,
-        commitment_variance: &Vec<Vec<CompressedRistretto>>,
-        proofs: &Vec<Vec<StdProof>>
+        commitment_std: &[Vec<CompressedRistretto>],
+        commitment_variance: &[Vec<CompressedRistretto>],
+        proofs: &[Vec<StdProof>],
+        labels: &TranscriptLabels,
+    ) -> Result<(), ProofError> {
+        StdProof::verify_all_with_rng(
+            bulletproof_generators,
+            pedersen_generators,
+            commitment_std,
+            commitment_variance,
+            proofs,
+            labels,
+            SQUARE_RANGE_BITSIZE,
+            &mut thread_rng(),
+        )
+    }
+
+    /// As `verify_all`, with the randomness of the verification drawn from `rng`, and range
+    /// proofs of `range_bitsize` bits.
+    pub fn verify_all_with_rng(
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        commitment_std: &[Vec<CompressedRistretto>],
+        commitment_variance: &[Vec<CompressedRistretto>],
+        proofs: &[Vec<StdProof>],
+        labels: &TranscriptLabels,
+        range_bitsize: usize,
+        mut rng: &mut dyn CryptoRngCore,
     ) -> Result<(), ProofError> {
-        for (index, a) in proofs.into_iter().enumerate() {
-            for (jindex, proof) in a.into_iter().enumerate() {
-                proof.clone().verify(
-                    &bulletproof_generators,
+        let _span = phase_span!("std_proofs_verify");
+        for (index, a) in proofs.iter().enumerate() {
+            for (jindex, proof) in a.iter().enumerate() {
+                proof.clone().verify_with_rng(
+                    bulletproof_generators,
                     pedersen_generators,
                     commitment_std[index][jindex],
-                    commitment_variance[index][jindex]
+                    commitment_variance[index][jindex],
+                    labels,
+                    range_bitsize,
+                    &mut rng,
                 )?;
             }
         }
-        return Ok(())
+        Ok(())
     }
 
+    #[cfg(feature = "std")]
     pub fn verify(
         self,
         bulletproof_generators: &BulletproofGens,
         pedersen_generators: &PedersenGens,
         commitment_std: CompressedRistretto,
         commitment_variance: CompressedRistretto,
+        labels: &TranscriptLabels,
+    ) -> Result<(), ProofError> {
+        self.verify_with_rng(
+            bulletproof_generators,
+            pedersen_generators,
+            commitment_std,
+            commitment_variance,
+            labels,
+            SQUARE_RANGE_BITSIZE,
+            &mut thread_rng(),
+        )
+    }
+
+    /// As `verify`, with the randomness of the verification drawn from `rng`, and range proofs
+    /// of `range_bitsize` bits.
+    pub fn verify_with_rng<T: RngCore + CryptoRng>(
+        self,
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        commitment_std: CompressedRistretto,
+        commitment_variance: CompressedRistretto,
+        labels: &TranscriptLabels,
+        range_bitsize: usize,
+        rng: &mut T,
     ) -> Result<(), ProofError> {
-        let mut transcript = Transcript::new(b"StandardDeviationProof");
+        let mut transcript = labels.transcript(labels.standard_deviation);
 
-        self.proof_floating_sqr.verify(
-            &bulletproof_generators,
+        self.proof_floating_sqr.verify_with_rng(
+            bulletproof_generators,
             *pedersen_generators,
             commitment_std,
             self.commitment_sq_std,
             commitment_variance,
-            &mut transcript
+            &mut transcript,
+            range_bitsize,
+            rng,
         )
     }
 }
 
+impl CanonicalPoints for StdProof {
+    fn points_are_canonical(&self) -> bool {
+        self.commitment_sq_std.points_are_canonical() && self.proof_floating_sqr.points_are_canonical()
+    }
+}
+
+impl SerializedSize for StdProof {
+    /// Size of the bincode encoding of the proof.
+    fn serialized_size(&self) -> usize {
+        bincode_size(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -126,6 +293,41 @@ mod tests {
     use crate::algebraic_proofs::variance_proof::VarianceProof;
     use crate::utils::misc::compute_subtraction_vector;
 
+    #[test]
+    fn test_std_proof_labels() {
+        let bulletproof_generators = BulletproofGens::new(64, 1);
+        let pedersen_generators: PedersenGens = PedersenGens::default();
+        let (std, variance) = (Scalar::from(42u64), Scalar::from(1800u64));
+        let blinding_std = Scalar::random(&mut thread_rng());
+        let blinding_variance = Scalar::random(&mut thread_rng());
+        let commitment_std = pedersen_generators.commit(std, blinding_std).compress();
+        let commitment_variance = pedersen_generators.commit(variance, blinding_variance).compress();
+
+        let labels = TranscriptLabels::with_domain(b"zkSENSE");
+        let proof = StdProof::create(
+            &bulletproof_generators,
+            &pedersen_generators,
+            std,
+            variance,
+            commitment_std,
+            blinding_std,
+            blinding_variance,
+            &labels,
+        ).unwrap();
+
+        let verify = |labels: &TranscriptLabels| proof.clone().verify(
+            &bulletproof_generators,
+            &pedersen_generators,
+            commitment_std,
+            commitment_variance,
+            labels,
+        );
+        assert!(verify(&labels).is_ok());
+        // The proof is bound to its application domain
+        assert!(verify(&TranscriptLabels::default()).is_err());
+        assert!(verify(&TranscriptLabels::with_domain(b"other")).is_err());
+    }
+
     #[test]
     fn test_vector_addition() {
         let dummy_sensor_values: Vec<[Vec<Scalar>; 3]> = vec![
@@ -0,0 +1,310 @@
+use alloc::vec::Vec;
+use ip_zk_proof::{BulletproofGens, PedersenGens};
+use ip_zk_proof::r1cs::{ConstraintSystem, Prover, R1CSError, R1CSProof, Variable, Verifier};
+use ip_zk_proof::r1cs::gadgets::{less_or_equal, less_than, range};
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::ristretto::CompressedRistretto;
+
+use merlin::Transcript;
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand_core::{CryptoRng, RngCore};
+use core::convert::TryInto;
+
+#[cfg(feature = "std")]
+use crate::utils::misc::map_sensor_coords;
+
+/// Bitsize of the standard deviation, and of the differences with the variance.
+const STD_BITSIZE: usize = 32;
+
+/// Number of generators needed by the proof, i.e. the multipliers of the constraint
+/// system rounded up to the next power of two.
+pub const STD_R1CS_GENS_CAPACITY: usize = 128;
+
+/// Constrains `std` to be the floor of the square root of `variance`, i.e.
+/// \\(std^2 \leq variance < (std + 1)^2\\). The assignments are only needed by the prover.
+pub fn std_gadget<CS: ConstraintSystem>(
+    cs: &mut CS,
+    std: Variable,
+    variance: Variable,
+    std_assignment: Option<u64>,
+    variance_assignment: Option<u64>,
+) -> Result<(), R1CSError> {
+    // Otherwise the square could wrap around the group order
+    range(cs, std.into(), std_assignment, STD_BITSIZE)?;
+
+    let (_, _, square) = cs.multiply(std.into(), std.into());
+    let assignments = std_assignment.zip(variance_assignment);
+
+    less_or_equal(
+        cs,
+        square.into(),
+        variance.into(),
+        assignments.map(|(std, variance)| variance.wrapping_sub(std.wrapping_mul(std))),
+        STD_BITSIZE,
+    )?;
+
+    // (std + 1)^2 is linear in the square
+    less_than(
+        cs,
+        variance.into(),
+        square + std * Scalar::from(2u64) + Scalar::ONE,
+        assignments.map(|(std, variance)| {
+            std.wrapping_mul(std).wrapping_add(std << 1).wrapping_sub(variance)
+        }),
+        STD_BITSIZE,
+    )
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// Proof that a committed value is the floor of the square root of a committed variance,
+/// as the `StdProof`, but expressed as a single constraint system. Instead of two square
+/// proofs and two range proofs, it proves one multiplication and three range constraints,
+/// with a single inner product argument.
+///
+/// The `BulletproofGens` must have a capacity of at least `STD_R1CS_GENS_CAPACITY`.
+pub struct StdR1CSProof {
+    proof: R1CSProof,
+}
+
+impl StdR1CSProof {
+    #[cfg(feature = "std")]
+    pub fn create_all(
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        stds: &[Vec<Scalar>],
+        variances: &[Vec<Scalar>],
+        blinding_commitment_std: &[Vec<Scalar>],
+        blinding_commitment_variance: &[Vec<Scalar>]
+    ) -> Result<Vec<Vec<StdR1CSProof>>, R1CSError> {
+        let shape: Vec<usize> = stds.iter().map(|a| a.len()).collect();
+        let proofs: Vec<Vec<Result<StdR1CSProof, R1CSError>>> = map_sensor_coords(
+            &shape,
+            |index, jindex| StdR1CSProof::create(
+                bulletproof_generators,
+                pedersen_generators,
+                stds[index][jindex],
+                variances[index][jindex],
+                blinding_commitment_std[index][jindex],
+                blinding_commitment_variance[index][jindex],
+            )
+        );
+        proofs.into_iter().map(|a| a.into_iter().collect()).collect()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn create(
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        std: Scalar,
+        variance: Scalar,
+        blinding_commitment_std: Scalar,
+        blinding_commitment_variance: Scalar,
+    ) -> Result<StdR1CSProof, R1CSError> {
+        StdR1CSProof::create_with_rng(
+            bulletproof_generators,
+            pedersen_generators,
+            std,
+            variance,
+            blinding_commitment_std,
+            blinding_commitment_variance,
+            &mut thread_rng(),
+        )
+    }
+
+    /// As `create`, with the blindings of the proof drawn from `rng`.
+    pub fn create_with_rng<T: RngCore + CryptoRng>(
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        std: Scalar,
+        variance: Scalar,
+        blinding_commitment_std: Scalar,
+        blinding_commitment_variance: Scalar,
+        rng: &mut T,
+    ) -> Result<StdR1CSProof, R1CSError> {
+        let mut prover = Prover::new(
+            pedersen_generators,
+            Transcript::new(b"StandardDeviationR1CSProof"),
+        );
+
+        let (_, std_var) = prover.commit(std, blinding_commitment_std);
+        let (_, variance_var) = prover.commit(variance, blinding_commitment_variance);
+        std_gadget(
+            &mut prover,
+            std_var,
+            variance_var,
+            Some(scalar_to_u64(&std)),
+            Some(scalar_to_u64(&variance)),
+        )?;
+
+        Ok(StdR1CSProof {
+            proof: prover.prove_with_rng(bulletproof_generators, rng)?,
+        })
+    }
+
+    #[cfg(feature = "std")]
+    pub fn verify_all(
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        commitment_std: &[Vec<CompressedRistretto>],
+        commitment_variance: &[Vec<CompressedRistretto>],
+        proofs: &[Vec<StdR1CSProof>]
+    ) -> Result<(), R1CSError> {
+        for (index, a) in proofs.iter().enumerate() {
+            for (jindex, proof) in a.iter().enumerate() {
+                proof.verify(
+                    bulletproof_generators,
+                    pedersen_generators,
+                    commitment_std[index][jindex],
+                    commitment_variance[index][jindex]
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn verify(
+        &self,
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        commitment_std: CompressedRistretto,
+        commitment_variance: CompressedRistretto,
+    ) -> Result<(), R1CSError> {
+        self.verify_with_rng(
+            bulletproof_generators,
+            pedersen_generators,
+            commitment_std,
+            commitment_variance,
+            &mut thread_rng(),
+        )
+    }
+
+    /// As `verify`, with the randomness of the verification drawn from `rng`.
+    pub fn verify_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        commitment_std: CompressedRistretto,
+        commitment_variance: CompressedRistretto,
+        rng: &mut T,
+    ) -> Result<(), R1CSError> {
+        let mut verifier = Verifier::new(Transcript::new(b"StandardDeviationR1CSProof"));
+
+        let std_var = verifier.commit(commitment_std);
+        let variance_var = verifier.commit(commitment_variance);
+        std_gadget(&mut verifier, std_var, variance_var, None, None)?;
+
+        verifier.verify_with_rng(&self.proof, pedersen_generators, bulletproof_generators, rng)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.proof.to_bytes()
+    }
+
+    pub fn from_bytes(slice: &[u8]) -> Result<StdR1CSProof, R1CSError> {
+        Ok(StdR1CSProof { proof: R1CSProof::from_bytes(slice)? })
+    }
+
+    /// As `from_bytes`, rejecting proofs with non-canonical points, see
+    /// `R1CSProof::from_bytes_strict`.
+    pub fn from_bytes_strict(slice: &[u8]) -> Result<StdR1CSProof, R1CSError> {
+        Ok(StdR1CSProof { proof: R1CSProof::from_bytes_strict(slice)? })
+    }
+}
+
+/// The witnesses are integers, so we keep the lower 64 bits of the scalar.
+pub(crate) fn scalar_to_u64(value: &Scalar) -> u64 {
+    u64::from_le_bytes(
+        value.to_bytes()[0..8]
+            .try_into()
+            .expect("Should never happen as we are taking a slice of 8."),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    fn std_proof_helper(std: u64, variance: u64) -> Result<(), R1CSError> {
+        let bulletproof_generators = BulletproofGens::new(STD_R1CS_GENS_CAPACITY, 1);
+        let pedersen_generators = PedersenGens::default();
+        let blinding_std = Scalar::random(&mut thread_rng());
+        let blinding_variance = Scalar::random(&mut thread_rng());
+
+        let proof = StdR1CSProof::create(
+            &bulletproof_generators,
+            &pedersen_generators,
+            Scalar::from(std),
+            Scalar::from(variance),
+            blinding_std,
+            blinding_variance,
+        )?;
+
+        let proof = StdR1CSProof::from_bytes_strict(&proof.to_bytes())?;
+        proof.verify(
+            &bulletproof_generators,
+            &pedersen_generators,
+            pedersen_generators.commit(Scalar::from(std), blinding_std).compress(),
+            pedersen_generators.commit(Scalar::from(variance), blinding_variance).compress(),
+        )
+    }
+
+    #[test]
+    fn test_std_r1cs_proof() {
+        assert!(std_proof_helper(11, 128).is_ok());
+        assert!(std_proof_helper(42, 1800).is_ok());
+        assert!(std_proof_helper(59879, 3585520562).is_ok());
+        // Exact squares
+        assert!(std_proof_helper(12, 144).is_ok());
+        assert!(std_proof_helper(0, 0).is_ok());
+
+        assert!(std_proof_helper(12, 143).is_err());
+        assert!(std_proof_helper(10, 128).is_err());
+        assert!(std_proof_helper(13, 144).is_err());
+    }
+
+    #[test]
+    fn test_std_r1cs_proof_all() {
+        let bulletproof_generators = BulletproofGens::new(STD_R1CS_GENS_CAPACITY, 1);
+        let pedersen_generators: PedersenGens = PedersenGens::default();
+
+        let stds = vec![vec![Scalar::from(11u64), Scalar::from(42u64)], vec![Scalar::from(183u64)]];
+        let variances = vec![vec![Scalar::from(128u64), Scalar::from(1800u64)], vec![Scalar::from(33800u64)]];
+        let blinding_std: Vec<Vec<Scalar>> = stds.iter().map(|a| a.iter().map(|_| Scalar::random(&mut thread_rng())).collect()).collect();
+        let blinding_variance: Vec<Vec<Scalar>> = stds.iter().map(|a| a.iter().map(|_| Scalar::random(&mut thread_rng())).collect()).collect();
+        let commit = |values: &Vec<Vec<Scalar>>, blindings: &Vec<Vec<Scalar>>| -> Vec<Vec<CompressedRistretto>> {
+            values.iter().zip(blindings.iter()).map(
+                |(v, b)| v.iter().zip(b.iter()).map(|(v, b)| pedersen_generators.commit(*v, *b).compress()).collect()
+            ).collect()
+        };
+
+        let proofs = StdR1CSProof::create_all(
+            &bulletproof_generators,
+            &pedersen_generators,
+            &stds,
+            &variances,
+            &blinding_std,
+            &blinding_variance,
+        ).unwrap();
+
+        assert!(StdR1CSProof::verify_all(
+            &bulletproof_generators,
+            &pedersen_generators,
+            &commit(&stds, &blinding_std),
+            &commit(&variances, &blinding_variance),
+            &proofs
+        ).is_ok());
+
+        // The proofs are bound to their own commitments
+        assert!(StdR1CSProof::verify_all(
+            &bulletproof_generators,
+            &pedersen_generators,
+            &commit(&variances, &blinding_variance),
+            &commit(&stds, &blinding_std),
+            &proofs
+        ).is_err());
+    }
+}
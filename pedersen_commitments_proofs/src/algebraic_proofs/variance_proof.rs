@@ -1,90 +1,150 @@
-use ip_zk_proof::{InnerProductZKProof, BulletproofGens, PedersenGens, inner_product, ProofError};
+use alloc::vec::Vec;
+use alloc::vec;
+use ip_zk_proof::{CanonicalPoints, InnerProductZKProof, BulletproofGens, PedersenGens, inner_product, ProofError, SerializedSize};
+#[cfg(not(feature = "prover-only"))]
+use ip_zk_proof::PrecomputedGens;
 
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::ristretto::{RistrettoPoint, CompressedRistretto};
 
-use merlin::Transcript;
-
+#[cfg(feature = "std")]
 use rand::thread_rng;
-use crate::PedersenVecGens;
-use crate::boolean_proofs::equality_proof::EqualityZKProof;
-use crate::algebraic_proofs::diff_vector_gen_proof::{prove_equality_commitments, verify_proof_equality_commitments};
+use rand_chacha::ChaChaRng;
+use rand_core::{CryptoRng, CryptoRngCore, RngCore, SeedableRng};
+use crate::metrics::{count, PhaseMetrics, ProofPhase, ProverMetrics, Stopwatch};
+#[cfg(feature = "std")]
+use crate::metrics::NoMetrics;
+use crate::{PedersenVecGens, TranscriptLabels};
+use crate::boolean_proofs::equality_proof::{BatchEqualityProof, EqualityProver, EqualityStatement};
 use crate::algebraic_proofs::std_proof::StdProof;
-use crate::utils::commitment_fns::multiple_commit;
-use crate::utils::misc::compute_subtraction_vector;
-
-define_proof! {
-    dlog,
-    "DLog",
-    (x),
-    (A),
-    (G) :
-    A = (x * G)
-}
-
-#[derive(Clone)]
+#[cfg(feature = "std")]
+use crate::boolean_proofs::square_proof::SQUARE_RANGE_BITSIZE;
+use crate::utils::commitment_fns::multiple_commit_with_rng;
+use crate::utils::misc::{compute_subtraction_vector, decompress_all, map_sensor_coords};
+use crate::utils::randomness::{random_scalar_matrix, random_seed_matrix};
+use crate::utils::misc::bincode_size;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
 pub struct VarianceProof {
     comm_sensors_base_H: Vec<Vec<CompressedRistretto>>,
-    proofs_base_H_comms: Vec<Vec<EqualityZKProof>>,
+    proofs_base_H_comms: BatchEqualityProof,
     variance_commitment: Vec<Vec<CompressedRistretto>>,
     proofs_variance: Vec<Vec<InnerProductZKProof>>,
     std_commitment: Vec<Vec<CompressedRistretto>>,
     proofs_std: Vec<Vec<StdProof>>
 }
 
+/// The inner product proofs of the variances, and the commitments to the variances.
+type VarianceProofs = (Vec<Vec<InnerProductZKProof>>, Vec<Vec<CompressedRistretto>>);
+
 impl VarianceProof {
+    /// Creates the proof, and returns it together with the blinding factors of the
+    /// commitments to the standard deviations.
+    #[cfg(feature = "std")]
     pub fn create(
-        all_sensor_vectors: &Vec<[Vec<Scalar>; 3]>,
-        all_sensor_stds: &Vec<Vec<Scalar>>,
-        sensor_additions: &Vec<Vec<Scalar>>,
-        variances: &Vec<Vec<Scalar>>,
+        all_sensor_vectors: &[[Vec<Scalar>; 3]],
+        all_sensor_stds: &[Vec<Scalar>],
+        sensor_additions: &[Vec<Scalar>],
+        variances: &[Vec<Scalar>],
         bulletproof_generators: &BulletproofGens,
         pedersen_generators: &PedersenGens,
         pedersen_vec_generators: &PedersenVecGens,
         // base of the "right hand side" bulleproof generators
         secondary_pedersen_vec_generators: &PedersenVecGens,
         // Blinding factors of the signed commitments of the sensors
-        signed_commitment_blinding_factors: &Vec<Vec<Scalar>>,
+        signed_commitment_blinding_factors: &[Vec<Scalar>],
         // Blinding factors of the diff commitments of the sensors
-        diff_blinding_factors: &Vec<Vec<Scalar>>,
-        size_sensors: &Vec<usize>,
+        diff_blinding_factors: &[Vec<Scalar>],
+        size_sensors: &[usize],
+        size_vectors: usize,
+        labels: &TranscriptLabels,
+    ) -> Result<(Self, Vec<Vec<Scalar>>), ProofError> {
+        VarianceProof::create_with_metrics(
+            all_sensor_vectors,
+            all_sensor_stds,
+            sensor_additions,
+            variances,
+            bulletproof_generators,
+            pedersen_generators,
+            pedersen_vec_generators,
+            secondary_pedersen_vec_generators,
+            signed_commitment_blinding_factors,
+            diff_blinding_factors,
+            size_sensors,
+            size_vectors,
+            labels,
+            SQUARE_RANGE_BITSIZE,
+            &mut thread_rng(),
+            &mut NoMetrics,
+        )
+    }
+
+    /// As `create`, with the blinding factors and the external randomness of the proofs drawn
+    /// from `rng`, with range proofs of `range_bitsize` bits in the proofs of the standard
+    /// deviations, and reporting the `VarianceProof` phase, and then the `StdProofs` phase of
+    /// the standard deviations, to `metrics`.
+    pub fn create_with_metrics(
+        all_sensor_vectors: &[[Vec<Scalar>; 3]],
+        all_sensor_stds: &[Vec<Scalar>],
+        sensor_additions: &[Vec<Scalar>],
+        variances: &[Vec<Scalar>],
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        pedersen_vec_generators: &PedersenVecGens,
+        secondary_pedersen_vec_generators: &PedersenVecGens,
+        signed_commitment_blinding_factors: &[Vec<Scalar>],
+        diff_blinding_factors: &[Vec<Scalar>],
+        size_sensors: &[usize],
         size_vectors: usize,
-    ) -> Result<Self, ProofError> {
+        labels: &TranscriptLabels,
+        range_bitsize: usize,
+        mut rng: &mut dyn CryptoRngCore,
+        metrics: &mut dyn ProverMetrics,
+    ) -> Result<(Self, Vec<Vec<Scalar>>), ProofError> {
+        let _span = phase_span!("variance_proof");
+        let stopwatch = Stopwatch::start(metrics);
         let length_all_vectors = all_sensor_vectors.len();
         let initial_nr_sensors = signed_commitment_blinding_factors.len();
         // We need to prove the commitment of the vectors with the sensor data with base H
-        let (comm_sensors_base_H, blinding_sensors_base_H) = multiple_commit(
+        let (comm_sensors_base_H, blinding_sensors_base_H) = multiple_commit_with_rng(
             secondary_pedersen_vec_generators,
-            &all_sensor_vectors
+            all_sensor_vectors,
+            &mut rng,
         );
 
-        let proofs_base_H_comms: Vec<Vec<EqualityZKProof>> = prove_equality_commitments(
-            &pedersen_vec_generators,
-            &vec![secondary_pedersen_vec_generators.clone(); length_all_vectors],
-            &all_sensor_vectors,
-            &signed_commitment_blinding_factors,
-            &blinding_sensors_base_H
-        );
+        // Only the signed vectors have a commitment under the signature generators
+        let mut prover_base_H_comms = EqualityProver::new();
+        for i in 0..initial_nr_sensors {
+            for j in 0..3 {
+                prover_base_H_comms = prover_base_H_comms.add_pair(
+                    pedersen_vec_generators,
+                    secondary_pedersen_vec_generators,
+                    &all_sensor_vectors[i][j],
+                    signed_commitment_blinding_factors[i][j],
+                    blinding_sensors_base_H[i][j],
+                );
+            }
+        }
+        let proofs_base_H_comms = prover_base_H_comms
+            .prove_with_rng(&mut labels.transcript(labels.commitment_equality), &mut rng)?;
 
         // Now we calculate the values of which we will compute the inner product of
         let subtraction_values: Vec<Vec<Vec<Scalar>>> = compute_subtraction_vector(
-            &size_sensors,
-            &all_sensor_vectors,
-            &sensor_additions
+            size_sensors,
+            all_sensor_vectors,
+            sensor_additions
         );
 
-        let blinders_comm_variances: Vec<Vec<Scalar>> = (0..length_all_vectors).map(
-            |_| (0..3).map(
-                |_| Scalar::random(&mut thread_rng())
-            ).collect()
-        ).collect();
+        let blinders_comm_variances: Vec<Vec<Scalar>> =
+            random_scalar_matrix(&mut rng, &vec![3; length_all_vectors]);
 
         let mut variances_a_blindings = vec![Vec::new(); length_all_vectors];
         for (i, a) in signed_commitment_blinding_factors.iter().enumerate() {
             for (j, signed_hash_blinding) in a.iter().enumerate() {
                 variances_a_blindings[i].push(
-                    Scalar::from(size_sensors[i] as u64) * signed_hash_blinding - &sensor_additions[i][j] +
-                        Scalar::from(size_sensors[i] as u64) * blinding_sensors_base_H[i][j] - &sensor_additions[i][j]
+                    Scalar::from(size_sensors[i] as u64) * signed_hash_blinding - sensor_additions[i][j] +
+                        Scalar::from(size_sensors[i] as u64) * blinding_sensors_base_H[i][j] - sensor_additions[i][j]
                 )
             }
         }
@@ -92,73 +152,139 @@ impl VarianceProof {
         for (i, a) in diff_blinding_factors.iter().enumerate() {
             for (j, sensor_diff_blinding) in a.iter().enumerate() {
                 variances_a_blindings[initial_nr_sensors + i].push(
-                    Scalar::from(size_sensors[initial_nr_sensors + i] as u64) * sensor_diff_blinding - &sensor_additions[initial_nr_sensors + i][j] +
-                        Scalar::from(size_sensors[initial_nr_sensors + i] as u64) * blinding_sensors_base_H[initial_nr_sensors + i][j] - &sensor_additions[initial_nr_sensors + i][j]
+                    Scalar::from(size_sensors[initial_nr_sensors + i] as u64) * sensor_diff_blinding - sensor_additions[initial_nr_sensors + i][j] +
+                        Scalar::from(size_sensors[initial_nr_sensors + i] as u64) * blinding_sensors_base_H[initial_nr_sensors + i][j] - sensor_additions[initial_nr_sensors + i][j]
                 )
             }
         }
 
         let proofs_variances = VarianceProof::all_proofs_variance(
             &subtraction_values,
-            &bulletproof_generators,
-            &pedersen_generators,
+            bulletproof_generators,
+            pedersen_generators,
             &blinders_comm_variances,
             &variances_a_blindings,
-            size_vectors
-        );
-
-        let stds_blindings: Vec<Vec<Scalar>> = (0..length_all_vectors).map(
-            |_| (0..3).map(
-                |_| Scalar::random(&mut thread_rng())
-            ).collect()
-        ).collect();
+            size_vectors,
+            labels,
+            &mut rng,
+        )?;
 
-        let stds_commitments = all_sensor_stds.into_iter()
-            .zip(stds_blindings.clone().into_iter())
+        metrics.phase(ProofPhase::VarianceProof, PhaseMetrics {
+            duration: stopwatch.elapsed(metrics),
+            commitments: count(&comm_sensors_base_H) + count(&proofs_variances.1),
+            proofs: 1 + count(&proofs_variances.0),
+            proof_size: bincode_size(&comm_sensors_base_H)
+                + bincode_size(&proofs_base_H_comms)
+                + bincode_size(&proofs_variances.1)
+                + bincode_size(&proofs_variances.0),
+        });
+
+        let stds_blindings: Vec<Vec<Scalar>> =
+            random_scalar_matrix(&mut rng, &vec![3; all_sensor_stds.len()]);
+
+        let stds_commitments = all_sensor_stds.iter()
+            .zip(stds_blindings.clone())
             .map(|(stds, blindings)|
-                stds.into_iter()
-                    .zip(blindings.into_iter())
+                stds.iter()
+                    .zip(blindings)
                     .map(|(&std, blinding)| pedersen_generators.commit(std, blinding).compress())
                     .collect())
             .collect();
 
-        let proof_std = StdProof::create_all(
-            &bulletproof_generators,
+        let proof_std = StdProof::create_all_with_metrics(
+            bulletproof_generators,
             pedersen_generators,
-            &all_sensor_stds,
-            &variances,
+            all_sensor_stds,
+            variances,
             &stds_commitments,
             &stds_blindings,
-            &blinders_comm_variances
+            &blinders_comm_variances,
+            labels,
+            range_bitsize,
+            rng,
+            metrics,
         )?;
 
-        Ok(VarianceProof{
+        Ok((VarianceProof{
             comm_sensors_base_H,
             proofs_base_H_comms,
             variance_commitment: proofs_variances.1,
             proofs_variance: proofs_variances.0,
             std_commitment: stds_commitments,
             proofs_std: proof_std,
-        })
+        }, stds_blindings))
+    }
+
+    /// The commitments to the variances, under the `PedersenGens`.
+    pub fn variance_commitment(&self) -> &Vec<Vec<CompressedRistretto>> {
+        &self.variance_commitment
     }
 
+    /// The commitments to the standard deviations, under the `PedersenGens`.
+    pub fn std_commitment(&self) -> &Vec<Vec<CompressedRistretto>> {
+        &self.std_commitment
+    }
+
+    #[cfg(feature = "std")]
     pub fn verify(
         self,
-        signed_commitments: &Vec<Vec<CompressedRistretto>>,
-        diff_commitments: &Vec<Vec<CompressedRistretto>>,
-        last_exps: &Vec<Vec<RistrettoPoint>>,
-        average_commitment_base_G: &Vec<Vec<RistrettoPoint>>,
-        average_commitment_base_H: &Vec<Vec<RistrettoPoint>>,
+        signed_commitments: &[Vec<RistrettoPoint>],
+        diff_commitments: &[Vec<RistrettoPoint>],
+        last_exps: &[Vec<RistrettoPoint>],
+        average_commitment_base_G: &[Vec<RistrettoPoint>],
+        average_commitment_base_H: &[Vec<RistrettoPoint>],
         bulletproof_generators: &BulletproofGens,
         pedersen_generators: &PedersenGens,
         pedersen_vec_generators: &PedersenVecGens,
         // base of the "right hand side" bulleproof generators
         secondary_pedersen_vec_generators: &PedersenVecGens,
-        size_sensors: &Vec<usize>,
+        size_sensors: &[usize],
+        size: usize,
+        length_all_vectors: usize,
+        labels: &TranscriptLabels,
+    ) -> Result<(), ProofError> {
+        self.verify_with_rng(
+            signed_commitments,
+            diff_commitments,
+            last_exps,
+            average_commitment_base_G,
+            average_commitment_base_H,
+            bulletproof_generators,
+            pedersen_generators,
+            pedersen_vec_generators,
+            secondary_pedersen_vec_generators,
+            size_sensors,
+            size,
+            length_all_vectors,
+            labels,
+            SQUARE_RANGE_BITSIZE,
+            &mut thread_rng(),
+        )
+    }
+
+    /// As `verify`, with the randomness of the verification drawn from `rng`, and range proofs
+    /// of `range_bitsize` bits in the proofs of the standard deviations.
+    pub fn verify_with_rng(
+        self,
+        signed_commitments: &[Vec<RistrettoPoint>],
+        diff_commitments: &[Vec<RistrettoPoint>],
+        last_exps: &[Vec<RistrettoPoint>],
+        average_commitment_base_G: &[Vec<RistrettoPoint>],
+        average_commitment_base_H: &[Vec<RistrettoPoint>],
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        pedersen_vec_generators: &PedersenVecGens,
+        secondary_pedersen_vec_generators: &PedersenVecGens,
+        size_sensors: &[usize],
         size: usize,
-        length_all_vectors: usize
+        length_all_vectors: usize,
+        labels: &TranscriptLabels,
+        range_bitsize: usize,
+        mut rng: &mut dyn CryptoRngCore,
     ) -> Result<(), ProofError> {
+        let _span = phase_span!("variance_proof_verify");
         let initial_nr_sensors = signed_commitments.len();
+        let comm_sensors_base_H = decompress_all(&self.comm_sensors_base_H)?;
 
         // So
         // A =
@@ -174,8 +300,8 @@ impl VarianceProof {
         for (i, a) in signed_commitments.iter().enumerate() {
             for (j, signed_hash) in a.iter().enumerate() {
                 expected_As[i].push(
-                    Scalar::from(size_sensors[i] as u64) * signed_hash.decompress().unwrap() - average_commitment_base_G[i][j] +
-                        Scalar::from(size_sensors[i] as u64) * self.comm_sensors_base_H[i][j].decompress().unwrap() - average_commitment_base_H[i][j]
+                    Scalar::from(size_sensors[i] as u64) * signed_hash - average_commitment_base_G[i][j] +
+                        Scalar::from(size_sensors[i] as u64) * comm_sensors_base_H[i][j] - average_commitment_base_H[i][j]
                 )
             }
         }
@@ -183,94 +309,123 @@ impl VarianceProof {
         for (i, a) in diff_commitments.iter().enumerate() {
             for (j, hash_diff) in a.iter().enumerate() {
                 expected_As[initial_nr_sensors + i].push(
-                    Scalar::from(size_sensors[initial_nr_sensors + i] as u64) * (hash_diff.decompress().unwrap() - last_exps[i][j]) - average_commitment_base_G[initial_nr_sensors + i][j] +
-                        Scalar::from(size_sensors[initial_nr_sensors + i] as u64) * self.comm_sensors_base_H[initial_nr_sensors + i][j].decompress().unwrap() - average_commitment_base_H[initial_nr_sensors + i][j]
+                    Scalar::from(size_sensors[initial_nr_sensors + i] as u64) * (hash_diff - last_exps[i][j]) - average_commitment_base_G[initial_nr_sensors + i][j] +
+                        Scalar::from(size_sensors[initial_nr_sensors + i] as u64) * comm_sensors_base_H[initial_nr_sensors + i][j] - average_commitment_base_H[initial_nr_sensors + i][j]
                 )
             }
         }
 
-        verify_proof_equality_commitments(
-            &pedersen_vec_generators,
-            &vec![secondary_pedersen_vec_generators.clone(); length_all_vectors],
-            &signed_commitments,
-            &self.comm_sensors_base_H,
-            &self.proofs_base_H_comms
-        )?;
+        let mut statement = EqualityStatement::new();
+        for i in 0..initial_nr_sensors {
+            for j in 0..3 {
+                statement = statement.add_pair(
+                    pedersen_vec_generators,
+                    secondary_pedersen_vec_generators,
+                    signed_commitments[i][j],
+                    comm_sensors_base_H[i][j],
+                );
+            }
+        }
+        self.proofs_base_H_comms.verify_with_rng(&statement, &mut labels.transcript(labels.commitment_equality), &mut rng)?;
 
         VarianceProof::all_proof_variance_verify(
-                &bulletproof_generators,
-                &pedersen_generators,
+                bulletproof_generators,
+                pedersen_generators,
                 &self.variance_commitment,
                 &self.proofs_variance,
                 size,
-                &expected_As
+                &expected_As,
+                labels,
+                &mut rng,
         )?;
 
-        StdProof::verify_all(
-                &bulletproof_generators,
+        StdProof::verify_all_with_rng(
+                bulletproof_generators,
                 pedersen_generators,
                 &self.std_commitment,
                 &self.variance_commitment,
-                &self.proofs_std
+                &self.proofs_std,
+                labels,
+                range_bitsize,
+                rng,
         )?;
 
         Ok(())
     }
 
     pub fn compute_all_variances(
-        subtracted_values: &Vec<Vec<Vec<Scalar>>>,
+        subtracted_values: &[Vec<Vec<Scalar>>],
     ) -> Vec<Vec<Scalar>> {
         subtracted_values.iter().map(
             |i| i.iter().map(
-                |subtracted_vector| inner_product(&subtracted_vector, &subtracted_vector)
+                |subtracted_vector| inner_product(subtracted_vector, subtracted_vector)
             ).collect()
         ).collect()
     }
 
-    fn all_proofs_variance(
-        subtracted_averages: &Vec<Vec<Vec<Scalar>>>,
+    fn all_proofs_variance<T: RngCore + CryptoRng>(
+        subtracted_averages: &[Vec<Vec<Scalar>>],
         bp_gens: &BulletproofGens,
         pd_gens: &PedersenGens,
-        v_blindings: &Vec<Vec<Scalar>>,
-        a_blindings: &Vec<Vec<Scalar>>,
-        size: usize
-    ) -> (Vec<Vec<InnerProductZKProof>>, Vec<Vec<CompressedRistretto>>) {
-        let mut compressed_points = vec![Vec::new(); subtracted_averages.len()];
-        let mut ip_proofs = vec![Vec::new(); subtracted_averages.len()];
-        for (i, a) in subtracted_averages.iter().enumerate() {
-            for (j, b) in a.iter().enumerate() {
-                let proof = VarianceProof::proof_variance(
-                    b,
-                    &bp_gens,
-                    &pd_gens,
-                    v_blindings[i][j],
-                    a_blindings[i][j],
-                    size
-                );
-                ip_proofs[i].push(proof.0);
-                compressed_points[i].push(proof.1);
-            }
-        }
-        (ip_proofs, compressed_points)
+        v_blindings: &[Vec<Scalar>],
+        a_blindings: &[Vec<Scalar>],
+        size: usize,
+        labels: &TranscriptLabels,
+        rng: &mut T,
+    ) -> Result<VarianceProofs, ProofError> {
+        let shape: Vec<usize> = subtracted_averages.iter().map(|a| a.len()).collect();
+        let seeds = random_seed_matrix(rng, &shape);
+        let proofs: Vec<Vec<Result<(InnerProductZKProof, CompressedRistretto), ProofError>>> = map_sensor_coords(
+            &shape,
+            |i, j| VarianceProof::proof_variance(
+                &subtracted_averages[i][j],
+                bp_gens,
+                pd_gens,
+                v_blindings[i][j],
+                a_blindings[i][j],
+                size,
+                labels,
+                &mut ChaChaRng::from_seed(seeds[i][j]),
+            )
+        );
+        let proofs: Vec<Vec<(InnerProductZKProof, CompressedRistretto)>> = proofs.into_iter()
+            .map(|a| a.into_iter().collect::<Result<_, _>>())
+            .collect::<Result<_, _>>()?;
+        Ok(proofs.into_iter().map(|a| a.into_iter().unzip()).unzip())
     }
 
-    fn all_proof_variance_verify(
+    fn all_proof_variance_verify<T: RngCore + CryptoRng>(
         bp_gens: &BulletproofGens,
         pc_gens: &PedersenGens,
-        commitments: &Vec<Vec<CompressedRistretto>>,
-        proofs: &Vec<Vec<InnerProductZKProof>>,
+        commitments: &[Vec<CompressedRistretto>],
+        proofs: &[Vec<InnerProductZKProof>],
         size_vector: usize,
-        expected_As: &Vec<Vec<RistrettoPoint>>
+        expected_As: &[Vec<RistrettoPoint>],
+        labels: &TranscriptLabels,
+        rng: &mut T,
     ) -> Result<(), ProofError> {
+        // Without the tables of the `prover-only` builds, every proof is verified on its own
+        #[cfg(not(feature = "prover-only"))]
+        let precomputed_gens = {
+            let nr_proofs = proofs.iter().map(|a| a.len()).sum();
+            PrecomputedGens::for_batch(bp_gens, pc_gens, size_vector, nr_proofs)
+        };
+
         for (i, a) in proofs.iter().enumerate() {
-            for (j, b) in a.iter().enumerate() {
-                VarianceProof::verify_variance(
-                    &bp_gens,
-                    pc_gens,
-                    commitments[i][j],
-                    b,
-                    size_vector,
-                    expected_As[i][j]
+            for (j, ip_proof) in a.iter().enumerate() {
+                // The vectors of the proof must be the ones committed in the expected A, which
+                // is bound to the transcript and the verification equation
+                let expected_A = expected_As[i][j].compress();
+                let mut transcript = labels.transcript(labels.variance);
+                #[cfg(not(feature = "prover-only"))]
+                if let Some(gens) = &precomputed_gens {
+                    ip_proof.verify_single_precomputed_with_expected_A(
+                        gens, &mut transcript, &commitments[i][j], &expected_A, rng
+                    )?;
+                    continue;
+                }
+                ip_proof.verify_single_with_expected_A(
+                    bp_gens, pc_gens, &mut transcript, &commitments[i][j], &expected_A, size_vector, rng
                 )?;
             }
         }
@@ -282,44 +437,44 @@ impl VarianceProof {
                       pd_gens: &PedersenGens,
                       v_blinding: Scalar,
                       a_blinding: Scalar,
-                      size: usize)
-                      -> (InnerProductZKProof, CompressedRistretto)
+                      size: usize,
+                      labels: &TranscriptLabels,
+                      rng: &mut ChaChaRng)
+                      -> Result<(InnerProductZKProof, CompressedRistretto), ProofError>
     {
         let variance = inner_product(&subtracted_average.clone(), &subtracted_average.clone()); // without division
 
-        let mut transcript = Transcript::new(b"InnerProductAverage");
-        let proof = InnerProductZKProof::prove_single(
+        let mut transcript = labels.transcript(labels.variance);
+        InnerProductZKProof::prove_single(
             bp_gens,
             pd_gens,
             &mut transcript,
             variance,
-            &subtracted_average,
-            &subtracted_average,
+            subtracted_average,
+            subtracted_average,
             v_blinding,
             a_blinding,
             size,
-            &mut thread_rng()
-        ).unwrap();
+            rng
+        )
+    }
+}
 
-        proof
+impl CanonicalPoints for VarianceProof {
+    fn points_are_canonical(&self) -> bool {
+        self.comm_sensors_base_H.points_are_canonical()
+            && self.proofs_base_H_comms.points_are_canonical()
+            && self.variance_commitment.points_are_canonical()
+            && self.proofs_variance.points_are_canonical()
+            && self.std_commitment.points_are_canonical()
+            && self.proofs_std.points_are_canonical()
     }
+}
 
-    fn verify_variance(
-        bp_gens: &BulletproofGens,
-        pc_gens: &PedersenGens,
-        commitment_variance: CompressedRistretto,
-        ip_proof: &InnerProductZKProof,
-        size_vector: usize,
-        expected_A: RistrettoPoint
-    )
-        -> Result<(), ProofError>
-    {
-        // We need to verify that S of the proof is indeed as we expect it to be
-        assert!(ip_proof.verify_expected_A(expected_A.compress()));
-        let mut transcript = Transcript::new(b"InnerProductAverage");
-        ip_proof.verify_single(
-            &bp_gens, &pc_gens, &mut transcript, &commitment_variance, size_vector, &mut thread_rng()
-        )
+impl SerializedSize for VarianceProof {
+    /// Size of the bincode encoding of the proof.
+    fn serialized_size(&self) -> usize {
+        bincode_size(self)
     }
 }
 
@@ -0,0 +1,46 @@
+//! Writes the test vectors of the zkSVM proof as JSON files, one per window size, for
+//! verifiers implemented in other languages. See `test_vectors::TestVector`.
+//!
+//! Usage: `test_vectors <output directory> [<size> ...]`, e.g.
+//! `cargo run --release --features test-vectors --bin test_vectors -- vectors 64 128`.
+//! The seed of each vector is its size.
+
+use std::path::PathBuf;
+use std::process::exit;
+use std::{env, fs};
+
+use pedersen_commitments_proofs::test_vectors::TestVector;
+
+/// Window sizes of the vectors written when none is given.
+const DEFAULT_SIZES: [usize; 2] = [64, 128];
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let out_dir = match args.next() {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            eprintln!("Usage: test_vectors <output directory> [<size> ...]");
+            exit(2);
+        }
+    };
+    let mut sizes: Vec<usize> = args
+        .map(|arg| arg.parse().unwrap_or_else(|_| {
+            eprintln!("Invalid size {}", arg);
+            exit(2);
+        }))
+        .collect();
+    if sizes.is_empty() {
+        sizes = DEFAULT_SIZES.to_vec();
+    }
+
+    fs::create_dir_all(&out_dir).expect("Cannot create the output directory");
+    for size in sizes {
+        let vector = TestVector::generate(size, size as u64).unwrap_or_else(|e| {
+            eprintln!("Cannot prove the vector of size {}: {}", size, e);
+            exit(1);
+        });
+        let path = out_dir.join(format!("zksvm_{}.json", size));
+        fs::write(&path, vector.to_json()).expect("Cannot write the test vector");
+        println!("{}", path.display());
+    }
+}
@@ -0,0 +1,48 @@
+//! Serves the verification of zkSVM proofs over HTTP, see `server::VerificationService`.
+//!
+//! Usage: `verify_server <params> [<address>]`, e.g.
+//! `cargo run --release --features server --bin verify_server -- params.bin 0.0.0.0:8080`.
+//! `<params>` is a file with the encoding of the `PublicParams`, see `PublicParams::to_bytes`,
+//! or a size, for `PublicParams::new(size)`. The address defaults to `127.0.0.1:8080`.
+
+use std::process::exit;
+use std::{env, fs};
+
+use pedersen_commitments_proofs::server::VerificationService;
+use pedersen_commitments_proofs::PublicParams;
+
+/// Address the service listens on when none is given.
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+
+#[tokio::main]
+async fn main() {
+    let mut args = env::args().skip(1);
+    let params = match args.next() {
+        Some(arg) => match arg.parse() {
+            Ok(size) => PublicParams::new(size),
+            Err(_) => {
+                let bytes = fs::read(&arg).unwrap_or_else(|e| {
+                    eprintln!("Cannot read the parameters {}: {}", arg, e);
+                    exit(2);
+                });
+                PublicParams::from_bytes(&bytes).unwrap_or_else(|e| {
+                    eprintln!("Invalid parameters {}: {}", arg, e);
+                    exit(2);
+                })
+            }
+        },
+        None => {
+            eprintln!("Usage: verify_server <params file | size> [<address>]");
+            exit(2);
+        }
+    };
+    let address = args.next().unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+
+    let service = VerificationService::new(params);
+    let listener = tokio::net::TcpListener::bind(&address).await.unwrap_or_else(|e| {
+        eprintln!("Cannot listen on {}: {}", address, e);
+        exit(1);
+    });
+    println!("Listening on {}, parameters {}", address, service.params_info().digest);
+    axum::serve(listener, service.router()).await.expect("The server failed");
+}
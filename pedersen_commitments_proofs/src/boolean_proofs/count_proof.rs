@@ -0,0 +1,383 @@
+#![allow(non_snake_case)]
+use alloc::vec::Vec;
+use alloc::vec;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "std")]
+use rand_core::OsRng;
+
+use crate::generators::PedersenVecGens;
+use crate::utils::misc::{bincode_deserialize, bincode_serialize};
+use crate::sigma::{or, PointVar, Prover, ScalarVar, SchnorrCS, SigmaProof, Verifier};
+use ip_zk_proof::{PedersenGens, ProofError, TranscriptProtocol, ZkProof};
+use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq, ConstantTimeGreater};
+
+/// Proof that a vector committed under `pc_gens` has at most `count` (potentially) non-zero
+/// readings, so that the claimed number of readings of a sensor is bound to its committed data.
+///
+/// The prover commits, under `ped_gens`, to each reading \\(x_i\\) and to an indicator bit
+/// \\(b_i\\), which is one for the non-zero readings, and proves that
+/// * each \\(b_i\\) is a bit, and \\(x_i = 0\\) if \\(b_i = 0\\),
+/// * \\(\sum_i b_i = count\\), with a proof of knowledge of the blinding of
+///   \\(\sum_i B_i - count \cdot G\\).
+///
+/// The positions of the non-zero readings are not revealed. If there are fewer non-zero readings
+/// than `count`, the prover sets the indicator of some zero readings, so this proves an upper
+/// bound. Combined with `PaddingZKProof`, it shows that the readings are the first `count`
+/// elements of the vector.
+///
+/// The proof is standalone: `zkSVMProver` neither creates nor checks it, and takes the numbers
+/// of readings from its `ProofHeader` as claimed. Applications that do not trust the claimed
+/// counts verify this proof next to the zkSVM proof, over the same commitments.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NonZeroCountProof {
+    /// Commitments to each reading
+    readings: Vec<CompressedRistretto>,
+    /// Commitments to each indicator bit
+    indicators: Vec<CompressedRistretto>,
+    proof: SigmaProof,
+}
+
+/// Secret scalars of the statement, in the order in which they are allocated.
+struct CountScalars {
+    opening: Vec<ScalarVar>,
+    blinding: ScalarVar,
+    reading_blindings: Vec<ScalarVar>,
+    // Blindings of the indicator and of the reading, for the branch where the reading is zero
+    zero_indicator_blindings: Vec<ScalarVar>,
+    zero_reading_blindings: Vec<ScalarVar>,
+    // Blinding of the indicator minus `G`, for the branch where the indicator is one
+    one_indicator_blindings: Vec<ScalarVar>,
+    sum_blinding: ScalarVar,
+}
+
+/// Public points of the statement, in the order in which they are allocated.
+struct CountPoints {
+    commitment: PointVar,
+    bases: Vec<PointVar>,
+    vector_blinding_base: PointVar,
+    G: PointVar,
+    H: PointVar,
+    readings: Vec<PointVar>,
+    indicators: Vec<PointVar>,
+    indicators_minus_G: Vec<PointVar>,
+    sum: PointVar,
+}
+
+fn count_statement<CS: SchnorrCS>(cs: &mut CS, scalars: &CountScalars, points: &CountPoints) {
+    cs.constrain(
+        points.commitment,
+        scalars.opening.iter().cloned().zip(points.bases.iter().cloned())
+            .chain(Some((scalars.blinding, points.vector_blinding_base)))
+            .collect(),
+    );
+    for i in 0..scalars.opening.len() {
+        cs.constrain(
+            points.readings[i],
+            vec![(scalars.opening[i], points.G), (scalars.reading_blindings[i], points.H)],
+        );
+        let (zero_indicator, zero_reading) = (scalars.zero_indicator_blindings[i], scalars.zero_reading_blindings[i]);
+        let (indicator, reading) = (points.indicators[i], points.readings[i]);
+        let (one_indicator, indicator_minus_G) = (scalars.one_indicator_blindings[i], points.indicators_minus_G[i]);
+        let H = points.H;
+        or(
+            cs,
+            |cs| {
+                cs.constrain(indicator, vec![(zero_indicator, H)]);
+                cs.constrain(reading, vec![(zero_reading, H)]);
+            },
+            |cs| cs.constrain(indicator_minus_G, vec![(one_indicator, H)]),
+        );
+    }
+    cs.constrain(points.sum, vec![(scalars.sum_blinding, points.H)]);
+}
+
+impl NonZeroCountProof {
+    /// Proves that `opening`, of the size of `pc_gens`, has at most `count` non-zero elements.
+    /// If it has more, the proof does not verify. The blindings are drawn from `OsRng`.
+    #[cfg(feature = "std")]
+    pub fn prove_count(
+        pc_gens: &PedersenVecGens,
+        ped_gens: &PedersenGens,
+        opening: &[Scalar],
+        blinding: Scalar,
+        count: usize,
+        transcript: &mut Transcript,
+    ) -> Result<NonZeroCountProof, ProofError> {
+        NonZeroCountProof::prove_count_with_rng(pc_gens, ped_gens, opening, blinding, count, transcript, &mut OsRng)
+    }
+
+    /// As `prove_count`, with the blindings and the nonces of the proof drawn from `rng`.
+    pub fn prove_count_with_rng<T: RngCore + CryptoRng>(
+        pc_gens: &PedersenVecGens,
+        ped_gens: &PedersenGens,
+        opening: &[Scalar],
+        blinding: Scalar,
+        count: usize,
+        transcript: &mut Transcript,
+        rng: &mut T,
+    ) -> Result<NonZeroCountProof, ProofError> {
+        let size = opening.len();
+        if size != pc_gens.B.len() || count > size {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        // The non-zero readings are indicated, and then the first zero ones up to `count`. The
+        // positions of the non-zero readings are secret, so they are not branched on
+        let non_zero: Vec<Choice> = opening.iter().map(|x| !x.ct_eq(&Scalar::ZERO)).collect();
+        let nr_non_zero: u64 = non_zero.iter().map(|b| b.unwrap_u8() as u64).sum();
+        let count_u64 = count as u64;
+        let mut missing = u64::conditional_select(&count_u64.wrapping_sub(nr_non_zero), &0, nr_non_zero.ct_gt(&count_u64));
+        let bits: Vec<Choice> = non_zero.iter()
+            .map(|&non_zero| {
+                let indicated = !non_zero & missing.ct_gt(&0);
+                missing -= indicated.unwrap_u8() as u64;
+                non_zero | indicated
+            })
+            .collect();
+
+        let reading_blindings: Vec<Scalar> = (0..size).map(|_| Scalar::random(rng)).collect();
+        let indicator_blindings: Vec<Scalar> = (0..size).map(|_| Scalar::random(rng)).collect();
+        let readings: Vec<RistrettoPoint> = opening.iter().zip(reading_blindings.iter())
+            .map(|(x, r)| ped_gens.commit(*x, *r))
+            .collect();
+        let indicators: Vec<RistrettoPoint> = bits.iter().zip(indicator_blindings.iter())
+            .map(|(b, r)| ped_gens.commit(Scalar::from(b.unwrap_u8() as u64), *r))
+            .collect();
+
+        transcript.append_integer(b"count", count as u64);
+        let mut prover = Prover::new(b"NonZeroCount", transcript);
+
+        // Only the blindings of the branch that holds are known
+        let scalars = CountScalars {
+            opening: opening.iter().map(|x| prover.allocate_scalar(b"x", *x)).collect(),
+            blinding: prover.allocate_scalar(b"blinding", blinding),
+            reading_blindings: reading_blindings.iter().map(|r| prover.allocate_scalar(b"reading blinding", *r)).collect(),
+            zero_indicator_blindings: bits.iter().zip(indicator_blindings.iter())
+                .map(|(&b, r)| prover.allocate_scalar(b"zero indicator blinding", Scalar::conditional_select(r, &Scalar::ZERO, b)))
+                .collect(),
+            zero_reading_blindings: bits.iter().zip(reading_blindings.iter())
+                .map(|(&b, r)| prover.allocate_scalar(b"zero reading blinding", Scalar::conditional_select(r, &Scalar::ZERO, b)))
+                .collect(),
+            one_indicator_blindings: bits.iter().zip(indicator_blindings.iter())
+                .map(|(&b, r)| prover.allocate_scalar(b"one indicator blinding", Scalar::conditional_select(&Scalar::ZERO, r, b)))
+                .collect(),
+            sum_blinding: prover.allocate_scalar(b"sum blinding", indicator_blindings.iter().sum()),
+        };
+
+        let commitment = pc_gens.commit(opening, blinding);
+        let sum: RistrettoPoint = indicators.iter().sum::<RistrettoPoint>() - Scalar::from(count as u64) * ped_gens.B;
+        let points = CountPoints {
+            commitment: prover.allocate_point(b"commitment", commitment).0,
+            bases: pc_gens.B.iter().map(|B| prover.allocate_point(b"base", *B).0).collect(),
+            vector_blinding_base: prover.allocate_point(b"blinding base", pc_gens.B_blinding).0,
+            G: prover.allocate_point(b"G", ped_gens.B).0,
+            H: prover.allocate_point(b"H", ped_gens.B_blinding).0,
+            readings: readings.iter().map(|Y| prover.allocate_point(b"reading", *Y).0).collect(),
+            indicators: indicators.iter().map(|B| prover.allocate_point(b"indicator", *B).0).collect(),
+            indicators_minus_G: indicators.iter().map(|B| prover.allocate_point(b"indicator minus G", B - ped_gens.B).0).collect(),
+            sum: prover.allocate_point(b"sum", sum).0,
+        };
+
+        count_statement(&mut prover, &scalars, &points);
+
+        Ok(NonZeroCountProof {
+            readings: readings.iter().map(|Y| Y.compress()).collect(),
+            indicators: indicators.iter().map(|B| B.compress()).collect(),
+            proof: prover.prove_with_rng(rng),
+        })
+    }
+
+    pub fn verify_count(
+        &self,
+        pc_gens: &PedersenVecGens,
+        ped_gens: &PedersenGens,
+        commitment: CompressedRistretto,
+        count: usize,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        let size = pc_gens.B.len();
+        if count > size {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        if self.readings.len() != size || self.indicators.len() != size {
+            return Err(ProofError::VerificationError);
+        }
+        let indicators = self.indicators.iter()
+            .map(|B| B.decompress().ok_or(ProofError::FormatError))
+            .collect::<Result<Vec<RistrettoPoint>, ProofError>>()?;
+        let sum: RistrettoPoint = indicators.iter().sum::<RistrettoPoint>() - Scalar::from(count as u64) * ped_gens.B;
+
+        transcript.append_integer(b"count", count as u64);
+        let mut verifier = Verifier::new(b"NonZeroCount", transcript);
+
+        let scalars = CountScalars {
+            opening: (0..size).map(|_| verifier.allocate_scalar(b"x")).collect(),
+            blinding: verifier.allocate_scalar(b"blinding"),
+            reading_blindings: (0..size).map(|_| verifier.allocate_scalar(b"reading blinding")).collect(),
+            zero_indicator_blindings: (0..size).map(|_| verifier.allocate_scalar(b"zero indicator blinding")).collect(),
+            zero_reading_blindings: (0..size).map(|_| verifier.allocate_scalar(b"zero reading blinding")).collect(),
+            one_indicator_blindings: (0..size).map(|_| verifier.allocate_scalar(b"one indicator blinding")).collect(),
+            sum_blinding: verifier.allocate_scalar(b"sum blinding"),
+        };
+
+        let points = CountPoints {
+            commitment: verifier.allocate_point(b"commitment", commitment)?,
+            bases: pc_gens.B.iter()
+                .map(|B| verifier.allocate_point(b"base", B.compress()))
+                .collect::<Result<Vec<PointVar>, ProofError>>()?,
+            vector_blinding_base: verifier.allocate_point(b"blinding base", pc_gens.B_blinding.compress())?,
+            G: verifier.allocate_point(b"G", ped_gens.B.compress())?,
+            H: verifier.allocate_point(b"H", ped_gens.B_blinding.compress())?,
+            readings: self.readings.iter()
+                .map(|Y| verifier.allocate_point(b"reading", *Y))
+                .collect::<Result<Vec<PointVar>, ProofError>>()?,
+            indicators: self.indicators.iter()
+                .map(|B| verifier.allocate_point(b"indicator", *B))
+                .collect::<Result<Vec<PointVar>, ProofError>>()?,
+            indicators_minus_G: indicators.iter()
+                .map(|B| verifier.allocate_point(b"indicator minus G", (B - ped_gens.B).compress()))
+                .collect::<Result<Vec<PointVar>, ProofError>>()?,
+            sum: verifier.allocate_point(b"sum", sum.compress())?,
+        };
+
+        count_statement(&mut verifier, &scalars, &points);
+        verifier.verify(&self.proof)
+    }
+}
+
+/// The parameters are the generators of the vector and of the readings, and the count, the
+/// witness the opening and its blinding factor, and the statement the commitment. The proof is
+/// serialized with bincode.
+impl ZkProof for NonZeroCountProof {
+    type Params<'a> = (&'a PedersenVecGens, &'a PedersenGens, usize);
+    type Witness<'a> = (&'a [Scalar], Scalar);
+    type Statement = CompressedRistretto;
+    type Error = ProofError;
+
+    fn prove<R: RngCore + CryptoRng>(
+        (pc_gens, ped_gens, count): Self::Params<'_>,
+        (opening, blinding): Self::Witness<'_>,
+        transcript: &mut Transcript,
+        rng: &mut R,
+    ) -> Result<(NonZeroCountProof, CompressedRistretto), ProofError> {
+        let proof = NonZeroCountProof::prove_count_with_rng(pc_gens, ped_gens, opening, blinding, count, transcript, rng)?;
+        Ok((proof, pc_gens.commit(opening, blinding).compress()))
+    }
+
+    fn verify(
+        &self,
+        (pc_gens, ped_gens, count): Self::Params<'_>,
+        commitment: &CompressedRistretto,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_count(pc_gens, ped_gens, *commitment, count, transcript)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        bincode_serialize(self).expect("A proof of vectors and points always serializes.")
+    }
+
+    fn from_bytes(slice: &[u8]) -> Result<NonZeroCountProof, ProofError> {
+        bincode_deserialize(slice).map_err(|_| ProofError::FormatError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PedersenConfig;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    fn count_proof_helper(non_zeros: &[usize], claimed: usize) -> Result<(), ProofError> {
+        let size = 8;
+        let pc_gens = PedersenVecGens::new(size);
+        let ped_gens = PedersenGens::default();
+        let mut csprng: OsRng = OsRng;
+
+        let mut opening = vec![Scalar::ZERO; size];
+        for &i in non_zeros {
+            opening[i] = Scalar::random(&mut csprng);
+        }
+        let blinding = Scalar::random(&mut csprng);
+        let commitment = pc_gens.commit(&opening, blinding).compress();
+
+        let mut transcript = Transcript::new(b"test");
+        let proof = NonZeroCountProof::prove_count(&pc_gens, &ped_gens, &opening, blinding, claimed, &mut transcript)?;
+
+        transcript = Transcript::new(b"test");
+        proof.verify_count(&pc_gens, &ped_gens, commitment, claimed, &mut transcript)
+    }
+
+    #[test]
+    fn proof_works() {
+        assert!(count_proof_helper(&[0, 1, 2, 3, 4], 5).is_ok());
+        assert!(count_proof_helper(&[1, 4, 7], 3).is_ok());
+        assert!(count_proof_helper(&[1, 4], 3).is_ok());
+        assert!(count_proof_helper(&[], 0).is_ok());
+    }
+
+    #[test]
+    fn proof_is_determined_by_the_rng() {
+        let size = 4;
+        let pc_gens = PedersenVecGens::new(size);
+        let ped_gens = PedersenGens::default();
+        let opening = vec![Scalar::from(3u64), Scalar::ZERO, Scalar::from(5u64), Scalar::ZERO];
+        let blinding = Scalar::from(11u64);
+        let prove = |seed: u8| {
+            let mut transcript = Transcript::new(b"test");
+            let mut rng = ChaChaRng::from_seed([seed; 32]);
+            NonZeroCountProof::prove_count_with_rng(&pc_gens, &ped_gens, &opening, blinding, 2, &mut transcript, &mut rng)
+                .unwrap()
+        };
+        assert_eq!(prove(1), prove(1));
+        assert_ne!(prove(1), prove(2));
+
+        let mut transcript = Transcript::new(b"test");
+        let commitment = pc_gens.commit(&opening, blinding).compress();
+        assert!(prove(1).verify_count(&pc_gens, &ped_gens, commitment, 2, &mut transcript).is_ok());
+
+        // The RNG keyed by the witness, see `PedersenConfig::with_witness_rng`
+        let config = PedersenConfig::new(b"test", &None, &None, &None, size).with_witness_rng();
+        let mut seeded = ChaChaRng::from_seed([1; 32]);
+        let witness: Vec<Scalar> = opening.iter().cloned().chain(Some(blinding)).collect();
+        let mut rng = config.prover_rng(b"count witness", &witness, &mut seeded);
+        let proof = NonZeroCountProof::prove_count_with_rng(&pc_gens, &ped_gens, &opening, blinding, 2, &mut Transcript::new(b"test"), &mut rng)
+            .unwrap();
+        assert_ne!(proof, prove(1));
+        assert!(proof.verify_count(&pc_gens, &ped_gens, commitment, 2, &mut Transcript::new(b"test")).is_ok());
+    }
+
+    #[test]
+    fn zk_proof_round_trip() {
+        let pc_gens = PedersenVecGens::new(4);
+        let ped_gens = PedersenGens::default();
+        let mut csprng: OsRng = OsRng;
+
+        let opening = [Scalar::ZERO, Scalar::from(7u64), Scalar::ZERO, Scalar::from(2u64)];
+        let witness = (&opening[..], Scalar::random(&mut csprng));
+        let (proof, commitment) = <NonZeroCountProof as ZkProof>::prove(
+            (&pc_gens, &ped_gens, 2),
+            witness,
+            &mut Transcript::new(b"test"),
+            &mut csprng,
+        ).unwrap();
+
+        let proof = <NonZeroCountProof as ZkProof>::from_bytes(&ZkProof::to_bytes(&proof)).unwrap();
+        assert!(ZkProof::verify(&proof, (&pc_gens, &ped_gens, 2), &commitment, &mut Transcript::new(b"test")).is_ok());
+        assert!(ZkProof::verify(&proof, (&pc_gens, &ped_gens, 1), &commitment, &mut Transcript::new(b"test")).is_err());
+    }
+
+    #[test]
+    fn proof_fails() {
+        assert_eq!(count_proof_helper(&[0, 1, 2, 3, 4], 4).err(), Some(ProofError::VerificationError));
+        assert_eq!(count_proof_helper(&[0], 0).err(), Some(ProofError::VerificationError));
+        assert_eq!(count_proof_helper(&[0], 9).err(), Some(ProofError::InvalidGeneratorsLength));
+    }
+}
@@ -0,0 +1,277 @@
+#![allow(non_snake_case)]
+use alloc::vec::Vec;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{VartimeMultiscalarMul, IsIdentity};
+
+use merlin::Transcript;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "std")]
+use rand_core::OsRng;
+
+use ip_zk_proof::{CanonicalPoints, ProofError, ProofReader, TranscriptProtocol};
+#[cfg(feature = "std")]
+use ip_zk_proof::ZkProof;
+use crate::utils::misc::check_canonical;
+use serde::{Deserialize, Serialize};
+
+/// Proof of knowledge of the discrete logarithm `x` of `commitment = x * base`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DlogZKProof {
+    /// Announcement
+    A: CompressedRistretto,
+    /// Response
+    r: Scalar,
+}
+
+impl DlogZKProof {
+    #[cfg(feature = "std")]
+    pub fn prove_dlog(
+        base: &RistrettoPoint,
+        x: Scalar,
+        transcript: &mut Transcript,
+    ) -> DlogZKProof {
+        DlogZKProof::prove_dlog_with_rng(base, x, transcript, &mut OsRng)
+    }
+
+    pub(crate) fn prove_dlog_with_rng<T: RngCore + CryptoRng>(
+        base: &RistrettoPoint,
+        x: Scalar,
+        transcript: &mut Transcript,
+        rng: &mut T,
+    ) -> DlogZKProof {
+        let blinding = Scalar::random(rng);
+
+        transcript.append_point(b"dlog base", &base.compress());
+        transcript.append_point(b"dlog commitment", &(x * base).compress());
+        let A = (blinding * base).compress();
+        transcript.append_point(b"announcement", &A);
+
+        let challenge: Scalar = transcript.challenge_scalar(b"challenge");
+
+        DlogZKProof {
+            A,
+            r: blinding + challenge * x,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn verify_dlog(
+        &self,
+        base: &RistrettoPoint,
+        commitment: &RistrettoPoint,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        DlogZKProof::batch_verify(&[self], &[*base], &[*commitment], &mut [transcript])
+    }
+
+    /// Verifies several proofs with a single multiscalar multiplication, where proof `i` is a
+    /// proof for `commitments[i] = x * bases[i]` over `transcripts[i]`. The verification is
+    /// the one of a random linear combination of the proofs, so it fails if any proof fails,
+    /// but without telling which one.
+    #[cfg(feature = "std")]
+    pub fn batch_verify(
+        proofs: &[&DlogZKProof],
+        bases: &[RistrettoPoint],
+        commitments: &[RistrettoPoint],
+        transcripts: &mut [&mut Transcript],
+    ) -> Result<(), ProofError> {
+        DlogZKProof::batch_verify_with_rng(proofs, bases, commitments, transcripts, &mut OsRng)
+    }
+
+    /// As `batch_verify`, with the weights of the linear combination drawn from `rng`.
+    pub fn batch_verify_with_rng<T: RngCore + CryptoRng>(
+        proofs: &[&DlogZKProof],
+        bases: &[RistrettoPoint],
+        commitments: &[RistrettoPoint],
+        transcripts: &mut [&mut Transcript],
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let nr_proofs = proofs.len();
+        if bases.len() != nr_proofs || commitments.len() != nr_proofs || transcripts.len() != nr_proofs {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        let mut scalars = Vec::with_capacity(3 * nr_proofs);
+        let mut points = Vec::with_capacity(3 * nr_proofs);
+        for (((proof, base), commitment), transcript) in proofs.iter()
+            .zip(bases.iter())
+            .zip(commitments.iter())
+            .zip(transcripts.iter_mut())
+        {
+            transcript.append_point(b"dlog base", &base.compress());
+            transcript.append_point(b"dlog commitment", &commitment.compress());
+            transcript.append_point(b"announcement", &proof.A);
+            let challenge: Scalar = transcript.challenge_scalar(b"challenge");
+
+            // A + challenge * commitment - r * base = 0, weighted by a random factor
+            let weight = Scalar::random(&mut *rng);
+            scalars.extend_from_slice(&[weight, weight * challenge, -(weight * proof.r)]);
+            points.extend_from_slice(&[proof.A.decompress(), Some(*commitment), Some(*base)]);
+        }
+
+        let mega_check = RistrettoPoint::optional_multiscalar_mul(scalars, points)
+            .ok_or(ProofError::VerificationError)?;
+
+        if mega_check.is_identity() {
+            Ok(())
+        }
+        else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Serializes the proof as the announcement followed by the response, 32 bytes each.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut buf = [0u8; 64];
+        buf[..32].copy_from_slice(self.A.as_bytes());
+        buf[32..].copy_from_slice(self.r.as_bytes());
+        buf
+    }
+
+    /// Parses a proof serialized with `to_bytes`. Returns a `FormatError` if the length is not
+    /// 64 bytes, or if the response is not a canonical scalar.
+    pub fn from_bytes(slice: &[u8]) -> Result<DlogZKProof, ProofError> {
+        let mut reader = ProofReader::new(slice);
+        let proof = DlogZKProof {
+            A: reader.read_point()?,
+            r: reader.read_scalar()?,
+        };
+        reader.finish()?;
+        Ok(proof)
+    }
+
+    /// As `from_bytes`, rejecting proofs with non-canonical points, see `CanonicalPoints`.
+    pub fn from_bytes_strict(slice: &[u8]) -> Result<DlogZKProof, ProofError> {
+        check_canonical(DlogZKProof::from_bytes(slice)?)
+    }
+}
+
+impl CanonicalPoints for DlogZKProof {
+    fn points_are_canonical(&self) -> bool {
+        self.A.points_are_canonical()
+    }
+}
+
+impl PartialOrd for DlogZKProof {
+    fn partial_cmp(&self, other: &DlogZKProof) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DlogZKProof {
+    fn cmp(&self, other: &DlogZKProof) -> Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
+impl Hash for DlogZKProof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+/// The parameter is the base, the witness the discrete logarithm `x` and the statement
+/// `x * base`.
+/// The proof is verified with a `thread_rng`.
+#[cfg(feature = "std")]
+impl ZkProof for DlogZKProof {
+    type Params<'a> = &'a RistrettoPoint;
+    type Witness<'a> = Scalar;
+    type Statement = RistrettoPoint;
+    type Error = ProofError;
+
+    fn prove<R: RngCore + CryptoRng>(
+        base: &RistrettoPoint,
+        x: Scalar,
+        transcript: &mut Transcript,
+        rng: &mut R,
+    ) -> Result<(DlogZKProof, RistrettoPoint), ProofError> {
+        Ok((DlogZKProof::prove_dlog_with_rng(base, x, transcript, rng), x * base))
+    }
+
+    fn verify(
+        &self,
+        base: &RistrettoPoint,
+        commitment: &RistrettoPoint,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_dlog(base, commitment, transcript)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        DlogZKProof::to_bytes(self).to_vec()
+    }
+
+    fn from_bytes(slice: &[u8]) -> Result<DlogZKProof, ProofError> {
+        DlogZKProof::from_bytes(slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proof_works() {
+        let mut csprng: OsRng = OsRng;
+        let base = RistrettoPoint::random(&mut csprng);
+        let x = Scalar::random(&mut csprng);
+
+        let mut transcript = Transcript::new(b"test");
+        let proof = DlogZKProof::prove_dlog(&base, x, &mut transcript);
+
+        let parsed = DlogZKProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert_eq!(parsed, proof);
+        let proof = parsed;
+        transcript = Transcript::new(b"test");
+        assert!(proof.verify_dlog(&base, &(x * base), &mut transcript).is_ok());
+
+        transcript = Transcript::new(b"test");
+        assert!(proof.verify_dlog(&base, &(x * base + base), &mut transcript).is_err());
+
+        assert_eq!(DlogZKProof::from_bytes(&[0u8; 63]).err(), Some(ProofError::FormatError));
+        assert_eq!(DlogZKProof::from_bytes(&[0u8; 65]).err(), Some(ProofError::FormatError));
+        assert_eq!(DlogZKProof::from_bytes(&[0xffu8; 64]).err(), Some(ProofError::FormatError));
+    }
+
+    #[test]
+    fn strict_parsing_rejects_non_canonical_points() {
+        let mut csprng: OsRng = OsRng;
+        let base = RistrettoPoint::random(&mut csprng);
+        let proof = DlogZKProof::prove_dlog(&base, Scalar::random(&mut csprng), &mut Transcript::new(b"test"));
+        let mut bytes = proof.to_bytes();
+        assert_eq!(DlogZKProof::from_bytes_strict(&bytes), Ok(proof));
+
+        bytes[..32].copy_from_slice(&[0xff; 32]);
+        assert!(DlogZKProof::from_bytes(&bytes).is_ok());
+        assert_eq!(DlogZKProof::from_bytes_strict(&bytes).err(), Some(ProofError::FormatError));
+    }
+
+    #[test]
+    fn batch_verification() {
+        let mut csprng: OsRng = OsRng;
+        let bases: Vec<RistrettoPoint> = (0..5).map(|_| RistrettoPoint::random(&mut csprng)).collect();
+        let xs: Vec<Scalar> = (0..5).map(|_| Scalar::random(&mut csprng)).collect();
+        let mut commitments: Vec<RistrettoPoint> = xs.iter().zip(bases.iter()).map(|(x, base)| x * base).collect();
+
+        let proofs: Vec<DlogZKProof> = xs.iter().zip(bases.iter())
+            .map(|(x, base)| DlogZKProof::prove_dlog(base, *x, &mut Transcript::new(b"test")))
+            .collect();
+        let proofs: Vec<&DlogZKProof> = proofs.iter().collect();
+
+        let verify = |commitments: &[RistrettoPoint]| {
+            let mut transcripts: Vec<Transcript> = (0..5).map(|_| Transcript::new(b"test")).collect();
+            let mut transcripts: Vec<&mut Transcript> = transcripts.iter_mut().collect();
+            DlogZKProof::batch_verify(&proofs, &bases, commitments, &mut transcripts)
+        };
+        assert!(verify(&commitments).is_ok());
+        assert_eq!(verify(&commitments[..4]).err(), Some(ProofError::InvalidGeneratorsLength));
+
+        commitments[3] += bases[3];
+        assert_eq!(verify(&commitments).err(), Some(ProofError::VerificationError));
+    }
+}
@@ -1,4 +1,5 @@
 #![allow(non_snake_case)]
+use alloc::vec::Vec;
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::traits::{VartimeMultiscalarMul, IsIdentity};
@@ -6,13 +7,17 @@ use curve25519_dalek::traits::{VartimeMultiscalarMul, IsIdentity};
 use core::iter;
 use merlin::Transcript;
 
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "std")]
 use rand_core::OsRng;
 
 use crate::generators::PedersenVecGens;
-use crate::transcript::TranscriptProtocol;
-use ip_zk_proof::ProofError;
+use ip_zk_proof::{CanonicalPoints, ProofError, SerializedSize, TranscriptProtocol, ZkProof};
+use crate::utils::misc::{bincode_deserialize, bincode_serialize, bincode_size, check_canonical};
+use serde::{Deserialize, Serialize};
+use core::fmt;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
 pub struct EqualityZKProof {
     /// Announcement
     A: CompressedRistretto,
@@ -24,25 +29,49 @@ pub struct EqualityZKProof {
 }
 
 impl EqualityZKProof {
+    /// Proves that the commitments under `pc_gens_1` and `pc_gens_2` open to the same
+    /// `opening`, with randomizations `randomization_1` and `randomization_2`. The blindings
+    /// are drawn from `OsRng`.
+    #[cfg(feature = "std")]
     pub fn prove_equality(
         pc_gens_1: &PedersenVecGens,
         pc_gens_2: &PedersenVecGens,
-        opening: &Vec<Scalar>,
+        opening: &[Scalar],
         randomization_1: Scalar,
         randomization_2: Scalar,
         transcript: &mut Transcript,
+    ) -> Result<EqualityZKProof, ProofError> {
+        EqualityZKProof::prove_equality_with_rng(
+            pc_gens_1,
+            pc_gens_2,
+            opening,
+            randomization_1,
+            randomization_2,
+            transcript,
+            &mut OsRng,
+        )
+    }
+
+    /// As `prove_equality`, with the blindings drawn from `rng`.
+    pub fn prove_equality_with_rng<T: RngCore + CryptoRng>(
+        pc_gens_1: &PedersenVecGens,
+        pc_gens_2: &PedersenVecGens,
+        opening: &[Scalar],
+        randomization_1: Scalar,
+        randomization_2: Scalar,
+        transcript: &mut Transcript,
+        rng: &mut T,
     ) -> Result<EqualityZKProof, ProofError> {
         if pc_gens_1.size != opening.len() || pc_gens_2.size != opening.len() {
             return Err(ProofError::InvalidGeneratorsLength);
         }
 
         let size = opening.len();
-        let mut csprng: OsRng = OsRng;
 
-        let randomization_blinding_1 = Scalar::random(&mut csprng);
-        let randomization_blinding_2 = Scalar::random(&mut csprng);
+        let randomization_blinding_1 = Scalar::random(rng);
+        let randomization_blinding_2 = Scalar::random(rng);
         let opening_blinding: Vec<Scalar> =
-            (0..size).map(|_| Scalar::random(&mut csprng)).collect();
+            (0..size).map(|_| Scalar::random(rng)).collect();
 
         let A = pc_gens_1
             .commit(&opening_blinding, randomization_blinding_1)
@@ -54,7 +83,7 @@ impl EqualityZKProof {
         transcript.append_point(b"announcement A", &A);
         transcript.append_point(b"announcement B", &B);
 
-        let challenge = transcript.challenge_scalar(b"challenge");
+        let challenge: Scalar = transcript.challenge_scalar(b"challenge");
 
         let r_randomization_1: Scalar = challenge * randomization_1 + randomization_blinding_1;
         let r_randomization_2: Scalar = challenge * randomization_2 + randomization_blinding_2;
@@ -81,29 +110,221 @@ impl EqualityZKProof {
         commitment_2: CompressedRistretto,
         transcript: &mut Transcript,
     ) -> Result<(), ProofError> {
+        let commitment_1 = commitment_1.decompress().ok_or(ProofError::VerificationError)?;
+        let commitment_2 = commitment_2.decompress().ok_or(ProofError::VerificationError)?;
+        self.verify_equality_points(pc_gens_1, pc_gens_2, &commitment_1, &commitment_2, transcript)
+    }
+
+    /// Verifies the proof against commitments the verifier already holds in decompressed form.
+    pub(crate) fn verify_equality_points(
+        &self,
+        pc_gens_1: &PedersenVecGens,
+        pc_gens_2: &PedersenVecGens,
+        commitment_1: &RistrettoPoint,
+        commitment_2: &RistrettoPoint,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        let (scalars, points) = self.verification_terms(pc_gens_1, pc_gens_2, commitment_1, commitment_2, transcript)?;
+        let _span = msm_span!("equality", size = scalars.len());
+        let mega_check = RistrettoPoint::optional_multiscalar_mul(scalars, points)
+            .ok_or(ProofError::VerificationError)?;
+
+        if mega_check.is_identity() {
+            Ok(())
+        }
+        else {
+            Err(ProofError::VerificationError)
+        }
+    }
+
+    /// Scalars and points of the multiscalar multiplication that is the identity if the proof
+    /// verifies, with the challenge drawn from `transcript`.
+    fn verification_terms(
+        &self,
+        pc_gens_1: &PedersenVecGens,
+        pc_gens_2: &PedersenVecGens,
+        commitment_1: &RistrettoPoint,
+        commitment_2: &RistrettoPoint,
+        transcript: &mut Transcript,
+    ) -> Result<(Vec<Scalar>, Vec<Option<RistrettoPoint>>), ProofError> {
+        if pc_gens_1.B.len() != self.r_opening.len() || pc_gens_2.B.len() != self.r_opening.len() {
+            return Err(ProofError::VerificationError);
+        }
         transcript.append_point(b"announcement A", &self.A);
         transcript.append_point(b"announcement B", &self.B);
 
-        let challenge = transcript.challenge_scalar(b"challenge");
-
-        let mega_check = RistrettoPoint::optional_multiscalar_mul(
-            iter::repeat(Scalar::one()).take(2)
-                .chain(iter::repeat(challenge).take(2))
-                .chain(iter::once(-self.r_randomization_1))
-                .chain(iter::once(-self.r_randomization_2))
-                .chain(self.r_opening.clone().into_iter().map(|r| -r))
-                .chain(self.r_opening.clone().into_iter().map(|r| -r))
-            ,
-            iter::once(self.A.decompress())
-                .chain(iter::once(self.B.decompress()))
-                .chain(iter::once(commitment_1.decompress()))
-                .chain(iter::once(commitment_2.decompress()))
-                .chain(iter::once(Some(pc_gens_1.B_blinding)))
-                .chain(iter::once(Some(pc_gens_2.B_blinding)))
-                .chain(pc_gens_1.B.clone().into_iter().map(|B| Some(B)))
-                .chain(pc_gens_2.B.clone().into_iter().map(|B| Some(B)))
-        )
-            .ok_or_else(|| ProofError::VerificationError)?;
+        let challenge: Scalar = transcript.challenge_scalar(b"challenge");
+
+        let scalars = iter::repeat_n(Scalar::ONE, 2)
+            .chain(iter::repeat_n(challenge, 2))
+            .chain(iter::once(-self.r_randomization_1))
+            .chain(iter::once(-self.r_randomization_2))
+            .chain(self.r_opening.iter().map(|r| -r))
+            .chain(self.r_opening.iter().map(|r| -r))
+            .collect();
+        let points = iter::once(self.A.decompress())
+            .chain(iter::once(self.B.decompress()))
+            .chain(iter::once(Some(*commitment_1)))
+            .chain(iter::once(Some(*commitment_2)))
+            .chain(iter::once(Some(pc_gens_1.B_blinding)))
+            .chain(iter::once(Some(pc_gens_2.B_blinding)))
+            .chain(pc_gens_1.B.iter().map(|B| Some(*B)))
+            .chain(pc_gens_2.B.iter().map(|B| Some(*B)))
+            .collect();
+        Ok((scalars, points))
+    }
+
+    /// Parses a proof serialized with `ZkProof::to_bytes`, rejecting proofs with non-canonical
+    /// points, see `CanonicalPoints`.
+    pub fn from_bytes_strict(slice: &[u8]) -> Result<EqualityZKProof, ProofError> {
+        check_canonical(<EqualityZKProof as ZkProof>::from_bytes(slice)?)
+    }
+}
+
+/// Public side of a batch of equality statements: pairs of commitments, each pair under its
+/// own pair of generators, where both commitments of a pair commit to the same vector.
+///
+/// The pairs are added in the order of the `EqualityProver` that proves them.
+#[derive(Clone, Default)]
+pub struct EqualityStatement<'g> {
+    pairs: Vec<(&'g PedersenVecGens, &'g PedersenVecGens, RistrettoPoint, RistrettoPoint)>,
+}
+
+impl<'g> EqualityStatement<'g> {
+    pub fn new() -> EqualityStatement<'g> {
+        EqualityStatement { pairs: Vec::new() }
+    }
+
+    /// Adds the statement that `commitment_1` under `pc_gens_1` and `commitment_2` under
+    /// `pc_gens_2` commit to the same vector.
+    pub fn add_pair(
+        mut self,
+        pc_gens_1: &'g PedersenVecGens,
+        pc_gens_2: &'g PedersenVecGens,
+        commitment_1: RistrettoPoint,
+        commitment_2: RistrettoPoint,
+    ) -> EqualityStatement<'g> {
+        self.pairs.push((pc_gens_1, pc_gens_2, commitment_1, commitment_2));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+/// Prover of a batch of equality statements, see `EqualityStatement`.
+#[derive(Clone, Default)]
+pub struct EqualityProver<'g> {
+    pairs: Vec<(&'g PedersenVecGens, &'g PedersenVecGens, Vec<Scalar>, Scalar, Scalar)>,
+}
+
+/// Only shows the number of pairs, so that the openings and blindings do not end up in logs.
+impl<'g> fmt::Debug for EqualityProver<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EqualityProver").field("pairs", &self.pairs.len()).finish_non_exhaustive()
+    }
+}
+
+impl<'g> EqualityProver<'g> {
+    pub fn new() -> EqualityProver<'g> {
+        EqualityProver { pairs: Vec::new() }
+    }
+
+    /// Adds the statement that the commitments to `opening` under `pc_gens_1` with
+    /// `blinding_1`, and under `pc_gens_2` with `blinding_2`, commit to the same vector.
+    pub fn add_pair(
+        mut self,
+        pc_gens_1: &'g PedersenVecGens,
+        pc_gens_2: &'g PedersenVecGens,
+        opening: &[Scalar],
+        blinding_1: Scalar,
+        blinding_2: Scalar,
+    ) -> EqualityProver<'g> {
+        self.pairs.push((pc_gens_1, pc_gens_2, opening.to_vec(), blinding_1, blinding_2));
+        self
+    }
+
+    /// Proves all the pairs, in the order in which they were added, over `transcript`.
+    /// Returns `InvalidGeneratorsLength` if an opening does not have the size of its
+    /// generators.
+    #[cfg(feature = "std")]
+    pub fn prove(self, transcript: &mut Transcript) -> Result<BatchEqualityProof, ProofError> {
+        self.prove_with_rng(transcript, &mut OsRng)
+    }
+
+    /// As `prove`, with the blindings of the proofs drawn from `rng`.
+    pub fn prove_with_rng<T: RngCore + CryptoRng>(
+        self,
+        transcript: &mut Transcript,
+        rng: &mut T,
+    ) -> Result<BatchEqualityProof, ProofError> {
+        let proofs = self.pairs.iter()
+            .map(|(pc_gens_1, pc_gens_2, opening, blinding_1, blinding_2)| EqualityZKProof::prove_equality_with_rng(
+                pc_gens_1,
+                pc_gens_2,
+                opening,
+                *blinding_1,
+                *blinding_2,
+                transcript,
+                rng,
+            ))
+            .collect::<Result<Vec<EqualityZKProof>, ProofError>>()?;
+        Ok(BatchEqualityProof { proofs })
+    }
+}
+
+/// Proof of all the pairs of an `EqualityStatement`, created with an `EqualityProver`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct BatchEqualityProof {
+    proofs: Vec<EqualityZKProof>,
+}
+
+impl BatchEqualityProof {
+    /// Number of pairs proven.
+    pub fn len(&self) -> usize {
+        self.proofs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proofs.is_empty()
+    }
+
+    /// Verifies all the pairs of `statement` with a single multiscalar multiplication, as a
+    /// random linear combination of the checks of each pair. It fails if any pair fails, but
+    /// without telling which one.
+    #[cfg(feature = "std")]
+    pub fn verify(&self, statement: &EqualityStatement, transcript: &mut Transcript) -> Result<(), ProofError> {
+        self.verify_with_rng(statement, transcript, &mut OsRng)
+    }
+
+    /// As `verify`, with the weights of the linear combination drawn from `rng`.
+    pub fn verify_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        statement: &EqualityStatement,
+        transcript: &mut Transcript,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        if statement.len() != self.proofs.len() {
+            return Err(ProofError::VerificationError);
+        }
+
+        let mut scalars = Vec::new();
+        let mut points = Vec::new();
+        for (proof, (pc_gens_1, pc_gens_2, commitment_1, commitment_2)) in self.proofs.iter().zip(statement.pairs.iter()) {
+            let (pair_scalars, pair_points) = proof.verification_terms(pc_gens_1, pc_gens_2, commitment_1, commitment_2, transcript)?;
+            let weight = Scalar::random(&mut *rng);
+            scalars.extend(pair_scalars.into_iter().map(|s| weight * s));
+            points.extend(pair_points);
+        }
+
+        let _span = msm_span!("equality", size = scalars.len());
+        let mega_check = RistrettoPoint::optional_multiscalar_mul(scalars, points)
+            .ok_or(ProofError::VerificationError)?;
 
         if mega_check.is_identity() {
             Ok(())
@@ -114,6 +335,74 @@ impl EqualityZKProof {
     }
 }
 
+impl CanonicalPoints for EqualityZKProof {
+    fn points_are_canonical(&self) -> bool {
+        [self.A, self.B].points_are_canonical()
+    }
+}
+
+impl CanonicalPoints for BatchEqualityProof {
+    fn points_are_canonical(&self) -> bool {
+        self.proofs.points_are_canonical()
+    }
+}
+
+impl SerializedSize for EqualityZKProof {
+    /// Size of the bincode encoding of the proof.
+    fn serialized_size(&self) -> usize {
+        bincode_size(self)
+    }
+}
+
+/// The parameters are the two generators, the witness the opening and its randomization under
+/// each of them, and the statement the pair of commitments. The proof is serialized with
+/// bincode.
+impl ZkProof for EqualityZKProof {
+    type Params<'a> = (&'a PedersenVecGens, &'a PedersenVecGens);
+    type Witness<'a> = (&'a Vec<Scalar>, Scalar, Scalar);
+    type Statement = (CompressedRistretto, CompressedRistretto);
+    type Error = ProofError;
+
+    fn prove<R: RngCore + CryptoRng>(
+        (pc_gens_1, pc_gens_2): Self::Params<'_>,
+        (opening, randomization_1, randomization_2): Self::Witness<'_>,
+        transcript: &mut Transcript,
+        rng: &mut R,
+    ) -> Result<(EqualityZKProof, (CompressedRistretto, CompressedRistretto)), ProofError> {
+        let proof = EqualityZKProof::prove_equality_with_rng(
+            pc_gens_1,
+            pc_gens_2,
+            opening,
+            randomization_1,
+            randomization_2,
+            transcript,
+            rng,
+        )?;
+        let commitments = (
+            pc_gens_1.commit(opening, randomization_1).compress(),
+            pc_gens_2.commit(opening, randomization_2).compress(),
+        );
+        Ok((proof, commitments))
+    }
+
+    fn verify(
+        &self,
+        (pc_gens_1, pc_gens_2): Self::Params<'_>,
+        (commitment_1, commitment_2): &(CompressedRistretto, CompressedRistretto),
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_equality(pc_gens_1, pc_gens_2, *commitment_1, *commitment_2, transcript)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        bincode_serialize(self).expect("A proof of vectors and points always serializes.")
+    }
+
+    fn from_bytes(slice: &[u8]) -> Result<EqualityZKProof, ProofError> {
+        bincode_deserialize(slice).map_err(|_| ProofError::FormatError)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -153,6 +442,42 @@ mod tests {
         ).is_ok())
     }
 
+    #[test]
+    fn seeded_proof() {
+        use rand_chacha::ChaChaRng;
+        use rand_core::SeedableRng;
+
+        let size = 8;
+        let ped_gens_1 = PedersenVecGens::new(size);
+        let ped_gens_2 = PedersenVecGens::new_random(size);
+        let mut csprng = ChaChaRng::seed_from_u64(1);
+
+        let randomization_1 = Scalar::random(&mut csprng);
+        let randomization_2 = Scalar::random(&mut csprng);
+        let opening: Vec<Scalar> = (0..size).map(|_| Scalar::random(&mut csprng)).collect();
+
+        let prove = |seed: u64| EqualityZKProof::prove_equality_with_rng(
+            &ped_gens_1,
+            &ped_gens_2,
+            &opening,
+            randomization_1,
+            randomization_2,
+            &mut Transcript::new(b"test"),
+            &mut ChaChaRng::seed_from_u64(seed),
+        ).unwrap();
+
+        let proof = prove(2);
+        assert_eq!(prove(2), proof);
+        assert_ne!(prove(3), proof);
+        assert!(proof.verify_equality(
+            &ped_gens_1,
+            &ped_gens_2,
+            ped_gens_1.commit(&opening, randomization_1).compress(),
+            ped_gens_2.commit(&opening, randomization_2).compress(),
+            &mut Transcript::new(b"test")
+        ).is_ok())
+    }
+
     #[test]
     fn proof_fails() {
         let size = 70;
@@ -223,4 +548,95 @@ mod tests {
             &mut transcript
         ).is_ok())
     }
+
+    #[test]
+    fn batch_proof() {
+        let mut csprng: OsRng = OsRng;
+        let ped_gens_1 = PedersenVecGens::new(8);
+        let ped_gens_2 = PedersenVecGens::new_random(8);
+        let ped_gens_3 = PedersenVecGens::new_random(4);
+
+        let openings: Vec<Vec<Scalar>> = [8, 8, 4].iter()
+            .map(|&size| (0..size).map(|_| Scalar::random(&mut csprng)).collect())
+            .collect();
+        let blindings: Vec<Scalar> = (0..6).map(|_| Scalar::random(&mut csprng)).collect();
+        let gens = [(&ped_gens_1, &ped_gens_2), (&ped_gens_2, &ped_gens_1), (&ped_gens_3, &ped_gens_3)];
+        let commitments: Vec<(RistrettoPoint, RistrettoPoint)> = (0..3)
+            .map(|i| (gens[i].0.commit(&openings[i], blindings[2 * i]), gens[i].1.commit(&openings[i], blindings[2 * i + 1])))
+            .collect();
+
+        let mut prover = EqualityProver::new();
+        for i in 0..3 {
+            prover = prover.add_pair(gens[i].0, gens[i].1, &openings[i], blindings[2 * i], blindings[2 * i + 1]);
+        }
+        let proof = prover.prove(&mut Transcript::new(b"test")).unwrap();
+        assert_eq!(proof.len(), 3);
+
+        let statement = (0..3).fold(EqualityStatement::new(), |statement, i| {
+            statement.add_pair(gens[i].0, gens[i].1, commitments[i].0, commitments[i].1)
+        });
+        assert!(proof.verify(&statement, &mut Transcript::new(b"test")).is_ok());
+
+        // A missing pair, and a pair with a commitment to another vector
+        let partial = EqualityStatement::new()
+            .add_pair(gens[0].0, gens[0].1, commitments[0].0, commitments[0].1);
+        assert_eq!(proof.verify(&partial, &mut Transcript::new(b"test")).err(), Some(ProofError::VerificationError));
+
+        let wrong = (0..3).fold(EqualityStatement::new(), |statement, i| {
+            let commitment_2 = if i == 1 { commitments[i].1 + ped_gens_1.B[0] } else { commitments[i].1 };
+            statement.add_pair(gens[i].0, gens[i].1, commitments[i].0, commitment_2)
+        });
+        assert_eq!(proof.verify(&wrong, &mut Transcript::new(b"test")).err(), Some(ProofError::VerificationError));
+
+        assert_eq!(
+            EqualityProver::new().add_pair(&ped_gens_1, &ped_gens_3, &openings[0], blindings[0], blindings[1])
+                .prove(&mut Transcript::new(b"test")).err(),
+            Some(ProofError::InvalidGeneratorsLength)
+        );
+    }
+
+    #[test]
+    fn zk_proof_round_trip() {
+        let size = 16;
+        let ped_gens_1 = PedersenVecGens::new(size);
+        let ped_gens_2 = PedersenVecGens::new_random(size);
+        let mut csprng: OsRng = OsRng;
+
+        let opening: Vec<Scalar> = (0..size).map(|_| Scalar::random(&mut csprng)).collect();
+        let witness = (&opening, Scalar::random(&mut csprng), Scalar::random(&mut csprng));
+        let (proof, commitments) = <EqualityZKProof as ZkProof>::prove(
+            (&ped_gens_1, &ped_gens_2),
+            witness,
+            &mut Transcript::new(b"test"),
+            &mut csprng,
+        ).unwrap();
+
+        let proof = <EqualityZKProof as ZkProof>::from_bytes(&ZkProof::to_bytes(&proof)).unwrap();
+        assert!(ZkProof::verify(&proof, (&ped_gens_1, &ped_gens_2), &commitments, &mut Transcript::new(b"test")).is_ok());
+        assert!(ZkProof::verify(&proof, (&ped_gens_1, &ped_gens_2), &(commitments.0, commitments.0), &mut Transcript::new(b"test")).is_err());
+        assert_eq!(<EqualityZKProof as ZkProof>::from_bytes(&[0u8; 64]).err(), Some(ProofError::FormatError));
+    }
+
+    #[test]
+    fn strict_parsing_rejects_non_canonical_points() {
+        let size = 4;
+        let ped_gens_1 = PedersenVecGens::new(size);
+        let ped_gens_2 = PedersenVecGens::new_random(size);
+        let mut csprng: OsRng = OsRng;
+        let opening: Vec<Scalar> = (0..size).map(|_| Scalar::random(&mut csprng)).collect();
+        let witness = (&opening, Scalar::random(&mut csprng), Scalar::random(&mut csprng));
+        let (proof, _) = <EqualityZKProof as ZkProof>::prove(
+            (&ped_gens_1, &ped_gens_2),
+            witness,
+            &mut Transcript::new(b"test"),
+            &mut csprng,
+        ).unwrap();
+        let mut bytes = ZkProof::to_bytes(&proof);
+        assert_eq!(EqualityZKProof::from_bytes_strict(&bytes), Ok(proof));
+
+        // The announcement A is encoded first
+        bytes[..32].copy_from_slice(&[0xff; 32]);
+        assert!(<EqualityZKProof as ZkProof>::from_bytes(&bytes).is_ok());
+        assert_eq!(EqualityZKProof::from_bytes_strict(&bytes).err(), Some(ProofError::FormatError));
+    }
 }
@@ -1,3 +1,10 @@
+pub mod dlog_proof;
 pub mod opening_proof;
 pub mod equality_proof;
-pub mod square_proof;
\ No newline at end of file
+pub mod square_proof;
+pub mod slice_proof;
+pub mod sparse_proof;
+pub mod padding_proof;
+pub mod count_proof;
+pub mod plausibility_proof;
+pub mod removal_proof;
\ No newline at end of file
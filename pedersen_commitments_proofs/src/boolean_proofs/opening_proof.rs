@@ -1,18 +1,24 @@
 #![allow(non_snake_case)]
+use alloc::vec::Vec;
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::traits::{VartimeMultiscalarMul, IsIdentity};
 
 use core::iter;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
 use merlin::Transcript;
 
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "std")]
 use rand_core::OsRng;
 
 use crate::generators::PedersenVecGens;
-use crate::transcript::TranscriptProtocol;
-use ip_zk_proof::ProofError;
+use ip_zk_proof::{CanonicalPoints, ProofError, ProofReader, SerializedSize, TranscriptProtocol, ZkProof};
+use crate::utils::misc::check_canonical;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct OpeningZKProof {
     /// Announcement
     A: CompressedRistretto,
@@ -22,25 +28,38 @@ pub struct OpeningZKProof {
 }
 
 impl OpeningZKProof {
+    /// Proves knowledge of the `opening` and `randomization` of their commitment under
+    /// `pc_gens`. The blindings are drawn from `OsRng`.
+    #[cfg(feature = "std")]
     pub fn prove_opening(
         pc_gens: &PedersenVecGens,
-        opening: &Vec<Scalar>,
+        opening: &[Scalar],
         randomization: Scalar,
         transcript: &mut Transcript,
+    ) -> OpeningZKProof {
+        OpeningZKProof::prove_opening_with_rng(pc_gens, opening, randomization, transcript, &mut OsRng)
+    }
+
+    /// As `prove_opening`, with the blindings drawn from `rng`.
+    pub fn prove_opening_with_rng<T: RngCore + CryptoRng>(
+        pc_gens: &PedersenVecGens,
+        opening: &[Scalar],
+        randomization: Scalar,
+        transcript: &mut Transcript,
+        rng: &mut T,
     ) -> OpeningZKProof {
         let size = opening.len();
-        let mut csprng: OsRng = OsRng;
 
-        let randomization_blinding = Scalar::random(&mut csprng);
+        let randomization_blinding = Scalar::random(rng);
         let opening_blinding: Vec<Scalar> =
-            (0..size).map(|_| Scalar::random(&mut csprng)).collect();
+            (0..size).map(|_| Scalar::random(rng)).collect();
 
         let announcement = pc_gens
             .commit(&opening_blinding, randomization_blinding)
             .compress();
         transcript.append_point(b"announcement", &announcement);
 
-        let challenge = transcript.challenge_scalar(b"challenge");
+        let challenge: Scalar = transcript.challenge_scalar(b"challenge");
 
         let r_randomization: Scalar = challenge * randomization + randomization_blinding;
         let r_opening = opening_blinding
@@ -62,11 +81,14 @@ impl OpeningZKProof {
         commitment: CompressedRistretto,
         transcript: &mut Transcript,
     ) -> Result<(), ProofError> {
+        if self.r_opening.len() != pc_gens.B.len() {
+            return Err(ProofError::VerificationError);
+        }
         transcript.append_point(b"announcement", &self.A);
-        let challenge = transcript.challenge_scalar(b"challenge");
+        let challenge: Scalar = transcript.challenge_scalar(b"challenge");
 
         let mega_check = RistrettoPoint::optional_multiscalar_mul(
-            iter::once(Scalar::one())
+            iter::once(Scalar::ONE)
                 .chain(iter::once(challenge))
                 .chain(iter::once(- &self.r_randomization))
                 .chain(self.r_opening.into_iter().map(|r| -r))
@@ -74,9 +96,9 @@ impl OpeningZKProof {
             iter::once(self.A.decompress())
                 .chain(iter::once(commitment.decompress()))
                 .chain(iter::once(Some(pc_gens.B_blinding)))
-                .chain(pc_gens.B.clone().into_iter().map(|B| Some(B)))
+                .chain(pc_gens.B.clone().into_iter().map(Some))
         )
-            .ok_or_else(|| ProofError::VerificationError)?;
+            .ok_or(ProofError::VerificationError)?;
 
         if mega_check.is_identity() {
             Ok(())
@@ -85,6 +107,111 @@ impl OpeningZKProof {
             Err(ProofError::VerificationError)
         }
     }
+
+    /// Serializes the proof as the announcement, the response of the randomization and the
+    /// responses of the opening, 32 bytes each.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.serialized_size());
+        buf.extend_from_slice(self.A.as_bytes());
+        buf.extend_from_slice(self.r_randomization.as_bytes());
+        for r in self.r_opening.iter() {
+            buf.extend_from_slice(r.as_bytes());
+        }
+        buf
+    }
+
+    /// Parses a proof serialized with `to_bytes`, with the size of the opening given by the
+    /// length of the slice. Returns a `FormatError` if the length is not a multiple of 32
+    /// bytes, or if a response is not a canonical scalar.
+    pub fn from_bytes(slice: &[u8]) -> Result<OpeningZKProof, ProofError> {
+        let mut reader = ProofReader::new(slice);
+        let A = reader.read_point()?;
+        let r_randomization = reader.read_scalar()?;
+        if !reader.remaining().is_multiple_of(32) {
+            return Err(ProofError::FormatError);
+        }
+        let r_opening = (0..reader.remaining() / 32)
+            .map(|_| reader.read_scalar())
+            .collect::<Result<Vec<Scalar>, ProofError>>()?;
+        reader.finish()?;
+
+        Ok(OpeningZKProof { A, r_randomization, r_opening })
+    }
+
+    /// As `from_bytes`, rejecting proofs with non-canonical points, see `CanonicalPoints`.
+    pub fn from_bytes_strict(slice: &[u8]) -> Result<OpeningZKProof, ProofError> {
+        check_canonical(OpeningZKProof::from_bytes(slice)?)
+    }
+}
+
+impl CanonicalPoints for OpeningZKProof {
+    fn points_are_canonical(&self) -> bool {
+        self.A.points_are_canonical()
+    }
+}
+
+impl PartialOrd for OpeningZKProof {
+    fn partial_cmp(&self, other: &OpeningZKProof) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpeningZKProof {
+    fn cmp(&self, other: &OpeningZKProof) -> Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
+impl Hash for OpeningZKProof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+impl SerializedSize for OpeningZKProof {
+    fn serialized_size(&self) -> usize {
+        64 + 32 * self.r_opening.len()
+    }
+}
+
+/// The parameters are the generators, the witness the opening and its randomization, and the
+/// statement the commitment. Returns `InvalidGeneratorsLength` if the opening does not have the
+/// size of the generators.
+impl ZkProof for OpeningZKProof {
+    type Params<'a> = &'a PedersenVecGens;
+    type Witness<'a> = (&'a Vec<Scalar>, Scalar);
+    type Statement = CompressedRistretto;
+    type Error = ProofError;
+
+    fn prove<R: RngCore + CryptoRng>(
+        pc_gens: &PedersenVecGens,
+        (opening, randomization): (&Vec<Scalar>, Scalar),
+        transcript: &mut Transcript,
+        rng: &mut R,
+    ) -> Result<(OpeningZKProof, CompressedRistretto), ProofError> {
+        if opening.len() != pc_gens.B.len() {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        let proof = OpeningZKProof::prove_opening_with_rng(pc_gens, opening, randomization, transcript, rng);
+        Ok((proof, pc_gens.commit(opening, randomization).compress()))
+    }
+
+    fn verify(
+        &self,
+        pc_gens: &PedersenVecGens,
+        commitment: &CompressedRistretto,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.clone().verify_opening_knowledge(pc_gens, *commitment, transcript)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        OpeningZKProof::to_bytes(self)
+    }
+
+    fn from_bytes(slice: &[u8]) -> Result<OpeningZKProof, ProofError> {
+        OpeningZKProof::from_bytes(slice)
+    }
 }
 
 #[cfg(test)]
@@ -106,8 +233,54 @@ mod tests {
         let proof =
             OpeningZKProof::prove_opening(&ped_gens, &opening, randomization, &mut transcript);
 
+        let proof = OpeningZKProof::from_bytes(&proof.to_bytes()).unwrap();
         transcript = Transcript::new(b"test");
-        assert!(proof.verify_opening_knowledge(&ped_gens, commitment, &mut transcript).is_ok())
+        assert!(proof.verify_opening_knowledge(&ped_gens, commitment, &mut transcript).is_ok());
+
+        assert_eq!(OpeningZKProof::from_bytes(&[0u8; 32]).err(), Some(ProofError::FormatError));
+        assert_eq!(OpeningZKProof::from_bytes(&[0u8; 65]).err(), Some(ProofError::FormatError));
+    }
+
+    #[test]
+    fn strict_parsing_rejects_non_canonical_points() {
+        let size = 4;
+        let ped_gens = PedersenVecGens::new(size);
+        let mut csprng: OsRng = OsRng;
+        let opening: Vec<Scalar> = (0..size).map(|_| Scalar::random(&mut csprng)).collect();
+        let proof = OpeningZKProof::prove_opening(&ped_gens, &opening, Scalar::random(&mut csprng), &mut Transcript::new(b"test"));
+        let mut bytes = proof.to_bytes();
+        assert_eq!(OpeningZKProof::from_bytes_strict(&bytes), Ok(proof));
+
+        bytes[..32].copy_from_slice(&[0xff; 32]);
+        assert!(OpeningZKProof::from_bytes(&bytes).is_ok());
+        assert_eq!(OpeningZKProof::from_bytes_strict(&bytes).err(), Some(ProofError::FormatError));
+    }
+
+    #[test]
+    fn seeded_proof() {
+        use rand_chacha::ChaChaRng;
+        use rand_core::SeedableRng;
+
+        let size = 8;
+        let ped_gens = PedersenVecGens::new(size);
+        let mut csprng = ChaChaRng::seed_from_u64(1);
+
+        let randomization = Scalar::random(&mut csprng);
+        let opening: Vec<Scalar> = (0..size).map(|_| Scalar::random(&mut csprng)).collect();
+        let commitment = ped_gens.commit(&opening, randomization).compress();
+
+        let prove = |seed: u64| OpeningZKProof::prove_opening_with_rng(
+            &ped_gens,
+            &opening,
+            randomization,
+            &mut Transcript::new(b"test"),
+            &mut ChaChaRng::seed_from_u64(seed),
+        );
+
+        let proof = prove(2);
+        assert_eq!(prove(2), proof);
+        assert_ne!(prove(3), proof);
+        assert!(proof.verify_opening_knowledge(&ped_gens, commitment, &mut Transcript::new(b"test")).is_ok());
     }
 
     #[test]
@@ -127,6 +300,13 @@ mod tests {
             OpeningZKProof::prove_opening(&ped_gens, &opening, randomization, &mut transcript);
 
         transcript = Transcript::new(b"test");
-        assert!(proof.verify_opening_knowledge(&ped_gens, commitment, &mut transcript).is_err())
+        assert!(proof.clone().verify_opening_knowledge(&ped_gens, commitment, &mut transcript).is_err());
+
+        // Generators of another size
+        transcript = Transcript::new(b"test");
+        assert_eq!(
+            proof.verify_opening_knowledge(&PedersenVecGens::new(size - 1), commitment, &mut transcript).err(),
+            Some(ProofError::VerificationError)
+        );
     }
 }
@@ -0,0 +1,182 @@
+use alloc::vec::Vec;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "std")]
+use rand_core::OsRng;
+
+use crate::boolean_proofs::sparse_proof::SparseZKProof;
+use crate::generators::PedersenVecGens;
+use crate::utils::misc::{bincode_deserialize, bincode_serialize};
+use ip_zk_proof::{ProofError, TranscriptProtocol, ZkProof};
+use serde::{Deserialize, Serialize};
+
+/// Proof that the positions `non_zero_elements..n` of a vector committed under `pc_gens` of
+/// size `n` are all zero, i.e. that the vector is zero-padded after its first
+/// `non_zero_elements` readings. The verifier then does not need to trust the claimed count.
+///
+/// This is a `SparseZKProof` with support `0..non_zero_elements`: the commitment opens under
+/// the first `non_zero_elements` bases alone, so the opening under the complementary bases is
+/// the trivial one.
+///
+/// As `NonZeroCountProof`, the proof is standalone, `zkSVMProver` does not include it.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PaddingZKProof {
+    proof_sparse: SparseZKProof,
+}
+
+impl PaddingZKProof {
+    /// Proves that `opening`, of the size of `pc_gens`, is zero after its first
+    /// `non_zero_elements` elements. If it is not, the proof does not verify. The blindings
+    /// are drawn from `OsRng`.
+    #[cfg(feature = "std")]
+    pub fn prove_padding(
+        pc_gens: &PedersenVecGens,
+        opening: &[Scalar],
+        non_zero_elements: usize,
+        blinding: Scalar,
+        transcript: &mut Transcript,
+    ) -> Result<PaddingZKProof, ProofError> {
+        PaddingZKProof::prove_padding_with_rng(pc_gens, opening, non_zero_elements, blinding, transcript, &mut OsRng)
+    }
+
+    /// As `prove_padding`, with the blindings drawn from `rng`.
+    pub fn prove_padding_with_rng<T: RngCore + CryptoRng>(
+        pc_gens: &PedersenVecGens,
+        opening: &[Scalar],
+        non_zero_elements: usize,
+        blinding: Scalar,
+        transcript: &mut Transcript,
+        rng: &mut T,
+    ) -> Result<PaddingZKProof, ProofError> {
+        if opening.len() != pc_gens.B.len() || non_zero_elements > opening.len() {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        transcript.append_integer(b"padding start", non_zero_elements as u64);
+        let support: Vec<usize> = (0..non_zero_elements).collect();
+        Ok(PaddingZKProof {
+            proof_sparse: SparseZKProof::prove_sparse_with_rng(
+                pc_gens,
+                &support,
+                &opening[..non_zero_elements],
+                blinding,
+                transcript,
+                rng,
+            )?,
+        })
+    }
+
+    pub fn verify_padding(
+        &self,
+        pc_gens: &PedersenVecGens,
+        commitment: CompressedRistretto,
+        non_zero_elements: usize,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        if non_zero_elements > pc_gens.B.len() {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        transcript.append_integer(b"padding start", non_zero_elements as u64);
+        let support: Vec<usize> = (0..non_zero_elements).collect();
+        self.proof_sparse.verify_sparse(pc_gens, &support, commitment, transcript)
+    }
+}
+
+/// The parameters are the generators and the number of non-zero elements, the witness the
+/// opening and its blinding factor, and the statement the commitment. The proof is serialized
+/// with bincode.
+impl ZkProof for PaddingZKProof {
+    type Params<'a> = (&'a PedersenVecGens, usize);
+    type Witness<'a> = (&'a [Scalar], Scalar);
+    type Statement = CompressedRistretto;
+    type Error = ProofError;
+
+    fn prove<R: RngCore + CryptoRng>(
+        (pc_gens, non_zero_elements): Self::Params<'_>,
+        (opening, blinding): Self::Witness<'_>,
+        transcript: &mut Transcript,
+        rng: &mut R,
+    ) -> Result<(PaddingZKProof, CompressedRistretto), ProofError> {
+        let proof = PaddingZKProof::prove_padding_with_rng(pc_gens, opening, non_zero_elements, blinding, transcript, rng)?;
+        Ok((proof, pc_gens.commit(opening, blinding).compress()))
+    }
+
+    fn verify(
+        &self,
+        (pc_gens, non_zero_elements): Self::Params<'_>,
+        commitment: &CompressedRistretto,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_padding(pc_gens, *commitment, non_zero_elements, transcript)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        bincode_serialize(self).expect("A proof of vectors and points always serializes.")
+    }
+
+    fn from_bytes(slice: &[u8]) -> Result<PaddingZKProof, ProofError> {
+        bincode_deserialize(slice).map_err(|_| ProofError::FormatError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::OsRng;
+
+    fn padding_proof_helper(claimed: usize, actual: usize) -> Result<(), ProofError> {
+        let size = 16;
+        let ped_gens = PedersenVecGens::new(size);
+        let mut csprng: OsRng = OsRng;
+
+        let opening: Vec<Scalar> = (0..size)
+            .map(|i| if i < actual { Scalar::random(&mut csprng) } else { Scalar::ZERO })
+            .collect();
+        let blinding = Scalar::random(&mut csprng);
+        let commitment = ped_gens.commit(&opening, blinding).compress();
+
+        let mut transcript = Transcript::new(b"test");
+        let proof = PaddingZKProof::prove_padding(&ped_gens, &opening, claimed, blinding, &mut transcript)?;
+
+        transcript = Transcript::new(b"test");
+        proof.verify_padding(&ped_gens, commitment, claimed, &mut transcript)
+    }
+
+    #[test]
+    fn proof_works() {
+        assert!(padding_proof_helper(10, 10).is_ok());
+        // Claiming more readings than there are is sound, the last ones are zero
+        assert!(padding_proof_helper(12, 10).is_ok());
+        assert!(padding_proof_helper(16, 16).is_ok());
+        assert!(padding_proof_helper(0, 0).is_ok());
+    }
+
+    #[test]
+    fn zk_proof_round_trip() {
+        let ped_gens = PedersenVecGens::new(8);
+        let mut csprng: OsRng = OsRng;
+
+        let opening = [Scalar::from(3u64), Scalar::from(5u64), Scalar::ZERO, Scalar::ZERO, Scalar::ZERO, Scalar::ZERO, Scalar::ZERO, Scalar::ZERO];
+        let witness = (&opening[..], Scalar::random(&mut csprng));
+        let (proof, commitment) = <PaddingZKProof as ZkProof>::prove(
+            (&ped_gens, 2),
+            witness,
+            &mut Transcript::new(b"test"),
+            &mut csprng,
+        ).unwrap();
+
+        let proof = <PaddingZKProof as ZkProof>::from_bytes(&ZkProof::to_bytes(&proof)).unwrap();
+        assert!(ZkProof::verify(&proof, (&ped_gens, 2), &commitment, &mut Transcript::new(b"test")).is_ok());
+        assert!(ZkProof::verify(&proof, (&ped_gens, 3), &commitment, &mut Transcript::new(b"test")).is_err());
+    }
+
+    #[test]
+    fn proof_fails() {
+        assert_eq!(padding_proof_helper(9, 10).err(), Some(ProofError::VerificationError));
+        assert_eq!(padding_proof_helper(17, 16).err(), Some(ProofError::InvalidGeneratorsLength));
+    }
+}
@@ -0,0 +1,392 @@
+#![allow(non_snake_case)]
+use alloc::vec::Vec;
+use alloc::vec;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::generators::PedersenVecGens;
+use crate::sigma::{PointVar, Prover, ScalarVar, SchnorrCS, SigmaProof, Verifier};
+#[cfg(feature = "std")]
+use crate::utils::randomness::random_scalars;
+#[cfg(feature = "std")]
+use ip_zk_proof::BulletproofGens;
+use ip_zk_proof::{PedersenGens, ProofError, RangeProof};
+#[cfg(feature = "std")]
+use ip_zk_proof::TranscriptProtocol;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use core::convert::TryInto;
+
+/// Bitsize of the range proofs of the readings. The bounds must be smaller than \\(2^{31}\\).
+pub const PLAUSIBILITY_RANGE_BITSIZE: usize = 32;
+
+/// Proof that every reading of the signed sensor vectors lies within physically plausible
+/// bounds, i.e. \\(|x_i| \leq bound\\) for the bound of its sensor, e.g. 16g for an
+/// accelerometer, encoded in the fixed point of the readings.
+///
+/// The prover commits to each reading \\(x_i\\) under `ped_gens`, proves with a sigma proof
+/// that these are the elements of the signed commitments, and proves with a single aggregated
+/// range proof that \\(bound + x_i\\) and \\(bound - x_i\\) are in \\([0, 2^{32})\\). The
+/// aggregation is padded with commitments to zero up to a power of two.
+///
+/// It needs the blinding factors of the signed commitments, so it is created next to
+/// `zkSVMProver::new_with_signed_blindings`, with the blindings of
+/// `zkSVMProver::hash_init_vectors_with_blindings`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct PlausibilityProof {
+    /// Commitments to each reading, per sensor and axis
+    readings: Vec<Vec<Vec<CompressedRistretto>>>,
+    proof: SigmaProof,
+    range_proof: RangeProof,
+}
+
+/// Secret scalars of the statement of one signed commitment.
+struct AxisScalars {
+    opening: Vec<ScalarVar>,
+    blinding: ScalarVar,
+    reading_blindings: Vec<ScalarVar>,
+}
+
+/// Public points of the statement of one signed commitment.
+struct AxisPoints {
+    commitment: PointVar,
+    readings: Vec<PointVar>,
+}
+
+/// Public points shared by the statements of all the signed commitments.
+struct BasePoints {
+    bases: Vec<PointVar>,
+    vector_blinding_base: PointVar,
+    G: PointVar,
+    H: PointVar,
+}
+
+fn plausibility_statement<CS: SchnorrCS>(cs: &mut CS, scalars: &[AxisScalars], points: &[AxisPoints], bases: &BasePoints) {
+    for (scalars, points) in scalars.iter().zip(points.iter()) {
+        cs.constrain(
+            points.commitment,
+            scalars.opening.iter().cloned().zip(bases.bases.iter().cloned())
+                .chain(Some((scalars.blinding, bases.vector_blinding_base)))
+                .collect(),
+        );
+        for i in 0..scalars.opening.len() {
+            cs.constrain(
+                points.readings[i],
+                vec![(scalars.opening[i], bases.G), (scalars.reading_blindings[i], bases.H)],
+            );
+        }
+    }
+}
+
+impl PlausibilityProof {
+    /// Proves that every element of `sensor_vectors`, committed under `pc_gens` with
+    /// `blindings` (one per axis), is at most `bounds[j]` in absolute value, for sensor `j`.
+    /// Readings out of their bounds give a proof that does not verify.
+    #[cfg(feature = "std")]
+    pub fn create(
+        pc_gens: &PedersenVecGens,
+        ped_gens: &PedersenGens,
+        sensor_vectors: &[[Vec<Scalar>; 3]],
+        blindings: &[Vec<Scalar>],
+        bounds: &[u64],
+        transcript: &mut Transcript,
+    ) -> Result<PlausibilityProof, ProofError> {
+        check_bounds(sensor_vectors.len(), bounds)?;
+        if blindings.len() != sensor_vectors.len() || blindings.iter().any(|b| b.len() != 3) {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+        if sensor_vectors.iter().flatten().any(|axis| axis.len() != pc_gens.B.len()) {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        let size = pc_gens.B.len();
+        let mut csprng = thread_rng();
+
+        let reading_blindings = random_scalars(&mut csprng, 3 * size * sensor_vectors.len());
+
+        append_bounds(bounds, transcript);
+        let (readings, proof) = prove_readings(
+            b"Plausibility",
+            pc_gens,
+            ped_gens,
+            sensor_vectors,
+            blindings,
+            &reading_blindings,
+            transcript,
+            &mut csprng,
+        );
+
+        // Bound plus and minus each reading, with the blinding factor of its commitment
+        let mut values = Vec::with_capacity(2 * reading_blindings.len());
+        let mut range_blindings = Vec::with_capacity(2 * reading_blindings.len());
+        for (j, sensor) in sensor_vectors.iter().enumerate() {
+            let bound = Scalar::from(bounds[j]);
+            for (k, axis) in sensor.iter().enumerate() {
+                for (i, x) in axis.iter().enumerate() {
+                    let blinding = reading_blindings[(3 * j + k) * size + i];
+                    values.extend_from_slice(&[low_u64(&(bound + x)), low_u64(&(bound - x))]);
+                    range_blindings.extend_from_slice(&[blinding, -blinding]);
+                }
+            }
+        }
+        let parties = values.len().next_power_of_two();
+        values.resize(parties, 0);
+        range_blindings.resize(parties, Scalar::ZERO);
+
+        let (range_proof, _) = RangeProof::prove_multiple_with_rng(
+            &BulletproofGens::new(PLAUSIBILITY_RANGE_BITSIZE, parties),
+            ped_gens,
+            transcript,
+            &values,
+            &range_blindings,
+            PLAUSIBILITY_RANGE_BITSIZE,
+            &mut csprng,
+        )?;
+
+        Ok(PlausibilityProof {
+            readings: readings.iter()
+                .map(|sensor| sensor.iter().map(|axis| axis.iter().map(|Y| Y.compress()).collect()).collect())
+                .collect(),
+            proof,
+            range_proof,
+        })
+    }
+
+    /// Verifies that every reading of the `signed_commitments`, under `pc_gens`, is within the
+    /// bound of its sensor.
+    #[cfg(feature = "std")]
+    pub fn verify(
+        &self,
+        pc_gens: &PedersenVecGens,
+        ped_gens: &PedersenGens,
+        signed_commitments: &[Vec<CompressedRistretto>],
+        bounds: &[u64],
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        check_bounds(signed_commitments.len(), bounds)?;
+        let size = pc_gens.B.len();
+        if self.readings.len() != signed_commitments.len()
+            || signed_commitments.iter().any(|c| c.len() != 3)
+            || self.readings.iter().flatten().any(|axis| axis.len() != size)
+        {
+            return Err(ProofError::VerificationError);
+        }
+
+        append_bounds(bounds, transcript);
+        verify_readings(b"Plausibility", pc_gens, ped_gens, signed_commitments, &self.readings, &self.proof, transcript)?;
+
+        let mut range_commitments = Vec::with_capacity(6 * signed_commitments.len() * size);
+        for (readings, &bound) in self.readings.iter().zip(bounds.iter()) {
+            let bound_commitment = Scalar::from(bound) * ped_gens.B;
+            for Y in readings.iter().flatten() {
+                let Y = Y.decompress().ok_or(ProofError::FormatError)?;
+                range_commitments.extend_from_slice(&[(bound_commitment + Y).compress(), (bound_commitment - Y).compress()]);
+            }
+        }
+        let parties = range_commitments.len().next_power_of_two();
+        range_commitments.resize(parties, RistrettoPoint::default().compress());
+
+        self.range_proof.verify_multiple(
+            &BulletproofGens::new(PLAUSIBILITY_RANGE_BITSIZE, parties),
+            ped_gens,
+            transcript,
+            &range_commitments,
+            PLAUSIBILITY_RANGE_BITSIZE,
+        )
+    }
+}
+
+/// Commits under `ped_gens` to each element of `sensor_vectors`, with the `reading_blindings`
+/// ordered by sensor, axis and element, and proves with a sigma proof that these are the
+/// elements of the signed commitments, under `pc_gens` with `blindings`. Returns the
+/// commitments to the readings, per sensor and axis, with the proof.
+pub(crate) fn prove_readings<T: RngCore + CryptoRng>(
+    label: &'static [u8],
+    pc_gens: &PedersenVecGens,
+    ped_gens: &PedersenGens,
+    sensor_vectors: &[[Vec<Scalar>; 3]],
+    blindings: &[Vec<Scalar>],
+    reading_blindings: &[Scalar],
+    transcript: &mut Transcript,
+    rng: &mut T,
+) -> (Vec<Vec<Vec<RistrettoPoint>>>, SigmaProof) {
+    let size = pc_gens.B.len();
+    let readings: Vec<Vec<Vec<RistrettoPoint>>> = sensor_vectors.iter().enumerate()
+        .map(|(j, sensor)| sensor.iter().enumerate()
+            .map(|(k, axis)| axis.iter().enumerate()
+                .map(|(i, x)| ped_gens.commit(*x, reading_blindings[(3 * j + k) * size + i]))
+                .collect())
+            .collect())
+        .collect();
+
+    let mut prover = Prover::new(label, transcript);
+
+    let mut scalars = Vec::with_capacity(3 * sensor_vectors.len());
+    for (j, sensor) in sensor_vectors.iter().enumerate() {
+        for (k, axis) in sensor.iter().enumerate() {
+            let offset = (3 * j + k) * size;
+            scalars.push(AxisScalars {
+                opening: axis.iter().map(|x| prover.allocate_scalar(b"x", *x)).collect(),
+                blinding: prover.allocate_scalar(b"blinding", blindings[j][k]),
+                reading_blindings: reading_blindings[offset..offset + size].iter()
+                    .map(|r| prover.allocate_scalar(b"reading blinding", *r))
+                    .collect(),
+            });
+        }
+    }
+
+    let bases = BasePoints {
+        bases: pc_gens.B.iter().map(|B| prover.allocate_point(b"base", *B).0).collect(),
+        vector_blinding_base: prover.allocate_point(b"blinding base", pc_gens.B_blinding).0,
+        G: prover.allocate_point(b"G", ped_gens.B).0,
+        H: prover.allocate_point(b"H", ped_gens.B_blinding).0,
+    };
+    let mut points = Vec::with_capacity(3 * sensor_vectors.len());
+    for (j, sensor) in sensor_vectors.iter().enumerate() {
+        for (k, axis) in sensor.iter().enumerate() {
+            points.push(AxisPoints {
+                commitment: prover.allocate_point(b"commitment", pc_gens.commit(axis, blindings[j][k])).0,
+                readings: readings[j][k].iter().map(|Y| prover.allocate_point(b"reading", *Y).0).collect(),
+            });
+        }
+    }
+
+    plausibility_statement(&mut prover, &scalars, &points, &bases);
+    (readings, prover.prove_with_rng(rng))
+}
+
+/// Verifies a proof of `prove_readings`, that the `readings` commit to the elements of the
+/// `signed_commitments`. The shapes must have been checked by the caller.
+pub(crate) fn verify_readings(
+    label: &'static [u8],
+    pc_gens: &PedersenVecGens,
+    ped_gens: &PedersenGens,
+    signed_commitments: &[Vec<CompressedRistretto>],
+    readings: &[Vec<Vec<CompressedRistretto>>],
+    proof: &SigmaProof,
+    transcript: &mut Transcript,
+) -> Result<(), ProofError> {
+    let size = pc_gens.B.len();
+    let mut verifier = Verifier::new(label, transcript);
+
+    let scalars: Vec<AxisScalars> = (0..3 * signed_commitments.len())
+        .map(|_| AxisScalars {
+            opening: (0..size).map(|_| verifier.allocate_scalar(b"x")).collect(),
+            blinding: verifier.allocate_scalar(b"blinding"),
+            reading_blindings: (0..size).map(|_| verifier.allocate_scalar(b"reading blinding")).collect(),
+        })
+        .collect();
+
+    let bases = BasePoints {
+        bases: pc_gens.B.iter()
+            .map(|B| verifier.allocate_point(b"base", B.compress()))
+            .collect::<Result<Vec<PointVar>, ProofError>>()?,
+        vector_blinding_base: verifier.allocate_point(b"blinding base", pc_gens.B_blinding.compress())?,
+        G: verifier.allocate_point(b"G", ped_gens.B.compress())?,
+        H: verifier.allocate_point(b"H", ped_gens.B_blinding.compress())?,
+    };
+    let mut points = Vec::with_capacity(scalars.len());
+    for (commitments, readings) in signed_commitments.iter().zip(readings.iter()) {
+        for (commitment, axis) in commitments.iter().zip(readings.iter()) {
+            points.push(AxisPoints {
+                commitment: verifier.allocate_point(b"commitment", *commitment)?,
+                readings: axis.iter()
+                    .map(|Y| verifier.allocate_point(b"reading", *Y))
+                    .collect::<Result<Vec<PointVar>, ProofError>>()?,
+            });
+        }
+    }
+
+    plausibility_statement(&mut verifier, &scalars, &points, &bases);
+    verifier.verify(proof)
+}
+
+#[cfg(feature = "std")]
+fn check_bounds(nr_sensors: usize, bounds: &[u64]) -> Result<(), ProofError> {
+    if bounds.len() != nr_sensors {
+        return Err(ProofError::InvalidGeneratorsLength);
+    }
+    if bounds.iter().any(|&b| b >= 1 << (PLAUSIBILITY_RANGE_BITSIZE - 1)) {
+        return Err(ProofError::InvalidBitsize);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+fn append_bounds(bounds: &[u64], transcript: &mut Transcript) {
+    transcript.append_integer(b"plausibility sensors", bounds.len() as u64);
+    for &bound in bounds {
+        transcript.append_integer(b"plausibility bound", bound);
+    }
+}
+
+/// The lowest 64 bits of a scalar. Out of range scalars are truncated, and their range
+/// proofs do not verify.
+#[cfg(feature = "std")]
+fn low_u64(scalar: &Scalar) -> u64 {
+    u64::from_le_bytes(
+        scalar.to_bytes()[0..8]
+            .try_into()
+            .expect("Should never happen as we are taking a slice of 8."),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::commitment_fns::multiple_commit;
+    use ip_zk_proof::r1cs::gadgets::signed_scalar;
+
+    fn plausibility_proof_helper(readings: &[i64], bounds: &[u64]) -> Result<(), ProofError> {
+        let size = 2;
+        let pc_gens = PedersenVecGens::new(size);
+        let ped_gens: PedersenGens = PedersenGens::default();
+
+        // Two sensors, with the given readings cycling through their axes
+        let sensor_vectors: Vec<[Vec<Scalar>; 3]> = (0..2)
+            .map(|j| {
+                let axis = |k: usize| (0..size)
+                    .map(|i| signed_scalar(readings[(j * 3 * size + k * size + i) % readings.len()]))
+                    .collect::<Vec<Scalar>>();
+                [axis(0), axis(1), axis(2)]
+            })
+            .collect();
+        let (commitments, blindings) = multiple_commit(&pc_gens, &sensor_vectors);
+
+        let mut transcript = Transcript::new(b"test");
+        let proof = PlausibilityProof::create(&pc_gens, &ped_gens, &sensor_vectors, &blindings, bounds, &mut transcript)?;
+
+        transcript = Transcript::new(b"test");
+        proof.verify(&pc_gens, &ped_gens, &commitments, bounds, &mut transcript)
+    }
+
+    #[test]
+    fn proof_works() {
+        assert!(plausibility_proof_helper(&[0, 1, -1, 150, -150, 37], &[150, 150]).is_ok());
+    }
+
+    #[test]
+    fn proof_fails() {
+        assert_eq!(
+            plausibility_proof_helper(&[0, 1, -1, 151, 37], &[150, 1000]).err(),
+            Some(ProofError::VerificationError)
+        );
+        assert_eq!(
+            plausibility_proof_helper(&[0, -151], &[150, 150]).err(),
+            Some(ProofError::VerificationError)
+        );
+        assert_eq!(
+            plausibility_proof_helper(&[0], &[150]).err(),
+            Some(ProofError::InvalidGeneratorsLength)
+        );
+        assert_eq!(
+            plausibility_proof_helper(&[0], &[1 << 31, 0]).err(),
+            Some(ProofError::InvalidBitsize)
+        );
+    }
+}
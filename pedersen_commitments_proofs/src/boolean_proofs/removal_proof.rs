@@ -0,0 +1,351 @@
+#![allow(non_snake_case)]
+use alloc::vec::Vec;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+use crate::boolean_proofs::dlog_proof::DlogZKProof;
+use crate::boolean_proofs::opening_proof::OpeningZKProof;
+use crate::generators::PedersenVecGens;
+use ip_zk_proof::{CanonicalPoints, ProofError, ProofReader};
+#[cfg(feature = "std")]
+use ip_zk_proof::ZkProof;
+use crate::utils::misc::check_canonical;
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "std")]
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+
+/// Proof that a point \\(x_p G_p\\) is the element at a public position \\(p\\) of a vector
+/// commitment, times its base, so that it can be removed from the commitment, e.g. to mask an
+/// individual sample or to drop the last reading of a sensor vector.
+///
+/// It consists of a proof of knowledge of the discrete logarithm of the removed point with
+/// respect to \\(G_p\\), and a proof of knowledge of an opening of the commitment minus the
+/// removed point under the generators without \\(G_p\\). Both proofs run over the same
+/// transcript, in this order.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ElementRemovalProof {
+    /// The removed element times its base
+    removed: RistrettoPoint,
+    proof_element: DlogZKProof,
+    proof_remaining: OpeningZKProof,
+}
+
+impl ElementRemovalProof {
+    /// Proves the removal of the element at `position` of the commitment to `opening` under
+    /// `pc_gens` with the given blinding factor. Returns `InvalidGeneratorsLength` if the
+    /// opening does not have the size of the generators, or if the position is out of range.
+    #[cfg(feature = "std")]
+    pub fn prove(
+        pc_gens: &PedersenVecGens,
+        opening: &[Scalar],
+        blinding: Scalar,
+        position: usize,
+        transcript: &mut Transcript,
+    ) -> Result<ElementRemovalProof, ProofError> {
+        ElementRemovalProof::prove_with_rng(pc_gens, opening, blinding, position, transcript, &mut OsRng)
+    }
+
+    pub(crate) fn prove_with_rng<T: RngCore + CryptoRng>(
+        pc_gens: &PedersenVecGens,
+        opening: &[Scalar],
+        blinding: Scalar,
+        position: usize,
+        transcript: &mut Transcript,
+        rng: &mut T,
+    ) -> Result<ElementRemovalProof, ProofError> {
+        if opening.len() != pc_gens.B.len() || position >= opening.len() {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        let element = opening[position];
+        let proof_element = DlogZKProof::prove_dlog_with_rng(&pc_gens.B[position], element, transcript, rng);
+
+        let kept = pc_gens.kept_positions(&[position])
+            .expect("Should never happen, as the position is checked above.");
+        let pc_gens_remaining = pc_gens.keep_bases(&kept)
+            .expect("Should never happen, as the positions are taken from the generators.");
+        let opening_remaining = PedersenVecGens::restrict_opening(opening, &kept)
+            .expect("Should never happen, as the opening has the size of the generators.");
+        let proof_remaining = OpeningZKProof::prove_opening_with_rng(&pc_gens_remaining, &opening_remaining, blinding, transcript, rng);
+
+        Ok(ElementRemovalProof {
+            removed: element * pc_gens.B[position],
+            proof_element,
+            proof_remaining,
+        })
+    }
+
+    /// The removed element times its base.
+    pub fn removed_element(&self) -> RistrettoPoint {
+        self.removed
+    }
+
+    /// Verifies the removal of the element at `position` of `commitment`, and returns the
+    /// commitment without it, which commits under `pc_gens.remove_bases(&[position])`.
+    #[cfg(feature = "std")]
+    pub fn verify(
+        &self,
+        pc_gens: &PedersenVecGens,
+        commitment: RistrettoPoint,
+        position: usize,
+        transcript: &mut Transcript,
+    ) -> Result<RistrettoPoint, ProofError> {
+        ElementRemovalProof::batch_verify(&[self], pc_gens, &[commitment], &[position], &mut [transcript])?;
+        Ok(commitment - self.removed)
+    }
+
+    /// Verifies several proofs, where proof `i` removes the element at `positions[i]` of
+    /// `commitments[i]` over `transcripts[i]`. The proofs of the removed elements are verified
+    /// with a single multiscalar multiplication, see `DlogZKProof::batch_verify`, and each
+    /// transcript then continues with the proof of the remaining opening.
+    #[cfg(feature = "std")]
+    pub fn batch_verify(
+        proofs: &[&ElementRemovalProof],
+        pc_gens: &PedersenVecGens,
+        commitments: &[RistrettoPoint],
+        positions: &[usize],
+        transcripts: &mut [&mut Transcript],
+    ) -> Result<(), ProofError> {
+        ElementRemovalProof::batch_verify_with_rng(proofs, pc_gens, commitments, positions, transcripts, &mut OsRng)
+    }
+
+    /// As `batch_verify`, with the weights of the batched verification drawn from `rng`.
+    pub fn batch_verify_with_rng<T: RngCore + CryptoRng>(
+        proofs: &[&ElementRemovalProof],
+        pc_gens: &PedersenVecGens,
+        commitments: &[RistrettoPoint],
+        positions: &[usize],
+        transcripts: &mut [&mut Transcript],
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let nr_proofs = proofs.len();
+        if commitments.len() != nr_proofs || positions.len() != nr_proofs || transcripts.len() != nr_proofs {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        if positions.iter().any(|&p| p >= pc_gens.B.len()) {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        DlogZKProof::batch_verify_with_rng(
+            &proofs.iter().map(|p| &p.proof_element).collect::<Vec<&DlogZKProof>>(),
+            &positions.iter().map(|&p| pc_gens.B[p]).collect::<Vec<RistrettoPoint>>(),
+            &proofs.iter().map(|p| p.removed).collect::<Vec<RistrettoPoint>>(),
+            transcripts,
+            rng,
+        )?;
+
+        for (((proof, commitment), &position), transcript) in proofs.iter()
+            .zip(commitments.iter())
+            .zip(positions.iter())
+            .zip(transcripts.iter_mut())
+        {
+            let pc_gens_remaining = pc_gens.remove_bases(&[position])
+                .ok_or(ProofError::InvalidGeneratorsLength)?;
+            proof.proof_remaining.clone().verify_opening_knowledge(
+                &pc_gens_remaining,
+                (commitment - proof.removed).compress(),
+                transcript,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Serializes the proof as the removed point (32 bytes), the proof of the element (64
+    /// bytes, see `DlogZKProof::to_bytes`) and the proof of the remaining opening (see
+    /// `OpeningZKProof::to_bytes`).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let proof_remaining = self.proof_remaining.to_bytes();
+        let mut buf = Vec::with_capacity(96 + proof_remaining.len());
+        buf.extend_from_slice(self.removed.compress().as_bytes());
+        buf.extend_from_slice(&self.proof_element.to_bytes());
+        buf.extend_from_slice(&proof_remaining);
+        buf
+    }
+
+    /// Parses a proof serialized with `to_bytes`. Returns a `FormatError` if the encoding is
+    /// invalid.
+    pub fn from_bytes(slice: &[u8]) -> Result<ElementRemovalProof, ProofError> {
+        let mut reader = ProofReader::new(slice);
+        let removed = reader.read_point::<CompressedRistretto>()?
+            .decompress()
+            .ok_or(ProofError::FormatError)?;
+        let proof_element = DlogZKProof::from_bytes(reader.read_bytes(64)?)?;
+        let proof_remaining = OpeningZKProof::from_bytes(reader.read_bytes(reader.remaining())?)?;
+
+        Ok(ElementRemovalProof { removed, proof_element, proof_remaining })
+    }
+
+    /// As `from_bytes`, rejecting proofs with non-canonical points, see `CanonicalPoints`.
+    pub fn from_bytes_strict(slice: &[u8]) -> Result<ElementRemovalProof, ProofError> {
+        check_canonical(ElementRemovalProof::from_bytes(slice)?)
+    }
+}
+
+/// The removed point is decompressed when parsing, so only the sub-proofs are checked.
+impl CanonicalPoints for ElementRemovalProof {
+    fn points_are_canonical(&self) -> bool {
+        self.proof_element.points_are_canonical() && self.proof_remaining.points_are_canonical()
+    }
+}
+
+impl PartialOrd for ElementRemovalProof {
+    fn partial_cmp(&self, other: &ElementRemovalProof) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ElementRemovalProof {
+    fn cmp(&self, other: &ElementRemovalProof) -> Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
+impl Hash for ElementRemovalProof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+/// The parameters are the generators and the position of the removed element, the witness the
+/// opening and its blinding factor, and the statement the commitment.
+/// The proof is verified with a `thread_rng`.
+#[cfg(feature = "std")]
+impl ZkProof for ElementRemovalProof {
+    type Params<'a> = (&'a PedersenVecGens, usize);
+    type Witness<'a> = (&'a [Scalar], Scalar);
+    type Statement = RistrettoPoint;
+    type Error = ProofError;
+
+    fn prove<R: RngCore + CryptoRng>(
+        (pc_gens, position): Self::Params<'_>,
+        (opening, blinding): Self::Witness<'_>,
+        transcript: &mut Transcript,
+        rng: &mut R,
+    ) -> Result<(ElementRemovalProof, RistrettoPoint), ProofError> {
+        let proof = ElementRemovalProof::prove_with_rng(pc_gens, opening, blinding, position, transcript, rng)?;
+        Ok((proof, pc_gens.commit(opening, blinding)))
+    }
+
+    fn verify(
+        &self,
+        (pc_gens, position): Self::Params<'_>,
+        commitment: &RistrettoPoint,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        ElementRemovalProof::verify(self, pc_gens, *commitment, position, transcript).map(|_| ())
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        ElementRemovalProof::to_bytes(self)
+    }
+
+    fn from_bytes(slice: &[u8]) -> Result<ElementRemovalProof, ProofError> {
+        ElementRemovalProof::from_bytes(slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::OsRng;
+
+    #[test]
+    fn proof_works() {
+        let size = 16;
+        let pc_gens = PedersenVecGens::new(size);
+        let mut csprng: OsRng = OsRng;
+
+        let opening: Vec<Scalar> = (0..size).map(|_| Scalar::random(&mut csprng)).collect();
+        let blinding = Scalar::random(&mut csprng);
+        let commitment = pc_gens.commit(&opening, blinding);
+
+        let mut transcript = Transcript::new(b"test");
+        let proof = ElementRemovalProof::prove(&pc_gens, &opening, blinding, 5, &mut transcript).unwrap();
+        assert_eq!(proof.removed_element(), opening[5] * pc_gens.B[5]);
+
+        let proof = ElementRemovalProof::from_bytes(&proof.to_bytes()).unwrap();
+        transcript = Transcript::new(b"test");
+        let remaining = proof.verify(&pc_gens, commitment, 5, &mut transcript).unwrap();
+
+        let opening_remaining = PedersenVecGens::restrict_opening(&opening, &pc_gens.kept_positions(&[5]).unwrap()).unwrap();
+        assert_eq!(remaining, pc_gens.remove_bases(&[5]).unwrap().commit(&opening_remaining, blinding));
+    }
+
+    #[test]
+    fn proof_fails() {
+        let size = 16;
+        let pc_gens = PedersenVecGens::new(size);
+        let mut csprng: OsRng = OsRng;
+
+        let opening: Vec<Scalar> = (0..size).map(|_| Scalar::random(&mut csprng)).collect();
+        let blinding = Scalar::random(&mut csprng);
+        let commitment = pc_gens.commit(&opening, blinding);
+
+        let mut transcript = Transcript::new(b"test");
+        let proof = ElementRemovalProof::prove(&pc_gens, &opening, blinding, 5, &mut transcript).unwrap();
+
+        // Removing the element from another position
+        transcript = Transcript::new(b"test");
+        assert_eq!(proof.verify(&pc_gens, commitment, 6, &mut transcript).err(), Some(ProofError::VerificationError));
+
+        transcript = Transcript::new(b"test");
+        assert_eq!(proof.verify(&pc_gens, commitment, size, &mut transcript).err(), Some(ProofError::InvalidGeneratorsLength));
+        assert_eq!(
+            ElementRemovalProof::prove(&pc_gens, &opening[1..], blinding, 5, &mut transcript).err(),
+            Some(ProofError::InvalidGeneratorsLength)
+        );
+        assert_eq!(ElementRemovalProof::from_bytes(&[0u8; 95]).err(), Some(ProofError::FormatError));
+    }
+
+    #[test]
+    fn strict_parsing_rejects_non_canonical_points() {
+        let size = 4;
+        let pc_gens = PedersenVecGens::new(size);
+        let mut csprng: OsRng = OsRng;
+        let opening: Vec<Scalar> = (0..size).map(|_| Scalar::random(&mut csprng)).collect();
+        let proof = ElementRemovalProof::prove(&pc_gens, &opening, Scalar::random(&mut csprng), 1, &mut Transcript::new(b"test")).unwrap();
+        let mut bytes = proof.to_bytes();
+        assert_eq!(ElementRemovalProof::from_bytes_strict(&bytes), Ok(proof));
+
+        // The announcement of the proof of the element follows the removed point
+        bytes[32..64].copy_from_slice(&[0xff; 32]);
+        assert!(ElementRemovalProof::from_bytes(&bytes).is_ok());
+        assert_eq!(ElementRemovalProof::from_bytes_strict(&bytes).err(), Some(ProofError::FormatError));
+    }
+
+    #[test]
+    fn batch_verification() {
+        let size = 8;
+        let pc_gens = PedersenVecGens::new(size);
+        let mut csprng: OsRng = OsRng;
+        let positions = [0, 3, 7];
+
+        let openings: Vec<Vec<Scalar>> = positions.iter()
+            .map(|_| (0..size).map(|_| Scalar::random(&mut csprng)).collect())
+            .collect();
+        let blindings: Vec<Scalar> = positions.iter().map(|_| Scalar::random(&mut csprng)).collect();
+        let mut commitments: Vec<RistrettoPoint> = openings.iter().zip(blindings.iter())
+            .map(|(opening, blinding)| pc_gens.commit(opening, *blinding))
+            .collect();
+
+        let proofs: Vec<ElementRemovalProof> = (0..positions.len())
+            .map(|i| ElementRemovalProof::prove(&pc_gens, &openings[i], blindings[i], positions[i], &mut Transcript::new(b"test")).unwrap())
+            .collect();
+        let proofs: Vec<&ElementRemovalProof> = proofs.iter().collect();
+
+        let verify = |commitments: &[RistrettoPoint]| {
+            let mut transcripts: Vec<Transcript> = (0..positions.len()).map(|_| Transcript::new(b"test")).collect();
+            let mut transcripts: Vec<&mut Transcript> = transcripts.iter_mut().collect();
+            ElementRemovalProof::batch_verify(&proofs, &pc_gens, commitments, &positions, &mut transcripts)
+        };
+        assert!(verify(&commitments).is_ok());
+        assert_eq!(verify(&commitments[..2]).err(), Some(ProofError::InvalidGeneratorsLength));
+
+        commitments[1] += pc_gens.B[3];
+        assert_eq!(verify(&commitments).err(), Some(ProofError::VerificationError));
+    }
+}
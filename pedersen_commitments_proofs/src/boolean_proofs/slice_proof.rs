@@ -0,0 +1,226 @@
+#![allow(non_snake_case)]
+use alloc::vec::Vec;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{VartimeMultiscalarMul, IsIdentity};
+
+use core::iter;
+use core::ops::Range;
+use merlin::Transcript;
+
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "std")]
+use rand_core::OsRng;
+
+use crate::generators::PedersenVecGens;
+use ip_zk_proof::{ProofError, TranscriptProtocol};
+use serde::{Deserialize, Serialize};
+
+/// Proof that a commitment under `pc_gens.slice(range)` commits to the elements in `range` of
+/// the vector committed under `pc_gens`, e.g. to prove statements about a sub-window of an
+/// already committed trace.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct SliceZKProof {
+    /// Announcement
+    A: CompressedRistretto,
+    B: CompressedRistretto,
+    /// Response
+    r_randomization_full: Scalar,
+    r_randomization_slice: Scalar,
+    r_opening: Vec<Scalar>,
+}
+
+impl SliceZKProof {
+    /// Proves that the commitment under the slice of `pc_gens` commits to the elements in
+    /// `range` of `opening`. The blindings are drawn from `OsRng`.
+    #[cfg(feature = "std")]
+    pub fn prove_slice(
+        pc_gens: &PedersenVecGens,
+        range: Range<usize>,
+        opening: &[Scalar],
+        randomization_full: Scalar,
+        randomization_slice: Scalar,
+        transcript: &mut Transcript,
+    ) -> Result<SliceZKProof, ProofError> {
+        SliceZKProof::prove_slice_with_rng(
+            pc_gens,
+            range,
+            opening,
+            randomization_full,
+            randomization_slice,
+            transcript,
+            &mut OsRng,
+        )
+    }
+
+    /// As `prove_slice`, with the blindings drawn from `rng`.
+    pub fn prove_slice_with_rng<T: RngCore + CryptoRng>(
+        pc_gens: &PedersenVecGens,
+        range: Range<usize>,
+        opening: &[Scalar],
+        randomization_full: Scalar,
+        randomization_slice: Scalar,
+        transcript: &mut Transcript,
+        rng: &mut T,
+    ) -> Result<SliceZKProof, ProofError> {
+        let pc_gens_slice = pc_gens.slice(range.clone()).ok_or(ProofError::InvalidGeneratorsLength)?;
+        if pc_gens.B.len() != opening.len() {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        let size = opening.len();
+
+        let randomization_blinding_full = Scalar::random(rng);
+        let randomization_blinding_slice = Scalar::random(rng);
+        let opening_blinding: Vec<Scalar> =
+            (0..size).map(|_| Scalar::random(rng)).collect();
+
+        let A = pc_gens
+            .commit(&opening_blinding, randomization_blinding_full)
+            .compress();
+        let B = pc_gens_slice
+            .commit(&opening_blinding[range], randomization_blinding_slice)
+            .compress();
+
+        transcript.append_point(b"announcement A", &A);
+        transcript.append_point(b"announcement B", &B);
+
+        let challenge: Scalar = transcript.challenge_scalar(b"challenge");
+
+        let r_randomization_full: Scalar = challenge * randomization_full + randomization_blinding_full;
+        let r_randomization_slice: Scalar = challenge * randomization_slice + randomization_blinding_slice;
+        let r_opening = opening_blinding
+            .iter()
+            .zip(opening.iter())
+            .map(|(x, y)| x + challenge * y)
+            .collect();
+
+        Ok(SliceZKProof {
+            A,
+            B,
+            r_randomization_full,
+            r_randomization_slice,
+            r_opening,
+        })
+    }
+
+    #[cfg(feature = "std")]
+    pub fn verify_slice(
+        &self,
+        pc_gens: &PedersenVecGens,
+        range: Range<usize>,
+        commitment_full: CompressedRistretto,
+        commitment_slice: CompressedRistretto,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_slice_with_rng(pc_gens, range, commitment_full, commitment_slice, transcript, &mut OsRng)
+    }
+
+    /// As `verify_slice`, with the weight of the combined check drawn from `rng`.
+    pub fn verify_slice_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        pc_gens: &PedersenVecGens,
+        range: Range<usize>,
+        commitment_full: CompressedRistretto,
+        commitment_slice: CompressedRistretto,
+        transcript: &mut Transcript,
+        rng: &mut T,
+    ) -> Result<(), ProofError> {
+        let pc_gens_slice = pc_gens.slice(range.clone()).ok_or(ProofError::InvalidGeneratorsLength)?;
+        if self.r_opening.len() != pc_gens.B.len() {
+            return Err(ProofError::VerificationError);
+        }
+
+        transcript.append_point(b"announcement A", &self.A);
+        transcript.append_point(b"announcement B", &self.B);
+
+        let challenge: Scalar = transcript.challenge_scalar(b"challenge");
+
+        // Both checks are combined with a random weight for the one of the slice
+        let weight = Scalar::random(rng);
+        let mega_check = RistrettoPoint::optional_multiscalar_mul(
+            iter::once(Scalar::ONE)
+                .chain(iter::once(challenge))
+                .chain(iter::once(-self.r_randomization_full))
+                .chain(self.r_opening.iter().map(|r| -r))
+                .chain(iter::once(weight))
+                .chain(iter::once(weight * challenge))
+                .chain(iter::once(-(weight * self.r_randomization_slice)))
+                .chain(self.r_opening[range].iter().map(|r| -(weight * r)))
+            ,
+            iter::once(self.A.decompress())
+                .chain(iter::once(commitment_full.decompress()))
+                .chain(iter::once(Some(pc_gens.B_blinding)))
+                .chain(pc_gens.B.iter().map(|B| Some(*B)))
+                .chain(iter::once(self.B.decompress()))
+                .chain(iter::once(commitment_slice.decompress()))
+                .chain(iter::once(Some(pc_gens_slice.B_blinding)))
+                .chain(pc_gens_slice.B.iter().map(|B| Some(*B)))
+        )
+            .ok_or(ProofError::VerificationError)?;
+
+        if mega_check.is_identity() {
+            Ok(())
+        }
+        else {
+            Err(ProofError::VerificationError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slice_proof_helper(range: Range<usize>, tamper: bool) -> Result<(), ProofError> {
+        let size = 16;
+        let ped_gens = PedersenVecGens::new(size);
+        let mut csprng: OsRng = OsRng;
+
+        let randomization_full = Scalar::random(&mut csprng);
+        let randomization_slice = Scalar::random(&mut csprng);
+        let opening: Vec<Scalar> = (0..size).map(|_| Scalar::random(&mut csprng)).collect();
+        let mut sliced_opening = opening[range.clone()].to_vec();
+        if tamper {
+            sliced_opening[0] += Scalar::ONE;
+        }
+
+        let commitment_full = ped_gens.commit(&opening, randomization_full).compress();
+        let commitment_slice = ped_gens.slice(range.clone()).unwrap()
+            .commit(&sliced_opening, randomization_slice)
+            .compress();
+
+        let mut transcript = Transcript::new(b"test");
+        let proof = SliceZKProof::prove_slice(
+            &ped_gens,
+            range.clone(),
+            &opening,
+            randomization_full,
+            randomization_slice,
+            &mut transcript,
+        )?;
+
+        let mut transcript = Transcript::new(b"test");
+        proof.verify_slice(&ped_gens, range, commitment_full, commitment_slice, &mut transcript)
+    }
+
+    #[test]
+    fn proof_works() {
+        assert!(slice_proof_helper(3..10, false).is_ok());
+        assert!(slice_proof_helper(0..16, false).is_ok());
+        assert!(slice_proof_helper(15..16, false).is_ok());
+    }
+
+    #[test]
+    fn proof_fails() {
+        assert_eq!(slice_proof_helper(3..10, true).err(), Some(ProofError::VerificationError));
+        assert!(SliceZKProof::prove_slice(
+            &PedersenVecGens::new(16),
+            10..17,
+            &vec![Scalar::ONE; 16],
+            Scalar::ONE,
+            Scalar::ONE,
+            &mut Transcript::new(b"test"),
+        ).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+#![allow(non_snake_case)]
+use alloc::vec::Vec;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "std")]
+use rand_core::OsRng;
+
+use crate::boolean_proofs::opening_proof::OpeningZKProof;
+use crate::generators::PedersenVecGens;
+use crate::utils::misc::{bincode_deserialize, bincode_serialize};
+use ip_zk_proof::{ProofError, TranscriptProtocol, ZkProof};
+use serde::{Deserialize, Serialize};
+
+/// Proof that a commitment under `pc_gens` only has non-zero elements at some public
+/// `indices`, e.g. a commitment computed with `commit_sparse`. This is a proof of knowledge of
+/// an opening under the bases at `indices` only, so by the binding property of the commitment
+/// all the other positions are zero.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SparseZKProof {
+    proof_opening: OpeningZKProof,
+}
+
+impl SparseZKProof {
+    /// Proves that `values` at `indices` (strictly increasing) and `blinding` open the
+    /// commitment, with zeros elsewhere. The blindings are drawn from `OsRng`.
+    #[cfg(feature = "std")]
+    pub fn prove_sparse(
+        pc_gens: &PedersenVecGens,
+        indices: &[usize],
+        values: &[Scalar],
+        blinding: Scalar,
+        transcript: &mut Transcript,
+    ) -> Result<SparseZKProof, ProofError> {
+        SparseZKProof::prove_sparse_with_rng(pc_gens, indices, values, blinding, transcript, &mut OsRng)
+    }
+
+    /// As `prove_sparse`, with the blindings drawn from `rng`.
+    pub fn prove_sparse_with_rng<T: RngCore + CryptoRng>(
+        pc_gens: &PedersenVecGens,
+        indices: &[usize],
+        values: &[Scalar],
+        blinding: Scalar,
+        transcript: &mut Transcript,
+        rng: &mut T,
+    ) -> Result<SparseZKProof, ProofError> {
+        let pc_gens_support = SparseZKProof::support_gens(pc_gens, indices)?;
+        if values.len() != indices.len() {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        SparseZKProof::append_indices(indices, transcript);
+        Ok(SparseZKProof {
+            proof_opening: OpeningZKProof::prove_opening_with_rng(&pc_gens_support, values, blinding, transcript, rng),
+        })
+    }
+
+    pub fn verify_sparse(
+        &self,
+        pc_gens: &PedersenVecGens,
+        indices: &[usize],
+        commitment: CompressedRistretto,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        let pc_gens_support = SparseZKProof::support_gens(pc_gens, indices)?;
+
+        SparseZKProof::append_indices(indices, transcript);
+        self.proof_opening.clone().verify_opening_knowledge(&pc_gens_support, commitment, transcript)
+    }
+
+    fn support_gens(pc_gens: &PedersenVecGens, indices: &[usize]) -> Result<PedersenVecGens, ProofError> {
+        if indices.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        pc_gens.keep_bases(indices).ok_or(ProofError::InvalidGeneratorsLength)
+    }
+
+    fn append_indices(indices: &[usize], transcript: &mut Transcript) {
+        transcript.append_integer(b"sparse support size", indices.len() as u64);
+        for &i in indices {
+            transcript.append_integer(b"sparse index", i as u64);
+        }
+    }
+}
+
+/// The parameters are the generators and the support, the witness the values at the support
+/// and the blinding factor, and the statement the commitment. The proof is serialized with
+/// bincode.
+impl ZkProof for SparseZKProof {
+    type Params<'a> = (&'a PedersenVecGens, &'a [usize]);
+    type Witness<'a> = (&'a [Scalar], Scalar);
+    type Statement = CompressedRistretto;
+    type Error = ProofError;
+
+    fn prove<R: RngCore + CryptoRng>(
+        (pc_gens, indices): Self::Params<'_>,
+        (values, blinding): Self::Witness<'_>,
+        transcript: &mut Transcript,
+        rng: &mut R,
+    ) -> Result<(SparseZKProof, CompressedRistretto), ProofError> {
+        let proof = SparseZKProof::prove_sparse_with_rng(pc_gens, indices, values, blinding, transcript, rng)?;
+        let commitment = pc_gens.commit_sparse(indices, values, blinding).ok_or(ProofError::InvalidGeneratorsLength)?;
+        Ok((proof, commitment.compress()))
+    }
+
+    fn verify(
+        &self,
+        (pc_gens, indices): Self::Params<'_>,
+        commitment: &CompressedRistretto,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_sparse(pc_gens, indices, *commitment, transcript)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        bincode_serialize(self).expect("A proof of vectors and points always serializes.")
+    }
+
+    fn from_bytes(slice: &[u8]) -> Result<SparseZKProof, ProofError> {
+        bincode_deserialize(slice).map_err(|_| ProofError::FormatError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::OsRng;
+
+    #[test]
+    fn proof_works() {
+        let ped_gens = PedersenVecGens::new(32);
+        let mut csprng: OsRng = OsRng;
+
+        let indices = [0, 5, 6, 20];
+        let values: Vec<Scalar> = indices.iter().map(|_| Scalar::random(&mut csprng)).collect();
+        let blinding = Scalar::random(&mut csprng);
+        let commitment = ped_gens.commit_sparse(&indices, &values, blinding).unwrap().compress();
+
+        let mut transcript = Transcript::new(b"test");
+        let proof = SparseZKProof::prove_sparse(&ped_gens, &indices, &values, blinding, &mut transcript).unwrap();
+
+        transcript = Transcript::new(b"test");
+        assert!(proof.verify_sparse(&ped_gens, &indices, commitment, &mut transcript).is_ok());
+    }
+
+    #[test]
+    fn zk_proof_round_trip() {
+        let ped_gens = PedersenVecGens::new(32);
+        let mut csprng: OsRng = OsRng;
+
+        let indices = [1, 2, 17];
+        let values: Vec<Scalar> = indices.iter().map(|_| Scalar::random(&mut csprng)).collect();
+        let witness = (&values[..], Scalar::random(&mut csprng));
+        let (proof, commitment) = <SparseZKProof as ZkProof>::prove(
+            (&ped_gens, &indices),
+            witness,
+            &mut Transcript::new(b"test"),
+            &mut csprng,
+        ).unwrap();
+
+        let proof = <SparseZKProof as ZkProof>::from_bytes(&ZkProof::to_bytes(&proof)).unwrap();
+        assert!(ZkProof::verify(&proof, (&ped_gens, &indices), &commitment, &mut Transcript::new(b"test")).is_ok());
+        assert!(ZkProof::verify(&proof, (&ped_gens, &indices[..2]), &commitment, &mut Transcript::new(b"test")).is_err());
+    }
+
+    #[test]
+    fn proof_fails() {
+        let ped_gens = PedersenVecGens::new(32);
+        let mut csprng: OsRng = OsRng;
+
+        // A non-zero element outside of the claimed support
+        let mut dense: Vec<Scalar> = vec![Scalar::ZERO; 32];
+        dense[3] = Scalar::from(7u64);
+        dense[30] = Scalar::ONE;
+        let blinding = Scalar::random(&mut csprng);
+        let commitment = ped_gens.commit(&dense, blinding).compress();
+
+        let mut transcript = Transcript::new(b"test");
+        let proof = SparseZKProof::prove_sparse(&ped_gens, &[3], &[dense[3]], blinding, &mut transcript).unwrap();
+
+        transcript = Transcript::new(b"test");
+        assert_eq!(
+            proof.verify_sparse(&ped_gens, &[3], commitment, &mut transcript).err(),
+            Some(ProofError::VerificationError)
+        );
+
+        transcript = Transcript::new(b"test");
+        assert_eq!(
+            proof.verify_sparse(&ped_gens, &[3, 3], commitment, &mut transcript).err(),
+            Some(ProofError::InvalidGeneratorsLength)
+        );
+        assert_eq!(
+            SparseZKProof::prove_sparse(&ped_gens, &[3, 32], &[dense[3], dense[3]], blinding, &mut transcript).err(),
+            Some(ProofError::InvalidGeneratorsLength)
+        );
+    }
+}
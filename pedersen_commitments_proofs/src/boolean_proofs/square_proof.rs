@@ -2,16 +2,23 @@
 use curve25519_dalek::ristretto::{CompressedRistretto};
 use curve25519_dalek::scalar::Scalar;
 
-use ip_zk_proof::{BulletproofGens, PedersenGens, RangeProof, ProofError};
+use ip_zk_proof::{BulletproofGens, CanonicalPoints, PedersenGens, RangeProof, ProofError};
 
 use merlin::Transcript;
-use std::convert::TryInto;
+use core::convert::TryInto;
 
 use crate::boolean_proofs::equality_proof::EqualityZKProof;
 use crate::generators::PedersenVecGens;
+#[cfg(feature = "std")]
 use rand::thread_rng;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+/// Bitsize of the range proofs between the square and the squares of its floored square
+/// root and of the floored square root plus one.
+pub const SQUARE_RANGE_BITSIZE: usize = 32;
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
 // Given that we are working on a finite field, if the square root of a number is not an integer,
 // the proof below is not of great help. If we want to calculate the floor rounding of a square
 // root, we need to complicate it one step further.
@@ -32,6 +39,7 @@ pub struct FloatingSquareZKProof {
 }
 
 impl FloatingSquareZKProof {
+    #[cfg(feature = "std")]
     pub fn create(
         bulletproof_generators: &BulletproofGens,
         pedersen_generators: PedersenGens,
@@ -43,6 +51,39 @@ impl FloatingSquareZKProof {
         blinding_factor_round_square: Scalar,
         commitment_floor_sqr: CompressedRistretto,
         transcript: &mut Transcript,
+    ) -> Result<Self, ProofError> {
+        FloatingSquareZKProof::create_with_rng(
+            bulletproof_generators,
+            pedersen_generators,
+            sq,
+            floor_sqr,
+            round_square,
+            blinding_factor_sq,
+            blinding_factor_floor_sqr,
+            blinding_factor_round_square,
+            commitment_floor_sqr,
+            transcript,
+            SQUARE_RANGE_BITSIZE,
+            &mut thread_rng(),
+        )
+    }
+
+    /// As `create`, with the blinding factors and the nonces drawn from `rng`, and range proofs
+    /// of `range_bitsize` bits, which bounds the difference of the square and the squares of
+    /// the floored square root and of the floored square root plus one.
+    pub fn create_with_rng<T: RngCore + CryptoRng>(
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: PedersenGens,
+        sq: Scalar,
+        floor_sqr: Scalar,
+        round_square: Scalar,
+        blinding_factor_sq: Scalar,
+        blinding_factor_floor_sqr: Scalar,
+        blinding_factor_round_square: Scalar,
+        commitment_floor_sqr: CompressedRistretto,
+        transcript: &mut Transcript,
+        range_bitsize: usize,
+        rng: &mut T,
     ) -> Result<Self, ProofError> {
         let square_zk_1 = SquareZKProof::create(
             pedersen_generators,
@@ -51,61 +92,65 @@ impl FloatingSquareZKProof {
             blinding_factor_round_square,
             commitment_floor_sqr,
             transcript,
+            rng,
         )?;
 
         // Now we need to prove the the value committed in commitment_round_square is smaller than
         // the one committed in commitment_sq
-        let subtracted_blinding = &blinding_factor_sq - &blinding_factor_round_square;
+        let subtracted_blinding = blinding_factor_sq - blinding_factor_round_square;
         let subtracted = u64::from_le_bytes(
-            ((&sq - &round_square).to_bytes()[0..8])
+            ((sq - round_square).to_bytes()[0..8])
                 .try_into()
                 .expect("Should never happen as we are taking a slice of 8."),
         );
 
-        let (leq_1, _) = RangeProof::prove_single(
+        let (leq_1, _) = RangeProof::prove_single_with_rng(
             bulletproof_generators,
             &pedersen_generators,
             transcript,
             subtracted,
             &subtracted_blinding,
-            32,
+            range_bitsize,
+            rng,
         )?;
 
         // Now we do the same, but with floor_sq + 1
-        let blinding_floor_sqr_p1 = blinding_factor_floor_sqr.clone();
+        let blinding_floor_sqr_p1 = blinding_factor_floor_sqr;
         let commitment_floor_sqr_p1 =
-            commitment_floor_sqr.decompress().ok_or_else(|| ProofError::FormatError)?
+            commitment_floor_sqr.decompress().ok_or(ProofError::FormatError)?
                 + pedersen_generators.B;
 
-        let round_square_p1 = (&floor_sqr + &Scalar::one()) * (&floor_sqr + &Scalar::one());
-        let blinding_round_square_p1 = Scalar::random(&mut thread_rng());
+        let round_square_p1 = (floor_sqr + Scalar::ONE) * (floor_sqr + Scalar::ONE);
+        let blinding_round_square_p1 = Scalar::random(rng);
         let commitment_round_square_p1 =
             pedersen_generators.commit(round_square_p1, blinding_round_square_p1);
         let square_zk_2 = SquareZKProof::create(
             pedersen_generators,
-            &floor_sqr + &Scalar::one(),
+            floor_sqr + Scalar::ONE,
             blinding_floor_sqr_p1,
             blinding_round_square_p1,
             commitment_floor_sqr_p1.compress(),
             transcript,
+            rng,
         )?;
 
         // Now we need to prove the the value committed in commitment_round_square_p1 is greater than
         // the one committed in commitment_sq
-        let subtracted_blinding_p1 = &blinding_round_square_p1 - &blinding_factor_sq;
+        let subtracted_blinding_p1 = blinding_round_square_p1 - blinding_factor_sq;
         let subtracted_p1 = u64::from_le_bytes(
-            ((&round_square_p1 - &sq).to_bytes()[0..8])
+            ((round_square_p1 - sq).to_bytes()[0..8])
                 .try_into()
                 .expect("Should never happen as we are taking a slice of 8."),
         );
 
-        let (leq_2, _) = RangeProof::prove_single(
+        let (leq_2, _) = RangeProof::prove_single_with_rng(
             bulletproof_generators,
             &pedersen_generators,
             transcript,
             subtracted_p1,
             &subtracted_blinding_p1,
-            32,
+            range_bitsize,
+            rng,
         )?;
 
         Ok(FloatingSquareZKProof {
@@ -117,6 +162,7 @@ impl FloatingSquareZKProof {
         })
     }
 
+    #[cfg(feature = "std")]
     pub fn verify(
         self,
         bulletproofs_generators: &BulletproofGens,
@@ -128,17 +174,42 @@ impl FloatingSquareZKProof {
         // commitment of the square in question
         commitment_sq: CompressedRistretto,
         transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.verify_with_rng(
+            bulletproofs_generators,
+            pedersen_generators,
+            commitment_floor_sqr,
+            commitment_round_sq,
+            commitment_sq,
+            transcript,
+            SQUARE_RANGE_BITSIZE,
+            &mut thread_rng(),
+        )
+    }
+
+    /// As `verify`, with the randomness of the verification of the range proofs drawn from
+    /// `rng`, and range proofs of `range_bitsize` bits.
+    pub fn verify_with_rng<T: RngCore + CryptoRng>(
+        self,
+        bulletproofs_generators: &BulletproofGens,
+        pedersen_generators: PedersenGens,
+        commitment_floor_sqr: CompressedRistretto,
+        commitment_round_sq: CompressedRistretto,
+        commitment_sq: CompressedRistretto,
+        transcript: &mut Transcript,
+        range_bitsize: usize,
+        rng: &mut T,
     ) -> Result<(), ProofError> {
         let subtracted_commitment =
-            commitment_sq.decompress().ok_or_else(|| ProofError::FormatError)? -
-                commitment_round_sq.decompress().ok_or_else(|| ProofError::FormatError)?;
+            commitment_sq.decompress().ok_or(ProofError::FormatError)? -
+                commitment_round_sq.decompress().ok_or(ProofError::FormatError)?;
 
         let commitment_floor_sqr_p1 =
-            commitment_floor_sqr.decompress().ok_or_else(|| ProofError::FormatError)? +
+            commitment_floor_sqr.decompress().ok_or(ProofError::FormatError)? +
                 pedersen_generators.B;
         let subtracted_commitment_p1 =
-            self.commitment_round_square_p1.decompress().ok_or_else(|| ProofError::FormatError)? -
-                commitment_sq.decompress().ok_or_else(|| ProofError::FormatError)?;
+            self.commitment_round_square_p1.decompress().ok_or(ProofError::FormatError)? -
+                commitment_sq.decompress().ok_or(ProofError::FormatError)?;
 
         if
 
@@ -153,12 +224,13 @@ impl FloatingSquareZKProof {
 
             self
             .leq_1
-            .verify_single(
-                &bulletproofs_generators,
+            .verify_single_with_rng(
+                bulletproofs_generators,
                 &pedersen_generators,
                 transcript,
                 &subtracted_commitment.compress(),
-                32,
+                range_bitsize,
+                rng,
             ).is_ok()
 
             &&
@@ -173,12 +245,13 @@ impl FloatingSquareZKProof {
             &&
 
             self.leq_2
-            .verify_single(
-                &bulletproofs_generators,
+            .verify_single_with_rng(
+                bulletproofs_generators,
                 &pedersen_generators,
                 transcript,
                 &subtracted_commitment_p1.compress(),
-                32
+                range_bitsize,
+                rng,
             ).is_ok()
         {
             Ok(())
@@ -190,28 +263,39 @@ impl FloatingSquareZKProof {
     }
 }
 
-#[derive(Clone)]
+impl CanonicalPoints for FloatingSquareZKProof {
+    fn points_are_canonical(&self) -> bool {
+        self.commitment_round_square_p1.points_are_canonical()
+            && self.leq_1.points_are_canonical()
+            && self.leq_2.points_are_canonical()
+            && self.square_zk_1.equality_proof.points_are_canonical()
+            && self.square_zk_2.equality_proof.points_are_canonical()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 struct SquareZKProof {
     equality_proof: EqualityZKProof,
 }
 
 impl SquareZKProof {
-    fn create(
+    fn create<T: RngCore + CryptoRng>(
         pedersen_generators: PedersenGens,
         sqr: Scalar,
         blinding_factor_sqr: Scalar,
         blinding_factor_sq: Scalar,
         commitment_sqr: CompressedRistretto,
         transcript: &mut Transcript,
+        rng: &mut T,
     ) -> Result<Self, ProofError> {
         // We calculate the blinding factor of the commitment of sqr over commitment base
         // announcement_sqr
-        let blinding_commitment_sq: Scalar = &blinding_factor_sq - sqr * blinding_factor_sqr;
+        let blinding_commitment_sq: Scalar = blinding_factor_sq - sqr * blinding_factor_sqr;
 
         // We generate new pedersen generators
         let new_pedersen_generators = PedersenGens {
             B: commitment_sqr.decompress()
-                .ok_or_else(|| ProofError::FormatError)?,
+                .ok_or(ProofError::FormatError)?,
             B_blinding: pedersen_generators.B_blinding,
         };
 
@@ -220,17 +304,18 @@ impl SquareZKProof {
         let vec_pedersen_generators = PedersenVecGens::from(pedersen_generators);
         let vec_new_pedersen_generators = PedersenVecGens::from(new_pedersen_generators);
 
-        let equality_proof = EqualityZKProof::prove_equality(
+        let equality_proof = EqualityZKProof::prove_equality_with_rng(
             &vec_pedersen_generators,
             &vec_new_pedersen_generators,
-            &vec![sqr],
+            &[sqr],
             blinding_factor_sqr,
             blinding_commitment_sq,
             transcript,
+            rng,
         )?;
 
         Ok(SquareZKProof {
-            equality_proof: equality_proof,
+            equality_proof,
         })
     }
 
@@ -247,7 +332,7 @@ impl SquareZKProof {
         let vec_pedersen_generators = PedersenVecGens::from(pedersen_generators);
         let vec_new_pedersen_generators = PedersenVecGens::from(PedersenGens {
             B: commitment_sqr.decompress()
-                .ok_or_else(|| ProofError::FormatError)?,
+                .ok_or(ProofError::FormatError)?,
             B_blinding: pedersen_generators.B_blinding,
         });
 
@@ -369,6 +454,7 @@ mod tests {
             blinding_sq,
             commitment_sqr.compress(),
             &mut transcript,
+            &mut thread_rng(),
         ).unwrap();
 
         transcript = Transcript::new(b"testProofSquare");
@@ -400,6 +486,7 @@ mod tests {
             blinding_sqr,
             commitment_sqr.compress(),
             &mut transcript,
+            &mut thread_rng(),
         ).unwrap();
 
         transcript = Transcript::new(b"testProofSquareFailure");
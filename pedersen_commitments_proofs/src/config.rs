@@ -1,32 +1,288 @@
-use ip_zk_proof::{BulletproofGens, PedersenGens};
+use alloc::vec::Vec;
+use alloc::vec;
+use ip_zk_proof::{BulletproofGens, PedersenGens, TranscriptProtocol};
 use crate::PedersenVecGens;
+use crate::statistics::StatisticSet;
+use crate::utils::misc::generate_permuted_gens;
+use crate::utils::randomness::ProverRng;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+use rand_core::CryptoRngCore;
+
+/// Labels of the transcripts used by the proofs of the zkSVM prover, see `zkSVMProver`.
+///
+/// The default labels are the ones the proofs have always used, followed by the `domain` of
+/// the application if any. The configurations of the zkSVM always have one, the label of the
+/// application the parameters were set up for, see `PedersenConfig::new`, so that a proof
+/// generated for one application (or for one version of its protocol) does not verify in
+/// another.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptLabels {
+    /// Application label. Empty by default, in which case nothing is appended.
+    pub domain: Vec<u8>,
+    /// Challenge of an outer transcript the proofs are bound to, see `bind_to`. Empty by
+    /// default, in which case nothing is appended.
+    pub context: Vec<u8>,
+    /// Statistics proven by the proofs created under these labels, see `with_statistics`.
+    /// Empty by default, in which case nothing is appended.
+    pub statistics: StatisticSet,
+    /// Sizes of the statement proven by the proofs created under these labels, see
+    /// `with_sizes`. Empty by default, in which case nothing is appended.
+    pub sizes: Vec<u64>,
+    /// Inner product proofs of the sum of each sensor vector, in `AvgProof`.
+    pub average: &'static [u8],
+    /// Proofs of the sum commitments under the accumulated bases, in `AvgProof`.
+    pub average_commitment: &'static [u8],
+    /// Inner product proofs of the variances, in `VarianceProof`.
+    pub variance: &'static [u8],
+    /// Equality proofs between commitments of the same vectors under different bases.
+    pub commitment_equality: &'static [u8],
+    /// Square root proofs of the standard deviations, in `StdProof`.
+    pub standard_deviation: &'static [u8],
+    /// Proofs of the removal of the last non zero element, in `DiffProofs`.
+    pub remove_last: &'static [u8],
+    /// Proofs of the decision of the model over the features, in `DecisionProof`.
+    pub decision: &'static [u8],
+    /// Proofs of the normalization of the features, in `NormalizationProof`.
+    pub normalization: &'static [u8],
+    /// Proofs of the alignment of the windows of several sensors, in `ConsistencyProof`.
+    pub consistency: &'static [u8],
+    /// Proofs of the kernel values of the features, in `KernelProof`.
+    pub kernel: &'static [u8],
+    /// Transcript shared by the windows of a `MultiWindowProof`.
+    pub multi_window: &'static [u8],
+    /// Transcript shared by the sensor hubs of an `AggregatedProof`.
+    pub aggregation: &'static [u8],
+}
+
+impl TranscriptLabels {
+    /// Default labels, namespaced by the given application domain.
+    pub fn with_domain(domain: &[u8]) -> TranscriptLabels {
+        TranscriptLabels {
+            domain: domain.to_vec(),
+            ..TranscriptLabels::default()
+        }
+    }
+
+    /// Labels whose transcripts are bound to the current state of `transcript`, e.g. the
+    /// transcript of a larger protocol or of a signature over the proof. The proofs created
+    /// under these labels only verify under the labels bound to a transcript in the same
+    /// state.
+    pub fn bind_to(&self, transcript: &mut Transcript) -> TranscriptLabels {
+        transcript.domain_sep(b"zkSVM");
+        if !self.domain.is_empty() {
+            transcript.append_bytes(b"app-domain", &self.domain);
+        }
+        let mut context = vec![0u8; 64];
+        transcript.challenge_into(b"zkSVM context", &mut context);
+
+        TranscriptLabels {
+            context,
+            ..self.clone()
+        }
+    }
+
+    /// Labels whose transcripts declare that they prove `statistics`. The proofs created under
+    /// these labels only verify under labels with the same statistics.
+    pub fn with_statistics(&self, statistics: StatisticSet) -> TranscriptLabels {
+        TranscriptLabels {
+            statistics,
+            ..self.clone()
+        }
+    }
+
+    /// Labels whose transcripts declare the `sizes` of the statement, e.g. those of the
+    /// `ProofHeader` of a zkSVM proof. The proofs created under these labels only verify under
+    /// labels with the same sizes.
+    pub fn with_sizes(&self, sizes: &[u64]) -> TranscriptLabels {
+        TranscriptLabels {
+            sizes: sizes.to_vec(),
+            ..self.clone()
+        }
+    }
+
+    /// The labels of the transcripts of each proof, the fields of this structure after the
+    /// application domain, the context, the statistics and the sizes.
+    pub(crate) fn proof_labels(&self) -> [&'static [u8]; 12] {
+        [
+            self.average,
+            self.average_commitment,
+            self.variance,
+            self.commitment_equality,
+            self.standard_deviation,
+            self.remove_last,
+            self.decision,
+            self.normalization,
+            self.consistency,
+            self.kernel,
+            self.multi_window,
+            self.aggregation,
+        ]
+    }
+
+    /// Creates a transcript with the given label, one of the fields of this structure,
+    /// followed by the application domain, the context of the outer transcript, the proven
+    /// statistics and the sizes of the statement.
+    pub fn transcript(&self, label: &'static [u8]) -> Transcript {
+        let mut transcript = Transcript::new(label);
+        if !self.domain.is_empty() {
+            transcript.append_bytes(b"app-domain", &self.domain);
+        }
+        if !self.context.is_empty() {
+            transcript.append_bytes(b"context", &self.context);
+        }
+        if !self.statistics.is_empty() {
+            transcript.append_integer(b"statistics", self.statistics.bits());
+        }
+        if !self.sizes.is_empty() {
+            transcript.append_integer(b"sizes", self.sizes.len() as u64);
+            for size in &self.sizes {
+                transcript.append_integer(b"size", *size);
+            }
+        }
+        transcript
+    }
+}
+
+impl Default for TranscriptLabels {
+    fn default() -> Self {
+        TranscriptLabels {
+            domain: Vec::new(),
+            context: Vec::new(),
+            statistics: StatisticSet::empty(),
+            sizes: Vec::new(),
+            average: b"InnerProductAverage",
+            average_commitment: b"ProofAverageCommitmentG",
+            variance: b"InnerProductAverage",
+            commitment_equality: b"TranscriptProofDiffCorrectness",
+            standard_deviation: b"StandardDeviationProof",
+            remove_last: b"ProofRemoveLastNonZeroElement",
+            decision: b"SvmDecisionProof",
+            normalization: b"FeatureNormalizationProof",
+            consistency: b"CrossSensorConsistencyProof",
+            kernel: b"SvmKernelProof",
+            multi_window: b"zkSVMMultiWindow",
+            aggregation: b"zkSVMSensorAggregation",
+        }
+    }
+}
 
 /// A structure for Pedersen commitmentts.
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct PedersenConfig {
     pedersenGens: PedersenGens,
     G_vec: PedersenVecGens,
     H_vec: PedersenVecGens,
-    size: usize
+    size: usize,
+    // Prefix sums of the vector bases, with the blinding base added. Position `i` contains
+    // B_blinding + G_0 + ... + G_{i-1}, which is the base of a commitment to a vector whose
+    // first `i` entries all equal the same value.
+    acc_bases_G: Vec<RistrettoPoint>,
+    acc_bases_H: Vec<RistrettoPoint>,
+    // G_vec iterated until each possible number of elements, see `PedersenVecGens::iterate`.
+    // Position `i` contains the generators for vectors with `i + 1` elements. These are
+    // shared by the prover and the verifier of every window using this configuration.
+    permuted_G_vecs: Vec<PedersenVecGens>,
+    // Label of the application, the domain of the labels
+    application: Vec<u8>,
+    labels: TranscriptLabels,
+    // Whether the provers key their RNG by their witness, see `with_witness_rng`
+    witness_rng: bool,
 }
 
 impl PedersenConfig {
+    /// Precomputes the configuration of the given generators for the application with the
+    /// given label, which is the domain of every transcript. Missing vector generators of
+    /// the signed vectors are derived from the label, see `PedersenVecGens::from_application`.
+    /// Missing ones of the unsigned vectors are random, or derived from the label as well
+    /// without the `std` feature.
+    ///
+    /// Panics if `application` is empty.
     pub fn new(
+        application: &[u8],
         pedersenGens: &Option<PedersenGens>,
         G_vec: &Option<PedersenVecGens>,
         H_vec: &Option<PedersenVecGens>,
         size: usize,
     ) -> PedersenConfig {
+        assert!(!application.is_empty(), "The application label must not be empty.");
+        let pedersenGens = pedersenGens.unwrap_or_else(PedersenGens::default);
+        let G_vec = G_vec.clone().unwrap_or_else(|| PedersenVecGens::from_application(application, b"G_vec", size));
+        #[cfg(feature = "std")]
+        let H_vec = H_vec.clone().unwrap_or_else(|| PedersenVecGens::new_random(size));
+        #[cfg(not(feature = "std"))]
+        let H_vec = H_vec.clone().unwrap_or_else(|| PedersenVecGens::from_application(application, b"H_vec", size));
+
+        let acc_bases_G = PedersenConfig::prefix_sums(pedersenGens.B_blinding, &G_vec.B[..size]);
+        let acc_bases_H = PedersenConfig::prefix_sums(pedersenGens.B_blinding, &H_vec.B[..size]);
+        let permuted_G_vecs = generate_permuted_gens(&G_vec, &(1..=size).collect::<Vec<usize>>());
+
         PedersenConfig{
-            pedersenGens: pedersenGens.unwrap_or(PedersenGens::default()),
-            G_vec: G_vec.unwrap_or(PedersenVecGens::new(size)),
-            H_vec: H_vec.unwrap_or(PedersenVecGens::new_random(size)),
-            size
+            pedersenGens,
+            G_vec,
+            H_vec,
+            size,
+            acc_bases_G,
+            acc_bases_H,
+            permuted_G_vecs,
+            application: application.to_vec(),
+            labels: TranscriptLabels::with_domain(application),
+            witness_rng: false,
         }
     }
 
-    pub fn get_bp_gens(
+    /// Replaces the transcript labels, keeping the application label as their domain. The
+    /// prover and the verifier need to use the same labels.
+    pub fn with_labels(mut self, labels: TranscriptLabels) -> PedersenConfig {
+        self.labels = TranscriptLabels { domain: self.application.clone(), ..labels };
+        self
+    }
+
+    /// Derives the randomness of every prover under this configuration from its witness and
+    /// the RNG it is given, see `prover_rng`, instead of taking it from the RNG alone. The
+    /// blindings of the proofs then remain secret on devices with a weak system RNG. It only
+    /// concerns the prover, the proofs verify as any other.
+    pub fn with_witness_rng(mut self) -> PedersenConfig {
+        self.witness_rng = true;
         self
+    }
+
+    /// The RNG of a prover of `witness`, see `with_witness_rng`. Without it, `rng` itself.
+    /// With it, a `TranscriptRng` keyed by the labels of the configuration, `label` and the
+    /// `witness`, and seeded by `rng`, see `TranscriptProtocol::witness_rng`, which stays
+    /// unpredictable as long as either the witness or `rng` are.
+    ///
+    /// The provers of `zkSVMProver` take it in each phase, see `ProverSession`. The standalone
+    /// proofs, e.g. `NonZeroCountProof` or `HiddenCountProof`, are given it through their
+    /// `*_with_rng` constructors.
+    pub fn prover_rng<'r>(&self, label: &'static [u8], witness: &[Scalar], rng: &'r mut dyn CryptoRngCore) -> ProverRng<'r> {
+        if !self.witness_rng {
+            return ProverRng::External(rng);
+        }
+        let transcript = self.labels.transcript(b"zkSVM witness RNG");
+        ProverRng::WitnessBound(transcript.witness_rng(label, witness, &mut &mut *rng))
+    }
+
+    /// Label of the application the configuration is for.
+    pub fn application(&self) -> &[u8] {
+        &self.application
+    }
+
+    /// Digest of the generators, the size and the transcript labels of the configuration, see
+    /// `PublicParams::fingerprint`.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        fingerprint(&self.pedersenGens, &self.G_vec, &self.H_vec, self.size, &self.labels)
+    }
+
+    pub fn labels(&self) -> &TranscriptLabels {
+        &self.labels
+    }
+
+    pub fn get_bp_gens(
+        &self
     ) -> BulletproofGens {
         BulletproofGens {
             gens_capacity: self.size,
@@ -35,4 +291,217 @@ impl PedersenConfig {
             H_vec: vec![self.H_vec.clone().B],
         }
     }
-}
\ No newline at end of file
+
+    pub fn pedersen_gens(&self) -> &PedersenGens {
+        &self.pedersenGens
+    }
+
+    /// Vector generators used for the signed commitments, which are the G bases of the
+    /// bulletproof generators.
+    pub fn G_vec(&self) -> &PedersenVecGens {
+        &self.G_vec
+    }
+
+    /// Vector generators corresponding to the H bases of the bulletproof generators.
+    pub fn H_vec(&self) -> &PedersenVecGens {
+        &self.H_vec
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns B_blinding plus the sum of the first `nr_elements` G bases.
+    pub fn acc_base_G(&self, nr_elements: usize) -> RistrettoPoint {
+        self.acc_bases_G[nr_elements]
+    }
+
+    /// Returns B_blinding plus the sum of the first `nr_elements` H bases.
+    pub fn acc_base_H(&self, nr_elements: usize) -> RistrettoPoint {
+        self.acc_bases_H[nr_elements]
+    }
+
+    /// Accumulated G bases for each of the given number of elements.
+    pub fn acc_bases_G(&self, size_sensors: &[usize]) -> Vec<RistrettoPoint> {
+        size_sensors.iter().map(|&size| self.acc_base_G(size)).collect()
+    }
+
+    /// Accumulated H bases for each of the given number of elements.
+    pub fn acc_bases_H(&self, size_sensors: &[usize]) -> Vec<RistrettoPoint> {
+        size_sensors.iter().map(|&size| self.acc_base_H(size)).collect()
+    }
+
+    /// G_vec iterated until position `nr_elements`.
+    pub fn permuted_G_vec(&self, nr_elements: usize) -> &PedersenVecGens {
+        &self.permuted_G_vecs[nr_elements - 1]
+    }
+
+    /// Iterated G_vec for each of the given number of elements.
+    pub fn permuted_G_vecs(&self, size_sensors: &[usize]) -> Vec<&PedersenVecGens> {
+        size_sensors.iter().map(|&size| self.permuted_G_vec(size)).collect()
+    }
+
+    fn prefix_sums(start: RistrettoPoint, bases: &[RistrettoPoint]) -> Vec<RistrettoPoint> {
+        let mut sums = Vec::with_capacity(bases.len() + 1);
+        sums.push(start);
+        for base in bases {
+            let last = sums[sums.len() - 1];
+            sums.push(last + base);
+        }
+        sums
+    }
+}
+
+/// Digest of the generators, the size, the application domain and the labels of the proofs.
+/// The context, the statistics and the sizes of the labels are left out, as they change from
+/// proof to proof.
+pub(crate) fn fingerprint(
+    pedersen_gens: &PedersenGens,
+    G_vec: &PedersenVecGens,
+    H_vec: &PedersenVecGens,
+    size: usize,
+    labels: &TranscriptLabels,
+) -> [u8; 32] {
+    let mut transcript = Transcript::new(b"zkSVM parameters fingerprint");
+    transcript.append_u64(b"size", size as u64);
+    transcript.append_message(b"B", pedersen_gens.B.compress().as_bytes());
+    transcript.append_message(b"B_blinding", pedersen_gens.B_blinding.compress().as_bytes());
+    for (label, gens) in [(b"G_vec", G_vec), (b"H_vec", H_vec)] {
+        transcript.append_message(label, gens.B_blinding.compress().as_bytes());
+        for base in gens.B.iter() {
+            transcript.append_message(b"base", base.compress().as_bytes());
+        }
+    }
+    transcript.append_message(b"domain", &labels.domain);
+    for label in labels.proof_labels().iter() {
+        transcript.append_message(b"label", label);
+    }
+
+    let mut fingerprint = [0u8; 32];
+    transcript.challenge_bytes(b"fingerprint", &mut fingerprint);
+    fingerprint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_chacha::ChaChaRng;
+    use rand_core::{RngCore, SeedableRng};
+
+    #[test]
+    fn accumulated_bases_match_sums() {
+        let size = 16;
+        let config = PedersenConfig::new(b"test", &None, &None, &None, size);
+        let bp_gens = config.get_bp_gens();
+
+        for nr_elements in 0..=size {
+            let mut expected_G = config.pedersen_gens().B_blinding;
+            for base in bp_gens.G_vec[0][0..nr_elements].iter() {
+                expected_G += base;
+            }
+            let mut expected_H = config.pedersen_gens().B_blinding;
+            for base in bp_gens.H_vec[0][0..nr_elements].iter() {
+                expected_H += base;
+            }
+            assert_eq!(config.acc_base_G(nr_elements), expected_G);
+            assert_eq!(config.acc_base_H(nr_elements), expected_H);
+        }
+
+        assert_eq!(
+            config.acc_bases_G(&[3, 7]),
+            vec![config.acc_base_G(3), config.acc_base_G(7)]
+        );
+    }
+
+    #[test]
+    fn prover_rng_is_keyed_by_the_witness() {
+        let config = PedersenConfig::new(b"test", &None, &None, &None, 4);
+        let draw = |config: &PedersenConfig, witness: &[Scalar]| {
+            let mut seeded = ChaChaRng::from_seed([7u8; 32]);
+            let mut bytes = [0u8; 32];
+            config.prover_rng(b"witness", witness, &mut seeded).fill_bytes(&mut bytes);
+            bytes
+        };
+        let (one, two) = ([Scalar::ONE], [Scalar::from(2u64)]);
+
+        // Without the setting, the RNG is the given one
+        let mut expected = [0u8; 32];
+        ChaChaRng::from_seed([7u8; 32]).fill_bytes(&mut expected);
+        assert_eq!(draw(&config, &one), expected);
+        assert_eq!(draw(&config, &two), expected);
+
+        let config = config.with_witness_rng();
+        assert_eq!(draw(&config, &one), draw(&config, &one));
+        assert_ne!(draw(&config, &one), draw(&config, &two));
+        assert_ne!(draw(&config, &one), expected);
+    }
+
+    #[test]
+    fn permuted_gens_match_iterate() {
+        let size = 16;
+        let config = PedersenConfig::new(b"test", &None, &None, &None, size);
+
+        for nr_elements in 1..=size {
+            assert!(*config.permuted_G_vec(nr_elements) == config.G_vec().iterate(nr_elements));
+        }
+        assert!(*config.permuted_G_vecs(&[5, 11])[1] == config.G_vec().iterate(11));
+    }
+
+    #[test]
+    fn transcript_labels_domain() {
+        let challenge = |mut transcript: Transcript| {
+            let mut bytes = [0u8; 32];
+            transcript.challenge_bytes(b"challenge", &mut bytes);
+            bytes
+        };
+        let labels = TranscriptLabels::default();
+
+        // Without a domain, the transcripts are the ones of the plain labels
+        assert_eq!(
+            challenge(labels.transcript(labels.average)),
+            challenge(Transcript::new(b"InnerProductAverage"))
+        );
+        assert_ne!(
+            challenge(TranscriptLabels::with_domain(b"app").transcript(labels.average)),
+            challenge(labels.transcript(labels.average))
+        );
+        assert_ne!(
+            challenge(TranscriptLabels::with_domain(b"app").transcript(labels.average)),
+            challenge(TranscriptLabels::with_domain(b"other app").transcript(labels.average))
+        );
+
+        // The configurations keep their application as the domain
+        let config = PedersenConfig::new(b"app", &None, &None, &None, 4)
+            .with_labels(TranscriptLabels::with_domain(b"other app"));
+        assert_eq!(config.labels().domain, b"app".to_vec());
+        assert_eq!(config.application(), b"app");
+        assert!(*config.G_vec() != *PedersenConfig::new(b"other app", &None, &None, &None, 4).G_vec());
+    }
+
+    #[test]
+    fn transcript_labels_bound_to_outer_transcript() {
+        let challenge = |mut transcript: Transcript| {
+            let mut bytes = [0u8; 32];
+            transcript.challenge_bytes(b"challenge", &mut bytes);
+            bytes
+        };
+        let labels = TranscriptLabels::default();
+        let outer = |message: &[u8]| {
+            let mut transcript = Transcript::new(b"outer protocol");
+            transcript.append_message(b"message", message);
+            transcript
+        };
+
+        let bound = labels.bind_to(&mut outer(b"hello"));
+        assert_eq!(bound, labels.bind_to(&mut outer(b"hello")));
+        assert_eq!(bound.average, labels.average);
+        assert_ne!(
+            challenge(bound.transcript(bound.average)),
+            challenge(labels.transcript(labels.average))
+        );
+        assert_ne!(
+            challenge(bound.transcript(bound.average)),
+            challenge(labels.bind_to(&mut outer(b"bye")).transcript(labels.average))
+        );
+    }
+}
@@ -0,0 +1,370 @@
+//! Extraction of the features over which the SVM model is evaluated.
+//!
+//! The statistics are computed once over integers, which gives both the plaintext features of
+//! the model and, converted to scalars, the witness of `zkSVMProver`. The integer square root
+//! of the standard deviations cannot be computed over scalars, so the integers are the
+//! reference, and the scalar helpers of `utils::misc` used within the proofs must agree with
+//! them.
+//!
+//! The integers are `i128` by default, and `BigInt` with the `bigint` feature, see
+//! `FeatureInt`.
+
+use alloc::vec::Vec;
+use alloc::vec;
+use curve25519_dalek::scalar::Scalar;
+use subtle::{Choice, ConditionallyNegatable};
+#[cfg(feature = "bigint")]
+use num_bigint::BigInt;
+
+#[cfg(feature = "bigint")]
+use crate::utils::conversion_scalar_bigint::bigInt_to_scalar;
+use ip_zk_proof::ProofError;
+use core::fmt;
+use core::iter::Sum;
+use core::ops::{Add, Mul, Sub};
+
+/// An integer type the features are computed over.
+///
+/// `i128` holds the features of readings below 2^32 in absolute value, e.g. the fixed-point
+/// readings of `zkSENSE_rust_proof`, over vectors of up to 2^16 elements. Beyond that the
+/// variances overflow, and `BigInt` is needed.
+pub trait FeatureInt:
+    Clone + PartialEq + fmt::Display + From<i64> + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Sum
+{
+    /// Integer square root. The value is not negative.
+    fn isqrt(&self) -> Self;
+
+    /// The integer as a scalar, reduced modulo the group order. Returns a `FormatError` if it
+    /// does not fit in 64 bytes.
+    fn to_scalar(&self) -> Result<Scalar, ProofError>;
+}
+
+impl FeatureInt for i128 {
+    fn isqrt(&self) -> Self {
+        i128::isqrt(*self)
+    }
+
+    fn to_scalar(&self) -> Result<Scalar, ProofError> {
+        // The sign is applied without branching on it, as the readings are secret
+        let mut scalar = Scalar::from(self.unsigned_abs());
+        scalar.conditional_negate(Choice::from((*self as u128 >> 127) as u8));
+        Ok(scalar)
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl FeatureInt for BigInt {
+    fn isqrt(&self) -> Self {
+        self.sqrt()
+    }
+
+    fn to_scalar(&self) -> Result<Scalar, ProofError> {
+        bigInt_to_scalar(self).map_err(|_| ProofError::FormatError)
+    }
+}
+
+/// The features of a set of sensor vectors, as integers.
+///
+/// The `Debug` output only shows the sizes of the vectors, as the features are the witness of
+/// the proof.
+#[derive(Clone, PartialEq)]
+pub struct SensorFeatures<T = i128> {
+    /// The sensor vectors, followed by their diff vectors with the last element set to zero,
+    /// which are the vectors over which the model is evaluated
+    pub evaluated_vectors: Vec<[Vec<T>; 3]>,
+    /// Number of readings of each evaluated vector
+    pub evaluated_sizes: Vec<usize>,
+    /// The diff vectors of the sensor vectors, i.e. the differences of adjacent readings,
+    /// including the one of the last and first readings
+    pub diff_vectors: Vec<[Vec<T>; 3]>,
+    /// Sum of the readings of each axis of each evaluated vector
+    pub additions: Vec<Vec<T>>,
+    /// \\(n^3\\) times the variance of each axis of each sensor vector, with \\(n\\) its
+    /// number of readings
+    pub variances: Vec<Vec<T>>,
+    /// Integer square root of `variances`
+    pub stds: Vec<Vec<T>>,
+}
+
+/// The features of `SensorFeatures` converted to scalars, as taken by `zkSVMProver::new`.
+///
+/// The `Debug` output only shows the sizes of the vectors, as `SensorFeatures`.
+#[derive(Clone)]
+pub struct FeatureWitness {
+    pub input_vector: Vec<[Vec<Scalar>; 3]>,
+    pub non_zero_elements: Vec<usize>,
+    pub diff_vectors: Vec<[Vec<Scalar>; 3]>,
+    pub additions: Vec<Vec<Scalar>>,
+    pub variances: Vec<Vec<Scalar>>,
+    pub stds: Vec<Vec<Scalar>>,
+}
+
+impl<T> fmt::Debug for SensorFeatures<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SensorFeatures")
+            .field("evaluated_sizes", &self.evaluated_sizes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for FeatureWitness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FeatureWitness")
+            .field("non_zero_elements", &self.non_zero_elements)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: FeatureInt> SensorFeatures<T> {
+    /// Computes the features of `input_vector`, where sensor vector `i` has
+    /// `non_zero_elements[i]` readings followed by zeros.
+    pub fn extract(input_vector: &[[Vec<T>; 3]], non_zero_elements: &[usize]) -> SensorFeatures<T> {
+        let diff_vectors = diff_computation(input_vector, non_zero_elements);
+
+        let mut evaluated_vectors: Vec<[Vec<T>; 3]> = input_vector.to_vec();
+        for (mut diff_vector, &non_zero_nr) in diff_vectors.clone().into_iter().zip(non_zero_elements.iter()) {
+            for coord in diff_vector.iter_mut() {
+                coord[non_zero_nr - 1] = T::from(0);
+            }
+            evaluated_vectors.push(diff_vector);
+        }
+
+        let mut evaluated_sizes: Vec<usize> = non_zero_elements.to_vec();
+        evaluated_sizes.extend(non_zero_elements.iter().map(|x| x - 1));
+
+        let additions = additions_vector(&evaluated_vectors);
+        let subtracted_values = subtractions_vector(non_zero_elements, input_vector, &additions);
+        let variances = variance_factor(&subtracted_values);
+        let stds = stds_factor(&variances);
+
+        SensorFeatures {
+            evaluated_vectors,
+            evaluated_sizes,
+            diff_vectors,
+            additions,
+            variances,
+            stds,
+        }
+    }
+
+    /// As `extract`, with each axis of `input_vector` first padded with zeros to `size`
+    /// elements, the size of the public parameters. The proofs then have the same size and
+    /// use the same generators whatever the number of readings of each window, although the
+    /// numbers themselves are public, see `ProofHeader`; `HiddenCountProof` hides them. Returns an
+    /// `InvalidGeneratorsLength` if an axis is longer than `size`, or has less elements than
+    /// the readings of its vector.
+    pub fn extract_padded(
+        input_vector: &[[Vec<T>; 3]],
+        non_zero_elements: &[usize],
+        size: usize,
+    ) -> Result<SensorFeatures<T>, ProofError> {
+        if input_vector.len() != non_zero_elements.len() {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        let mut padded = input_vector.to_vec();
+        for (vector, &non_zero_nr) in padded.iter_mut().zip(non_zero_elements.iter()) {
+            for coord in vector.iter_mut() {
+                if coord.len() > size || coord.len() < non_zero_nr {
+                    return Err(ProofError::InvalidGeneratorsLength);
+                }
+                coord.resize(size, T::from(0));
+            }
+        }
+        Ok(SensorFeatures::extract(&padded, non_zero_elements))
+    }
+
+    /// Converts the features to scalars. Returns a `FormatError` if an integer does not fit
+    /// in 64 bytes.
+    pub fn witness(&self) -> Result<FeatureWitness, ProofError> {
+        Ok(FeatureWitness {
+            input_vector: self.evaluated_vectors.iter().map(coords_to_scalars).collect::<Result<_, _>>()?,
+            non_zero_elements: self.evaluated_sizes.clone(),
+            diff_vectors: self.diff_vectors.iter().map(coords_to_scalars).collect::<Result<_, _>>()?,
+            additions: self.additions.iter().map(|x| to_scalars(x)).collect::<Result<_, _>>()?,
+            variances: self.variances.iter().map(|x| to_scalars(x)).collect::<Result<_, _>>()?,
+            stds: self.stds.iter().map(|x| to_scalars(x)).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+fn to_scalars<T: FeatureInt>(input: &[T]) -> Result<Vec<Scalar>, ProofError> {
+    input.iter().map(|x| x.to_scalar()).collect()
+}
+
+fn coords_to_scalars<T: FeatureInt>(coords: &[Vec<T>; 3]) -> Result<[Vec<Scalar>; 3], ProofError> {
+    Ok([to_scalars(&coords[0])?, to_scalars(&coords[1])?, to_scalars(&coords[2])?])
+}
+
+/// We use this subtraction vector to calculate what we will use as the variance.
+/// We need to multiply by the size, because we subtract the addition, and not the average.
+/// in this way, the result will not be the variance, but n**3 * variance.
+fn subtractions_vector<T: FeatureInt>(
+    non_zero_elements: &[usize],
+    input_vector: &[[Vec<T>; 3]],
+    additions: &[Vec<T>]
+) -> Vec<Vec<Vec<T>>> {
+    let length = input_vector.len();
+    let mut subtractions_vector = vec![Vec::new(); length];
+    for i in 0..length {
+        for j in 0..3 {
+            let mut value_vector: Vec<T> = vec![T::from(0); input_vector[i][j].len()];
+            for (index, value) in input_vector[i][j][0..non_zero_elements[i]].iter().enumerate() {
+                value_vector[index] = T::from(non_zero_elements[i] as i64) * value.clone() - additions[i][j].clone();
+            }
+            subtractions_vector[i].push(value_vector);
+        }
+    }
+    subtractions_vector
+}
+
+/// Computes the addition of all inputed vectors
+fn additions_vector<T: FeatureInt>(
+    input_vector: &[[Vec<T>; 3]]
+) -> Vec<Vec<T>> {
+    input_vector.iter()
+        .map(|vector| vector.iter().map(|x| x.iter().cloned().sum()).collect())
+        .collect()
+}
+
+// Computes the difference of all adjacent values of a vector. Does so for all inputed vectors.
+fn diff_computation<T: FeatureInt>(
+    input_vector: &[[Vec<T>; 3]],
+    non_zero_elements: &[usize],
+) -> Vec<[Vec<T>; 3]> {
+    input_vector.iter().zip(non_zero_elements.iter())
+        .map(|(vector, &non_zero_nr)| [
+            one_dimension_diff_computation(&vector[0], non_zero_nr),
+            one_dimension_diff_computation(&vector[1], non_zero_nr),
+            one_dimension_diff_computation(&vector[2], non_zero_nr),
+        ])
+        .collect()
+}
+
+// Computes the difference of adjacent values for a single vector
+fn one_dimension_diff_computation<T: FeatureInt>(
+    coord_vector: &[T],
+    nmbr_non_zero_elements: usize
+) -> Vec<T> {
+    let mut diff_vector: Vec<T> = coord_vector.to_vec();
+    for i in 0..(nmbr_non_zero_elements - 1) {
+        diff_vector[i] = coord_vector[i].clone() - coord_vector[i + 1].clone();
+    }
+    diff_vector[nmbr_non_zero_elements - 1] = coord_vector[nmbr_non_zero_elements - 1].clone() - coord_vector[0].clone();
+    diff_vector
+}
+
+// Computes a factor of the variance, mainly Y^3 times the variance, where Y is the number of
+// non-zero entries in each vector.
+fn variance_factor<T: FeatureInt>(
+    subtracted_values: &[Vec<Vec<T>>],
+) -> Vec<Vec<T>> {
+    subtracted_values.iter().map(
+        |x| x.iter().map(
+            |subtracted_vector| subtracted_vector.iter().map(|v| v.clone() * v.clone()).sum()
+        ).collect()
+    ).collect()
+}
+
+/// Instead of calculating the standard deviation, we calculate a factor of it. Namely, the square
+/// root of the factor of the variance above.
+fn stds_factor<T: FeatureInt>(
+    variances: &[Vec<T>],
+) -> Vec<Vec<T>> {
+    variances.iter()
+        .map(|vectors| vectors.iter()
+            .map(|variance| variance.isqrt())
+            .collect()
+        ).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebraic_proofs::average_proof::AvgProof;
+    use crate::utils::misc::{compute_subtraction_vector, diff_computation as diff_computation_scalar};
+
+    fn sensor_vectors<T: FeatureInt>() -> (Vec<[Vec<T>; 3]>, Vec<usize>) {
+        let coord = |values: &[i64]| -> Vec<T> {
+            let mut vector: Vec<T> = values.iter().map(|&x| T::from(x)).collect();
+            vector.resize(8, T::from(0));
+            vector
+        };
+        (
+            vec![
+                [coord(&[5, 3, 8, 1]), coord(&[2, 2, 2, 2]), coord(&[9, 0, 4, 7])],
+                [coord(&[1, 6, 3, 9, 4, 2]), coord(&[7, 7, 1, 3, 5, 8]), coord(&[4, 4, 4, 0, 1, 6])],
+            ],
+            vec![4, 6],
+        )
+    }
+
+    #[test]
+    fn features_of_small_vectors() {
+        let (input, sizes) = sensor_vectors::<i128>();
+        let features = SensorFeatures::extract(&input, &sizes);
+
+        assert_eq!(features.evaluated_sizes, vec![4, 6, 3, 5]);
+        assert_eq!(features.diff_vectors[0][0][..4], [2, -5, 7, -4]);
+        assert_eq!(features.evaluated_vectors[2][0][3], 0);
+        assert_eq!(features.additions[0][0], 17);
+        assert_eq!(format!("{:?}", features), "SensorFeatures { evaluated_sizes: [4, 6, 3, 5], .. }");
+
+        // 4 * x - 17 for x in (5, 3, 8, 1)
+        assert_eq!(features.variances[0][0], 9 + 25 + 225 + 169);
+        assert_eq!(features.stds[0][0], 20);
+        assert_eq!(features.variances[0][1], 0);
+        for (stds, variances) in features.stds.iter().zip(features.variances.iter()) {
+            for (std, variance) in stds.iter().zip(variances.iter()) {
+                assert!(std * std <= *variance && *variance < (std + 1) * (std + 1));
+            }
+        }
+    }
+
+    #[test]
+    fn witness_matches_scalar_helpers() {
+        let (input, sizes) = sensor_vectors::<i128>();
+        let witness = SensorFeatures::extract(&input, &sizes).witness().unwrap();
+        let scalar_input = witness.input_vector[..2].to_vec();
+
+        assert_eq!(witness.diff_vectors, diff_computation_scalar(&scalar_input, &sizes));
+        assert_eq!(witness.additions, AvgProof::compute_sensors_addition(&witness.input_vector));
+
+        let subtractions = compute_subtraction_vector(&sizes, &scalar_input, &witness.additions);
+        let variances: Vec<Vec<Scalar>> = subtractions.iter()
+            .map(|x| x.iter().map(|v| v.iter().map(|s| s * s).sum()).collect())
+            .collect();
+        assert_eq!(witness.variances, variances);
+    }
+
+    #[test]
+    fn padded_features() {
+        let (input, sizes) = sensor_vectors::<i128>();
+        let truncated: Vec<[Vec<i128>; 3]> = input.iter()
+            .zip(sizes.iter())
+            .map(|(vector, &n)| [vector[0][..n].to_vec(), vector[1][..n].to_vec(), vector[2][..n].to_vec()])
+            .collect();
+
+        let padded = SensorFeatures::extract_padded(&truncated, &sizes, 8).unwrap();
+        assert!(padded == SensorFeatures::extract(&input, &sizes));
+        assert_eq!(padded.evaluated_vectors[3][1].len(), 8);
+
+        assert!(SensorFeatures::extract_padded(&input, &sizes, 4).is_err());
+        assert!(SensorFeatures::extract_padded(&truncated, &[4, 7], 8).is_err());
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn bigint_features_match() {
+        let (input, sizes) = sensor_vectors::<i128>();
+        let (big_input, _) = sensor_vectors::<BigInt>();
+        let features = SensorFeatures::extract(&input, &sizes);
+        let big_features = SensorFeatures::extract(&big_input, &sizes);
+
+        assert_eq!(big_features.stds[1][2].to_string(), features.stds[1][2].to_string());
+        let (witness, big_witness) = (features.witness().unwrap(), big_features.witness().unwrap());
+        assert_eq!(witness.input_vector, big_witness.input_vector);
+        assert_eq!(witness.diff_vectors, big_witness.diff_vectors);
+        assert_eq!(witness.variances, big_witness.variances);
+        assert_eq!(witness.stds, big_witness.stds);
+    }
+}
@@ -1,12 +1,16 @@
 #![allow(non_snake_case)]
+use alloc::vec::Vec;
+use alloc::vec;
 use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
-use curve25519_dalek::traits::MultiscalarMul;
+use curve25519_dalek::traits::{Identity, MultiscalarMul};
 
-use ip_zk_proof::PedersenGens;
+use ip_zk_proof::{BulletproofGens, PedersenGens, ProofError};
 
 use core::iter;
+use core::ops::Range;
 use sha3::Sha3_512;
+use rand_core::{CryptoRng, RngCore};
 
 use curve25519_dalek::constants::RISTRETTO_BASEPOINT_COMPRESSED;
 use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
@@ -21,7 +25,7 @@ use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
 ///
 /// * `B`: the `ristretto255` basepoint;
 /// * `B_blinding`: the result of `ristretto255` SHA3-512
-/// hash-to-group on input `B_bytes`.
+///   hash-to-group on input `B_bytes`.
 
 #[derive(Clone, Debug)]
 pub struct PedersenVecGens {
@@ -35,13 +39,53 @@ pub struct PedersenVecGens {
 
 impl PedersenVecGens {
     /// Creates a Pedersen commitment using the value scalar and a blinding factor.
-    pub fn commit(&self, values: &Vec<Scalar>, blinding: Scalar) -> RistrettoPoint {
+    pub fn commit(&self, values: &[Scalar], blinding: Scalar) -> RistrettoPoint {
+        let _span = msm_span!("commit", size = values.len() + 1);
         RistrettoPoint::multiscalar_mul(
             iter::once(&blinding).chain(values.iter()),
             iter::once(&self.B_blinding).chain(self.B.iter()),
         )
     }
 
+    /// Creates a Pedersen commitment to the vector with `values` at the positions `indices`,
+    /// and zeros elsewhere, with a multiscalar multiplication over the bases at `indices` only.
+    /// Returns `None` if `indices` and `values` have different lengths, or if `indices` is not
+    /// strictly increasing or out of range. See `SparseZKProof` to prove that the other
+    /// positions are zero.
+    pub fn commit_sparse(&self, indices: &[usize], values: &[Scalar], blinding: Scalar) -> Option<RistrettoPoint> {
+        if indices.len() != values.len()
+            || indices.windows(2).any(|w| w[0] >= w[1])
+            || indices.last().is_some_and(|&i| i >= self.B.len())
+        {
+            return None;
+        }
+        Some(RistrettoPoint::multiscalar_mul(
+            iter::once(&blinding).chain(values.iter()),
+            iter::once(&self.B_blinding).chain(indices.iter().map(|&i| &self.B[i])),
+        ))
+    }
+
+    /// Checks that the basis has `size` bases, and that they and the blinding base are all
+    /// distinct and not the identity. Returns an `InvalidGenerators` error if not.
+    pub fn validate(&self) -> Result<(), ProofError> {
+        if self.B.len() != self.size {
+            return Err(ProofError::InvalidGenerators);
+        }
+        let identity = RistrettoPoint::identity();
+        let mut encodings: Vec<[u8; 32]> = Vec::with_capacity(self.size + 1);
+        for base in iter::once(&self.B_blinding).chain(self.B.iter()) {
+            if *base == identity {
+                return Err(ProofError::InvalidGenerators);
+            }
+            encodings.push(base.compress().to_bytes());
+        }
+        encodings.sort_unstable();
+        if encodings.windows(2).any(|w| w[0] == w[1]) {
+            return Err(ProofError::InvalidGenerators);
+        }
+        Ok(())
+    }
+
     pub fn new(size: usize) -> PedersenVecGens {
         let mut generators: Vec<RistrettoPoint> = vec![RISTRETTO_BASEPOINT_POINT];
         for i in 0..(size - 1) {
@@ -58,16 +102,20 @@ impl PedersenVecGens {
         }
     }
 
+    #[cfg(feature = "std")]
     pub fn new_random(size: usize) -> PedersenVecGens {
-        let mut rng = rand::thread_rng();
+        PedersenVecGens::new_random_with_rng(size, &mut rand::thread_rng())
+    }
 
+    /// As `new_random`, with the bases drawn from the given RNG.
+    pub fn new_random_with_rng<T: RngCore + CryptoRng>(size: usize, rng: &mut T) -> PedersenVecGens {
         let mut generators: Vec<RistrettoPoint> =
             vec![RistrettoPoint::hash_from_bytes::<Sha3_512>(
-                &Scalar::random(&mut rng).to_bytes(),
+                &Scalar::random(rng).to_bytes(),
             )];
         for _ in 0..(size - 1) {
             generators.push(RistrettoPoint::hash_from_bytes::<Sha3_512>(
-                &Scalar::random(&mut rng).to_bytes(),
+                &Scalar::random(rng).to_bytes(),
             ));
         }
         PedersenVecGens {
@@ -79,14 +127,73 @@ impl PedersenVecGens {
         }
     }
 
+    /// Creates `size` bases derived from `seed`, so that anyone can recompute them, with the
+    /// same blinding base as `new`.
+    pub fn from_seed(seed: &[u8], size: usize) -> PedersenVecGens {
+        let generators: Vec<RistrettoPoint> = (0..size)
+            .map(|i| RistrettoPoint::hash_from_bytes::<Sha3_512>(
+                &[seed, &(i as u64).to_be_bytes()].concat(),
+            ))
+            .collect();
+        PedersenVecGens {
+            size,
+            B: generators,
+            B_blinding: RistrettoPoint::hash_from_bytes::<Sha3_512>(
+                RISTRETTO_BASEPOINT_COMPRESSED.as_bytes(),
+            ),
+        }
+    }
+
+    /// Creates `size` bases derived from the label of an application and a `seed`, as
+    /// `from_seed`. The label is prefixed with its length, so that two applications never
+    /// share bases, whatever their seeds.
+    pub fn from_application(application: &[u8], seed: &[u8], size: usize) -> PedersenVecGens {
+        PedersenVecGens::from_seed(&[&(application.len() as u64).to_be_bytes(), application, seed].concat(), size)
+    }
+
+    /// Grows the basis to `new_size` bases, keeping the existing ones. Does nothing if the
+    /// basis already has `new_size` bases or more.
+    ///
+    /// The new bases are hashed from the first base (or the blinding base of an empty basis)
+    /// and their position, so extending is deterministic: extending in several steps gives
+    /// the same bases as extending at once, and the bases added to different sets are
+    /// independent. Note that `new(n).extend_to(m)` are not the bases of `new(m)`, so a prover
+    /// and a verifier must resize their generators in the same way.
+    pub fn extend_to(&mut self, new_size: usize) {
+        let anchor = self.B.first().unwrap_or(&self.B_blinding).compress();
+        for i in self.B.len()..new_size {
+            self.B.push(RistrettoPoint::hash_from_bytes::<Sha3_512>(
+                &[b"PedersenVecGens extension".as_ref(), anchor.as_bytes(), &(i as u64).to_be_bytes()].concat(),
+            ));
+        }
+        self.size = self.B.len();
+    }
+
+    /// Shrinks the basis to its first `new_size` bases. Does nothing if the basis has
+    /// `new_size` bases or less.
+    pub fn truncate(&mut self, new_size: usize) {
+        self.B.truncate(new_size);
+        self.size = self.B.len();
+    }
+
+    /// Generators of the contiguous sub-vector at `range`, i.e. the bases at `range` with the
+    /// same blinding base. Returns `None` if the range is out of bounds. See `SliceZKProof` to
+    /// relate a commitment under the slice to one under `self`.
+    pub fn slice(&self, range: Range<usize>) -> Option<PedersenVecGens> {
+        let bases = self.B.get(range)?.to_vec();
+        Some(PedersenVecGens {
+            size: bases.len(),
+            B: bases,
+            B_blinding: self.B_blinding,
+        })
+    }
+
     /// Iter the generators until 'position' by one position to the left
     /// This is used to prove statements about the 'diff' values in zkSENSE
     pub fn iterate(&self, position: usize) -> PedersenVecGens {
         let mut new_B = self.B.clone();
         new_B[0] = new_B[position - 1];
-        for i in 1..position {
-            new_B[i] = self.B[i - 1]
-        }
+        new_B[1..position].copy_from_slice(&self.B[..position - 1]);
 
         PedersenVecGens {
             size: self.size,
@@ -95,17 +202,74 @@ impl PedersenVecGens {
         }
     }
 
-    /// Remove base in positions given by values in input vector
+    /// Bulletproof generators with the bases of `self` as G and the bases of `rhs_gens`
+    /// as H, so that a commitment with each of them can be used directly in
+    /// `InnerProductZKProof::prove_with_vector_commitments`. Returns `None` if the number
+    /// of bases or the blinding bases differ.
+    pub fn bulletproof_gens(&self, rhs_gens: &PedersenVecGens) -> Option<BulletproofGens> {
+        if self.B.len() != rhs_gens.B.len() || self.B_blinding != rhs_gens.B_blinding {
+            return None;
+        }
+        Some(BulletproofGens {
+            gens_capacity: self.B.len(),
+            party_capacity: 1,
+            G_vec: vec![self.B.clone()],
+            H_vec: vec![rhs_gens.B.clone()],
+        })
+    }
+
+    /// Remove base in positions given by values in input vector. As `remove_bases`, panicking
+    /// if a position is out of range.
     pub fn remove_base(&self, position: &[usize]) -> PedersenVecGens {
-        let mut new_B = self.B.clone();
-        for i in position {
-            new_B.remove(*i);
+        self.remove_bases(position)
+            .expect("Positions of the removed bases out of range.")
+    }
+
+    /// Removes the bases at `positions`, which are positions in `self` and may be given in any
+    /// order. Returns `None` if a position is out of range.
+    ///
+    /// A commitment under `self` with zeros at `positions` is a commitment under the result to
+    /// the opening restricted with `restrict_opening`.
+    pub fn remove_bases(&self, positions: &[usize]) -> Option<PedersenVecGens> {
+        let kept = self.kept_positions(positions)?;
+        self.keep_bases(&kept)
+    }
+
+    /// Keeps only the bases at `positions`, in increasing order of position and ignoring
+    /// duplicates. Returns `None` if a position is out of range.
+    pub fn keep_bases(&self, positions: &[usize]) -> Option<PedersenVecGens> {
+        if positions.iter().any(|&i| i >= self.B.len()) {
+            return None;
         }
-        PedersenVecGens {
-            size: self.size,
-            B: new_B,
+        let mut positions = positions.to_vec();
+        positions.sort_unstable();
+        positions.dedup();
+
+        Some(PedersenVecGens {
+            size: positions.len(),
+            B: positions.iter().map(|&i| self.B[i]).collect(),
             B_blinding: self.B_blinding,
+        })
+    }
+
+    /// Positions in `self` of the bases left after removing the ones at `removed`, i.e. base
+    /// `i` of `remove_bases(removed)` is base `kept_positions(removed)[i]` of `self`. Returns
+    /// `None` if a position is out of range.
+    pub fn kept_positions(&self, removed: &[usize]) -> Option<Vec<usize>> {
+        if removed.iter().any(|&i| i >= self.B.len()) {
+            return None;
         }
+        Some((0..self.B.len()).filter(|i| !removed.contains(i)).collect())
+    }
+
+    /// Restricts an opening under some generators to the elements at `positions` (as given by
+    /// `kept_positions`), to obtain the opening under the generators `keep_bases(positions)`.
+    /// Returns `None` if a position is out of range of the opening.
+    pub fn restrict_opening(opening: &[Scalar], positions: &[usize]) -> Option<Vec<Scalar>> {
+        let mut positions = positions.to_vec();
+        positions.sort_unstable();
+        positions.dedup();
+        positions.iter().map(|&i| opening.get(i).cloned()).collect()
     }
 }
 
@@ -140,6 +304,8 @@ impl PartialEq for PedersenVecGens {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ip_zk_proof::{inner_product, InnerProductZKProof};
+    use merlin::Transcript;
     use rand::thread_rng;
 
     #[test]
@@ -156,6 +322,121 @@ mod tests {
         assert_eq!(iter_gens, part2_iter_gens);
     }
 
+    #[test]
+    fn test_extend_and_truncate() {
+        let ped_gens = PedersenVecGens::new(8);
+
+        let mut extended = ped_gens.clone();
+        extended.extend_to(16);
+        assert_eq!(extended.size, 16);
+        assert_eq!(extended.B[..8], ped_gens.B[..]);
+
+        let mut extended_twice = ped_gens.clone();
+        extended_twice.extend_to(12);
+        extended_twice.extend_to(16);
+        assert_eq!(extended_twice, extended);
+
+        // Bases added to other sets are independent
+        let mut seeded = PedersenVecGens::from_seed(b"seed", 8);
+        seeded.extend_to(16);
+        assert!(seeded.B[8..].iter().all(|base| !extended.B.contains(base)));
+
+        let mut truncated = extended.clone();
+        truncated.truncate(8);
+        assert_eq!(truncated.size, 8);
+        assert_eq!(truncated, ped_gens);
+
+        // Resizing to a smaller or larger size respectively does nothing
+        truncated.extend_to(4);
+        truncated.truncate(10);
+        assert_eq!(truncated, ped_gens);
+    }
+
+    #[test]
+    fn test_remove_and_keep_bases() {
+        let ped_gens = PedersenVecGens::new_random(8);
+        let opening: Vec<Scalar> = (0..8).map(|_| Scalar::random(&mut thread_rng())).collect();
+        let blinding = Scalar::random(&mut thread_rng());
+
+        // The order of the positions does not matter
+        let removed = ped_gens.remove_bases(&[6, 1, 3]).unwrap();
+        assert_eq!(removed, ped_gens.remove_bases(&[1, 3, 6]).unwrap());
+        assert_eq!(removed.size, 5);
+        assert_eq!(removed.B, vec![ped_gens.B[0], ped_gens.B[2], ped_gens.B[4], ped_gens.B[5], ped_gens.B[7]]);
+
+        let kept = ped_gens.kept_positions(&[6, 1, 3]).unwrap();
+        assert_eq!(kept, vec![0, 2, 4, 5, 7]);
+        assert_eq!(ped_gens.keep_bases(&kept).unwrap(), removed);
+
+        // A commitment with zeros at the removed positions is a commitment under the new bases
+        let mut zeroed = opening.clone();
+        for i in [1, 3, 6].iter() {
+            zeroed[*i] = Scalar::ZERO;
+        }
+        let restricted = PedersenVecGens::restrict_opening(&opening, &kept).unwrap();
+        assert_eq!(ped_gens.commit(&zeroed, blinding), removed.commit(&restricted, blinding));
+
+        assert!(ped_gens.remove_bases(&[8]).is_none());
+        assert!(ped_gens.keep_bases(&[0, 9]).is_none());
+        assert!(PedersenVecGens::restrict_opening(&opening[..4], &kept).is_none());
+
+        let mut truncated = ped_gens.clone();
+        truncated.truncate(7);
+        assert_eq!(ped_gens.remove_base(&[7]), truncated);
+        assert_eq!(ped_gens.remove_base(&[7]).size, 7);
+    }
+
+    #[test]
+    fn test_validate() {
+        let ped_gens = PedersenVecGens::new(8);
+        assert!(ped_gens.validate().is_ok());
+
+        let mut repeated = ped_gens.clone();
+        repeated.B[5] = repeated.B[2];
+        assert_eq!(repeated.validate(), Err(ProofError::InvalidGenerators));
+
+        let mut blinding = ped_gens.clone();
+        blinding.B[3] = blinding.B_blinding;
+        assert_eq!(blinding.validate(), Err(ProofError::InvalidGenerators));
+
+        let mut identity = ped_gens.clone();
+        identity.B[0] = RistrettoPoint::identity();
+        assert_eq!(identity.validate(), Err(ProofError::InvalidGenerators));
+
+        let mut wrong_size = ped_gens;
+        wrong_size.size = 9;
+        assert_eq!(wrong_size.validate(), Err(ProofError::InvalidGenerators));
+    }
+
+    #[test]
+    fn test_commit_sparse() {
+        let ped_gens = PedersenVecGens::new(8);
+        let blinding = Scalar::random(&mut thread_rng());
+        let mut dense = vec![Scalar::ZERO; 8];
+        dense[1] = Scalar::from(4u64);
+        dense[6] = Scalar::from(9u64);
+
+        assert_eq!(
+            ped_gens.commit_sparse(&[1, 6], &[dense[1], dense[6]], blinding),
+            Some(ped_gens.commit(&dense, blinding))
+        );
+        assert_eq!(ped_gens.commit_sparse(&[], &[], blinding), Some(blinding * ped_gens.B_blinding));
+        assert!(ped_gens.commit_sparse(&[6, 1], &[dense[6], dense[1]], blinding).is_none());
+        assert!(ped_gens.commit_sparse(&[1, 1], &[dense[1], dense[1]], blinding).is_none());
+        assert!(ped_gens.commit_sparse(&[1, 8], &[dense[1], dense[6]], blinding).is_none());
+        assert!(ped_gens.commit_sparse(&[1], &[dense[1], dense[6]], blinding).is_none());
+    }
+
+    #[test]
+    fn test_slice() {
+        let ped_gens = PedersenVecGens::new(8);
+        let sliced = ped_gens.slice(2..5).unwrap();
+        assert_eq!(sliced.size, 3);
+        assert_eq!(sliced, ped_gens.keep_bases(&[2, 3, 4]).unwrap());
+        assert_eq!(ped_gens.slice(0..8).unwrap(), ped_gens);
+        assert!(ped_gens.slice(6..9).is_none());
+    }
+
     #[test]
     fn test_from_pedersen_generators() {
         let ped_gens = PedersenGens::default();
@@ -165,8 +446,56 @@ mod tests {
         let ped_vec_gens = PedersenVecGens::from(ped_gens);
 
         let comm_single = ped_gens.commit(opening, blinding);
-        let comm_vec = ped_vec_gens.commit(&vec![opening], blinding);
+        let comm_vec = ped_vec_gens.commit(&[opening], blinding);
 
         assert_eq!(comm_single, comm_vec);
     }
+
+    #[test]
+    fn test_ip_proof_over_vector_commitments() {
+        let size = 8;
+        let mut rng = thread_rng();
+        let lhs_gens = PedersenVecGens::new(size);
+        let rhs_gens = PedersenVecGens::new_random(size);
+        let ped_gens = PedersenGens {
+            B: RistrettoPoint::hash_from_bytes::<Sha3_512>(b"value base"),
+            B_blinding: lhs_gens.B_blinding,
+        };
+        let bp_gens = lhs_gens.bulletproof_gens(&rhs_gens).unwrap();
+
+        let lhs: Vec<Scalar> = (0..size).map(|_| Scalar::random(&mut rng)).collect();
+        let rhs: Vec<Scalar> = (0..size).map(|_| Scalar::random(&mut rng)).collect();
+        let (lhs_blinding, rhs_blinding) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+        let lhs_commitment = lhs_gens.commit(&lhs, lhs_blinding).compress();
+        let rhs_commitment = rhs_gens.commit(&rhs, rhs_blinding).compress();
+
+        let mut transcript = Transcript::new(b"test");
+        let (proof, value_commitment) = InnerProductZKProof::prove_with_vector_commitments(
+            &bp_gens,
+            &ped_gens,
+            &mut transcript,
+            inner_product(&lhs, &rhs),
+            &lhs,
+            &rhs,
+            Scalar::random(&mut rng),
+            lhs_blinding,
+            rhs_blinding,
+            size,
+            &mut rng,
+        ).unwrap();
+
+        let mut transcript = Transcript::new(b"test");
+        assert!(proof.verify_with_vector_commitments(
+            &bp_gens,
+            &ped_gens,
+            &mut transcript,
+            &value_commitment,
+            &lhs_commitment,
+            &rhs_commitment,
+            size,
+            &mut rng,
+        ).is_ok());
+
+        assert!(lhs_gens.bulletproof_gens(&PedersenVecGens::new(size - 1)).is_none());
+    }
 }
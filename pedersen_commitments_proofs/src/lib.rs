@@ -1,17 +1,87 @@
+//! Proofs that an SVM model is evaluated over the statistics of sensor vectors committed by a
+//! trusted module, see `zkSVMProver`.
+//!
+//! As those of `ip_zk_proof`, the proofs are ordered and hashed by their `to_bytes` encoding.
+//!
+//! Without the `std` feature the crate is `no_std + alloc`, and the proofs are created and
+//! verified with the `*_with_rng` functions, from an RNG given by the caller.
+
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(non_snake_case)]
 #![allow(non_camel_case_types)]
-#[macro_use]
-extern crate zkp;
-extern crate rand;
+// The proofs take each of their generators, commitments and blinding factors as a separate
+// argument, as those of `ip_zk_proof` do.
+#![allow(clippy::too_many_arguments)]
+extern crate alloc;
+
+/// Enters an `info` span named after a proving or verification phase, closed at the end of
+/// the enclosing scope. Does nothing without the `tracing` feature.
+#[cfg(feature = "tracing")]
+macro_rules! phase_span {
+    ($name:literal $(, $($field:tt)+)?) => {
+        tracing::info_span!($name $(, $($field)+)?).entered()
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! phase_span {
+    ($name:literal $(, $($field:tt)+)?) => {
+        ()
+    };
+}
+
+/// Enters a `trace` span named `msm` around the multiscalar multiplication labelled `check`,
+/// as `ip_zk_proof` does for its own. Does nothing without the `tracing` feature.
+#[cfg(feature = "tracing")]
+macro_rules! msm_span {
+    ($check:literal $(, $($field:tt)+)?) => {
+        tracing::trace_span!("msm", check = $check $(, $($field)+)?).entered()
+    };
+}
 
-mod transcript;
+#[cfg(not(feature = "tracing"))]
+macro_rules! msm_span {
+    ($check:literal $(, $($field:tt)+)?) => {
+        ()
+    };
+}
 
 pub(crate) mod generators;
+pub mod config;
+pub mod params;
+pub mod features;
+pub mod statistics;
+pub mod statement_graph;
+pub mod window;
+pub mod metrics;
+pub mod sigma;
 pub mod algebraic_proofs;
 pub mod svm_proof;
 pub mod boolean_proofs;
 pub mod utils;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
+#[cfg(feature = "server")]
+pub mod server;
 
 pub use crate::generators::PedersenVecGens;
-pub use crate::svm_proof::adhoc_proof::zkSVMProver;
+pub use crate::config::{PedersenConfig, TranscriptLabels};
+pub use crate::params::PublicParams;
+pub use crate::features::{FeatureInt, FeatureWitness, SensorFeatures};
+pub use crate::window::SensorWindow;
+pub use crate::statistics::{Statistic, StatisticSet};
+pub use crate::statement_graph::StatementGraph;
+pub use crate::metrics::{NoMetrics, PhaseMetrics, ProofPhase, ProverMetrics};
+pub use crate::svm_proof::adhoc_proof::{zkSVMProver, ProofHeader};
+#[cfg(feature = "std")]
+pub use crate::svm_proof::aggregation::{AggregatedProof, Coordinator, SensorHub};
+pub use crate::svm_proof::builder::zkSVMProverBuilder;
+pub use crate::svm_proof::decision_proof::{ClassificationProof, RevealedClassificationProof, SvmModel};
+#[cfg(feature = "std")]
+pub use crate::svm_proof::kernel_proof::{KernelProof, KernelSvmModel};
+#[cfg(feature = "std")]
+pub use crate::svm_proof::multi_window::MultiWindowProof;
+pub use crate::svm_proof::session::ProverSession;
 
@@ -0,0 +1,247 @@
+//! Telemetry of the zkSVM prover.
+//!
+//! The prover reports each phase of the proof to a `ProverMetrics` as it finishes: how long
+//! it took, how many commitments and sub-proofs it added to the proof, and how many bytes
+//! these take in the encoding of `zkSVMProver::to_bytes`. See `zkSVMProver::new_with_metrics`
+//! and the `create_with_metrics` functions of the composite proofs.
+//!
+//! The durations are read from the `Clock` of the metrics. `SystemClock` reads
+//! `std::time::Instant`, which panics on targets without a system clock, such as
+//! `wasm32-unknown-unknown`. There the metrics are given their own clock with `WithClock`.
+//! The provers without metrics use `NoMetrics`, which never reads a clock. Without the `std`
+//! feature, there is no `SystemClock` and the metrics default to `NoClock`.
+
+use alloc::vec::Vec;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+/// A phase of the creation of a zkSVM proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ProofPhase {
+    /// Commitments to the sensor vectors, computed by the trusted module.
+    Commitment,
+    /// Iterated commitments and removal proofs of the diff vectors, see `DiffProofs`.
+    DiffProofs,
+    /// Sums of the vectors, see `AvgProof`.
+    AvgProof,
+    /// Variances of the vectors, see `VarianceProof`, without its standard deviations.
+    VarianceProof,
+    /// Standard deviations, see `StdProof::create_all`.
+    StdProofs,
+}
+
+/// Measurements of a phase of the proof.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PhaseMetrics {
+    /// Time spent in the phase.
+    pub duration: Duration,
+    /// Number of commitments the phase adds to the proof.
+    pub commitments: usize,
+    /// Number of sub-proofs the phase adds to the proof, counting a batched proof once.
+    pub proofs: usize,
+    /// Size in bytes of what the phase adds to the proof.
+    pub proof_size: usize,
+}
+
+/// A monotonic clock, from which the durations of the phases are measured.
+pub trait Clock {
+    /// Time elapsed since an arbitrary, fixed origin.
+    fn now(&self) -> Duration;
+}
+
+impl<F: Fn() -> Duration> Clock for F {
+    fn now(&self) -> Duration {
+        self()
+    }
+}
+
+/// The system clock, measured from the first time it is read in the process.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        static ORIGIN: OnceLock<Instant> = OnceLock::new();
+        ORIGIN.get_or_init(Instant::now).elapsed()
+    }
+}
+
+/// A clock that is always at its origin, so that every phase takes no time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoClock;
+
+impl Clock for NoClock {
+    fn now(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// Receives the measurements of each phase of the proof, in the order in which the phases
+/// finish. It is implemented by closures taking the phase and its metrics.
+pub trait ProverMetrics {
+    /// Called when `phase` is finished.
+    fn phase(&mut self, phase: ProofPhase, metrics: PhaseMetrics);
+
+    /// The clock the durations are measured with. Defaults to `SystemClock`, or to `NoClock`
+    /// without the `std` feature.
+    fn clock(&self) -> &dyn Clock {
+        #[cfg(feature = "std")]
+        return &SystemClock;
+        #[cfg(not(feature = "std"))]
+        return &NoClock;
+    }
+}
+
+impl<F: FnMut(ProofPhase, PhaseMetrics)> ProverMetrics for F {
+    fn phase(&mut self, phase: ProofPhase, metrics: PhaseMetrics) {
+        self(phase, metrics)
+    }
+}
+
+/// Discards all measurements. This is what the provers without a `ProverMetrics` argument use.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoMetrics;
+
+impl ProverMetrics for NoMetrics {
+    fn phase(&mut self, _phase: ProofPhase, _metrics: PhaseMetrics) {}
+
+    fn clock(&self) -> &dyn Clock {
+        &NoClock
+    }
+}
+
+/// Reports to `metrics`, with the durations measured by `clock` instead of the clock of
+/// `metrics`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WithClock<M, C> {
+    pub metrics: M,
+    pub clock: C,
+}
+
+impl<M: ProverMetrics, C: Clock> ProverMetrics for WithClock<M, C> {
+    fn phase(&mut self, phase: ProofPhase, metrics: PhaseMetrics) {
+        self.metrics.phase(phase, metrics)
+    }
+
+    fn clock(&self) -> &dyn Clock {
+        &self.clock
+    }
+}
+
+/// Measures the time elapsed on the clock of the metrics.
+pub(crate) struct Stopwatch(Duration);
+
+impl Stopwatch {
+    pub(crate) fn start(metrics: &dyn ProverMetrics) -> Stopwatch {
+        Stopwatch(metrics.clock().now())
+    }
+
+    pub(crate) fn elapsed(&self, metrics: &dyn ProverMetrics) -> Duration {
+        metrics.clock().now().saturating_sub(self.0)
+    }
+}
+
+/// Number of elements of a per-sensor, per-axis matrix.
+pub(crate) fn count<T>(matrix: &[Vec<T>]) -> usize {
+    matrix.iter().map(|row| row.len()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FeatureWitness, PublicParams, SensorFeatures, zkSVMProver};
+    use ip_zk_proof::SerializedSize;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+    use core::cell::Cell;
+
+    fn witness(size: usize) -> FeatureWitness {
+        let sizes = vec![40, 60, 50, 64];
+        let input: Vec<[Vec<i128>; 3]> = sizes.iter()
+            .map(|&n| {
+                let coord = |j: usize| (0..size)
+                    .map(|k| if k < n { ((n + j + k * k) % 11) as i128 } else { 0 })
+                    .collect();
+                [coord(0), coord(1), coord(2)]
+            })
+            .collect();
+        SensorFeatures::extract(&input, &sizes).witness().unwrap()
+    }
+
+    #[test]
+    fn phases_add_up_to_the_proof() {
+        let size = 64;
+        let witness = witness(size);
+
+        let mut phases = Vec::new();
+        let prover = zkSVMProver::new_with_metrics(
+            &PublicParams::new(size),
+            &mut |phase, metrics| phases.push((phase, metrics)),
+            &witness.input_vector,
+            &witness.non_zero_elements,
+            &witness.diff_vectors,
+            &witness.additions,
+            &witness.variances,
+            &witness.stds,
+        ).unwrap();
+
+        assert_eq!(
+            phases.iter().map(|(phase, _)| *phase).collect::<Vec<ProofPhase>>(),
+            vec![
+                ProofPhase::Commitment,
+                ProofPhase::DiffProofs,
+                ProofPhase::AvgProof,
+                ProofPhase::VarianceProof,
+                ProofPhase::StdProofs,
+            ]
+        );
+        assert_eq!(phases[0].1.commitments, 12);
+        assert_eq!(phases[4].1.proofs, 12);
+        // The encoding only adds the version, the header, the fingerprint and the statement
+        // graph to the phases
+        let proof_size = phases.iter().map(|(_, metrics)| metrics.proof_size).sum::<usize>()
+            + crate::utils::misc::bincode_size(&prover.header())
+            + prover.fingerprint().len()
+            + crate::utils::misc::bincode_size(prover.statement_graph());
+        assert!(proof_size < prover.serialized_size() && prover.serialized_size() < proof_size + 128);
+        assert!(prover.verify().is_ok());
+    }
+
+    #[test]
+    fn injected_rng_and_clock() {
+        let size = 64;
+        let witness = witness(size);
+        let params = PublicParams::new(size);
+
+        // A clock that advances a second every time it is read
+        let ticks = Cell::new(0);
+        let clock = || {
+            ticks.set(ticks.get() + 1);
+            Duration::from_secs(ticks.get())
+        };
+        let mut durations = Vec::new();
+        let prove = |seed: u64, durations: &mut Vec<Duration>| zkSVMProver::new_with_rng(
+            &params,
+            &mut ChaChaRng::seed_from_u64(seed),
+            &mut WithClock { metrics: |_, metrics: PhaseMetrics| durations.push(metrics.duration), clock: &clock },
+            &witness.input_vector,
+            &witness.non_zero_elements,
+            &witness.diff_vectors,
+            &witness.additions,
+            &witness.variances,
+            &witness.stds,
+        ).unwrap();
+
+        let prover = prove(1, &mut durations);
+        assert_eq!(durations, vec![Duration::from_secs(1); 5]);
+        assert_eq!(prove(1, &mut Vec::new()).to_bytes(), prover.to_bytes());
+        assert_ne!(prove(2, &mut Vec::new()).to_bytes(), prover.to_bytes());
+        assert!(prover.verify_with_rng(&params, &mut ChaChaRng::seed_from_u64(3)).is_ok());
+        assert_eq!(NoMetrics.clock().now(), Duration::ZERO);
+    }
+}
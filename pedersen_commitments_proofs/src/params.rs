@@ -0,0 +1,340 @@
+use alloc::vec::Vec;
+use ip_zk_proof::{write_section, BulletproofGens, PedersenGens, ProofError, ProofReader};
+use crate::boolean_proofs::square_proof::SQUARE_RANGE_BITSIZE;
+use crate::config::fingerprint;
+use crate::{PedersenConfig, PedersenVecGens, TranscriptLabels};
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+
+use merlin::Transcript;
+
+/// Seed of the vector generators of `PublicParams::new`.
+const DEFAULT_SEED: &[u8] = b"zkSVM public parameters";
+
+/// Application label of `PublicParams::new`.
+const DEFAULT_APPLICATION: &[u8] = b"zkSVM";
+
+/// Public parameters shared by the prover and the verifier of the zkSVM proofs: the
+/// generators, the bitsize of the range proofs and the transcript labels.
+///
+/// The parameters are created once with `setup`, and then distributed (e.g. with `to_bytes`)
+/// to every prover and verifier, which can compare their `digest` to check that they agree
+/// on them. They are set up for an application, e.g. `b"zkSENSE-v2-acme-app"`, whose label
+/// derives the vector generators and is the domain of every transcript, so that the proofs
+/// of different deployments never verify under each other's parameters.
+#[derive(Clone)]
+pub struct PublicParams {
+    pedersen_gens: PedersenGens,
+    // Bases of the vectors signed by the trusted module
+    G_vec: PedersenVecGens,
+    // Bases of the right hand sides of the inner products
+    H_vec: PedersenVecGens,
+    // Bulletproof generators over G_vec and H_vec
+    bp_gens: BulletproofGens,
+    size: usize,
+    range_bitsize: usize,
+    application: Vec<u8>,
+    labels: TranscriptLabels,
+}
+
+impl PublicParams {
+    /// Creates the parameters of `application` for vectors of `size` elements. The bases of
+    /// the signed vectors are derived from the application label, and the bases of the right
+    /// hand sides from the label and `seed`, see `PedersenVecGens::from_application`, so the
+    /// parameters can be recomputed by anyone from both.
+    ///
+    /// Panics if `application` is empty.
+    pub fn setup(application: &[u8], seed: &[u8], size: usize) -> PublicParams {
+        assert!(!application.is_empty(), "The application label must not be empty.");
+        let pedersen_gens = PedersenGens::default();
+        let G_vec = PedersenVecGens::from_application(application, b"G_vec", size);
+        let H_vec = PedersenVecGens::from_application(application, &[b"H_vec", seed].concat(), size);
+        let bp_gens = G_vec.bulletproof_gens(&H_vec)
+            .expect("Should never happen, as both generators have the same size and blinding base.");
+
+        PublicParams {
+            pedersen_gens,
+            G_vec,
+            H_vec,
+            bp_gens,
+            size,
+            range_bitsize: SQUARE_RANGE_BITSIZE,
+            application: application.to_vec(),
+            labels: TranscriptLabels::with_domain(application),
+        }
+    }
+
+    /// Parameters for vectors of `size` elements with the default application label and
+    /// seed, for tests and examples. Deployments set up their own with `setup`.
+    pub fn new(size: usize) -> PublicParams {
+        PublicParams::setup(DEFAULT_APPLICATION, DEFAULT_SEED, size)
+    }
+
+    /// Replaces the transcript labels, keeping the application label as their domain.
+    pub fn with_labels(mut self, labels: TranscriptLabels) -> PublicParams {
+        self.labels = TranscriptLabels { domain: self.application.clone(), ..labels };
+        self
+    }
+
+    /// Label of the application the parameters are set up for.
+    pub fn application(&self) -> &[u8] {
+        &self.application
+    }
+
+    pub fn pedersen_gens(&self) -> &PedersenGens {
+        &self.pedersen_gens
+    }
+
+    pub fn G_vec(&self) -> &PedersenVecGens {
+        &self.G_vec
+    }
+
+    pub fn H_vec(&self) -> &PedersenVecGens {
+        &self.H_vec
+    }
+
+    pub fn bp_gens(&self) -> &BulletproofGens {
+        &self.bp_gens
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn range_bitsize(&self) -> usize {
+        self.range_bitsize
+    }
+
+    pub fn labels(&self) -> &TranscriptLabels {
+        &self.labels
+    }
+
+    /// Precomputes the configuration of the proofs under these parameters.
+    pub fn config(&self) -> PedersenConfig {
+        PedersenConfig::new(
+            &self.application,
+            &Some(self.pedersen_gens),
+            &Some(self.G_vec.clone()),
+            &Some(self.H_vec.clone()),
+            self.size,
+        ).with_labels(self.labels.clone())
+    }
+
+    /// Serializes the parameters as the size and the range bitsize (8 bytes each, little
+    /// endian), the Pedersen generators, the blinding base and the bases of `G_vec` and
+    /// `H_vec` (32 bytes each), followed by the length of the application label (8 bytes)
+    /// and the label itself.
+    ///
+    /// Only the application label of the transcript labels is serialized, the labels
+    /// themselves are always the default ones when parsed with `from_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(16 + 32 * (3 + 2 * self.size) + 8 + self.application.len());
+        buf.extend_from_slice(&(self.size as u64).to_le_bytes());
+        buf.extend_from_slice(&(self.range_bitsize as u64).to_le_bytes());
+        buf.extend_from_slice(self.pedersen_gens.B.compress().as_bytes());
+        buf.extend_from_slice(self.pedersen_gens.B_blinding.compress().as_bytes());
+        buf.extend_from_slice(self.G_vec.B_blinding.compress().as_bytes());
+        for base in self.G_vec.B.iter().chain(self.H_vec.B.iter()) {
+            buf.extend_from_slice(base.compress().as_bytes());
+        }
+        write_section(&mut buf, &self.application);
+        buf
+    }
+
+    /// Parses the parameters serialized with `to_bytes`. Returns a `FormatError` if the
+    /// encoding is invalid, if the range bitsize is not the one of the proofs, or if the
+    /// application label is empty, and an
+    /// `InvalidGenerators` error if the generators do not pass `validate`.
+    pub fn from_bytes(slice: &[u8]) -> Result<PublicParams, ProofError> {
+        let mut reader = ProofReader::new(slice);
+        let size = reader.read_count(64)?;
+        let range_bitsize = reader.read_u64()?;
+        if size == 0 || range_bitsize != SQUARE_RANGE_BITSIZE as u64 {
+            return Err(ProofError::FormatError);
+        }
+
+        let pedersen_gens = PedersenGens { B: read_point(&mut reader)?, B_blinding: read_point(&mut reader)? };
+        let B_blinding = read_point(&mut reader)?;
+        let bases = (0..2 * size)
+            .map(|_| read_point(&mut reader))
+            .collect::<Result<Vec<RistrettoPoint>, ProofError>>()?;
+
+        let mut section = reader.read_section()?;
+        let application = section.read_bytes(section.remaining())?.to_vec();
+        reader.finish()?;
+        if application.is_empty() {
+            return Err(ProofError::FormatError);
+        }
+
+        let G_vec = PedersenVecGens { size, B: bases[..size].to_vec(), B_blinding };
+        let H_vec = PedersenVecGens { size, B: bases[size..].to_vec(), B_blinding };
+        let bp_gens = G_vec.bulletproof_gens(&H_vec).ok_or(ProofError::FormatError)?;
+
+        let params = PublicParams {
+            pedersen_gens,
+            G_vec,
+            H_vec,
+            bp_gens,
+            size,
+            range_bitsize: SQUARE_RANGE_BITSIZE,
+            labels: TranscriptLabels::with_domain(&application),
+            application,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Checks that the generators are distinct non-identity bases of the size of the
+    /// parameters, and that the Bulletproof generators are the ones over `G_vec` and `H_vec`.
+    /// Returns an `InvalidGenerators` error if not.
+    ///
+    /// The parameters of `setup` are always valid, but a verifier must check parameters it
+    /// did not derive itself, as bases with a known relation break the soundness of the proofs.
+    pub fn validate(&self) -> Result<(), ProofError> {
+        self.pedersen_gens.validate()?;
+        self.G_vec.validate()?;
+        self.H_vec.validate()?;
+        self.bp_gens.validate()?;
+        if self.G_vec.size != self.size
+            || self.H_vec.size != self.size
+            || self.bp_gens.gens_capacity != self.size
+            || self.bp_gens.party_capacity != 1
+            || self.bp_gens.G_vec[0] != self.G_vec.B
+            || self.bp_gens.H_vec[0] != self.H_vec.B
+        {
+            return Err(ProofError::InvalidGenerators);
+        }
+        Ok(())
+    }
+
+    /// Digest of the generators, the size and the transcript labels of the parameters, with
+    /// the application label. Every zkSVM proof carries the fingerprint of the parameters it
+    /// was created under, and is rejected with an `InvalidGenerators` error by a verifier
+    /// under parameters of another fingerprint, see `zkSVMProver::fingerprint`. This is also
+    /// the fingerprint of the `config` of the parameters.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        fingerprint(&self.pedersen_gens, &self.G_vec, &self.H_vec, self.size, &self.labels)
+    }
+
+    /// Digest of the parameters, including all the transcript labels.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut transcript = Transcript::new(b"zkSVM public parameters digest");
+        transcript.append_message(b"params", &self.to_bytes());
+        for label in [
+            self.labels.average,
+            self.labels.average_commitment,
+            self.labels.variance,
+            self.labels.commitment_equality,
+            self.labels.standard_deviation,
+            self.labels.remove_last,
+            self.labels.decision,
+        ].iter() {
+            transcript.append_message(b"label", label);
+        }
+
+        let mut digest = [0u8; 32];
+        transcript.challenge_bytes(b"digest", &mut digest);
+        digest
+    }
+}
+
+/// Reads a point, which must be a valid encoding.
+fn read_point(reader: &mut ProofReader) -> Result<RistrettoPoint, ProofError> {
+    reader.read_point::<CompressedRistretto>()?
+        .decompress()
+        .ok_or(ProofError::FormatError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialization_roundtrip() {
+        let params = PublicParams::setup(b"zkSENSE", b"seed", 16);
+        let bytes = params.to_bytes();
+
+        let parsed = PublicParams::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.to_bytes(), bytes);
+        assert_eq!(parsed.digest(), params.digest());
+        assert_eq!(parsed.labels(), params.labels());
+        assert!(*parsed.H_vec() == *params.H_vec());
+
+        assert_eq!(PublicParams::from_bytes(&bytes[..bytes.len() - 1]).err(), Some(ProofError::FormatError));
+        assert_eq!(PublicParams::from_bytes(&[]).err(), Some(ProofError::FormatError));
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(PublicParams::from_bytes(&trailing).err(), Some(ProofError::FormatError));
+
+        let mut wrong_bitsize = bytes.clone();
+        wrong_bitsize[8] = 64;
+        assert_eq!(PublicParams::from_bytes(&wrong_bitsize).err(), Some(ProofError::FormatError));
+    }
+
+    #[test]
+    fn forged_generators_are_rejected() {
+        let params = PublicParams::new(8);
+        assert!(params.validate().is_ok());
+        let bytes = params.to_bytes();
+
+        // The value base of the Pedersen generators is replaced by the blinding base
+        let mut repeated = bytes.clone();
+        repeated.copy_within(48..80, 16);
+        assert_eq!(PublicParams::from_bytes(&repeated).err(), Some(ProofError::InvalidGenerators));
+
+        // A base of H_vec is replaced by one of G_vec
+        let mut shared = bytes.clone();
+        shared.copy_within(112..144, 112 + 32 * 8);
+        assert_eq!(PublicParams::from_bytes(&shared).err(), Some(ProofError::InvalidGenerators));
+
+        // A base of G_vec is replaced by the identity
+        let mut identity = bytes;
+        identity[112..144].copy_from_slice(&[0u8; 32]);
+        assert_eq!(PublicParams::from_bytes(&identity).err(), Some(ProofError::InvalidGenerators));
+    }
+
+    #[test]
+    fn setup_is_deterministic() {
+        assert_eq!(PublicParams::setup(b"app", b"seed", 8).digest(), PublicParams::setup(b"app", b"seed", 8).digest());
+        assert_ne!(PublicParams::setup(b"app", b"seed", 8).digest(), PublicParams::setup(b"app", b"other seed", 8).digest());
+        assert_ne!(PublicParams::new(8).digest(), PublicParams::new(16).digest());
+    }
+
+    #[test]
+    fn applications_are_separated() {
+        let params = PublicParams::setup(b"zkSENSE-v2-acme-app", DEFAULT_SEED, 8);
+        let other = PublicParams::setup(b"zkSENSE-v2-other-app", DEFAULT_SEED, 8);
+        assert!(*params.G_vec() != *other.G_vec() && *params.H_vec() != *other.H_vec());
+        assert_ne!(params.digest(), other.digest());
+        assert_eq!(params.config().labels().domain, b"zkSENSE-v2-acme-app".to_vec());
+
+        // The labels keep the application
+        let relabeled = params.clone().with_labels(TranscriptLabels::with_domain(b"zkSENSE-v2-other-app"));
+        assert_eq!(relabeled.labels().domain, params.application().to_vec());
+
+        // Parameters without an application are rejected
+        let bytes = params.to_bytes();
+        let mut unlabeled = bytes[..bytes.len() - 8 - params.application().len()].to_vec();
+        unlabeled.extend_from_slice(&0u64.to_le_bytes());
+        assert_eq!(PublicParams::from_bytes(&unlabeled).err(), Some(ProofError::FormatError));
+    }
+
+    #[test]
+    fn fingerprints() {
+        let params = PublicParams::setup(b"app", b"seed", 8);
+        assert_eq!(params.fingerprint(), params.config().fingerprint());
+        assert_eq!(params.fingerprint(), PublicParams::from_bytes(&params.to_bytes()).unwrap().fingerprint());
+
+        // The generators, the size and the labels change the fingerprint
+        assert_ne!(params.fingerprint(), PublicParams::setup(b"app", b"other seed", 8).fingerprint());
+        assert_ne!(params.fingerprint(), PublicParams::setup(b"other app", b"seed", 8).fingerprint());
+        assert_ne!(params.fingerprint(), PublicParams::setup(b"app", b"seed", 16).fingerprint());
+        let relabeled = params.clone().with_labels(TranscriptLabels { decision: b"OtherDecisionProof", ..TranscriptLabels::default() });
+        assert_ne!(params.fingerprint(), relabeled.fingerprint());
+
+        // But not the labels that change from proof to proof
+        let mut outer = Transcript::new(b"outer protocol");
+        let bound = params.labels().bind_to(&mut outer).with_sizes(&[8, 2, 4, 4]);
+        assert_eq!(params.fingerprint(), params.config().with_labels(bound).fingerprint());
+    }
+}
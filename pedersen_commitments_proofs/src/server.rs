@@ -0,0 +1,260 @@
+//! HTTP verification service of the zkSVM proofs. Enabled with the `server` feature, and run
+//! by the `verify_server` binary.
+//!
+//! The service holds the `PublicParams` the proofs are verified under, and takes JSON
+//! requests with the proof and its public inputs, with proofs encoded in base64 and points in
+//! hex, see `utils::text_encoding`:
+//!
+//! - `GET /v1/params`: the size, the digest and the fingerprint of the parameters, see
+//!   `ParamsInfo`.
+//! - `POST /v1/verify/zksvm`: a `zkSVMProver`, see `ZkSvmRequest`.
+//! - `POST /v1/verify/classification`: a `ClassificationProof`, see `ClassificationRequest`.
+//! - `POST /v1/verify/revealed-classification`: a `RevealedClassificationProof`, see
+//!   `RevealedClassificationRequest`.
+//!
+//! Verifications answer a `VerificationResult`, with status 200 whether the proof is valid or
+//! not, as long as the request is well formed JSON. A proof or public input that cannot be
+//! parsed is reported as an invalid proof, with the error of the parser.
+
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use curve25519_dalek::ristretto::CompressedRistretto;
+use ip_zk_proof::ProofError;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::text_encoding::{to_hex, TextEncoding};
+use crate::{ClassificationProof, ProofHeader, PublicParams, RevealedClassificationProof, StatisticSet, SvmModel, zkSVMProver};
+
+/// Request to verify a `zkSVMProver`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZkSvmRequest {
+    /// The proof, see `zkSVMProver::to_base64`
+    pub proof: String,
+    /// Commitments of the trusted module to the windows, in hex. If present, the proof must
+    /// be about them.
+    #[serde(default)]
+    pub signed_commitments: Option<Vec<Vec<String>>>,
+    /// Statistics the proof must cover, see `zkSVMProver::verify_covering`
+    #[serde(default)]
+    pub required: Option<StatisticSet>,
+    /// Sizes of the statement of the proof, see `zkSVMProver::verify_with_header`
+    #[serde(default)]
+    pub header: Option<ProofHeader>,
+}
+
+/// Request to verify a `ClassificationProof` for a committed decision.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassificationRequest {
+    /// The proof, see `ClassificationProof::to_base64`
+    pub proof: String,
+    /// The model the decision is taken with
+    pub model: SvmModel,
+    /// Commitment to the decision, in hex
+    pub decision_commitment: String,
+}
+
+/// Request to verify a `RevealedClassificationProof`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevealedClassificationRequest {
+    /// The proof, see `RevealedClassificationProof::to_base64`
+    pub proof: String,
+    /// The model the decision is taken with
+    pub model: SvmModel,
+}
+
+/// Outcome of a verification.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationResult {
+    pub valid: bool,
+    /// Why the proof was rejected, the `Display` of the `ProofError`
+    pub error: Option<String>,
+    /// The decision of a valid `RevealedClassificationProof`
+    pub decision: Option<bool>,
+}
+
+impl VerificationResult {
+    fn from_result(result: Result<(), ProofError>) -> VerificationResult {
+        match result {
+            Ok(()) => VerificationResult { valid: true, error: None, decision: None },
+            Err(e) => VerificationResult { valid: false, error: Some(e.to_string()), decision: None },
+        }
+    }
+}
+
+/// Public parameters of the service.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParamsInfo {
+    pub size: usize,
+    /// `PublicParams::digest`, in hex
+    pub digest: String,
+    /// `PublicParams::fingerprint`, in hex
+    pub fingerprint: String,
+}
+
+/// Verifies the requests under a fixed set of parameters.
+#[derive(Clone)]
+pub struct VerificationService {
+    params: Arc<PublicParams>,
+}
+
+impl VerificationService {
+    pub fn new(params: PublicParams) -> VerificationService {
+        VerificationService { params: Arc::new(params) }
+    }
+
+    pub fn params_info(&self) -> ParamsInfo {
+        ParamsInfo {
+            size: self.params.size(),
+            digest: to_hex(&self.params.digest()),
+            fingerprint: to_hex(&self.params.fingerprint()),
+        }
+    }
+
+    pub fn verify_zksvm(&self, request: &ZkSvmRequest) -> VerificationResult {
+        VerificationResult::from_result((|| {
+            let proof = zkSVMProver::from_base64(&request.proof, &self.params)?;
+            if let Some(signed_commitments) = &request.signed_commitments {
+                if proof.signed_commitments() != &parse_commitments(signed_commitments)? {
+                    return Err(ProofError::VerificationError);
+                }
+            }
+            if let Some(header) = &request.header {
+                if &proof.header() != header {
+                    return Err(ProofError::VerificationError);
+                }
+            }
+            match &request.required {
+                Some(required) => proof.verify_covering(&self.params, required),
+                None => proof.verify_with_params(&self.params),
+            }
+        })())
+    }
+
+    pub fn verify_classification(&self, request: &ClassificationRequest) -> VerificationResult {
+        VerificationResult::from_result((|| {
+            let proof = ClassificationProof::from_base64(&request.proof, &self.params)?;
+            let decision_commitment = CompressedRistretto::from_hex(&request.decision_commitment, ())?;
+            proof.verify_with_params(&self.params, &request.model, decision_commitment)
+        })())
+    }
+
+    pub fn verify_revealed_classification(&self, request: &RevealedClassificationRequest) -> VerificationResult {
+        let decision = RevealedClassificationProof::from_base64(&request.proof, &self.params)
+            .and_then(|proof| proof.verify_with_params(&self.params, &request.model));
+        match decision {
+            Ok(decision) => VerificationResult { valid: true, error: None, decision: Some(decision) },
+            Err(e) => VerificationResult::from_result(Err(e)),
+        }
+    }
+
+    /// The routes of the service, listed in the module documentation.
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/v1/params", get(params_info))
+            .route("/v1/verify/zksvm", post(verify_zksvm))
+            .route("/v1/verify/classification", post(verify_classification))
+            .route("/v1/verify/revealed-classification", post(verify_revealed_classification))
+            .with_state(self)
+    }
+}
+
+fn parse_commitments(commitments: &[Vec<String>]) -> Result<Vec<Vec<CompressedRistretto>>, ProofError> {
+    commitments.iter()
+        .map(|c| c.iter().map(|p| CompressedRistretto::from_hex(p, ())).collect())
+        .collect()
+}
+
+/// Runs a verification on the blocking pool, as it takes too long for the async workers.
+async fn blocking<F>(verify: F) -> Result<Json<VerificationResult>, StatusCode>
+where
+    F: FnOnce() -> VerificationResult + Send + 'static,
+{
+    tokio::task::spawn_blocking(verify)
+        .await
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn params_info(State(service): State<VerificationService>) -> Json<ParamsInfo> {
+    Json(service.params_info())
+}
+
+async fn verify_zksvm(
+    State(service): State<VerificationService>,
+    Json(request): Json<ZkSvmRequest>,
+) -> Result<Json<VerificationResult>, StatusCode> {
+    blocking(move || service.verify_zksvm(&request)).await
+}
+
+async fn verify_classification(
+    State(service): State<VerificationService>,
+    Json(request): Json<ClassificationRequest>,
+) -> Result<Json<VerificationResult>, StatusCode> {
+    blocking(move || service.verify_classification(&request)).await
+}
+
+async fn verify_revealed_classification(
+    State(service): State<VerificationService>,
+    Json(request): Json<RevealedClassificationRequest>,
+) -> Result<Json<VerificationResult>, StatusCode> {
+    blocking(move || service.verify_revealed_classification(&request)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_requests_are_invalid() {
+        let service = VerificationService::new(PublicParams::new(64));
+        assert_eq!(service.params_info().size, 64);
+
+        let request: ZkSvmRequest = serde_json::from_str(r#"{"proof": "AAAA"}"#).unwrap();
+        let result = service.verify_zksvm(&request);
+        assert!(!result.valid);
+        assert_eq!(result.error, Some(ProofError::FormatError.to_string()));
+
+        let request = ClassificationRequest {
+            proof: "not base64".to_string(),
+            model: SvmModel { addition_weights: vec![], std_weights: vec![], bias: 0 },
+            decision_commitment: "00".to_string(),
+        };
+        assert!(!service.verify_classification(&request).valid);
+
+        let json = serde_json::to_string(&service.verify_revealed_classification(&RevealedClassificationRequest {
+            proof: String::new(),
+            model: request.model.clone(),
+        })).unwrap();
+        let result: VerificationResult = serde_json::from_str(&json).unwrap();
+        assert!(!result.valid && result.decision.is_none());
+    }
+
+    #[cfg(feature = "test-utils")]
+    #[test]
+    fn zksvm_requests() {
+        let fixture = crate::test_utils::Fixture::new(64, 3);
+        let service = VerificationService::new(fixture.params.clone());
+        let signed_commitments: Vec<Vec<String>> = fixture.signed_commitments.iter()
+            .map(|c| c.iter().map(|p| p.to_hex()).collect())
+            .collect();
+        let proof = fixture.prove().unwrap();
+        let mut request = ZkSvmRequest {
+            proof: proof.to_base64(),
+            signed_commitments: Some(signed_commitments),
+            required: None,
+            header: Some(proof.header()),
+        };
+        assert_eq!(service.verify_zksvm(&request), VerificationResult { valid: true, error: None, decision: None });
+
+        request.header.as_mut().unwrap().size_sensors.reverse();
+        assert!(!service.verify_zksvm(&request).valid);
+        request.header = None;
+
+        request.signed_commitments.as_mut().unwrap().swap(0, 1);
+        assert!(!service.verify_zksvm(&request).valid);
+    }
+}
@@ -0,0 +1,629 @@
+//! Schnorr proofs of knowledge for linear relations over Ristretto points.
+//!
+//! A statement is a set of constraints `lhs = s_0 * P_0 + s_1 * P_1 + ...` over secret
+//! scalars `s_i` and public points. It is written once as a function over the [`SchnorrCS`]
+//! trait, which is then run by a [`Prover`] to produce a [`SigmaProof`], and by a
+//! [`Verifier`] to check it, as the constraint system proofs of `ip_zk_proof::r1cs`.
+//!
+//! All points are bound to the transcript when they are allocated, so the verifier must
+//! allocate the same points, with the same labels and in the same order as the prover.
+//!
+//! Statements are composed with [`and`], which requires both statements to hold, and with
+//! [`or`] and [`any`], which require one of them to hold without revealing which one. The
+//! branches of a disjunction are proven with the OR composition of Cramer, Damgård and
+//! Schoenmakers: the prover simulates the branches that do not hold, with challenges of its
+//! choice, and the challenges of all branches add up to the challenge of the proof.
+
+#![allow(non_snake_case)]
+
+use alloc::vec::Vec;
+use alloc::vec;
+use alloc::string::String;
+use alloc::boxed::Box;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{MultiscalarMul, VartimeMultiscalarMul};
+
+use ip_zk_proof::{ProofError, ProofReader, TranscriptProtocol};
+use merlin::Transcript;
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+/// A secret scalar of a statement.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScalarVar(usize);
+
+/// A public point of a statement.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PointVar(usize);
+
+/// The constraints of a statement, common to the prover and the verifier.
+pub trait SchnorrCS {
+    #[doc(hidden)]
+    fn constraints(&mut self) -> &mut Constraints;
+
+    /// Constrains `lhs` to be the sum of the points of `linear_combination`, each multiplied
+    /// by its secret scalar.
+    fn constrain(&mut self, lhs: PointVar, linear_combination: Vec<(ScalarVar, PointVar)>) {
+        self.constraints().push(lhs, linear_combination);
+    }
+}
+
+/// Constrains both statements to hold.
+pub fn and<CS, L, R>(cs: &mut CS, left: L, right: R)
+where
+    CS: SchnorrCS,
+    L: FnOnce(&mut CS),
+    R: FnOnce(&mut CS),
+{
+    left(cs);
+    right(cs);
+}
+
+/// A statement over the variables of a constraint system, as a branch of `any`.
+pub type Statement<'a, CS> = Box<dyn FnOnce(&mut CS) + 'a>;
+
+/// Constrains at least one of the statements to hold, without revealing which one. See `any`.
+pub fn or<CS, L, R>(cs: &mut CS, left: L, right: R)
+where
+    CS: SchnorrCS,
+    L: FnOnce(&mut CS),
+    R: FnOnce(&mut CS),
+{
+    cs.constraints().begin_disjunction();
+    left(cs);
+    cs.constraints().next_branch();
+    right(cs);
+    cs.constraints().end_disjunction();
+}
+
+/// Constrains at least one of the statements to hold, without revealing which one.
+///
+/// The secret scalars of a branch must not be used outside of it, and the prover assigns any
+/// value to the ones of the branches that do not hold. Disjunctions cannot be nested.
+pub fn any<CS: SchnorrCS>(cs: &mut CS, branches: Vec<Statement<'_, CS>>) {
+    cs.constraints().begin_disjunction();
+    for (index, branch) in branches.into_iter().enumerate() {
+        if index > 0 {
+            cs.constraints().next_branch();
+        }
+        branch(cs);
+    }
+    cs.constraints().end_disjunction();
+}
+
+/// Branch of a constraint, as the disjunction and the branch within it, or `None` for the
+/// constraints that must always hold.
+type Branch = Option<(usize, usize)>;
+
+type Constraint = (Branch, PointVar, Vec<(ScalarVar, PointVar)>);
+
+/// The constraints added to a `Prover` or a `Verifier`.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct Constraints {
+    constraints: Vec<Constraint>,
+    // Number of branches of each disjunction
+    disjunctions: Vec<usize>,
+    current: Branch,
+}
+
+impl Constraints {
+    fn push(&mut self, lhs: PointVar, linear_combination: Vec<(ScalarVar, PointVar)>) {
+        self.constraints.push((self.current, lhs, linear_combination));
+    }
+
+    fn begin_disjunction(&mut self) {
+        assert!(self.current.is_none(), "Disjunctions cannot be nested.");
+        self.disjunctions.push(1);
+        self.current = Some((self.disjunctions.len() - 1, 0));
+    }
+
+    fn next_branch(&mut self) {
+        let (disjunction, branch) = self.current.expect("Should never happen, as it is called within a disjunction.");
+        self.disjunctions[disjunction] += 1;
+        self.current = Some((disjunction, branch + 1));
+    }
+
+    fn end_disjunction(&mut self) {
+        self.current = None;
+    }
+
+    /// Branch of each secret scalar, where the scalars that are not constrained are in none.
+    /// Returns `None` if a scalar is used in several branches.
+    fn scalar_branches(&self, nr_scalars: usize) -> Option<Vec<Branch>> {
+        let mut branches: Vec<Option<Branch>> = vec![None; nr_scalars];
+        for (branch, _, linear_combination) in self.constraints.iter() {
+            for (scalar, _) in linear_combination.iter() {
+                match branches[scalar.0] {
+                    None => branches[scalar.0] = Some(*branch),
+                    Some(other) if other == *branch => {}
+                    Some(_) => return None,
+                }
+            }
+        }
+        Some(branches.into_iter().map(Option::flatten).collect())
+    }
+
+    /// Challenges of the branches of each disjunction, from the challenge of the proof and the
+    /// challenges of all but the last branch of each disjunction. Returns `None` if the number
+    /// of challenges is not the one of the branches.
+    fn branch_challenges(&self, challenge: Scalar, free_challenges: &[Scalar]) -> Option<Vec<Vec<Scalar>>> {
+        if free_challenges.len() != self.disjunctions.iter().map(|k| k - 1).sum::<usize>() {
+            return None;
+        }
+
+        let mut free_challenges = free_challenges.iter();
+        Some(self.disjunctions.iter().map(|&nr_branches| {
+            let mut challenges: Vec<Scalar> = free_challenges.by_ref().take(nr_branches - 1).cloned().collect();
+            let last = challenge - challenges.iter().sum::<Scalar>();
+            challenges.push(last);
+            challenges
+        }).collect())
+    }
+}
+
+/// Challenge of the constraints of `branch`.
+fn challenge_of(challenge: Scalar, branch_challenges: &[Vec<Scalar>], branch: Branch) -> Scalar {
+    branch.map_or(challenge, |(disjunction, index)| branch_challenges[disjunction][index])
+}
+
+/// A proof in compact form: the challenge, the challenges of all but the last branch of each
+/// disjunction, and one response per secret scalar.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SigmaProof {
+    challenge: Scalar,
+    branch_challenges: Vec<Scalar>,
+    responses: Vec<Scalar>,
+}
+
+impl SigmaProof {
+    /// Serializes the proof as the challenge, the number of branch challenges (8 bytes, little
+    /// endian), the branch challenges and the responses, 32 bytes each.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(8 + 32 * (1 + self.branch_challenges.len() + self.responses.len()));
+        buf.extend_from_slice(self.challenge.as_bytes());
+        buf.extend_from_slice(&(self.branch_challenges.len() as u64).to_le_bytes());
+        for scalar in self.branch_challenges.iter().chain(self.responses.iter()) {
+            buf.extend_from_slice(scalar.as_bytes());
+        }
+        buf
+    }
+
+    /// Parses a proof serialized with `to_bytes`. Returns a `FormatError` if the encoding is
+    /// invalid, or if a scalar is not canonical.
+    pub fn from_bytes(slice: &[u8]) -> Result<SigmaProof, ProofError> {
+        let mut reader = ProofReader::new(slice);
+        let challenge = reader.read_scalar()?;
+        let nr_branch_challenges = reader.read_count(32)?;
+        let branch_challenges = (0..nr_branch_challenges)
+            .map(|_| reader.read_scalar())
+            .collect::<Result<Vec<Scalar>, ProofError>>()?;
+        if !reader.remaining().is_multiple_of(32) {
+            return Err(ProofError::FormatError);
+        }
+        let responses = (0..reader.remaining() / 32)
+            .map(|_| reader.read_scalar())
+            .collect::<Result<Vec<Scalar>, ProofError>>()?;
+        reader.finish()?;
+
+        Ok(SigmaProof { challenge, branch_challenges, responses })
+    }
+}
+
+impl PartialOrd for SigmaProof {
+    fn partial_cmp(&self, other: &SigmaProof) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SigmaProof {
+    fn cmp(&self, other: &SigmaProof) -> Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
+impl Hash for SigmaProof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+/// Creates a `SigmaProof` of the statement built over it.
+pub struct Prover<'t> {
+    transcript: &'t mut Transcript,
+    scalars: Vec<(&'static [u8], Scalar)>,
+    points: Vec<RistrettoPoint>,
+    constraints: Constraints,
+}
+
+/// Only shows the labels of the secret scalars, so that their assignments do not end up in
+/// logs.
+impl<'t> fmt::Debug for Prover<'t> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let labels: Vec<String> = self.scalars.iter()
+            .map(|(label, _)| String::from_utf8_lossy(label).into_owned())
+            .collect();
+        f.debug_struct("Prover")
+            .field("scalars", &labels)
+            .field("points", &self.points)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'t> Prover<'t> {
+    /// Starts the proof of the statement `label` over `transcript`.
+    pub fn new(label: &'static [u8], transcript: &'t mut Transcript) -> Prover<'t> {
+        transcript.domain_sep(label);
+        Prover { transcript, scalars: Vec::new(), points: Vec::new(), constraints: Constraints::default() }
+    }
+
+    pub fn allocate_scalar(&mut self, label: &'static [u8], assignment: Scalar) -> ScalarVar {
+        self.scalars.push((label, assignment));
+        ScalarVar(self.scalars.len() - 1)
+    }
+
+    /// Allocates a public point, which is appended to the transcript. Returns its compressed
+    /// form, which the verifier needs.
+    pub fn allocate_point(&mut self, label: &'static [u8], assignment: RistrettoPoint) -> (PointVar, CompressedRistretto) {
+        let compressed = assignment.compress();
+        self.transcript.append_bytes(label, compressed.as_bytes());
+        self.points.push(assignment);
+        (PointVar(self.points.len() - 1), compressed)
+    }
+
+    /// Creates the proof. In each disjunction, the first branch that holds is proven and the
+    /// other ones are simulated. If none holds, the proof does not verify.
+    ///
+    /// Panics if a secret scalar is used in several branches.
+    #[cfg(feature = "std")]
+    pub fn prove(self) -> SigmaProof {
+        self.prove_with_rng(&mut thread_rng())
+    }
+
+    /// As `prove`, with `rng` as the external randomness of the nonces, which are bound to the
+    /// statement and to the secrets as well.
+    pub fn prove_with_rng<T: RngCore + CryptoRng>(self, rng: &mut T) -> SigmaProof {
+        let scalar_branches = self.constraints.scalar_branches(self.scalars.len())
+            .expect("A secret scalar is used in several branches.");
+        // Which branch holds is secret, so every branch is checked, and the proven one is
+        // selected without branching or indexing on it
+        let real_branches: Vec<u64> = self.constraints.disjunctions.iter().enumerate().map(
+            |(disjunction, &nr_branches)| {
+                let mut real = 0u64;
+                let mut found = Choice::from(0);
+                for index in 0..nr_branches {
+                    let holds = self.branch_holds(Some((disjunction, index)));
+                    real.conditional_assign(&(index as u64), holds & !found);
+                    found |= holds;
+                }
+                real
+            }
+        ).collect();
+        let is_simulated = |branch: Branch| -> Choice {
+            branch.map_or(Choice::from(0), |(disjunction, index)| !real_branches[disjunction].ct_eq(&(index as u64)))
+        };
+
+        // The nonces are bound to the statement and to the secrets. In the simulated branches,
+        // the nonces are the responses, and the commitments are computed from the challenges.
+        let mut rng_builder = self.transcript.build_rng();
+        for (label, scalar) in self.scalars.iter() {
+            rng_builder = rng_builder.rekey_with_witness_bytes(label, scalar.as_bytes());
+        }
+        let mut rng = rng_builder.finalize(rng);
+        let nonces: Vec<Scalar> = self.scalars.iter().map(|_| Scalar::random(&mut rng)).collect();
+        let mut branch_challenges: Vec<Vec<Scalar>> = self.constraints.disjunctions.iter()
+            .map(|&nr_branches| (0..nr_branches).map(|_| Scalar::random(&mut rng)).collect())
+            .collect();
+
+        for (branch, lhs, linear_combination) in self.constraints.constraints.iter() {
+            let simulated_challenge = Scalar::conditional_select(
+                &Scalar::ZERO,
+                &challenge_of(Scalar::ZERO, &branch_challenges, *branch),
+                is_simulated(*branch),
+            );
+            let commitment = RistrettoPoint::multiscalar_mul(
+                linear_combination.iter().map(|(scalar, _)| nonces[scalar.0]).chain(Some(-simulated_challenge)),
+                linear_combination.iter().map(|(_, point)| self.points[point.0]).chain(Some(self.points[lhs.0])),
+            );
+            self.transcript.append_bytes(b"commitment", commitment.compress().as_bytes());
+        }
+
+        // The challenge of the proven branch is set so that the challenges of each disjunction
+        // add up to the challenge of the proof
+        let challenge = challenge_scalar(self.transcript);
+        for (challenges, real) in branch_challenges.iter_mut().zip(real_branches.iter()) {
+            let simulated: Scalar = challenges.iter().enumerate()
+                .map(|(index, c)| Scalar::conditional_select(c, &Scalar::ZERO, real.ct_eq(&(index as u64))))
+                .sum();
+            for (index, c) in challenges.iter_mut().enumerate() {
+                c.conditional_assign(&(challenge - simulated), real.ct_eq(&(index as u64)));
+            }
+        }
+
+        let responses = nonces.iter()
+            .zip(self.scalars.iter())
+            .zip(scalar_branches.iter())
+            .map(|((nonce, (_, secret)), &branch)| Scalar::conditional_select(
+                &(nonce + challenge_of(challenge, &branch_challenges, branch) * secret),
+                nonce,
+                is_simulated(branch),
+            ))
+            .collect();
+
+        SigmaProof {
+            challenge,
+            branch_challenges: branch_challenges.into_iter()
+                .flat_map(|mut challenges| {
+                    challenges.pop();
+                    challenges
+                })
+                .collect(),
+            responses,
+        }
+    }
+
+    fn branch_holds(&self, branch: Branch) -> Choice {
+        self.constraints.constraints.iter()
+            .filter(|(constraint_branch, _, _)| *constraint_branch == branch)
+            .fold(Choice::from(1), |holds, (_, lhs, linear_combination)| {
+                holds & self.points[lhs.0].ct_eq(&RistrettoPoint::multiscalar_mul(
+                    linear_combination.iter().map(|(scalar, _)| self.scalars[scalar.0].1),
+                    linear_combination.iter().map(|(_, point)| self.points[point.0]),
+                ))
+            })
+    }
+}
+
+impl<'t> SchnorrCS for Prover<'t> {
+    fn constraints(&mut self) -> &mut Constraints {
+        &mut self.constraints
+    }
+}
+
+/// Verifies a `SigmaProof` of the statement built over it.
+pub struct Verifier<'t> {
+    transcript: &'t mut Transcript,
+    nr_scalars: usize,
+    points: Vec<RistrettoPoint>,
+    constraints: Constraints,
+}
+
+impl<'t> Verifier<'t> {
+    /// Starts the verification of the statement `label` over `transcript`.
+    pub fn new(label: &'static [u8], transcript: &'t mut Transcript) -> Verifier<'t> {
+        transcript.domain_sep(label);
+        Verifier { transcript, nr_scalars: 0, points: Vec::new(), constraints: Constraints::default() }
+    }
+
+    pub fn allocate_scalar(&mut self, _label: &'static [u8]) -> ScalarVar {
+        self.nr_scalars += 1;
+        ScalarVar(self.nr_scalars - 1)
+    }
+
+    /// Allocates a public point, which is appended to the transcript. Returns a `FormatError`
+    /// if the point is not a valid encoding.
+    pub fn allocate_point(&mut self, label: &'static [u8], assignment: CompressedRistretto) -> Result<PointVar, ProofError> {
+        self.transcript.append_bytes(label, assignment.as_bytes());
+        self.points.push(assignment.decompress().ok_or(ProofError::FormatError)?);
+        Ok(PointVar(self.points.len() - 1))
+    }
+
+    pub fn verify(self, proof: &SigmaProof) -> Result<(), ProofError> {
+        if proof.responses.len() != self.nr_scalars || self.constraints.scalar_branches(self.nr_scalars).is_none() {
+            return Err(ProofError::FormatError);
+        }
+        let branch_challenges = self.constraints
+            .branch_challenges(proof.challenge, &proof.branch_challenges)
+            .ok_or(ProofError::FormatError)?;
+
+        // Each commitment is recomputed as the responses minus the challenge times the lhs
+        for (branch, lhs, linear_combination) in self.constraints.constraints.iter() {
+            let commitment = RistrettoPoint::vartime_multiscalar_mul(
+                linear_combination.iter()
+                    .map(|(scalar, _)| proof.responses[scalar.0])
+                    .chain(Some(-challenge_of(proof.challenge, &branch_challenges, *branch))),
+                linear_combination.iter()
+                    .map(|(_, point)| self.points[point.0])
+                    .chain(Some(self.points[lhs.0])),
+            );
+            self.transcript.append_bytes(b"commitment", commitment.compress().as_bytes());
+        }
+
+        if challenge_scalar(self.transcript) == proof.challenge {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationError)
+        }
+    }
+}
+
+impl<'t> SchnorrCS for Verifier<'t> {
+    fn constraints(&mut self) -> &mut Constraints {
+        &mut self.constraints
+    }
+}
+
+fn challenge_scalar(transcript: &mut Transcript) -> Scalar {
+    let mut buf = [0u8; 64];
+    transcript.challenge_into(b"chal", &mut buf);
+    Scalar::from_bytes_mod_order_wide(&buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+
+    fn representation_statement<CS: SchnorrCS>(cs: &mut CS, x: ScalarVar, r: ScalarVar, A: PointVar, G: PointVar, B: PointVar) {
+        cs.constrain(A, vec![(x, G), (r, B)]);
+    }
+
+    fn verify_representation(proof: &SigmaProof, A: CompressedRistretto, G: CompressedRistretto, B: CompressedRistretto) -> Result<(), ProofError> {
+        let mut transcript = Transcript::new(b"test");
+        let mut verifier = Verifier::new(b"Representation", &mut transcript);
+        let x = verifier.allocate_scalar(b"x");
+        let r = verifier.allocate_scalar(b"r");
+        let A = verifier.allocate_point(b"A", A)?;
+        let G = verifier.allocate_point(b"G", G)?;
+        let B = verifier.allocate_point(b"B", B)?;
+        representation_statement(&mut verifier, x, r, A, G, B);
+        verifier.verify(proof)
+    }
+
+    #[test]
+    fn prove_and_verify_representation() {
+        let G = RISTRETTO_BASEPOINT_POINT;
+        let B = RistrettoPoint::random(&mut thread_rng());
+        let x = Scalar::random(&mut thread_rng());
+        let r = Scalar::random(&mut thread_rng());
+
+        let mut transcript = Transcript::new(b"test");
+        let mut prover = Prover::new(b"Representation", &mut transcript);
+        let x_var = prover.allocate_scalar(b"x", x);
+        let r_var = prover.allocate_scalar(b"r", r);
+        let (A_var, A) = prover.allocate_point(b"A", x * G + r * B);
+        let (G_var, G) = prover.allocate_point(b"G", G);
+        let (B_var, B) = prover.allocate_point(b"B", B);
+        representation_statement(&mut prover, x_var, r_var, A_var, G_var, B_var);
+        let proof = prover.prove();
+
+        assert!(verify_representation(&proof, A, G, B).is_ok());
+        let wrong_A = (A.decompress().unwrap() + G.decompress().unwrap()).compress();
+        assert_eq!(verify_representation(&proof, wrong_A, G, B), Err(ProofError::VerificationError));
+
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), 8 + 3 * 32);
+        assert!(verify_representation(&SigmaProof::from_bytes(&bytes).unwrap(), A, G, B).is_ok());
+        assert_eq!(
+            verify_representation(&SigmaProof::from_bytes(&bytes[..72]).unwrap(), A, G, B),
+            Err(ProofError::FormatError)
+        );
+        assert_eq!(SigmaProof::from_bytes(&bytes[..103]).map(|_| ()), Err(ProofError::FormatError));
+        assert_eq!(SigmaProof::from_bytes(&[0xff; 72]).map(|_| ()), Err(ProofError::FormatError));
+    }
+
+    /// Proves that the value committed in `V` (with the blinding `r`) is zero, or is the value
+    /// committed in `X` (with the blinding `s`).
+    fn zero_or_equal_proof(v: u64, r: Scalar, x: u64, s: Scalar, B: RistrettoPoint) -> (SigmaProof, CompressedRistretto, CompressedRistretto) {
+        let G = RISTRETTO_BASEPOINT_POINT;
+        let V = Scalar::from(v) * G + r * B;
+        let X = Scalar::from(x) * G + s * B;
+
+        let mut transcript = Transcript::new(b"test");
+        let mut prover = Prover::new(b"ZeroOrEqual", &mut transcript);
+        // Only the blinding of the branch that holds is known
+        let r_zero = prover.allocate_scalar(b"r_zero", if v == 0 { r } else { Scalar::ZERO });
+        let r_diff = prover.allocate_scalar(b"r_diff", if v == x { r - s } else { Scalar::ZERO });
+        let (V_var, V) = prover.allocate_point(b"V", V);
+        let (_, X) = prover.allocate_point(b"X", X);
+        let (D_var, _) = prover.allocate_point(b"D", V.decompress().unwrap() - X.decompress().unwrap());
+        let (B_var, _) = prover.allocate_point(b"B", B);
+        or(
+            &mut prover,
+            |cs| cs.constrain(V_var, vec![(r_zero, B_var)]),
+            |cs| cs.constrain(D_var, vec![(r_diff, B_var)]),
+        );
+
+        (prover.prove(), V, X)
+    }
+
+    fn verify_zero_or_equal(proof: &SigmaProof, V: CompressedRistretto, X: CompressedRistretto, B: RistrettoPoint) -> Result<(), ProofError> {
+        let mut transcript = Transcript::new(b"test");
+        let mut verifier = Verifier::new(b"ZeroOrEqual", &mut transcript);
+        let r_zero = verifier.allocate_scalar(b"r_zero");
+        let r_diff = verifier.allocate_scalar(b"r_diff");
+        let V_var = verifier.allocate_point(b"V", V)?;
+        verifier.allocate_point(b"X", X)?;
+        let D_var = verifier.allocate_point(b"D", (V.decompress().unwrap() - X.decompress().unwrap()).compress())?;
+        let B_var = verifier.allocate_point(b"B", B.compress())?;
+        or(
+            &mut verifier,
+            |cs| cs.constrain(V_var, vec![(r_zero, B_var)]),
+            |cs| cs.constrain(D_var, vec![(r_diff, B_var)]),
+        );
+        verifier.verify(proof)
+    }
+
+    #[test]
+    fn disjunction_of_statements() {
+        let B = RistrettoPoint::random(&mut thread_rng());
+        let r = Scalar::random(&mut thread_rng());
+        let s = Scalar::random(&mut thread_rng());
+
+        for (v, x, holds) in [(0, 5, true), (5, 5, true), (0, 0, true), (4, 5, false)].iter() {
+            let (proof, V, X) = zero_or_equal_proof(*v, r, *x, s, B);
+            assert_eq!(verify_zero_or_equal(&proof, V, X, B).is_ok(), *holds);
+
+            let parsed = SigmaProof::from_bytes(&proof.to_bytes()).unwrap();
+            assert_eq!(parsed.to_bytes(), proof.to_bytes());
+            assert_eq!(verify_zero_or_equal(&parsed, V, X, B).is_ok(), *holds);
+        }
+    }
+
+    /// Knowledge of `x` with `A = x * G`, and of the discrete logarithm of `C` or of `D` in
+    /// base `B`, or of `D` in base `G`.
+    fn composed_statement<CS: SchnorrCS>(cs: &mut CS, scalars: [ScalarVar; 4], A: PointVar, C: PointVar, D: PointVar, G: PointVar, B: PointVar) {
+        let [x, y, z, w] = scalars;
+        and(
+            cs,
+            |cs| cs.constrain(A, vec![(x, G)]),
+            |cs| any(cs, vec![
+                Box::new(move |cs: &mut CS| cs.constrain(C, vec![(y, B)])),
+                Box::new(move |cs: &mut CS| cs.constrain(D, vec![(z, B)])),
+                Box::new(move |cs: &mut CS| cs.constrain(D, vec![(w, G)])),
+            ]),
+        )
+    }
+
+    #[test]
+    fn conjunction_with_disjunction() {
+        let G = RISTRETTO_BASEPOINT_POINT;
+        let B = RistrettoPoint::random(&mut thread_rng());
+        let x = Scalar::random(&mut thread_rng());
+        let z = Scalar::random(&mut thread_rng());
+
+        let mut transcript = Transcript::new(b"test");
+        let mut prover = Prover::new(b"Composed", &mut transcript);
+        let scalars = [
+            prover.allocate_scalar(b"x", x),
+            prover.allocate_scalar(b"y", Scalar::ZERO),
+            prover.allocate_scalar(b"z", z),
+            prover.allocate_scalar(b"w", Scalar::ZERO),
+        ];
+        let (A, A_compressed) = prover.allocate_point(b"A", x * G);
+        let (C, C_compressed) = prover.allocate_point(b"C", RistrettoPoint::random(&mut thread_rng()));
+        let (D, D_compressed) = prover.allocate_point(b"D", z * B);
+        let (G_var, _) = prover.allocate_point(b"G", G);
+        let (B_var, _) = prover.allocate_point(b"B", B);
+        composed_statement(&mut prover, scalars, A, C, D, G_var, B_var);
+        let proof = prover.prove();
+        assert_eq!(proof.to_bytes().len(), 8 + 32 * (1 + 2 + 4));
+
+        let verify = |A_compressed| -> Result<(), ProofError> {
+            let mut transcript = Transcript::new(b"test");
+            let mut verifier = Verifier::new(b"Composed", &mut transcript);
+            let scalars = [
+                verifier.allocate_scalar(b"x"),
+                verifier.allocate_scalar(b"y"),
+                verifier.allocate_scalar(b"z"),
+                verifier.allocate_scalar(b"w"),
+            ];
+            let A = verifier.allocate_point(b"A", A_compressed)?;
+            let C = verifier.allocate_point(b"C", C_compressed)?;
+            let D = verifier.allocate_point(b"D", D_compressed)?;
+            let G_var = verifier.allocate_point(b"G", G.compress())?;
+            let B_var = verifier.allocate_point(b"B", B.compress())?;
+            composed_statement(&mut verifier, scalars, A, C, D, G_var, B_var);
+            verifier.verify(&proof)
+        };
+        assert!(verify(A_compressed).is_ok());
+        assert_eq!(verify((x * B).compress()), Err(ProofError::VerificationError));
+    }
+}
@@ -0,0 +1,194 @@
+//! The wiring of the commitments of a `zkSVMProver` into its sub-proofs.
+//!
+//! The sub-proofs share commitments, e.g. the `VarianceProof` takes the diff commitments of the
+//! `DiffProofs` and proves the standard deviations over its own commitments to the variances.
+//! This wiring is implicit in the code of the prover and of the verifier. The `StatementGraph`
+//! makes it explicit: the prover records, phase by phase, which set of commitments feeds which
+//! sub-proof, together with a digest of the commitments, and the graph is part of the encoding
+//! of the proof. The verifier records the commitments it feeds to each sub-proof in the same
+//! way, and rejects the proof if both graphs differ, see `StatementGraph::check`.
+
+use alloc::vec::Vec;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use ip_zk_proof::ProofError;
+use crate::algebraic_proofs::average_proof::AvgProof;
+use crate::algebraic_proofs::variance_proof::VarianceProof;
+use crate::utils::misc::compress_all;
+use merlin::Transcript;
+use serde::{Deserialize, Serialize};
+use core::fmt;
+
+/// A set of commitments of the statement, one per axis of each vector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CommitmentSet {
+    /// The commitments to the sensor vectors signed by the trusted module
+    Signed,
+    /// The commitments to the iterated sensor vectors, see `DiffProofs`
+    Iterated,
+    /// The commitments to the diff vectors, derived from the signed and iterated ones
+    Diff,
+    /// The last element of each diff vector times its base, see `DiffProofs::last_exp`
+    RemovedLast,
+    /// The commitments to the additions, under the `PedersenGens`
+    Additions,
+    /// The commitments to the additions under the accumulated bases of `G_vec`
+    AdditionsBaseG,
+    /// The commitments to the additions under the accumulated bases of `H_vec`
+    AdditionsBaseH,
+    /// The commitments to the variances
+    Variances,
+    /// The commitments to the standard deviations
+    Stds,
+}
+
+/// A sub-proof of a `zkSVMProver`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SubProof {
+    /// The `DiffProofs`
+    Diff,
+    /// The `AvgProof`
+    Average,
+    /// The inner product proofs of the `VarianceProof`
+    Variance,
+    /// The proofs of the standard deviations within the `VarianceProof`
+    Std,
+}
+
+/// A set of commitments fed into a sub-proof.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Wire {
+    pub commitments: CommitmentSet,
+    pub proof: SubProof,
+    /// Digest of the commitments, see `StatementGraph::record`
+    pub digest: [u8; 32],
+}
+
+/// The wires of a proof, in the order they were recorded.
+#[derive(Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StatementGraph {
+    wires: Vec<Wire>,
+}
+
+impl StatementGraph {
+    pub fn new() -> StatementGraph {
+        StatementGraph::default()
+    }
+
+    /// Records that `commitments`, whose points are given by sensor and axis, feed `proof`.
+    pub fn record<'a, I>(&mut self, commitments: CommitmentSet, proof: SubProof, points: I)
+    where
+        I: IntoIterator<Item = &'a Vec<CompressedRistretto>>,
+    {
+        let mut transcript = Transcript::new(b"zkSVM statement graph wire");
+        for (index, vector) in points.into_iter().enumerate() {
+            transcript.append_u64(b"vector", index as u64);
+            for point in vector {
+                transcript.append_message(b"commitment", point.as_bytes());
+            }
+        }
+        let mut digest = [0u8; 32];
+        transcript.challenge_bytes(b"digest", &mut digest);
+        self.wires.push(Wire { commitments, proof, digest });
+    }
+
+    pub fn wires(&self) -> &[Wire] {
+        &self.wires
+    }
+
+    /// The sets of commitments fed into `proof`.
+    pub fn inputs(&self, proof: SubProof) -> impl Iterator<Item = CommitmentSet> + '_ {
+        self.wires.iter().filter(move |w| w.proof == proof).map(|w| w.commitments)
+    }
+
+    /// Whether `commitments` feed `proof`.
+    pub fn feeds(&self, commitments: CommitmentSet, proof: SubProof) -> bool {
+        self.inputs(proof).any(|c| c == commitments)
+    }
+
+    /// Records the wires of the `DiffProofs`, which relate the signed commitments to the
+    /// iterated ones and to the diff commitments.
+    pub(crate) fn record_diff(
+        &mut self,
+        signed: &Vec<Vec<CompressedRistretto>>,
+        iterated: &Vec<Vec<CompressedRistretto>>,
+        diff: &Vec<Vec<CompressedRistretto>>,
+    ) {
+        self.record(CommitmentSet::Signed, SubProof::Diff, signed);
+        self.record(CommitmentSet::Iterated, SubProof::Diff, iterated);
+        self.record(CommitmentSet::Diff, SubProof::Diff, diff);
+    }
+
+    /// Records the wires of the `AvgProof`, which proves the additions under the three sets
+    /// of bases.
+    pub(crate) fn record_average(&mut self, proof_avg: &AvgProof) {
+        self.record(CommitmentSet::Additions, SubProof::Average, &proof_avg.average_commitment);
+        self.record(CommitmentSet::AdditionsBaseG, SubProof::Average, &compress_all(&proof_avg.average_commitment_base_G));
+        self.record(CommitmentSet::AdditionsBaseH, SubProof::Average, &compress_all(&proof_avg.average_commitment_base_H));
+    }
+
+    /// Records the wires of the `VarianceProof`: the variances are proven over the sensor
+    /// vectors, through their signed and diff commitments, and the additions under the bases
+    /// of `G_vec` and `H_vec`, and the standard deviations over the variances.
+    pub(crate) fn record_variance(
+        &mut self,
+        signed: &Vec<Vec<CompressedRistretto>>,
+        diff: &Vec<Vec<CompressedRistretto>>,
+        removed_last: &Vec<Vec<CompressedRistretto>>,
+        proof_avg: &AvgProof,
+        proof_variance: &VarianceProof,
+    ) {
+        self.record(CommitmentSet::Signed, SubProof::Variance, signed);
+        self.record(CommitmentSet::Diff, SubProof::Variance, diff);
+        self.record(CommitmentSet::RemovedLast, SubProof::Variance, removed_last);
+        self.record(CommitmentSet::AdditionsBaseG, SubProof::Variance, &compress_all(&proof_avg.average_commitment_base_G));
+        self.record(CommitmentSet::AdditionsBaseH, SubProof::Variance, &compress_all(&proof_avg.average_commitment_base_H));
+        self.record(CommitmentSet::Variances, SubProof::Variance, proof_variance.variance_commitment());
+        self.record(CommitmentSet::Variances, SubProof::Std, proof_variance.variance_commitment());
+        self.record(CommitmentSet::Stds, SubProof::Std, proof_variance.std_commitment());
+    }
+
+    /// Checks that the graph of the proof is `expected`, the graph recorded by the verifier.
+    /// Returns a `VerificationError` otherwise.
+    pub fn check(&self, expected: &StatementGraph) -> Result<(), ProofError> {
+        if self != expected {
+            return Err(ProofError::VerificationError);
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Wire {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} -> {:?}", self.commitments, self.proof)
+    }
+}
+
+/// Shows the wires, without their digests.
+impl fmt::Debug for StatementGraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.wires.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wires() {
+        let commitments = vec![vec![CompressedRistretto([1u8; 32]); 3]; 2];
+        let mut graph = StatementGraph::new();
+        graph.record(CommitmentSet::Signed, SubProof::Diff, &commitments);
+        graph.record(CommitmentSet::Variances, SubProof::Std, &commitments);
+        assert!(graph.feeds(CommitmentSet::Signed, SubProof::Diff));
+        assert!(!graph.feeds(CommitmentSet::Signed, SubProof::Std));
+        assert_eq!(graph.inputs(SubProof::Std).collect::<Vec<_>>(), vec![CommitmentSet::Variances]);
+        assert_eq!(format!("{:?}", graph), "[Signed -> Diff, Variances -> Std]");
+
+        let mut expected = StatementGraph::new();
+        expected.record(CommitmentSet::Signed, SubProof::Diff, &commitments);
+        expected.record(CommitmentSet::Variances, SubProof::Std, &commitments[..1]);
+        assert_eq!(graph.check(&expected), Err(ProofError::VerificationError));
+        assert_eq!(graph.wires()[0], expected.wires()[0]);
+    }
+}
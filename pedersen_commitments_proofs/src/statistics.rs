@@ -0,0 +1,107 @@
+//! The statistics of the vectors proven by a `zkSVMProver`.
+//!
+//! A proof declares the statistics it proves as a `StatisticSet`, which is part of its encoding
+//! and of the transcripts of the sub-proofs that prove them. A verifier compares it with the
+//! statistics its model is evaluated over, see `SvmModel::required_statistics`, before
+//! accepting the proof, see `zkSVMProver::verify_covering`.
+
+use serde::{Deserialize, Serialize};
+
+/// A statistic of each axis of a vector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Statistic {
+    /// The sum of the readings, of each evaluated vector
+    Addition,
+    /// The floored standard deviation, of each sensor vector
+    Std,
+}
+
+/// A set of statistics of the vectors, as flags: bit `i` is the addition of evaluated vector
+/// `i`, and bit `32 + i` the standard deviation of sensor vector `i`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StatisticSet(u64);
+
+impl Statistic {
+    fn offset(self) -> usize {
+        match self {
+            Statistic::Addition => 0,
+            Statistic::Std => StatisticSet::MAX_VECTORS,
+        }
+    }
+}
+
+impl StatisticSet {
+    /// Number of vectors a set has flags for.
+    pub const MAX_VECTORS: usize = 32;
+
+    /// The set without any statistic.
+    pub const fn empty() -> StatisticSet {
+        StatisticSet(0)
+    }
+
+    /// Adds `statistic` of `vector`.
+    ///
+    /// Panics if `vector` is not below `MAX_VECTORS`.
+    pub fn with(self, statistic: Statistic, vector: usize) -> StatisticSet {
+        assert!(vector < StatisticSet::MAX_VECTORS, "There are flags for {} vectors.", StatisticSet::MAX_VECTORS);
+        StatisticSet(self.0 | 1 << (statistic.offset() + vector))
+    }
+
+    /// Adds `statistic` of the first `nr_vectors` vectors.
+    ///
+    /// Panics if `nr_vectors` is above `MAX_VECTORS`.
+    pub fn with_all(self, statistic: Statistic, nr_vectors: usize) -> StatisticSet {
+        (0..nr_vectors).fold(self, |set, vector| set.with(statistic, vector))
+    }
+
+    /// Whether the set has `statistic` of `vector`.
+    pub fn contains(&self, statistic: Statistic, vector: usize) -> bool {
+        vector < StatisticSet::MAX_VECTORS && self.0 & 1 << (statistic.offset() + vector) != 0
+    }
+
+    /// Whether the set has all the statistics of `other`.
+    pub fn covers(&self, other: &StatisticSet) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// The statistics of either set.
+    pub fn union(self, other: StatisticSet) -> StatisticSet {
+        StatisticSet(self.0 | other.0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// The flags of the set.
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// The set of the given flags, see `bits`.
+    pub fn from_bits(bits: u64) -> StatisticSet {
+        StatisticSet(bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags() {
+        let set = StatisticSet::empty().with_all(Statistic::Addition, 8).with(Statistic::Std, 2);
+        assert_eq!(set.bits(), 0xff | 1 << 34);
+        assert!(set.contains(Statistic::Addition, 7));
+        assert!(!set.contains(Statistic::Addition, 8));
+        assert!(set.contains(Statistic::Std, 2));
+        assert!(!set.contains(Statistic::Std, 40));
+
+        let required = StatisticSet::empty().with(Statistic::Addition, 3).with(Statistic::Std, 2);
+        assert!(set.covers(&required));
+        assert!(!required.covers(&set));
+        assert!(!set.covers(&required.with(Statistic::Std, 1)));
+        assert_eq!(required.union(set), set);
+        assert!(StatisticSet::default().is_empty());
+    }
+}
@@ -1,143 +1,455 @@
-#[allow(non_snake_case)]
-use crate::utils::commitment_fns::{multiple_commit};
+#![allow(non_snake_case)]
+use alloc::vec::Vec;
+use alloc::vec;
+#[cfg(feature = "std")]
+use crate::utils::commitment_fns::multiple_commit;
+use crate::utils::compression::{compress, decompress};
 use crate::utils::misc::*;
 use crate::algebraic_proofs::variance_proof::VarianceProof;
 use crate::algebraic_proofs::diff_vector_gen_proof::*;
 use crate::algebraic_proofs::average_proof::*;
+use crate::svm_proof::decision_proof::FeatureBlindings;
+use crate::svm_proof::session::ProverSession;
+use crate::metrics::ProverMetrics;
+#[cfg(feature = "std")]
+use crate::metrics::NoMetrics;
 
-use crate::PedersenVecGens;
+use crate::{PedersenConfig, PublicParams, StatementGraph, Statistic, StatisticSet};
+#[cfg(feature = "std")]
+use crate::{PedersenVecGens, TranscriptLabels};
 
-use ip_zk_proof::{BulletproofGens, PedersenGens, ProofError};
+use ip_zk_proof::{CanonicalPoints, ProofError, SerializedSize};
+#[cfg(feature = "std")]
+use ip_zk_proof::TranscriptProtocol;
 
 use curve25519_dalek::scalar::Scalar;
-use curve25519_dalek::ristretto::{CompressedRistretto};
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 
+#[cfg(feature = "std")]
+use merlin::Transcript;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
 use rand::thread_rng;
-use std::time::{Duration, Instant};
+use rand_core::CryptoRngCore;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+/// Version of the encodings of `to_bytes` and `to_cbor`.
+const ENCODING_VERSION: u8 = 8;
+
+/// The commitments to the standard deviations of a proof that does not prove them.
+static NO_COMMITMENTS: Vec<Vec<CompressedRistretto>> = Vec::new();
+
+/// The fields of a `zkSVMProver` that make up the proof, as encoded by `to_bytes` and
+/// `to_cbor`.
+#[derive(Serialize, Deserialize)]
+struct EncodedProof {
+    version: u8,
+    header: ProofHeader,
+    fingerprint: [u8; 32],
+    signed_commitments: Vec<Vec<CompressedRistretto>>,
+    proof_diff: DiffProofs,
+    proof_avg: AvgProof,
+    range_bitsize: usize,
+    proof_variance: Option<VarianceProof>,
+    statistics: StatisticSet,
+    statement_graph: StatementGraph,
+}
+
+/// The sizes of the statement of a `zkSVMProver`, at the start of its encoding. They are
+/// declared in the transcripts of all its sub-proofs, see `TranscriptLabels::with_sizes`, and
+/// the proof is checked against them before its sub-proofs are verified, so that a proof of
+/// inconsistent sizes is rejected instead of making the verifier panic.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProofHeader {
+    /// Size of the vectors, which is the size of the parameters
+    pub size: usize,
+    /// Number of readings of each evaluated vector
+    pub size_sensors: Vec<usize>,
+    /// Number of evaluated vectors: the sensor vectors, followed by their diff vectors
+    pub length_all_vectors: usize,
+}
+
+impl ProofHeader {
+    /// The header of a proof over vectors of `size` elements, with `size_sensors` readings.
+    pub fn new(size: usize, size_sensors: &[usize]) -> ProofHeader {
+        ProofHeader { size, size_sensors: size_sensors.to_vec(), length_all_vectors: size_sensors.len() }
+    }
+
+    /// The sizes as declared in the transcripts: the size, the number of vectors, and the
+    /// readings of each vector.
+    pub(crate) fn sizes(&self) -> Vec<u64> {
+        let mut sizes = vec![self.size as u64, self.length_all_vectors as u64];
+        sizes.extend(self.size_sensors.iter().map(|&s| s as u64));
+        sizes
+    }
+
+    /// Checks that the sizes are consistent: there are readings for each vector, between one
+    /// and `size` of them, and each sensor vector has its diff vector. Returns a `FormatError`
+    /// otherwise.
+    pub(crate) fn check(&self) -> Result<(), ProofError> {
+        if self.length_all_vectors != self.size_sensors.len()
+            || !self.length_all_vectors.is_multiple_of(2)
+            || self.length_all_vectors > StatisticSet::MAX_VECTORS
+            || self.size_sensors.iter().any(|&s| s == 0 || s > self.size)
+        {
+            return Err(ProofError::FormatError);
+        }
+        Ok(())
+    }
+}
 
 /// This is the prover structure. It will generate a proof that the
 /// model was evaluated correctly.
 #[derive(Clone)]
 pub struct zkSVMProver {
-    // Generators used for inner product proofs
-    bp_generators: BulletproofGens,
-    // Pedersen generators used for single value commitments
-    ped_generators: PedersenGens,
+    // Generators used for the whole proof, with the precomputed summed bases
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(super) config: PedersenConfig,
     // Commitments signed by the TPM
-    signed_commitments: Vec<Vec<CompressedRistretto>>,
+    pub(super) signed_commitments: Vec<Vec<CompressedRistretto>>,
     // Diff proofs, containing the diff commitments and the proofs to achieve correctness
-    proof_diff: DiffProofs,
+    pub(super) proof_diff: DiffProofs,
     // // Proofs of average computations
-    proof_avg: AvgProof,
-    // Proof of variance computations (inside is the proof of stds)
-    proof_variance: VarianceProof,
-    // time computing the hash in millis
-    pub hash_computation_time: Duration,
-    // Time computing the proof
-    pub proof_computation_time: Duration,
+    pub(super) proof_avg: AvgProof,
+    // Bitsize of the range proofs of the stds
+    pub(super) range_bitsize: usize,
+    // Proof of variance computations (inside is the proof of stds), if the stds are proven
+    pub(super) proof_variance: Option<VarianceProof>,
     // size of the vectors. this is equal for all sensors
-    size: usize,
+    pub(super) size: usize,
     // number of sensor elements in each vector. This is different per vector
-    size_sensors: Vec<usize>,
+    pub(super) size_sensors: Vec<usize>,
+    // Commitments fed into each sub-proof, checked against those of the verifier
+    pub(super) statement_graph: StatementGraph,
+    // Fingerprint of the configuration the proof was created under
+    pub(super) fingerprint: [u8; 32],
 }
 
 impl zkSVMProver {
+    #[cfg(feature = "std")]
     pub fn new(
-        input_vector: &Vec<[Vec<Scalar>; 3]>,
-        non_zero_elements: &Vec<usize>,
-        diff_vector_scalar: &Vec<[Vec<Scalar>; 3]>,
+        input_vector: &[[Vec<Scalar>; 3]],
+        non_zero_elements: &[usize],
+        diff_vector_scalar: &[[Vec<Scalar>; 3]],
         additions: &Vec<Vec<Scalar>>,
         variances: &Vec<Vec<Scalar>>,
         sensor_vectors_stds: &Vec<Vec<Scalar>>,
     ) -> Result<zkSVMProver, ProofError> {
-        let size_vectors = input_vector[0][0].len();
-        let length_all_vectors = input_vector.len();
-
-        // We begin by creating the generators. This should have the option of taking them from an
-        // outer source.
-
-        let ped_generators_signature = PedersenVecGens::new(size_vectors);
-        let H_vec = PedersenVecGens::new_random(size_vectors);
-        let bp_generators = BulletproofGens {
-            gens_capacity: size_vectors,
-            party_capacity: 1,
-            G_vec: vec![ped_generators_signature.clone().B],
-            H_vec: vec![H_vec.clone().B],
-        };
-        let ped_generators = PedersenGens::default();
+        zkSVMProver::new_with_labels(
+            input_vector,
+            non_zero_elements,
+            diff_vector_scalar,
+            additions,
+            variances,
+            sensor_vectors_stds,
+            TranscriptLabels::default(),
+        )
+    }
 
-        // This is performed by the trusted module, but only the prover can have access to the
-        // blinding factors. We only hash the initial sensors, which are the first half
+    /// As `new`, with the transcripts of all the proofs created under the given labels. The
+    /// proof is then verified under the same labels.
+    #[cfg(feature = "std")]
+    pub fn new_with_labels(
+        input_vector: &[[Vec<Scalar>; 3]],
+        non_zero_elements: &[usize],
+        diff_vector_scalar: &[[Vec<Scalar>; 3]],
+        additions: &Vec<Vec<Scalar>>,
+        variances: &Vec<Vec<Scalar>>,
+        sensor_vectors_stds: &Vec<Vec<Scalar>>,
+        labels: TranscriptLabels,
+    ) -> Result<zkSVMProver, ProofError> {
+        zkSVMProver::new_with_params(
+            &PublicParams::new(input_vector[0][0].len()).with_labels(labels),
+            input_vector,
+            non_zero_elements,
+            diff_vector_scalar,
+            additions,
+            variances,
+            sensor_vectors_stds,
+        )
+    }
 
-        let mut now = Instant::now();
-        let all_signed_hash: (Vec<Vec<CompressedRistretto>>, Vec<Vec<Scalar>>) = multiple_commit(
-            &ped_generators_signature,
-            &input_vector[..(length_all_vectors / 2)].to_vec()
-        );
-        let hash_computation_time = now.elapsed();
-        now = Instant::now();
-
-        // Now we generate the diff_vectors
-        let (proof_diff, diff_blindings) = DiffProofs::create(
-            &input_vector[..(length_all_vectors / 2)].to_vec(),
-            &diff_vector_scalar,
-            &all_signed_hash.0,
-            &all_signed_hash.1,
-            &ped_generators_signature,
-            &non_zero_elements
-        );
+    /// As `new`, with the generators and the labels of the given public parameters, which
+    /// the verifier then passes to `verify_with_params`. The size of the parameters must be
+    /// the size of the vectors.
+    #[cfg(feature = "std")]
+    pub fn new_with_params(
+        params: &PublicParams,
+        input_vector: &[[Vec<Scalar>; 3]],
+        non_zero_elements: &[usize],
+        diff_vector_scalar: &[[Vec<Scalar>; 3]],
+        additions: &Vec<Vec<Scalar>>,
+        variances: &Vec<Vec<Scalar>>,
+        sensor_vectors_stds: &Vec<Vec<Scalar>>,
+    ) -> Result<zkSVMProver, ProofError> {
+        if params.size() != input_vector[0][0].len() {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
 
-        let add_comm_blinding: Vec<Vec<Scalar>> = (0..length_all_vectors).map(
-            |_| (0..3).map(
-                |_| Scalar::random(&mut thread_rng())
-            ).collect()
-        ).collect();
+        zkSVMProver::create_with_config(
+            params.config(),
+            None,
+            &mut thread_rng(),
+            &mut NoMetrics,
+            input_vector,
+            non_zero_elements,
+            diff_vector_scalar,
+            additions,
+            variances,
+            sensor_vectors_stds,
+        ).map(|(prover, _)| prover)
+    }
 
-        let mut blind_factors_all_vectors = all_signed_hash.1.clone();
-        blind_factors_all_vectors.append(&mut diff_blindings.clone());
+    /// As `new_with_params`, with the blinding factors of the signed commitments given by the
+    /// trusted module, e.g. as exported by `hash_init_vectors_with_blindings`, instead of drawn
+    /// by the prover. `signed_blindings` has three factors (one per axis) for each of the
+    /// signed sensor vectors, which are the first half of `input_vector`.
+    #[cfg(feature = "std")]
+    pub fn new_with_signed_blindings(
+        params: &PublicParams,
+        signed_blindings: &Vec<Vec<Scalar>>,
+        input_vector: &[[Vec<Scalar>; 3]],
+        non_zero_elements: &[usize],
+        diff_vector_scalar: &[[Vec<Scalar>; 3]],
+        additions: &Vec<Vec<Scalar>>,
+        variances: &Vec<Vec<Scalar>>,
+        sensor_vectors_stds: &Vec<Vec<Scalar>>,
+    ) -> Result<zkSVMProver, ProofError> {
+        if params.size() != input_vector[0][0].len() {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
 
-        // Now we calculate the average proof
-        let average_proof = AvgProof::create(
-            &non_zero_elements,
-            &bp_generators,
-            &ped_generators,
-            &input_vector,
-            &add_comm_blinding,
-            &blind_factors_all_vectors,
-        );
+        zkSVMProver::create_with_config(
+            params.config(),
+            Some(signed_blindings),
+            &mut thread_rng(),
+            &mut NoMetrics,
+            input_vector,
+            non_zero_elements,
+            diff_vector_scalar,
+            additions,
+            variances,
+            sensor_vectors_stds,
+        ).map(|(prover, _)| prover)
+    }
 
-        let variance_proof = VarianceProof::create(
-            &input_vector,
-            &sensor_vectors_stds,
-            &additions,
-            &variances,
-            &bp_generators,
-            &ped_generators,
-            &ped_generators_signature,
-            &H_vec,
-            &all_signed_hash.1,
-            &diff_blindings,
-            &non_zero_elements,
-            size_vectors
-        )?;
+    /// As `new_with_params`, reporting the duration, the number of commitments and sub-proofs,
+    /// and the size of each phase of the proof to `metrics`, see `ProverMetrics`.
+    #[cfg(feature = "std")]
+    pub fn new_with_metrics(
+        params: &PublicParams,
+        metrics: &mut dyn ProverMetrics,
+        input_vector: &[[Vec<Scalar>; 3]],
+        non_zero_elements: &[usize],
+        diff_vector_scalar: &[[Vec<Scalar>; 3]],
+        additions: &Vec<Vec<Scalar>>,
+        variances: &Vec<Vec<Scalar>>,
+        sensor_vectors_stds: &Vec<Vec<Scalar>>,
+    ) -> Result<zkSVMProver, ProofError> {
+        if params.size() != input_vector[0][0].len() {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        zkSVMProver::create_with_config(
+            params.config(),
+            None,
+            &mut thread_rng(),
+            metrics,
+            input_vector,
+            non_zero_elements,
+            diff_vector_scalar,
+            additions,
+            variances,
+            sensor_vectors_stds,
+        ).map(|(prover, _)| prover)
+    }
+
+    /// As `new_with_metrics`, with all the randomness of the proof drawn from `rng` instead of
+    /// the thread RNG. This, with a `metrics` that does not read the system clock (e.g.
+    /// `NoMetrics`, or any metrics given a `Clock` with `WithClock`), is how the proof is
+    /// created on targets without an operating system, such as `wasm32-unknown-unknown`.
+    pub fn new_with_rng(
+        params: &PublicParams,
+        rng: &mut dyn CryptoRngCore,
+        metrics: &mut dyn ProverMetrics,
+        input_vector: &[[Vec<Scalar>; 3]],
+        non_zero_elements: &[usize],
+        diff_vector_scalar: &[[Vec<Scalar>; 3]],
+        additions: &Vec<Vec<Scalar>>,
+        variances: &Vec<Vec<Scalar>>,
+        sensor_vectors_stds: &Vec<Vec<Scalar>>,
+    ) -> Result<zkSVMProver, ProofError> {
+        if params.size() != input_vector[0][0].len() {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        zkSVMProver::create_with_config(
+            params.config(),
+            None,
+            rng,
+            metrics,
+            input_vector,
+            non_zero_elements,
+            diff_vector_scalar,
+            additions,
+            variances,
+            sensor_vectors_stds,
+        ).map(|(prover, _)| prover)
+    }
+
+    /// As `new_with_params`, returning as well the blinding factors of the commitments to the
+    /// features, for the proofs about the model evaluated over them.
+    #[cfg(feature = "std")]
+    pub(crate) fn new_with_feature_blindings(
+        params: &PublicParams,
+        input_vector: &[[Vec<Scalar>; 3]],
+        non_zero_elements: &[usize],
+        diff_vector_scalar: &[[Vec<Scalar>; 3]],
+        additions: &Vec<Vec<Scalar>>,
+        variances: &Vec<Vec<Scalar>>,
+        sensor_vectors_stds: &Vec<Vec<Scalar>>,
+    ) -> Result<(zkSVMProver, FeatureBlindings), ProofError> {
+        if params.size() != input_vector[0][0].len() {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        zkSVMProver::create_with_config(
+            params.config(),
+            None,
+            &mut thread_rng(),
+            &mut NoMetrics,
+            input_vector,
+            non_zero_elements,
+            diff_vector_scalar,
+            additions,
+            variances,
+            sensor_vectors_stds,
+        )
+    }
+
+    /// As `new_with_params`, with all the proofs bound to the current state of `transcript`,
+    /// e.g. the transcript of a larger protocol. The signed commitments are then appended to
+    /// `transcript`, so that the rest of the protocol depends on them. The verifier passes its
+    /// transcript, in the same state, to `verify_with_transcript`.
+    #[cfg(feature = "std")]
+    pub fn new_with_transcript(
+        params: &PublicParams,
+        transcript: &mut Transcript,
+        input_vector: &[[Vec<Scalar>; 3]],
+        non_zero_elements: &[usize],
+        diff_vector_scalar: &[[Vec<Scalar>; 3]],
+        additions: &Vec<Vec<Scalar>>,
+        variances: &Vec<Vec<Scalar>>,
+        sensor_vectors_stds: &Vec<Vec<Scalar>>,
+    ) -> Result<zkSVMProver, ProofError> {
+        if params.size() != input_vector[0][0].len() {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        let labels = params.labels().bind_to(transcript);
+        let prover = zkSVMProver::create_with_config(
+            params.config().with_labels(labels),
+            None,
+            &mut thread_rng(),
+            &mut NoMetrics,
+            input_vector,
+            non_zero_elements,
+            diff_vector_scalar,
+            additions,
+            variances,
+            sensor_vectors_stds,
+        )?.0;
+        append_signed_commitments(transcript, &prover.signed_commitments);
+
+        Ok(prover)
+    }
+
+    pub(super) fn create_with_config(
+        config: PedersenConfig,
+        signed_blindings: Option<&Vec<Vec<Scalar>>>,
+        rng: &mut dyn CryptoRngCore,
+        metrics: &mut dyn ProverMetrics,
+        input_vector: &[[Vec<Scalar>; 3]],
+        non_zero_elements: &[usize],
+        diff_vector_scalar: &[[Vec<Scalar>; 3]],
+        additions: &Vec<Vec<Scalar>>,
+        variances: &Vec<Vec<Scalar>>,
+        sensor_vectors_stds: &Vec<Vec<Scalar>>,
+    ) -> Result<(zkSVMProver, FeatureBlindings), ProofError> {
+        let _span = phase_span!("zksvm_prove", size = input_vector[0][0].len());
+
+        let session = ProverSession::commit_with_config(config, signed_blindings, rng, metrics, input_vector, non_zero_elements)?
+            .prove_diff(diff_vector_scalar, rng, metrics)
+            .prove_averages(rng, metrics)?
+            .prove_variances(additions, variances, sensor_vectors_stds, rng, metrics)?;
+
+        Ok(session.finish_with_feature_blindings())
+    }
+
+    /// Serializes the proof in a compact binary encoding (bincode), without the generators.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode_serialize(&self.encoded())
+            .expect("Should never happen, as all the fields can be serialized.")
+    }
+
+    /// Parses a proof serialized with `to_bytes`, which is then verified under the given public
+    /// parameters.
+    pub fn from_bytes(bytes: &[u8], params: &PublicParams) -> Result<zkSVMProver, ProofError> {
+        let proof: EncodedProof = bincode_deserialize(bytes).map_err(|_| ProofError::FormatError)?;
+        proof.into_prover(params)
+    }
+
+    /// As `from_bytes`, rejecting proofs with non-canonical points, see `CanonicalPoints`.
+    pub fn from_bytes_strict(bytes: &[u8], params: &PublicParams) -> Result<zkSVMProver, ProofError> {
+        check_canonical(zkSVMProver::from_bytes(bytes, params)?)
+    }
+
+    /// As `to_bytes`, with the repeated points stored only once, see `compression::compress`.
+    pub fn to_compressed_bytes(&self) -> Vec<u8> {
+        compress(&self.to_bytes())
+    }
+
+    /// Parses a proof serialized with `to_compressed_bytes`.
+    pub fn from_compressed_bytes(bytes: &[u8], params: &PublicParams) -> Result<zkSVMProver, ProofError> {
+        zkSVMProver::from_bytes(&decompress(bytes)?, params)
+    }
 
+    /// Encodes the proof in CBOR, as a map with the named fields of the proof, where each
+    /// sub-proof is again a map and the proofs of each sensor are arrays. The encoding is
+    /// self-describing, for verifiers in other languages and to inspect the proofs.
+    #[cfg(feature = "cbor")]
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        ciborium::ser::into_writer(&self.encoded(), &mut buf)
+            .expect("Should never happen, as writing into a vector does not fail.");
+        buf
+    }
 
-        let proof_computation_time = now.elapsed();
+    /// Decodes a proof encoded with `to_cbor`, as `from_bytes`.
+    #[cfg(feature = "cbor")]
+    pub fn from_cbor(bytes: &[u8], params: &PublicParams) -> Result<zkSVMProver, ProofError> {
+        let proof: EncodedProof = ciborium::de::from_reader(bytes).map_err(|_| ProofError::FormatError)?;
+        proof.into_prover(params)
+    }
 
-        Ok(zkSVMProver {
-            bp_generators: bp_generators,
-            ped_generators: ped_generators,
-            signed_commitments: all_signed_hash.0,
-            proof_diff: proof_diff,
-            proof_avg: average_proof,
-            proof_variance: variance_proof,
-            hash_computation_time: hash_computation_time,
-            proof_computation_time: proof_computation_time,
-            size: size_vectors,
-            size_sensors: non_zero_elements.clone(),
-        })
+    fn encoded(&self) -> EncodedProof {
+        EncodedProof {
+            version: ENCODING_VERSION,
+            header: self.header(),
+            fingerprint: self.fingerprint,
+            signed_commitments: self.signed_commitments.clone(),
+            proof_diff: self.proof_diff.clone(),
+            proof_avg: self.proof_avg.clone(),
+            range_bitsize: self.range_bitsize,
+            proof_variance: self.proof_variance.clone(),
+            statistics: self.statistics(),
+            statement_graph: self.statement_graph.clone(),
+        }
     }
 
+    #[cfg(feature = "std")]
     pub fn hash_init_vectors(ped_gens_signature: PedersenVecGens, all_sensor_vectors: Vec<[Vec<Scalar>; 3]>) -> Vec<Vec<CompressedRistretto>> {
         multiple_commit(
             &ped_gens_signature,
@@ -145,65 +457,361 @@ impl zkSVMProver {
         ).0
     }
 
+    /// Computes the signed commitments as `hash_init_vectors`, and returns them together with
+    /// their blinding factors, three independent ones per sensor vector. This is the phase of
+    /// the trusted module, which hands the blindings to the prover for
+    /// `new_with_signed_blindings`.
+    #[cfg(feature = "std")]
+    pub fn hash_init_vectors_with_blindings(
+        ped_gens_signature: &PedersenVecGens,
+        all_sensor_vectors: &[[Vec<Scalar>; 3]],
+    ) -> (Vec<Vec<CompressedRistretto>>, Vec<Vec<Scalar>>) {
+        multiple_commit(ped_gens_signature, all_sensor_vectors)
+    }
+
+    /// The commitments to the sensor vectors signed by the trusted module.
+    pub fn signed_commitments(&self) -> &Vec<Vec<CompressedRistretto>> {
+        &self.signed_commitments
+    }
+
+    /// The commitments to the additions of each axis of each evaluated vector, and to the
+    /// standard deviations of each axis of each sensor vector, under the `PedersenGens` of
+    /// the parameters. These are the features over which the model is evaluated. There are no
+    /// commitments to the standard deviations if they are not proven, see `proves_stds`.
+    pub fn feature_commitments(&self) -> (&Vec<Vec<CompressedRistretto>>, &Vec<Vec<CompressedRistretto>>) {
+        (&self.proof_avg.average_commitment, self.proof_variance.as_ref().map_or(&NO_COMMITMENTS, |p| p.std_commitment()))
+    }
+
+    /// The sizes of the statement of the proof.
+    pub fn header(&self) -> ProofHeader {
+        ProofHeader::new(self.size, &self.size_sensors)
+    }
+
+    /// Fingerprint of the parameters the proof was created under, see
+    /// `PublicParams::fingerprint`.
+    pub fn fingerprint(&self) -> &[u8; 32] {
+        &self.fingerprint
+    }
+
+    /// Which commitments feed which sub-proof, as recorded by the prover.
+    pub fn statement_graph(&self) -> &StatementGraph {
+        &self.statement_graph
+    }
+
+    /// Whether the proof covers the standard deviations of the sensor vectors, or only the
+    /// additions, see `zkSVMProverBuilder::without_stds`.
+    pub fn proves_stds(&self) -> bool {
+        self.proof_variance.is_some()
+    }
+
+    /// The statistics the proof covers: the additions of all the evaluated vectors, and the
+    /// standard deviations of all the sensor vectors if it proves them. These are declared in
+    /// the transcripts of the proofs of the statistics.
+    pub fn statistics(&self) -> StatisticSet {
+        let additions = additions(self.size_sensors.len());
+        match self.proof_variance {
+            Some(_) => additions.union(stds(self.signed_commitments.len())),
+            None => additions,
+        }
+    }
+
+    /// Verifies the proof as `verify_with_params`, after checking that it covers the
+    /// `required` statistics, see `SvmModel::required_statistics`. Returns a
+    /// `VerificationError` if it does not.
+    #[cfg(feature = "std")]
+    pub fn verify_covering(self, params: &PublicParams, required: &StatisticSet) -> Result<(), ProofError> {
+        if !self.statistics().covers(required) {
+            return Err(ProofError::VerificationError);
+        }
+        self.verify_with_params(params)
+    }
+
+    /// Verifies the proof as `verify_with_params`, after checking that it is about a statement
+    /// of the `expected` sizes, e.g. those the verifier knows from the windows of the sensors.
+    /// Returns a `VerificationError` if it is not.
+    #[cfg(feature = "std")]
+    pub fn verify_with_header(self, params: &PublicParams, expected: &ProofHeader) -> Result<(), ProofError> {
+        if &self.header() != expected {
+            return Err(ProofError::VerificationError);
+        }
+        self.verify_with_params(params)
+    }
+
+    /// Verifies the proof under the generators it was created with.
+    #[cfg(feature = "std")]
     pub fn verify(self) -> Result<(), ProofError>{
-        let ped_gens_signature = PedersenVecGens {
-            size: self.size,
-            B: self.bp_generators.G_vec[0].clone(),
-            B_blinding: self.ped_generators.B_blinding
-        };
+        let config = self.config.clone();
+        self.verify_with_config(&config, &mut thread_rng())
+    }
 
-        let H_vec = PedersenVecGens{
-            size: self.size,
-            B: self.bp_generators.H_vec[0].clone(),
-            B_blinding: self.ped_generators.B_blinding
-        };
+    /// Verifies the proof under the given public parameters, instead of the generators
+    /// carried by the prover.
+    #[cfg(feature = "std")]
+    pub fn verify_with_params(self, params: &PublicParams) -> Result<(), ProofError> {
+        if params.size() != self.size {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        self.verify_with_config(&params.config(), &mut thread_rng())
+    }
+
+    /// As `verify_with_params`, with the randomness of the batched verifications drawn from
+    /// `rng` instead of the thread RNG.
+    pub fn verify_with_rng(self, params: &PublicParams, rng: &mut dyn CryptoRngCore) -> Result<(), ProofError> {
+        if params.size() != self.size {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        self.verify_with_config(&params.config(), rng)
+    }
 
-        let mut multiply_ped_sign_acc_bases_G = self.ped_generators.B_blinding;
-        for base in self.bp_generators.G_vec[0].clone() {
-            multiply_ped_sign_acc_bases_G += &base;
+    /// Verifies a proof created with `new_with_transcript`, with `transcript` in the same state
+    /// as the one of the prover. The signed commitments are then appended to `transcript`.
+    #[cfg(feature = "std")]
+    pub fn verify_with_transcript(
+        self,
+        params: &PublicParams,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        if params.size() != self.size {
+            return Err(ProofError::InvalidGeneratorsLength);
         }
 
-        let mut multiply_ped_acc_bases_H = self.ped_generators.B_blinding;
-        for base in self.bp_generators.H_vec[0].clone() {
-            multiply_ped_acc_bases_H += &base;
+        let labels = params.labels().bind_to(transcript);
+        let signed_commitments = self.signed_commitments.clone();
+        self.verify_with_config(&params.config().with_labels(labels), &mut thread_rng())?;
+        append_signed_commitments(transcript, &signed_commitments);
+
+        Ok(())
+    }
+
+    fn verify_with_config(self, config: &PedersenConfig, rng: &mut dyn CryptoRngCore) -> Result<(), ProofError> {
+        let _span = phase_span!("zksvm_verify", size = self.size);
+        let header = self.header();
+        if header.size != config.size() {
+            return Err(ProofError::InvalidGeneratorsLength);
         }
+        if self.fingerprint != config.fingerprint() {
+            return Err(ProofError::InvalidGenerators);
+        }
+        self.check_shapes(&header)?;
+        let config = &config.clone().with_labels(config.labels().with_sizes(&header.sizes()));
+        let bp_generators = config.get_bp_gens();
+        let ped_generators = *config.pedersen_gens();
+        let ped_gens_signature = config.G_vec();
+        let H_vec = config.H_vec();
+
+        // Commitments are decompressed once and shared across all sub-proofs
+        let signed_commitments = decompress_all(&self.signed_commitments)?;
+        let iter_commitments = decompress_all(&self.proof_diff.iter_commitments)?;
 
         // Then it generates the diff commitments from the provably iterated commitments
-        let diff_commitments: Vec<Vec<CompressedRistretto>> = all_sensors_diff_comm(
-            &self.signed_commitments,
-            &self.proof_diff.iter_commitments
+        let diff_commitments: Vec<Vec<RistrettoPoint>> = all_sensors_diff_points(
+            &signed_commitments,
+            &iter_commitments
         );
 
-        self.proof_diff.clone().verify(
+        // The sub-proofs must take the commitments the prover fed them
+        let mut graph = StatementGraph::new();
+        let compressed_diff_commitments = compress_all(&diff_commitments);
+        graph.record_diff(&self.signed_commitments, &self.proof_diff.iter_commitments, &compressed_diff_commitments);
+        graph.record_average(&self.proof_avg);
+        if let Some(proof_variance) = &self.proof_variance {
+            graph.record_variance(
                 &self.signed_commitments,
+                &compressed_diff_commitments,
+                &compress_all(&self.proof_diff.last_exp()),
+                &self.proof_avg,
+                proof_variance,
+            );
+        }
+        self.statement_graph.check(&graph)?;
+
+        self.proof_diff.clone().verify_with_rng(
+                &signed_commitments,
+                &iter_commitments,
                 &diff_commitments,
-                &ped_gens_signature,
-                &self.size_sensors
+                config,
+                &self.size_sensors,
+                rng,
             )?;
 
-        let length_all_vectors = self.proof_avg.average_commitment.len();
-        self.proof_avg.verify(
-            &self.bp_generators,
-            &self.ped_generators,
+        self.proof_avg.verify_with_rng(
+            &bp_generators,
+            &ped_generators,
+            &config.acc_bases_G(&self.size_sensors),
+            &config.acc_bases_H(&self.size_sensors),
             self.size,
-            &self.size_sensors
+            &config.labels().with_statistics(additions(self.size_sensors.len())),
+            rng,
         )?;
 
-        self.proof_variance.verify(
-            &self.signed_commitments,
+        let proof_variance = match self.proof_variance {
+            Some(proof_variance) => proof_variance,
+            None => return Ok(()),
+        };
+        proof_variance.verify_with_rng(
+            &signed_commitments,
             &diff_commitments,
-            &self.proof_diff.last_exp,
+            &self.proof_diff.last_exp(),
             &self.proof_avg.average_commitment_base_G,
             &self.proof_avg.average_commitment_base_H,
-            &self.bp_generators,
-            &self.ped_generators,
-            &ped_gens_signature,
-            &H_vec,
+            &bp_generators,
+            &ped_generators,
+            ped_gens_signature,
+            H_vec,
             &self.size_sensors,
             self.size,
-            length_all_vectors
+            header.length_all_vectors,
+            &config.labels().with_statistics(stds(self.signed_commitments.len())),
+            self.range_bitsize,
+            rng,
         )?;
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Checks that the header is consistent, and that there are commitments for each axis of
+    /// each of the vectors it declares. Returns a `FormatError` otherwise.
+    fn check_shapes(&self, header: &ProofHeader) -> Result<(), ProofError> {
+        header.check()?;
+        let nr_sensors = header.length_all_vectors / 2;
+        fn has_shape<T>(commitments: &[Vec<T>], nr_vectors: usize) -> bool {
+            commitments.len() == nr_vectors && commitments.iter().all(|c| c.len() == 3)
+        }
+        let consistent = has_shape(&self.signed_commitments, nr_sensors)
+            && has_shape(&self.proof_diff.iter_commitments, nr_sensors)
+            && has_shape(&self.proof_avg.average_commitment, header.length_all_vectors)
+            && has_shape(&self.proof_avg.average_commitment_base_G, header.length_all_vectors)
+            && has_shape(&self.proof_avg.average_commitment_base_H, header.length_all_vectors)
+            && self.proof_variance.as_ref().is_none_or(|p| {
+                has_shape(p.variance_commitment(), header.length_all_vectors) && has_shape(p.std_commitment(), nr_sensors)
+            });
+        if !consistent {
+            return Err(ProofError::FormatError);
+        }
+        Ok(())
+    }
+}
+
+/// Shows the proof, without the configuration.
+impl fmt::Debug for zkSVMProver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("zkSVMProver")
+            .field("size", &self.size)
+            .field("size_sensors", &self.size_sensors)
+            .field("signed_commitments", &self.signed_commitments)
+            .field("proof_diff", &self.proof_diff)
+            .field("proof_avg", &self.proof_avg)
+            .field("range_bitsize", &self.range_bitsize)
+            .field("proof_variance", &self.proof_variance)
+            .field("statement_graph", &self.statement_graph)
+            .field("fingerprint", &self.fingerprint)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Two provers are equal if they hold the same proof, regardless of the configuration.
+impl PartialEq for zkSVMProver {
+    fn eq(&self, other: &zkSVMProver) -> bool {
+        self.size == other.size
+            && self.size_sensors == other.size_sensors
+            && self.signed_commitments == other.signed_commitments
+            && self.proof_diff == other.proof_diff
+            && self.proof_avg == other.proof_avg
+            && self.range_bitsize == other.range_bitsize
+            && self.proof_variance == other.proof_variance
+            && self.statement_graph == other.statement_graph
+            && self.fingerprint == other.fingerprint
+    }
+}
+
+impl Eq for zkSVMProver {}
+
+impl PartialOrd for zkSVMProver {
+    fn partial_cmp(&self, other: &zkSVMProver) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for zkSVMProver {
+    fn cmp(&self, other: &zkSVMProver) -> Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
+impl Hash for zkSVMProver {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+impl CanonicalPoints for zkSVMProver {
+    fn points_are_canonical(&self) -> bool {
+        self.signed_commitments.points_are_canonical()
+            && self.proof_diff.points_are_canonical()
+            && self.proof_avg.points_are_canonical()
+            && self.proof_variance.points_are_canonical()
+    }
+}
+
+impl SerializedSize for zkSVMProver {
+    /// Size of the encoding of `to_bytes`.
+    fn serialized_size(&self) -> usize {
+        bincode_size(&self.encoded())
+    }
+}
+
+impl EncodedProof {
+    fn into_prover(self, params: &PublicParams) -> Result<zkSVMProver, ProofError> {
+        if self.version != ENCODING_VERSION {
+            return Err(ProofError::FormatError);
+        }
+        if self.header.size != params.size() {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        self.header.check()?;
+        if self.signed_commitments.len() > StatisticSet::MAX_VECTORS {
+            return Err(ProofError::FormatError);
+        }
+        if self.fingerprint != params.fingerprint() {
+            return Err(ProofError::InvalidGenerators);
+        }
+
+        let statistics = self.statistics;
+        let prover = zkSVMProver {
+            config: params.config(),
+            signed_commitments: self.signed_commitments,
+            proof_diff: self.proof_diff,
+            proof_avg: self.proof_avg,
+            range_bitsize: self.range_bitsize,
+            proof_variance: self.proof_variance,
+            size: self.header.size,
+            size_sensors: self.header.size_sensors,
+            statement_graph: self.statement_graph,
+            fingerprint: self.fingerprint,
+        };
+        // The declared statistics are those the structure of the proof implies
+        if prover.statistics() != statistics {
+            return Err(ProofError::FormatError);
+        }
+
+        Ok(prover)
+    }
+}
+
+/// The additions of the first `nr_vectors` evaluated vectors.
+pub(super) fn additions(nr_vectors: usize) -> StatisticSet {
+    StatisticSet::empty().with_all(Statistic::Addition, nr_vectors)
+}
+
+/// The standard deviations of the first `nr_vectors` sensor vectors.
+pub(super) fn stds(nr_vectors: usize) -> StatisticSet {
+    StatisticSet::empty().with_all(Statistic::Std, nr_vectors)
+}
+
+/// Appends the signed commitments of all sensors to the transcript of an outer protocol.
+#[cfg(feature = "std")]
+pub(super) fn append_signed_commitments(transcript: &mut Transcript, signed_commitments: &[Vec<CompressedRistretto>]) {
+    for commitment in signed_commitments.iter().flatten() {
+        transcript.append_point(b"signed commitment", commitment);
+    }
+}
@@ -0,0 +1,452 @@
+//! Aggregation of the proofs of independent sensor hubs into a single decision.
+//!
+//! The sensors of a user are often spread over several devices, e.g. a wearable and a phone,
+//! each with its own trusted module. Each device, a `SensorHub`, commits and proves the
+//! statistics of its own sensors, and a `Coordinator` merges them into an `AggregatedProof` of
+//! the decision of a model over the union of the sensors. The rounds follow those of the
+//! aggregation of range proofs by a dealer and its parties, see `ip_zk_proof::dealer`:
+//!
+//! 1. Each hub commits to its sensor vectors and sends a `HubCommitment` to the coordinator.
+//! 2. The coordinator binds the model and the commitments of all the hubs to a transcript, and
+//!    sends back the `AggregationContext` derived from it.
+//! 3. Each hub proves its statistics under labels bound to the context, and sends a `HubShare`
+//!    with its proof and the opening of its part of the score.
+//! 4. The coordinator checks the shares, blaming the hubs of the malformed ones, and proves the
+//!    decision over the sum of the parts of the score.
+//!
+//! The features of the hubs are laid out one hub after the other: the model has the weights of
+//! the evaluated vectors of each hub, in the order of the hubs, followed by the weights of its
+//! sensor vectors, in the same order. The coordinator learns the score, but neither the sensor
+//! vectors nor the statistics of the hubs.
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use ip_zk_proof::r1cs::gadgets::signed_scalar;
+use ip_zk_proof::{BulletproofGens, CanonicalPoints, MPCError, ProofError, SerializedSize, TranscriptProtocol};
+use merlin::Transcript;
+use rand::thread_rng;
+use serde::{Deserialize, Serialize};
+
+use crate::features::FeatureWitness;
+use crate::metrics::NoMetrics;
+use crate::svm_proof::adhoc_proof::append_signed_commitments;
+use crate::svm_proof::decision_proof::{decision_gens_capacity, DecisionProof};
+use crate::svm_proof::session::{Committed, ProverSession};
+use crate::utils::misc::{bincode_deserialize, bincode_serialize, bincode_size, check_canonical};
+use crate::{zkSVMProver, PublicParams, SvmModel, TranscriptLabels};
+
+/// The commitments to the sensor vectors of a hub, sent to the coordinator.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct HubCommitment {
+    pub signed_commitments: Vec<Vec<CompressedRistretto>>,
+}
+
+/// The context of the proofs of the hubs, derived from the model and the commitments of all
+/// the hubs, sent by the coordinator.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct AggregationContext {
+    /// Number of sensor vectors of each hub
+    pub sensors: Vec<usize>,
+    pub challenge: [u8; 32],
+}
+
+/// The proof of the statistics of a hub, and the opening of its part of the score, sent to
+/// the coordinator.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct HubShare {
+    pub proof: zkSVMProver,
+    /// Part of the score over the features of the hub, without the bias
+    pub score: Scalar,
+    pub score_blinding: Scalar,
+}
+
+/// The proofs of the statistics of each hub, together with the proof of the decision of a
+/// model over the features of all of them.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AggregatedProof {
+    pub hubs: Vec<zkSVMProver>,
+    proof_decision: DecisionProof,
+}
+
+/// Used to construct a hub of the aggregation protocol.
+pub struct SensorHub {}
+
+impl SensorHub {
+    /// Commits to the sensor vectors of `witness`, see `SensorFeatures::witness`, under
+    /// `params`, for the hub at `position` in the order of the hubs.
+    pub fn commit(
+        params: &PublicParams,
+        position: usize,
+        witness: FeatureWitness,
+    ) -> Result<(HubAwaitingContext, HubCommitment), ProofError> {
+        let session = ProverSession::commit(
+            params,
+            &mut thread_rng(),
+            &mut NoMetrics,
+            &witness.input_vector,
+            &witness.non_zero_elements,
+        )?;
+        let commitment = HubCommitment { signed_commitments: session.signed_commitments().clone() };
+
+        Ok((HubAwaitingContext { params: params.clone(), position, session, witness }, commitment))
+    }
+}
+
+/// A hub which has sent its commitments, and waits for the context of its proof.
+pub struct HubAwaitingContext {
+    params: PublicParams,
+    position: usize,
+    session: ProverSession<Committed>,
+    witness: FeatureWitness,
+}
+
+impl HubAwaitingContext {
+    /// Proves the statistics of the hub under `context`, and opens its part of the score of
+    /// `model`. Returns `InvalidWeights` if the model has no weights for the features of the
+    /// hub.
+    pub fn prove(self, model: &SvmModel, context: &AggregationContext) -> Result<HubShare, ProofError> {
+        let hub_model = hub_model(model, &context.sensors, self.position)?;
+        let mut transcript = context_transcript(&self.params, context, b"hub", self.position);
+        let labels = self.params.labels().bind_to(&mut transcript);
+
+        let witness = &self.witness;
+        let rng = &mut thread_rng();
+        let (proof, blindings) = self.session
+            .with_labels(labels)
+            .prove_diff(&witness.diff_vectors, rng, &mut NoMetrics)
+            .prove_averages(rng, &mut NoMetrics)?
+            .prove_variances(&witness.additions, &witness.variances, &witness.stds, rng, &mut NoMetrics)?
+            .finish_with_feature_blindings();
+
+        Ok(HubShare {
+            proof,
+            score: hub_model.score(&witness.additions, &witness.stds)?,
+            score_blinding: hub_model.score(&blindings.additions, &blindings.stds)?,
+        })
+    }
+}
+
+/// Used to construct a coordinator of the aggregation protocol.
+pub struct Coordinator {}
+
+impl Coordinator {
+    /// Creates a coordinator of `nr_hubs` hubs, proving the decision of `model` over their
+    /// features under `params`.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(params: &PublicParams, model: &SvmModel, nr_hubs: usize) -> CoordinatorAwaitingCommitments {
+        CoordinatorAwaitingCommitments { params: params.clone(), model: model.clone(), nr_hubs }
+    }
+}
+
+/// A coordinator waiting for the commitments of the hubs.
+pub struct CoordinatorAwaitingCommitments {
+    params: PublicParams,
+    model: SvmModel,
+    nr_hubs: usize,
+}
+
+impl CoordinatorAwaitingCommitments {
+    /// Receives the commitments of the hubs, in the order of the hubs, and returns the context
+    /// of their proofs.
+    pub fn receive_commitments(
+        self,
+        commitments: Vec<HubCommitment>,
+    ) -> Result<(CoordinatorAwaitingShares, AggregationContext), ProofError> {
+        if self.nr_hubs == 0 || commitments.len() != self.nr_hubs {
+            return Err(MPCError::WrongNumBitCommitments.into());
+        }
+        let signed_commitments: Vec<&Vec<Vec<CompressedRistretto>>> =
+            commitments.iter().map(|c| &c.signed_commitments).collect();
+        let context = aggregation_context(&self.params, &self.model, &signed_commitments);
+
+        Ok((
+            CoordinatorAwaitingShares {
+                params: self.params,
+                model: self.model,
+                commitments,
+                context: context.clone(),
+            },
+            context,
+        ))
+    }
+}
+
+/// A coordinator waiting for the shares of the hubs.
+pub struct CoordinatorAwaitingShares {
+    params: PublicParams,
+    model: SvmModel,
+    commitments: Vec<HubCommitment>,
+    context: AggregationContext,
+}
+
+impl CoordinatorAwaitingShares {
+    /// Receives the shares of the hubs, in the order of the hubs, and proves the decision over
+    /// the sum of their parts of the score. Returns the commitment to the decision under the
+    /// `PedersenGens` of the parameters, together with the aggregated proof.
+    ///
+    /// A share is malformed if its proof is not over the commitments the hub sent, does not
+    /// verify under the context, or if its part of the score is not the one committed by the
+    /// proof. The error then lists the positions of the malformed shares.
+    pub fn receive_shares(
+        self,
+        shares: Vec<HubShare>,
+    ) -> Result<(CompressedRistretto, AggregatedProof), ProofError> {
+        if shares.len() != self.commitments.len() {
+            return Err(MPCError::WrongNumProofShares.into());
+        }
+        let ped_gens = self.params.pedersen_gens();
+
+        let mut bad_shares = Vec::new();
+        for (position, (share, commitment)) in shares.iter().zip(self.commitments.iter()).enumerate() {
+            let hub_model = hub_model(&self.model, &self.context.sensors, position)?;
+            let (addition_commitments, std_commitments) = share.proof.feature_commitments();
+            let well_formed = share.proof.signed_commitments() == &commitment.signed_commitments
+                && hub_model.score_commitment(ped_gens, addition_commitments, std_commitments)
+                    .is_ok_and(|c| c == ped_gens.commit(share.score, share.score_blinding).compress())
+                && share.proof.clone()
+                    .verify_with_transcript(&self.params, &mut context_transcript(&self.params, &self.context, b"hub", position))
+                    .is_ok();
+            if !well_formed {
+                bad_shares.push(position);
+            }
+        }
+        if !bad_shares.is_empty() {
+            return Err(MPCError::MalformedProofShares { bad_shares }.into());
+        }
+
+        let score = shares.iter().map(|s| s.score).sum::<Scalar>() + signed_scalar(self.model.bias);
+        let score_blinding = shares.iter().map(|s| s.score_blinding).sum();
+        let (proof_decision, decision_commitment, _) = DecisionProof::create_for_score(
+            &BulletproofGens::new(decision_gens_capacity(1), 1),
+            ped_gens,
+            &decision_labels(&self.params, &self.context),
+            score,
+            score_blinding,
+        )?;
+
+        Ok((decision_commitment, AggregatedProof {
+            hubs: shares.into_iter().map(|s| s.proof).collect(),
+            proof_decision,
+        }))
+    }
+}
+
+impl AggregatedProof {
+    /// Verifies the statistics of every hub under the given public parameters, and that
+    /// `decision_commitment` commits to the decision of `model` over the features of all the
+    /// hubs.
+    pub fn verify_with_params(
+        self,
+        params: &PublicParams,
+        model: &SvmModel,
+        decision_commitment: CompressedRistretto,
+    ) -> Result<(), ProofError> {
+        if self.hubs.is_empty() {
+            return Err(ProofError::VerificationError);
+        }
+        let signed_commitments: Vec<&Vec<Vec<CompressedRistretto>>> =
+            self.hubs.iter().map(|h| h.signed_commitments()).collect();
+        let context = aggregation_context(params, model, &signed_commitments);
+
+        let mut addition_commitments = Vec::new();
+        let mut std_commitments = Vec::new();
+        for (position, hub) in self.hubs.iter().enumerate() {
+            let (additions, stds) = hub.feature_commitments();
+            addition_commitments.extend(additions.iter().cloned());
+            std_commitments.extend(stds.iter().cloned());
+            hub.clone().verify_with_transcript(params, &mut context_transcript(params, &context, b"hub", position))?;
+        }
+        // The features are laid out with the evaluated vectors of all the hubs first
+        let score_commitment = model.score_commitment(params.pedersen_gens(), &addition_commitments, &std_commitments)?;
+
+        self.proof_decision.verify_for_score(
+            &BulletproofGens::new(decision_gens_capacity(1), 1),
+            params.pedersen_gens(),
+            &decision_labels(params, &context),
+            score_commitment,
+            decision_commitment,
+        )
+    }
+
+    /// Serializes the proof, with the proof of each hub encoded as `zkSVMProver::to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let hubs: Vec<Vec<u8>> = self.hubs.iter().map(zkSVMProver::to_bytes).collect();
+        bincode_serialize(&(hubs, &self.proof_decision))
+            .expect("Should never happen, as all the fields can be serialized.")
+    }
+
+    /// Parses a proof serialized with `to_bytes`, to be verified under the given public
+    /// parameters.
+    pub fn from_bytes(bytes: &[u8], params: &PublicParams) -> Result<AggregatedProof, ProofError> {
+        let (hubs, proof_decision): (Vec<Vec<u8>>, DecisionProof) =
+            bincode_deserialize(bytes).map_err(|_| ProofError::FormatError)?;
+        Ok(AggregatedProof {
+            hubs: hubs.iter()
+                .map(|hub| zkSVMProver::from_bytes(hub, params))
+                .collect::<Result<_, ProofError>>()?,
+            proof_decision,
+        })
+    }
+
+    /// As `from_bytes`, rejecting proofs with non-canonical points, see `CanonicalPoints`.
+    pub fn from_bytes_strict(bytes: &[u8], params: &PublicParams) -> Result<AggregatedProof, ProofError> {
+        check_canonical(AggregatedProof::from_bytes(bytes, params)?)
+    }
+}
+
+impl CanonicalPoints for AggregatedProof {
+    fn points_are_canonical(&self) -> bool {
+        self.hubs.points_are_canonical() && self.proof_decision.points_are_canonical()
+    }
+}
+
+impl SerializedSize for AggregatedProof {
+    /// Size of the encoding of `to_bytes`, where the proof of each hub is nested as a
+    /// length-prefixed byte vector.
+    fn serialized_size(&self) -> usize {
+        8 + self.hubs.iter().map(|h| 8 + h.serialized_size()).sum::<usize>() + bincode_size(&self.proof_decision)
+    }
+}
+
+/// The context bound to the model and to the commitments of the hubs, in order.
+fn aggregation_context(
+    params: &PublicParams,
+    model: &SvmModel,
+    signed_commitments: &[&Vec<Vec<CompressedRistretto>>],
+) -> AggregationContext {
+    let labels = params.labels();
+    let mut transcript = labels.transcript(labels.aggregation);
+    transcript.append_integer(b"hubs", signed_commitments.len() as u64);
+    model.append_to_transcript(&mut transcript);
+    for commitments in signed_commitments {
+        transcript.append_integer(b"sensors", commitments.len() as u64);
+        append_signed_commitments(&mut transcript, commitments);
+    }
+    let mut challenge = [0u8; 32];
+    transcript.challenge_into(b"aggregation context", &mut challenge);
+
+    AggregationContext {
+        sensors: signed_commitments.iter().map(|c| c.len()).collect(),
+        challenge,
+    }
+}
+
+/// The transcript of the proof of the party at `position`, bound to `context`.
+fn context_transcript(
+    params: &PublicParams,
+    context: &AggregationContext,
+    party: &'static [u8],
+    position: usize,
+) -> Transcript {
+    let labels = params.labels();
+    let mut transcript = labels.transcript(labels.aggregation);
+    transcript.append_bytes(b"context", &context.challenge);
+    transcript.append_bytes(b"party", party);
+    transcript.append_integer(b"position", position as u64);
+    transcript
+}
+
+/// The labels of the proof of the decision, bound to `context`.
+fn decision_labels(params: &PublicParams, context: &AggregationContext) -> TranscriptLabels {
+    params.labels().bind_to(&mut context_transcript(params, context, b"coordinator", 0))
+}
+
+/// The model over the features of the hub at `position`, without the bias. Each hub has one
+/// evaluated vector per sensor vector, and its diff vector.
+fn hub_model(model: &SvmModel, sensors: &[usize], position: usize) -> Result<SvmModel, ProofError> {
+    let offset: usize = sensors.get(..position).ok_or(ProofError::InvalidWeights)?.iter().sum();
+    let nr_sensors = *sensors.get(position).ok_or(ProofError::InvalidWeights)?;
+    let rows = |weights: &Vec<Vec<i64>>, start: usize, len: usize| -> Result<Vec<Vec<i64>>, ProofError> {
+        weights.get(start..start + len).map(<[Vec<i64>]>::to_vec).ok_or(ProofError::InvalidWeights)
+    };
+    if model.addition_weights.len() != 2 * sensors.iter().sum::<usize>() {
+        return Err(ProofError::InvalidWeights);
+    }
+
+    Ok(SvmModel {
+        addition_weights: rows(&model.addition_weights, 2 * offset, 2 * nr_sensors)?,
+        std_weights: rows(&model.std_weights, offset, nr_sensors)?,
+        bias: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::SensorFeatures;
+
+    fn witness(offset: i128) -> FeatureWitness {
+        let coord = |values: &[i128]| -> Vec<i128> {
+            let mut vector: Vec<i128> = values.iter().map(|x| x + offset).collect();
+            vector.resize(64, 0);
+            vector
+        };
+        let input = vec![
+            [coord(&[5, 3, 8, 1]), coord(&[2, 2, 2, 2]), coord(&[9, 0, 4, 7])],
+            [coord(&[1, 6, 3, 9, 4, 2]), coord(&[7, 7, 1, 3, 5, 8]), coord(&[4, 4, 4, 0, 1, 6])],
+            [coord(&[3, 1, 4, 1, 5]), coord(&[9, 2, 6, 5, 3]), coord(&[5, 8, 9, 7, 9])],
+            [coord(&[2, 7, 1, 8]), coord(&[2, 8, 1, 8]), coord(&[4, 5, 9, 0])],
+        ];
+        SensorFeatures::extract(&input, &[4, 6, 5, 4]).witness().unwrap()
+    }
+
+    fn shares(params: &PublicParams, model: &SvmModel) -> (CoordinatorAwaitingShares, Vec<HubShare>) {
+        let (hub_0, commitment_0) = SensorHub::commit(params, 0, witness(0)).unwrap();
+        let (hub_1, commitment_1) = SensorHub::commit(params, 1, witness(1)).unwrap();
+        let (coordinator, context) = Coordinator::new(params, model, 2)
+            .receive_commitments(vec![commitment_0, commitment_1])
+            .unwrap();
+        let shares = vec![hub_0.prove(model, &context).unwrap(), hub_1.prove(model, &context).unwrap()];
+        (coordinator, shares)
+    }
+
+    #[test]
+    fn aggregated_proof() {
+        let params = PublicParams::new(64);
+        // The addition of the first axis of the first vector of the second hub is 17 + 4
+        let mut addition_weights = vec![vec![0; 3]; 16];
+        addition_weights[8] = vec![1, 0, 0];
+        let model = SvmModel { addition_weights, std_weights: vec![vec![0; 3]; 8], bias: -20 };
+
+        let (coordinator, shares) = shares(&params, &model);
+        let (decision_commitment, proof) = coordinator.receive_shares(shares).unwrap();
+
+        let parsed = AggregatedProof::from_bytes_strict(&proof.to_bytes(), &params).unwrap();
+        assert!(parsed == proof);
+        assert_eq!(parsed.serialized_size(), proof.to_bytes().len());
+        assert!(parsed.verify_with_params(&params, &model, decision_commitment).is_ok());
+
+        let mut tampered = proof.clone();
+        tampered.hubs[1].signed_commitments[0][0] = CompressedRistretto([0xff; 32]);
+        let bytes = tampered.to_bytes();
+        assert!(AggregatedProof::from_bytes(&bytes, &params).is_ok());
+        assert_eq!(AggregatedProof::from_bytes_strict(&bytes, &params).err(), Some(ProofError::FormatError));
+
+        let other_model = SvmModel { bias: -21, ..model.clone() };
+        assert!(proof.clone().verify_with_params(&params, &other_model, decision_commitment).is_err());
+        let mut reordered = proof;
+        reordered.hubs.swap(0, 1);
+        assert!(reordered.verify_with_params(&params, &model, decision_commitment).is_err());
+    }
+
+    #[test]
+    fn malformed_shares() {
+        let params = PublicParams::new(64);
+        let model = SvmModel {
+            addition_weights: vec![vec![1, 0, 0]; 16],
+            std_weights: vec![vec![0; 3]; 8],
+            bias: 0,
+        };
+        assert_eq!(
+            Coordinator::new(&params, &model, 2).receive_commitments(Vec::new()).err(),
+            Some(ProofError::ProvingError(MPCError::WrongNumBitCommitments))
+        );
+
+        let (coordinator, mut shares) = shares(&params, &model);
+        shares[1].score += Scalar::ONE;
+        assert_eq!(
+            coordinator.receive_shares(shares).err(),
+            Some(ProofError::ProvingError(MPCError::MalformedProofShares { bad_shares: vec![1] }))
+        );
+    }
+}
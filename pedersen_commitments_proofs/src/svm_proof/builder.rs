@@ -0,0 +1,301 @@
+//! Builder of a `zkSVMProver` over a subset of the statistics.
+
+use alloc::vec::Vec;
+use crate::boolean_proofs::square_proof::SQUARE_RANGE_BITSIZE;
+use crate::features::FeatureWitness;
+use crate::metrics::{NoMetrics, ProverMetrics};
+use crate::svm_proof::session::ProverSession;
+use crate::{zkSVMProver, PedersenConfig, PublicParams};
+
+use curve25519_dalek::scalar::Scalar;
+use ip_zk_proof::ProofError;
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand_core::CryptoRngCore;
+
+/// Builds a `zkSVMProver` over the features of a `FeatureWitness`.
+///
+/// By default, the proof covers the additions and the standard deviations, under
+/// `PublicParams::new` of the size of the vectors, as `zkSVMProver::new`. With `without_stds`,
+/// the proof only covers the additions, which is enough for a model without weights over the
+/// standard deviations, and skips their (costly) range proofs.
+pub struct zkSVMProverBuilder<'a> {
+    witness: &'a FeatureWitness,
+    // Generators and labels of the proof, those of `PublicParams::new` if none
+    config: Option<PedersenConfig>,
+    signed_blindings: Option<&'a Vec<Vec<Scalar>>>,
+    // Bitsize of the range proofs of the stds, `SQUARE_RANGE_BITSIZE` if none
+    range_bitsize: Option<usize>,
+    stds: bool,
+    witness_rng: bool,
+    rng: Option<&'a mut dyn CryptoRngCore>,
+    metrics: Option<&'a mut dyn ProverMetrics>,
+}
+
+impl<'a> zkSVMProverBuilder<'a> {
+    /// Starts a proof of the features of `witness`, see `SensorFeatures::witness`.
+    pub fn new(witness: &'a FeatureWitness) -> zkSVMProverBuilder<'a> {
+        zkSVMProverBuilder {
+            witness,
+            config: None,
+            signed_blindings: None,
+            range_bitsize: None,
+            stds: true,
+            witness_rng: false,
+            rng: None,
+            metrics: None,
+        }
+    }
+
+    /// Proves under the generators, the labels and the range bitsize of `params`, which the
+    /// verifier then passes to `zkSVMProver::verify_with_params`.
+    pub fn with_params(mut self, params: &PublicParams) -> zkSVMProverBuilder<'a> {
+        self.config = Some(params.config());
+        self.range_bitsize = self.range_bitsize.or(Some(params.range_bitsize()));
+        self
+    }
+
+    /// Proves under the generators and the labels of `config`, which the proof carries for
+    /// `zkSVMProver::verify`.
+    pub fn with_config(mut self, config: PedersenConfig) -> zkSVMProverBuilder<'a> {
+        self.config = Some(config);
+        self
+    }
+
+    /// Commits to the sensor vectors with the blinding factors of the trusted module, see
+    /// `zkSVMProver::new_with_signed_blindings`.
+    pub fn with_signed_blindings(mut self, signed_blindings: &'a Vec<Vec<Scalar>>) -> zkSVMProverBuilder<'a> {
+        self.signed_blindings = Some(signed_blindings);
+        self
+    }
+
+    /// Proves the standard deviations with range proofs of `range_bitsize` bits, one of 8, 16,
+    /// 32 or 64. The differences of each variance and the squares of its floored standard
+    /// deviation, and of the floored standard deviation plus one, must fit in that many bits.
+    pub fn with_range_bitsize(mut self, range_bitsize: usize) -> zkSVMProverBuilder<'a> {
+        self.range_bitsize = Some(range_bitsize);
+        self
+    }
+
+    /// Only proves the additions of the vectors, and not the standard deviations.
+    pub fn without_stds(mut self) -> zkSVMProverBuilder<'a> {
+        self.stds = false;
+        self
+    }
+
+    /// Draws all the randomness of the proof from `rng` instead of the thread RNG. Required
+    /// without the `std` feature.
+    pub fn with_rng(mut self, rng: &'a mut dyn CryptoRngCore) -> zkSVMProverBuilder<'a> {
+        self.rng = Some(rng);
+        self
+    }
+
+    /// Keys the randomness of every sub-proof by the witness, see
+    /// `PedersenConfig::with_witness_rng`, so that a weak RNG does not reveal the witness.
+    pub fn with_witness_rng(mut self) -> zkSVMProverBuilder<'a> {
+        self.witness_rng = true;
+        self
+    }
+
+    /// Reports each phase of the proof to `metrics`, see `ProverMetrics`.
+    pub fn with_metrics(mut self, metrics: &'a mut dyn ProverMetrics) -> zkSVMProverBuilder<'a> {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Creates the proof. Returns an `InvalidGeneratorsLength` if the generators do not have
+    /// the size of the vectors, and an `InvalidBitsize` if the range bitsize is not supported.
+    ///
+    /// Without the `std` feature there is no thread RNG, and it panics if no RNG was given with
+    /// `with_rng`.
+    pub fn prove(self) -> Result<zkSVMProver, ProofError> {
+        let witness = self.witness;
+        let size = witness.input_vector[0][0].len();
+        let range_bitsize = self.range_bitsize.unwrap_or(SQUARE_RANGE_BITSIZE);
+        if !matches!(range_bitsize, 8 | 16 | 32 | 64) {
+            return Err(ProofError::InvalidBitsize);
+        }
+        let config = match self.config {
+            Some(config) if config.size() != size => return Err(ProofError::InvalidGeneratorsLength),
+            Some(config) => config,
+            None => PublicParams::new(size).config(),
+        };
+        let config = if self.witness_rng { config.with_witness_rng() } else { config };
+
+        #[cfg(feature = "std")]
+        let mut thread_rng = thread_rng();
+        let rng: &mut dyn CryptoRngCore = match self.rng {
+            Some(rng) => rng,
+            #[cfg(feature = "std")]
+            None => &mut thread_rng,
+            #[cfg(not(feature = "std"))]
+            None => panic!("Without the std feature, the RNG is given with `with_rng`."),
+        };
+        let mut no_metrics = NoMetrics;
+        let metrics: &mut dyn ProverMetrics = match self.metrics {
+            Some(metrics) => metrics,
+            None => &mut no_metrics,
+        };
+
+        let _span = phase_span!("zksvm_prove", size = size);
+        let session = ProverSession::commit_with_config(
+            config,
+            self.signed_blindings,
+            rng,
+            metrics,
+            &witness.input_vector,
+            &witness.non_zero_elements,
+        )?
+            .with_range_bitsize(range_bitsize)
+            .prove_diff(&witness.diff_vectors, rng, metrics)
+            .prove_averages(rng, metrics)?;
+
+        if !self.stds {
+            return Ok(session.finish());
+        }
+        Ok(session
+            .prove_variances(&witness.additions, &witness.variances, &witness.stds, rng, metrics)?
+            .finish())
+    }
+}
+
+impl zkSVMProver {
+    /// Starts a `zkSVMProverBuilder` over the features of `witness`.
+    pub fn builder(witness: &FeatureWitness) -> zkSVMProverBuilder<'_> {
+        zkSVMProverBuilder::new(witness)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::SensorFeatures;
+    use crate::{Statistic, StatisticSet};
+    use curve25519_dalek::ristretto::CompressedRistretto;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn witness() -> FeatureWitness {
+        let coord = |values: &[i128]| -> Vec<i128> {
+            let mut vector = values.to_vec();
+            vector.resize(64, 0);
+            vector
+        };
+        let input = vec![
+            [coord(&[5, 3, 8, 1]), coord(&[2, 2, 2, 2]), coord(&[9, 0, 4, 7])],
+            [coord(&[1, 6, 3, 9, 4, 2]), coord(&[7, 7, 1, 3, 5, 8]), coord(&[4, 4, 4, 0, 1, 6])],
+            [coord(&[3, 1, 4, 1, 5]), coord(&[9, 2, 6, 5, 3]), coord(&[5, 8, 9, 7, 9])],
+            [coord(&[2, 7, 1, 8]), coord(&[2, 8, 1, 8]), coord(&[4, 5, 9, 0])],
+        ];
+        SensorFeatures::extract(&input, &[4, 6, 5, 4]).witness().unwrap()
+    }
+
+    #[test]
+    fn proof_without_stds() {
+        let params = PublicParams::new(64);
+        let witness = witness();
+        let proof = zkSVMProver::builder(&witness)
+            .with_params(&params)
+            .with_rng(&mut StdRng::seed_from_u64(1))
+            .without_stds()
+            .prove()
+            .unwrap();
+
+        assert!(!proof.proves_stds());
+        assert!(proof.feature_commitments().1.is_empty());
+        let parsed = zkSVMProver::from_bytes(&proof.to_bytes(), &params).unwrap();
+        assert!(parsed == proof);
+        assert!(parsed.verify_with_params(&params).is_ok());
+
+        let additions = StatisticSet::empty().with_all(Statistic::Addition, 8);
+        assert_eq!(proof.statistics(), additions);
+        assert!(proof.clone().verify_covering(&params, &additions).is_ok());
+        assert_eq!(
+            proof.verify_covering(&params, &additions.with(Statistic::Std, 0)).err(),
+            Some(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn proof_with_witness_rng() {
+        let params = PublicParams::new(64);
+        let witness = witness();
+        let prove = |witness_rng: bool| {
+            let builder = zkSVMProver::builder(&witness).with_params(&params);
+            let builder = if witness_rng { builder.with_witness_rng() } else { builder };
+            builder.with_rng(&mut StdRng::seed_from_u64(1)).prove().unwrap()
+        };
+        let proof = prove(true);
+        assert!(proof == prove(true));
+
+        // The same RNG gives other blindings once keyed by the witness
+        assert!(proof != prove(false));
+        assert!(proof.verify_with_params(&params).is_ok());
+    }
+
+    #[test]
+    fn invalid_builders() {
+        let witness = witness();
+        assert!(matches!(
+            zkSVMProver::builder(&witness).with_range_bitsize(24).prove(),
+            Err(ProofError::InvalidBitsize)
+        ));
+        assert!(matches!(
+            zkSVMProver::builder(&witness).with_params(&PublicParams::new(32)).prove(),
+            Err(ProofError::InvalidGeneratorsLength)
+        ));
+    }
+
+    #[test]
+    fn proof_header() {
+        let params = PublicParams::new(64);
+        let proof = zkSVMProver::builder(&witness()).with_params(&params).without_stds().prove().unwrap();
+        let header = proof.header();
+        assert_eq!((header.size, header.length_all_vectors), (64, 8));
+        assert_eq!(&header.size_sensors[..4], &[4, 6, 5, 4]);
+        assert!(proof.clone().verify_with_header(&params, &header).is_ok());
+
+        let mut expected = header.clone();
+        expected.size_sensors[0] = 5;
+        assert_eq!(proof.clone().verify_with_header(&params, &expected).err(), Some(ProofError::VerificationError));
+
+        // The sizes are bound into the transcripts, and checked against the commitments
+        let tampered = |tamper: fn(&mut zkSVMProver)| {
+            let mut tampered = proof.clone();
+            tamper(&mut tampered);
+            tampered.verify_with_params(&params).err()
+        };
+        assert_eq!(tampered(|p| p.size_sensors[0] = 5), Some(ProofError::VerificationError));
+        assert_eq!(tampered(|p| p.size_sensors[0] = 65), Some(ProofError::FormatError));
+        assert_eq!(tampered(|p| p.size_sensors[0] = 0), Some(ProofError::FormatError));
+        assert_eq!(tampered(|p| { p.size_sensors.pop(); }), Some(ProofError::FormatError));
+        assert_eq!(tampered(|p| { p.proof_avg.average_commitment.pop(); }), Some(ProofError::FormatError));
+        assert_eq!(tampered(|p| { p.signed_commitments[0].pop(); }), Some(ProofError::FormatError));
+    }
+
+    #[test]
+    fn strict_parsing_rejects_non_canonical_points() {
+        let params = PublicParams::new(64);
+        let proof = zkSVMProver::builder(&witness()).with_params(&params).without_stds().prove().unwrap();
+        assert!(zkSVMProver::from_bytes_strict(&proof.to_bytes(), &params).unwrap() == proof);
+
+        let mut tampered = proof;
+        tampered.signed_commitments[0][0] = CompressedRistretto([0xff; 32]);
+        let bytes = tampered.to_bytes();
+        assert!(zkSVMProver::from_bytes(&bytes, &params).is_ok());
+        assert_eq!(zkSVMProver::from_bytes_strict(&bytes, &params).err(), Some(ProofError::FormatError));
+    }
+
+    #[test]
+    fn proofs_carry_the_fingerprint_of_the_params() {
+        let params = PublicParams::new(64);
+        let proof = zkSVMProver::builder(&witness()).with_params(&params).without_stds().prove().unwrap();
+        assert_eq!(proof.fingerprint(), &params.fingerprint());
+
+        // Parameters of the same size, with other generators
+        let other = PublicParams::setup(b"zkSVM", b"other seed", 64);
+        assert_eq!(zkSVMProver::from_bytes(&proof.to_bytes(), &other).err(), Some(ProofError::InvalidGenerators));
+        assert_eq!(proof.clone().verify_with_params(&other).err(), Some(ProofError::InvalidGenerators));
+        assert!(proof.verify_with_params(&params).is_ok());
+    }
+}
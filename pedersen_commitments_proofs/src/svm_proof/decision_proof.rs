@@ -0,0 +1,809 @@
+//! Decision of a linear SVM over the features proven by `zkSVMProver`.
+//!
+//! The model is evaluated over the committed features, see `zkSVMProver::feature_commitments`.
+//! Its weights are integers, e.g. the weights of a trained model scaled and rounded, so that
+//! the verifier computes the commitment to the score \\(s = \sum_j w_j f_j + bias\\) from the
+//! commitments to the features \\(f_j\\). The `DecisionProof` then shows that a committed bit
+//! is the decision \\(s \geq 0\\), where the threshold of the model is folded in the bias.
+
+use alloc::vec::Vec;
+use ip_zk_proof::{CanonicalPoints, ProofError, SerializedSize};
+#[cfg(feature = "std")]
+use ip_zk_proof::TranscriptProtocol;
+#[cfg(feature = "std")]
+use ip_zk_proof::PedersenGens;
+#[cfg(feature = "std")]
+use ip_zk_proof::BulletproofGens;
+use ip_zk_proof::r1cs::{ConstraintSystem, LinearCombination, R1CSError, R1CSProof};
+#[cfg(feature = "std")]
+use ip_zk_proof::r1cs::{Prover, Verifier};
+use ip_zk_proof::r1cs::gadgets::{range, signed_scalar};
+
+use curve25519_dalek::scalar::Scalar;
+#[cfg(feature = "std")]
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+#[cfg(feature = "std")]
+use curve25519_dalek::traits::VartimeMultiscalarMul;
+
+#[cfg(feature = "std")]
+use merlin::Transcript;
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use core::convert::TryInto;
+#[cfg(feature = "std")]
+use core::iter;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+use crate::utils::misc::{bincode_deserialize, bincode_serialize, bincode_size, check_canonical};
+use crate::{zkSVMProver, PublicParams, Statistic, StatisticSet};
+#[cfg(feature = "std")]
+use crate::TranscriptLabels;
+
+/// Bitsize of the score, in absolute value.
+pub const SCORE_BITSIZE: usize = 64;
+
+/// Number of generators needed by the `DecisionProof`, i.e. the multipliers of its
+/// constraint system rounded up to the next power of two.
+pub const DECISION_GENS_CAPACITY: usize = 128;
+
+/// Number of generators needed by a `DecisionProof` over `nr_decisions` scores, see
+/// `DecisionProof::create_for_scores`. Each decision takes two multipliers, and the range
+/// proof of its score `SCORE_BITSIZE` more.
+pub fn decision_gens_capacity(nr_decisions: usize) -> usize {
+    (nr_decisions * (SCORE_BITSIZE + 2)).next_power_of_two()
+}
+
+/// A linear SVM over the features of `zkSVMProver`, with integer weights.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SvmModel {
+    /// Weights of the additions of each axis of each evaluated vector
+    pub addition_weights: Vec<Vec<i64>>,
+    /// Weights of the standard deviations of each axis of each sensor vector
+    pub std_weights: Vec<Vec<i64>>,
+    pub bias: i64,
+}
+
+/// Blinding factors of the commitments to the features, see `zkSVMProver::feature_commitments`.
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+pub(crate) struct FeatureBlindings {
+    pub(crate) additions: Vec<Vec<Scalar>>,
+    pub(crate) stds: Vec<Vec<Scalar>>,
+}
+
+impl fmt::Debug for FeatureBlindings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FeatureBlindings").finish_non_exhaustive()
+    }
+}
+
+impl SvmModel {
+    /// Parses a model given as text, with one line `bias <b>`, one line
+    /// `addition <w_x> <w_y> <w_z>` per evaluated vector and one line `std <w_x> <w_y> <w_z>`
+    /// per sensor vector, in the order of the vectors. Empty lines and lines starting with
+    /// `#` are ignored. Returns a `FormatError` if the model is malformed.
+    pub fn parse(model: &str) -> Result<SvmModel, ProofError> {
+        let mut bias = None;
+        let mut addition_weights = Vec::new();
+        let mut std_weights = Vec::new();
+
+        for line in model.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let mut fields = line.split_whitespace();
+            let key = fields.next().ok_or(ProofError::FormatError)?;
+            let values: Vec<i64> = fields
+                .map(|w| w.parse().map_err(|_| ProofError::FormatError))
+                .collect::<Result<_, _>>()?;
+            match (key, values.len()) {
+                ("bias", 1) if bias.is_none() => bias = Some(values[0]),
+                ("addition", 3) => addition_weights.push(values),
+                ("std", 3) => std_weights.push(values),
+                _ => return Err(ProofError::FormatError),
+            }
+        }
+
+        Ok(SvmModel {
+            addition_weights,
+            std_weights,
+            bias: bias.ok_or(ProofError::FormatError)?,
+        })
+    }
+
+    /// The statistics with a non-zero weight in the model, which a proof must cover for the
+    /// model to be evaluated over its features, see `zkSVMProver::verify_covering`. Returns a
+    /// `FormatError` if a weighted vector has no flag in a `StatisticSet`.
+    pub fn required_statistics(&self) -> Result<StatisticSet, ProofError> {
+        let weighted = |weights: &Vec<Vec<i64>>| -> Vec<usize> {
+            weights.iter().enumerate()
+                .filter(|(_, w)| w.iter().any(|&w| w != 0))
+                .map(|(vector, _)| vector)
+                .collect()
+        };
+        let required = weighted(&self.addition_weights).into_iter().map(|v| (Statistic::Addition, v))
+            .chain(weighted(&self.std_weights).into_iter().map(|v| (Statistic::Std, v)));
+
+        let mut statistics = StatisticSet::empty();
+        for (statistic, vector) in required {
+            if vector >= StatisticSet::MAX_VECTORS {
+                return Err(ProofError::FormatError);
+            }
+            statistics = statistics.with(statistic, vector);
+        }
+        Ok(statistics)
+    }
+
+    /// Score of the model over the given features, as a scalar.
+    pub fn score(&self, additions: &Vec<Vec<Scalar>>, stds: &Vec<Vec<Scalar>>) -> Result<Scalar, ProofError> {
+        self.check_shape(additions, stds)?;
+        Ok(self.weights()
+            .zip(additions.iter().flatten().chain(stds.iter().flatten()))
+            .map(|(w, f)| w * f)
+            .sum::<Scalar>() + signed_scalar(self.bias))
+    }
+
+    /// The commitment to the score, computed from the commitments to the features.
+    #[cfg(feature = "std")]
+    pub(crate) fn score_commitment(
+        &self,
+        pedersen_generators: &PedersenGens,
+        addition_commitments: &Vec<Vec<CompressedRistretto>>,
+        std_commitments: &Vec<Vec<CompressedRistretto>>,
+    ) -> Result<CompressedRistretto, ProofError> {
+        self.check_shape(addition_commitments, std_commitments)?;
+        // Flattened iterators have no exact size, which the multiscalar multiplication needs
+        let scalars: Vec<Scalar> = self.weights().chain(iter::once(signed_scalar(self.bias))).collect();
+        let points: Vec<Option<RistrettoPoint>> = addition_commitments.iter().flatten()
+            .chain(std_commitments.iter().flatten())
+            .map(|c| c.decompress())
+            .chain(iter::once(Some(pedersen_generators.B)))
+            .collect();
+        let _span = msm_span!("decision", size = scalars.len());
+        let commitment = RistrettoPoint::optional_multiscalar_mul(scalars, points)
+            .ok_or(ProofError::VerificationError)?;
+        Ok(commitment.compress())
+    }
+
+    /// Appends the weights and the bias of the model to `transcript`.
+    #[cfg(feature = "std")]
+    pub(crate) fn append_to_transcript(&self, transcript: &mut Transcript) {
+        transcript.append_integer(b"bias", self.bias as u64);
+        for weight in self.addition_weights.iter().chain(self.std_weights.iter()).flatten() {
+            transcript.append_integer(b"weight", *weight as u64);
+        }
+    }
+
+    fn weights(&self) -> impl Iterator<Item = Scalar> + '_ {
+        self.addition_weights.iter().flatten()
+            .chain(self.std_weights.iter().flatten())
+            .map(|&w| signed_scalar(w))
+    }
+
+    fn check_shape<T>(&self, additions: &Vec<Vec<T>>, stds: &Vec<Vec<T>>) -> Result<(), ProofError> {
+        let shape = |weights: &Vec<Vec<i64>>| weights.iter().map(|w| w.len()).collect::<Vec<usize>>();
+        let shape_features = |features: &Vec<Vec<T>>| features.iter().map(|f| f.len()).collect::<Vec<usize>>();
+        if shape(&self.addition_weights) != shape_features(additions) || shape(&self.std_weights) != shape_features(stds) {
+            return Err(ProofError::InvalidWeights);
+        }
+        Ok(())
+    }
+}
+
+/// Constrains `decision` to be the bit \\(score \geq 0\\), where the score is an integer with
+/// an absolute value of at most `SCORE_BITSIZE` bits. The assignment, only needed by the
+/// prover, is \\(s\\) if the score is non-negative, and \\(-s - 1\\) otherwise.
+pub fn decision_gadget<CS: ConstraintSystem>(
+    cs: &mut CS,
+    score: LinearCombination,
+    decision: LinearCombination,
+    assignment: Option<u64>,
+) -> Result<(), R1CSError> {
+    let (_, _, zero) = cs.multiply(decision.clone(), LinearCombination::from(Scalar::ONE) - decision.clone());
+    cs.constrain(zero.into());
+
+    // The decision times 2s + 1, minus s + 1, is s for a decision of 1, and -s - 1 for a
+    // decision of 0. With the wrong decision, it is the additive inverse of a small value.
+    let (_, _, product) = cs.multiply(decision, score.clone() * Scalar::from(2u64) + Scalar::ONE);
+    range(cs, product - score - Scalar::ONE, assignment, SCORE_BITSIZE)
+}
+
+/// Proof that a committed bit is the decision of an `SvmModel` over committed features.
+///
+/// The `BulletproofGens` must have a capacity of at least `DECISION_GENS_CAPACITY`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct DecisionProof {
+    proof: R1CSProof,
+}
+
+impl DecisionProof {
+    /// Proves the decision of `model` over the features committed under `pedersen_generators`
+    /// with the given blindings. Returns the proof, with the commitment to the decision and its
+    /// blinding factor. Returns `InvalidWeights` if the shape of the model is not the one of
+    /// the features, or if the score does not fit in `SCORE_BITSIZE` bits.
+    #[cfg(feature = "std")]
+    pub fn create(
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        labels: &TranscriptLabels,
+        model: &SvmModel,
+        additions: &Vec<Vec<Scalar>>,
+        addition_blindings: &Vec<Vec<Scalar>>,
+        stds: &Vec<Vec<Scalar>>,
+        std_blindings: &Vec<Vec<Scalar>>,
+    ) -> Result<(DecisionProof, CompressedRistretto, Scalar), ProofError> {
+        let score = model.score(additions, stds)?;
+        let score_blinding = model.score(addition_blindings, std_blindings)? - signed_scalar(model.bias);
+        DecisionProof::create_for_score(bulletproof_generators, pedersen_generators, labels, score, score_blinding)
+    }
+
+    /// Proves the decision \\(s \geq 0\\) over the score \\(s\\) committed under
+    /// `pedersen_generators` with `score_blinding`, e.g. the score of a model whose commitment
+    /// the verifier computes from other commitments. Returns the proof, with the commitment to
+    /// the decision and its blinding factor, or `InvalidWeights` if the score does not fit in
+    /// `SCORE_BITSIZE` bits.
+    #[cfg(feature = "std")]
+    pub fn create_for_score(
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        labels: &TranscriptLabels,
+        score: Scalar,
+        score_blinding: Scalar,
+    ) -> Result<(DecisionProof, CompressedRistretto, Scalar), ProofError> {
+        let (proof, decision_commitments, decision_blindings) = DecisionProof::create_for_scores(
+            bulletproof_generators,
+            pedersen_generators,
+            labels,
+            &[score],
+            &[score_blinding],
+        )?;
+        Ok((proof, decision_commitments[0], decision_blindings[0]))
+    }
+
+    /// Proves the decisions over several committed scores in a single proof, e.g. the scores of
+    /// consecutive windows, see `create_for_score`. The range proofs of the scores are then
+    /// aggregated in one constraint system, with a `BulletproofGens` of a capacity of at least
+    /// `decision_gens_capacity(scores.len())`. Returns the proof, with the commitments to the
+    /// decisions and their blinding factors.
+    #[cfg(feature = "std")]
+    pub fn create_for_scores(
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        labels: &TranscriptLabels,
+        scores: &[Scalar],
+        score_blindings: &[Scalar],
+    ) -> Result<(DecisionProof, Vec<CompressedRistretto>, Vec<Scalar>), ProofError> {
+        if score_blindings.len() != scores.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+        let assignments = scores.iter()
+            .map(decision_assignment)
+            .collect::<Option<Vec<(bool, u64)>>>()
+            .ok_or(ProofError::InvalidWeights)?;
+
+        let mut prover = Prover::new(pedersen_generators, labels.transcript(labels.decision));
+        let mut decision_commitments = Vec::with_capacity(scores.len());
+        let mut decision_blindings = Vec::with_capacity(scores.len());
+        for ((score, score_blinding), (decision, assignment)) in scores.iter().zip(score_blindings.iter()).zip(assignments) {
+            let (_, score_var) = prover.commit(*score, *score_blinding);
+            let decision_blinding = Scalar::random(&mut thread_rng());
+            let (decision_commitment, decision_var) = prover.commit(Scalar::from(decision as u64), decision_blinding);
+            decision_gadget(&mut prover, score_var.into(), decision_var.into(), Some(assignment))
+                .map_err(from_r1cs_error)?;
+            decision_commitments.push(decision_commitment);
+            decision_blindings.push(decision_blinding);
+        }
+
+        let proof = prover.prove_with_rng(bulletproof_generators, &mut thread_rng())
+            .map_err(from_r1cs_error)?;
+        Ok((DecisionProof { proof }, decision_commitments, decision_blindings))
+    }
+
+    /// Verifies that `decision_commitment` commits to the decision of `model` over the
+    /// features with the given commitments.
+    #[cfg(feature = "std")]
+    pub fn verify(
+        &self,
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        labels: &TranscriptLabels,
+        model: &SvmModel,
+        addition_commitments: &Vec<Vec<CompressedRistretto>>,
+        std_commitments: &Vec<Vec<CompressedRistretto>>,
+        decision_commitment: CompressedRistretto,
+    ) -> Result<(), ProofError> {
+        let score_commitment = model.score_commitment(pedersen_generators, addition_commitments, std_commitments)?;
+        self.verify_for_score(bulletproof_generators, pedersen_generators, labels, score_commitment, decision_commitment)
+    }
+
+    /// Verifies that `decision_commitment` commits to the decision over the score of
+    /// `score_commitment`, see `create_for_score`.
+    #[cfg(feature = "std")]
+    pub fn verify_for_score(
+        &self,
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        labels: &TranscriptLabels,
+        score_commitment: CompressedRistretto,
+        decision_commitment: CompressedRistretto,
+    ) -> Result<(), ProofError> {
+        self.verify_for_scores(
+            bulletproof_generators,
+            pedersen_generators,
+            labels,
+            &[score_commitment],
+            &[decision_commitment],
+        )
+    }
+
+    /// Verifies that each of the `decision_commitments` commits to the decision over the score
+    /// of the same position of `score_commitments`, see `create_for_scores`.
+    #[cfg(feature = "std")]
+    pub fn verify_for_scores(
+        &self,
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        labels: &TranscriptLabels,
+        score_commitments: &[CompressedRistretto],
+        decision_commitments: &[CompressedRistretto],
+    ) -> Result<(), ProofError> {
+        if score_commitments.len() != decision_commitments.len() {
+            return Err(ProofError::VerificationError);
+        }
+
+        let mut verifier = Verifier::new(labels.transcript(labels.decision));
+        for (score_commitment, decision_commitment) in score_commitments.iter().zip(decision_commitments.iter()) {
+            let score_var = verifier.commit(*score_commitment);
+            let decision_var = verifier.commit(*decision_commitment);
+            decision_gadget(&mut verifier, score_var.into(), decision_var.into(), None)
+                .map_err(from_r1cs_error)?;
+        }
+
+        verifier.verify(&self.proof, pedersen_generators, bulletproof_generators)
+            .map_err(from_r1cs_error)
+    }
+}
+
+impl CanonicalPoints for DecisionProof {
+    fn points_are_canonical(&self) -> bool {
+        self.proof.points_are_canonical()
+    }
+}
+
+/// A `zkSVMProver`, proving the features of the sensor vectors, together with the proof of
+/// the decision of a model over them.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ClassificationProof {
+    pub prover: zkSVMProver,
+    proof_decision: DecisionProof,
+}
+
+impl ClassificationProof {
+    /// Proves the features as `zkSVMProver::new_with_params`, and the decision of `model`
+    /// over them. Returns the commitment to the decision, under the `PedersenGens` of the
+    /// parameters, together with the proof.
+    #[cfg(feature = "std")]
+    pub fn create(
+        params: &PublicParams,
+        model: &SvmModel,
+        input_vector: &[[Vec<Scalar>; 3]],
+        non_zero_elements: &[usize],
+        diff_vector_scalar: &[[Vec<Scalar>; 3]],
+        additions: &Vec<Vec<Scalar>>,
+        variances: &Vec<Vec<Scalar>>,
+        sensor_vectors_stds: &Vec<Vec<Scalar>>,
+    ) -> Result<(CompressedRistretto, ClassificationProof), ProofError> {
+        let (decision_commitment, _, proof) = ClassificationProof::create_with_opening(
+            params,
+            model,
+            input_vector,
+            non_zero_elements,
+            diff_vector_scalar,
+            additions,
+            variances,
+            sensor_vectors_stds,
+        )?;
+        Ok((decision_commitment, proof))
+    }
+
+    /// As `create`, returning as well the blinding factor of the commitment to the decision.
+    #[cfg(feature = "std")]
+    fn create_with_opening(
+        params: &PublicParams,
+        model: &SvmModel,
+        input_vector: &[[Vec<Scalar>; 3]],
+        non_zero_elements: &[usize],
+        diff_vector_scalar: &[[Vec<Scalar>; 3]],
+        additions: &Vec<Vec<Scalar>>,
+        variances: &Vec<Vec<Scalar>>,
+        sensor_vectors_stds: &Vec<Vec<Scalar>>,
+    ) -> Result<(CompressedRistretto, Scalar, ClassificationProof), ProofError> {
+        let (prover, blindings) = zkSVMProver::new_with_feature_blindings(
+            params,
+            input_vector,
+            non_zero_elements,
+            diff_vector_scalar,
+            additions,
+            variances,
+            sensor_vectors_stds,
+        )?;
+
+        let (proof_decision, decision_commitment, decision_blinding) = DecisionProof::create(
+            &BulletproofGens::new(DECISION_GENS_CAPACITY, 1),
+            params.pedersen_gens(),
+            params.labels(),
+            model,
+            additions,
+            &blindings.additions,
+            sensor_vectors_stds,
+            &blindings.stds,
+        )?;
+
+        Ok((decision_commitment, decision_blinding, ClassificationProof { prover, proof_decision }))
+    }
+
+    /// Verifies the features under the given public parameters, and that
+    /// `decision_commitment` commits to the decision of `model` over them.
+    #[cfg(feature = "std")]
+    pub fn verify_with_params(
+        self,
+        params: &PublicParams,
+        model: &SvmModel,
+        decision_commitment: CompressedRistretto,
+    ) -> Result<(), ProofError> {
+        let (addition_commitments, std_commitments) = self.prover.feature_commitments();
+        self.proof_decision.verify(
+            &BulletproofGens::new(DECISION_GENS_CAPACITY, 1),
+            params.pedersen_gens(),
+            params.labels(),
+            model,
+            addition_commitments,
+            std_commitments,
+            decision_commitment,
+        )?;
+
+        self.prover.verify_with_params(params)
+    }
+
+    /// Serializes the proof, with the features encoded as `zkSVMProver::to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode_serialize(&(self.prover.to_bytes(), &self.proof_decision))
+            .expect("Should never happen, as all the fields can be serialized.")
+    }
+
+    /// Parses a proof serialized with `to_bytes`, to be verified under the given public
+    /// parameters.
+    pub fn from_bytes(bytes: &[u8], params: &PublicParams) -> Result<ClassificationProof, ProofError> {
+        let (prover, proof_decision): (Vec<u8>, DecisionProof) =
+            bincode_deserialize(bytes).map_err(|_| ProofError::FormatError)?;
+        Ok(ClassificationProof {
+            prover: zkSVMProver::from_bytes(&prover, params)?,
+            proof_decision,
+        })
+    }
+
+    /// As `from_bytes`, rejecting proofs with non-canonical points, see `CanonicalPoints`.
+    pub fn from_bytes_strict(bytes: &[u8], params: &PublicParams) -> Result<ClassificationProof, ProofError> {
+        check_canonical(ClassificationProof::from_bytes(bytes, params)?)
+    }
+}
+
+impl PartialOrd for ClassificationProof {
+    fn partial_cmp(&self, other: &ClassificationProof) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ClassificationProof {
+    fn cmp(&self, other: &ClassificationProof) -> Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
+impl Hash for ClassificationProof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+impl CanonicalPoints for ClassificationProof {
+    fn points_are_canonical(&self) -> bool {
+        self.prover.points_are_canonical() && self.proof_decision.points_are_canonical()
+    }
+}
+
+impl SerializedSize for ClassificationProof {
+    /// Size of the encoding of `to_bytes`, where the proof of the features is nested as a
+    /// length-prefixed byte vector.
+    fn serialized_size(&self) -> usize {
+        8 + self.prover.serialized_size() + bincode_size(&self.proof_decision)
+    }
+}
+
+/// A `ClassificationProof` that reveals the decision, by opening its commitment. The features
+/// remain hidden, and the blinding factor of the commitment to a bit reveals nothing else.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RevealedClassificationProof {
+    proof: ClassificationProof,
+    decision: bool,
+    decision_blinding: Scalar,
+}
+
+impl RevealedClassificationProof {
+    /// Proves the features and the decision of `model` over them, as
+    /// `ClassificationProof::create`, with the decision revealed.
+    #[cfg(feature = "std")]
+    pub fn create(
+        params: &PublicParams,
+        model: &SvmModel,
+        input_vector: &[[Vec<Scalar>; 3]],
+        non_zero_elements: &[usize],
+        diff_vector_scalar: &[[Vec<Scalar>; 3]],
+        additions: &Vec<Vec<Scalar>>,
+        variances: &Vec<Vec<Scalar>>,
+        sensor_vectors_stds: &Vec<Vec<Scalar>>,
+    ) -> Result<RevealedClassificationProof, ProofError> {
+        let (decision_commitment, decision_blinding, proof) = ClassificationProof::create_with_opening(
+            params,
+            model,
+            input_vector,
+            non_zero_elements,
+            diff_vector_scalar,
+            additions,
+            variances,
+            sensor_vectors_stds,
+        )?;
+        let decision = decision_commitment == params.pedersen_gens().commit(Scalar::ONE, decision_blinding).compress();
+
+        Ok(RevealedClassificationProof { proof, decision, decision_blinding })
+    }
+
+    /// The claimed decision, i.e. whether the score of the model is non-negative. It is only
+    /// proven once the proof is verified.
+    pub fn decision(&self) -> bool {
+        self.decision
+    }
+
+    /// Verifies the proof under the given public parameters, and returns the decision of
+    /// `model` over the hidden features.
+    #[cfg(feature = "std")]
+    pub fn verify_with_params(self, params: &PublicParams, model: &SvmModel) -> Result<bool, ProofError> {
+        let decision_commitment = params.pedersen_gens()
+            .commit(Scalar::from(self.decision as u64), self.decision_blinding)
+            .compress();
+        self.proof.verify_with_params(params, model, decision_commitment)?;
+        Ok(self.decision)
+    }
+
+    /// Serializes the proof, with the classification encoded as `ClassificationProof::to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode_serialize(&(self.proof.to_bytes(), self.decision, self.decision_blinding))
+            .expect("Should never happen, as all the fields can be serialized.")
+    }
+
+    /// Parses a proof serialized with `to_bytes`, to be verified under the given public
+    /// parameters.
+    pub fn from_bytes(bytes: &[u8], params: &PublicParams) -> Result<RevealedClassificationProof, ProofError> {
+        let (proof, decision, decision_blinding): (Vec<u8>, bool, Scalar) =
+            bincode_deserialize(bytes).map_err(|_| ProofError::FormatError)?;
+        Ok(RevealedClassificationProof {
+            proof: ClassificationProof::from_bytes(&proof, params)?,
+            decision,
+            decision_blinding,
+        })
+    }
+
+    /// As `from_bytes`, rejecting proofs with non-canonical points, see `CanonicalPoints`.
+    pub fn from_bytes_strict(bytes: &[u8], params: &PublicParams) -> Result<RevealedClassificationProof, ProofError> {
+        check_canonical(RevealedClassificationProof::from_bytes(bytes, params)?)
+    }
+}
+
+impl CanonicalPoints for RevealedClassificationProof {
+    fn points_are_canonical(&self) -> bool {
+        self.proof.points_are_canonical()
+    }
+}
+
+impl PartialOrd for RevealedClassificationProof {
+    fn partial_cmp(&self, other: &RevealedClassificationProof) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RevealedClassificationProof {
+    fn cmp(&self, other: &RevealedClassificationProof) -> Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
+impl Hash for RevealedClassificationProof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+pub(crate) fn from_r1cs_error(error: R1CSError) -> ProofError {
+    match error {
+        R1CSError::InvalidGeneratorsLength => ProofError::InvalidGeneratorsLength,
+        R1CSError::FormatError => ProofError::FormatError,
+        _ => ProofError::VerificationError,
+    }
+}
+
+/// The decision \\(s \geq 0\\) for the score \\(s\\), with the assignment of `decision_gadget`,
+/// if the score fits in `SCORE_BITSIZE` bits.
+#[cfg(feature = "std")]
+fn decision_assignment(score: &Scalar) -> Option<(bool, u64)> {
+    let to_u64 = |value: Scalar| {
+        let bytes = value.to_bytes();
+        if bytes[8..].iter().all(|&b| b == 0) {
+            Some(u64::from_le_bytes(bytes[..8].try_into().expect("Should never happen as we are taking a slice of 8.")))
+        } else {
+            None
+        }
+    };
+    match to_u64(*score) {
+        Some(assignment) => Some((true, assignment)),
+        None => to_u64(-score - Scalar::ONE).map(|assignment| (false, assignment)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn features(values: &[i64]) -> Vec<Vec<Scalar>> {
+        values.chunks(3).map(|c| c.iter().map(|&x| signed_scalar(x)).collect()).collect()
+    }
+
+    fn blindings(shape: usize) -> Vec<Vec<Scalar>> {
+        (0..shape).map(|_| (0..3).map(|_| Scalar::random(&mut thread_rng())).collect()).collect()
+    }
+
+    /// Proves the decision of a model with the given bias over fixed features, and verifies
+    /// it under `verifier_model`, or the same model. Returns the proven decision.
+    fn decision_helper(bias: i64, verifier_model: Option<&SvmModel>) -> Result<bool, ProofError> {
+        let bulletproof_generators = BulletproofGens::new(DECISION_GENS_CAPACITY, 1);
+        let pedersen_generators: PedersenGens = PedersenGens::default();
+        let labels = TranscriptLabels::default();
+        let model = SvmModel {
+            addition_weights: vec![vec![2, -1, 0], vec![0, 3, 1]],
+            std_weights: vec![vec![-4, 0, 1]],
+            bias,
+        };
+        // The score without bias is 2 * 10 + 5 + 3 * 7 - 2 - 4 * 6 + 9 = 29
+        let additions = features(&[10, -5, 100, 0, 7, -2]);
+        let stds = features(&[6, 50, 9]);
+        let addition_blindings = blindings(2);
+        let std_blindings = blindings(1);
+        let commit = |values: &Vec<Vec<Scalar>>, blindings: &Vec<Vec<Scalar>>| -> Vec<Vec<CompressedRistretto>> {
+            values.iter().zip(blindings.iter()).map(
+                |(v, b)| v.iter().zip(b.iter()).map(|(v, b)| pedersen_generators.commit(*v, *b).compress()).collect()
+            ).collect()
+        };
+
+        let (proof, decision_commitment, decision_blinding) = DecisionProof::create(
+            &bulletproof_generators,
+            &pedersen_generators,
+            &labels,
+            &model,
+            &additions,
+            &addition_blindings,
+            &stds,
+            &std_blindings,
+        )?;
+        let decision = decision_commitment == pedersen_generators.commit(Scalar::ONE, decision_blinding).compress();
+
+        proof.verify(
+            &bulletproof_generators,
+            &pedersen_generators,
+            &labels,
+            verifier_model.unwrap_or(&model),
+            &commit(&additions, &addition_blindings),
+            &commit(&stds, &std_blindings),
+            decision_commitment,
+        )?;
+        Ok(decision)
+    }
+
+    #[test]
+    fn decision_proof_works() {
+        assert_eq!(decision_helper(0, None), Ok(true));
+        assert_eq!(decision_helper(-29, None), Ok(true));
+        assert_eq!(decision_helper(-30, None), Ok(false));
+        assert_eq!(decision_helper(-1 << 40, None), Ok(false));
+    }
+
+    #[test]
+    fn decision_proof_fails() {
+        // The decision is bound to the model
+        let other_bias = SvmModel {
+            addition_weights: vec![vec![2, -1, 0], vec![0, 3, 1]],
+            std_weights: vec![vec![-4, 0, 1]],
+            bias: -30,
+        };
+        assert_eq!(decision_helper(-29, Some(&other_bias)).err(), Some(ProofError::VerificationError));
+
+        let other_shape = SvmModel {
+            addition_weights: vec![vec![2, -1, 0]],
+            std_weights: vec![vec![-4, 0, 1]],
+            bias: 0,
+        };
+        assert_eq!(decision_helper(0, Some(&other_shape)).err(), Some(ProofError::InvalidWeights));
+    }
+
+    #[test]
+    fn committed_bit_is_the_decision() {
+        let bulletproof_generators = BulletproofGens::new(DECISION_GENS_CAPACITY, 1);
+        let pedersen_generators: PedersenGens = PedersenGens::default();
+        let labels = TranscriptLabels::default();
+        let model = SvmModel { addition_weights: vec![vec![1, 1, 1]], std_weights: vec![], bias: -10 };
+        let additions = features(&[1, 2, 3]);
+        let addition_blindings = blindings(1);
+        let addition_commitments: Vec<Vec<CompressedRistretto>> = vec![additions[0].iter()
+            .zip(addition_blindings[0].iter())
+            .map(|(v, b)| pedersen_generators.commit(*v, *b).compress())
+            .collect()];
+
+        // A prover claiming the wrong decision commits to the other bit, for a score of -4
+        let score = model.score(&additions, &vec![]).unwrap();
+        let score_blinding = model.score(&addition_blindings, &vec![]).unwrap() - signed_scalar(model.bias);
+        let mut prover = Prover::new(&pedersen_generators, labels.transcript(labels.decision));
+        let (_, score_var) = prover.commit(score, score_blinding);
+        let (decision_commitment, decision_var) = prover.commit(Scalar::ONE, Scalar::random(&mut thread_rng()));
+        decision_gadget(&mut prover, score_var.into(), decision_var.into(), Some(u64::MAX - 3)).unwrap();
+        let proof = DecisionProof { proof: prover.prove(&bulletproof_generators).unwrap() };
+
+        assert_eq!(
+            proof.verify(&bulletproof_generators, &pedersen_generators, &labels, &model, &addition_commitments, &vec![], decision_commitment).err(),
+            Some(ProofError::VerificationError)
+        );
+    }
+
+    #[test]
+    fn score_out_of_range() {
+        let model = SvmModel { addition_weights: vec![vec![i64::MAX, i64::MAX, 0]], std_weights: vec![], bias: 0 };
+        assert_eq!(
+            DecisionProof::create(
+                &BulletproofGens::new(DECISION_GENS_CAPACITY, 1),
+                &PedersenGens::default(),
+                &TranscriptLabels::default(),
+                &model,
+                &features(&[i64::MAX, 1, 0]),
+                &blindings(1),
+                &vec![],
+                &vec![],
+            ).err(),
+            Some(ProofError::InvalidWeights)
+        );
+    }
+
+    #[test]
+    fn model_parsing() {
+        let model = SvmModel::parse("# model\nbias -12\n\naddition 1 -2 3\naddition 0 0 4\nstd 5 6 -7\n").unwrap();
+        assert_eq!(model, SvmModel {
+            addition_weights: vec![vec![1, -2, 3], vec![0, 0, 4]],
+            std_weights: vec![vec![5, 6, -7]],
+            bias: -12,
+        });
+
+        assert_eq!(SvmModel::parse("addition 1 2 3").err(), Some(ProofError::FormatError));
+        assert_eq!(SvmModel::parse("bias 1\nbias 2").err(), Some(ProofError::FormatError));
+        assert_eq!(SvmModel::parse("bias 1\nstd 1 2").err(), Some(ProofError::FormatError));
+        assert_eq!(SvmModel::parse("bias 1\nweight 1 2 3").err(), Some(ProofError::FormatError));
+        assert_eq!(SvmModel::parse("bias 0.5").err(), Some(ProofError::FormatError));
+    }
+
+    #[test]
+    fn required_statistics() {
+        let model = SvmModel::parse("bias 1\naddition 0 0 0\naddition 0 2 0\nstd 1 0 0\nstd 0 0 0\n").unwrap();
+        assert_eq!(
+            model.required_statistics(),
+            Ok(StatisticSet::empty().with(Statistic::Addition, 1).with(Statistic::Std, 0))
+        );
+
+        let model = SvmModel { addition_weights: vec![vec![1, 0, 0]; 33], std_weights: vec![], bias: 0 };
+        assert_eq!(model.required_statistics().err(), Some(ProofError::FormatError));
+    }
+}
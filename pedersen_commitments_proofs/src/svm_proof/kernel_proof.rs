@@ -0,0 +1,394 @@
+//! Decision of a kernel SVM with public support vectors over the features proven by
+//! `zkSVMProver`.
+//!
+//! The score of the model is \\(s = \sum_i \alpha_i K(x, sv_i) + bias\\), over the kernel values
+//! of the features \\(x\\) with each support vector \\(sv_i\\). With public support vectors and the
+//! linear kernel \\(K(x, sv_i) = \langle x, sv_i \rangle\\), the prover commits to each kernel
+//! value and proves it with an inner product proof with a public right hand side. The
+//! verifier computes the commitment to the score from the commitments to the kernel values,
+//! and a `DecisionProof` shows that a committed bit is the decision over it. This is a first
+//! step towards kernels over private support vectors.
+
+use ip_zk_proof::{inner_product, BulletproofGens, InnerProductZKProof, PedersenGens, ProofError};
+use ip_zk_proof::r1cs::gadgets::signed_scalar;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{MultiscalarMul, VartimeMultiscalarMul};
+
+use rand::thread_rng;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::iter;
+
+use crate::sigma::{PointVar, Prover, ScalarVar, SchnorrCS, SigmaProof, Verifier};
+use crate::svm_proof::decision_proof::{DecisionProof, DECISION_GENS_CAPACITY};
+use crate::utils::randomness::random_scalars;
+use crate::TranscriptLabels;
+
+/// A kernel SVM over the features of `zkSVMProver`, with public support vectors and integer
+/// coefficients.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KernelSvmModel {
+    /// Support vectors, over the additions of each axis of each evaluated vector followed by
+    /// the standard deviations of each axis of each sensor vector
+    pub support_vectors: Vec<Vec<i64>>,
+    /// Coefficient of each support vector, i.e. its dual coefficient times its label
+    pub coefficients: Vec<i64>,
+    pub bias: i64,
+}
+
+impl KernelSvmModel {
+    /// The kernel value of `features` with each support vector. Returns `InvalidWeights` if
+    /// the support vectors do not have the length of the features.
+    pub fn kernel_values(&self, features: &[Scalar]) -> Result<Vec<Scalar>, ProofError> {
+        self.check_shape(features.len())?;
+        Ok(self.support_vectors.iter()
+            .map(|sv| inner_product(features, &signed_scalars(sv)))
+            .collect())
+    }
+
+    /// Score of the model over the given features, as a scalar.
+    pub fn score(&self, features: &[Scalar]) -> Result<Scalar, ProofError> {
+        Ok(self.combine(&self.kernel_values(features)?) + signed_scalar(self.bias))
+    }
+
+    /// The commitment to the score, computed from the commitments to the kernel values.
+    fn score_commitment(
+        &self,
+        pedersen_generators: &PedersenGens,
+        kernel_commitments: &[CompressedRistretto],
+    ) -> Result<CompressedRistretto, ProofError> {
+        let scalars: Vec<Scalar> = signed_scalars(&self.coefficients).into_iter()
+            .chain(iter::once(signed_scalar(self.bias)))
+            .collect();
+        let points: Vec<Option<RistrettoPoint>> = kernel_commitments.iter()
+            .map(|c| c.decompress())
+            .chain(iter::once(Some(pedersen_generators.B)))
+            .collect();
+        let _span = msm_span!("kernel", size = scalars.len());
+        let commitment = RistrettoPoint::optional_multiscalar_mul(scalars, points)
+            .ok_or(ProofError::VerificationError)?;
+        Ok(commitment.compress())
+    }
+
+    /// The combination of the kernel values with the coefficients, without the bias.
+    fn combine(&self, kernel_values: &[Scalar]) -> Scalar {
+        inner_product(kernel_values, &signed_scalars(&self.coefficients))
+    }
+
+    fn check_shape(&self, nr_features: usize) -> Result<(), ProofError> {
+        if self.coefficients.len() != self.support_vectors.len()
+            || self.support_vectors.iter().any(|sv| sv.len() != nr_features)
+        {
+            return Err(ProofError::InvalidWeights);
+        }
+        Ok(())
+    }
+}
+
+/// Proof that a committed bit is the decision of a `KernelSvmModel` over committed features.
+///
+/// The prover commits to the features as a vector, \\(A = \tilde{a} \cdot \tilde{B} + \langle x,
+/// \mathbf{G} \rangle\\) over the `BulletproofGens`, and shows with a sigma proof that its
+/// elements are the openings of the commitments to the features. The kernel proofs are inner
+/// product proofs with public right hand sides over that same \\(A\\).
+///
+/// The `BulletproofGens` must have a capacity of at least `DECISION_GENS_CAPACITY`, and of
+/// the number of features rounded up to the next power of two.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct KernelProof {
+    // Commitment to the vector of features
+    features_commitment: CompressedRistretto,
+    proof_features: SigmaProof,
+    // Commitment to the kernel value of each support vector, with its proof
+    kernel_commitments: Vec<CompressedRistretto>,
+    proofs_kernel: Vec<InnerProductZKProof>,
+    proof_decision: DecisionProof,
+}
+
+/// Shows that the elements of the vector commitment `A` are the openings of the feature
+/// commitments `X_j = x_j * G + r_j * B`.
+fn features_statement<CS: SchnorrCS>(
+    cs: &mut CS,
+    openings: &[ScalarVar],
+    blindings: &[ScalarVar],
+    vector_blinding: ScalarVar,
+    A: PointVar,
+    bases: &[PointVar],
+    features: &[PointVar],
+    G: PointVar,
+    B: PointVar,
+) {
+    cs.constrain(
+        A,
+        openings.iter().cloned().zip(bases.iter().cloned())
+            .chain(iter::once((vector_blinding, B)))
+            .collect(),
+    );
+    for ((x, r), X) in openings.iter().zip(blindings.iter()).zip(features.iter()) {
+        cs.constrain(*X, vec![(*x, G), (*r, B)]);
+    }
+}
+
+impl KernelProof {
+    /// Proves the decision of `model` over the `features`, committed under
+    /// `pedersen_generators` with `feature_blindings`, flattened as the support vectors.
+    /// Returns the proof, with the commitment to the decision and its blinding factor.
+    ///
+    /// Returns `InvalidWeights` if the shape of the model is not the one of the features, or if
+    /// the score does not fit in `SCORE_BITSIZE` bits, and `InvalidGeneratorsLength` if the
+    /// generators do not have the capacity of the proof.
+    pub fn create(
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        labels: &TranscriptLabels,
+        model: &KernelSvmModel,
+        features: &[Scalar],
+        feature_blindings: &[Scalar],
+    ) -> Result<(KernelProof, CompressedRistretto, Scalar), ProofError> {
+        KernelProof::create_with_rng(
+            bulletproof_generators,
+            pedersen_generators,
+            labels,
+            model,
+            features,
+            feature_blindings,
+            &mut thread_rng(),
+        )
+    }
+
+    /// As `create`, with the randomness of the proofs of the kernel values drawn from `rng`.
+    pub fn create_with_rng<T: RngCore + CryptoRng>(
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        labels: &TranscriptLabels,
+        model: &KernelSvmModel,
+        features: &[Scalar],
+        feature_blindings: &[Scalar],
+        rng: &mut T,
+    ) -> Result<(KernelProof, CompressedRistretto, Scalar), ProofError> {
+        let kernel_values = model.kernel_values(features)?;
+        if feature_blindings.len() != features.len() {
+            return Err(ProofError::WrongNumBlindingFactors);
+        }
+        let n = check_capacity(bulletproof_generators, features.len())?;
+        let mut padded_features = features.to_vec();
+        padded_features.resize(n, Scalar::ZERO);
+
+        let vector_blinding = Scalar::random(rng);
+        let bases: Vec<RistrettoPoint> = bulletproof_generators.G(n, 1).take(features.len()).cloned().collect();
+        let features_commitment = RistrettoPoint::multiscalar_mul(
+            features.iter().chain(iter::once(&vector_blinding)),
+            bases.iter().chain(iter::once(&pedersen_generators.B_blinding)),
+        );
+
+        let mut transcript = labels.transcript(labels.kernel);
+        let mut prover = Prover::new(b"KernelFeatures", &mut transcript);
+        let openings: Vec<ScalarVar> = features.iter().map(|x| prover.allocate_scalar(b"x", *x)).collect();
+        let blindings: Vec<ScalarVar> = feature_blindings.iter().map(|r| prover.allocate_scalar(b"r", *r)).collect();
+        let vector_blinding_var = prover.allocate_scalar(b"vector blinding", vector_blinding);
+        let (A, features_commitment) = prover.allocate_point(b"A", features_commitment);
+        let base_vars: Vec<PointVar> = bases.iter().map(|G| prover.allocate_point(b"base", *G).0).collect();
+        let feature_vars: Vec<PointVar> = features.iter().zip(feature_blindings.iter())
+            .map(|(x, r)| prover.allocate_point(b"feature", pedersen_generators.commit(*x, *r)).0)
+            .collect();
+        let G = prover.allocate_point(b"G", pedersen_generators.B).0;
+        let B = prover.allocate_point(b"B", pedersen_generators.B_blinding).0;
+        features_statement(&mut prover, &openings, &blindings, vector_blinding_var, A, &base_vars, &feature_vars, G, B);
+        let proof_features = prover.prove_with_rng(rng);
+
+        let kernel_blindings = random_scalars(rng, kernel_values.len());
+        let mut kernel_commitments = Vec::with_capacity(kernel_values.len());
+        let mut proofs_kernel = Vec::with_capacity(kernel_values.len());
+        for ((sv, value), blinding) in model.support_vectors.iter().zip(kernel_values.iter()).zip(kernel_blindings.iter()) {
+            let (proof, commitment) = InnerProductZKProof::prove_with_public_rhs(
+                bulletproof_generators,
+                pedersen_generators,
+                &mut transcript,
+                *value,
+                &padded_features,
+                &padded_support_vector(sv, n),
+                *blinding,
+                vector_blinding,
+                n,
+                rng,
+            )?;
+            kernel_commitments.push(commitment);
+            proofs_kernel.push(proof);
+        }
+
+        let score = model.combine(&kernel_values) + signed_scalar(model.bias);
+        let score_blinding = model.combine(&kernel_blindings);
+        let (proof_decision, decision_commitment, decision_blinding) = DecisionProof::create_for_score(
+            bulletproof_generators,
+            pedersen_generators,
+            labels,
+            score,
+            score_blinding,
+        )?;
+
+        let proof = KernelProof {
+            features_commitment,
+            proof_features,
+            kernel_commitments,
+            proofs_kernel,
+            proof_decision,
+        };
+        Ok((proof, decision_commitment, decision_blinding))
+    }
+
+    /// The commitments to the kernel value of the features with each support vector.
+    pub fn kernel_commitments(&self) -> &Vec<CompressedRistretto> {
+        &self.kernel_commitments
+    }
+
+    /// Verifies that `decision_commitment` commits to the decision of `model` over the
+    /// features with the given commitments, e.g. the flattened
+    /// `zkSVMProver::feature_commitments`.
+    pub fn verify(
+        &self,
+        bulletproof_generators: &BulletproofGens,
+        pedersen_generators: &PedersenGens,
+        labels: &TranscriptLabels,
+        model: &KernelSvmModel,
+        feature_commitments: &[CompressedRistretto],
+        decision_commitment: CompressedRistretto,
+    ) -> Result<(), ProofError> {
+        model.check_shape(feature_commitments.len())?;
+        if self.kernel_commitments.len() != model.support_vectors.len()
+            || self.proofs_kernel.len() != model.support_vectors.len()
+        {
+            return Err(ProofError::VerificationError);
+        }
+        let n = check_capacity(bulletproof_generators, feature_commitments.len())?;
+
+        let mut transcript = labels.transcript(labels.kernel);
+        let mut verifier = Verifier::new(b"KernelFeatures", &mut transcript);
+        let openings: Vec<ScalarVar> = feature_commitments.iter().map(|_| verifier.allocate_scalar(b"x")).collect();
+        let blindings: Vec<ScalarVar> = feature_commitments.iter().map(|_| verifier.allocate_scalar(b"r")).collect();
+        let vector_blinding = verifier.allocate_scalar(b"vector blinding");
+        let A = verifier.allocate_point(b"A", self.features_commitment)?;
+        let bases = bulletproof_generators.G(n, 1).take(feature_commitments.len())
+            .map(|G| verifier.allocate_point(b"base", G.compress()))
+            .collect::<Result<Vec<PointVar>, ProofError>>()?;
+        let features = feature_commitments.iter()
+            .map(|X| verifier.allocate_point(b"feature", *X))
+            .collect::<Result<Vec<PointVar>, ProofError>>()?;
+        let G = verifier.allocate_point(b"G", pedersen_generators.B.compress())?;
+        let B = verifier.allocate_point(b"B", pedersen_generators.B_blinding.compress())?;
+        features_statement(&mut verifier, &openings, &blindings, vector_blinding, A, &bases, &features, G, B);
+        verifier.verify(&self.proof_features)?;
+
+        let mut rng = thread_rng();
+        for ((sv, commitment), proof) in model.support_vectors.iter().zip(self.kernel_commitments.iter()).zip(self.proofs_kernel.iter()) {
+            proof.verify_with_public_rhs_with_expected_A(
+                bulletproof_generators,
+                pedersen_generators,
+                &mut transcript,
+                commitment,
+                &self.features_commitment,
+                &padded_support_vector(sv, n),
+                n,
+                &mut rng,
+            )?;
+        }
+
+        let score_commitment = model.score_commitment(pedersen_generators, &self.kernel_commitments)?;
+        self.proof_decision.verify_for_score(
+            bulletproof_generators,
+            pedersen_generators,
+            labels,
+            score_commitment,
+            decision_commitment,
+        )
+    }
+}
+
+/// The length of the vectors of the kernel proofs, the number of features rounded up to the
+/// next power of two. Returns `InvalidGeneratorsLength` if the generators do not have the
+/// capacity of the proof.
+fn check_capacity(bulletproof_generators: &BulletproofGens, nr_features: usize) -> Result<usize, ProofError> {
+    let n = nr_features.next_power_of_two();
+    if bulletproof_generators.gens_capacity < n.max(DECISION_GENS_CAPACITY) {
+        return Err(ProofError::InvalidGeneratorsLength);
+    }
+    Ok(n)
+}
+
+fn signed_scalars(values: &[i64]) -> Vec<Scalar> {
+    values.iter().map(|&v| signed_scalar(v)).collect()
+}
+
+fn padded_support_vector(support_vector: &[i64], n: usize) -> Vec<Scalar> {
+    let mut padded = signed_scalars(support_vector);
+    padded.resize(n, Scalar::ZERO);
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(bias: i64) -> KernelSvmModel {
+        KernelSvmModel {
+            support_vectors: vec![vec![1, 0, 2, -1, 0], vec![0, 3, 0, 0, 1], vec![-2, 1, 1, 1, 1]],
+            coefficients: vec![2, -1, 1],
+            bias,
+        }
+    }
+
+    /// Proves the decision of `model(bias)` over fixed features, and verifies it under
+    /// `verifier_model`. Returns the proven decision.
+    fn kernel_helper(bias: i64, verifier_model: &KernelSvmModel) -> Result<bool, ProofError> {
+        let bulletproof_generators = BulletproofGens::new(DECISION_GENS_CAPACITY, 1);
+        let pedersen_generators: PedersenGens = PedersenGens::default();
+        let labels = TranscriptLabels::default();
+        let features: Vec<Scalar> = signed_scalars(&[10, -5, 3, 7, 2]);
+        let blindings = random_scalars(&mut thread_rng(), features.len());
+        let feature_commitments: Vec<CompressedRistretto> = features.iter().zip(blindings.iter())
+            .map(|(x, r)| pedersen_generators.commit(*x, *r).compress())
+            .collect();
+
+        let (proof, decision_commitment, decision_blinding) = KernelProof::create(
+            &bulletproof_generators,
+            &pedersen_generators,
+            &labels,
+            &model(bias),
+            &features,
+            &blindings,
+        )?;
+        proof.verify(
+            &bulletproof_generators,
+            &pedersen_generators,
+            &labels,
+            verifier_model,
+            &feature_commitments,
+            decision_commitment,
+        )?;
+
+        let decision = pedersen_generators.commit(Scalar::ONE, decision_blinding).compress() == decision_commitment;
+        Ok(decision)
+    }
+
+    #[test]
+    fn kernel_proof_works() {
+        // The kernel values are (9, -13, -13), so the score without bias is 18 + 13 - 13 = 18
+        assert_eq!(model(0).score(&signed_scalars(&[10, -5, 3, 7, 2])), Ok(Scalar::from(18u64)));
+        assert_eq!(kernel_helper(-18, &model(-18)), Ok(true));
+        assert_eq!(kernel_helper(-19, &model(-19)), Ok(false));
+    }
+
+    #[test]
+    fn kernel_proof_fails() {
+        assert_eq!(kernel_helper(-18, &model(-19)).err(), Some(ProofError::VerificationError));
+
+        let mut other_support_vectors = model(-18);
+        other_support_vectors.support_vectors[1][4] = 2;
+        assert_eq!(kernel_helper(-18, &other_support_vectors).err(), Some(ProofError::VerificationError));
+
+        let mut short_model = model(0);
+        short_model.support_vectors[0].pop();
+        assert_eq!(kernel_helper(0, &short_model).err(), Some(ProofError::InvalidWeights));
+    }
+}
@@ -1 +1,11 @@
-pub mod adhoc_proof;
\ No newline at end of file
+// The sensor hubs, the kernels and the batches of windows prove with the thread RNG
+#[cfg(feature = "std")]
+pub mod aggregation;
+pub mod adhoc_proof;
+pub mod builder;
+pub mod decision_proof;
+#[cfg(feature = "std")]
+pub mod kernel_proof;
+#[cfg(feature = "std")]
+pub mod multi_window;
+pub mod session;
@@ -0,0 +1,216 @@
+//! Proofs of the decisions of one model over consecutive windows.
+//!
+//! A device typically uploads a batch of windows every few minutes, all of them classified by
+//! the same model. A `MultiWindowProof` proves them in one shot: the windows share the public
+//! parameters, the model is bound once to a single transcript, which the proofs of the windows
+//! are chained on, and the decisions over all the windows, with the range proofs of their
+//! scores, are proven in a single `DecisionProof`.
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use ip_zk_proof::r1cs::gadgets::signed_scalar;
+use ip_zk_proof::{BulletproofGens, CanonicalPoints, ProofError, SerializedSize, TranscriptProtocol};
+use merlin::Transcript;
+use rand::thread_rng;
+
+use crate::features::FeatureWitness;
+use crate::metrics::NoMetrics;
+use crate::svm_proof::adhoc_proof::append_signed_commitments;
+use crate::svm_proof::decision_proof::{decision_gens_capacity, DecisionProof};
+use crate::utils::misc::{bincode_deserialize, bincode_serialize, bincode_size, check_canonical};
+use crate::{zkSVMProver, PublicParams, SvmModel};
+
+/// The `zkSVMProver` of each of a batch of windows, together with the proof of the decisions
+/// of a model over them.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MultiWindowProof {
+    pub windows: Vec<zkSVMProver>,
+    proof_decisions: DecisionProof,
+}
+
+impl MultiWindowProof {
+    /// Proves the features of each window of `witnesses`, see `SensorFeatures::witness`, under
+    /// `params`, and the decisions of `model` over them. Returns the commitments to the
+    /// decisions, one per window, under the `PedersenGens` of the parameters, together with the
+    /// proof. Returns a `FormatError` if there are no windows.
+    pub fn create(
+        params: &PublicParams,
+        model: &SvmModel,
+        witnesses: &[FeatureWitness],
+    ) -> Result<(Vec<CompressedRistretto>, MultiWindowProof), ProofError> {
+        if witnesses.is_empty() {
+            return Err(ProofError::FormatError);
+        }
+        let mut transcript = batch_transcript(params, model, witnesses.len());
+
+        let mut windows = Vec::with_capacity(witnesses.len());
+        let mut scores = Vec::with_capacity(witnesses.len());
+        let mut score_blindings = Vec::with_capacity(witnesses.len());
+        for witness in witnesses {
+            if params.size() != witness.input_vector[0][0].len() {
+                return Err(ProofError::InvalidGeneratorsLength);
+            }
+            let labels = params.labels().bind_to(&mut transcript);
+            let (prover, blindings) = zkSVMProver::create_with_config(
+                params.config().with_labels(labels),
+                None,
+                &mut thread_rng(),
+                &mut NoMetrics,
+                &witness.input_vector,
+                &witness.non_zero_elements,
+                &witness.diff_vectors,
+                &witness.additions,
+                &witness.variances,
+                &witness.stds,
+            )?;
+            append_signed_commitments(&mut transcript, prover.signed_commitments());
+
+            scores.push(model.score(&witness.additions, &witness.stds)?);
+            score_blindings.push(model.score(&blindings.additions, &blindings.stds)? - signed_scalar(model.bias));
+            windows.push(prover);
+        }
+
+        let (proof_decisions, decision_commitments, _) = DecisionProof::create_for_scores(
+            &BulletproofGens::new(decision_gens_capacity(witnesses.len()), 1),
+            params.pedersen_gens(),
+            &params.labels().bind_to(&mut transcript),
+            &scores,
+            &score_blindings,
+        )?;
+
+        Ok((decision_commitments, MultiWindowProof { windows, proof_decisions }))
+    }
+
+    /// Verifies the features of every window under the given public parameters, and that each
+    /// of the `decision_commitments` commits to the decision of `model` over the features of
+    /// its window.
+    pub fn verify_with_params(
+        self,
+        params: &PublicParams,
+        model: &SvmModel,
+        decision_commitments: &[CompressedRistretto],
+    ) -> Result<(), ProofError> {
+        if self.windows.is_empty() || decision_commitments.len() != self.windows.len() {
+            return Err(ProofError::VerificationError);
+        }
+        let mut transcript = batch_transcript(params, model, self.windows.len());
+
+        let mut score_commitments = Vec::with_capacity(self.windows.len());
+        for prover in self.windows {
+            let (addition_commitments, std_commitments) = prover.feature_commitments();
+            score_commitments.push(model.score_commitment(params.pedersen_gens(), addition_commitments, std_commitments)?);
+            prover.verify_with_transcript(params, &mut transcript)?;
+        }
+
+        self.proof_decisions.verify_for_scores(
+            &BulletproofGens::new(decision_gens_capacity(decision_commitments.len()), 1),
+            params.pedersen_gens(),
+            &params.labels().bind_to(&mut transcript),
+            &score_commitments,
+            decision_commitments,
+        )
+    }
+
+    /// Serializes the proof, with the features of each window encoded as
+    /// `zkSVMProver::to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let windows: Vec<Vec<u8>> = self.windows.iter().map(zkSVMProver::to_bytes).collect();
+        bincode_serialize(&(windows, &self.proof_decisions))
+            .expect("Should never happen, as all the fields can be serialized.")
+    }
+
+    /// Parses a proof serialized with `to_bytes`, to be verified under the given public
+    /// parameters.
+    pub fn from_bytes(bytes: &[u8], params: &PublicParams) -> Result<MultiWindowProof, ProofError> {
+        let (windows, proof_decisions): (Vec<Vec<u8>>, DecisionProof) =
+            bincode_deserialize(bytes).map_err(|_| ProofError::FormatError)?;
+        Ok(MultiWindowProof {
+            windows: windows.iter()
+                .map(|window| zkSVMProver::from_bytes(window, params))
+                .collect::<Result<_, ProofError>>()?,
+            proof_decisions,
+        })
+    }
+
+    /// As `from_bytes`, rejecting proofs with non-canonical points, see `CanonicalPoints`.
+    pub fn from_bytes_strict(bytes: &[u8], params: &PublicParams) -> Result<MultiWindowProof, ProofError> {
+        check_canonical(MultiWindowProof::from_bytes(bytes, params)?)
+    }
+}
+
+impl CanonicalPoints for MultiWindowProof {
+    fn points_are_canonical(&self) -> bool {
+        self.windows.points_are_canonical() && self.proof_decisions.points_are_canonical()
+    }
+}
+
+impl SerializedSize for MultiWindowProof {
+    /// Size of the encoding of `to_bytes`, where the proof of the features of each window is
+    /// nested as a length-prefixed byte vector.
+    fn serialized_size(&self) -> usize {
+        8 + self.windows.iter().map(|w| 8 + w.serialized_size()).sum::<usize>() + bincode_size(&self.proof_decisions)
+    }
+}
+
+/// The transcript shared by the proofs of all the windows, bound to the model and to the
+/// number of windows.
+fn batch_transcript(params: &PublicParams, model: &SvmModel, nr_windows: usize) -> Transcript {
+    let labels = params.labels();
+    let mut transcript = labels.transcript(labels.multi_window);
+    transcript.append_integer(b"windows", nr_windows as u64);
+    model.append_to_transcript(&mut transcript);
+    transcript
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::SensorFeatures;
+
+    fn witness(offset: i128) -> FeatureWitness {
+        let coord = |values: &[i128]| -> Vec<i128> {
+            let mut vector: Vec<i128> = values.iter().map(|x| x + offset).collect();
+            vector.resize(64, 0);
+            vector
+        };
+        let input = vec![
+            [coord(&[5, 3, 8, 1]), coord(&[2, 2, 2, 2]), coord(&[9, 0, 4, 7])],
+            [coord(&[1, 6, 3, 9, 4, 2]), coord(&[7, 7, 1, 3, 5, 8]), coord(&[4, 4, 4, 0, 1, 6])],
+            [coord(&[3, 1, 4, 1, 5]), coord(&[9, 2, 6, 5, 3]), coord(&[5, 8, 9, 7, 9])],
+            [coord(&[2, 7, 1, 8]), coord(&[2, 8, 1, 8]), coord(&[4, 5, 9, 0])],
+        ];
+        SensorFeatures::extract(&input, &[4, 6, 5, 4]).witness().unwrap()
+    }
+
+    #[test]
+    fn multi_window_proof() {
+        let params = PublicParams::new(64);
+        // The addition of the first axis of the first vector is 17 + 4 * offset
+        let model = SvmModel {
+            addition_weights: vec![vec![1, 0, 0], vec![0; 3], vec![0; 3], vec![0; 3], vec![0; 3], vec![0; 3], vec![0; 3], vec![0; 3]],
+            std_weights: vec![vec![0; 3]; 4],
+            bias: -20,
+        };
+        let (decision_commitments, proof) = MultiWindowProof::create(&params, &model, &[witness(0), witness(1)]).unwrap();
+        assert_eq!(decision_commitments.len(), 2);
+
+        let parsed = MultiWindowProof::from_bytes_strict(&proof.to_bytes(), &params).unwrap();
+        assert!(parsed == proof);
+        assert_eq!(parsed.serialized_size(), proof.to_bytes().len());
+        assert!(parsed.verify_with_params(&params, &model, &decision_commitments).is_ok());
+
+        let mut tampered = proof.clone();
+        tampered.windows[1].signed_commitments[0][0] = CompressedRistretto([0xff; 32]);
+        let bytes = tampered.to_bytes();
+        assert!(MultiWindowProof::from_bytes(&bytes, &params).is_ok());
+        assert_eq!(MultiWindowProof::from_bytes_strict(&bytes, &params).err(), Some(ProofError::FormatError));
+
+        let swapped = vec![decision_commitments[1], decision_commitments[0]];
+        assert_eq!(
+            proof.clone().verify_with_params(&params, &model, &swapped).err(),
+            Some(ProofError::VerificationError)
+        );
+        let mut reordered = proof;
+        reordered.windows.swap(0, 1);
+        assert!(reordered.verify_with_params(&params, &model, &swapped).is_err());
+    }
+}
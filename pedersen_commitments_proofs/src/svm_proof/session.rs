@@ -0,0 +1,478 @@
+//! The phases of a `zkSVMProver`, one type per phase.
+//!
+//! A `ProverSession` starts with the commitments to the sensor vectors, and each sub-proof
+//! consumes the session of the previous phase, so the sub-proofs cannot be skipped or
+//! reordered:
+//!
+//! `ProverSession<Committed>` → `ProverSession<DiffProven>` → `ProverSession<AveragesProven>`
+//! → `ProverSession<VariancesProven>` → `zkSVMProver`
+//!
+//! A session may also be finished after the averages, for a proof that does not cover the
+//! standard deviations. All the sub-proofs are created under the generators and the transcript
+//! labels the session was committed with, which are those the resulting proof is verified
+//! under, and the proofs of the statistics declare them in their transcripts, see
+//! `StatisticSet`. Each phase records the commitments its sub-proof takes in the
+//! `StatementGraph` of the proof. With `PedersenConfig::with_witness_rng`, each phase keys the
+//! RNG it is given by the vectors, the blinding factors and the features proven so far, and
+//! every prover of the phase draws its randomness from the keyed RNG.
+
+use alloc::vec::Vec;
+use alloc::vec;
+use crate::algebraic_proofs::average_proof::AvgProof;
+use crate::algebraic_proofs::diff_vector_gen_proof::DiffProofs;
+use crate::algebraic_proofs::variance_proof::VarianceProof;
+use crate::boolean_proofs::square_proof::SQUARE_RANGE_BITSIZE;
+use crate::metrics::{count, PhaseMetrics, ProofPhase, ProverMetrics, Stopwatch};
+use crate::svm_proof::decision_proof::FeatureBlindings;
+use crate::utils::commitment_fns::{multiple_commit_with_blindings, multiple_commit_with_rng};
+use crate::utils::misc::{all_sensors_diff_points, bincode_size, compress_all, decompress_all};
+use crate::utils::randomness::{random_scalar_matrix, ProverRng};
+use crate::svm_proof::adhoc_proof::{additions, stds, ProofHeader};
+use crate::{zkSVMProver, PedersenConfig, PublicParams, StatementGraph};
+#[cfg(feature = "std")]
+use crate::TranscriptLabels;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use ip_zk_proof::ProofError;
+use rand_core::CryptoRngCore;
+
+/// A `zkSVMProver` in the making, in phase `S`.
+pub struct ProverSession<S> {
+    // Generators and labels of all the sub-proofs
+    config: PedersenConfig,
+    // The sensor vectors followed by their diff vectors, see `zkSVMProver::new`
+    input_vector: Vec<[Vec<Scalar>; 3]>,
+    // Number of readings of each vector of `input_vector`
+    non_zero_elements: Vec<usize>,
+    // Commitments signed by the TPM, with their blinding factors
+    signed_commitments: Vec<Vec<CompressedRistretto>>,
+    signed_blindings: Vec<Vec<Scalar>>,
+    // Bitsize of the range proofs of the stds
+    range_bitsize: usize,
+    // Wiring of the commitments into the sub-proofs proven so far
+    graph: StatementGraph,
+    state: S,
+}
+
+/// The sensor vectors are committed.
+pub struct Committed;
+
+/// The diff vectors are proven.
+pub struct DiffProven {
+    proof_diff: DiffProofs,
+    diff_blindings: Vec<Vec<Scalar>>,
+    diff_commitments: Vec<Vec<CompressedRistretto>>,
+}
+
+/// The additions of the vectors are proven.
+pub struct AveragesProven {
+    proof_diff: DiffProofs,
+    diff_blindings: Vec<Vec<Scalar>>,
+    diff_commitments: Vec<Vec<CompressedRistretto>>,
+    proof_avg: AvgProof,
+    add_comm_blindings: Vec<Vec<Scalar>>,
+}
+
+/// The variances and the standard deviations of the sensor vectors are proven.
+pub struct VariancesProven {
+    proof_diff: DiffProofs,
+    proof_avg: AvgProof,
+    proof_variance: VarianceProof,
+    feature_blindings: FeatureBlindings,
+}
+
+impl ProverSession<Committed> {
+    /// Commits to the sensor vectors, the first half of `input_vector`, under the generators
+    /// and the labels of `params`, with the blinding factors drawn from `rng`. The arguments
+    /// are those of `zkSVMProver::new_with_rng`, and the size of the parameters must be the
+    /// size of the vectors. The range proofs have the bitsize of the parameters.
+    pub fn commit(
+        params: &PublicParams,
+        rng: &mut dyn CryptoRngCore,
+        metrics: &mut dyn ProverMetrics,
+        input_vector: &[[Vec<Scalar>; 3]],
+        non_zero_elements: &[usize],
+    ) -> Result<ProverSession<Committed>, ProofError> {
+        if params.size() != input_vector[0][0].len() {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        Ok(ProverSession::commit_with_config(params.config(), None, rng, metrics, input_vector, non_zero_elements)?
+            .with_range_bitsize(params.range_bitsize()))
+    }
+
+    /// As `commit`, with the given configuration, and with the blinding factors of the signed
+    /// commitments given by the trusted module if any.
+    pub(crate) fn commit_with_config(
+        config: PedersenConfig,
+        signed_blindings: Option<&Vec<Vec<Scalar>>>,
+        rng: &mut dyn CryptoRngCore,
+        metrics: &mut dyn ProverMetrics,
+        input_vector: &[[Vec<Scalar>; 3]],
+        non_zero_elements: &[usize],
+    ) -> Result<ProverSession<Committed>, ProofError> {
+        // All the sub-proofs declare the sizes of the statement
+        let header = ProofHeader::new(input_vector[0][0].len(), non_zero_elements);
+        header.check()?;
+        let labels = config.labels().with_sizes(&header.sizes());
+        let config = config.with_labels(labels);
+
+        // This is performed by the trusted module, but only the prover can have access to the
+        // blinding factors. We only hash the initial sensors, which are the first half
+        let stopwatch = Stopwatch::start(metrics);
+        let signed_vectors = input_vector[..(input_vector.len() / 2)].to_vec();
+        let witness: Vec<Scalar> = signed_vectors.iter().flatten().flatten().cloned().collect();
+        let mut rng = config.prover_rng(b"commitment witness", &witness, rng);
+        let (signed_commitments, signed_blindings) = {
+            let _span = phase_span!("commitment");
+            match signed_blindings {
+                Some(blindings) => (
+                    multiple_commit_with_blindings(config.G_vec(), &signed_vectors, blindings)
+                        .ok_or(ProofError::WrongNumBlindingFactors)?,
+                    blindings.clone(),
+                ),
+                None => multiple_commit_with_rng(config.G_vec(), &signed_vectors, &mut rng),
+            }
+        };
+        metrics.phase(ProofPhase::Commitment, PhaseMetrics {
+            duration: stopwatch.elapsed(metrics),
+            commitments: count(&signed_commitments),
+            proofs: 0,
+            proof_size: bincode_size(&signed_commitments),
+        });
+
+        Ok(ProverSession {
+            config,
+            input_vector: input_vector.to_vec(),
+            non_zero_elements: non_zero_elements.to_vec(),
+            signed_commitments,
+            signed_blindings,
+            range_bitsize: SQUARE_RANGE_BITSIZE,
+            graph: StatementGraph::new(),
+            state: Committed,
+        })
+    }
+
+    /// Proves the standard deviations with range proofs of `range_bitsize` bits instead, see
+    /// `FloatingSquareZKProof::create_with_rng`.
+    pub fn with_range_bitsize(mut self, range_bitsize: usize) -> ProverSession<Committed> {
+        self.range_bitsize = range_bitsize;
+        self
+    }
+
+    /// Creates the sub-proofs under `labels` instead, e.g. labels bound to a transcript that
+    /// the signed commitments were appended to, see `aggregation`. The sizes of the statement
+    /// are still declared.
+    #[cfg(feature = "std")]
+    pub(crate) fn with_labels(mut self, labels: TranscriptLabels) -> ProverSession<Committed> {
+        let labels = labels.with_sizes(&self.config.labels().sizes);
+        self.config = self.config.with_labels(labels);
+        self
+    }
+
+    /// Proves that the commitments to `diff_vector_scalar`, the diff vectors of the sensor
+    /// vectors, follow from the signed commitments, see `DiffProofs`.
+    pub fn prove_diff(
+        mut self,
+        diff_vector_scalar: &[[Vec<Scalar>; 3]],
+        rng: &mut dyn CryptoRngCore,
+        metrics: &mut dyn ProverMetrics,
+    ) -> ProverSession<DiffProven> {
+        let mut rng = self.rng(b"diff witness", &[], rng);
+        let (proof_diff, diff_blindings) = DiffProofs::create_with_metrics(
+            &self.input_vector[..(self.input_vector.len() / 2)],
+            diff_vector_scalar,
+            &self.signed_blindings,
+            &self.config,
+            &self.non_zero_elements,
+            &mut rng,
+            metrics,
+        );
+
+        let diff_commitments = diff_commitments(&self.signed_commitments, &proof_diff.iter_commitments);
+        self.graph.record_diff(&self.signed_commitments, &proof_diff.iter_commitments, &diff_commitments);
+        self.into_phase(DiffProven { proof_diff, diff_blindings, diff_commitments })
+    }
+}
+
+impl ProverSession<DiffProven> {
+    /// Proves the additions of each axis of each vector, see `AvgProof`.
+    pub fn prove_averages(
+        mut self,
+        rng: &mut dyn CryptoRngCore,
+        metrics: &mut dyn ProverMetrics,
+    ) -> Result<ProverSession<AveragesProven>, ProofError> {
+        let mut rng = self.rng(b"averages witness", &[&self.state.diff_blindings], rng);
+        let add_comm_blindings: Vec<Vec<Scalar>> =
+            random_scalar_matrix(&mut rng, &vec![3; self.input_vector.len()]);
+
+        let labels = self.config.labels().with_statistics(additions(self.non_zero_elements.len()));
+        let mut blind_factors_all_vectors = self.signed_blindings.clone();
+        blind_factors_all_vectors.extend(self.state.diff_blindings.iter().cloned());
+
+        let proof_avg = AvgProof::create_with_metrics(
+            &self.config.get_bp_gens(),
+            self.config.pedersen_gens(),
+            &self.config.acc_bases_G(&self.non_zero_elements),
+            &self.config.acc_bases_H(&self.non_zero_elements),
+            &self.input_vector,
+            &add_comm_blindings,
+            &blind_factors_all_vectors,
+            &labels,
+            &mut rng,
+            metrics,
+        )?;
+
+        self.graph.record_average(&proof_avg);
+        let DiffProven { proof_diff, diff_blindings, diff_commitments } = self.state;
+        Ok(ProverSession {
+            config: self.config,
+            input_vector: self.input_vector,
+            non_zero_elements: self.non_zero_elements,
+            signed_commitments: self.signed_commitments,
+            signed_blindings: self.signed_blindings,
+            range_bitsize: self.range_bitsize,
+            graph: self.graph,
+            state: AveragesProven { proof_diff, diff_blindings, diff_commitments, proof_avg, add_comm_blindings },
+        })
+    }
+}
+
+impl ProverSession<AveragesProven> {
+    /// The proof of the additions, without the standard deviations, verified under the
+    /// parameters of the session.
+    pub fn finish(self) -> zkSVMProver {
+        let fingerprint = self.config.fingerprint();
+        zkSVMProver {
+            size: self.input_vector[0][0].len(),
+            config: self.config,
+            signed_commitments: self.signed_commitments,
+            proof_diff: self.state.proof_diff,
+            proof_avg: self.state.proof_avg,
+            range_bitsize: self.range_bitsize,
+            proof_variance: None,
+            size_sensors: self.non_zero_elements,
+            statement_graph: self.graph,
+            fingerprint,
+        }
+    }
+
+    /// Proves the variances and the standard deviations of each axis of each sensor vector,
+    /// from the `additions` of the evaluated vectors, see `VarianceProof`.
+    pub fn prove_variances(
+        mut self,
+        additions: &Vec<Vec<Scalar>>,
+        variances: &Vec<Vec<Scalar>>,
+        sensor_vectors_stds: &Vec<Vec<Scalar>>,
+        rng: &mut dyn CryptoRngCore,
+        metrics: &mut dyn ProverMetrics,
+    ) -> Result<ProverSession<VariancesProven>, ProofError> {
+        let features = [additions, variances, sensor_vectors_stds, &self.state.diff_blindings, &self.state.add_comm_blindings];
+        let mut rng = self.rng(b"variances witness", &features, rng);
+        let labels = self.config.labels().with_statistics(stds(self.signed_commitments.len()));
+        let (proof_variance, stds_blindings) = VarianceProof::create_with_metrics(
+            &self.input_vector,
+            sensor_vectors_stds,
+            additions,
+            variances,
+            &self.config.get_bp_gens(),
+            self.config.pedersen_gens(),
+            self.config.G_vec(),
+            self.config.H_vec(),
+            &self.signed_blindings,
+            &self.state.diff_blindings,
+            &self.non_zero_elements,
+            self.input_vector[0][0].len(),
+            &labels,
+            self.range_bitsize,
+            &mut rng,
+            metrics,
+        )?;
+
+        self.graph.record_variance(
+            &self.signed_commitments,
+            &self.state.diff_commitments,
+            &compress_all(&self.state.proof_diff.last_exp()),
+            &self.state.proof_avg,
+            &proof_variance,
+        );
+        let AveragesProven { proof_diff, proof_avg, add_comm_blindings, .. } = self.state;
+        Ok(ProverSession {
+            config: self.config,
+            input_vector: self.input_vector,
+            non_zero_elements: self.non_zero_elements,
+            signed_commitments: self.signed_commitments,
+            signed_blindings: self.signed_blindings,
+            range_bitsize: self.range_bitsize,
+            graph: self.graph,
+            state: VariancesProven {
+                proof_diff,
+                proof_avg,
+                proof_variance,
+                feature_blindings: FeatureBlindings { additions: add_comm_blindings, stds: stds_blindings },
+            },
+        })
+    }
+}
+
+impl ProverSession<VariancesProven> {
+    /// The proof, verified under the parameters of the session.
+    pub fn finish(self) -> zkSVMProver {
+        self.finish_with_feature_blindings().0
+    }
+
+    /// As `finish`, returning as well the blinding factors of the commitments to the features.
+    pub(crate) fn finish_with_feature_blindings(self) -> (zkSVMProver, FeatureBlindings) {
+        let fingerprint = self.config.fingerprint();
+        let prover = zkSVMProver {
+            size: self.input_vector[0][0].len(),
+            config: self.config,
+            signed_commitments: self.signed_commitments,
+            proof_diff: self.state.proof_diff,
+            proof_avg: self.state.proof_avg,
+            range_bitsize: self.range_bitsize,
+            proof_variance: Some(self.state.proof_variance),
+            size_sensors: self.non_zero_elements,
+            statement_graph: self.graph,
+            fingerprint,
+        };
+        (prover, self.state.feature_blindings)
+    }
+}
+
+impl<S> ProverSession<S> {
+    /// The commitments to the sensor vectors signed by the trusted module.
+    pub fn signed_commitments(&self) -> &Vec<Vec<CompressedRistretto>> {
+        &self.signed_commitments
+    }
+
+    /// The RNG of the provers of the phase labelled `label`, keyed by the vectors and the signed
+    /// blinding factors of the session, and by `features`, see `PedersenConfig::prover_rng`.
+    fn rng<'r>(&self, label: &'static [u8], features: &[&Vec<Vec<Scalar>>], rng: &'r mut dyn CryptoRngCore) -> ProverRng<'r> {
+        let witness: Vec<Scalar> = self.input_vector.iter().flatten().flatten()
+            .chain(self.signed_blindings.iter().flatten())
+            .chain(features.iter().flat_map(|matrix| matrix.iter().flatten()))
+            .cloned()
+            .collect();
+        self.config.prover_rng(label, &witness, rng)
+    }
+
+    fn into_phase<T>(self, state: T) -> ProverSession<T> {
+        ProverSession {
+            config: self.config,
+            input_vector: self.input_vector,
+            non_zero_elements: self.non_zero_elements,
+            signed_commitments: self.signed_commitments,
+            signed_blindings: self.signed_blindings,
+            range_bitsize: self.range_bitsize,
+            graph: self.graph,
+            state,
+        }
+    }
+}
+
+/// The diff commitments, derived from the signed commitments and the iterated ones of the
+/// `DiffProofs`, as the verifier does.
+fn diff_commitments(
+    signed_commitments: &[Vec<CompressedRistretto>],
+    iter_commitments: &[Vec<CompressedRistretto>],
+) -> Vec<Vec<CompressedRistretto>> {
+    let decompress = |commitments| decompress_all(commitments)
+        .expect("Should never happen, as the commitments were computed by the prover.");
+    compress_all(&all_sensors_diff_points(&decompress(signed_commitments), &decompress(iter_commitments)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::SensorFeatures;
+    use crate::metrics::NoMetrics;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn witness() -> crate::FeatureWitness {
+        let coord = |values: &[i64]| -> Vec<i128> {
+            let mut vector: Vec<i128> = values.iter().map(|&x| x as i128).collect();
+            vector.resize(64, 0);
+            vector
+        };
+        let input = vec![
+            [coord(&[5, 3, 8, 1]), coord(&[2, 2, 2, 2]), coord(&[9, 0, 4, 7])],
+            [coord(&[1, 6, 3, 9, 4, 2]), coord(&[7, 7, 1, 3, 5, 8]), coord(&[4, 4, 4, 0, 1, 6])],
+            [coord(&[3, 1, 4, 1, 5]), coord(&[9, 2, 6, 5, 3]), coord(&[5, 8, 9, 7, 9])],
+            [coord(&[2, 7, 1, 8]), coord(&[2, 8, 1, 8]), coord(&[4, 5, 9, 0])],
+        ];
+        SensorFeatures::extract(&input, &[4, 6, 5, 4]).witness().unwrap()
+    }
+
+    #[test]
+    fn session_matches_the_prover() {
+        let params = PublicParams::new(64);
+        let witness = witness();
+        let (rng, metrics) = (&mut StdRng::seed_from_u64(1), &mut NoMetrics);
+
+        let session = ProverSession::commit(&params, rng, metrics, &witness.input_vector, &witness.non_zero_elements)
+            .unwrap();
+        let signed_commitments = session.signed_commitments().clone();
+        let proof = session
+            .prove_diff(&witness.diff_vectors, rng, metrics)
+            .prove_averages(rng, metrics)
+            .unwrap()
+            .prove_variances(&witness.additions, &witness.variances, &witness.stds, rng, metrics)
+            .unwrap()
+            .finish();
+
+        let expected = zkSVMProver::new_with_rng(
+            &params,
+            &mut StdRng::seed_from_u64(1),
+            &mut NoMetrics,
+            &witness.input_vector,
+            &witness.non_zero_elements,
+            &witness.diff_vectors,
+            &witness.additions,
+            &witness.variances,
+            &witness.stds,
+        ).unwrap();
+        assert_eq!(proof.signed_commitments(), &signed_commitments);
+        assert!(proof == expected);
+    }
+
+    #[test]
+    fn session_records_the_wiring() {
+        use crate::statement_graph::{CommitmentSet, SubProof};
+
+        let params = PublicParams::new(64);
+        let witness = witness();
+        let (rng, metrics) = (&mut StdRng::seed_from_u64(2), &mut NoMetrics);
+        let mut proof = ProverSession::commit(&params, rng, metrics, &witness.input_vector, &witness.non_zero_elements)
+            .unwrap()
+            .prove_diff(&witness.diff_vectors, rng, metrics)
+            .prove_averages(rng, metrics)
+            .unwrap()
+            .prove_variances(&witness.additions, &witness.variances, &witness.stds, rng, metrics)
+            .unwrap()
+            .finish();
+
+        let graph = proof.statement_graph();
+        assert!(graph.feeds(CommitmentSet::Diff, SubProof::Variance));
+        assert!(graph.feeds(CommitmentSet::Variances, SubProof::Std));
+        assert!(!graph.feeds(CommitmentSet::Stds, SubProof::Variance));
+        assert!(proof.clone().verify_with_params(&params).is_ok());
+
+        proof.statement_graph = StatementGraph::new();
+        assert_eq!(proof.verify_with_params(&params).err(), Some(ProofError::VerificationError));
+    }
+
+    #[test]
+    fn session_checks_the_size() {
+        let witness = witness();
+        let session = ProverSession::commit(
+            &PublicParams::new(32),
+            &mut StdRng::seed_from_u64(1),
+            &mut NoMetrics,
+            &witness.input_vector,
+            &witness.non_zero_elements,
+        );
+        assert!(matches!(session, Err(ProofError::InvalidGeneratorsLength)));
+    }
+}
@@ -0,0 +1,175 @@
+//! Generators of valid statements and witnesses, for the tests of the crates built on top of
+//! the zkSVM proofs. Enabled with the `test-utils` feature.
+//!
+//! A `Fixture` holds four sensor windows with readings as in the dataset, their features, and
+//! the public parameters and signed commitments of a proof over them, all derived from a seed.
+//! The `strategies` module wraps them as `proptest` strategies.
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use ip_zk_proof::ProofError;
+use rand::Rng;
+use rand_chacha::ChaChaRng;
+use rand_core::SeedableRng;
+
+use crate::utils::commitment_fns::hash_sensor_data_with_rng;
+use crate::{FeatureWitness, PublicParams, SensorFeatures, zkSVMProver};
+
+/// Number of sensor windows of a zkSVM proof.
+pub const NR_SENSORS: usize = 4;
+
+/// Readings are drawn within `READING_CENTER` +- `READING_SPREAD`, as in a window of the
+/// dataset. Much wider spreads overflow the range proofs of the standard deviations of the
+/// largest windows.
+pub const READING_CENTER: u64 = 100_000_000;
+
+/// See `READING_CENTER`.
+pub const READING_SPREAD: u64 = 500_000;
+
+/// Draws `sizes.len()` windows of `size` elements, where window `i` has `sizes[i]` readings
+/// followed by zeros.
+pub fn sensor_windows<R: Rng>(rng: &mut R, size: usize, sizes: &[usize]) -> Vec<[Vec<i128>; 3]> {
+    sizes.iter()
+        .map(|&readings| {
+            let mut axis = || -> Vec<i128> {
+                (0..size)
+                    .map(|i| if i < readings {
+                        rng.gen_range(READING_CENTER - READING_SPREAD..=READING_CENTER + READING_SPREAD) as i128
+                    } else {
+                        0
+                    })
+                    .collect()
+            };
+            [axis(), axis(), axis()]
+        })
+        .collect()
+}
+
+/// A valid statement and witness of a zkSVM proof over windows of `size` elements.
+///
+/// The `Debug` output only shows the sizes, as `SensorFeatures`.
+#[derive(Clone)]
+pub struct Fixture {
+    pub params: PublicParams,
+    /// The sensor windows, zero padded to the size of the parameters
+    pub windows: Vec<[Vec<i128>; 3]>,
+    /// Number of readings of each window
+    pub sizes: Vec<usize>,
+    /// The features of the windows, as taken by the prover
+    pub witness: FeatureWitness,
+    /// Commitments of the trusted module to the windows
+    pub signed_commitments: Vec<Vec<CompressedRistretto>>,
+    /// Blinding factors of `signed_commitments`
+    pub signed_blindings: Vec<Vec<Scalar>>,
+}
+
+impl std::fmt::Debug for Fixture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Fixture")
+            .field("size", &self.params.size())
+            .field("sizes", &self.sizes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Fixture {
+    /// Fixture of four windows of `size` elements with a random number of readings each,
+    /// derived from `seed`. `size` must be at least 64, for the generators of the range
+    /// proofs.
+    pub fn new(size: usize, seed: u64) -> Fixture {
+        let mut rng = ChaChaRng::seed_from_u64(seed);
+        let sizes: Vec<usize> = (0..NR_SENSORS).map(|_| rng.gen_range(2..=size)).collect();
+        Fixture::from_rng(&mut rng, PublicParams::new(size), &sizes)
+    }
+
+    /// As `new`, with `sizes[i]` readings in window `i`, each between 2 and `size`.
+    pub fn with_sizes(size: usize, sizes: &[usize], seed: u64) -> Fixture {
+        Fixture::from_rng(&mut ChaChaRng::seed_from_u64(seed), PublicParams::new(size), sizes)
+    }
+
+    fn from_rng(rng: &mut ChaChaRng, params: PublicParams, sizes: &[usize]) -> Fixture {
+        let windows = sensor_windows(rng, params.size(), sizes);
+        let witness = SensorFeatures::extract(&windows, sizes)
+            .witness()
+            .expect("The readings are far below the scalar field.");
+        let (signed_commitments, signed_blindings) = witness.input_vector[..NR_SENSORS].iter()
+            .map(|window| hash_sensor_data_with_rng(params.G_vec(), window, rng))
+            .unzip();
+        Fixture {
+            params,
+            windows,
+            sizes: sizes.to_vec(),
+            witness,
+            signed_commitments,
+            signed_blindings,
+        }
+    }
+
+    /// Proves the statement, with the signed commitments of the fixture.
+    pub fn prove(&self) -> Result<zkSVMProver, ProofError> {
+        zkSVMProver::new_with_signed_blindings(
+            &self.params,
+            &self.signed_blindings,
+            &self.witness.input_vector,
+            &self.witness.non_zero_elements,
+            &self.witness.diff_vectors,
+            &self.witness.additions,
+            &self.witness.variances,
+            &self.witness.stds,
+        )
+    }
+}
+
+/// `proptest` strategies over the generators of this module.
+pub mod strategies {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Any scalar, reduced from 32 random bytes.
+    pub fn scalar() -> impl Strategy<Value = Scalar> {
+        any::<[u8; 32]>().prop_map(Scalar::from_bytes_mod_order)
+    }
+
+    /// Numbers of readings of the four windows of `size` elements.
+    pub fn window_sizes(size: usize) -> impl Strategy<Value = Vec<usize>> {
+        proptest::collection::vec(2..=size, NR_SENSORS)
+    }
+
+    /// Four windows of `size` elements and their numbers of readings, as taken by
+    /// `SensorFeatures::extract`.
+    pub fn sensor_windows(size: usize) -> impl Strategy<Value = (Vec<[Vec<i128>; 3]>, Vec<usize>)> {
+        (window_sizes(size), any::<u64>()).prop_map(move |(sizes, seed)| {
+            (super::sensor_windows(&mut ChaChaRng::seed_from_u64(seed), size, &sizes), sizes)
+        })
+    }
+
+    /// Fixtures over windows of `size` elements, see `Fixture::with_sizes`.
+    pub fn fixture(size: usize) -> impl Strategy<Value = Fixture> {
+        (window_sizes(size), any::<u64>()).prop_map(move |(sizes, seed)| Fixture::with_sizes(size, &sizes, seed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn fixtures_are_deterministic() {
+        let fixture = Fixture::new(64, 7);
+        assert_eq!(Fixture::new(64, 7).signed_commitments, fixture.signed_commitments);
+        assert_ne!(Fixture::new(64, 8).signed_commitments, fixture.signed_commitments);
+        assert_eq!(format!("{:?}", fixture), format!("Fixture {{ size: 64, sizes: {:?}, .. }}", fixture.sizes));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(4))]
+
+        #[test]
+        fn fixtures_prove(fixture in strategies::fixture(64)) {
+            let proof = fixture.prove().unwrap();
+            prop_assert_eq!(proof.signed_commitments(), &fixture.signed_commitments);
+            prop_assert!(proof.verify_with_params(&fixture.params).is_ok());
+        }
+    }
+}
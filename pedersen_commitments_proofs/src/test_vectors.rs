@@ -0,0 +1,166 @@
+//! Test vectors of the zkSVM proof, for verifiers implemented in other languages. Enabled with
+//! the `test-vectors` feature, and written to files by the `test_vectors` binary.
+//!
+//! A `TestVector` holds the public parameters, the sensor windows and their features, the
+//! signed commitments and a serialized proof of a `Fixture`. Integers are written as decimal
+//! strings, as the variances do not fit in 64 bits, and scalars, points and encodings as hex
+//! strings, with scalars and points in their 32-byte little endian and compressed encodings.
+//!
+//! Everything but the proof is derived from the seed, so regenerating a vector gives the same
+//! statement and witness. The blinding factors of the proof are fresh, so the proof bytes
+//! change across runs, but any of them must verify.
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use ip_zk_proof::ProofError;
+use serde::{Deserialize, Serialize};
+
+use crate::test_utils::Fixture;
+use crate::{PublicParams, SensorFeatures, zkSVMProver};
+
+/// A zkSVM statement, its witness and a proof of it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestVector {
+    /// Seed of the fixture
+    pub seed: u64,
+    /// Size of the vectors and of the parameters
+    pub size: usize,
+    /// Encoding of the parameters, see `PublicParams::to_bytes`
+    pub params: String,
+    /// Digest of the parameters, see `PublicParams::digest`
+    pub params_digest: String,
+    /// The sensor windows, zero padded to `size`
+    pub windows: Vec<[Vec<String>; 3]>,
+    /// The features of the windows, as extracted by `SensorFeatures::extract`
+    pub features: Features,
+    /// Commitments of the trusted module to the windows
+    pub signed_commitments: Vec<Vec<String>>,
+    /// Blinding factors of `signed_commitments`
+    pub signed_blindings: Vec<Vec<String>>,
+    /// Encoding of the proof, see `zkSVMProver::to_bytes`
+    pub proof: String,
+}
+
+/// The features of `SensorFeatures`, as decimal strings.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Features {
+    pub evaluated_sizes: Vec<usize>,
+    pub diff_vectors: Vec<[Vec<String>; 3]>,
+    pub additions: Vec<Vec<String>>,
+    pub variances: Vec<Vec<String>>,
+    pub stds: Vec<Vec<String>>,
+}
+
+impl TestVector {
+    /// Generates the test vector of `Fixture::new(size, seed)`.
+    pub fn generate(size: usize, seed: u64) -> Result<TestVector, ProofError> {
+        let fixture = Fixture::new(size, seed);
+        let proof = fixture.prove()?;
+        let features = SensorFeatures::extract(&fixture.windows, &fixture.sizes);
+
+        Ok(TestVector {
+            seed,
+            size,
+            params: hex::encode(fixture.params.to_bytes()),
+            params_digest: hex::encode(fixture.params.digest()),
+            windows: fixture.windows.iter().map(coords_to_strings).collect(),
+            features: Features {
+                evaluated_sizes: features.evaluated_sizes,
+                diff_vectors: features.diff_vectors.iter().map(coords_to_strings).collect(),
+                additions: features.additions.iter().map(|x| to_strings(x)).collect(),
+                variances: features.variances.iter().map(|x| to_strings(x)).collect(),
+                stds: features.stds.iter().map(|x| to_strings(x)).collect(),
+            },
+            signed_commitments: fixture.signed_commitments.iter()
+                .map(|c| c.iter().map(|p| hex::encode(p.as_bytes())).collect())
+                .collect(),
+            signed_blindings: fixture.signed_blindings.iter()
+                .map(|b| b.iter().map(|s| hex::encode(s.as_bytes())).collect())
+                .collect(),
+            proof: hex::encode(proof.to_bytes()),
+        })
+    }
+
+    /// Serializes the vector as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Should never happen, as all the fields are strings and integers.")
+    }
+
+    /// Parses a vector serialized with `to_json`.
+    pub fn from_json(json: &str) -> Result<TestVector, ProofError> {
+        serde_json::from_str(json).map_err(|_| ProofError::FormatError)
+    }
+
+    /// Checks the vector against this implementation: the commitments open to the windows
+    /// with the given blindings, and the proof verifies under the parameters, for the signed
+    /// commitments of the vector.
+    pub fn check(&self) -> Result<(), ProofError> {
+        let params = PublicParams::from_bytes(&from_hex(&self.params)?)?;
+        if hex::encode(params.digest()) != self.params_digest {
+            return Err(ProofError::FormatError);
+        }
+
+        let fixture = Fixture::new(self.size, self.seed);
+        let signed_commitments: Vec<Vec<CompressedRistretto>> = self.signed_commitments.iter()
+            .map(|c| c.iter().map(|p| Ok(CompressedRistretto(read32(p)?))).collect())
+            .collect::<Result<_, ProofError>>()?;
+        let signed_blindings: Vec<Vec<Scalar>> = self.signed_blindings.iter()
+            .map(|b| b.iter().map(|s| Option::from(Scalar::from_canonical_bytes(read32(s)?)).ok_or(ProofError::FormatError)).collect())
+            .collect::<Result<_, ProofError>>()?;
+        if signed_commitments != fixture.signed_commitments || signed_blindings != fixture.signed_blindings {
+            return Err(ProofError::VerificationError);
+        }
+
+        let proof = zkSVMProver::from_bytes(&from_hex(&self.proof)?, &params)?;
+        if proof.signed_commitments() != &signed_commitments {
+            return Err(ProofError::VerificationError);
+        }
+        proof.verify_with_params(&params)
+    }
+}
+
+fn to_strings(values: &[i128]) -> Vec<String> {
+    values.iter().map(|x| x.to_string()).collect()
+}
+
+fn coords_to_strings(coords: &[Vec<i128>; 3]) -> [Vec<String>; 3] {
+    [to_strings(&coords[0]), to_strings(&coords[1]), to_strings(&coords[2])]
+}
+
+fn from_hex(string: &str) -> Result<Vec<u8>, ProofError> {
+    hex::decode(string).map_err(|_| ProofError::FormatError)
+}
+
+fn read32(string: &str) -> Result<[u8; 32], ProofError> {
+    let mut bytes = [0u8; 32];
+    hex::decode_to_slice(string, &mut bytes).map_err(|_| ProofError::FormatError)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vectors_roundtrip_and_check() {
+        let vector = TestVector::generate(64, 3).unwrap();
+        let parsed = TestVector::from_json(&vector.to_json()).unwrap();
+        assert_eq!(parsed, vector);
+        assert!(parsed.check().is_ok());
+
+        // The statement is derived from the seed, the proof is not
+        let other = TestVector::generate(64, 3).unwrap();
+        assert_eq!(other.windows, vector.windows);
+        assert_eq!(other.signed_commitments, vector.signed_commitments);
+        assert_ne!(other.proof, vector.proof);
+
+        let mut reproved = vector.clone();
+        reproved.proof = other.proof;
+        assert!(reproved.check().is_ok());
+
+        let mut tampered = vector.clone();
+        tampered.signed_blindings[0][0] = hex::encode(Scalar::ONE.as_bytes());
+        assert_eq!(tampered.check(), Err(ProofError::VerificationError));
+        assert_eq!(TestVector::from_json("{}"), Err(ProofError::FormatError));
+    }
+}
@@ -1,71 +0,0 @@
-//! Defines a `TranscriptProtocol` trait for using a Merlin transcript.
-
-use curve25519_dalek::ristretto::CompressedRistretto;
-use curve25519_dalek::scalar::Scalar;
-use merlin::Transcript;
-
-pub (crate) trait TranscriptProtocol {
-    /// Append a domain separator for an `n`-bit, `m`-party range proof.
-    fn rangeproof_domain_sep(&mut self, n: u64, m: u64);
-
-    /// Append a domain separator for a length-`n` inner product proof.
-    fn innerproduct_domain_sep(&mut self, n: u64);
-
-    /// Append a domain separator for a constraint system.
-    fn r1cs_domain_sep(&mut self);
-
-    /// Commit a domain separator for a CS without randomized constraints.
-    fn r1cs_1phase_domain_sep(&mut self);
-
-    /// Commit a domain separator for a CS with randomized constraints.
-    fn r1cs_2phase_domain_sep(&mut self);
-
-    /// Append a `scalar` with the given `label`.
-    fn append_scalar(&mut self, label: &'static [u8], scalar: &Scalar);
-
-    /// Append a `point` with the given `label`.
-    fn append_point(&mut self, label: &'static [u8], point: &CompressedRistretto);
-
-    /// Compute a `label`ed challenge variable.
-    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar;
-}
-
-impl TranscriptProtocol for Transcript {
-    fn rangeproof_domain_sep(&mut self, n: u64, m: u64) {
-        self.append_message(b"dom-sep", b"rangeproof v1");
-        self.append_u64(b"n", n);
-        self.append_u64(b"m", m);
-    }
-
-    fn innerproduct_domain_sep(&mut self, n: u64) {
-        self.append_message(b"dom-sep", b"ipp v1");
-        self.append_u64(b"n", n);
-    }
-
-    fn r1cs_domain_sep(&mut self) {
-        self.append_message(b"dom-sep", b"r1cs v1");
-    }
-
-    fn r1cs_1phase_domain_sep(&mut self) {
-        self.append_message(b"dom-sep", b"r1cs-1phase");
-    }
-
-    fn r1cs_2phase_domain_sep(&mut self) {
-        self.append_message(b"dom-sep", b"r1cs-2phase");
-    }
-
-    fn append_scalar(&mut self, label: &'static [u8], scalar: &Scalar) {
-        self.append_message(label, scalar.as_bytes());
-    }
-
-    fn append_point(&mut self, label: &'static [u8], point: &CompressedRistretto) {
-        self.append_message(label, point.as_bytes());
-    }
-
-    fn challenge_scalar(&mut self, label: &'static [u8]) -> Scalar {
-        let mut buf = [0u8; 64];
-        self.challenge_bytes(label, &mut buf);
-
-        Scalar::from_bytes_mod_order_wide(&buf)
-    }
-}
@@ -1,52 +1,223 @@
+use alloc::vec::Vec;
+use alloc::vec;
+#[cfg(feature = "std")]
 use rand::thread_rng;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::utils::misc::map_sensor_coords;
+use crate::utils::randomness::{random_scalars, random_scalar_matrix};
 
 use crate::PedersenVecGens;
 use curve25519_dalek::scalar::Scalar;
-use curve25519_dalek::ristretto::{CompressedRistretto, };
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 
-pub fn multiple_commit_iter_gens(
-    ped_vec_generators: &Vec<PedersenVecGens>,
-    vectors: &Vec<[Vec<Scalar>; 3]>,
+/// Commits to each sensor vector under its own generators, with blinding factors drawn from
+/// `rng`.
+pub fn multiple_commit_iter_gens<R: RngCore + CryptoRng>(
+    ped_vec_generators: &[&PedersenVecGens],
+    vectors: &[[Vec<Scalar>; 3]],
+    rng: &mut R,
 ) -> (Vec<Vec<CompressedRistretto>>, Vec<Vec<Scalar>>) {
     let mut commits = Vec::new();
-    let mut blindings = Vec::new();
+    let blindings = random_scalar_matrix(rng, &[3; 4]);
     for i in 0..4 {
-        let commitments = hash_sensor_data(
-            &ped_vec_generators[i],
-            &vectors[i]
-        );
-        commits.push(commitments.0);
-        blindings.push(commitments.1);
+        commits.push(commit_sensor_data(
+            ped_vec_generators[i],
+            &vectors[i],
+            &blindings[i]
+        ));
     }
     (commits, blindings)
 }
 
+/// Commits to each axis of each sensor vector with independent blinding factors, see
+/// `commit_all`.
+#[cfg(feature = "std")]
 pub fn multiple_commit(
     ped_vec_generators: &PedersenVecGens,
-    sensor_vectors: &Vec<[Vec<Scalar>; 3]>,
+    sensor_vectors: &[[Vec<Scalar>; 3]],
 ) -> (Vec<Vec<CompressedRistretto>>, Vec<Vec<Scalar>>) {
-    let mut commits = Vec::new();
-    let mut blindings = Vec::new();
-    for i in 0..sensor_vectors.len() {
-        let commitments = hash_sensor_data(
-            &ped_vec_generators,
-            &sensor_vectors[i]
-        );
-        commits.push(commitments.0);
-        blindings.push(commitments.1);
+    multiple_commit_with_rng(ped_vec_generators, sensor_vectors, &mut thread_rng())
+}
+
+/// As `multiple_commit`, with the blinding factors drawn from `rng`.
+pub fn multiple_commit_with_rng<R: RngCore + CryptoRng>(
+    ped_vec_generators: &PedersenVecGens,
+    sensor_vectors: &[[Vec<Scalar>; 3]],
+    rng: &mut R,
+) -> (Vec<Vec<CompressedRistretto>>, Vec<Vec<Scalar>>) {
+    let blindings = random_scalar_matrix(rng, &vec![3; sensor_vectors.len()]);
+    (commit_all(ped_vec_generators, sensor_vectors, &blindings), blindings)
+}
+
+/// As `multiple_commit`, with the given blinding factors, one per axis of each sensor vector.
+/// Returns `None` if `blindings` does not have three factors per sensor vector.
+pub fn multiple_commit_with_blindings(
+    ped_vec_generators: &PedersenVecGens,
+    sensor_vectors: &[[Vec<Scalar>; 3]],
+    blindings: &[Vec<Scalar>],
+) -> Option<Vec<Vec<CompressedRistretto>>> {
+    if blindings.len() != sensor_vectors.len() || blindings.iter().any(|b| b.len() != 3) {
+        return None;
     }
-    (commits, blindings)
+    Some(commit_all(ped_vec_generators, sensor_vectors, blindings))
+}
+
+/// Lazily commits to each sensor vector of `sensor_vectors`, with three blinding factors per
+/// vector drawn from `rng`, as `hash_sensor_data_with_rng`. Only the commitments of the vector
+/// being consumed are held in memory, so that long traces can be committed (e.g. signed and
+/// sent) one window at a time.
+pub fn commit_stream<'a, I, R>(
+    ped_vec_generators: &'a PedersenVecGens,
+    sensor_vectors: I,
+    mut rng: R,
+) -> impl Iterator<Item = (Vec<CompressedRistretto>, Vec<Scalar>)> + 'a
+where
+    I: IntoIterator<Item = [Vec<Scalar>; 3]>,
+    I::IntoIter: 'a,
+    R: RngCore + CryptoRng + 'a,
+{
+    sensor_vectors.into_iter()
+        .map(move |sensor_vector| hash_sensor_data_with_rng(ped_vec_generators, &sensor_vector, &mut rng))
+}
+
+/// Commits to every axis of every sensor vector, with the blindings checked to have three
+/// factors per vector. With the `rayon` feature the multiscalar multiplications of the axes
+/// run in parallel, see `map_sensor_coords`.
+///
+/// The compression of a point costs an inversion, so all the points are compressed in a single
+/// batch with `double_and_compress_batch`, which compresses the doubles of the points and
+/// shares the inversion. The commitments are therefore computed over the halves of the values
+/// and blindings.
+fn commit_all(
+    ped_vec_generators: &PedersenVecGens,
+    sensor_vectors: &[[Vec<Scalar>; 3]],
+    blindings: &[Vec<Scalar>],
+) -> Vec<Vec<CompressedRistretto>> {
+    let half = Scalar::from(2u64).invert();
+    let halves: Vec<Vec<RistrettoPoint>> = map_sensor_coords(&vec![3; sensor_vectors.len()], |i, j| {
+        ped_vec_generators.commit(
+            &sensor_vectors[i][j].iter().map(|x| half * x).collect::<Vec<Scalar>>(),
+            half * blindings[i][j],
+        )
+    });
+
+    RistrettoPoint::double_and_compress_batch(halves.iter().flatten())
+        .chunks(3)
+        .map(|commitments| commitments.to_vec())
+        .collect()
 }
 
 /// Hash sensor data. Return a vector of the points and scalars used for blinding
+#[cfg(feature = "std")]
 pub fn hash_sensor_data(
     ped_vec_generators: &PedersenVecGens,
     sensor_vector: &[Vec<Scalar>; 3],
 ) -> (Vec<CompressedRistretto>, Vec<Scalar>) {
+    hash_sensor_data_with_rng(ped_vec_generators, sensor_vector, &mut thread_rng())
+}
+
+/// As `hash_sensor_data`, with independent blinding factors for the three axes drawn from
+/// `rng`, e.g. to derive them from a seed held by the trusted module.
+pub fn hash_sensor_data_with_rng<R: RngCore + CryptoRng>(
+    ped_vec_generators: &PedersenVecGens,
+    sensor_vector: &[Vec<Scalar>; 3],
+    rng: &mut R,
+) -> (Vec<CompressedRistretto>, Vec<Scalar>) {
+    let blinding_factor: Vec<Scalar> = random_scalars(rng, 3);
+    (commit_sensor_data(ped_vec_generators, sensor_vector, &blinding_factor), blinding_factor)
+}
+
+/// As `hash_sensor_data`, with the given blinding factors of the three axes, for callers that
+/// manage the blindings themselves.
+pub fn hash_sensor_data_with_blindings(
+    ped_vec_generators: &PedersenVecGens,
+    sensor_vector: &[Vec<Scalar>; 3],
+    blinding_factor: &[Scalar; 3],
+) -> Vec<CompressedRistretto> {
+    commit_sensor_data(ped_vec_generators, sensor_vector, blinding_factor)
+}
 
-    let blinding_factor: Vec<Scalar> = vec![Scalar::random(&mut thread_rng()); 3];
-    ((0..3).map(|index| ped_vec_generators.commit(
+fn commit_sensor_data(
+    ped_vec_generators: &PedersenVecGens,
+    sensor_vector: &[Vec<Scalar>; 3],
+    blinding_factor: &[Scalar],
+) -> Vec<CompressedRistretto> {
+    (0..3).map(|index| ped_vec_generators.commit(
         &sensor_vector[index],
         blinding_factor[index]
-    ).compress()).collect(), blinding_factor)
-}
\ No newline at end of file
+    ).compress()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_axis_gets_its_own_blinding() {
+        let gens = PedersenVecGens::new(4);
+        let vectors: [Vec<Scalar>; 3] = [
+            vec![Scalar::ONE; 4],
+            vec![Scalar::ONE; 4],
+            vec![Scalar::ONE; 4],
+        ];
+        let (commitments, blindings) = hash_sensor_data(&gens, &vectors);
+        assert_ne!(blindings[0], blindings[1]);
+        assert_ne!(commitments[0], commitments[1]);
+        assert_eq!(commitments[2], gens.commit(&vectors[2], blindings[2]).compress());
+    }
+
+    #[test]
+    fn supplied_and_derived_blindings() {
+        use rand_chacha::ChaChaRng;
+        use rand_core::SeedableRng;
+
+        let gens = PedersenVecGens::new(4);
+        let vectors: [Vec<Scalar>; 3] = [
+            vec![Scalar::ONE; 4],
+            vec![Scalar::ZERO; 4],
+            vec![Scalar::from(3u64); 4],
+        ];
+
+        let (commitments, blindings) = hash_sensor_data_with_rng(&gens, &vectors, &mut ChaChaRng::from_seed([3u8; 32]));
+        let (again, _) = hash_sensor_data_with_rng(&gens, &vectors, &mut ChaChaRng::from_seed([3u8; 32]));
+        assert_eq!(commitments, again);
+        assert_ne!(blindings[0], blindings[1]);
+
+        let supplied = [blindings[0], blindings[1], blindings[2]];
+        assert_eq!(hash_sensor_data_with_blindings(&gens, &vectors, &supplied), commitments);
+        assert_eq!(
+            multiple_commit_with_blindings(&gens, core::slice::from_ref(&vectors), core::slice::from_ref(&blindings)),
+            Some(vec![commitments])
+        );
+        assert!(multiple_commit_with_blindings(&gens, &[vectors], &[blindings[..2].to_vec()]).is_none());
+    }
+
+    #[test]
+    fn batched_and_streamed_commitments() {
+        use rand_chacha::ChaChaRng;
+        use rand_core::SeedableRng;
+
+        let gens = PedersenVecGens::new(4);
+        let vectors: Vec<[Vec<Scalar>; 3]> = (0..5u64)
+            .map(|i| [vec![Scalar::from(i); 4], vec![Scalar::from(i + 1); 4], vec![-Scalar::from(i); 4]])
+            .collect();
+
+        let (commitments, blindings) = multiple_commit(&gens, &vectors);
+        for i in 0..vectors.len() {
+            for j in 0..3 {
+                assert_eq!(commitments[i][j], gens.commit(&vectors[i][j], blindings[i][j]).compress());
+            }
+        }
+        assert_eq!(multiple_commit(&gens, &Vec::new()).0, Vec::<Vec<CompressedRistretto>>::new());
+
+        let streamed: Vec<(Vec<CompressedRistretto>, Vec<Scalar>)> =
+            commit_stream(&gens, vectors.clone(), ChaChaRng::from_seed([5u8; 32])).collect();
+        let mut rng = ChaChaRng::from_seed([5u8; 32]);
+        for (vector, (commitments, blindings)) in vectors.iter().zip(streamed.iter()) {
+            assert_eq!(*blindings, random_scalars(&mut rng, 3));
+            let blindings = [blindings[0], blindings[1], blindings[2]];
+            assert_eq!(*commitments, hash_sensor_data_with_blindings(&gens, vector, &blindings));
+        }
+    }
+}
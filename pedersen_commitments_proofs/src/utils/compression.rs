@@ -0,0 +1,147 @@
+use alloc::vec::Vec;
+use alloc::vec;
+use ip_zk_proof::ProofError;
+
+use alloc::collections::BTreeMap;
+use core::convert::TryInto;
+
+/// Size of the encodings that are deduplicated, i.e. of the compressed points.
+const POINT_SIZE: usize = 32;
+
+/// Version of the format written by `compress`.
+const COMPRESSION_VERSION: u8 = 1;
+
+const TAG_LITERAL: u8 = 0;
+const TAG_REFERENCE: u8 = 1;
+
+/// Compresses a serialized proof by storing each repeated point only once.
+///
+/// Sub-proofs about the same commitments may each serialize them. Here every 32 bytes that
+/// already appeared earlier in `bytes` are replaced by a reference to their first occurrence,
+/// of 5 bytes, so that the shared points are stored once. Data without repetitions only grows
+/// by the 6 bytes of the version and of a literal run. The output is the version byte, followed by a sequence of literal
+/// runs (a zero tag, the length as 4 bytes little endian and the bytes) and references (a one
+/// tag and the position in the decompressed proof as 4 bytes little endian).
+///
+/// Scalars are uniformly random, so only repeated points are deduplicated in practice.
+pub fn compress(bytes: &[u8]) -> Vec<u8> {
+    let mut output = vec![COMPRESSION_VERSION];
+    // First position at which each encoding starts, for the positions before `registered`
+    let mut seen: BTreeMap<&[u8], usize> = BTreeMap::new();
+    let mut registered = 0;
+    let mut literal_start = 0;
+    let mut position = 0;
+
+    while position + POINT_SIZE <= bytes.len() {
+        // Only encodings fully before `position` are available to the decompressor
+        while registered + POINT_SIZE <= position {
+            seen.entry(&bytes[registered..registered + POINT_SIZE]).or_insert(registered);
+            registered += 1;
+        }
+
+        match seen.get(&bytes[position..position + POINT_SIZE]) {
+            Some(&reference) => {
+                push_literal(&mut output, &bytes[literal_start..position]);
+                output.push(TAG_REFERENCE);
+                output.extend_from_slice(&(reference as u32).to_le_bytes());
+                position += POINT_SIZE;
+                literal_start = position;
+            }
+            None => position += 1,
+        }
+    }
+    push_literal(&mut output, &bytes[literal_start..]);
+
+    output
+}
+
+/// Recovers the serialized proof from the output of `compress`. Returns a `FormatError` if
+/// the encoding is invalid.
+pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, ProofError> {
+    if bytes.first() != Some(&COMPRESSION_VERSION) {
+        return Err(ProofError::FormatError);
+    }
+
+    let read_u32 = |pos: usize| -> Result<usize, ProofError> {
+        let slice = bytes.get(pos..pos + 4).ok_or(ProofError::FormatError)?;
+        Ok(u32::from_le_bytes(slice.try_into().unwrap()) as usize)
+    };
+
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut position = 1;
+    while position < bytes.len() {
+        let value = read_u32(position + 1)?;
+        match bytes[position] {
+            TAG_LITERAL => {
+                let literal = bytes
+                    .get(position + 5..position + 5 + value)
+                    .ok_or(ProofError::FormatError)?;
+                output.extend_from_slice(literal);
+                position += 5 + value;
+            }
+            TAG_REFERENCE => {
+                if value + POINT_SIZE > output.len() {
+                    return Err(ProofError::FormatError);
+                }
+                output.extend_from_within(value..value + POINT_SIZE);
+                position += 5;
+            }
+            _ => return Err(ProofError::FormatError),
+        }
+    }
+
+    Ok(output)
+}
+
+fn push_literal(output: &mut Vec<u8>, literal: &[u8]) {
+    if !literal.is_empty() {
+        output.push(TAG_LITERAL);
+        output.extend_from_slice(&(literal.len() as u32).to_le_bytes());
+        output.extend_from_slice(literal);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+    use curve25519_dalek::scalar::Scalar;
+    use rand::thread_rng;
+
+    #[test]
+    fn compression_roundtrip() {
+        let points: Vec<[u8; 32]> = (0..4)
+            .map(|_| (RISTRETTO_BASEPOINT_POINT * Scalar::random(&mut thread_rng())).compress().to_bytes())
+            .collect();
+
+        // Repeated points at unaligned positions, separated by other data
+        let mut bytes = vec![7u8; 3];
+        for index in [0, 1, 0, 2, 3, 1, 1].iter() {
+            bytes.extend_from_slice(&points[*index]);
+            bytes.push(*index as u8);
+        }
+
+        // Three points are replaced by references, between four literal runs
+        let compressed = compress(&bytes);
+        assert_eq!(compressed.len(), 1 + bytes.len() - 3 * POINT_SIZE + 3 * 5 + 4 * 5);
+        assert_eq!(decompress(&compressed).unwrap(), bytes);
+
+        assert_eq!(decompress(&compress(&[])).unwrap(), Vec::<u8>::new());
+        assert_eq!(decompress(&compress(&bytes[..20])).unwrap(), bytes[..20].to_vec());
+    }
+
+    #[test]
+    fn decompress_rejects_invalid_encodings() {
+        let point = (RISTRETTO_BASEPOINT_POINT * Scalar::random(&mut thread_rng())).compress().to_bytes();
+        let compressed = compress(&[point, point].concat());
+
+        assert_eq!(decompress(&compressed[..compressed.len() - 1]), Err(ProofError::FormatError));
+        assert_eq!(decompress(&compressed[1..]), Err(ProofError::FormatError));
+        assert_eq!(decompress(&[]), Err(ProofError::FormatError));
+
+        // References to data that is not decompressed yet
+        let mut forward = vec![COMPRESSION_VERSION, TAG_REFERENCE];
+        forward.extend_from_slice(&0u32.to_le_bytes());
+        assert_eq!(decompress(&forward), Err(ProofError::FormatError));
+    }
+}
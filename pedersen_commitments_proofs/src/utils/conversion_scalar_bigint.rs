@@ -1,6 +1,6 @@
 use curve25519_dalek::scalar::Scalar;
 use num_bigint::{BigInt, Sign};
-use std::ops::Neg;
+use subtle::{Choice, ConditionallyNegatable};
 
 pub fn scalar_to_bigInt(scalar: &Scalar) -> BigInt {
     let bytes = scalar.to_bytes();
@@ -8,6 +8,8 @@ pub fn scalar_to_bigInt(scalar: &Scalar) -> BigInt {
     BigInt::from_bytes_le(Sign::Plus, &bytes)
 }
 
+/// The integer as a scalar, reduced modulo the group order. The sign is applied without
+/// branching on it, but the arithmetic of `BigInt` itself is variable time.
 pub fn bigInt_to_scalar(bigInt: &BigInt) -> Result<Scalar, &'static str> {
     let mut buf = [0u8; 64];
     let bytes = bigInt.to_bytes_le();
@@ -19,18 +21,15 @@ pub fn bigInt_to_scalar(bigInt: &BigInt) -> Result<Scalar, &'static str> {
         buf[index] = value;
     }
 
-    if bigInt.sign() == Sign::Plus {
-        return Ok(Scalar::from_bytes_mod_order_wide(&buf))
-    }
-
-    else {
-        return Ok(Scalar::from_bytes_mod_order_wide(&buf).neg())
-    }
+    let mut scalar = Scalar::from_bytes_mod_order_wide(&buf);
+    scalar.conditional_negate(Choice::from((bytes.0 == Sign::Minus) as u8));
+    Ok(scalar)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use core::ops::Neg;
 
     #[test]
     fn test_conversion() {
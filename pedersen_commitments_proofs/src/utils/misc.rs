@@ -1,20 +1,64 @@
+use alloc::vec::Vec;
+use alloc::vec;
 use curve25519_dalek::scalar::Scalar;
 use crate::PedersenVecGens;
-use curve25519_dalek::ristretto::{CompressedRistretto};
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use ip_zk_proof::{CanonicalPoints, ProofError};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Configuration of the bincode encoding of the proofs: little endian, with the integers and
+/// the lengths on 8 bytes, as in the earlier releases.
+const BINCODE_CONFIG: bincode::config::Configuration<
+    bincode::config::LittleEndian,
+    bincode::config::Fixint,
+    bincode::config::NoLimit,
+> = bincode::config::legacy();
+
+/// Serializes `value` with the bincode encoding of the proofs.
+pub(crate) fn bincode_serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, bincode::error::EncodeError> {
+    bincode::serde::encode_to_vec(value, BINCODE_CONFIG)
+}
+
+/// Parses a value serialized with `bincode_serialize`. Trailing bytes are ignored.
+pub(crate) fn bincode_deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, bincode::error::DecodeError> {
+    bincode::serde::decode_from_slice(bytes, BINCODE_CONFIG).map(|(value, _)| value)
+}
+
+/// Size of the bincode encoding of `value`, which is how the sub-proofs are nested in
+/// `zkSVMProver::to_bytes`.
+pub(crate) fn bincode_size<T: Serialize>(value: &T) -> usize {
+    bincode_serialize(value)
+        .expect("Should never happen, as all the proofs can be serialized.")
+        .len()
+}
+
+/// Returns `proof` if all its points are canonically encoded, and a `FormatError` otherwise,
+/// for the `from_bytes_strict` of the proofs.
+pub(crate) fn check_canonical<T: CanonicalPoints>(proof: T) -> Result<T, ProofError> {
+    if proof.points_are_canonical() {
+        Ok(proof)
+    } else {
+        Err(ProofError::FormatError)
+    }
+}
 
 /// We use this subtraction vector to calculate what we will use as the variance.
 /// We need to multiply by the size, because we subtract the addition, and not the average.
 /// in this way, the result will not be the variance, but n**3 * variance.
 pub fn compute_subtraction_vector(
-    size_sensors: &Vec<usize>,
-    sensor_vectors: &Vec<[Vec<Scalar>; 3]>,
-    sensor_additions: &Vec<Vec<Scalar>>
+    size_sensors: &[usize],
+    sensor_vectors: &[[Vec<Scalar>; 3]],
+    sensor_additions: &[Vec<Scalar>]
 ) -> Vec<Vec<Vec<Scalar>>> {
     let mut subtraction_vectors = vec![Vec::new(); sensor_vectors.len()];
     for i in 0..sensor_vectors.len() {
         for j in 0..3 {
-            let mut value_vector: Vec<Scalar> = vec![Scalar::zero(); sensor_vectors[i][j].len()];
-            for (index, value) in sensor_vectors[i][j][0..size_sensors[i]].into_iter().enumerate() {
+            let mut value_vector: Vec<Scalar> = vec![Scalar::ZERO; sensor_vectors[i][j].len()];
+            for (index, value) in sensor_vectors[i][j][0..size_sensors[i]].iter().enumerate() {
                 value_vector[index] = Scalar::from(size_sensors[i] as u64) * value - sensor_additions[i][j];
             }
             subtraction_vectors[i].push(value_vector);
@@ -24,7 +68,7 @@ pub fn compute_subtraction_vector(
 }
 
 pub fn compute_sensors_addition(
-    sensors_vectors: &Vec<[Vec<Scalar>; 3]>
+    sensors_vectors: &[[Vec<Scalar>; 3]]
 ) -> Vec<Vec<Scalar>> {
     let mut additions: Vec<Vec<Scalar>> = (0..sensors_vectors.len()).map(
         |_| Vec::new()
@@ -42,14 +86,14 @@ pub fn compute_sensors_addition(
 
 pub fn generate_permuted_gens(
     ped_vec_generators: &PedersenVecGens,
-    number_values: &Vec<usize>
+    number_values: &[usize]
 ) -> Vec<PedersenVecGens> {
     number_values.iter().map(|&nr| ped_vec_generators.iterate(nr)).collect()
 }
 
 pub fn all_sensors_diff_comm(
-    signed_comms: &Vec<Vec<CompressedRistretto>>,
-    iter_comms: &Vec<Vec<CompressedRistretto>>,
+    signed_comms: &[Vec<CompressedRistretto>],
+    iter_comms: &[Vec<CompressedRistretto>],
 ) -> Vec<Vec<CompressedRistretto>> {
     (0..4).map(
         |i| (0..3).map(
@@ -58,9 +102,66 @@ pub fn all_sensors_diff_comm(
     ).collect()
 }
 
+/// Evaluates `f(i, j)` for every sensor `i` and every coordinate `j < shape[i]`, returning the
+/// results in the same layout. With the `rayon` feature the evaluations run on the rayon thread
+/// pool. Callers must therefore make sure that each evaluation is independent: every sub-proof
+/// builds its own `Transcript` and draws its randomness from the calling thread's `thread_rng`,
+/// and the generators are only read.
+pub fn map_sensor_coords<U, F>(shape: &[usize], f: F) -> Vec<Vec<U>>
+where
+    F: Fn(usize, usize) -> U + Send + Sync,
+    U: Send,
+{
+    #[cfg(feature = "rayon")]
+    {
+        shape.par_iter().enumerate().map(
+            |(i, &nr_coords)| (0..nr_coords).into_par_iter().map(
+                |j| f(i, j)
+            ).collect()
+        ).collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        shape.iter().enumerate().map(
+            |(i, &nr_coords)| (0..nr_coords).map(
+                |j| f(i, j)
+            ).collect()
+        ).collect()
+    }
+}
+
+/// Decompresses a matrix of commitments once, so that the verifier can pass the resulting
+/// points to every sub-proof that needs them instead of decompressing them over and over.
+pub fn decompress_all(
+    comms: &[Vec<CompressedRistretto>],
+) -> Result<Vec<Vec<RistrettoPoint>>, ProofError> {
+    comms.iter().map(
+        |sensor| sensor.iter().map(
+            |comm| comm.decompress().ok_or(ProofError::VerificationError)
+        ).collect()
+    ).collect()
+}
+
+/// Compresses a matrix of points, the inverse of `decompress_all`.
+pub fn compress_all(points: &[Vec<RistrettoPoint>]) -> Vec<Vec<CompressedRistretto>> {
+    points.iter().map(|sensor| sensor.iter().map(|point| point.compress()).collect()).collect()
+}
+
+/// Same as `all_sensors_diff_comm`, but over already decompressed commitments.
+pub fn all_sensors_diff_points(
+    signed_comms: &[Vec<RistrettoPoint>],
+    iter_comms: &[Vec<RistrettoPoint>],
+) -> Vec<Vec<RistrettoPoint>> {
+    signed_comms.iter().zip(iter_comms.iter()).map(
+        |(signed, iter)| signed.iter().zip(iter.iter()).map(
+            |(signed_comm, iter_comm)| signed_comm - iter_comm
+        ).collect()
+    ).collect()
+}
+
 pub fn diff_computation(
-    input_vector: &Vec<[Vec<Scalar>; 3]>,
-    nmbr_nonzero_elements: &Vec<usize>,
+    input_vector: &[[Vec<Scalar>; 3]],
+    nmbr_nonzero_elements: &[usize],
 ) -> Vec<[Vec<Scalar>; 3]> {
     let nr_sensors = input_vector.len();
     let mut diff_vectors: Vec<[Vec<Scalar>; 3]> = (0..nr_sensors).map(
@@ -75,10 +176,10 @@ pub fn diff_computation(
 }
 
 fn one_coord_diff_value(
-    coord_vector: &Vec<Scalar>,
+    coord_vector: &[Scalar],
     nmbr_non_zero_elements:  usize
 ) -> Vec<Scalar> {
-    let mut diff_vector: Vec<Scalar> = coord_vector.clone();
+    let mut diff_vector: Vec<Scalar> = coord_vector.to_vec();
     for i in 0..(nmbr_non_zero_elements - 1) {
         diff_vector[i] -= &coord_vector[i + 1];
     }
@@ -86,3 +187,47 @@ fn one_coord_diff_value(
     diff_vector
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn diff_points_match_compressed_diff() {
+        let mut csprng = thread_rng();
+        let signed: Vec<Vec<CompressedRistretto>> = (0..4).map(
+            |_| (0..3).map(|_| RistrettoPoint::random(&mut csprng).compress()).collect()
+        ).collect();
+        let iter: Vec<Vec<CompressedRistretto>> = (0..4).map(
+            |_| (0..3).map(|_| RistrettoPoint::random(&mut csprng).compress()).collect()
+        ).collect();
+
+        let diff_points = all_sensors_diff_points(
+            &decompress_all(&signed).unwrap(),
+            &decompress_all(&iter).unwrap()
+        );
+        let expected = all_sensors_diff_comm(&signed, &iter);
+
+        for (points, comms) in diff_points.iter().zip(expected.iter()) {
+            for (point, comm) in points.iter().zip(comms.iter()) {
+                assert_eq!(point.compress(), *comm);
+            }
+        }
+    }
+
+    #[test]
+    fn map_sensor_coords_keeps_layout() {
+        let mapped = map_sensor_coords(&[3, 1, 2], |i, j| (i, j));
+        assert_eq!(mapped, vec![
+            vec![(0, 0), (0, 1), (0, 2)],
+            vec![(1, 0)],
+            vec![(2, 0), (2, 1)]
+        ]);
+    }
+
+    #[test]
+    fn decompress_all_rejects_invalid_points() {
+        let invalid = CompressedRistretto([0xffu8; 32]);
+        assert!(decompress_all(&[vec![invalid]]).is_err());
+    }
+}
@@ -1,3 +1,8 @@
+#[cfg(feature = "bigint")]
 pub mod conversion_scalar_bigint;
 pub mod commitment_fns;
-pub mod misc;
\ No newline at end of file
+pub mod compression;
+pub mod misc;
+pub mod randomness;
+#[cfg(feature = "text-encoding")]
+pub mod text_encoding;
\ No newline at end of file
@@ -0,0 +1,118 @@
+use alloc::vec::Vec;
+use alloc::vec;
+use curve25519_dalek::scalar::Scalar;
+use merlin::TranscriptRng;
+use rand_core::{CryptoRng, CryptoRngCore, RngCore};
+
+/// Number of random bytes reduced into each scalar, so that the result is statistically close
+/// to uniform.
+const BYTES_PER_SCALAR: usize = 64;
+
+/// Generate `nr_scalars` random scalars from a single fill of `rng`.
+///
+/// Drawing each blinding factor with its own `Scalar::random(&mut thread_rng())` call goes
+/// through the thread-local RNG lookup and reseeding checks every time, which dominates the
+/// setup of the prover on mobile devices. Here all bytes are requested at once. Any
+/// `RngCore + CryptoRng` can be used, e.g. a `TranscriptRng` to derive the blindings from the
+/// transcript and the witness.
+pub fn random_scalars<R: RngCore + CryptoRng>(rng: &mut R, nr_scalars: usize) -> Vec<Scalar> {
+    let mut bytes = vec![0u8; nr_scalars * BYTES_PER_SCALAR];
+    rng.fill_bytes(&mut bytes);
+    bytes
+        .chunks(BYTES_PER_SCALAR)
+        .map(|chunk| {
+            let mut wide = [0u8; BYTES_PER_SCALAR];
+            wide.copy_from_slice(chunk);
+            Scalar::from_bytes_mod_order_wide(&wide)
+        })
+        .collect()
+}
+
+/// Generate a matrix of random scalars with `shape[i]` elements in row `i`, from a single fill
+/// of `rng`. See `random_scalars`.
+pub fn random_scalar_matrix<R: RngCore + CryptoRng>(rng: &mut R, shape: &[usize]) -> Vec<Vec<Scalar>> {
+    let mut scalars = random_scalars(rng, shape.iter().sum()).into_iter();
+    shape.iter().map(|&len| scalars.by_ref().take(len).collect()).collect()
+}
+
+/// Draw a 32-byte seed per element of a matrix with `shape[i]` elements in row `i`, from a
+/// single fill of `rng`. The proofs created in parallel, see `map_sensor_coords`, seed their
+/// own RNG with them, so that a single `rng` is not shared across threads.
+pub fn random_seed_matrix<R: RngCore + CryptoRng>(rng: &mut R, shape: &[usize]) -> Vec<Vec<[u8; 32]>> {
+    let mut bytes = vec![0u8; shape.iter().sum::<usize>() * 32];
+    rng.fill_bytes(&mut bytes);
+    let mut seeds = bytes.chunks(32).map(|chunk| {
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(chunk);
+        seed
+    });
+    shape.iter().map(|&len| seeds.by_ref().take(len).collect()).collect()
+}
+
+/// The RNG of a prover, see `PedersenConfig::prover_rng`: the RNG given by the caller, or one
+/// keyed by the witness of the prover.
+pub enum ProverRng<'r> {
+    External(&'r mut dyn CryptoRngCore),
+    WitnessBound(TranscriptRng),
+}
+
+impl RngCore for ProverRng<'_> {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            ProverRng::External(rng) => rng.next_u32(),
+            ProverRng::WitnessBound(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            ProverRng::External(rng) => rng.next_u64(),
+            ProverRng::WitnessBound(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            ProverRng::External(rng) => rng.fill_bytes(dest),
+            ProverRng::WitnessBound(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        match self {
+            ProverRng::External(rng) => rng.try_fill_bytes(dest),
+            ProverRng::WitnessBound(rng) => rng.try_fill_bytes(dest),
+        }
+    }
+}
+
+impl CryptoRng for ProverRng<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn matrix_follows_shape() {
+        let mut rng = ChaChaRng::from_seed([7u8; 32]);
+        let matrix = random_scalar_matrix(&mut rng, &[3, 0, 5]);
+        assert_eq!(matrix.iter().map(|a| a.len()).collect::<Vec<usize>>(), vec![3, 0, 5]);
+
+        // Same stream of randomness as the flat version.
+        let mut rng = ChaChaRng::from_seed([7u8; 32]);
+        let flat = random_scalars(&mut rng, 8);
+        assert_eq!(matrix.concat(), flat);
+    }
+
+    #[test]
+    fn scalars_are_distinct() {
+        let scalars = random_scalars(&mut rand::thread_rng(), 16);
+        for i in 0..scalars.len() {
+            for j in (i + 1)..scalars.len() {
+                assert_ne!(scalars[i], scalars[j]);
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Hex and base64 encodings of the serialized proofs and commitments, to move them through
+//! text formats such as JSON APIs.
+//!
+//! [`TextEncoding`] is implemented by every type with a byte encoding, and encodes the output
+//! of its `to_bytes`. The proofs of the zkSVM are parsed against the `PublicParams`, so their
+//! `Params` are the parameters, while self-contained encodings take `()`. Hex is lowercase on
+//! output and accepts either case on input, and base64 is the standard alphabet with padding.
+
+use core::convert::TryInto;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use ip_zk_proof::r1cs::R1CSProof;
+use ip_zk_proof::{InnerProductZKProof, InnerProductZKProofPlus, PrimeGroup, ProofError, RangeProof, RangeProofPlus, ZkProof};
+
+use crate::algebraic_proofs::hidden_count_proof::HiddenCountProof;
+use crate::algebraic_proofs::std_r1cs_proof::StdR1CSProof;
+use crate::boolean_proofs::dlog_proof::DlogZKProof;
+use crate::boolean_proofs::equality_proof::EqualityZKProof;
+use crate::boolean_proofs::opening_proof::OpeningZKProof;
+use crate::boolean_proofs::removal_proof::ElementRemovalProof;
+use crate::sigma::SigmaProof;
+use crate::{AggregatedProof, ClassificationProof, MultiWindowProof, PublicParams, RevealedClassificationProof, zkSVMProver};
+
+/// Encodes `bytes` as lowercase hex.
+pub fn to_hex(bytes: &[u8]) -> String {
+    hex::encode(bytes)
+}
+
+/// Decodes a hex string, failing with `FormatError` on invalid characters or an odd length.
+pub fn from_hex(string: &str) -> Result<Vec<u8>, ProofError> {
+    hex::decode(string).map_err(|_| ProofError::FormatError)
+}
+
+/// Encodes `bytes` as padded base64 of the standard alphabet.
+pub fn to_base64(bytes: &[u8]) -> String {
+    STANDARD.encode(bytes)
+}
+
+/// Decodes a padded base64 string of the standard alphabet, failing with `FormatError`
+/// otherwise.
+pub fn from_base64(string: &str) -> Result<Vec<u8>, ProofError> {
+    STANDARD.decode(string).map_err(|_| ProofError::FormatError)
+}
+
+/// A value with a byte encoding, that can be moved as hex or base64 text.
+pub trait TextEncoding: Sized {
+    /// What the value is parsed against, `()` if the encoding is self-contained.
+    type Params<'a>: Copy;
+
+    /// The byte encoding of the value, i.e. its `to_bytes`.
+    fn encode_bytes(&self) -> Vec<u8>;
+
+    /// Parses a value encoded with `encode_bytes`, i.e. its `from_bytes`.
+    fn decode_bytes(bytes: &[u8], params: Self::Params<'_>) -> Result<Self, ProofError>;
+
+    /// The byte encoding of the value as lowercase hex.
+    fn to_hex(&self) -> String {
+        to_hex(&self.encode_bytes())
+    }
+
+    /// Parses a value encoded with `to_hex`.
+    fn from_hex(string: &str, params: Self::Params<'_>) -> Result<Self, ProofError> {
+        Self::decode_bytes(&from_hex(string)?, params)
+    }
+
+    /// The byte encoding of the value as padded base64.
+    fn to_base64(&self) -> String {
+        to_base64(&self.encode_bytes())
+    }
+
+    /// Parses a value encoded with `to_base64`.
+    fn from_base64(string: &str, params: Self::Params<'_>) -> Result<Self, ProofError> {
+        Self::decode_bytes(&from_base64(string)?, params)
+    }
+}
+
+/// Implements `TextEncoding` for types with `to_bytes` and a self-contained `from_bytes`.
+macro_rules! self_contained {
+    ($($proof:ty),+ $(,)?) => {
+        $(
+            impl TextEncoding for $proof {
+                type Params<'a> = ();
+
+                fn encode_bytes(&self) -> Vec<u8> {
+                    self.to_bytes().to_vec()
+                }
+
+                fn decode_bytes(bytes: &[u8], _: ()) -> Result<Self, ProofError> {
+                    <$proof>::from_bytes(bytes).map_err(|_| ProofError::FormatError)
+                }
+            }
+        )+
+    };
+}
+
+/// Implements `TextEncoding` for the proofs parsed against the `PublicParams`.
+macro_rules! with_params {
+    ($($proof:ty),+ $(,)?) => {
+        $(
+            impl TextEncoding for $proof {
+                type Params<'a> = &'a PublicParams;
+
+                fn encode_bytes(&self) -> Vec<u8> {
+                    self.to_bytes()
+                }
+
+                fn decode_bytes(bytes: &[u8], params: &PublicParams) -> Result<Self, ProofError> {
+                    <$proof>::from_bytes(bytes, params)
+                }
+            }
+        )+
+    };
+}
+
+self_contained!(
+    RangeProof,
+    RangeProofPlus,
+    R1CSProof,
+    StdR1CSProof,
+    SigmaProof,
+    DlogZKProof,
+    EqualityZKProof,
+    OpeningZKProof,
+    ElementRemovalProof,
+    HiddenCountProof,
+    PublicParams,
+);
+
+with_params!(zkSVMProver, AggregatedProof, MultiWindowProof, ClassificationProof, RevealedClassificationProof);
+
+impl<G: PrimeGroup> TextEncoding for InnerProductZKProof<G> {
+    type Params<'a> = ();
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn decode_bytes(bytes: &[u8], _: ()) -> Result<Self, ProofError> {
+        InnerProductZKProof::from_bytes(bytes)
+    }
+}
+
+impl<G: PrimeGroup> TextEncoding for InnerProductZKProofPlus<G> {
+    type Params<'a> = ();
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn decode_bytes(bytes: &[u8], _: ()) -> Result<Self, ProofError> {
+        InnerProductZKProofPlus::from_bytes(bytes)
+    }
+}
+
+/// Commitments, encoded as their 32 bytes. Decoding does not check that they are valid points,
+/// as for the commitments inside the proofs.
+impl TextEncoding for CompressedRistretto {
+    type Params<'a> = ();
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+
+    fn decode_bytes(bytes: &[u8], _: ()) -> Result<Self, ProofError> {
+        CompressedRistretto::from_slice(bytes).map_err(|_| ProofError::FormatError)
+    }
+}
+
+/// Scalars, such as blinding factors, encoded as their 32 canonical bytes.
+impl TextEncoding for Scalar {
+    type Params<'a> = ();
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+
+    fn decode_bytes(bytes: &[u8], _: ()) -> Result<Self, ProofError> {
+        let bytes: [u8; 32] = bytes.try_into().map_err(|_| ProofError::FormatError)?;
+        Option::from(Scalar::from_canonical_bytes(bytes)).ok_or(ProofError::FormatError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use merlin::Transcript;
+
+    #[test]
+    fn text_round_trips() {
+        let params = PublicParams::new(4);
+        let opening: Vec<Scalar> = (1..=4u64).map(Scalar::from).collect();
+        let proof = OpeningZKProof::prove_opening(params.G_vec(), &opening, Scalar::from(5u64), &mut Transcript::new(b"test"));
+
+        let hex = proof.to_hex();
+        assert_eq!(hex, to_hex(&proof.to_bytes()));
+        assert_eq!(OpeningZKProof::from_hex(&hex.to_uppercase(), ()).unwrap(), proof);
+        assert_eq!(OpeningZKProof::from_base64(&proof.to_base64(), ()).unwrap(), proof);
+
+        let decoded = PublicParams::from_base64(&params.to_base64(), ()).unwrap();
+        assert_eq!(decoded.to_bytes(), params.to_bytes());
+
+        let commitment = CompressedRistretto([7u8; 32]);
+        assert_eq!(CompressedRistretto::from_hex(&commitment.to_hex(), ()).unwrap(), commitment);
+        assert!(CompressedRistretto::from_hex(&commitment.to_hex()[2..], ()).is_err());
+
+        assert!(from_hex("0g").is_err());
+        assert!(from_base64("AAA").is_err());
+        assert!(zkSVMProver::from_base64(&to_base64(&[1, 2, 3]), &params).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+//! Sensor windows with their shape in the type.
+//!
+//! The dynamic API takes the sensor vectors as `Vec<[Vec<T>; 3]>`, with the number of readings
+//! of each vector aside, and panics or fails late when a vector does not have three axes, or
+//! the same size as the others. A `SensorWindow<AXES, N>` has `AXES` axes of `N` elements, so
+//! the windows of a slice `&[SensorWindow<3, N>]` have, by construction, the shape of the
+//! sensor vectors, and are converted into the dynamic representation with `to_dynamic`. Only
+//! the number of readings of each window is checked at runtime.
+
+use alloc::vec::Vec;
+use ip_zk_proof::ProofError;
+
+use crate::features::{FeatureInt, SensorFeatures};
+
+/// A window of at most `N` readings of a sensor with `AXES` axes, zero-padded to `N`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SensorWindow<const AXES: usize, const N: usize, T = i128> {
+    // The elements of each axis
+    axes: [[T; N]; AXES],
+    // Number of readings, followed by zeros
+    nr_readings: usize,
+}
+
+impl<const AXES: usize, const N: usize, T: FeatureInt> SensorWindow<AXES, N, T> {
+    /// Creates the window of `readings`, each with one value per axis. Returns an
+    /// `InvalidGeneratorsLength` if there are more than `N` readings, or less than two, as the
+    /// diff vector of the window would not be defined.
+    pub fn from_readings(readings: &[[T; AXES]]) -> Result<SensorWindow<AXES, N, T>, ProofError> {
+        if readings.len() < 2 || readings.len() > N {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+
+        Ok(SensorWindow {
+            axes: core::array::from_fn(|axis| core::array::from_fn(|i| match readings.get(i) {
+                Some(reading) => reading[axis].clone(),
+                None => T::from(0),
+            })),
+            nr_readings: readings.len(),
+        })
+    }
+
+    /// Creates the window of the first `len` elements of each axis of `axes`. Returns an
+    /// `InvalidGeneratorsLength` if `len` is not between two and `N`, and a `FormatError` if
+    /// an element after the first `len` is not zero.
+    pub fn from_axes(axes: [[T; N]; AXES], len: usize) -> Result<SensorWindow<AXES, N, T>, ProofError> {
+        if len < 2 || len > N {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        if axes.iter().any(|axis| axis[len..].iter().any(|x| *x != T::from(0))) {
+            return Err(ProofError::FormatError);
+        }
+
+        Ok(SensorWindow { axes, nr_readings: len })
+    }
+
+    /// Number of readings of the window.
+    pub fn nr_readings(&self) -> usize {
+        self.nr_readings
+    }
+
+    /// The elements of each axis, with the readings followed by zeros.
+    pub fn axes(&self) -> &[[T; N]; AXES] {
+        &self.axes
+    }
+}
+
+impl<const N: usize, T: FeatureInt> SensorWindow<3, N, T> {
+    /// The window as a sensor vector of the dynamic API, with its number of readings.
+    pub fn into_dynamic(self) -> ([Vec<T>; 3], usize) {
+        let [x, y, z] = self.axes;
+        ([x.to_vec(), y.to_vec(), z.to_vec()], self.nr_readings)
+    }
+}
+
+/// The sensor vectors and their numbers of readings of `windows`, as taken by
+/// `SensorFeatures::extract` and `zkSENSE_rust_proof::zkSVM::create`.
+pub fn to_dynamic<const N: usize, T: FeatureInt>(
+    windows: &[SensorWindow<3, N, T>],
+) -> (Vec<[Vec<T>; 3]>, Vec<usize>) {
+    windows.iter().cloned().map(SensorWindow::into_dynamic).unzip()
+}
+
+impl<T: FeatureInt> SensorFeatures<T> {
+    /// Computes the features of `windows`, as `extract` over their dynamic representation.
+    pub fn from_windows<const N: usize>(windows: &[SensorWindow<3, N, T>]) -> SensorFeatures<T> {
+        let (input_vector, non_zero_elements) = to_dynamic(windows);
+        SensorFeatures::extract(&input_vector, &non_zero_elements)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_match_the_dynamic_representation() {
+        let first = SensorWindow::<3, 8>::from_readings(&[[5, 2, 9], [3, 2, 0], [8, 2, 4], [1, 2, 7]]).unwrap();
+        let second = SensorWindow::<3, 8>::from_axes(
+            [[1, 6, 3, 0, 0, 0, 0, 0], [7, 7, 1, 0, 0, 0, 0, 0], [4, 4, 4, 0, 0, 0, 0, 0]],
+            3,
+        ).unwrap();
+
+        let (input_vector, non_zero_elements) = to_dynamic(&[first.clone(), second.clone()]);
+        assert_eq!(non_zero_elements, vec![4, 3]);
+        assert_eq!(input_vector[0][0], vec![5, 3, 8, 1, 0, 0, 0, 0]);
+        assert_eq!(input_vector[1][2], vec![4, 4, 4, 0, 0, 0, 0, 0]);
+        assert_eq!(first.axes()[2][..4], [9, 0, 4, 7]);
+
+        assert_eq!(
+            SensorFeatures::from_windows(&[first, second]),
+            SensorFeatures::extract(&input_vector, &non_zero_elements)
+        );
+    }
+
+    #[test]
+    fn invalid_windows() {
+        assert_eq!(SensorWindow::<3, 2>::from_readings(&[[1, 2, 3]; 3]).err(), Some(ProofError::InvalidGeneratorsLength));
+        assert_eq!(SensorWindow::<3, 8>::from_readings(&[[1, 2, 3]]).err(), Some(ProofError::InvalidGeneratorsLength));
+        assert_eq!(SensorWindow::<1, 4>::from_axes([[1, 2, 3, 0]], 5).err(), Some(ProofError::InvalidGeneratorsLength));
+        assert_eq!(SensorWindow::<1, 4>::from_axes([[1, 2, 3, 4]], 3).err(), Some(ProofError::FormatError));
+    }
+}
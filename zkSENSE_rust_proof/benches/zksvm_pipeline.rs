@@ -0,0 +1,63 @@
+#![allow(non_snake_case)]
+//! End-to-end benchmarks of `zkSVM::create` and `verify`, from the raw sensor vectors, over
+//! several window sizes and numbers of readings per sensor. The proof always covers the four
+//! windows of `zkSVM`: two of the accelerometer and two of the gyroscope.
+#[macro_use]
+extern crate criterion;
+
+use criterion::{BenchmarkId, Criterion};
+use pedersen_commitments_proofs::PublicParams;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use zkSENSE_rust_proof::zkSVM;
+
+/// Window sizes, i.e. the size of the vectors and of the generators.
+const WINDOW_SIZES: [usize; 3] = [64, 128, 256];
+
+/// Fractions of the window filled with readings, in percent.
+const FILL_RATES: [usize; 2] = [50, 100];
+
+/// Four windows of `size` elements with `readings` readings each, zero padded. The readings
+/// are within 10^8 +- 5 * 10^5, as in a window of the dataset. Much wider spreads overflow the
+/// `SQUARE_RANGE_BITSIZE` of the standard deviations for the largest windows.
+fn sensor_windows(size: usize, readings: usize) -> (Vec<[Vec<i128>; 3]>, Vec<usize>) {
+    let mut rng = StdRng::seed_from_u64(size as u64 * 1000 + readings as u64);
+    let mut axis = || -> Vec<i128> {
+        (0..size)
+            .map(|i| if i < readings { rng.gen_range(99_500_000i128..100_500_000) } else { 0 })
+            .collect()
+    };
+    let windows = (0..4).map(|_| [axis(), axis(), axis()]).collect();
+    (windows, vec![readings; 4])
+}
+
+fn zksvm_pipeline(c: &mut Criterion) {
+    let mut group = c.benchmark_group("zkSVM pipeline");
+    for &size in WINDOW_SIZES.iter() {
+        let params = PublicParams::new(size);
+        for &fill in FILL_RATES.iter() {
+            let readings = size * fill / 100;
+            let (windows, sizes) = sensor_windows(size, readings);
+            let id = format!("{}/{}", size, readings);
+
+            group.bench_with_input(BenchmarkId::new("create", &id), &windows, |b, windows| {
+                b.iter(|| zkSVM::create_with_params(&params, windows, &sizes).unwrap())
+            });
+
+            let proof = zkSVM::create_with_params(&params, &windows, &sizes).unwrap();
+            group.bench_with_input(BenchmarkId::new("verify", &id), &proof, |b, proof| {
+                b.iter(|| proof.clone().verify_with_params(&params).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets =
+    zksvm_pipeline
+);
+
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+#![allow(non_snake_case)]
+//! Runs the whole pipeline over the bundled sensor trace: the proof is created, serialized,
+//! parsed back and verified under the public parameters, and, bound to a transcript, verified
+//! under the transcript of the verifier.
+//!
+//! Run with `cargo run --release --features examples --example full_pipeline`.
+use merlin::Transcript;
+use pedersen_commitments_proofs::PublicParams;
+use zkSENSE_rust_proof::dataset::{sensor_trace, TRACE_VECTOR_SIZE};
+use zkSENSE_rust_proof::zkSVM;
+use std::time::Instant;
+
+fn main() {
+    let (all_sensor_vectors, size_sensors) = sensor_trace();
+    let params = PublicParams::new(TRACE_VECTOR_SIZE);
+
+    let proof_gen = Instant::now();
+    let proof = zkSVM::create_with_params(&params, &all_sensor_vectors, &size_sensors)
+        .expect("Error generating the proof");
+    println!("Proof created in {:?}", proof_gen.elapsed());
+
+    let bytes = proof.to_bytes();
+    println!("Proof size: {} bytes", bytes.len());
+
+    let proof_verif = Instant::now();
+    zkSVM::from_bytes(&bytes, &params)
+        .expect("Error parsing the proof")
+        .verify_with_params(&params)
+        .expect("Error verifying the proof");
+    println!("Proof verified in {:?}", proof_verif.elapsed());
+
+    // A truncated proof is rejected when parsed
+    assert!(zkSVM::from_bytes(&bytes[..bytes.len() - 1], &params).is_err());
+
+    // Bound to a transcript, the proof only verifies under a transcript in the same state
+    let mut transcript = Transcript::new(b"full pipeline");
+    let bound_proof = zkSVM::create_with_transcript(&params, &mut transcript, &all_sensor_vectors, &size_sensors)
+        .expect("Error generating the proof");
+    let bytes = bound_proof.to_bytes();
+
+    let mut transcript = Transcript::new(b"full pipeline");
+    zkSVM::from_bytes(&bytes, &params).unwrap()
+        .verify_with_transcript(&params, &mut transcript)
+        .expect("Error verifying the proof bound to the transcript");
+
+    let mut transcript = Transcript::new(b"another protocol");
+    assert!(zkSVM::from_bytes(&bytes, &params).unwrap()
+        .verify_with_transcript(&params, &mut transcript)
+        .is_err());
+    println!("All checks passed");
+}
@@ -0,0 +1,197 @@
+//! Parsing of batches of Android `SensorEvent`s into the sensor vectors of the proof.
+//!
+//! The windowing determines the statement that is proven, so the mobile client passes its raw
+//! events here instead of building the windows itself. A batch has one event per line, as
+//! `sensor_type,timestamp_ns,v0,v1,v2`, with the sensor type and the timestamp (in nanoseconds)
+//! of the event and its `float` values. Extra values, as in the uncalibrated sensors, are
+//! ignored, as are the lines starting with `#` and the empty lines.
+
+use ip_zk_proof::ProofError;
+
+/// `Sensor.TYPE_ACCELEROMETER`
+pub const TYPE_ACCELEROMETER: u32 = 1;
+/// `Sensor.TYPE_GYROSCOPE`
+pub const TYPE_GYROSCOPE: u32 = 4;
+
+/// Sensor vectors, zero-padded to the vector size, with the number of readings of each.
+pub type Windows = (Vec<[Vec<i128>; 3]>, Vec<usize>);
+
+/// A reading of a sensor, as reported by `SensorEvent`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SensorEvent {
+    pub sensor_type: u32,
+    pub timestamp_ns: u64,
+    pub values: [f32; 3],
+}
+
+/// How the events are aggregated into sensor vectors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WindowConfig {
+    /// Sensors for which windows are built, in the order of the sensor vectors
+    pub sensor_types: Vec<u32>,
+    /// Number of consecutive windows of each sensor
+    pub nr_windows: usize,
+    /// Duration of a window
+    pub window_ns: u64,
+    /// Size of the sensor vectors, i.e. maximum number of readings of a window
+    pub vector_size: usize,
+    /// A value `v` is encoded as the integer `round(v * scale) + offset`
+    pub scale: f64,
+    pub offset: i64,
+}
+
+impl WindowConfig {
+    /// Two windows of `window_ns` of the accelerometer and then two of the gyroscope, as the
+    /// sensor vectors of `zkSVM::create`, of `vector_size` readings. The values are encoded
+    /// with six decimals and an offset of \\(10^8\\), which keeps them positive.
+    pub fn new(window_ns: u64, vector_size: usize) -> WindowConfig {
+        WindowConfig {
+            sensor_types: vec![TYPE_ACCELEROMETER, TYPE_GYROSCOPE],
+            nr_windows: 2,
+            window_ns,
+            vector_size,
+            scale: 1e6,
+            offset: 100_000_000,
+        }
+    }
+
+    /// Aggregates `events` into the sensor vectors: for each sensor of `sensor_types`, its
+    /// `nr_windows` windows. The windows of all sensors start at the first event of these
+    /// sensors, and the events after the last window, or of other sensors, are ignored.
+    ///
+    /// Returns the vectors, zero-padded to `vector_size`, and the number of readings of each.
+    /// Returns a `FormatError` if a value cannot be encoded, and an
+    /// `InvalidGeneratorsLength` if a window has more than `vector_size` readings, or less
+    /// than two.
+    pub fn windows(&self, events: &[SensorEvent]) -> Result<Windows, ProofError> {
+        let start = events.iter()
+            .filter(|e| self.sensor_types.contains(&e.sensor_type))
+            .map(|e| e.timestamp_ns)
+            .min()
+            .unwrap_or(0);
+        self.windows_from(events, start)
+    }
+
+    /// As `windows`, with the windows starting at `start_ns`. The events before are ignored.
+    pub fn windows_from(&self, events: &[SensorEvent], start_ns: u64) -> Result<Windows, ProofError> {
+        let mut events: Vec<&SensorEvent> = events.iter()
+            .filter(|e| self.sensor_types.contains(&e.sensor_type) && e.timestamp_ns >= start_ns)
+            .collect();
+        events.sort_by_key(|e| e.timestamp_ns);
+        let start = start_ns;
+
+        let mut vectors: Vec<[Vec<i128>; 3]> =
+            vec![[Vec::new(), Vec::new(), Vec::new()]; self.sensor_types.len() * self.nr_windows];
+        for event in events {
+            let window = ((event.timestamp_ns - start) / self.window_ns) as usize;
+            if window >= self.nr_windows {
+                continue;
+            }
+            let sensor = self.sensor_types.iter().position(|&t| t == event.sensor_type)
+                .expect("Should never happen, as the events of other sensors are filtered out.");
+            for (coord, value) in vectors[sensor * self.nr_windows + window].iter_mut().zip(event.values.iter()) {
+                coord.push(self.encode(*value)?);
+            }
+        }
+
+        let sizes: Vec<usize> = vectors.iter().map(|v| v[0].len()).collect();
+        if sizes.iter().any(|&n| n < 2 || n > self.vector_size) {
+            return Err(ProofError::InvalidGeneratorsLength);
+        }
+        for vector in vectors.iter_mut() {
+            for coord in vector.iter_mut() {
+                coord.resize(self.vector_size, 0);
+            }
+        }
+
+        Ok((vectors, sizes))
+    }
+
+    fn encode(&self, value: f32) -> Result<i128, ProofError> {
+        let scaled = (value as f64 * self.scale).round();
+        if !scaled.is_finite() || scaled.abs() >= i64::MAX as f64 / 2.0 {
+            return Err(ProofError::FormatError);
+        }
+        Ok(scaled as i128 + self.offset as i128)
+    }
+}
+
+impl SensorEvent {
+    /// Parses a batch of events. Returns a `FormatError` if a line is malformed.
+    pub fn parse_batch(batch: &str) -> Result<Vec<SensorEvent>, ProofError> {
+        batch.lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|line| {
+                let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+                if fields.len() < 5 {
+                    return Err(ProofError::FormatError);
+                }
+                let value = |i: usize| fields[i].parse::<f32>().map_err(|_| ProofError::FormatError);
+                Ok(SensorEvent {
+                    sensor_type: fields[0].parse().map_err(|_| ProofError::FormatError)?,
+                    timestamp_ns: fields[1].parse().map_err(|_| ProofError::FormatError)?,
+                    values: [value(2)?, value(3)?, value(4)?],
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    fn batch() -> String {
+        let mut lines = vec!["# type,timestamp,x,y,z".to_string()];
+        // Readings every 250ms, interleaved and out of order, plus a magnetometer
+        for i in (0..10u64).rev() {
+            lines.push(format!("{},{},{},0.5,-9.81", TYPE_ACCELEROMETER, 7 + i * SECOND / 4, i));
+            lines.push(format!("{},{},0.01,0.02,0.03,0.0,0.0,0.0", TYPE_GYROSCOPE, 9 + i * SECOND / 4));
+            lines.push(format!("2,{},30.0,1.0,2.0", 8 + i * SECOND / 4));
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn events_are_windowed() {
+        let events = SensorEvent::parse_batch(&batch()).unwrap();
+        assert_eq!(events.len(), 30);
+
+        let (vectors, sizes) = WindowConfig::new(SECOND, 8).windows(&events).unwrap();
+        // Two windows of four readings per sensor, and the last two readings are dropped
+        assert_eq!(sizes, vec![4, 4, 4, 4]);
+        assert!(vectors.iter().all(|v| v.iter().all(|coord| coord.len() == 8)));
+        assert_eq!(vectors[1][0][..4], [104_000_000, 105_000_000, 106_000_000, 107_000_000]);
+        assert_eq!(vectors[0][2][0], 100_000_000 - 9_810_000);
+        assert_eq!(vectors[3][1][3], 100_020_000);
+        assert_eq!(vectors[3][1][4], 0);
+    }
+
+    #[test]
+    fn events_feed_the_prover() {
+        use crate::zkSVM;
+        use pedersen_commitments_proofs::PublicParams;
+
+        let events = SensorEvent::parse_batch(&batch()).unwrap();
+        let params = PublicParams::new(64);
+        let proof = zkSVM::create_from_events(&params, &events, &WindowConfig::new(SECOND, 64)).unwrap();
+        assert!(proof.verify_with_params(&params).is_ok());
+    }
+
+    #[test]
+    fn invalid_batches() {
+        assert_eq!(SensorEvent::parse_batch("1,100,0.1,0.2").err(), Some(ProofError::FormatError));
+        assert_eq!(SensorEvent::parse_batch("1,-100,0.1,0.2,0.3").err(), Some(ProofError::FormatError));
+
+        let events = SensorEvent::parse_batch(&batch()).unwrap();
+        assert_eq!(WindowConfig::new(SECOND, 3).windows(&events).err(), Some(ProofError::InvalidGeneratorsLength));
+        assert_eq!(WindowConfig::new(SECOND / 4, 8).windows(&events).err(), Some(ProofError::InvalidGeneratorsLength));
+        assert_eq!(
+            WindowConfig::new(SECOND, 8).windows(&[SensorEvent { sensor_type: 1, timestamp_ns: 0, values: [f32::NAN; 3] }]).err(),
+            Some(ProofError::FormatError)
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! A small recorded accelerometer and gyroscope trace, to run the whole pipeline on real data.
+//!
+//! The trace is stored in `data/sensor_trace.csv`, with one reading per line as
+//! `window,x,y,z`. The readings of each window are zero-padded to `TRACE_VECTOR_SIZE`, the size
+//! of the public parameters of the proof.
+
+use alloc::vec::Vec;
+use ip_zk_proof::ProofError;
+
+const SENSOR_TRACE: &str = include_str!("../data/sensor_trace.csv");
+
+/// Size of the sensor vectors of the bundled trace.
+pub const TRACE_VECTOR_SIZE: usize = 64;
+
+/// Zero-padded sensor vectors, with the number of readings of each.
+pub type SensorTrace = (Vec<[Vec<i128>; 3]>, Vec<usize>);
+
+/// The sensor vectors of the bundled trace, with the number of readings of each, as taken by
+/// `zkSVM::create`.
+pub fn sensor_trace() -> SensorTrace {
+    parse_trace(SENSOR_TRACE, TRACE_VECTOR_SIZE)
+        .expect("Should never happen, as the bundled trace is well formed.")
+}
+
+/// Parses a trace in the format of the bundled one into zero-padded vectors of `size`
+/// readings. Lines starting with `#` and empty lines are ignored. Returns a `FormatError` if a
+/// line is malformed, if the windows are not numbered consecutively from zero, or if a window
+/// has no readings or more than `size`.
+pub fn parse_trace(trace: &str, size: usize) -> Result<SensorTrace, ProofError> {
+    let mut vectors: Vec<[Vec<i128>; 3]> = Vec::new();
+    for line in trace.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != 4 {
+            return Err(ProofError::FormatError);
+        }
+        let window: usize = fields[0].parse().map_err(|_| ProofError::FormatError)?;
+        if window == vectors.len() {
+            vectors.push([Vec::new(), Vec::new(), Vec::new()]);
+        } else if window + 1 != vectors.len() {
+            return Err(ProofError::FormatError);
+        }
+        for (coord, field) in vectors[window].iter_mut().zip(fields[1..].iter()) {
+            coord.push(field.parse().map_err(|_| ProofError::FormatError)?);
+        }
+    }
+
+    let sizes: Vec<usize> = vectors.iter().map(|v| v[0].len()).collect();
+    if sizes.iter().any(|&n| n == 0 || n > size) {
+        return Err(ProofError::FormatError);
+    }
+    for vector in vectors.iter_mut() {
+        for coord in vector.iter_mut() {
+            coord.resize(size, 0);
+        }
+    }
+
+    Ok((vectors, sizes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_trace() {
+        let (vectors, sizes) = sensor_trace();
+        assert_eq!(sizes, vec![11, 10, 52, 49]);
+        assert!(vectors.iter().all(|v| v.iter().all(|coord| coord.len() == TRACE_VECTOR_SIZE)));
+        assert_eq!(vectors[0][0][0], 100005003);
+        assert_eq!(vectors[3][2][48], 100043994);
+    }
+
+    #[test]
+    fn malformed_traces() {
+        assert!(parse_trace("0,1,2,3\n0,4,5,6\n1,7,8,9", 2).is_ok());
+        assert_eq!(parse_trace("0,1,2,3\n0,4,5,6\n0,7,8,9", 2).err(), Some(ProofError::FormatError));
+        assert_eq!(parse_trace("0,1,2,3\n2,4,5,6", 2).err(), Some(ProofError::FormatError));
+        assert_eq!(parse_trace("0,1,2", 2).err(), Some(ProofError::FormatError));
+        assert_eq!(parse_trace("0,1,2,x", 2).err(), Some(ProofError::FormatError));
+    }
+}
@@ -1,7 +1,17 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(non_snake_case)]
 #![allow(non_camel_case_types)]
 
+extern crate alloc;
+
 mod zksense;
 mod utils;
+#[cfg(feature = "std")]
+pub mod android;
+#[cfg(feature = "std")]
+pub mod window_manager;
+pub mod model;
+#[cfg(feature = "examples")]
+pub mod dataset;
 
-pub use crate::zksense::zkSVM;
\ No newline at end of file
+pub use crate::zksense::{zkSVM, zkSVMPadded};
\ No newline at end of file
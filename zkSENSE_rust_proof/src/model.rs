@@ -0,0 +1,128 @@
+//! The SVM model over which `zkSVM::classify_and_prove` proves the decision.
+//!
+//! The trained weights of zkSENSE are not distributed with this repository. With the
+//! `embedded-model` feature, the model in the file at `ZKSENSE_SVM_MODEL` when building is
+//! embedded in the library and returned by `embedded`, in the text form of `SvmModel::parse`.
+
+#[cfg(feature = "embedded-model")]
+use pedersen_commitments_proofs::SvmModel;
+
+#[cfg(feature = "embedded-model")]
+const EMBEDDED_MODEL: &str = include_str!(env!("ZKSENSE_SVM_MODEL"));
+
+/// The model embedded at build time.
+///
+/// Panics if the embedded model is malformed.
+#[cfg(feature = "embedded-model")]
+pub fn embedded() -> SvmModel {
+    SvmModel::parse(EMBEDDED_MODEL).expect("The embedded model should be well formed.")
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::zkSVM;
+    use ip_zk_proof::{ProofError, SerializedSize};
+    use pedersen_commitments_proofs::{ClassificationProof, PublicParams, RevealedClassificationProof, SvmModel};
+
+    /// A model over the zkSENSE features, i.e. eight evaluated vectors and four sensor
+    /// vectors, that only weights the addition of the first axis of the first sensor vector.
+    fn first_addition_model(bias: i64) -> SvmModel {
+        let mut addition_weights = vec![vec![0; 3]; 8];
+        addition_weights[0][0] = 1;
+        SvmModel { addition_weights, std_weights: vec![vec![0; 3]; 4], bias }
+    }
+
+    fn sensor_vectors() -> (Vec<[Vec<i128>; 3]>, Vec<usize>) {
+        let coord = |start: i128| -> Vec<i128> {
+            let mut vector: Vec<i128> = (0..10).map(|i| 100_000_000 + start + 37 * i * i).collect();
+            vector.resize(64, 0);
+            vector
+        };
+        (
+            (0..4).map(|i| [coord(i), coord(2 * i + 5), coord(3 * i + 11)]).collect(),
+            vec![10; 4],
+        )
+    }
+
+    /// Replaces the first occurrence of `point` in `bytes` by a non-canonical encoding.
+    fn non_canonical(bytes: &[u8], point: &[u8; 32]) -> Vec<u8> {
+        let position = bytes.windows(32).position(|w| w == point).unwrap();
+        let mut tampered = bytes.to_vec();
+        tampered[position..position + 32].copy_from_slice(&[0xff; 32]);
+        tampered
+    }
+
+    #[test]
+    fn classify_and_prove() {
+        let params = PublicParams::new(64);
+        let (input, sizes) = sensor_vectors();
+        // The addition of the first axis of the first sensor vector
+        let addition: i64 = (0..10).map(|i| 100_000_000 + 37 * i * i).sum();
+
+        let accepting = first_addition_model(-addition);
+        let (decision, proof) = zkSVM::classify_and_prove(&params, &accepting, &input, &sizes).unwrap();
+        assert_eq!(proof.serialized_size(), proof.to_bytes().len());
+        let parsed = ClassificationProof::from_bytes_strict(&proof.to_bytes(), &params).unwrap();
+        assert!(parsed == proof);
+        let tampered = non_canonical(&proof.to_bytes(), proof.prover.feature_commitments().0[0][0].as_bytes());
+        assert!(ClassificationProof::from_bytes(&tampered, &params).is_ok());
+        assert_eq!(ClassificationProof::from_bytes_strict(&tampered, &params).err(), Some(ProofError::FormatError));
+        let proof = parsed;
+        assert!(proof.clone().verify_with_params(&params, &accepting, decision).is_ok());
+        assert!(proof.verify_with_params(&params, &first_addition_model(-addition - 1), decision).is_err());
+
+        // The commitments to both decisions differ in the bit, which the verifier cannot tell
+        let rejecting = first_addition_model(-addition - 1);
+        let (decision, proof) = zkSVM::classify_and_prove(&params, &rejecting, &input, &sizes).unwrap();
+        assert!(proof.verify_with_params(&params, &rejecting, decision).is_ok());
+    }
+
+    #[test]
+    fn classify_and_reveal() {
+        let params = PublicParams::new(64);
+        let (input, sizes) = sensor_vectors();
+        let addition: i64 = (0..10).map(|i| 100_000_000 + 37 * i * i).sum();
+
+        for (bias, expected) in [(-addition, true), (-addition - 1, false)].iter() {
+            let model = first_addition_model(*bias);
+            let proof = zkSVM::classify_and_reveal(&params, &model, &input, &sizes).unwrap();
+            assert_eq!(proof.decision(), *expected);
+
+            let bytes = proof.to_bytes();
+            // The classification is serialized first, after its length
+            let classification = ClassificationProof::from_bytes(&bytes[8..bytes.len() - 33], &params).unwrap();
+            let tampered = non_canonical(&bytes, classification.prover.feature_commitments().0[0][0].as_bytes());
+            assert!(RevealedClassificationProof::from_bytes(&tampered, &params).is_ok());
+            assert_eq!(
+                RevealedClassificationProof::from_bytes_strict(&tampered, &params).err(),
+                Some(ProofError::FormatError)
+            );
+            let proof = RevealedClassificationProof::from_bytes_strict(&bytes, &params).unwrap();
+            assert_eq!(proof.verify_with_params(&params, &model), Ok(*expected));
+
+            // The revealed decision is followed by the 32 bytes of its blinding
+            let mut flipped = bytes.clone();
+            let position = flipped.len() - 33;
+            flipped[position] ^= 1;
+            let proof = RevealedClassificationProof::from_bytes(&flipped, &params).unwrap();
+            assert_eq!(proof.verify_with_params(&params, &model), Err(ProofError::VerificationError));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "bigint")]
+    fn bigint_sensor_vectors() {
+        use num_bigint::BigInt;
+
+        let params = PublicParams::new(64);
+        let (input, sizes) = sensor_vectors();
+        let input: Vec<[Vec<BigInt>; 3]> = input.iter()
+            .map(|v| [0, 1, 2].map(|axis| v[axis].iter().map(|&x| BigInt::from(x)).collect()))
+            .collect();
+        let addition: i64 = (0..10).map(|i| 100_000_000 + 37 * i * i).sum();
+
+        let proof = zkSVM::classify_and_reveal(&params, &first_addition_model(-addition), &input, &sizes).unwrap();
+        assert!(proof.decision());
+        assert!(zkSVM::create_with_params(&params, &input, &sizes).unwrap().verify_with_params(&params).is_ok());
+    }
+}
@@ -0,0 +1,228 @@
+//! Overlapping windows over a stream of sensor events.
+//!
+//! A `WindowManager` buffers the events of the sensors of its `WindowConfig` and, every
+//! `step_ns`, emits a `Frame` with the sensor vectors of the windows starting at that time, as
+//! `WindowConfig::windows_from`. When the step is shorter than the windows of a frame, e.g. one
+//! window out of two, consecutive frames share windows. With `with_commitment_reuse`, a shared
+//! window is committed with the same blindings in each frame, so that its signed commitment is
+//! the same in all the proofs that use it. The blindings are drawn from the thread RNG, or
+//! from the one given with `with_rng`.
+
+use std::collections::HashMap;
+
+use curve25519_dalek::scalar::Scalar;
+use ip_zk_proof::ProofError;
+use pedersen_commitments_proofs::utils::randomness::random_scalars;
+use pedersen_commitments_proofs::PublicParams;
+use rand::thread_rng;
+use rand_core::CryptoRngCore;
+
+use crate::android::{SensorEvent, WindowConfig};
+use crate::zkSVM;
+
+/// The sensor vectors of the windows starting at `start_ns`, ready to be proven.
+#[derive(Clone, Debug)]
+pub struct Frame {
+    pub start_ns: u64,
+    /// Sensor vectors, zero-padded to the vector size of the configuration
+    pub input_vector: Vec<[Vec<i128>; 3]>,
+    /// Number of readings of each sensor vector
+    pub non_zero_elements: Vec<usize>,
+    // Blindings of the signed commitments, when they are reused
+    signed_blindings: Option<Vec<Vec<Scalar>>>,
+}
+
+impl Frame {
+    /// Proves the frame with `zkSVM::create_with_params`, or with the reused blindings.
+    pub fn prove(&self, params: &PublicParams) -> Result<zkSVM, ProofError> {
+        match &self.signed_blindings {
+            Some(blindings) => zkSVM::create_with_signed_blindings(params, blindings, &self.input_vector, &self.non_zero_elements),
+            None => zkSVM::create_with_params(params, &self.input_vector, &self.non_zero_elements),
+        }
+    }
+}
+
+/// Maintains overlapping windows over a stream of sensor events.
+pub struct WindowManager {
+    config: WindowConfig,
+    step_ns: u64,
+    reuse_commitments: bool,
+    // Start of the next frame, set by the first event
+    start_ns: Option<u64>,
+    // Events at or after `start_ns`
+    events: Vec<SensorEvent>,
+    // Blindings of the windows, by sensor and start
+    blindings: HashMap<(u32, u64), Vec<Scalar>>,
+    // RNG of the blindings, the thread RNG if none
+    rng: Option<Box<dyn CryptoRngCore + Send>>,
+}
+
+impl WindowManager {
+    /// Creates a manager emitting a frame every `step_ns`, starting at the first event.
+    ///
+    /// Panics if `step_ns` is zero.
+    pub fn new(config: WindowConfig, step_ns: u64) -> WindowManager {
+        assert!(step_ns > 0, "The step between frames must not be zero.");
+        WindowManager {
+            config,
+            step_ns,
+            reuse_commitments: false,
+            start_ns: None,
+            events: Vec::new(),
+            blindings: HashMap::new(),
+            rng: None,
+        }
+    }
+
+    /// Draws the reused blinding factors from `rng` instead of the thread RNG, e.g. on targets
+    /// without one.
+    pub fn with_rng<R: CryptoRngCore + Send + 'static>(mut self, rng: R) -> WindowManager {
+        self.rng = Some(Box::new(rng));
+        self
+    }
+
+    /// Reuses the blinding factors, and thus the signed commitments, of the windows shared by
+    /// consecutive frames.
+    pub fn with_commitment_reuse(mut self) -> WindowManager {
+        self.reuse_commitments = true;
+        self
+    }
+
+    /// Adds an event of the stream. Returns the frames completed by it, i.e. the ones that end
+    /// at or before its timestamp, where a frame is an error if one of its windows has too
+    /// many or too few readings (see `WindowConfig::windows_from`).
+    ///
+    /// The events of other sensors, and the ones before the start of the next frame, e.g. the
+    /// ones that arrive after a frame they belong to was emitted, are ignored.
+    pub fn push(&mut self, event: SensorEvent) -> Vec<Result<Frame, ProofError>> {
+        if !self.config.sensor_types.contains(&event.sensor_type) {
+            return Vec::new();
+        }
+        let start = *self.start_ns.get_or_insert(event.timestamp_ns);
+        if event.timestamp_ns < start {
+            return Vec::new();
+        }
+        let timestamp_ns = event.timestamp_ns;
+        let position = self.events.partition_point(|e| e.timestamp_ns <= timestamp_ns);
+        self.events.insert(position, event);
+
+        let frame_ns = self.config.window_ns * self.config.nr_windows as u64;
+        let mut frames = Vec::new();
+        while let Some(start) = self.start_ns.filter(|start| timestamp_ns >= start + frame_ns) {
+            frames.push(self.frame(start));
+
+            let next = start + self.step_ns;
+            self.start_ns = Some(next);
+            self.events.retain(|e| e.timestamp_ns >= next);
+            self.blindings.retain(|&(_, window_start), _| window_start >= next);
+        }
+        frames
+    }
+
+    fn frame(&mut self, start_ns: u64) -> Result<Frame, ProofError> {
+        let (input_vector, non_zero_elements) = self.config.windows_from(&self.events, start_ns)?;
+
+        let signed_blindings = if self.reuse_commitments {
+            let rng = &mut self.rng;
+            let mut signed_blindings = Vec::with_capacity(input_vector.len());
+            for &sensor_type in self.config.sensor_types.iter() {
+                for window in 0..self.config.nr_windows as u64 {
+                    let window_start = start_ns + window * self.config.window_ns;
+                    signed_blindings.push(
+                        self.blindings.entry((sensor_type, window_start))
+                            .or_insert_with(|| match rng {
+                                Some(rng) => random_scalars(rng, 3),
+                                None => random_scalars(&mut thread_rng(), 3),
+                            })
+                            .clone()
+                    );
+                }
+            }
+            Some(signed_blindings)
+        } else {
+            None
+        };
+
+        Ok(Frame { start_ns, input_vector, non_zero_elements, signed_blindings })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::android::{TYPE_ACCELEROMETER, TYPE_GYROSCOPE};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    /// Events of both sensors every 250ms for `seconds`, with the accelerometer x axis counting
+    /// the events.
+    fn stream(seconds: u64) -> Vec<SensorEvent> {
+        (0..4 * seconds).flat_map(|i| vec![
+            SensorEvent { sensor_type: TYPE_ACCELEROMETER, timestamp_ns: i * SECOND / 4, values: [i as f32, 0.5, -9.81] },
+            SensorEvent { sensor_type: TYPE_GYROSCOPE, timestamp_ns: i * SECOND / 4 + 1, values: [0.01, (i % 3) as f32, 0.03] },
+        ]).collect()
+    }
+
+    #[test]
+    fn overlapping_frames() {
+        let mut manager = WindowManager::new(WindowConfig::new(SECOND, 8), SECOND);
+        let frames: Vec<Frame> = stream(5).into_iter()
+            .flat_map(|e| manager.push(e))
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        // Frames of two seconds every second, the last one completed by an event at 4s
+        assert_eq!(frames.iter().map(|f| f.start_ns).collect::<Vec<u64>>(), vec![0, SECOND, 2 * SECOND]);
+        for pair in frames.windows(2) {
+            assert_eq!(pair[0].non_zero_elements, vec![4, 4, 4, 4]);
+            // The second window of a frame is the first one of the next frame
+            assert_eq!(pair[0].input_vector[1], pair[1].input_vector[0]);
+            assert_eq!(pair[0].input_vector[3], pair[1].input_vector[2]);
+        }
+        assert_eq!(frames[1].input_vector[0][0][0], 104_000_000);
+
+        // Late events are ignored
+        assert!(manager.push(SensorEvent { sensor_type: TYPE_ACCELEROMETER, timestamp_ns: 0, values: [0.0; 3] }).is_empty());
+    }
+
+    #[test]
+    fn shared_windows_reuse_commitments() {
+        let params = PublicParams::new(64);
+        let mut manager = WindowManager::new(WindowConfig::new(SECOND, 64), SECOND).with_commitment_reuse();
+        let frames: Vec<Frame> = stream(4).into_iter()
+            .flat_map(|e| manager.push(e))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+
+        let proofs: Vec<zkSVM> = frames.iter().map(|f| f.prove(&params).unwrap()).collect();
+        let commitments: Vec<_> = proofs.iter().map(|p| p.prover.signed_commitments().clone()).collect();
+        assert_eq!(commitments[0][1], commitments[1][0]);
+        assert_eq!(commitments[0][3], commitments[1][2]);
+        assert_ne!(commitments[0][0], commitments[1][0]);
+
+        for proof in proofs {
+            assert!(proof.verify_with_params(&params).is_ok());
+        }
+    }
+
+    #[test]
+    fn seeded_blindings() {
+        let frames = |seed: u64| -> Vec<Frame> {
+            let mut manager = WindowManager::new(WindowConfig::new(SECOND, 8), SECOND)
+                .with_commitment_reuse()
+                .with_rng(StdRng::seed_from_u64(seed));
+            stream(4).into_iter()
+                .flat_map(|e| manager.push(e))
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+        let blindings = |frames: Vec<Frame>| -> Vec<Vec<Vec<Scalar>>> {
+            frames.into_iter().map(|f| f.signed_blindings.unwrap()).collect()
+        };
+        assert_eq!(blindings(frames(1)), blindings(frames(1)));
+        assert_ne!(blindings(frames(1)), blindings(frames(2)));
+    }
+}
@@ -1,13 +1,27 @@
-extern crate num_bigint;
-
+use alloc::vec::Vec;
+use core::convert::{TryFrom, TryInto};
+#[cfg(feature = "std")]
+use crate::android::{SensorEvent, WindowConfig};
 use crate::utils::*;
-use num_bigint::BigInt;
-use pedersen_commitments_proofs::zkSVMProver;
-use ip_zk_proof::ProofError;
+use curve25519_dalek::ristretto::CompressedRistretto;
+#[cfg(feature = "std")]
+use curve25519_dalek::scalar::Scalar;
+#[cfg(feature = "std")]
+use pedersen_commitments_proofs::{ClassificationProof, RevealedClassificationProof, SensorWindow, SvmModel};
+use pedersen_commitments_proofs::{zkSVMProver, FeatureInt, PublicParams, SensorFeatures};
+use pedersen_commitments_proofs::algebraic_proofs::hidden_count_proof::HiddenCountProof;
+#[cfg(feature = "text-encoding")]
+use pedersen_commitments_proofs::utils::text_encoding::TextEncoding;
+use ip_zk_proof::{ProofError, SerializedSize};
+use merlin::Transcript;
+use rand_core::CryptoRngCore;
 
 /// Structure that will encapsulate the zero-knowledge proof of the computations performed to
 /// evaluate the SVM in a privacy preserving manner.
-#[derive(Clone)]
+///
+/// Without the `std` feature, the proof is created with `create_with_rng` and verified with
+/// `verify_with_rng`, as the other entry points draw from the thread RNG.
+#[derive(Clone, Debug)]
 pub struct zkSVM {
     // Proof of model computation
     pub prover: zkSVMProver,
@@ -17,56 +31,389 @@ pub struct zkSVM {
 impl zkSVM {
     /// Given the input vectors (to evaluate the SVM model), `create` computes the preprocessing of
     /// the input vectors (mainly the difference, additions, factor of the variance and factor of the
-    /// standard deviations), and proves correctness.
-    pub fn create(
+    /// standard deviations, see `SensorFeatures`), and proves correctness.
+    #[cfg(feature = "std")]
+    pub fn create<T: FeatureInt>(
         // Vector containing sensor data
-        input_vector: &Vec<[Vec<BigInt>; 3]>,
+        input_vector: &[[Vec<T>; 3]],
         // Number of non-zero elements in the input vector
-        non_zero_elements: &Vec<usize>,
+        non_zero_elements: &[usize],
     ) -> Result<zkSVM, ProofError> {
-        // Compute the difference vectors
-        let mut diff_vectors: Vec<[Vec<BigInt>; 3]> = diff_computation(input_vector, &non_zero_elements);
+        zkSVM::create_with_params(
+            &PublicParams::new(input_vector[0][0].len()),
+            input_vector,
+            non_zero_elements,
+        )
+    }
 
-        let initial_diff_vectors = diff_vectors.clone();
+    /// As `create`, with the generators and labels of the given public parameters, which must
+    /// have the size of the input vectors. The proof is then checked with `verify_with_params`.
+    #[cfg(feature = "std")]
+    pub fn create_with_params<T: FeatureInt>(
+        params: &PublicParams,
+        // Vector containing sensor data
+        input_vector: &[[Vec<T>; 3]],
+        // Number of non-zero elements in the input vector
+        non_zero_elements: &[usize],
+    ) -> Result<zkSVM, ProofError> {
+        zkSVM::create_internal(params, None, input_vector, non_zero_elements)
+    }
 
-        for (index, non_zero_nr) in non_zero_elements.into_iter().enumerate() {
-            for i in 0..3 {
-                diff_vectors[index][i][non_zero_nr - 1] = BigInt::from(0);
-            }
-        }
+    /// As `create_with_params`, with all the randomness of the proof drawn from `rng`, see
+    /// `zkSVMProver::new_with_rng`.
+    pub fn create_with_rng<T: FeatureInt>(
+        params: &PublicParams,
+        rng: &mut dyn CryptoRngCore,
+        // Vector containing sensor data
+        input_vector: &[[Vec<T>; 3]],
+        // Number of non-zero elements in the input vector
+        non_zero_elements: &[usize],
+    ) -> Result<zkSVM, ProofError> {
+        let features = SensorFeatures::extract(input_vector, non_zero_elements);
+        let prover = preprocess_and_prove_with_rng(params, rng, &features)?;
+
+        Ok(zkSVM {prover,})
+    }
+
+    /// As `create_with_params`, with the proof bound to the current state of `transcript`, e.g.
+    /// the transcript of a larger protocol or of a signature over the proof. The proof is then
+    /// checked with `verify_with_transcript`.
+    #[cfg(feature = "std")]
+    pub fn create_with_transcript<T: FeatureInt>(
+        params: &PublicParams,
+        transcript: &mut Transcript,
+        // Vector containing sensor data
+        input_vector: &[[Vec<T>; 3]],
+        // Number of non-zero elements in the input vector
+        non_zero_elements: &[usize],
+    ) -> Result<zkSVM, ProofError> {
+        zkSVM::create_internal(params, Some(transcript), input_vector, non_zero_elements)
+    }
+
+    /// As `create_with_params`, with the blinding factors of the commitments to the sensor
+    /// vectors given, three per sensor vector. The same vector committed with the same
+    /// blindings gives the same commitment, e.g. for the windows shared by consecutive proofs
+    /// of a `WindowManager`.
+    #[cfg(feature = "std")]
+    pub fn create_with_signed_blindings<T: FeatureInt>(
+        params: &PublicParams,
+        signed_blindings: &Vec<Vec<Scalar>>,
+        // Vector containing sensor data
+        input_vector: &[[Vec<T>; 3]],
+        // Number of non-zero elements in the input vector
+        non_zero_elements: &[usize],
+    ) -> Result<zkSVM, ProofError> {
+        let features = SensorFeatures::extract(input_vector, non_zero_elements);
+        let prover = preprocess_and_prove_with_signed_blindings(params, signed_blindings, &features)?;
+
+        Ok(zkSVM {prover,})
+    }
+
+    /// As `create_with_params`, with the sensor vectors built from a batch of Android sensor
+    /// events, see `android::WindowConfig::windows`. The vector size of `config` must be the
+    /// size of the parameters.
+    #[cfg(feature = "std")]
+    pub fn create_from_events(
+        params: &PublicParams,
+        events: &[SensorEvent],
+        config: &WindowConfig,
+    ) -> Result<zkSVM, ProofError> {
+        let (input_vector, non_zero_elements) = config.windows(events)?;
+        zkSVM::create_with_params(params, &input_vector, &non_zero_elements)
+    }
 
-        // We generate the vectors, and their corresponding sizes of non-zero element, over which
-        // we evaluate the model
-        let mut evaluated_vectors: Vec<[Vec<BigInt>; 3]> = input_vector.clone();
-        evaluated_vectors.extend(diff_vectors);
-
-        let mut evaluated_sizes: Vec<usize> = non_zero_elements.clone();
-        let diff_sizes: Vec<usize> = non_zero_elements.iter().map(|x| x - 1).collect();
-        evaluated_sizes.extend(
-            diff_sizes
-        );
-
-        let additions = additions_vector(&evaluated_vectors);
-        let subtracted_values = subtractions_vector(&non_zero_elements, &input_vector, &additions);
-        let variances = variance_factor(&subtracted_values);
-        let stds = stds_factor(&variances);
-
-        let prover = preprocess_and_prove(
-            &evaluated_vectors,
-            &evaluated_sizes,
-            &initial_diff_vectors,
-            &additions,
-            &variances,
-            &stds
-        )?;
+    /// As `create_with_params`, with the sensor vectors given as windows of `N` elements, whose
+    /// shape is checked at compile time, see `SensorWindow`. The size of the parameters must be
+    /// `N`.
+    #[cfg(feature = "std")]
+    pub fn create_from_windows<T: FeatureInt, const N: usize>(
+        params: &PublicParams,
+        windows: &[SensorWindow<3, N, T>],
+    ) -> Result<zkSVM, ProofError> {
+        let features = SensorFeatures::from_windows(windows);
+        let prover = preprocess_and_prove(params, None, &features)?;
+
+        Ok(zkSVM {prover,})
+    }
+
+    /// Proves the features of the input vectors as `create_with_params`, and the decision of
+    /// `model` over them, e.g. `model::embedded`. Returns the commitment to the decision, i.e.
+    /// to one if the score of the model is non-negative and to zero otherwise, with the proof,
+    /// which is checked with `ClassificationProof::verify_with_params`.
+    #[cfg(feature = "std")]
+    pub fn classify_and_prove<T: FeatureInt>(
+        params: &PublicParams,
+        model: &SvmModel,
+        // Vector containing sensor data
+        input_vector: &[[Vec<T>; 3]],
+        // Number of non-zero elements in the input vector
+        non_zero_elements: &[usize],
+    ) -> Result<(CompressedRistretto, ClassificationProof), ProofError> {
+        let features = SensorFeatures::extract(input_vector, non_zero_elements);
+        preprocess_and_classify(params, model, &features)
+    }
+
+    /// As `classify_and_prove`, with the decision revealed to the verifier instead of
+    /// committed. `RevealedClassificationProof::verify_with_params` returns the verified
+    /// decision.
+    #[cfg(feature = "std")]
+    pub fn classify_and_reveal<T: FeatureInt>(
+        params: &PublicParams,
+        model: &SvmModel,
+        // Vector containing sensor data
+        input_vector: &[[Vec<T>; 3]],
+        // Number of non-zero elements in the input vector
+        non_zero_elements: &[usize],
+    ) -> Result<RevealedClassificationProof, ProofError> {
+        let features = SensorFeatures::extract(input_vector, non_zero_elements);
+        preprocess_and_reveal(params, model, &features)
+    }
+
+    #[cfg(feature = "std")]
+    fn create_internal<T: FeatureInt>(
+        params: &PublicParams,
+        transcript: Option<&mut Transcript>,
+        input_vector: &[[Vec<T>; 3]],
+        non_zero_elements: &[usize],
+    ) -> Result<zkSVM, ProofError> {
+        let features = SensorFeatures::extract(input_vector, non_zero_elements);
+        let prover = preprocess_and_prove(params, transcript, &features)?;
 
         Ok(zkSVM {prover,})
     }
 
+    #[cfg(feature = "std")]
     pub fn verify(
         self,
     ) -> Result<(), ProofError> {
         self.prover.verify()?;
-        return Ok(())
+        Ok(())
+    }
+
+    /// Serializes the proof, see `zkSVMProver::to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.prover.to_bytes()
+    }
+
+    /// Parses a proof serialized with `to_bytes`, to be verified under the given public
+    /// parameters.
+    pub fn from_bytes(bytes: &[u8], params: &PublicParams) -> Result<zkSVM, ProofError> {
+        Ok(zkSVM { prover: zkSVMProver::from_bytes(bytes, params)? })
+    }
+
+    /// Encodes the proof in CBOR, see `zkSVMProver::to_cbor`.
+    #[cfg(feature = "cbor")]
+    pub fn to_cbor(&self) -> Vec<u8> {
+        self.prover.to_cbor()
+    }
+
+    /// Decodes a proof encoded with `to_cbor`, to be verified under the given public parameters.
+    #[cfg(feature = "cbor")]
+    pub fn from_cbor(bytes: &[u8], params: &PublicParams) -> Result<zkSVM, ProofError> {
+        Ok(zkSVM { prover: zkSVMProver::from_cbor(bytes, params)? })
+    }
+
+    /// Verifies the proof under the given public parameters.
+    #[cfg(feature = "std")]
+    pub fn verify_with_params(
+        self,
+        params: &PublicParams,
+    ) -> Result<(), ProofError> {
+        self.prover.verify_with_params(params)
+    }
+
+    /// As `verify_with_params`, with the randomness of the verification drawn from `rng`.
+    pub fn verify_with_rng(
+        self,
+        params: &PublicParams,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Result<(), ProofError> {
+        self.prover.verify_with_rng(params, rng)
+    }
+
+    /// Verifies a proof created with `create_with_transcript`, with `transcript` in the same
+    /// state as the one of the prover.
+    #[cfg(feature = "std")]
+    pub fn verify_with_transcript(
+        self,
+        params: &PublicParams,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        self.prover.verify_with_transcript(params, transcript)
+    }
+}
+
+impl SerializedSize for zkSVM {
+    fn serialized_size(&self) -> usize {
+        self.prover.serialized_size()
+    }
+}
+
+/// Proof of the features of input vectors padded to the size of the parameters, with the number
+/// of readings of each vector committed, see `HiddenCountProof`. Where the header of a `zkSVM`
+/// declares the readings of each vector, the statement of this proof only carries the size of
+/// the parameters, which bounds them: the size of the proof and the generators it uses do not
+/// depend on the number of samples of each window, nor does anything else the verifier sees.
+#[derive(Clone, Debug)]
+pub struct zkSVMPadded {
+    /// Size of the vectors, which is the size of the parameters
+    pub size: usize,
+    /// Commitments to the sensor vectors, three per vector
+    pub signed_commitments: Vec<Vec<CompressedRistretto>>,
+    pub proof: HiddenCountProof,
+}
+
+impl zkSVMPadded {
+    /// Pads the input vectors with zeros to the size of the parameters, see
+    /// `SensorFeatures::extract_padded`, and proves their features with the number of readings
+    /// of each vector committed. Each vector has between two and `params.size()` readings.
+    #[cfg(feature = "std")]
+    pub fn create<T: FeatureInt>(
+        params: &PublicParams,
+        // Vector containing sensor data
+        input_vector: &[[Vec<T>; 3]],
+        // Number of non-zero elements in the input vector
+        non_zero_elements: &[usize],
+    ) -> Result<zkSVMPadded, ProofError> {
+        zkSVMPadded::create_with_rng(params, &mut rand::thread_rng(), input_vector, non_zero_elements)
+    }
+
+    /// As `create`, with all the randomness of the proof drawn from `rng`.
+    pub fn create_with_rng<T: FeatureInt>(
+        params: &PublicParams,
+        rng: &mut dyn CryptoRngCore,
+        // Vector containing sensor data
+        input_vector: &[[Vec<T>; 3]],
+        // Number of non-zero elements in the input vector
+        non_zero_elements: &[usize],
+    ) -> Result<zkSVMPadded, ProofError> {
+        let features = SensorFeatures::extract_padded(input_vector, non_zero_elements, params.size())?;
+        let (signed_commitments, proof) =
+            preprocess_and_prove_hidden_counts(params, rng, &mut padded_transcript(params), &features)?;
+
+        Ok(zkSVMPadded { size: params.size(), signed_commitments, proof })
+    }
+
+    /// Verifies the proof under the given public parameters.
+    #[cfg(feature = "std")]
+    pub fn verify_with_params(
+        self,
+        params: &PublicParams,
+    ) -> Result<(), ProofError> {
+        self.verify_with_rng(params, &mut rand::thread_rng())
+    }
+
+    /// As `verify_with_params`, with the randomness of the verification drawn from `rng`.
+    pub fn verify_with_rng(
+        self,
+        params: &PublicParams,
+        mut rng: &mut dyn CryptoRngCore,
+    ) -> Result<(), ProofError> {
+        if self.size != params.size() {
+            return Err(ProofError::VerificationError);
+        }
+        self.proof.verify_with_rng(
+            params,
+            &self.signed_commitments,
+            self.size as u64,
+            &mut padded_transcript(params),
+            &mut rng,
+        )
+    }
+
+    /// Serializes the proof as the size and the number of sensor vectors (8 bytes each, little
+    /// endian), the signed commitments (32 bytes each), followed by the `HiddenCountProof`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let proof = self.proof.to_bytes();
+        let mut buf = Vec::with_capacity(16 + 96 * self.signed_commitments.len() + proof.len());
+        buf.extend_from_slice(&(self.size as u64).to_le_bytes());
+        buf.extend_from_slice(&(self.signed_commitments.len() as u64).to_le_bytes());
+        for commitment in self.signed_commitments.iter().flatten() {
+            buf.extend_from_slice(commitment.as_bytes());
+        }
+        buf.extend_from_slice(&proof);
+        buf
+    }
+
+    /// Parses a proof serialized with `to_bytes`, to be verified under the given public
+    /// parameters. Returns a `FormatError` if it is of another size.
+    pub fn from_bytes(bytes: &[u8], params: &PublicParams) -> Result<zkSVMPadded, ProofError> {
+        let read_u64 = |offset: usize| {
+            bytes.get(offset..offset + 8)
+                .map(|b| u64::from_le_bytes(b.try_into().expect("Slice of 8 bytes")))
+                .ok_or(ProofError::FormatError)
+        };
+        let size = read_u64(0)? as usize;
+        if size != params.size() {
+            return Err(ProofError::FormatError);
+        }
+        let end = usize::try_from(read_u64(8)?).ok()
+            .and_then(|nr_sensors| nr_sensors.checked_mul(96))
+            .and_then(|len| len.checked_add(16))
+            .filter(|&end| end <= bytes.len())
+            .ok_or(ProofError::FormatError)?;
+        let signed_commitments = bytes[16..end].chunks(96)
+            .map(|sensor| sensor.chunks(32)
+                .map(|c| CompressedRistretto::from_slice(c).map_err(|_| ProofError::FormatError))
+                .collect())
+            .collect::<Result<_, _>>()?;
+
+        Ok(zkSVMPadded { size, signed_commitments, proof: HiddenCountProof::from_bytes(&bytes[end..])? })
+    }
+}
+
+impl SerializedSize for zkSVMPadded {
+    fn serialized_size(&self) -> usize {
+        self.to_bytes().len()
+    }
+}
+
+/// The transcript of a `zkSVMPadded`, which declares the size of the parameters as the only
+/// size of the statement.
+fn padded_transcript(params: &PublicParams) -> Transcript {
+    params.labels().with_sizes(&[params.size() as u64]).transcript(b"zkSVMPadded")
+}
+
+/// Hex and base64 encodings of the proof, see `pedersen_commitments_proofs::utils::text_encoding`.
+#[cfg(feature = "text-encoding")]
+impl TextEncoding for zkSVM {
+    type Params<'a> = &'a PublicParams;
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn decode_bytes(bytes: &[u8], params: &PublicParams) -> Result<zkSVM, ProofError> {
+        zkSVM::from_bytes(bytes, params)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padded_proofs_hide_the_number_of_readings() {
+        let params = PublicParams::new(16);
+        let vectors = |n: usize| -> Vec<[Vec<i128>; 3]> {
+            (0..4i128).map(|s| {
+                let axis = |a: i128| (0..n as i128).map(|i| (i * 7 + s * 3 + a) % 11).collect();
+                [axis(0), axis(1), axis(2)]
+            }).collect()
+        };
+
+        let short = zkSVMPadded::create(&params, &vectors(5), &[5; 4]).unwrap();
+        let long = zkSVMPadded::create(&params, &vectors(12), &[12; 4]).unwrap();
+        let bytes = short.to_bytes();
+        assert_eq!(bytes.len(), long.to_bytes().len());
+        // The only size in the encoding is the one of the parameters
+        assert_eq!(bytes[..8], 16u64.to_le_bytes());
+
+        let short = zkSVMPadded::from_bytes(&bytes, &params).unwrap();
+        assert!(short.verify_with_params(&params).is_ok());
+        assert!(long.verify_with_params(&params).is_ok());
+        assert_eq!(zkSVMPadded::from_bytes(&bytes, &PublicParams::new(32)).err(), Some(ProofError::FormatError));
+
+        assert!(zkSVMPadded::create(&params, &vectors(17), &[17; 4]).is_err());
     }
-}
\ No newline at end of file
+}